@@ -1,62 +1,542 @@
 //! Base L2 RPC client.
 //!
 //! Provides a thin wrapper around an alloy HTTP provider for interacting with
-//! the Base L2 network. Only exposes balance queries and connectivity checks
-//! for now — transaction signing is deferred to T-021.
+//! the Base L2 network: typed contract reads, fee estimates, and the
+//! transactions the CLI signs with the agent's key (so far, validation
+//! verdicts: see [`ChainClient::submit_validation`]).
+//!
+//! Reads are spaced at least [`MIN_READ_INTERVAL`] apart per client, and
+//! [`ChainClient::shared`] hands out one client per RPC endpoint so that
+//! spacing holds across a whole command. Reads that fail transiently (a
+//! timeout, a rate limit) are retried under the client's
+//! [`RetryPolicy`].
+//!
+//! Transactions get their nonces from the client's [`NonceManager`], so
+//! several can be sent back to back: see [`ChainClient::send_with_nonce`].
+//! One stuck under-priced can be replaced with its nonce reused: see
+//! [`ChainClient::replace_transaction`]. Clients built from the config also
+//! record what they send, and how it ended, in the [`TxLog`].
 
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Display;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
 use std::time::Duration;
 
-use alloy::primitives::{Address, U256};
-use alloy::providers::{Provider, ProviderBuilder, RootProvider};
-use anyhow::{Context, Result};
-use tracing::debug;
+use alloy::primitives::{Address, B256, U256};
+use alloy::providers::{Provider, ProviderBuilder, RootProvider, WsConnect};
+use alloy::rpc::types::TransactionRequest;
+use alloy::sol_types::SolCall;
+use anyhow::{bail, Context, Result};
+use futures::{Stream, StreamExt};
+use serde::Serialize;
+use tokio::time::Instant;
+use tracing::{debug, warn};
+
+use crate::clock::unix_now;
+use crate::config::store::RpcEndpoints;
+
+use super::contracts::{
+    self, AgentRegistry, Contract, ContractAddresses, RequestRegistry, ValidationRegistry, USDC,
+};
+use super::events::{self, DecodedEvent, EventFilter, RequestEvent};
+use super::failover::Failover;
+use super::fees::{self, FeePolicy, FeeQuote};
+use super::nonce::{self, NonceManager};
+use super::receipt::{self, TxOutcome};
+use super::retry::{self, RetryPolicy, Transient};
+use super::signer::TransactionSigner;
+use super::txlog::{TxKind, TxLog, TxRecord};
+use super::types::{
+    AgentId, AllowanceAction, Balance, Balances, FeeEstimate, OnchainRequest, Replacement,
+    RequestId, RequestStatus, Response, SellerRecord, Validation, ValidationSubmission,
+};
+
+/// Minimum spacing between two reads issued through the same client, so
+/// bursts of checks stay under public RPC rate limits.
+pub const MIN_READ_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How often [`ChainClient::wait_for_receipt`] checks on a transaction.
+pub const RECEIPT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Whether `--allow-chain-mismatch` was passed.
+static ALLOW_CHAIN_MISMATCH: AtomicBool = AtomicBool::new(false);
+
+/// Connect even when the network's chain ID is not the configured one (the
+/// global `--allow-chain-mismatch` flag, for development against a local
+/// node).
+pub fn set_allow_chain_mismatch(enabled: bool) {
+    ALLOW_CHAIN_MISMATCH.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether a chain ID mismatch is only warned about.
+pub fn allow_chain_mismatch() -> bool {
+    ALLOW_CHAIN_MISMATCH.load(Ordering::Relaxed)
+}
+
+/// The chain ID a network reported, against the one configured in
+/// `network.expected_chain_id`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct ChainCheck {
+    pub expected: u64,
+    pub actual: u64,
+}
+
+impl ChainCheck {
+    /// Whether the network is the configured one.
+    pub fn matches(&self) -> bool {
+        self.expected == self.actual
+    }
+
+    /// Fail with a message naming both chain IDs unless they match.
+    pub fn ensure_matches(&self) -> Result<()> {
+        if !self.matches() {
+            anyhow::bail!(
+                "Connected network ({}) doesn't match configured network ({}). \
+                 Point network.chain_rpc at the right network, change \
+                 network.expected_chain_id, or pass --allow-chain-mismatch.",
+                self.actual,
+                self.expected
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Clients handed out by [`ChainClient::shared`], keyed by RPC URL.
+static SHARED: OnceLock<Mutex<HashMap<String, Arc<ChainClient>>>> = OnceLock::new();
 
 // ---------------------------------------------------------------------------
 // ChainClient
 // ---------------------------------------------------------------------------
 
+/// Builds the provider for one endpoint URL.
+type Connector = Arc<dyn Fn(&str) -> Result<RootProvider> + Send + Sync>;
+
 /// Client for interacting with the Base L2 network over JSON-RPC.
 ///
 /// Wraps an alloy [`RootProvider`] configured for HTTP transport. All public
 /// methods return user-friendly error messages with no blockchain jargon (see
 /// CLAUDE.md "Zero-crypto UX" constraint).
+///
+/// With several endpoints configured, reads go to the one chosen by a
+/// [`Failover`]; when it fails transiently the client moves to the next
+/// endpoint and builds a fresh provider for it.
 pub struct ChainClient {
+    connect: Connector,
+    /// The endpoint reads go to, and its provider.
+    active: Mutex<Active>,
+    /// When the most recent throttled read was allowed through.
+    last_read: tokio::sync::Mutex<Option<Instant>>,
+    /// How transient read failures are retried.
+    retry: RetryPolicy,
+    /// WebSocket endpoint for event subscriptions, if one is configured.
+    ws_url: Option<String>,
+    /// Outcome of the last [`ChainClient::verify_chain`].
+    chain_check: Mutex<Option<ChainCheck>>,
+    /// Nonces handed out to transactions sent through this client.
+    nonces: NonceManager,
+    /// How often [`ChainClient::wait_for_receipt`] polls.
+    receipt_poll: Duration,
+    /// Where sent transactions are recorded, if anywhere.
+    txlog: Option<TxLog>,
+    /// The contracts on the network this client talks to.
+    contracts: ContractAddresses,
+    /// How much the transactions this client sends bid for gas.
+    fee_policy: FeePolicy,
+}
+
+struct Active {
+    failover: Failover,
     provider: RootProvider,
-    rpc_url: String,
 }
 
 impl ChainClient {
-    /// Create a new chain client connected to the given RPC endpoint.
+    /// Create a new chain client for the given RPC endpoint, or endpoints
+    /// tried in order.
     ///
-    /// The URL is parsed and an HTTP provider is built via alloy's
+    /// Every URL is parsed up front. Providers are built via alloy's
     /// [`ProviderBuilder`] with a custom reqwest client configured with a
     /// 30-second timeout. No actual network call is made during construction
     /// — use [`is_connected`] to verify reachability.
-    pub async fn new(rpc_url: &str) -> Result<Self> {
-        debug!(rpc_url, "creating chain client");
+    pub async fn new(endpoints: impl Into<RpcEndpoints>) -> Result<Self> {
+        let endpoints = endpoints.into();
+        debug!(%endpoints, "creating chain client");
 
-        let url: reqwest::Url = rpc_url
-            .parse()
-            .with_context(|| format!("invalid network endpoint: {rpc_url}"))?;
+        for rpc_url in endpoints.urls() {
+            rpc_url
+                .parse::<reqwest::Url>()
+                .with_context(|| format!("invalid network endpoint: {rpc_url}"))?;
+        }
 
         let http_client = reqwest::Client::builder()
             .timeout(Duration::from_secs(30))
             .build()
             .context("failed to build HTTP client for chain provider")?;
 
-        let provider = ProviderBuilder::default().connect_reqwest(http_client, url);
+        let connect: Connector = Arc::new(move |rpc_url: &str| {
+            let url: reqwest::Url = rpc_url
+                .parse()
+                .with_context(|| format!("invalid network endpoint: {rpc_url}"))?;
+            Ok(ProviderBuilder::default().connect_reqwest(http_client.clone(), url))
+        });
+        Self::with_connector(connect, &endpoints)
+    }
 
+    fn with_connector(connect: Connector, endpoints: &RpcEndpoints) -> Result<Self> {
+        let failover = Failover::new(endpoints);
+        let provider = connect(failover.url())?;
         Ok(Self {
-            provider,
-            rpc_url: rpc_url.to_string(),
+            connect,
+            active: Mutex::new(Active { failover, provider }),
+            last_read: tokio::sync::Mutex::new(None),
+            retry: RetryPolicy::default(),
+            ws_url: None,
+            chain_check: Mutex::new(None),
+            nonces: NonceManager::default(),
+            receipt_poll: RECEIPT_POLL_INTERVAL,
+            txlog: None,
+            contracts: ContractAddresses::default(),
+            fee_policy: FeePolicy::default(),
+        })
+    }
+
+    /// Use `policy` for retrying transient read failures instead of the
+    /// default.
+    pub fn with_retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    /// Poll for receipts every `interval` instead of
+    /// [`RECEIPT_POLL_INTERVAL`].
+    pub fn with_receipt_poll(mut self, interval: Duration) -> Self {
+        self.receipt_poll = interval;
+        self
+    }
+
+    /// Record sent transactions and their outcomes in `txlog`.
+    pub fn with_txlog(mut self, txlog: TxLog) -> Self {
+        self.txlog = Some(txlog);
+        self
+    }
+
+    /// Talk to `contracts` instead of the Base mainnet preset.
+    pub fn with_contracts(mut self, contracts: ContractAddresses) -> Self {
+        self.contracts = contracts;
+        self
+    }
+
+    /// The contracts this client talks to.
+    pub fn contracts(&self) -> &ContractAddresses {
+        &self.contracts
+    }
+
+    /// Bid for gas as `policy` says instead of the `[fees]` defaults.
+    pub fn with_fee_policy(mut self, policy: FeePolicy) -> Self {
+        self.fee_policy = policy;
+        self
+    }
+
+    /// Subscribe to events over the WebSocket endpoint `ws_url`; reads
+    /// still go to the HTTP endpoints.
+    pub fn with_ws(mut self, ws_url: impl Into<String>) -> Self {
+        self.ws_url = Some(ws_url.into());
+        self
+    }
+
+    /// Return the process-wide client for `config.network.chain_rpc`,
+    /// creating it (as [`ChainClient::from_config`] does) on first use.
+    /// Callers sharing a handle also share its read rate limit.
+    pub async fn shared(config: &crate::config::store::Config) -> Result<Arc<Self>> {
+        let key = config.network.chain_rpc.to_string();
+        let cache = SHARED.get_or_init(Default::default);
+        if let Some(client) = cache.lock().unwrap_or_else(|e| e.into_inner()).get(&key) {
+            return Ok(client.clone());
+        }
+
+        let client = Arc::new(Self::from_config(config).await?);
+        let mut cache = cache.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(cache.entry(key).or_insert(client).clone())
+    }
+
+    /// Client backed by alloy's mock transport, for tests.
+    #[cfg(test)]
+    pub(crate) fn mocked(asserter: alloy::providers::mock::Asserter) -> Self {
+        Self::mocked_endpoints(vec![("mock://", asserter)])
+    }
+
+    /// Client with one mocked endpoint per `(url, asserter)`, for failover
+    /// tests.
+    #[cfg(test)]
+    pub(crate) fn mocked_endpoints(
+        endpoints: Vec<(&str, alloy::providers::mock::Asserter)>,
+    ) -> Self {
+        let urls = endpoints.iter().map(|(url, _)| url.to_string()).collect();
+        let asserters: HashMap<String, _> = endpoints
+            .into_iter()
+            .map(|(url, asserter)| (url.to_string(), asserter))
+            .collect();
+        let connect: Connector = Arc::new(move |url: &str| {
+            let asserter = asserters[url].clone();
+            Ok(ProviderBuilder::default().connect_mocked_client(asserter))
+        });
+        Self::with_connector(
+            connect,
+            &RpcEndpoints::new(urls).expect("at least one endpoint"),
+        )
+        .expect("mocked providers always connect")
+    }
+
+    /// Wait until at least [`MIN_READ_INTERVAL`] has passed since the
+    /// previous read through this client.
+    async fn throttle(&self) {
+        let mut last = self.last_read.lock().await;
+        if let Some(previous) = *last {
+            tokio::time::sleep_until(previous + MIN_READ_INTERVAL).await;
+        }
+        *last = Some(Instant::now());
+    }
+
+    /// Run the read `call` against the active endpoint's provider,
+    /// throttled, retrying transient failures under this client's
+    /// [`RetryPolicy`]. Each transient failure also moves to the next
+    /// endpoint, and every endpoint gets a try however few retries the
+    /// policy allows. `what` names the read in the log.
+    async fn read<T, E, F, Fut>(&self, what: &str, call: F) -> Result<T, E>
+    where
+        F: FnMut(RootProvider) -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+        E: Transient + Display,
+    {
+        self.read_with(&self.retry, what, call).await
+    }
+
+    /// [`ChainClient::read`] under `retry` instead of the client's policy.
+    async fn read_with<T, E, F, Fut>(
+        &self,
+        retry: &RetryPolicy,
+        what: &str,
+        mut call: F,
+    ) -> Result<T, E>
+    where
+        F: FnMut(RootProvider) -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+        E: Transient + Display,
+    {
+        let endpoints = self.lock_active().failover.len();
+        let policy = RetryPolicy {
+            max_retries: retry.max_retries.max(endpoints as u32 - 1),
+            ..retry.clone()
+        };
+        retry::retry_async(&policy, what, || {
+            let (used, provider) = {
+                let active = self.lock_active();
+                (active.failover.active(), active.provider.clone())
+            };
+            let attempt = call(provider);
+            async move {
+                self.throttle().await;
+                let result = attempt.await;
+                self.record(used, result.as_ref().err());
+                result
+            }
         })
+        .await
+    }
+
+    fn lock_active(&self) -> std::sync::MutexGuard<'_, Active> {
+        self.active.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Record how a read through endpoint `used` went, failing over to the
+    /// next endpoint if `error` is transient.
+    fn record<E: Transient + Display>(&self, used: usize, error: Option<&E>) {
+        let now = unix_now();
+        let mut active = self.lock_active();
+        let Some(error) = error.filter(|err| err.is_transient()) else {
+            active.failover.succeeded(used, now);
+            return;
+        };
+        if active
+            .failover
+            .failed(used, &error.to_string(), now)
+            .is_none()
+        {
+            return;
+        }
+        match (self.connect)(active.failover.url()) {
+            Ok(provider) => active.provider = provider,
+            Err(err) => {
+                debug!(error = %format!("{err:#}"), "could not connect to the next endpoint")
+            }
+        }
     }
 
     /// Create a chain client from the loaded application configuration.
     ///
-    /// Uses `config.network.chain_rpc` as the RPC endpoint.
+    /// Uses the endpoints in `config.network.chain_rpc`, the retry policy
+    /// from `[network]`, the fee policy from `[fees]`, and
+    /// `network.chain_ws` for subscriptions if set.
+    ///
+    /// Fails if the network reports a chain ID other than
+    /// `network.expected_chain_id` (see [`ChainClient::ensure_chain`]).
     pub async fn from_config(config: &crate::config::store::Config) -> Result<Self> {
-        Self::new(&config.network.chain_rpc).await
+        let client = Self::new(config.network.chain_rpc.clone())
+            .await?
+            .with_retry(RetryPolicy::from_config(&config.network))
+            .with_txlog(TxLog::open()?)
+            .with_contracts(contracts::for_config(config)?)
+            .with_fee_policy(FeePolicy::from_config(&config.fees));
+        let client = match config.network.chain_ws.as_str() {
+            "" => client,
+            ws_url => client.with_ws(ws_url),
+        };
+
+        client
+            .ensure_chain(config.network.expected_chain_id)
+            .await?;
+        Ok(client)
+    }
+
+    /// Fail unless the network reports chain `expected`. With
+    /// `--allow-chain-mismatch` a mismatch is only warned about; a chain ID
+    /// that cannot be read is left for the next read to report.
+    pub async fn ensure_chain(&self, expected: u64) -> Result<()> {
+        match self.verify_chain(expected).await {
+            Ok(check) => {
+                if let Err(err) = check.ensure_matches() {
+                    if !allow_chain_mismatch() {
+                        return Err(err);
+                    }
+                    warn!("{err:#}");
+                }
+            }
+            Err(err) => debug!(error = %format!("{err:#}"), "could not verify the chain id"),
+        }
+        Ok(())
+    }
+
+    /// Compare the chain ID the network reports with `expected`, and cache
+    /// the result on this client (see [`ChainClient::chain_check`]). Fails
+    /// only if the chain ID cannot be read; the caller decides what a
+    /// mismatch means.
+    pub async fn verify_chain(&self, expected: u64) -> Result<ChainCheck> {
+        if let Some(check) = self.chain_check().filter(|c| c.expected == expected) {
+            return Ok(check);
+        }
+
+        // One attempt per endpoint: a connect-time check should not hold up
+        // a command that then fails on its first read anyway.
+        let actual = self
+            .read_with(&RetryPolicy::none(), "chain id", |provider| async move {
+                provider.get_chain_id().await
+            })
+            .await
+            .context("unable to reach the network — check your connection")?;
+
+        let check = ChainCheck { expected, actual };
+        debug!(expected, actual, "chain id verified");
+        *self.chain_check.lock().unwrap_or_else(|e| e.into_inner()) = Some(check);
+        Ok(check)
+    }
+
+    /// Result of the last [`ChainClient::verify_chain`], if one succeeded.
+    pub fn chain_check(&self) -> Option<ChainCheck> {
+        *self.chain_check.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Whether [`ChainClient::subscribe_request_events`] can be used.
+    pub fn can_subscribe(&self) -> bool {
+        self.ws_url.is_some()
+    }
+
+    /// Subscribe to the request events emitted by `registry` from now on.
+    ///
+    /// Opens a WebSocket connection to the configured `chain_ws` endpoint.
+    /// Brief drops are reconnected by the transport; the stream ends once
+    /// the connection is lost for good, and the caller should subscribe
+    /// again. Logs removed by a reorg and logs that do not decode are
+    /// skipped.
+    pub async fn subscribe_request_events(
+        &self,
+        registry: Address,
+    ) -> Result<impl Stream<Item = RequestEvent> + Send + 'static> {
+        let Some(ws_url) = &self.ws_url else {
+            anyhow::bail!("no WebSocket endpoint configured — set network.chain_ws");
+        };
+        debug!(%ws_url, %registry, "subscribing to request events");
+
+        let provider: RootProvider = ProviderBuilder::default()
+            .connect_ws(WsConnect::new(ws_url.as_str()))
+            .await
+            .with_context(|| format!("could not connect to {ws_url}"))?;
+        let subscription = provider
+            .subscribe_logs(&events::request_events_filter(registry))
+            .await
+            .context("the network refused the event subscription")?;
+
+        // The stream keeps the provider alive: dropping it closes the socket.
+        Ok(subscription.into_stream().filter_map(move |log| {
+            let _connection = &provider;
+            let event = if log.removed {
+                None
+            } else {
+                RequestEvent::decode(&log.inner)
+            };
+            std::future::ready(event)
+        }))
+    }
+
+    /// Read the request events `registry` emitted in blocks `from_block`
+    /// through `to_block` (inclusive) that match `filter`, oldest first.
+    ///
+    /// Asks for [`events::SCAN_WINDOW`] blocks at a time, halving any window
+    /// the provider refuses as too large until it fits. Removed and
+    /// undecodable logs are skipped.
+    pub async fn scan_request_events(
+        &self,
+        registry: Address,
+        from_block: u64,
+        to_block: u64,
+        filter: EventFilter,
+    ) -> Result<Vec<DecodedEvent>> {
+        debug!(%registry, from_block, to_block, ?filter, "scanning request events");
+
+        let filter = filter.to_filter(registry);
+        let mut windows: VecDeque<_> =
+            events::scan_windows(from_block, to_block, events::SCAN_WINDOW).into();
+        let mut found = Vec::new();
+        while let Some((from, to)) = windows.pop_front() {
+            let window = &filter.clone().from_block(from).to_block(to);
+            let result = self
+                .read("request event logs", |provider| async move {
+                    provider.get_logs(window).await
+                })
+                .await;
+            match result {
+                Ok(logs) => found.extend(logs.iter().filter_map(DecodedEvent::decode)),
+                Err(err) => match events::split_window(from, to) {
+                    Some((lower, upper)) if events::is_range_too_large(&err.to_string()) => {
+                        debug!(from, to, "log range too large, splitting");
+                        windows.push_front(upper);
+                        windows.push_front(lower);
+                    }
+                    _ => {
+                        return Err(err).with_context(|| {
+                            format!(
+                                "unable to read request events in blocks {from} to {to} \
+                                 — check your network connection"
+                            )
+                        })
+                    }
+                },
+            }
+        }
+
+        debug!(count = found.len(), "request events scanned");
+        Ok(found)
     }
 
     /// Get the ETH balance for an address, returned in wei.
@@ -64,8 +544,9 @@ impl ChainClient {
         debug!(%address, "fetching balance");
 
         let balance = self
-            .provider
-            .get_balance(address)
+            .read("balance", |provider| async move {
+                provider.get_balance(address).await
+            })
             .await
             .context("unable to retrieve account balance — check your network connection")?;
 
@@ -78,8 +559,9 @@ impl ChainClient {
         debug!("fetching current block number");
 
         let block_number = self
-            .provider
-            .get_block_number()
+            .read("block number", |provider| async move {
+                provider.get_block_number().await
+            })
             .await
             .context("unable to reach the network — check your connection")?;
 
@@ -87,48 +569,3044 @@ impl ChainClient {
         Ok(block_number)
     }
 
+    /// Get the hash of block `number` on the chain as the RPC endpoint now
+    /// sees it, or `None` if there is no such block yet.
+    pub async fn get_block_hash(&self, number: u64) -> Result<Option<B256>> {
+        debug!(number, "fetching block hash");
+
+        let block = self
+            .read("block hash", |provider| async move {
+                provider.get_block_by_number(number.into()).await
+            })
+            .await
+            .with_context(|| format!("unable to read block {number} — check your connection"))?;
+
+        let hash = block.map(|block| block.header.hash);
+        debug!(number, ?hash, "block hash retrieved");
+        Ok(hash)
+    }
+
+    /// Get the chain ID reported by the RPC endpoint.
+    pub async fn get_chain_id(&self) -> Result<u64> {
+        debug!("fetching chain id");
+
+        let chain_id = self
+            .read("chain id", |provider| async move {
+                provider.get_chain_id().await
+            })
+            .await
+            .context("unable to reach the network — check your connection")?;
+
+        debug!(chain_id, "chain id retrieved");
+        Ok(chain_id)
+    }
+
     /// Check whether the client can reach the network.
     ///
     /// Attempts to fetch the current block number. Returns `true` on success,
     /// `false` on any error (network unreachable, invalid RPC URL, etc.).
     pub async fn is_connected(&self) -> bool {
         let connected = self.get_block_number().await.is_ok();
-        debug!(rpc_url = %self.rpc_url, connected, "connectivity check");
+        debug!(rpc_url = %self.rpc_url(), connected, "connectivity check");
         connected
     }
 
-    /// Returns the RPC URL this client is connected to.
-    pub fn rpc_url(&self) -> &str {
-        &self.rpc_url
+    /// Read the Request Registry's `validatorFeeBps` parameter.
+    pub async fn get_validator_fee_bps(&self, registry: Address) -> Result<u64> {
+        debug!(%registry, "fetching validator fee rate");
+
+        let bps = self
+            .read("validator fee rate", |provider| async move {
+                RequestRegistry::new(registry, &provider)
+                    .validatorFeeBps()
+                    .call()
+                    .await
+            })
+            .await
+            .context("unable to read marketplace fee settings — check your network connection")?;
+
+        let bps = u64::try_from(bps).context("validator fee rate out of range")?;
+        debug!(bps, "validator fee rate retrieved");
+        Ok(bps)
+    }
+
+    /// USDC balance of `owner`, in atomic units (saturating at `u64::MAX`).
+    pub async fn get_usdc_balance(&self, owner: Address) -> Result<u64> {
+        debug!(%owner, "fetching USDC balance");
+
+        let usdc = self.contracts.require(Contract::Usdc)?;
+        let balance = self
+            .read("USDC balance", |provider| async move {
+                USDC::new(usdc, &provider).balanceOf(owner).call().await
+            })
+            .await
+            .context("unable to read USDC balance — check your network connection")?;
+
+        debug!(%owner, %balance, "USDC balance retrieved");
+        Ok(saturating_u64(balance))
     }
-}
 
-// ---------------------------------------------------------------------------
-// Tests
-// ---------------------------------------------------------------------------
+    /// Both the ETH and the USDC balance of `address`.
+    pub async fn get_balances(&self, address: Address) -> Result<Balances> {
+        let eth = Balance {
+            wei: self.get_eth_balance(address).await?,
+        };
+        let usdc = self.get_usdc_balance(address).await?;
+        Ok(Balances { eth, usdc })
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// USDC that `spender` may still pull from `owner`, in atomic units
+    /// (saturating at `u64::MAX`, which covers the usual "unlimited" approval).
+    pub async fn get_usdc_allowance(&self, owner: Address, spender: Address) -> Result<u64> {
+        debug!(%owner, %spender, "fetching USDC allowance");
 
-    #[tokio::test]
-    async fn new_with_valid_url() {
-        let client = ChainClient::new("https://mainnet.base.org").await;
-        assert!(client.is_ok());
-        assert_eq!(client.unwrap().rpc_url(), "https://mainnet.base.org");
+        let usdc = self.contracts.require(Contract::Usdc)?;
+        let allowance = self
+            .read("USDC allowance", |provider| async move {
+                USDC::new(usdc, &provider)
+                    .allowance(owner, spender)
+                    .call()
+                    .await
+            })
+            .await
+            .context("unable to read USDC approval — check your network connection")?;
+
+        debug!(%owner, %spender, %allowance, "USDC allowance retrieved");
+        Ok(saturating_u64(allowance))
     }
 
-    #[tokio::test]
-    async fn new_with_invalid_url() {
-        let result = ChainClient::new("not a url").await;
-        assert!(result.is_err());
+    /// Make sure `spender` may pull `amount` USDC from `owner`, approving
+    /// it if not. Reads the current allowance and plans with
+    /// [`AllowanceAction::plan`] (`cap` as there); when an approval is
+    /// needed, `approve` is called with the current allowance and the
+    /// amount to approve. It asks the user and sends the `approve`
+    /// transaction, failing if either does not go ahead.
+    pub async fn ensure_usdc_allowance<F, Fut>(
+        &self,
+        owner: Address,
+        spender: Address,
+        amount: u64,
+        cap: u64,
+        approve: F,
+    ) -> Result<AllowanceAction>
+    where
+        F: FnOnce(u64, u64) -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        let allowance = self.get_usdc_allowance(owner, spender).await?;
+        let action = AllowanceAction::plan(allowance, amount, cap);
+        if let AllowanceAction::Approve { allowance, amount } = action {
+            debug!(%owner, %spender, allowance, amount, "USDC approval needed");
+            approve(allowance, amount).await?;
+        }
+        Ok(action)
     }
 
-    #[tokio::test]
-    async fn from_config_uses_chain_rpc() {
-        let config = crate::config::store::Config::default();
-        let client = ChainClient::from_config(&config).await;
-        assert!(client.is_ok());
-        assert_eq!(client.unwrap().rpc_url(), "https://mainnet.base.org");
+    /// Read request `request_id` from the Request Registry.
+    ///
+    /// Returns `None` if the registry has no such request (the mapping slot
+    /// is empty).
+    pub async fn get_request(
+        &self,
+        registry: Address,
+        request_id: U256,
+    ) -> Result<Option<OnchainRequest>> {
+        debug!(%registry, %request_id, "fetching request");
+
+        let stored = self
+            .read("request", |provider| async move {
+                RequestRegistry::new(registry, &provider)
+                    .requests(request_id)
+                    .call()
+                    .await
+            })
+            .await
+            .context("unable to read the request — check your network connection")?;
+
+        // Public mapping getters return the struct's fields positionally:
+        // buyer, price, deadline, targetAgentId, ipfsCid, status.
+        if stored._0 == Address::ZERO {
+            debug!(%request_id, "request not found");
+            return Ok(None);
+        }
+
+        let status = RequestStatus::from_u8(stored._5);
+        if status.is_none() {
+            warn!(%request_id, status = stored._5, "request has an unknown status");
+        }
+
+        debug!(%request_id, ?status, "request retrieved");
+        Ok(Some(OnchainRequest {
+            id: RequestId(request_id),
+            buyer: stored._0,
+            price_usdc: saturating_u64(stored._1),
+            deadline: saturating_u64(stored._2),
+            target_agent_id: AgentId(stored._3),
+            ipfs_cid: stored._4,
+            status,
+        }))
+    }
+
+    /// Read the response to request `request_id` from the Request Registry.
+    ///
+    /// Returns `None` if no response has been submitted.
+    pub async fn get_response(
+        &self,
+        registry: Address,
+        request_id: U256,
+    ) -> Result<Option<Response>> {
+        debug!(%registry, %request_id, "fetching response");
+
+        let stored = self
+            .read("response", |provider| async move {
+                RequestRegistry::new(registry, &provider)
+                    .responses(request_id)
+                    .call()
+                    .await
+            })
+            .await
+            .context("unable to read the response — check your network connection")?;
+
+        // Fields in order: seller, ipfsCid, secretHash.
+        if stored._0 == Address::ZERO {
+            debug!(%request_id, "no response submitted");
+            return Ok(None);
+        }
+        Ok(Some(Response {
+            request_id: RequestId(request_id),
+            seller: stored._0,
+            ipfs_cid: stored._1,
+            secret_hash: stored._2.0,
+        }))
+    }
+
+    /// Read the validator assigned to request `request_id` in the Request
+    /// Registry.
+    ///
+    /// Returns `None` if no validator has been assigned.
+    pub async fn get_validator(
+        &self,
+        registry: Address,
+        request_id: U256,
+    ) -> Result<Option<Address>> {
+        debug!(%registry, %request_id, "fetching validator");
+
+        let validator = self
+            .read("validator", |provider| async move {
+                RequestRegistry::new(registry, &provider)
+                    .validators(request_id)
+                    .call()
+                    .await
+            })
+            .await
+            .context("unable to read the validator — check your network connection")?;
+
+        Ok(Some(validator).filter(|validator| *validator != Address::ZERO))
+    }
+
+    /// Read the verdict recorded for request `request_id` in the Validation
+    /// Registry at `registry`.
+    ///
+    /// Returns `None` if no verdict has been recorded.
+    pub async fn get_validation(
+        &self,
+        registry: Address,
+        request_id: U256,
+    ) -> Result<Option<Validation>> {
+        debug!(%registry, %request_id, "fetching validation");
+
+        let stored = self
+            .read("validation", |provider| async move {
+                ValidationRegistry::new(registry, &provider)
+                    .getValidation(request_id)
+                    .call()
+                    .await
+            })
+            .await
+            .context("unable to read the validation — check your network connection")?;
+
+        if stored.validator == Address::ZERO {
+            debug!(%request_id, "no validation recorded");
+            return Ok(None);
+        }
+        debug!(%request_id, validator = %stored.validator, passed = stored.passed, "validation retrieved");
+        Ok(Some(Validation {
+            request_id: RequestId(request_id),
+            validator: stored.validator,
+            score: stored.score,
+            passed: stored.passed,
+            validated_at: saturating_u64(stored.timestamp),
+        }))
+    }
+
+    /// Read the history of responses `seller` submitted to the Request
+    /// Registry from its events: each request's price, its validation
+    /// verdict, and whether payment was claimed. Scans the registry's whole
+    /// history, oldest first.
+    pub async fn get_seller_history(
+        &self,
+        registry: Address,
+        seller: Address,
+    ) -> Result<Vec<SellerRecord>> {
+        use alloy::rpc::types::Filter;
+        use alloy::sol_types::SolEvent;
+        use RequestRegistry::{
+            RequestClaimed, RequestCreated, RequestValidated, ResponseSubmitted,
+        };
+
+        const CONTEXT: &str = "unable to read the agent's history — check your network connection";
+        debug!(%registry, %seller, "fetching seller history");
+
+        let from_registry = Filter::new().address(registry).from_block(0);
+        let responses_filter = &from_registry
+            .clone()
+            .event_signature(ResponseSubmitted::SIGNATURE_HASH)
+            .topic2(seller.into_word());
+        let responses = self
+            .read("seller responses", |provider| async move {
+                provider.get_logs(responses_filter).await
+            })
+            .await
+            .context(CONTEXT)?;
+
+        let mut records: Vec<SellerRecord> = responses
+            .iter()
+            .filter_map(|log| log.log_decode::<ResponseSubmitted>().ok())
+            .map(|log| SellerRecord {
+                request_id: RequestId(log.inner.data.requestId),
+                price_usdc: 0,
+                passed: None,
+                validator: None,
+                validated_at: None,
+                claimed: false,
+            })
+            .collect();
+        if records.is_empty() {
+            debug!(%seller, "no responses found");
+            return Ok(records);
+        }
+
+        let ids: Vec<_> = records
+            .iter()
+            .map(|record| alloy::primitives::B256::from(record.request_id.0))
+            .collect();
+        let events_filter = &from_registry
+            .event_signature(vec![
+                RequestCreated::SIGNATURE_HASH,
+                RequestValidated::SIGNATURE_HASH,
+                RequestClaimed::SIGNATURE_HASH,
+            ])
+            .topic1(ids);
+        let events = self
+            .read("request events", |provider| async move {
+                provider.get_logs(events_filter).await
+            })
+            .await
+            .context(CONTEXT)?;
+
+        for log in &events {
+            let Some(request_id) = log.topics().get(1).map(|id| U256::from_be_bytes(id.0)) else {
+                continue;
+            };
+            let Some(record) = records.iter_mut().find(|r| r.request_id.0 == request_id) else {
+                continue;
+            };
+            if let Ok(created) = log.log_decode::<RequestCreated>() {
+                record.price_usdc = saturating_u64(created.inner.data.price);
+            } else if let Ok(validated) = log.log_decode::<RequestValidated>() {
+                record.passed = Some(validated.inner.data.passed);
+                record.validator = Some(validated.inner.data.validator);
+                record.validated_at = log.block_timestamp;
+            } else if log.log_decode::<RequestClaimed>().is_ok() {
+                record.claimed = true;
+            }
+        }
+
+        debug!(%seller, count = records.len(), "seller history retrieved");
+        Ok(records)
+    }
+
+    /// Look up the agent ID owned by `owner` in the Agent Registry.
+    ///
+    /// Returns `None` if `owner` has not registered.
+    pub async fn get_agent_of(&self, registry: Address, owner: Address) -> Result<Option<U256>> {
+        debug!(%registry, %owner, "looking up agent of owner");
+
+        let agent_id = self
+            .read("agent of owner", |provider| async move {
+                AgentRegistry::new(registry, &provider)
+                    .agentOf(owner)
+                    .call()
+                    .await
+            })
+            .await
+            .context("unable to look up the agent — check your network connection")?;
+
+        debug!(%owner, %agent_id, "agent of owner retrieved");
+        Ok(Some(agent_id).filter(|id| !id.is_zero()))
+    }
+
+    /// Read the profile URI of agent `agent_id` from the Agent Registry.
+    ///
+    /// Returns `None` if the agent has no URI (the ID is not registered).
+    pub async fn get_agent_uri(&self, registry: Address, agent_id: U256) -> Result<Option<String>> {
+        debug!(%registry, %agent_id, "fetching agent URI");
+
+        let uri = self
+            .read("agent URI", |provider| async move {
+                AgentRegistry::new(registry, &provider)
+                    .agentURI(agent_id)
+                    .call()
+                    .await
+            })
+            .await
+            .context("unable to look up the agent — check your network connection")?;
+
+        debug!(%agent_id, %uri, "agent URI retrieved");
+        Ok(Some(uri).filter(|uri| !uri.is_empty()))
+    }
+
+    /// The nonce for the next transaction from `from`: the chain's pending
+    /// transaction count the first time, then one more than the last nonce
+    /// handed out.
+    pub async fn next_nonce(&self, from: Address) -> Result<u64> {
+        self.nonces
+            .next(from, async {
+                self.read("pending nonce", |provider| async move {
+                    provider.get_transaction_count(from).pending().await
+                })
+                .await
+                .context("unable to read the transaction count — check your network connection")
+            })
+            .await
+    }
+
+    /// Send a transaction from `from` with the next nonce: `send` is given
+    /// the nonce, submits the transaction, and returns its hash. Every
+    /// helper that sends a transaction goes through here, so each one sent
+    /// is recorded in the [`TxLog`] as `kind`, for `request_id` if given.
+    /// If the node rejects the nonce, the error is returned and the next
+    /// transaction from `from` reads its nonce from the chain again.
+    pub async fn send_with_nonce<F, Fut>(
+        &self,
+        from: Address,
+        kind: TxKind,
+        request_id: Option<&str>,
+        send: F,
+    ) -> Result<B256>
+    where
+        F: FnOnce(u64) -> Fut,
+        Fut: Future<Output = Result<B256>>,
+    {
+        let nonce = self.next_nonce(from).await?;
+        debug!(%from, nonce, %kind, "sending transaction");
+        let result = send(nonce).await;
+        match &result {
+            Ok(hash) => self.log_tx(&TxRecord::pending(
+                *hash,
+                kind,
+                request_id,
+                from,
+                nonce,
+                unix_now(),
+            )),
+            Err(err) => {
+                let message = format!("{err:#}");
+                if nonce::is_nonce_error(&message) {
+                    warn!(%from, nonce, error = %message, "transaction nonce rejected; resyncing");
+                    self.nonces.resync(from).await;
+                }
+            }
+        }
+        result
+    }
+
+    /// Record `signer`'s verdict on `request_id` in the Validation Registry
+    /// at `registry`: `score` out of 100, and whether the response passed.
+    ///
+    /// Safe to repeat: if a verdict is already recorded (by an earlier run
+    /// whose confirmation was not waited for, say), nothing is sent and the
+    /// recorded one is returned. Otherwise the transaction is sent and its
+    /// hash returned; wait for it with [`ChainClient::wait_for_receipt`].
+    /// `approve_fee` decides whether a fee over the cap is paid (see
+    /// [`ChainClient::send_call`]).
+    pub async fn submit_validation<F>(
+        &self,
+        registry: Address,
+        signer: &TransactionSigner,
+        request_id: U256,
+        score: u8,
+        passed: bool,
+        approve_fee: F,
+    ) -> Result<ValidationSubmission>
+    where
+        F: FnOnce(&FeeQuote) -> Result<()>,
+    {
+        if let Some(recorded) = self.get_validation(registry, request_id).await? {
+            debug!(%request_id, validator = %recorded.validator, "validation already recorded; not sending");
+            return Ok(ValidationSubmission::AlreadyRecorded(recorded));
+        }
+
+        let call = ValidationRegistry::submitValidationCall {
+            requestId: request_id,
+            score,
+            passed,
+        };
+        let hash = self
+            .send_call(
+                signer,
+                registry,
+                &call,
+                "validation",
+                TxKind::Validate,
+                Some(&request_id.to_string()),
+                approve_fee,
+            )
+            .await?;
+        Ok(ValidationSubmission::Sent(hash))
+    }
+
+    /// Claim payment for `request_id` in the Request Registry at `registry`
+    /// by revealing `secret`, signed by `signer`. Returns the transaction
+    /// hash; wait for it with [`ChainClient::wait_for_receipt`].
+    /// `approve_fee` decides whether a fee over the cap is paid (see
+    /// [`ChainClient::send_call`]).
+    pub async fn claim<F>(
+        &self,
+        registry: Address,
+        signer: &TransactionSigner,
+        request_id: U256,
+        secret: B256,
+        approve_fee: F,
+    ) -> Result<B256>
+    where
+        F: FnOnce(&FeeQuote) -> Result<()>,
+    {
+        let call = RequestRegistry::claimCall {
+            requestId: request_id,
+            secret,
+        };
+        self.send_call(
+            signer,
+            registry,
+            &call,
+            "claim",
+            TxKind::Claim,
+            Some(&request_id.to_string()),
+            approve_fee,
+        )
+        .await
+    }
+
+    /// Cancel `request_id` in the Request Registry at `registry`, signed by
+    /// its buyer `signer`. Returns the transaction hash; as for
+    /// [`ChainClient::claim`].
+    pub async fn cancel<F>(
+        &self,
+        registry: Address,
+        signer: &TransactionSigner,
+        request_id: U256,
+        approve_fee: F,
+    ) -> Result<B256>
+    where
+        F: FnOnce(&FeeQuote) -> Result<()>,
+    {
+        let call = RequestRegistry::cancelCall {
+            requestId: request_id,
+        };
+        self.send_call(
+            signer,
+            registry,
+            &call,
+            "cancel",
+            TxKind::Cancel,
+            Some(&request_id.to_string()),
+            approve_fee,
+        )
+        .await
+    }
+
+    /// Expire the overdue `request_id` in the Request Registry at
+    /// `registry`, signed by `signer`. Returns the transaction hash; as for
+    /// [`ChainClient::claim`].
+    pub async fn expire<F>(
+        &self,
+        registry: Address,
+        signer: &TransactionSigner,
+        request_id: U256,
+        approve_fee: F,
+    ) -> Result<B256>
+    where
+        F: FnOnce(&FeeQuote) -> Result<()>,
+    {
+        let call = RequestRegistry::expireCall {
+            requestId: request_id,
+        };
+        self.send_call(
+            signer,
+            registry,
+            &call,
+            "expiry",
+            TxKind::Expire,
+            Some(&request_id.to_string()),
+            approve_fee,
+        )
+        .await
+    }
+
+    /// Let `spender` pull up to `amount_usdc` (atomic units) of USDC from
+    /// `signer`. Returns the transaction hash; as for
+    /// [`ChainClient::claim`].
+    pub async fn approve_usdc<F>(
+        &self,
+        signer: &TransactionSigner,
+        spender: Address,
+        amount_usdc: u64,
+        approve_fee: F,
+    ) -> Result<B256>
+    where
+        F: FnOnce(&FeeQuote) -> Result<()>,
+    {
+        let call = USDC::approveCall {
+            spender,
+            amount: U256::from(amount_usdc),
+        };
+        self.send_call(
+            signer,
+            self.contracts.require(Contract::Usdc)?,
+            &call,
+            "approval",
+            TxKind::Approve,
+            None,
+            approve_fee,
+        )
+        .await
+    }
+
+    /// Submit a response to `request_id` in the Request Registry at
+    /// `registry`: the deliverable at `ipfs_cid`, locked with
+    /// `secret_hash`, signed by `signer`. Returns the transaction hash; as
+    /// for [`ChainClient::claim`].
+    pub async fn submit_response<F>(
+        &self,
+        registry: Address,
+        signer: &TransactionSigner,
+        request_id: U256,
+        ipfs_cid: &str,
+        secret_hash: B256,
+        approve_fee: F,
+    ) -> Result<B256>
+    where
+        F: FnOnce(&FeeQuote) -> Result<()>,
+    {
+        let call = RequestRegistry::submitResponseCall {
+            requestId: request_id,
+            ipfsCid: ipfs_cid.to_string(),
+            secretHash: secret_hash,
+        };
+        self.send_call(
+            signer,
+            registry,
+            &call,
+            "response",
+            TxKind::Respond,
+            Some(&request_id.to_string()),
+            approve_fee,
+        )
+        .await
+    }
+
+    /// Register `signer`'s address with the Agent Registry at `registry`,
+    /// publishing `agent_uri`. Returns the transaction hash; as for
+    /// [`ChainClient::claim`].
+    pub async fn register<F>(
+        &self,
+        registry: Address,
+        signer: &TransactionSigner,
+        agent_uri: &str,
+        approve_fee: F,
+    ) -> Result<B256>
+    where
+        F: FnOnce(&FeeQuote) -> Result<()>,
+    {
+        let call = AgentRegistry::registerCall {
+            agentURI: agent_uri.to_string(),
+        };
+        self.send_call(
+            signer,
+            registry,
+            &call,
+            "registration",
+            TxKind::Register,
+            None,
+            approve_fee,
+        )
+        .await
+    }
+
+    /// Send `amount_usdc` (atomic units) of USDC from `signer` to `to`.
+    /// Returns the transaction hash; as for [`ChainClient::claim`].
+    pub async fn transfer_usdc<F>(
+        &self,
+        signer: &TransactionSigner,
+        to: Address,
+        amount_usdc: u64,
+        approve_fee: F,
+    ) -> Result<B256>
+    where
+        F: FnOnce(&FeeQuote) -> Result<()>,
+    {
+        let call = USDC::transferCall {
+            to,
+            amount: U256::from(amount_usdc),
+        };
+        self.send_call(
+            signer,
+            self.contracts.require(Contract::Usdc)?,
+            &call,
+            "transfer",
+            TxKind::Withdraw,
+            None,
+            approve_fee,
+        )
+        .await
+    }
+
+    /// Send `amount_wei` of ETH from `signer` to `to` as a plain value
+    /// transfer. Returns the transaction hash; as for
+    /// [`ChainClient::claim`].
+    pub async fn transfer_eth<F>(
+        &self,
+        signer: &TransactionSigner,
+        to: Address,
+        amount_wei: U256,
+        approve_fee: F,
+    ) -> Result<B256>
+    where
+        F: FnOnce(&FeeQuote) -> Result<()>,
+    {
+        self.send_tx(
+            signer,
+            to,
+            Vec::new(),
+            amount_wei,
+            "transfer",
+            TxKind::Withdraw,
+            None,
+            approve_fee,
+        )
+        .await
+    }
+
+    /// Sign and send `call` to `to` from `signer`, through
+    /// [`ChainClient::send_with_nonce`] (so it is logged as `kind`). It is
+    /// [simulated](ChainClient::simulate) first, so one that would revert
+    /// fails with the reason and costs nothing; `what` names its fee.
+    ///
+    /// This is where every transaction gets its EIP-1559 fees, from
+    /// [`ChainClient::fee_quote`]. A quote over `fees.max_fee_gwei_cap` is
+    /// passed to `approve_fee`, and nothing is sent unless it returns `Ok`.
+    #[allow(clippy::too_many_arguments)]
+    async fn send_call<C, F>(
+        &self,
+        signer: &TransactionSigner,
+        to: Address,
+        call: &C,
+        what: &str,
+        kind: TxKind,
+        request_id: Option<&str>,
+        approve_fee: F,
+    ) -> Result<B256>
+    where
+        C: SolCall,
+        F: FnOnce(&FeeQuote) -> Result<()>,
+    {
+        self.simulate(signer.address(), to, call).await?;
+        self.send_tx(
+            signer,
+            to,
+            call.abi_encode(),
+            U256::ZERO,
+            what,
+            kind,
+            request_id,
+            approve_fee,
+        )
+        .await
+    }
+
+    /// Sign and send `input` with `value` wei to `to` from `signer`, as
+    /// [`ChainClient::send_call`] does but without simulating it first.
+    #[allow(clippy::too_many_arguments)]
+    async fn send_tx<F>(
+        &self,
+        signer: &TransactionSigner,
+        to: Address,
+        input: Vec<u8>,
+        value: U256,
+        what: &str,
+        kind: TxKind,
+        request_id: Option<&str>,
+        approve_fee: F,
+    ) -> Result<B256>
+    where
+        F: FnOnce(&FeeQuote) -> Result<()>,
+    {
+        use alloy::network::TransactionBuilder;
+
+        let from = signer.address();
+        let tx = TransactionRequest::default()
+            .from(from)
+            .to(to)
+            .value(value)
+            .input(input.clone().into());
+        let gas = self.estimate_gas(&tx, what).await?;
+        let quote = self.fee_quote().await?;
+        if quote.over_cap() {
+            approve_fee(&quote)?;
+        }
+        let chain_id = self.get_chain_id().await?;
+
+        self.send_with_nonce(from, kind, request_id, |nonce| async move {
+            let tx = TransactionRequest::default()
+                .from(from)
+                .to(to)
+                .value(value)
+                .input(input.into())
+                .nonce(nonce)
+                .gas_limit(gas)
+                .max_fee_per_gas(quote.max_fee_per_gas_wei)
+                .max_priority_fee_per_gas(quote.max_priority_fee_per_gas_wei)
+                .with_chain_id(chain_id);
+            self.broadcast(signer, tx).await
+        })
+        .await
+    }
+
+    /// Sign `tx` with `signer` and hand it to the network. Returns its hash.
+    async fn broadcast(&self, signer: &TransactionSigner, tx: TransactionRequest) -> Result<B256> {
+        use alloy::eips::eip2718::Encodable2718;
+        use alloy::network::{EthereumWallet, TransactionBuilder};
+
+        let wallet = EthereumWallet::from(signer.inner().clone());
+        let raw = tx
+            .build(&wallet)
+            .await
+            .context("failed to sign the transaction")?
+            .encoded_2718();
+        let raw = &raw;
+        // Not retried: a timed-out broadcast may still have gone out.
+        let pending = self
+            .read_with(
+                &RetryPolicy::none(),
+                "transaction broadcast",
+                |provider| async move { provider.send_raw_transaction(raw).await },
+            )
+            .await
+            .context("the network did not accept the transaction")?;
+        Ok(*pending.tx_hash())
+    }
+
+    /// Replace the stuck transaction `tx_hash`, sent by `signer`, with one
+    /// that takes the same nonce and bids at least `fee_bump_percent` more
+    /// (see [`fees::bump`]): the same call again to speed it up, or with
+    /// `cancel` an empty send to the agent itself, so that it never runs.
+    /// Then wait as [`ChainClient::wait_for_receipt`] does for whichever of
+    /// the two is mined, and record in the [`TxLog`] which one landed.
+    ///
+    /// Refuses a transaction that is already mined, that the node does not
+    /// know, or that another address sent. A fee over the cap is passed to
+    /// `approve_fee`, as in [`ChainClient::send_call`].
+    #[allow(clippy::too_many_arguments)]
+    pub async fn replace_transaction<F>(
+        &self,
+        signer: &TransactionSigner,
+        tx_hash: B256,
+        fee_bump_percent: u64,
+        cancel: bool,
+        confirmations: u64,
+        timeout: Duration,
+        approve_fee: F,
+    ) -> Result<Replacement>
+    where
+        F: FnOnce(&FeeQuote) -> Result<()>,
+    {
+        use alloy::consensus::Transaction as _;
+
+        let receipt = self
+            .read("transaction receipt", |provider| async move {
+                provider.get_transaction_receipt(tx_hash).await
+            })
+            .await
+            .context("unable to look up the transaction — check your network connection")?;
+        if let Some(receipt) = receipt {
+            bail!(
+                "Transaction {tx_hash} was already mined in block {}; there is nothing to replace.",
+                receipt.block_number.unwrap_or_default()
+            );
+        }
+        let original = self
+            .read("transaction", |provider| async move {
+                provider.get_transaction_by_hash(tx_hash).await
+            })
+            .await
+            .context("unable to look up the transaction — check your network connection")?
+            .with_context(|| {
+                format!(
+                    "The network does not know transaction {tx_hash}. It may have been dropped, \
+                     or replaced already."
+                )
+            })?;
+        if let Some(block_number) = original.block_number {
+            bail!(
+                "Transaction {tx_hash} was already mined in block {block_number}; there is nothing to replace."
+            );
+        }
+        let from = original.inner.signer();
+        if from != signer.address() {
+            bail!(
+                "Transaction {tx_hash} was sent by {from}, not by this agent ({}); only its sender can replace it.",
+                signer.address()
+            );
+        }
+
+        let previous_max_fee = original.max_fee_per_gas();
+        let previous_priority = original
+            .max_priority_fee_per_gas()
+            .unwrap_or(previous_max_fee);
+        let quote = fees::bump(
+            previous_max_fee,
+            previous_priority,
+            fee_bump_percent,
+            &self.fee_quote().await?,
+        );
+        if quote.over_cap() {
+            approve_fee(&quote)?;
+        }
+        let chain_id = match original.chain_id() {
+            Some(chain_id) => chain_id,
+            None => self.get_chain_id().await?,
+        };
+
+        let nonce = original.nonce();
+        debug!(%tx_hash, nonce, cancel, ?quote, "replacing transaction");
+        let tx = replacement_request(&original, from, &quote, cancel, chain_id);
+        let replacement = self.broadcast(signer, tx).await?;
+        if let Some(record) = self.logged(tx_hash) {
+            let kind = if cancel { TxKind::Void } else { record.kind };
+            self.log_tx(&TxRecord::pending(
+                replacement,
+                kind,
+                record.request_id.as_deref(),
+                from,
+                nonce,
+                unix_now(),
+            ));
+        }
+
+        let (landed, outcome) = self
+            .wait_for_first(&[replacement, tx_hash], confirmations, timeout)
+            .await;
+        if let Some(landed) = landed {
+            let dropped = if landed == replacement {
+                tx_hash
+            } else {
+                replacement
+            };
+            if let Some(record) = self.logged(dropped) {
+                self.log_tx(&record.replaced(landed, unix_now()));
+            }
+        }
+        Ok(Replacement {
+            original: tx_hash,
+            replacement,
+            nonce,
+            cancel,
+            max_fee_per_gas_wei: quote.max_fee_per_gas_wei,
+            landed,
+            outcome,
+        })
+    }
+
+    /// Append `record` to the transaction log. The transaction has already
+    /// gone out, so a log that cannot be written is only warned about.
+    fn log_tx(&self, record: &TxRecord) {
+        let Some(txlog) = &self.txlog else {
+            return;
+        };
+        if let Err(err) = txlog.append(record) {
+            warn!(hash = %record.hash, "could not record transaction: {err:#}");
+        }
+    }
+
+    /// The latest record of `tx_hash` in the transaction log, if it is
+    /// there.
+    fn logged(&self, tx_hash: B256) -> Option<TxRecord> {
+        let txlog = self.txlog.as_ref()?;
+        match txlog.find(tx_hash) {
+            Ok(Some(record)) => Some(record),
+            Ok(None) => {
+                debug!(%tx_hash, "transaction not in the log");
+                None
+            }
+            Err(err) => {
+                warn!(%tx_hash, "could not read the transaction log: {err:#}");
+                None
+            }
+        }
+    }
+
+    /// Record the final `outcome` of `tx_hash`, if it was sent through the
+    /// transaction log.
+    fn log_outcome(&self, tx_hash: B256, outcome: &TxOutcome) {
+        if let Some(settled) = self
+            .logged(tx_hash)
+            .and_then(|record| record.settled(outcome, unix_now()))
+        {
+            self.log_tx(&settled);
+        }
+    }
+
+    /// Wait for the transaction `tx_hash` to be mined and buried under
+    /// `confirmations` blocks (its own block counts as the first), or for
+    /// `timeout` to pass. A reverted transaction is reported as soon as it
+    /// is mined, with the reason recovered by replaying it. Failed polls
+    /// are retried until the timeout rather than reported. A zero timeout
+    /// looks once. Final outcomes are recorded in the [`TxLog`].
+    pub async fn wait_for_receipt(
+        &self,
+        tx_hash: B256,
+        confirmations: u64,
+        timeout: Duration,
+    ) -> Result<TxOutcome> {
+        debug!(%tx_hash, confirmations, ?timeout, "waiting for receipt");
+        let (_, outcome) = self
+            .wait_for_first(&[tx_hash], confirmations, timeout)
+            .await;
+        Ok(outcome)
+    }
+
+    /// Wait as [`ChainClient::wait_for_receipt`] does for the first of
+    /// `hashes` to settle, looking at each in turn every poll. Returns it
+    /// and its outcome (recorded in the [`TxLog`]), or `None` and
+    /// [`TxOutcome::TimedOut`] at the timeout.
+    async fn wait_for_first(
+        &self,
+        hashes: &[B256],
+        confirmations: u64,
+        timeout: Duration,
+    ) -> (Option<B256>, TxOutcome) {
+        let deadline = Instant::now() + timeout;
+        loop {
+            for &tx_hash in hashes {
+                match self.poll_receipt(tx_hash, confirmations).await {
+                    Ok(Some(outcome)) => {
+                        debug!(%tx_hash, ?outcome, "transaction settled");
+                        self.log_outcome(tx_hash, &outcome);
+                        return (Some(tx_hash), outcome);
+                    }
+                    Ok(None) => {}
+                    Err(err) => {
+                        debug!(%tx_hash, error = %format!("{err:#}"), "receipt poll failed")
+                    }
+                }
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                debug!(?hashes, "gave up waiting for receipt");
+                return (None, TxOutcome::TimedOut);
+            }
+            tokio::time::sleep(self.receipt_poll.min(deadline - now)).await;
+        }
+    }
+
+    /// One look at `tx_hash`: its outcome once it is settled, `None` while
+    /// it is pending or not yet confirmed enough.
+    async fn poll_receipt(&self, tx_hash: B256, confirmations: u64) -> Result<Option<TxOutcome>> {
+        let receipt = self
+            .read("transaction receipt", |provider| async move {
+                provider.get_transaction_receipt(tx_hash).await
+            })
+            .await?;
+        let Some(receipt) = receipt else {
+            return Ok(None);
+        };
+        let block_number = receipt.block_number.unwrap_or_default();
+
+        if !receipt.status() {
+            let reason = self.replay_revert(tx_hash, block_number).await;
+            return Ok(Some(TxOutcome::Reverted {
+                block_number,
+                reason,
+            }));
+        }
+
+        let head = self.get_block_number().await?;
+        let depth = head.saturating_sub(block_number).saturating_add(1);
+        if depth < confirmations.max(1) {
+            debug!(%tx_hash, depth, confirmations, "awaiting confirmations");
+            return Ok(None);
+        }
+        Ok(Some(TxOutcome::Success {
+            block_number,
+            gas_used: receipt.gas_used,
+        }))
+    }
+
+    /// Why `tx_hash`, mined in `block_number`, reverted: the transaction is
+    /// replayed with `eth_call` on the state before that block. `None` if
+    /// it cannot be fetched or the replay no longer reverts.
+    async fn replay_revert(&self, tx_hash: B256, block_number: u64) -> Option<String> {
+        let tx = self
+            .read("transaction", |provider| async move {
+                provider.get_transaction_by_hash(tx_hash).await
+            })
+            .await
+            .ok()
+            .flatten()?;
+        let from = tx.inner.signer();
+        let replay = &tx.into_request().from(from);
+        let parent = block_number.saturating_sub(1);
+
+        match self
+            .read("revert replay", |provider| async move {
+                provider.call(replay.clone()).block(parent.into()).await
+            })
+            .await
+        {
+            Ok(_) => {
+                debug!(%tx_hash, "replay did not revert");
+                None
+            }
+            Err(err) => receipt::revert_reason(&err),
+        }
+    }
+
+    /// Estimate the fee for `from` registering `agent_uri` with the Agent
+    /// Registry at `registry`, at the current gas price. Nothing is sent.
+    pub async fn estimate_register_cost(
+        &self,
+        registry: Address,
+        from: Address,
+        agent_uri: &str,
+    ) -> Result<FeeEstimate> {
+        debug!(%registry, %from, "estimating registration fee");
+
+        let call = AgentRegistry::registerCall {
+            agentURI: agent_uri.to_string(),
+        };
+        self.estimate_call(registry, from, call.abi_encode(), "registration")
+            .await
+    }
+
+    /// Estimate the fee for `from` creating a request with the Request
+    /// Registry at `registry`, at the current gas price. Nothing is sent.
+    pub async fn estimate_create_request_cost(
+        &self,
+        registry: Address,
+        from: Address,
+        request_uri: &str,
+        price_usdc: u64,
+        deadline: u64,
+        target_agent_id: u64,
+    ) -> Result<FeeEstimate> {
+        debug!(%registry, %from, "estimating request fee");
+
+        let call = RequestRegistry::createRequestCall {
+            ipfsCid: request_uri.to_string(),
+            price: U256::from(price_usdc),
+            deadline: U256::from(deadline),
+            targetAgentId: U256::from(target_agent_id),
+        };
+        self.estimate_call(registry, from, call.abi_encode(), "request")
+            .await
+    }
+
+    /// Estimate the fee for `from` submitting a response to `request_id`
+    /// with the Request Registry at `registry`. Nothing is sent.
+    pub async fn estimate_submit_response_cost(
+        &self,
+        registry: Address,
+        from: Address,
+        request_id: U256,
+        response_uri: &str,
+        secret_hash: B256,
+    ) -> Result<FeeEstimate> {
+        debug!(%registry, %from, %request_id, "estimating response fee");
+
+        let call = RequestRegistry::submitResponseCall {
+            requestId: request_id,
+            ipfsCid: response_uri.to_string(),
+            secretHash: secret_hash,
+        };
+        self.estimate_call(registry, from, call.abi_encode(), "response")
+            .await
+    }
+
+    /// Estimate the fee for `from` claiming `request_id` by revealing
+    /// `secret` to the Request Registry at `registry`. Nothing is sent; an
+    /// estimate fails if the claim would revert.
+    pub async fn estimate_claim_cost(
+        &self,
+        registry: Address,
+        from: Address,
+        request_id: U256,
+        secret: B256,
+    ) -> Result<FeeEstimate> {
+        debug!(%registry, %from, %request_id, "estimating claim fee");
+
+        let call = RequestRegistry::claimCall {
+            requestId: request_id,
+            secret,
+        };
+        self.estimate_call(registry, from, call.abi_encode(), "claim")
+            .await
+    }
+
+    /// Estimate the fee for `from` cancelling `request_id` with the Request
+    /// Registry at `registry`. Nothing is sent.
+    pub async fn estimate_cancel_cost(
+        &self,
+        registry: Address,
+        from: Address,
+        request_id: U256,
+    ) -> Result<FeeEstimate> {
+        debug!(%registry, %from, %request_id, "estimating cancel fee");
+
+        let call = RequestRegistry::cancelCall {
+            requestId: request_id,
+        };
+        self.estimate_call(registry, from, call.abi_encode(), "cancel")
+            .await
+    }
+
+    /// Estimate the fee for `from` recording a verdict on `request_id` with
+    /// the Validation Registry at `registry`. Nothing is sent.
+    pub async fn estimate_submit_validation_cost(
+        &self,
+        registry: Address,
+        from: Address,
+        request_id: U256,
+        score: u8,
+        passed: bool,
+    ) -> Result<FeeEstimate> {
+        debug!(%registry, %from, %request_id, "estimating validation fee");
+
+        let call = ValidationRegistry::submitValidationCall {
+            requestId: request_id,
+            score,
+            passed,
+        };
+        self.estimate_call(registry, from, call.abi_encode(), "validation")
+            .await
+    }
+
+    /// Estimate the fee for `from` sending `amount_usdc` (atomic units) of
+    /// USDC to `to`, at the current gas price. Nothing is sent.
+    pub async fn estimate_usdc_transfer_cost(
+        &self,
+        from: Address,
+        to: Address,
+        amount_usdc: u64,
+    ) -> Result<FeeEstimate> {
+        debug!(%from, %to, amount_usdc, "estimating transfer fee");
+
+        let call = USDC::transferCall {
+            to,
+            amount: U256::from(amount_usdc),
+        };
+        self.estimate_call(
+            self.contracts.require(Contract::Usdc)?,
+            from,
+            call.abi_encode(),
+            "transfer",
+        )
+        .await
+    }
+
+    /// Estimate the fee for `from` sending `amount_wei` of ETH to `to`, at
+    /// the current gas price. Nothing is sent.
+    pub async fn estimate_eth_transfer_cost(
+        &self,
+        from: Address,
+        to: Address,
+        amount_wei: U256,
+    ) -> Result<FeeEstimate> {
+        debug!(%from, %to, %amount_wei, "estimating transfer fee");
+
+        let tx = TransactionRequest::default()
+            .from(from)
+            .to(to)
+            .value(amount_wei);
+        self.estimate_tx(tx, "transfer").await
+    }
+
+    /// Run `call` to `to` from `from` with `eth_call`, as it would execute
+    /// if sent now, without sending it. Fails with the contract's reason
+    /// (see [`receipt::decode_revert`]) if it would revert, so a doomed
+    /// transaction costs no gas.
+    pub async fn simulate<C: SolCall>(&self, from: Address, to: Address, call: &C) -> Result<()> {
+        debug!(%from, %to, call = C::SIGNATURE, "simulating transaction");
+
+        let tx = &TransactionRequest::default()
+            .from(from)
+            .to(to)
+            .input(call.abi_encode().into());
+        let result = self
+            .read("simulation", |provider| async move {
+                provider.call(tx.clone()).await
+            })
+            .await;
+        match result {
+            Ok(_) => Ok(()),
+            Err(err) if receipt::is_revert(&err) => {
+                let reason = receipt::revert_reason(&err);
+                debug!(call = C::SIGNATURE, ?reason, "simulation reverted");
+                match reason {
+                    Some(reason) => {
+                        bail!("the transaction would be rejected by the contract: {reason}")
+                    }
+                    None => bail!("the transaction would be rejected by the contract"),
+                }
+            }
+            Err(err) => Err(err).context(
+                "unable to check the transaction before sending it — check your network connection",
+            ),
+        }
+    }
+
+    /// Gas for `from` sending `input` to `to`, and the current gas price.
+    /// `what` names the fee in errors.
+    async fn estimate_call(
+        &self,
+        to: Address,
+        from: Address,
+        input: Vec<u8>,
+        what: &str,
+    ) -> Result<FeeEstimate> {
+        let tx = TransactionRequest::default()
+            .from(from)
+            .to(to)
+            .input(input.into());
+        self.estimate_tx(tx, what).await
+    }
+
+    /// Estimate the network fee for sending `tx`: the gas it would use and
+    /// the most it would pay per unit of gas. Nothing is sent.
+    pub async fn estimate_fee(&self, tx: TransactionRequest) -> Result<FeeEstimate> {
+        self.estimate_tx(tx, "network").await
+    }
+
+    /// The fees a transaction sent now would bid, per the `[fees]` policy:
+    /// the next block's base fee scaled by the strategy, plus the tip.
+    pub async fn fee_quote(&self) -> Result<FeeQuote> {
+        use alloy::eips::BlockNumberOrTag;
+
+        let history = self
+            .read("fee history", |provider| async move {
+                provider
+                    .get_fee_history(1, BlockNumberOrTag::Latest, &[])
+                    .await
+            })
+            .await
+            .context("unable to read the current network fee — check your network connection")?;
+        let base_fee = history.next_block_base_fee().unwrap_or_default();
+        let quote = fees::compute(base_fee, &self.fee_policy);
+        debug!(?quote, "fees quoted");
+        Ok(quote)
+    }
+
+    /// Gas for `tx` and the current max fee per gas. `what` names the fee
+    /// in errors.
+    async fn estimate_tx(&self, tx: TransactionRequest, what: &str) -> Result<FeeEstimate> {
+        let gas = self.estimate_gas(&tx, what).await?;
+
+        let max_fee_per_gas_wei = self
+            .read("gas price", |provider| async move {
+                provider.get_gas_price().await
+            })
+            .await
+            .context("unable to read the current network fee — check your network connection")?;
+
+        let estimate = FeeEstimate {
+            gas,
+            max_fee_per_gas_wei,
+        };
+        debug!(?estimate, what, "fee estimated");
+        Ok(estimate)
+    }
+
+    /// Gas `tx` would use. `what` names the fee in errors.
+    async fn estimate_gas(&self, tx: &TransactionRequest, what: &str) -> Result<u64> {
+        self.read("gas estimate", |provider| async move {
+            provider.estimate_gas(tx.clone()).await
+        })
+        .await
+        .with_context(|| {
+            format!("unable to estimate the {what} fee — check your network connection")
+        })
+    }
+
+    /// Returns the RPC URL reads currently go to.
+    pub fn rpc_url(&self) -> String {
+        self.lock_active().failover.url().to_string()
+    }
+}
+
+/// Gas for a plain ETH transfer to an account with no code, which is all a
+/// cancelling replacement is.
+const TRANSFER_GAS: u64 = 21_000;
+
+/// The transaction replacing `original`, sent by `from`, bidding `fees`. It
+/// keeps the original's nonce, so only one of the two can be mined. A
+/// speed-up repeats the original call; a cancel sends nothing to `from`
+/// itself, which does nothing but use up the nonce.
+fn replacement_request(
+    original: &impl alloy::consensus::Transaction,
+    from: Address,
+    fees: &FeeQuote,
+    cancel: bool,
+    chain_id: u64,
+) -> TransactionRequest {
+    use alloy::network::TransactionBuilder;
+
+    let tx = TransactionRequest::default()
+        .from(from)
+        .nonce(original.nonce())
+        .max_fee_per_gas(fees.max_fee_per_gas_wei)
+        .max_priority_fee_per_gas(fees.max_priority_fee_per_gas_wei)
+        .with_chain_id(chain_id);
+    if cancel {
+        return tx.to(from).value(U256::ZERO).gas_limit(TRANSFER_GAS);
+    }
+    let mut tx = tx
+        .value(original.value())
+        .input(original.input().clone().into())
+        .gas_limit(original.gas_limit());
+    if let Some(access_list) = original.access_list() {
+        tx = tx.access_list(access_list.clone());
+    }
+    match original.to() {
+        Some(to) => tx.to(to),
+        None => tx.into_create(),
+    }
+}
+
+fn saturating_u64(value: U256) -> u64 {
+    u64::try_from(value).unwrap_or(u64::MAX)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::failover;
+
+    #[tokio::test]
+    async fn new_with_valid_url() {
+        let client = ChainClient::new("https://mainnet.base.org").await;
+        assert!(client.is_ok());
+        assert_eq!(client.unwrap().rpc_url(), "https://mainnet.base.org");
+    }
+
+    #[tokio::test]
+    async fn new_with_invalid_url() {
+        let result = ChainClient::new("not a url").await;
+        assert!(result.is_err());
+
+        // Every endpoint is checked, not just the one connected first.
+        let endpoints = RpcEndpoints::new(vec![
+            "https://mainnet.base.org".to_string(),
+            "not a url".to_string(),
+        ])
+        .unwrap();
+        let err = ChainClient::new(endpoints).await.err().unwrap();
+        assert!(err.to_string().contains("not a url"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn from_config_uses_chain_rpc() {
+        let config = crate::config::store::Config::default();
+        let client = ChainClient::from_config(&config).await;
+        assert!(client.is_ok());
+        assert_eq!(client.unwrap().rpc_url(), "https://mainnet.base.org");
+    }
+
+    #[tokio::test]
+    async fn subscriptions_need_a_ws_endpoint() {
+        let mut config = crate::config::store::Config::default();
+        let client = ChainClient::from_config(&config).await.unwrap();
+        assert!(!client.can_subscribe());
+        let err = client
+            .subscribe_request_events(Address::ZERO)
+            .await
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("network.chain_ws"), "{err}");
+
+        config.network.chain_ws = "ws://127.0.0.1:1".to_string();
+        let client = ChainClient::from_config(&config).await.unwrap();
+        assert!(client.can_subscribe());
+        assert!(
+            client
+                .subscribe_request_events(Address::ZERO)
+                .await
+                .is_err(),
+            "nothing listens on port 1"
+        );
+    }
+
+    #[tokio::test]
+    async fn shared_returns_one_client_per_endpoint() {
+        let config = |rpc: &str| {
+            let mut config = crate::config::store::Config::default();
+            config.network.chain_rpc = rpc.into();
+            config
+        };
+        let a = ChainClient::shared(&config("https://shared.example"))
+            .await
+            .unwrap();
+        let b = ChainClient::shared(&config("https://shared.example"))
+            .await
+            .unwrap();
+        let c = ChainClient::shared(&config("https://other.example"))
+            .await
+            .unwrap();
+        assert!(Arc::ptr_eq(&a, &b));
+        assert!(!Arc::ptr_eq(&a, &c));
+    }
+
+    #[tokio::test]
+    async fn reads_are_spaced_by_min_interval() {
+        let client = ChainClient::mocked(Default::default());
+        let start = Instant::now();
+        for _ in 0..3 {
+            client.throttle().await;
+        }
+        assert!(start.elapsed() >= MIN_READ_INTERVAL * 2);
+    }
+
+    #[tokio::test]
+    async fn reads_retry_rate_limits_but_not_rejections() {
+        let failure = |code: i64, message: &str| {
+            serde_json::from_value(serde_json::json!({ "code": code, "message": message })).unwrap()
+        };
+        let asserter = alloy::providers::mock::Asserter::new();
+        let client = ChainClient::mocked(asserter.clone()).with_retry(RetryPolicy {
+            max_retries: 2,
+            base_delay: Duration::from_millis(1),
+            jitter: false,
+        });
+
+        asserter.push_failure(failure(-32005, "limit exceeded"));
+        asserter.push_failure(failure(-32005, "limit exceeded"));
+        asserter.push_success(&"0x10");
+        assert_eq!(client.get_block_number().await.unwrap(), 16);
+
+        asserter.push_failure(failure(-32602, "invalid params"));
+        asserter.push_success(&"0x11");
+        assert!(client.get_block_number().await.is_err());
+        assert_eq!(
+            client.get_block_number().await.unwrap(),
+            17,
+            "the rejected read was not retried"
+        );
+
+        for _ in 0..3 {
+            asserter.push_failure(failure(-32005, "limit exceeded"));
+        }
+        assert!(
+            client.get_block_number().await.is_err(),
+            "gives up after two retries"
+        );
+    }
+
+    #[tokio::test]
+    async fn reads_fail_over_to_the_next_endpoint() {
+        let failure = |code: i64, message: &str| {
+            serde_json::from_value(serde_json::json!({ "code": code, "message": message })).unwrap()
+        };
+        let primary = alloy::providers::mock::Asserter::new();
+        let fallback = alloy::providers::mock::Asserter::new();
+        let endpoints = || {
+            vec![
+                ("https://failover-primary.example", primary.clone()),
+                ("https://failover-fallback.example", fallback.clone()),
+            ]
+        };
+        // No retries configured: every endpoint still gets one try.
+        let client = ChainClient::mocked_endpoints(endpoints()).with_retry(RetryPolicy::none());
+        assert_eq!(client.rpc_url(), "https://failover-primary.example");
+
+        primary.push_failure(failure(-32005, "limit exceeded"));
+        fallback.push_success(&"0x10");
+        assert_eq!(client.get_block_number().await.unwrap(), 16);
+        assert_eq!(client.rpc_url(), "https://failover-fallback.example");
+
+        // Reads stay on the endpoint that works.
+        fallback.push_success(&"0x11");
+        assert_eq!(client.get_block_number().await.unwrap(), 17);
+
+        // A client created later starts at the healthy endpoint.
+        let later = ChainClient::mocked_endpoints(endpoints());
+        assert_eq!(later.rpc_url(), "https://failover-fallback.example");
+        let health = failover::health("https://failover-primary.example");
+        assert_eq!(
+            health.last_error.as_deref(),
+            Some("server returned an error response: error code -32005: limit exceeded")
+        );
+
+        // A rejection is an answer: no failover.
+        fallback.push_failure(failure(-32602, "invalid params"));
+        assert!(client.get_block_number().await.is_err());
+        assert_eq!(client.rpc_url(), "https://failover-fallback.example");
+
+        // Once the fallback goes down too, reads wrap around to the primary.
+        fallback.push_failure(failure(-32005, "limit exceeded"));
+        primary.push_success(&"0x12");
+        assert_eq!(client.get_block_number().await.unwrap(), 18);
+        assert_eq!(client.rpc_url(), "https://failover-primary.example");
+    }
+
+    #[test]
+    fn chain_check_names_both_networks() {
+        let check = ChainCheck {
+            expected: 8453,
+            actual: 1,
+        };
+        assert!(!check.matches());
+        let err = check.ensure_matches().unwrap_err().to_string();
+        assert!(
+            err.starts_with("Connected network (1) doesn't match configured network (8453)."),
+            "{err}"
+        );
+        assert!(err.contains("--allow-chain-mismatch"), "{err}");
+        assert!(ChainCheck {
+            expected: 8453,
+            actual: 8453
+        }
+        .ensure_matches()
+        .is_ok());
+    }
+
+    #[tokio::test]
+    async fn ensure_chain_refuses_the_wrong_network() {
+        let asserter = alloy::providers::mock::Asserter::new();
+        asserter.push_success(&"0x1");
+        let client = ChainClient::mocked(asserter);
+        let err = client.ensure_chain(8453).await.unwrap_err();
+        assert!(err.to_string().contains("Connected network (1)"), "{err}");
+        assert_eq!(
+            client.chain_check(),
+            Some(ChainCheck {
+                expected: 8453,
+                actual: 1
+            })
+        );
+
+        set_allow_chain_mismatch(true);
+        let allowed = client.ensure_chain(8453).await;
+        set_allow_chain_mismatch(false);
+        assert!(allowed.is_ok(), "the cached mismatch is only warned about");
+    }
+
+    #[tokio::test]
+    async fn ensure_chain_accepts_the_configured_network() {
+        let asserter = alloy::providers::mock::Asserter::new();
+        asserter.push_success(&"0x2105");
+        let client = ChainClient::mocked(asserter);
+        client.ensure_chain(8453).await.unwrap();
+        // Cached: the mock has nothing left to answer with.
+        client.ensure_chain(8453).await.unwrap();
+        assert!(client.chain_check().unwrap().matches());
+
+        // An unreadable chain ID does not stop the client; reads report it.
+        let client = ChainClient::mocked(alloy::providers::mock::Asserter::new());
+        client.ensure_chain(8453).await.unwrap();
+        assert_eq!(client.chain_check(), None);
+    }
+
+    #[tokio::test]
+    async fn get_chain_id_decodes_quantity() {
+        let asserter = alloy::providers::mock::Asserter::new();
+        asserter.push_success(&"0x2105");
+        let client = ChainClient::mocked(asserter);
+        assert_eq!(client.get_chain_id().await.unwrap(), 8453);
+    }
+
+    #[tokio::test]
+    async fn estimate_register_cost_multiplies_gas_by_price() {
+        let asserter = alloy::providers::mock::Asserter::new();
+        asserter.push_success(&"0x249f0"); // 150_000 gas
+        asserter.push_success(&"0x989680"); // 0.01 gwei
+        let client = ChainClient::mocked(asserter);
+
+        let estimate = client
+            .estimate_register_cost(
+                Address::repeat_byte(0x11),
+                Address::repeat_byte(0x22),
+                "ipfs://bafyprofile",
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            estimate,
+            FeeEstimate {
+                gas: 150_000,
+                max_fee_per_gas_wei: 10_000_000
+            }
+        );
+        assert_eq!(estimate.cost().display_eth_with(8), "0.00000150 ETH");
+    }
+
+    #[tokio::test]
+    async fn estimate_create_request_cost_reports_failures() {
+        let asserter = alloy::providers::mock::Asserter::new();
+        asserter.push_success(&"0x30d40"); // 200_000 gas
+        asserter.push_success(&"0x3b9aca00"); // 1 gwei
+        asserter.push_failure_msg("execution reverted");
+        let client = ChainClient::mocked(asserter);
+        let estimate = || {
+            client.estimate_create_request_cost(
+                Address::repeat_byte(0x11),
+                Address::repeat_byte(0x22),
+                "ipfs://bafyrequest",
+                5_000_000,
+                1_700_000_000,
+                42,
+            )
+        };
+
+        assert_eq!(
+            estimate().await.unwrap().cost().display_eth_with(4),
+            "0.0002 ETH"
+        );
+        let err = format!("{:#}", estimate().await.unwrap_err());
+        assert!(err.contains("unable to estimate the request fee"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn concurrent_sends_get_increasing_nonces() {
+        let asserter = alloy::providers::mock::Asserter::new();
+        asserter.push_success(&"0x5");
+        let client = ChainClient::mocked(asserter.clone());
+        let from = Address::repeat_byte(0x11);
+        let sent = Mutex::new(Vec::new());
+        let send = || {
+            client.send_with_nonce(from, TxKind::Claim, None, |nonce| {
+                sent.lock().unwrap().push(nonce);
+                async move { Ok(B256::with_last_byte(nonce as u8)) }
+            })
+        };
+
+        let (a, b, c) = tokio::join!(send(), send(), send());
+        assert_eq!(
+            [a.unwrap(), b.unwrap(), c.unwrap()],
+            [5, 6, 7].map(B256::with_last_byte)
+        );
+        assert_eq!(*sent.lock().unwrap(), [5, 6, 7], "assigned in order");
+        assert!(asserter.read_q().is_empty());
+    }
+
+    #[tokio::test]
+    async fn nonce_errors_resync_from_the_chain() {
+        let asserter = alloy::providers::mock::Asserter::new();
+        asserter.push_success(&"0x5");
+        let client = ChainClient::mocked(asserter.clone());
+        let from = Address::repeat_byte(0x11);
+
+        // A failure unrelated to the nonce keeps counting locally.
+        let err = client
+            .send_with_nonce(from, TxKind::Claim, None, |_| async {
+                anyhow::bail!("execution reverted") as Result<B256>
+            })
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("execution reverted"));
+        assert_eq!(client.next_nonce(from).await.unwrap(), 6);
+
+        // Another wallet used the address: the node rejects nonce 7.
+        let err = client
+            .send_with_nonce(from, TxKind::Claim, None, |nonce| async move {
+                anyhow::bail!("nonce too low: next nonce 9, tx nonce {nonce}") as Result<B256>
+            })
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("tx nonce 7"), "{err}");
+
+        asserter.push_success(&"0x9");
+        let hash = client.send_with_nonce(from, TxKind::Claim, None, |nonce| async move {
+            Ok(B256::with_last_byte(nonce as u8))
+        });
+        assert_eq!(hash.await.unwrap(), B256::with_last_byte(9));
+        assert_eq!(client.next_nonce(from).await.unwrap(), 10);
+    }
+
+    const TX_HASH: B256 = B256::repeat_byte(0xab);
+
+    /// A receipt for [`TX_HASH`] mined in `block`, as a node returns it.
+    fn receipt_json(block: u64, success: bool) -> serde_json::Value {
+        serde_json::json!({
+            "transactionHash": TX_HASH,
+            "transactionIndex": "0x0",
+            "blockHash": B256::repeat_byte(0x01),
+            "blockNumber": format!("{block:#x}"),
+            "from": Address::repeat_byte(0x11),
+            "to": Address::repeat_byte(0x22),
+            "contractAddress": null,
+            "gasUsed": "0xc350",
+            "cumulativeGasUsed": "0xc350",
+            "effectiveGasPrice": "0x989680",
+            "logs": [],
+            "logsBloom": format!("0x{}", "0".repeat(512)),
+            "type": "0x2",
+            "status": if success { "0x1" } else { "0x0" },
+        })
+    }
+
+    /// The transaction behind [`TX_HASH`], for replaying a revert.
+    fn transaction_json(block: u64) -> serde_json::Value {
+        serde_json::json!({
+            "hash": TX_HASH,
+            "nonce": "0x4",
+            "blockHash": B256::repeat_byte(0x01),
+            "blockNumber": format!("{block:#x}"),
+            "transactionIndex": "0x0",
+            "from": Address::repeat_byte(0x11),
+            "to": Address::repeat_byte(0x22),
+            "value": "0x0",
+            "gasPrice": "0x989680",
+            "gas": "0x30d40",
+            "input": "0x",
+            "chainId": "0x2105",
+            "v": "0x422d", // EIP-155 on chain 8453
+            "r": "0x1",
+            "s": "0x1",
+            "type": "0x0",
+        })
+    }
+
+    #[tokio::test]
+    async fn wait_for_receipt_counts_confirmations() {
+        let asserter = alloy::providers::mock::Asserter::new();
+        asserter.push_success(&Option::<()>::None); // still pending
+        asserter.push_success(&receipt_json(10, true));
+        asserter.push_success(&"0xa"); // head: mined, 1 confirmation
+        asserter.push_success(&receipt_json(10, true));
+        asserter.push_success(&"0xb"); // head: 2 confirmations
+        let client =
+            ChainClient::mocked(asserter.clone()).with_receipt_poll(Duration::from_millis(1));
+
+        let outcome = client
+            .wait_for_receipt(TX_HASH, 2, Duration::from_secs(30))
+            .await
+            .unwrap();
+        assert_eq!(
+            outcome,
+            TxOutcome::Success {
+                block_number: 10,
+                gas_used: 50_000
+            }
+        );
+        assert!(asserter.read_q().is_empty());
+    }
+
+    #[tokio::test]
+    async fn wait_for_receipt_replays_reverts_for_the_reason() {
+        let asserter = alloy::providers::mock::Asserter::new();
+        asserter.push_success(&receipt_json(10, false));
+        asserter.push_success(&transaction_json(10));
+        asserter.push_failure(
+            serde_json::from_value(serde_json::json!({
+                "code": 3,
+                "message": "execution reverted",
+                "data": "0x08c379a0\
+                    0000000000000000000000000000000000000000000000000000000000000020\
+                    000000000000000000000000000000000000000000000000000000000000000e\
+                    4e6f74207468652073656c6c6572000000000000000000000000000000000000",
+            }))
+            .unwrap(),
+        );
+        let client = ChainClient::mocked(asserter);
+
+        let outcome = client
+            .wait_for_receipt(TX_HASH, 3, Duration::from_secs(30))
+            .await
+            .unwrap();
+        assert_eq!(
+            outcome,
+            TxOutcome::Reverted {
+                block_number: 10,
+                reason: Some("Not the seller".to_string())
+            },
+            "a revert is final without waiting for confirmations"
+        );
+    }
+
+    #[tokio::test]
+    async fn wait_for_receipt_reverts_without_replay_have_no_reason() {
+        let asserter = alloy::providers::mock::Asserter::new();
+        asserter.push_success(&receipt_json(10, false));
+        asserter.push_success(&Option::<()>::None); // transaction pruned
+        let client = ChainClient::mocked(asserter);
+
+        let outcome = client
+            .wait_for_receipt(TX_HASH, 1, Duration::from_secs(30))
+            .await
+            .unwrap();
+        assert_eq!(
+            outcome,
+            TxOutcome::Reverted {
+                block_number: 10,
+                reason: None
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn wait_for_receipt_times_out_through_failed_polls() {
+        let asserter = alloy::providers::mock::Asserter::new();
+        asserter.push_success(&Option::<()>::None);
+        asserter.push_failure_msg("upstream unavailable");
+        let client = ChainClient::mocked(asserter)
+            .with_retry(RetryPolicy::none())
+            .with_receipt_poll(Duration::from_millis(1));
+
+        let outcome = client
+            .wait_for_receipt(TX_HASH, 1, Duration::from_millis(400))
+            .await
+            .unwrap();
+        assert_eq!(outcome, TxOutcome::TimedOut);
+    }
+
+    #[tokio::test]
+    async fn estimate_fee_prices_any_transaction() {
+        let asserter = alloy::providers::mock::Asserter::new();
+        asserter.push_success(&"0x5208"); // 21_000 gas
+        asserter.push_success(&"0x3b9aca00"); // 1 gwei
+        asserter.push_failure_msg("execution reverted");
+        let client = ChainClient::mocked(asserter);
+        let tx = TransactionRequest::default()
+            .from(Address::repeat_byte(0x11))
+            .to(Address::repeat_byte(0x22));
+
+        let estimate = client.estimate_fee(tx.clone()).await.unwrap();
+        assert_eq!(
+            estimate,
+            FeeEstimate {
+                gas: 21_000,
+                max_fee_per_gas_wei: 1_000_000_000
+            }
+        );
+        assert_eq!(
+            estimate.display(3000.0),
+            "0.00002100 ETH (~$0.06; 21000 gas at up to 1 gwei)"
+        );
+
+        let err = format!("{:#}", client.estimate_fee(tx).await.unwrap_err());
+        assert!(err.contains("unable to estimate the network fee"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn get_balances_reads_eth_then_usdc() {
+        let asserter = alloy::providers::mock::Asserter::new();
+        asserter.push_success(&"0x470de4df820000"); // 0.02 ETH
+        asserter.push_success(&alloy::primitives::Bytes::from(
+            U256::from(14_500_000u64).to_be_bytes::<32>().to_vec(),
+        ));
+        let client = ChainClient::mocked(asserter);
+
+        let balances = client
+            .get_balances(Address::repeat_byte(0x11))
+            .await
+            .unwrap();
+        assert_eq!(balances.display_gas(), "Gas balance: 0.0200 ETH");
+        assert_eq!(balances.usdc, 14_500_000);
+    }
+
+    #[tokio::test]
+    async fn usdc_reads_need_a_configured_token() {
+        use super::contracts::Network;
+
+        // Nothing is queued: the read must fail before reaching the network.
+        let asserter = alloy::providers::mock::Asserter::new();
+        let client = ChainClient::mocked(asserter).with_contracts(Network::Local.preset());
+        let owner = Address::repeat_byte(0x11);
+
+        let err = client
+            .get_usdc_balance(owner)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("contracts.usdc"), "{err}");
+        let err = client
+            .estimate_usdc_transfer_cost(owner, owner, 1)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("AGENTMARKET_USDC"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn get_agent_uri_treats_empty_as_missing() {
+        use alloy::providers::mock::Asserter;
+        use alloy::sol_types::SolValue;
+
+        let asserter = Asserter::new();
+        let encoded =
+            |uri: &str| alloy::primitives::Bytes::from((uri.to_string(),).abi_encode_params());
+        asserter.push_success(&encoded("ipfs://bafyprofile"));
+        asserter.push_success(&encoded(""));
+        let client = ChainClient::mocked(asserter);
+        let registry = Address::repeat_byte(0x11);
+
+        assert_eq!(
+            client.get_agent_uri(registry, U256::from(7)).await.unwrap(),
+            Some("ipfs://bafyprofile".to_string())
+        );
+        assert_eq!(
+            client.get_agent_uri(registry, U256::from(8)).await.unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn get_seller_history_joins_registry_events() {
+        use alloy::primitives::B256;
+        use alloy::providers::mock::Asserter;
+        use alloy::sol_types::SolEvent;
+        use RequestRegistry::{
+            RequestClaimed, RequestCreated, RequestValidated, ResponseSubmitted,
+        };
+
+        let registry = Address::repeat_byte(0x11);
+        let seller = Address::repeat_byte(0x33);
+        let validator = Address::repeat_byte(0x44);
+        let log =
+            |data: alloy::primitives::LogData, timestamp: Option<u64>| alloy::rpc::types::Log {
+                inner: alloy::primitives::Log {
+                    address: registry,
+                    data,
+                },
+                block_timestamp: timestamp,
+                ..Default::default()
+            };
+        let responded = |id: u64| {
+            log(
+                ResponseSubmitted {
+                    requestId: U256::from(id),
+                    seller,
+                    secretHash: B256::repeat_byte(0xab),
+                }
+                .encode_log_data(),
+                None,
+            )
+        };
+        let created = |id: u64, price: u64| {
+            log(
+                RequestCreated {
+                    requestId: U256::from(id),
+                    buyer: Address::repeat_byte(0x22),
+                    price: U256::from(price),
+                    deadline: U256::from(1_700_000_000u64),
+                }
+                .encode_log_data(),
+                None,
+            )
+        };
+        let validated = |id: u64, passed: bool| {
+            log(
+                RequestValidated {
+                    requestId: U256::from(id),
+                    passed,
+                    validator,
+                }
+                .encode_log_data(),
+                Some(1_700_000_100),
+            )
+        };
+        let claimed = |id: u64| {
+            log(
+                RequestClaimed {
+                    requestId: U256::from(id),
+                    secret: B256::repeat_byte(0xcd),
+                }
+                .encode_log_data(),
+                None,
+            )
+        };
+
+        let asserter = Asserter::new();
+        asserter.push_success(&vec![responded(7), responded(8), responded(9)]);
+        asserter.push_success(&vec![
+            created(7, 5_000_000),
+            created(8, 2_000_000),
+            created(9, 1_000_000),
+            validated(7, true),
+            validated(8, false),
+            claimed(7),
+        ]);
+        asserter.push_success(&Vec::<alloy::rpc::types::Log>::new());
+        let client = ChainClient::mocked(asserter);
+
+        let history = client.get_seller_history(registry, seller).await.unwrap();
+        assert_eq!(
+            history,
+            vec![
+                SellerRecord {
+                    request_id: RequestId(U256::from(7)),
+                    price_usdc: 5_000_000,
+                    passed: Some(true),
+                    validator: Some(validator),
+                    validated_at: Some(1_700_000_100),
+                    claimed: true,
+                },
+                SellerRecord {
+                    request_id: RequestId(U256::from(8)),
+                    price_usdc: 2_000_000,
+                    passed: Some(false),
+                    validator: Some(validator),
+                    validated_at: Some(1_700_000_100),
+                    claimed: false,
+                },
+                SellerRecord {
+                    request_id: RequestId(U256::from(9)),
+                    price_usdc: 1_000_000,
+                    passed: None,
+                    validator: None,
+                    validated_at: None,
+                    claimed: false,
+                },
+            ]
+        );
+
+        // A seller with no responses needs no second query.
+        assert!(client
+            .get_seller_history(registry, seller)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn get_request_decodes_mapping_and_detects_missing() {
+        use alloy::providers::mock::Asserter;
+        use alloy::sol_types::SolValue;
+
+        let buyer = Address::repeat_byte(0x22);
+        let stored = (
+            buyer,
+            U256::from(5_000_000u64),
+            U256::from(1_700_000_000u64),
+            U256::from(42u64),
+            "bafyrequest".to_string(),
+            U256::from(5u8), // a uint8 encodes as a full word
+        );
+        let empty = (
+            Address::ZERO,
+            U256::ZERO,
+            U256::ZERO,
+            U256::ZERO,
+            String::new(),
+            U256::ZERO,
+        );
+
+        let asserter = Asserter::new();
+        asserter.push_success(&alloy::primitives::Bytes::from(stored.abi_encode_params()));
+        asserter.push_success(&alloy::primitives::Bytes::from(empty.abi_encode_params()));
+        let client = ChainClient::mocked(asserter);
+        let registry = Address::repeat_byte(0x11);
+
+        let request = client
+            .get_request(registry, U256::from(7))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(request.id, RequestId(U256::from(7)));
+        assert_eq!(request.buyer, buyer);
+        assert_eq!(request.price_usdc, 5_000_000);
+        assert_eq!(request.deadline, 1_700_000_000);
+        assert_eq!(request.target_agent_id, AgentId(U256::from(42)));
+        assert_eq!(request.ipfs_cid, "bafyrequest");
+        assert_eq!(request.status, Some(RequestStatus::Expired));
+
+        assert!(client
+            .get_request(registry, U256::from(8))
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn get_request_tolerates_unknown_status() {
+        use alloy::providers::mock::Asserter;
+        use alloy::sol_types::SolValue;
+
+        let stored = (
+            Address::repeat_byte(0x22),
+            U256::from(1u64),
+            U256::from(2u64),
+            U256::ZERO,
+            "bafyrequest".to_string(),
+            U256::from(6u8), // one past Expired
+        );
+        let asserter = Asserter::new();
+        asserter.push_success(&alloy::primitives::Bytes::from(stored.abi_encode_params()));
+        let client = ChainClient::mocked(asserter);
+
+        let request = client
+            .get_request(Address::repeat_byte(0x11), U256::from(7))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(request.ipfs_cid, "bafyrequest");
+        assert_eq!(request.status, None);
+    }
+
+    #[tokio::test]
+    async fn get_response_decodes_mapping_and_detects_missing() {
+        use alloy::primitives::B256;
+        use alloy::providers::mock::Asserter;
+        use alloy::sol_types::SolValue;
+
+        let seller = Address::repeat_byte(0x33);
+        let stored = (seller, "bafyresponse".to_string(), B256::repeat_byte(0xab));
+        let empty = (Address::ZERO, String::new(), B256::ZERO);
+
+        let asserter = Asserter::new();
+        asserter.push_success(&alloy::primitives::Bytes::from(stored.abi_encode_params()));
+        asserter.push_success(&alloy::primitives::Bytes::from(empty.abi_encode_params()));
+        let client = ChainClient::mocked(asserter);
+        let registry = Address::repeat_byte(0x11);
+
+        let response = client
+            .get_response(registry, U256::from(7))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(response.request_id, RequestId(U256::from(7)));
+        assert_eq!(response.seller, seller);
+        assert_eq!(response.ipfs_cid, "bafyresponse");
+        assert_eq!(response.secret_hash, [0xab; 32]);
+
+        assert!(client
+            .get_response(registry, U256::from(8))
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn get_validator_reads_mapping_and_detects_unassigned() {
+        use alloy::providers::mock::Asserter;
+        use alloy::sol_types::SolValue;
+
+        let validator = Address::repeat_byte(0x44);
+        let asserter = Asserter::new();
+        asserter.push_success(&alloy::primitives::Bytes::from(validator.abi_encode()));
+        asserter.push_success(&alloy::primitives::Bytes::from(Address::ZERO.abi_encode()));
+        let client = ChainClient::mocked(asserter);
+        let registry = Address::repeat_byte(0x11);
+
+        assert_eq!(
+            client.get_validator(registry, U256::from(7)).await.unwrap(),
+            Some(validator)
+        );
+        assert_eq!(
+            client.get_validator(registry, U256::from(8)).await.unwrap(),
+            None
+        );
+    }
+
+    /// `eth_feeHistory` for one block, with `next_base_fee` as the next
+    /// block's base fee.
+    fn fee_history(next_base_fee: u128) -> serde_json::Value {
+        serde_json::json!({
+            "oldestBlock": "0x10",
+            "baseFeePerGas": ["0x3b9aca00", format!("{next_base_fee:#x}")],
+            "gasUsedRatio": [0.5],
+        })
+    }
+
+    fn recorded_validation(
+        validator: Address,
+        score: u8,
+        passed: bool,
+    ) -> alloy::primitives::Bytes {
+        use alloy::sol_types::SolValue;
+        ValidationRegistry::Validation {
+            validator,
+            score,
+            passed,
+            timestamp: U256::from(1_700_000_000u64),
+        }
+        .abi_encode()
+        .into()
+    }
+
+    #[tokio::test]
+    async fn get_validation_decodes_and_detects_unrecorded() {
+        let validator = Address::repeat_byte(0x44);
+        let asserter = alloy::providers::mock::Asserter::new();
+        asserter.push_success(&recorded_validation(validator, 92, true));
+        asserter.push_success(&recorded_validation(Address::ZERO, 0, false));
+        let client = ChainClient::mocked(asserter);
+        let registry = Address::repeat_byte(0x33);
+
+        assert_eq!(
+            client
+                .get_validation(registry, U256::from(7))
+                .await
+                .unwrap(),
+            Some(Validation {
+                request_id: RequestId(U256::from(7)),
+                validator,
+                score: 92,
+                passed: true,
+                validated_at: 1_700_000_000,
+            })
+        );
+        assert_eq!(
+            client
+                .get_validation(registry, U256::from(8))
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn submit_validation_skips_a_recorded_verdict() {
+        let validator = Address::repeat_byte(0x44);
+        let asserter = alloy::providers::mock::Asserter::new();
+        asserter.push_success(&recorded_validation(validator, 30, false));
+        let client = ChainClient::mocked(asserter);
+        let signer = TransactionSigner::from_bytes(&mut [0x5a; 32]).unwrap();
+
+        // Only the lookup is answered: anything sent would fail the test.
+        let submission = client
+            .submit_validation(
+                Address::repeat_byte(0x33),
+                &signer,
+                U256::from(7),
+                90,
+                true,
+                |_| unreachable!("nothing is sent"),
+            )
+            .await
+            .unwrap();
+        match submission {
+            ValidationSubmission::AlreadyRecorded(recorded) => {
+                assert_eq!(recorded.validator, validator);
+                assert!(!recorded.passed);
+            }
+            other => panic!("expected the recorded verdict, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn submit_validation_signs_sends_and_logs() {
+        let tmp = tempfile::tempdir().unwrap();
+        let txlog = TxLog::at(tmp.path().join("txlog.jsonl"));
+        let asserter = alloy::providers::mock::Asserter::new();
+        asserter.push_success(&recorded_validation(Address::ZERO, 0, false));
+        asserter.push_success(&alloy::primitives::Bytes::new()); // simulation
+        asserter.push_success(&"0xc350"); // 50_000 gas
+        asserter.push_success(&fee_history(1_000_000_000));
+        asserter.push_success(&"0x2105"); // chain 8453
+        asserter.push_success(&"0x3"); // pending nonce
+        asserter.push_success(&TX_HASH);
+        let client = ChainClient::mocked(asserter).with_txlog(txlog.clone());
+        let signer = TransactionSigner::from_bytes(&mut [0x5a; 32]).unwrap();
+
+        let submission = client
+            .submit_validation(
+                Address::repeat_byte(0x33),
+                &signer,
+                U256::from(7),
+                90,
+                true,
+                |quote| panic!("no cap is set, yet {quote:?} was over it"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(submission, ValidationSubmission::Sent(TX_HASH));
+
+        let records = txlog.records().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].kind, TxKind::Validate);
+        assert_eq!(records[0].request_id.as_deref(), Some("7"));
+        assert_eq!(records[0].from, signer.address());
+        assert_eq!(records[0].nonce, 3);
+    }
+
+    #[tokio::test]
+    async fn transfer_eth_sends_a_plain_transfer_and_logs_it() {
+        let tmp = tempfile::tempdir().unwrap();
+        let txlog = TxLog::at(tmp.path().join("txlog.jsonl"));
+        let asserter = alloy::providers::mock::Asserter::new();
+        // Nothing to simulate: a plain transfer goes straight to estimation.
+        asserter.push_success(&"0x5208"); // 21_000 gas
+        asserter.push_success(&fee_history(1_000_000_000));
+        asserter.push_success(&"0x2105"); // chain 8453
+        asserter.push_success(&"0x4"); // pending nonce
+        asserter.push_success(&TX_HASH);
+        let client = ChainClient::mocked(asserter).with_txlog(txlog.clone());
+        let signer = TransactionSigner::from_bytes(&mut [0x5a; 32]).unwrap();
+
+        let hash = client
+            .transfer_eth(
+                &signer,
+                Address::repeat_byte(0x44),
+                U256::from(1_000_000_000_000_000u64),
+                |quote| panic!("no cap is set, yet {quote:?} was over it"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(hash, TX_HASH);
+
+        let records = txlog.records().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].kind, TxKind::Withdraw);
+        assert_eq!(records[0].request_id, None);
+        assert_eq!(records[0].nonce, 4);
+    }
+
+    #[tokio::test]
+    async fn submit_validation_asks_before_paying_over_the_cap() {
+        use super::super::fees::{FeeAboveCap, FeeStrategy};
+
+        let tmp = tempfile::tempdir().unwrap();
+        let txlog = TxLog::at(tmp.path().join("txlog.jsonl"));
+        let asserter = alloy::providers::mock::Asserter::new();
+        asserter.push_success(&recorded_validation(Address::ZERO, 0, false));
+        asserter.push_success(&alloy::primitives::Bytes::new()); // simulation
+        asserter.push_success(&"0xc350"); // 50_000 gas
+        asserter.push_success(&fee_history(3_000_000_000));
+        let client = ChainClient::mocked(asserter)
+            .with_txlog(txlog.clone())
+            .with_fee_policy(FeePolicy {
+                strategy: FeeStrategy::Normal,
+                max_priority_wei: 1_000_000,
+                cap_wei: Some(5_000_000_000),
+            });
+        let signer = TransactionSigner::from_bytes(&mut [0x5a; 32]).unwrap();
+
+        let mut asked = None;
+        let err = client
+            .submit_validation(
+                Address::repeat_byte(0x33),
+                &signer,
+                U256::from(7),
+                90,
+                true,
+                |quote| {
+                    asked = Some(*quote);
+                    Err(FeeAboveCap { quote: *quote }.into())
+                },
+            )
+            .await
+            .unwrap_err();
+        let quote = asked.expect("asked about the fee");
+        assert_eq!(quote.max_fee_per_gas_wei, 6_001_000_000);
+        assert!(err.downcast_ref::<FeeAboveCap>().is_some(), "{err}");
+        assert!(txlog.records().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn fee_quote_applies_the_policy_to_the_next_base_fee() {
+        use super::super::fees::FeeStrategy;
+
+        let asserter = alloy::providers::mock::Asserter::new();
+        asserter.push_success(&fee_history(2_000_000_000));
+        let client = ChainClient::mocked(asserter).with_fee_policy(FeePolicy {
+            strategy: FeeStrategy::Slow,
+            max_priority_wei: 1_000_000,
+            cap_wei: None,
+        });
+
+        let quote = client.fee_quote().await.unwrap();
+        assert_eq!(quote.base_fee_wei, 2_000_000_000);
+        assert_eq!(quote.max_fee_per_gas_wei, 2_501_000_000);
+        assert_eq!(quote.max_priority_fee_per_gas_wei, 1_000_000);
+        assert!(!quote.over_cap());
+    }
+
+    #[tokio::test]
+    async fn submit_validation_sends_nothing_that_would_revert() {
+        use alloy::sol_types::SolError;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let txlog = TxLog::at(tmp.path().join("txlog.jsonl"));
+        let asserter = alloy::providers::mock::Asserter::new();
+        asserter.push_success(&recorded_validation(Address::ZERO, 0, false));
+        let data = RequestRegistry::NotValidator {
+            caller: Address::repeat_byte(0x44),
+        }
+        .abi_encode();
+        asserter.push_failure(
+            serde_json::from_value(serde_json::json!({
+                "code": 3,
+                "message": "execution reverted",
+                "data": alloy::primitives::Bytes::from(data),
+            }))
+            .unwrap(),
+        );
+        let client = ChainClient::mocked(asserter).with_txlog(txlog.clone());
+        let signer = TransactionSigner::from_bytes(&mut [0x5a; 32]).unwrap();
+
+        let err = client
+            .submit_validation(
+                Address::repeat_byte(0x33),
+                &signer,
+                U256::from(7),
+                90,
+                true,
+                |_| unreachable!("nothing is sent"),
+            )
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("is not the assigned validator"),
+            "{err}"
+        );
+        assert!(txlog.records().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn simulate_reports_reverts_in_friendly_terms() {
+        use crate::output::formatter;
+
+        let revert = |data: Option<&str>, message: &str| {
+            serde_json::from_value(match data {
+                Some(data) => serde_json::json!({ "code": 3, "message": message, "data": data }),
+                None => serde_json::json!({ "code": -32000, "message": message }),
+            })
+            .unwrap()
+        };
+        let asserter = alloy::providers::mock::Asserter::new();
+        asserter.push_success(&alloy::primitives::Bytes::new());
+        // RequestNotOpen(7), hand-encoded.
+        let not_open = format!(
+            "0x{}{:064x}",
+            alloy::hex::encode(&alloy::primitives::keccak256("RequestNotOpen(uint256)")[..4]),
+            7
+        );
+        asserter.push_failure(revert(Some(&not_open), "execution reverted"));
+        asserter.push_failure(revert(None, "execution reverted"));
+        asserter.push_failure(revert(Some("0xdeadbeef"), "execution reverted"));
+        let client = ChainClient::mocked(asserter).with_retry(RetryPolicy::none());
+        let from = Address::repeat_byte(0x11);
+        let registry = Address::repeat_byte(0x22);
+        let cancel = RequestRegistry::cancelCall {
+            requestId: U256::from(7),
+        };
+
+        client.simulate(from, registry, &cancel).await.unwrap();
+
+        let err = client.simulate(from, registry, &cancel).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "the transaction would be rejected by the contract: request 7 is not open"
+        );
+        assert_eq!(
+            formatter::format_error(&err),
+            "Request already has a response and can no longer be changed."
+        );
+
+        let err = client.simulate(from, registry, &cancel).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "the transaction would be rejected by the contract"
+        );
+
+        // An error no contract declares is named by its selector.
+        let err = client.simulate(from, registry, &cancel).await.unwrap_err();
+        assert!(
+            err.to_string().ends_with("custom error 0xdeadbeef"),
+            "{err}"
+        );
+    }
+
+    #[tokio::test]
+    async fn simulate_failing_to_reach_the_node_is_a_network_error() {
+        let asserter = alloy::providers::mock::Asserter::new();
+        asserter.push_failure_msg("upstream unavailable");
+        let client = ChainClient::mocked(asserter).with_retry(RetryPolicy::none());
+
+        let err = client
+            .simulate(
+                Address::repeat_byte(0x11),
+                Address::repeat_byte(0x22),
+                &RequestRegistry::expireCall {
+                    requestId: U256::from(7),
+                },
+            )
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("check your network connection"),
+            "{err}"
+        );
+    }
+
+    #[tokio::test]
+    async fn get_block_hash_reads_the_header_hash() {
+        use alloy::primitives::B256;
+        use alloy::providers::mock::Asserter;
+
+        let asserter = Asserter::new();
+        let mut block = alloy::rpc::types::Block::<alloy::rpc::types::Transaction>::default();
+        block.header.hash = B256::repeat_byte(0x42);
+        asserter.push_success(&block);
+        asserter.push_success(&serde_json::Value::Null);
+        let client = ChainClient::mocked(asserter);
+
+        assert_eq!(
+            client.get_block_hash(100).await.unwrap(),
+            Some(B256::repeat_byte(0x42))
+        );
+        assert_eq!(client.get_block_hash(1_000_000).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn scan_request_events_chunks_and_splits_large_windows() {
+        use crate::chain::events::{EventFilter, RequestEvent};
+        use alloy::primitives::B256;
+        use alloy::providers::mock::Asserter;
+        use alloy::sol_types::SolEvent;
+        use RequestRegistry::{RequestClaimed, RequestCreated};
+
+        let failure = |code: i64, message: &str| {
+            serde_json::from_value(serde_json::json!({ "code": code, "message": message })).unwrap()
+        };
+        let registry = Address::repeat_byte(0x11);
+        let buyer = Address::repeat_byte(0x22);
+        let log = |data: alloy::primitives::LogData, block: u64| alloy::rpc::types::Log {
+            inner: alloy::primitives::Log {
+                address: registry,
+                data,
+            },
+            block_number: Some(block),
+            block_hash: Some(B256::repeat_byte(block as u8)),
+            transaction_hash: Some(B256::with_last_byte(block as u8)),
+            log_index: Some(0),
+            ..Default::default()
+        };
+        let created = |id: u64, block: u64| {
+            log(
+                RequestCreated {
+                    requestId: U256::from(id),
+                    buyer,
+                    price: U256::from(1_000_000u64),
+                    deadline: U256::from(1_700_000_000u64),
+                }
+                .encode_log_data(),
+                block,
+            )
+        };
+        let claimed = |id: u64, block: u64| {
+            log(
+                RequestClaimed {
+                    requestId: U256::from(id),
+                    secret: B256::repeat_byte(0xcd),
+                }
+                .encode_log_data(),
+                block,
+            )
+        };
+
+        // Blocks 0..=24_999 make three windows; the first is refused as too
+        // large and split in two.
+        let asserter = Asserter::new();
+        asserter.push_failure(failure(-32005, "query returned more than 10000 results"));
+        asserter.push_success(&vec![created(1, 10)]);
+        let mut removed = created(2, 6_000);
+        removed.removed = true;
+        asserter.push_success(&vec![created(3, 6_001), removed]);
+        asserter.push_success(&Vec::<alloy::rpc::types::Log>::new());
+        asserter.push_success(&vec![claimed(1, 24_000)]);
+        let client = ChainClient::mocked(asserter.clone());
+
+        let found = client
+            .scan_request_events(registry, 0, 24_999, EventFilter::default())
+            .await
+            .unwrap();
+        assert!(asserter.read_q().is_empty(), "one call per window");
+        let seen: Vec<_> = found
+            .iter()
+            .map(|event| (event.block_number, event.event.request_id().0))
+            .collect();
+        assert_eq!(
+            seen,
+            [
+                (10, U256::from(1)),
+                (6_001, U256::from(3)),
+                (24_000, U256::from(1))
+            ]
+        );
+        assert!(matches!(found[2].event, RequestEvent::Claimed { .. }));
+
+        // Other errors, and a single block still too large, are reported.
+        asserter.push_failure(failure(-32602, "invalid params"));
+        let err = client
+            .scan_request_events(registry, 0, 100, EventFilter::default())
+            .await
+            .unwrap_err();
+        assert!(format!("{err:#}").contains("blocks 0 to 100"), "{err:#}");
+
+        asserter.push_failure(failure(-32005, "query returned more than 10000 results"));
+        assert!(client
+            .scan_request_events(registry, 7, 7, EventFilter::default())
+            .await
+            .is_err());
+        assert!(asserter.read_q().is_empty());
+
+        assert!(client
+            .scan_request_events(registry, 8, 7, EventFilter::default())
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn ensure_usdc_allowance_approves_only_when_short() {
+        use alloy::providers::mock::Asserter;
+        use alloy::sol_types::SolValue;
+        use std::cell::Cell;
+
+        let allowance = |units: u64| alloy::primitives::Bytes::from(U256::from(units).abi_encode());
+
+        let owner = Address::repeat_byte(0x22);
+        let spender = Address::repeat_byte(0x11);
+        let asserter = Asserter::new();
+        let client = ChainClient::mocked(asserter.clone());
+        let approved = Cell::new(None);
+        let approve = |allowance: u64, amount: u64| {
+            approved.set(Some((allowance, amount)));
+            async { Ok(()) }
+        };
+
+        asserter.push_success(&allowance(5_000_000));
+        let action = client
+            .ensure_usdc_allowance(owner, spender, 5_000_000, 0, approve)
+            .await
+            .unwrap();
+        assert_eq!(
+            action,
+            AllowanceAction::Sufficient {
+                allowance: 5_000_000
+            }
+        );
+        assert_eq!(approved.get(), None);
+
+        asserter.push_success(&allowance(1_000_000));
+        let action = client
+            .ensure_usdc_allowance(owner, spender, 5_000_000, 20_000_000, approve)
+            .await
+            .unwrap();
+        assert!(action.needs_approval());
+        assert_eq!(approved.get(), Some((1_000_000, 20_000_000)));
+
+        // A declined approval fails the whole call.
+        asserter.push_success(&allowance(0));
+        let err = client
+            .ensure_usdc_allowance(owner, spender, 5_000_000, 0, |_, _| async {
+                anyhow::bail!("Stopped at your request. Nothing was done.")
+            })
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Stopped"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn sent_transactions_and_outcomes_are_logged() {
+        use crate::chain::txlog::TxStatus;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let txlog = TxLog::at(tmp.path().join("txlog.jsonl"));
+        let asserter = alloy::providers::mock::Asserter::new();
+        let client = ChainClient::mocked(asserter.clone()).with_txlog(txlog.clone());
+        let from = Address::repeat_byte(0x11);
+
+        asserter.push_success(&"0x4");
+        let hash = client
+            .send_with_nonce(from, TxKind::Claim, Some("7"), |_| async { Ok(TX_HASH) })
+            .await
+            .unwrap();
+        let failed = client
+            .send_with_nonce(from, TxKind::Cancel, None, |_| async {
+                anyhow::bail!("insufficient funds for gas") as Result<B256>
+            })
+            .await;
+        assert!(failed.is_err());
+
+        let records = txlog.records().unwrap();
+        assert_eq!(records.len(), 1, "only sent transactions are recorded");
+        assert_eq!(records[0].hash, hash);
+        assert_eq!(records[0].kind, TxKind::Claim);
+        assert_eq!(records[0].request_id.as_deref(), Some("7"));
+        assert_eq!(records[0].nonce, 4);
+        assert_eq!(records[0].status, TxStatus::Pending);
+
+        // One look that finds nothing leaves it pending.
+        asserter.push_success(&Option::<()>::None);
+        let outcome = client
+            .wait_for_receipt(TX_HASH, 1, Duration::ZERO)
+            .await
+            .unwrap();
+        assert_eq!(outcome, TxOutcome::TimedOut);
+        assert_eq!(txlog.records().unwrap()[0].status, TxStatus::Pending);
+
+        asserter.push_success(&receipt_json(10, true));
+        asserter.push_success(&"0xa");
+        client
+            .wait_for_receipt(TX_HASH, 1, Duration::ZERO)
+            .await
+            .unwrap();
+        let record = &txlog.records().unwrap()[0];
+        assert_eq!(record.status, TxStatus::Confirmed);
+        assert_eq!(record.block_number, Some(10));
+        assert_eq!(record.gas_used, Some(50_000));
+    }
+
+    const REPLACEMENT_HASH: B256 = B256::repeat_byte(0xcd);
+
+    /// A pending EIP-1559 transaction from `from` with nonce 4, as a node
+    /// returns it.
+    fn pending_transaction_json(from: Address) -> serde_json::Value {
+        serde_json::json!({
+            "hash": TX_HASH,
+            "nonce": "0x4",
+            "blockHash": null,
+            "blockNumber": null,
+            "transactionIndex": null,
+            "from": from,
+            "to": Address::repeat_byte(0x22),
+            "value": "0x0",
+            "gas": "0x30d40",
+            "maxFeePerGas": "0x3b9aca00", // 1 gwei
+            "maxPriorityFeePerGas": "0xf4240", // 0.001 gwei
+            "input": "0x1234",
+            "chainId": "0x2105",
+            "accessList": [],
+            "type": "0x2",
+            "v": "0x0",
+            "yParity": "0x0",
+            "r": "0x1",
+            "s": "0x1",
+        })
+    }
+
+    #[test]
+    fn replacements_reuse_the_nonce() {
+        use alloy::consensus::TxEip1559;
+        use alloy::network::TransactionBuilder;
+
+        let from = Address::repeat_byte(0x11);
+        let to = Address::repeat_byte(0x22);
+        let original = TxEip1559 {
+            chain_id: 8453,
+            nonce: 4,
+            gas_limit: 200_000,
+            max_fee_per_gas: 1_000_000_000,
+            max_priority_fee_per_gas: 1_000_000,
+            to: alloy::primitives::TxKind::Call(to),
+            value: U256::from(5),
+            input: vec![0x12, 0x34].into(),
+            ..Default::default()
+        };
+        let quote = fees::bump(
+            1_000_000_000,
+            1_000_000,
+            10,
+            &fees::compute(0, &FeePolicy::default()),
+        );
+
+        let speed_up = replacement_request(&original, from, &quote, false, 8453);
+        assert_eq!(speed_up.nonce, Some(4));
+        assert_eq!(speed_up.to, Some(to.into()));
+        assert_eq!(speed_up.value, Some(U256::from(5)));
+        assert_eq!(
+            speed_up.input.input().map(|input| input.to_vec()),
+            Some(vec![0x12, 0x34])
+        );
+        assert_eq!(speed_up.gas, Some(200_000));
+        assert_eq!(speed_up.max_fee_per_gas, Some(1_100_000_000));
+        assert_eq!(speed_up.max_priority_fee_per_gas, Some(1_100_000));
+        assert_eq!(speed_up.chain_id(), Some(8453));
+
+        let cancel = replacement_request(&original, from, &quote, true, 8453);
+        assert_eq!(cancel.nonce, Some(4));
+        assert_eq!(cancel.to, Some(from.into()));
+        assert_eq!(cancel.value, Some(U256::ZERO));
+        assert!(cancel.input.input().is_none_or(|input| input.is_empty()));
+        assert_eq!(cancel.gas, Some(TRANSFER_GAS));
+        assert_eq!(cancel.max_fee_per_gas, Some(1_100_000_000));
+    }
+
+    #[tokio::test]
+    async fn replace_transaction_refuses_a_mined_transaction() {
+        let asserter = alloy::providers::mock::Asserter::new();
+        asserter.push_success(&receipt_json(10, true));
+        let client = ChainClient::mocked(asserter);
+        let signer = TransactionSigner::from_bytes(&mut [0x5a; 32]).unwrap();
+
+        let err = client
+            .replace_transaction(&signer, TX_HASH, 10, false, 1, Duration::ZERO, |_| {
+                unreachable!("nothing is sent")
+            })
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("already mined in block 10"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn replace_transaction_refuses_another_senders_transaction() {
+        let asserter = alloy::providers::mock::Asserter::new();
+        asserter.push_success(&Option::<()>::None);
+        asserter.push_success(&pending_transaction_json(Address::repeat_byte(0x11)));
+        let client = ChainClient::mocked(asserter);
+        let signer = TransactionSigner::from_bytes(&mut [0x5a; 32]).unwrap();
+
+        let err = client
+            .replace_transaction(&signer, TX_HASH, 10, true, 1, Duration::ZERO, |_| {
+                unreachable!("nothing is sent")
+            })
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("only its sender can replace it"), "{err}");
+    }
+
+    /// A client logging to a fresh transaction log in which `signer` has
+    /// sent [`TX_HASH`] as a claim with nonce 4.
+    async fn client_with_stuck_claim(
+        asserter: &alloy::providers::mock::Asserter,
+        signer: &TransactionSigner,
+    ) -> (ChainClient, TxLog, tempfile::TempDir) {
+        let tmp = tempfile::tempdir().unwrap();
+        let txlog = TxLog::at(tmp.path().join("txlog.jsonl"));
+        let client = ChainClient::mocked(asserter.clone())
+            .with_txlog(txlog.clone())
+            .with_receipt_poll(Duration::from_millis(1));
+        asserter.push_success(&"0x4"); // pending nonce
+        client
+            .send_with_nonce(signer.address(), TxKind::Claim, Some("7"), |_| async {
+                Ok(TX_HASH)
+            })
+            .await
+            .unwrap();
+        (client, txlog, tmp)
+    }
+
+    #[tokio::test]
+    async fn replace_transaction_records_the_replacement_that_landed() {
+        use crate::chain::txlog::TxStatus;
+
+        let asserter = alloy::providers::mock::Asserter::new();
+        let signer = TransactionSigner::from_bytes(&mut [0x5a; 32]).unwrap();
+        let (client, txlog, _tmp) = client_with_stuck_claim(&asserter, &signer).await;
+
+        asserter.push_success(&Option::<()>::None); // no receipt yet
+        asserter.push_success(&pending_transaction_json(signer.address()));
+        asserter.push_success(&fee_history(1_000)); // the network is quiet
+        asserter.push_success(&REPLACEMENT_HASH);
+        asserter.push_success(&Option::<()>::None); // replacement pending
+        asserter.push_success(&Option::<()>::None); // original pending
+        asserter.push_success(&receipt_json(11, true)); // replacement mined
+        asserter.push_success(&"0xb");
+
+        let replacement = client
+            .replace_transaction(
+                &signer,
+                TX_HASH,
+                10,
+                false,
+                1,
+                Duration::from_secs(30),
+                |quote| panic!("no cap is set, yet {quote:?} was over it"),
+            )
+            .await
+            .unwrap();
+        assert!(asserter.read_q().is_empty());
+        assert_eq!(
+            replacement,
+            Replacement {
+                original: TX_HASH,
+                replacement: REPLACEMENT_HASH,
+                nonce: 4,
+                cancel: false,
+                max_fee_per_gas_wei: 1_100_000_000,
+                landed: Some(REPLACEMENT_HASH),
+                outcome: TxOutcome::Success {
+                    block_number: 11,
+                    gas_used: 50_000
+                },
+            }
+        );
+
+        let records = txlog.records().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].hash, TX_HASH);
+        assert_eq!(records[0].status, TxStatus::Replaced);
+        assert_eq!(records[0].replaced_by, Some(REPLACEMENT_HASH));
+        assert_eq!(records[1].hash, REPLACEMENT_HASH);
+        assert_eq!(records[1].kind, TxKind::Claim);
+        assert_eq!(records[1].request_id.as_deref(), Some("7"));
+        assert_eq!(records[1].nonce, 4, "the replacement reuses the nonce");
+        assert_eq!(records[1].status, TxStatus::Confirmed);
+        assert_eq!(records[1].block_number, Some(11));
+    }
+
+    #[tokio::test]
+    async fn replace_transaction_reports_the_original_landing_first() {
+        use crate::chain::txlog::TxStatus;
+
+        let asserter = alloy::providers::mock::Asserter::new();
+        let signer = TransactionSigner::from_bytes(&mut [0x5a; 32]).unwrap();
+        let (client, txlog, _tmp) = client_with_stuck_claim(&asserter, &signer).await;
+
+        asserter.push_success(&Option::<()>::None);
+        asserter.push_success(&pending_transaction_json(signer.address()));
+        asserter.push_success(&fee_history(1_000));
+        asserter.push_success(&REPLACEMENT_HASH);
+        asserter.push_success(&Option::<()>::None); // replacement pending
+        asserter.push_success(&receipt_json(10, true)); // original mined
+        asserter.push_success(&"0xa");
+
+        let replacement = client
+            .replace_transaction(
+                &signer,
+                TX_HASH,
+                25,
+                true,
+                1,
+                Duration::from_secs(30),
+                |_| Ok(()),
+            )
+            .await
+            .unwrap();
+        assert_eq!(replacement.landed, Some(TX_HASH));
+        assert_eq!(replacement.max_fee_per_gas_wei, 1_250_000_000);
+        assert_eq!(
+            replacement.outcome,
+            TxOutcome::Success {
+                block_number: 10,
+                gas_used: 50_000
+            }
+        );
+
+        let records = txlog.records().unwrap();
+        assert_eq!(records[0].status, TxStatus::Confirmed);
+        assert_eq!(records[1].hash, REPLACEMENT_HASH);
+        assert_eq!(records[1].kind, TxKind::Void);
+        assert_eq!(records[1].status, TxStatus::Replaced);
+        assert_eq!(records[1].replaced_by, Some(TX_HASH));
     }
 }
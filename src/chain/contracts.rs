@@ -6,8 +6,24 @@
 //! - **AgentRegistry** — ERC-8004 identity NFT (register, lookup, URI).
 //! - **USDC** — Minimal ERC-20 interface (approve, transferFrom, balanceOf).
 //! - **RequestRegistry** — Placeholder for Phase 3 (T-040 / T-043).
+//! - **ValidationRegistry** — Validators' verdicts (submit, lookup).
+//!
+//! Where those contracts live depends on the network: [`resolve`] picks the
+//! built-in [`Network`] preset and applies any addresses set in the
+//! `[contracts]` section of the config (or its `AGENTMARKET_*` overrides),
+//! so a testnet or local deployment needs no rebuild. The preset is chosen
+//! with `network.name` (see [`Network::apply`]), or by chain ID in configs
+//! that do not name one.
+
+use std::fmt;
+use std::str::FromStr;
 
+use alloy::primitives::{address, Address};
 use alloy::sol;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::config::store::{Config, ContractsConfig, NetworkConfig};
 
 // ---------------------------------------------------------------------------
 // ERC-8004 Agent Registry
@@ -44,20 +60,23 @@ sol! {
 }
 
 // ---------------------------------------------------------------------------
-// USDC (ERC-20) — minimal interface for approve + transferFrom
+// USDC (ERC-20) — minimal interface for approve, transfer, and transferFrom
 // ---------------------------------------------------------------------------
 
 sol! {
     /// Minimal ERC-20 interface for USDC interactions.
     ///
     /// Only the functions needed by the payment flow are included:
-    /// approve (buyer grants allowance), transferFrom (contract pulls funds),
-    /// balanceOf, allowance, and decimals.
+    /// approve (buyer grants allowance), transfer (`withdraw`), transferFrom
+    /// (contract pulls funds), balanceOf, allowance, and decimals.
     #[sol(rpc)]
     contract USDC {
         /// Approve `spender` to transfer up to `amount` tokens on behalf of the caller.
         function approve(address spender, uint256 amount) external returns (bool);
 
+        /// Transfer `amount` of the caller's tokens to `to`.
+        function transfer(address to, uint256 amount) external returns (bool);
+
         /// Transfer `amount` tokens from `from` to `to` (requires prior approval).
         function transferFrom(address from, address to, uint256 amount) external returns (bool);
 
@@ -75,6 +94,12 @@ sol! {
 
         /// Emitted on token transfer.
         event Transfer(address indexed from, address indexed to, uint256 value);
+
+        /// ERC-6093: `sender` holds less than the `needed` amount.
+        error ERC20InsufficientBalance(address sender, uint256 balance, uint256 needed);
+
+        /// ERC-6093: `spender` may pull less than the `needed` amount.
+        error ERC20InsufficientAllowance(address spender, uint256 allowance, uint256 needed);
     }
 }
 
@@ -173,6 +198,66 @@ sol! {
 
         /// Emitted when a request expires past its deadline.
         event RequestExpired(uint256 indexed requestId);
+
+        /// No request has this ID.
+        error RequestNotFound(uint256 requestId);
+
+        /// The request is not open, so it takes no response and cannot be
+        /// cancelled.
+        error RequestNotOpen(uint256 requestId);
+
+        /// The request already has a response.
+        error AlreadyResponded(uint256 requestId);
+
+        /// The request's deadline has passed.
+        error DeadlinePassed(uint256 requestId, uint256 deadline);
+
+        /// The revealed preimage does not hash to the response's hash lock.
+        error InvalidSecret(uint256 requestId);
+
+        /// Only the request's buyer may do this.
+        error NotBuyer(address caller);
+
+        /// Only the request's seller may do this.
+        error NotSeller(address caller);
+
+        /// Only the request's assigned validator may do this.
+        error NotValidator(address caller);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Validation Registry — validators' verdicts on responses
+// ---------------------------------------------------------------------------
+
+sol! {
+    /// Validation Registry — where a request's validator records its verdict.
+    ///
+    /// The Request Registry's `validationRegistry` points here. Each request
+    /// takes one verdict, from the validator assigned to it; a request with
+    /// no verdict yet reads back as a zero `validator`.
+    #[sol(rpc)]
+    contract ValidationRegistry {
+        /// A validator's verdict on a request's response.
+        struct Validation {
+            address validator;
+            uint8 score;
+            bool passed;
+            uint256 timestamp;
+        }
+
+        /// Record the caller's verdict on `requestId`: a score out of 100
+        /// and whether the response passed.
+        function submitValidation(uint256 requestId, uint8 score, bool passed) external;
+
+        /// The verdict recorded for `requestId` (all zero if none).
+        function getValidation(uint256 requestId) external view returns (Validation memory);
+
+        /// Emitted when a validator records a verdict.
+        event ValidationSubmitted(uint256 indexed requestId, address indexed validator, uint8 score, bool passed);
+
+        /// The request already has a verdict.
+        error AlreadyValidated(uint256 requestId);
     }
 }
 
@@ -196,8 +281,299 @@ pub mod addresses {
     /// Request Registry on Base mainnet (placeholder -- Phase 3 deployment).
     pub const REQUEST_REGISTRY: Address = address!("0000000000000000000000000000000000000000");
 
+    /// Validation Registry on Base mainnet (placeholder -- Phase 3 deployment).
+    pub const VALIDATION_REGISTRY: Address = address!("0000000000000000000000000000000000000000");
     /// USDC uses 6 decimal places.
     pub const USDC_DECIMALS: u8 = 6;
+
+    /// Chain ID of Base mainnet, where the contracts above live.
+    pub const CHAIN_ID: u64 = 8453;
+}
+
+// ---------------------------------------------------------------------------
+// Per-network addresses
+// ---------------------------------------------------------------------------
+
+/// Circle's test USDC on Base Sepolia.
+const SEPOLIA_USDC: Address = address!("036CbD53842c5426634e7929541eC2318f3dCF7e");
+
+/// A network with a built-in set of contract addresses.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Network {
+    BaseMainnet,
+    BaseSepolia,
+    /// A development chain such as Anvil. Nothing is deployed there by
+    /// default, so every address comes from `[contracts]`.
+    Local,
+}
+
+impl Network {
+    pub const ALL: [Network; 3] = [Network::BaseMainnet, Network::BaseSepolia, Network::Local];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::BaseMainnet => "base-mainnet",
+            Self::BaseSepolia => "base-sepolia",
+            Self::Local => "local",
+        }
+    }
+
+    /// The chain ID the network reports.
+    pub fn chain_id(self) -> u64 {
+        match self {
+            Self::BaseMainnet => addresses::CHAIN_ID,
+            Self::BaseSepolia => 84532,
+            Self::Local => 31337,
+        }
+    }
+
+    /// The public RPC endpoint `network.chain_rpc` is set to when switching
+    /// to this network.
+    pub fn public_rpc(self) -> &'static str {
+        match self {
+            Self::BaseMainnet => "https://mainnet.base.org",
+            Self::BaseSepolia => "https://sepolia.base.org",
+            Self::Local => "http://localhost:8545",
+        }
+    }
+
+    /// Whether funds on this network are play money. Output from these
+    /// networks is badged TESTNET.
+    pub fn is_testnet(self) -> bool {
+        self != Self::BaseMainnet
+    }
+
+    /// The network selected by `network`: the one `network.name` names, or
+    /// for configs without a name, the one reporting
+    /// `network.expected_chain_id`. A named preset whose chain ID disagrees
+    /// with `expected_chain_id` is refused, since the contracts and the
+    /// chain would not match.
+    pub fn for_config(network: &NetworkConfig) -> Result<Self> {
+        let name = network.name.trim();
+        if name.is_empty() {
+            return Ok(Self::for_chain_id(network.expected_chain_id));
+        }
+        let named: Self = name.parse().context("invalid 'network.name'")?;
+        if named != Self::Local && named.chain_id() != network.expected_chain_id {
+            bail!(
+                "network.name is {named} (chain {}) but network.expected_chain_id is {}. \
+                 Run `agentmarket config set network.name {named}` to reset the network settings.",
+                named.chain_id(),
+                network.expected_chain_id
+            );
+        }
+        Ok(named)
+    }
+
+    /// Point `network` at this preset: its name, chain ID, and public RPC
+    /// endpoint. A WebSocket endpoint is cleared, since it would belong to
+    /// the previous network.
+    pub fn apply(self, network: &mut NetworkConfig) {
+        network.name = self.name().to_string();
+        network.expected_chain_id = self.chain_id();
+        network.chain_rpc = self.public_rpc().into();
+        network.chain_ws.clear();
+    }
+
+    /// The network reporting `chain_id`. Chains without a preset are
+    /// treated as [`Network::Local`]: everything must be configured.
+    pub fn for_chain_id(chain_id: u64) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|network| network.chain_id() == chain_id)
+            .unwrap_or(Self::Local)
+    }
+
+    /// The addresses built into the CLI for this network; zero where a
+    /// contract is not deployed there (yet).
+    pub fn preset(self) -> ContractAddresses {
+        let (usdc, agent_registry, request_registry, validation_registry) = match self {
+            Self::BaseMainnet => (
+                addresses::USDC,
+                addresses::AGENT_REGISTRY,
+                addresses::REQUEST_REGISTRY,
+                addresses::VALIDATION_REGISTRY,
+            ),
+            Self::BaseSepolia => (SEPOLIA_USDC, Address::ZERO, Address::ZERO, Address::ZERO),
+            Self::Local => (Address::ZERO, Address::ZERO, Address::ZERO, Address::ZERO),
+        };
+        ContractAddresses {
+            network: self,
+            usdc,
+            agent_registry,
+            request_registry,
+            validation_registry,
+        }
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+impl FromStr for Network {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|network| network.name() == s)
+            .with_context(|| {
+                let names: Vec<&str> = Self::ALL.iter().map(|n| n.name()).collect();
+                format!(
+                    "unknown network \"{s}\"; expected one of {}",
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// One of the contracts the CLI talks to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Contract {
+    Usdc,
+    AgentRegistry,
+    RequestRegistry,
+    ValidationRegistry,
+}
+
+impl Contract {
+    pub const ALL: [Contract; 4] = [
+        Contract::Usdc,
+        Contract::AgentRegistry,
+        Contract::RequestRegistry,
+        Contract::ValidationRegistry,
+    ];
+
+    /// Its key in `[contracts]`.
+    pub fn key(self) -> &'static str {
+        match self {
+            Self::Usdc => "contracts.usdc",
+            Self::AgentRegistry => "contracts.agent_registry",
+            Self::RequestRegistry => "contracts.request_registry",
+            Self::ValidationRegistry => "contracts.validation_registry",
+        }
+    }
+
+    /// The environment variable overriding its key.
+    pub fn env_var(self) -> &'static str {
+        match self {
+            Self::Usdc => "AGENTMARKET_USDC",
+            Self::AgentRegistry => "AGENTMARKET_AGENT_REGISTRY",
+            Self::RequestRegistry => "AGENTMARKET_REQUEST_REGISTRY",
+            Self::ValidationRegistry => "AGENTMARKET_VALIDATION_REGISTRY",
+        }
+    }
+
+    /// How to point the CLI at a deployment of this contract, for messages
+    /// about it having no address.
+    pub fn setup_hint(self) -> String {
+        format!(
+            "To use a deployment of your own, set `{}` or {}.",
+            self.key(),
+            self.env_var()
+        )
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Usdc => "USDC token",
+            Self::AgentRegistry => "agent registry",
+            Self::RequestRegistry => "request registry",
+            Self::ValidationRegistry => "validation registry",
+        }
+    }
+
+    fn configured(self, config: &ContractsConfig) -> &str {
+        match self {
+            Self::Usdc => &config.usdc,
+            Self::AgentRegistry => &config.agent_registry,
+            Self::RequestRegistry => &config.request_registry,
+            Self::ValidationRegistry => &config.validation_registry,
+        }
+    }
+}
+
+/// The contracts to use on one network.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct ContractAddresses {
+    pub network: Network,
+    pub usdc: Address,
+    pub agent_registry: Address,
+    pub request_registry: Address,
+    pub validation_registry: Address,
+}
+
+impl ContractAddresses {
+    pub fn get(&self, contract: Contract) -> Address {
+        match contract {
+            Contract::Usdc => self.usdc,
+            Contract::AgentRegistry => self.agent_registry,
+            Contract::RequestRegistry => self.request_registry,
+            Contract::ValidationRegistry => self.validation_registry,
+        }
+    }
+
+    fn set(&mut self, contract: Contract, address: Address) {
+        match contract {
+            Contract::Usdc => self.usdc = address,
+            Contract::AgentRegistry => self.agent_registry = address,
+            Contract::RequestRegistry => self.request_registry = address,
+            Contract::ValidationRegistry => self.validation_registry = address,
+        }
+    }
+
+    /// The address of `contract`, or an error naming the key to set if it
+    /// has none on this network. Call this before sending a transaction to
+    /// it: a transaction to the zero address goes through and does nothing.
+    pub fn require(&self, contract: Contract) -> Result<Address> {
+        let address = self.get(contract);
+        if address.is_zero() {
+            bail!(
+                "No {} address is configured for {}, so no transaction can be sent to it. \
+                 Set one with `agentmarket config set {} <address>` or the {} \
+                 environment variable.",
+                contract.label(),
+                self.network,
+                contract.key(),
+                contract.env_var()
+            );
+        }
+        Ok(address)
+    }
+}
+
+impl Default for ContractAddresses {
+    fn default() -> Self {
+        Network::BaseMainnet.preset()
+    }
+}
+
+/// The contracts to use on `network`: its preset, with each address set in
+/// `config.contracts` taking its place. Environment overrides are already
+/// part of a loaded config (see [`crate::config::store::load`]).
+pub fn resolve(network: Network, config: &Config) -> Result<ContractAddresses> {
+    let mut resolved = network.preset();
+    for contract in Contract::ALL {
+        let value = contract.configured(&config.contracts).trim();
+        if value.is_empty() {
+            continue;
+        }
+        let address: Address = value
+            .parse()
+            .with_context(|| format!("'{}' is not an address: \"{value}\"", contract.key()))?;
+        resolved.set(contract, address);
+    }
+    Ok(resolved)
+}
+
+/// The contracts for the network `config` connects to (see
+/// [`Network::for_config`]).
+pub fn for_config(config: &Config) -> Result<ContractAddresses> {
+    resolve(Network::for_config(&config.network)?, config)
 }
 
 // ---------------------------------------------------------------------------
@@ -235,6 +611,129 @@ mod tests {
         assert_eq!(addresses::USDC_DECIMALS, 6);
     }
 
+    // -- Per-network addresses ----------------------------------------------
+
+    #[test]
+    fn presets_are_selected_by_name_and_chain_id() {
+        for network in Network::ALL {
+            assert_eq!(network.name().parse::<Network>().unwrap(), network);
+            assert_eq!(Network::for_chain_id(network.chain_id()), network);
+        }
+        assert_eq!(Network::for_chain_id(8453), Network::BaseMainnet);
+        assert_eq!(Network::for_chain_id(84532), Network::BaseSepolia);
+        // Chains without a preset need every address configured.
+        assert_eq!(Network::for_chain_id(1), Network::Local);
+
+        let err = "base-goerli".parse::<Network>().unwrap_err().to_string();
+        assert!(err.contains("base-mainnet, base-sepolia, local"), "{err}");
+
+        assert_eq!(Network::BaseMainnet.preset().usdc, addresses::USDC);
+        assert_eq!(
+            Network::BaseSepolia.preset().usdc,
+            "0x036CbD53842c5426634e7929541eC2318f3dCF7e"
+                .parse::<Address>()
+                .unwrap()
+        );
+        let local = Network::Local.preset();
+        assert!(Contract::ALL.iter().all(|c| local.get(*c).is_zero()));
+    }
+
+    #[test]
+    fn resolve_applies_configured_addresses() {
+        let registry: Address = "0x5FbDB2315678afecb367f032d93F642f64180aa3"
+            .parse()
+            .unwrap();
+        let mut cfg = Config::default();
+        assert_eq!(
+            resolve(Network::BaseSepolia, &cfg).unwrap(),
+            Network::BaseSepolia.preset()
+        );
+
+        cfg.contracts.request_registry = format!(" {registry} ");
+        let resolved = resolve(Network::BaseSepolia, &cfg).unwrap();
+        assert_eq!(resolved.network, Network::BaseSepolia);
+        assert_eq!(resolved.request_registry, registry);
+        // Unset keys keep the preset.
+        assert_eq!(resolved.usdc, Network::BaseSepolia.preset().usdc);
+        assert!(resolved.agent_registry.is_zero());
+
+        // The network follows the chain the config expects.
+        cfg.network.expected_chain_id = 31337;
+        let resolved = for_config(&cfg).unwrap();
+        assert_eq!(resolved.network, Network::Local);
+        assert_eq!(resolved.request_registry, registry);
+        assert!(resolved.usdc.is_zero());
+
+        cfg.contracts.usdc = "0xnot-an-address".to_string();
+        let err = format!("{:#}", for_config(&cfg).unwrap_err());
+        assert!(err.contains("'contracts.usdc' is not an address"), "{err}");
+    }
+
+    #[test]
+    fn network_name_selects_and_applies_presets() {
+        let mut network = NetworkConfig::default();
+        assert_eq!(Network::for_config(&network).unwrap(), Network::BaseMainnet);
+
+        network.chain_ws = "wss://mainnet.example.org".to_string();
+        Network::BaseSepolia.apply(&mut network);
+        assert_eq!(network.name, "base-sepolia");
+        assert_eq!(network.expected_chain_id, 84532);
+        assert_eq!(network.chain_rpc.urls(), ["https://sepolia.base.org"]);
+        assert_eq!(network.chain_ws, "");
+        assert_eq!(Network::for_config(&network).unwrap(), Network::BaseSepolia);
+        assert!(Network::BaseSepolia.is_testnet());
+        assert!(Network::Local.is_testnet());
+        assert!(!Network::BaseMainnet.is_testnet());
+
+        // A named preset must agree with the chain it expects...
+        network.expected_chain_id = 8453;
+        let err = Network::for_config(&network).unwrap_err().to_string();
+        assert!(
+            err.contains("config set network.name base-sepolia"),
+            "{err}"
+        );
+        // ...except a local chain, which may have any ID.
+        network.name = "local".to_string();
+        assert_eq!(Network::for_config(&network).unwrap(), Network::Local);
+
+        network.name = "base-goerli".to_string();
+        let err = format!("{:#}", Network::for_config(&network).unwrap_err());
+        assert!(err.contains("invalid 'network.name'"), "{err}");
+
+        // Configs without a name follow the chain ID.
+        network.name.clear();
+        network.expected_chain_id = 84532;
+        assert_eq!(Network::for_config(&network).unwrap(), Network::BaseSepolia);
+    }
+
+    #[test]
+    fn require_refuses_zero_address_and_names_the_key() {
+        let resolved = Network::BaseSepolia.preset();
+        assert_eq!(
+            resolved.require(Contract::Usdc).unwrap(),
+            Network::BaseSepolia.preset().usdc
+        );
+
+        let err = resolved
+            .require(Contract::RequestRegistry)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("request registry"), "{err}");
+        assert!(err.contains("base-sepolia"), "{err}");
+        assert!(
+            err.contains("agentmarket config set contracts.request_registry"),
+            "{err}"
+        );
+        assert!(err.contains("AGENTMARKET_REQUEST_REGISTRY"), "{err}");
+
+        let err = Network::Local
+            .preset()
+            .require(Contract::ValidationRegistry)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("contracts.validation_registry"), "{err}");
+    }
+
     // -- AgentRegistry sol! type generation --------------------------------
 
     #[test]
@@ -454,4 +953,62 @@ mod tests {
         assert_eq!(event.requestId, U256::from(5u64));
         assert_eq!(event.secret, B256::ZERO);
     }
+
+    // -- ValidationRegistry sol! type generation -----------------------------
+
+    #[test]
+    fn test_validation_registry_submit_validation_call() {
+        use alloy::sol_types::SolCall;
+        let call = ValidationRegistry::submitValidationCall {
+            requestId: U256::from(3u64),
+            score: 87,
+            passed: true,
+        };
+        let decoded =
+            ValidationRegistry::submitValidationCall::abi_decode(&call.abi_encode()).unwrap();
+        assert_eq!(decoded.requestId, U256::from(3u64));
+        assert_eq!(decoded.score, 87);
+        assert!(decoded.passed);
+    }
+
+    #[test]
+    fn test_validation_registry_get_validation_return() {
+        use alloy::sol_types::{SolCall, SolValue};
+        let validation = ValidationRegistry::Validation {
+            validator: Address::repeat_byte(0x11),
+            score: 40,
+            passed: false,
+            timestamp: U256::from(1_700_000_000u64),
+        };
+        let decoded =
+            ValidationRegistry::getValidationCall::abi_decode_returns(&validation.abi_encode())
+                .unwrap();
+        assert_eq!(decoded.validator, Address::repeat_byte(0x11));
+        assert_eq!(decoded.score, 40);
+        assert!(!decoded.passed);
+        assert_eq!(decoded.timestamp, U256::from(1_700_000_000u64));
+    }
+
+    #[test]
+    fn test_validation_registry_validation_submitted_event() {
+        use alloy::primitives::{Bytes, Log, LogData};
+        use alloy::sol_types::SolEvent;
+        let event = ValidationRegistry::ValidationSubmitted {
+            requestId: U256::from(9u64),
+            validator: Address::repeat_byte(0x11),
+            score: 100,
+            passed: true,
+        };
+        let data = event.encode_log_data();
+        assert_eq!(data.topics().len(), 3);
+        let log = Log {
+            address: Address::ZERO,
+            data: LogData::new_unchecked(data.topics().to_vec(), Bytes::from(data.data.to_vec())),
+        };
+        let decoded = ValidationRegistry::ValidationSubmitted::decode_log(&log).unwrap();
+        assert_eq!(decoded.requestId, U256::from(9u64));
+        assert_eq!(decoded.validator, Address::repeat_byte(0x11));
+        assert_eq!(decoded.score, 100);
+        assert!(decoded.passed);
+    }
 }
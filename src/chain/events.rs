@@ -0,0 +1,570 @@
+//! Request lifecycle events emitted by the RequestRegistry.
+//!
+//! [`RequestEvent`] is the decoded form of the four events that move a
+//! request along: created, responded, validated, claimed. Logs for any other
+//! event, or that do not decode, are dropped. The daemon receives these from
+//! [`ChainClient::subscribe_request_events`] when `network.chain_ws` is set.
+//!
+//! Past events are read with [`ChainClient::scan_request_events`], which
+//! asks for logs [`SCAN_WINDOW`] blocks at a time and halves any window the
+//! provider says is too large (see [`is_range_too_large`]). Each comes back
+//! as a [`DecodedEvent`] carrying where in the chain it was emitted.
+//!
+//! [`ChainClient::subscribe_request_events`]: super::client::ChainClient::subscribe_request_events
+//! [`ChainClient::scan_request_events`]: super::client::ChainClient::scan_request_events
+
+use alloy::primitives::{Address, Log, B256, U256};
+use alloy::rpc::types::{Filter, Log as RpcLog};
+use alloy::sol_types::SolEvent;
+use serde::Serialize;
+
+use super::contracts::RequestRegistry::{
+    RequestClaimed, RequestCreated, RequestValidated, ResponseSubmitted,
+};
+use super::types::RequestId;
+
+/// One step in a request's on-chain lifecycle.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum RequestEvent {
+    /// A buyer opened a request and escrowed its price.
+    Created {
+        request_id: RequestId,
+        buyer: Address,
+        /// Price in USDC base units (6 decimals).
+        price_usdc: u64,
+        /// Unix timestamp after which the request can expire.
+        deadline: u64,
+    },
+    /// A seller submitted a response.
+    ResponseSubmitted {
+        request_id: RequestId,
+        seller: Address,
+        secret_hash: B256,
+    },
+    /// A validator passed or failed the response.
+    Validated {
+        request_id: RequestId,
+        passed: bool,
+        validator: Address,
+    },
+    /// The seller revealed the secret and was paid.
+    Claimed { request_id: RequestId, secret: B256 },
+}
+
+impl RequestEvent {
+    /// The request this event is about.
+    pub fn request_id(&self) -> &RequestId {
+        match self {
+            Self::Created { request_id, .. }
+            | Self::ResponseSubmitted { request_id, .. }
+            | Self::Validated { request_id, .. }
+            | Self::Claimed { request_id, .. } => request_id,
+        }
+    }
+
+    /// Decode `log`, or `None` if it is not one of the four request events
+    /// or its data does not match the event's ABI.
+    pub fn decode(log: &Log) -> Option<Self> {
+        let event = match *log.topics().first()? {
+            RequestCreated::SIGNATURE_HASH => {
+                let event = RequestCreated::decode_log(log).ok()?.data;
+                Self::Created {
+                    request_id: RequestId(event.requestId),
+                    buyer: event.buyer,
+                    price_usdc: saturating_u64(event.price),
+                    deadline: saturating_u64(event.deadline),
+                }
+            }
+            ResponseSubmitted::SIGNATURE_HASH => {
+                let event = ResponseSubmitted::decode_log(log).ok()?.data;
+                Self::ResponseSubmitted {
+                    request_id: RequestId(event.requestId),
+                    seller: event.seller,
+                    secret_hash: event.secretHash,
+                }
+            }
+            RequestValidated::SIGNATURE_HASH => {
+                let event = RequestValidated::decode_log(log).ok()?.data;
+                Self::Validated {
+                    request_id: RequestId(event.requestId),
+                    passed: event.passed,
+                    validator: event.validator,
+                }
+            }
+            RequestClaimed::SIGNATURE_HASH => {
+                let event = RequestClaimed::decode_log(log).ok()?.data;
+                Self::Claimed {
+                    request_id: RequestId(event.requestId),
+                    secret: event.secret,
+                }
+            }
+            _ => return None,
+        };
+        Some(event)
+    }
+}
+
+/// Filter matching the four request events emitted by `registry`.
+pub fn request_events_filter(registry: Address) -> Filter {
+    EventFilter::default().to_filter(registry)
+}
+
+// ---------------------------------------------------------------------------
+// Scanning past events
+// ---------------------------------------------------------------------------
+
+/// Blocks asked for in one `eth_getLogs` call. Most providers cap a call at
+/// 10,000 blocks or 10,000 results, whichever comes first.
+pub const SCAN_WINDOW: u64 = 10_000;
+
+/// Fragments of provider errors meaning an `eth_getLogs` call covered too
+/// many blocks or matched too many logs, as worded by Infura, Alchemy,
+/// QuickNode, Ankr, and the public Base endpoints.
+const RANGE_TOO_LARGE: [&str; 5] = [
+    "response too large",
+    "response size exceeded",
+    "query returned more than",
+    "block range",
+    "is limited to a",
+];
+
+/// Whether `message` is a provider refusing an `eth_getLogs` call because
+/// its range was too large. A smaller range may succeed.
+pub fn is_range_too_large(message: &str) -> bool {
+    let message = message.to_ascii_lowercase();
+    RANGE_TOO_LARGE
+        .iter()
+        .any(|fragment| message.contains(fragment))
+}
+
+/// Split the inclusive block range `from..=to` into consecutive windows of
+/// at most `window` blocks. Empty if `from` is past `to`.
+pub fn scan_windows(from: u64, to: u64, window: u64) -> Vec<(u64, u64)> {
+    let window = window.max(1);
+    let mut windows = Vec::new();
+    let mut start = from;
+    while start <= to {
+        let end = start.saturating_add(window - 1).min(to);
+        windows.push((start, end));
+        if end == u64::MAX {
+            break;
+        }
+        start = end + 1;
+    }
+    windows
+}
+
+/// Halve the inclusive window `from..=to`, or `None` if it is a single
+/// block and cannot be split.
+pub fn split_window(from: u64, to: u64) -> Option<((u64, u64), (u64, u64))> {
+    if from >= to {
+        return None;
+    }
+    let mid = from + (to - from) / 2;
+    Some(((from, mid), (mid + 1, to)))
+}
+
+/// The kinds of [`RequestEvent`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventKind {
+    Created,
+    ResponseSubmitted,
+    Validated,
+    Claimed,
+}
+
+impl EventKind {
+    /// All four kinds.
+    pub const ALL: [Self; 4] = [
+        Self::Created,
+        Self::ResponseSubmitted,
+        Self::Validated,
+        Self::Claimed,
+    ];
+
+    /// The event's topic 0.
+    pub fn signature_hash(self) -> B256 {
+        match self {
+            Self::Created => RequestCreated::SIGNATURE_HASH,
+            Self::ResponseSubmitted => ResponseSubmitted::SIGNATURE_HASH,
+            Self::Validated => RequestValidated::SIGNATURE_HASH,
+            Self::Claimed => RequestClaimed::SIGNATURE_HASH,
+        }
+    }
+}
+
+/// Which request events a scan returns. The default matches all four kinds
+/// for every request.
+#[derive(Clone, Debug, Default)]
+pub struct EventFilter {
+    /// Only these kinds of event; all four when empty.
+    pub kinds: Vec<EventKind>,
+    /// Only events about these requests; every request when empty.
+    pub request_ids: Vec<RequestId>,
+    /// Only events naming this address as their indexed party: the buyer of
+    /// `Created`, the seller of `ResponseSubmitted`. `Validated` and
+    /// `Claimed` name no party, so setting this leaves them out.
+    pub party: Option<Address>,
+}
+
+impl EventFilter {
+    /// The `eth_getLogs` filter for these events emitted by `registry`, with
+    /// no block range.
+    pub fn to_filter(&self, registry: Address) -> Filter {
+        let kinds = if self.kinds.is_empty() {
+            &EventKind::ALL[..]
+        } else {
+            &self.kinds[..]
+        };
+        let mut filter = Filter::new().address(registry).event_signature(
+            kinds
+                .iter()
+                .map(|kind| kind.signature_hash())
+                .collect::<Vec<_>>(),
+        );
+        if !self.request_ids.is_empty() {
+            filter = filter.topic1(
+                self.request_ids
+                    .iter()
+                    .map(|id| B256::from(id.0))
+                    .collect::<Vec<_>>(),
+            );
+        }
+        if let Some(party) = self.party {
+            filter = filter.topic2(party.into_word());
+        }
+        filter
+    }
+}
+
+/// A request event found by a scan, with where it was emitted.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct DecodedEvent {
+    pub block_number: u64,
+    /// Hash of the block, so a later reorg that replaces it can be noticed.
+    pub block_hash: B256,
+    pub transaction_hash: B256,
+    /// Position of the log within its block.
+    pub log_index: u64,
+    #[serde(flatten)]
+    pub event: RequestEvent,
+}
+
+impl DecodedEvent {
+    /// Decode `log` as returned by `eth_getLogs`, or `None` if it is not a
+    /// request event, was removed by a reorg, or is not yet in a block.
+    pub fn decode(log: &RpcLog) -> Option<Self> {
+        if log.removed {
+            return None;
+        }
+        Some(Self {
+            block_number: log.block_number?,
+            block_hash: log.block_hash?,
+            transaction_hash: log.transaction_hash?,
+            log_index: log.log_index?,
+            event: RequestEvent::decode(&log.inner)?,
+        })
+    }
+}
+
+fn saturating_u64(value: U256) -> u64 {
+    u64::try_from(value).unwrap_or(u64::MAX)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::{address, b256, bytes, keccak256, Bytes, LogData};
+
+    const REGISTRY: Address = address!("00000000000000000000000000000000000000aa");
+    const PARTY: Address = address!("71c7656ec7ab88b098defb751b7401b5f6d8976f");
+
+    /// A log from the registry with the given raw topics and ABI-encoded
+    /// data, as it arrives over the wire.
+    fn fixture(topics: Vec<B256>, data: Bytes) -> Log {
+        Log {
+            address: REGISTRY,
+            data: LogData::new(topics, data).unwrap(),
+        }
+    }
+
+    fn request_id_topic(id: u8) -> B256 {
+        B256::left_padding_from(&[id])
+    }
+
+    #[test]
+    fn test_signatures_match_the_abi() {
+        let signatures = [
+            (
+                RequestCreated::SIGNATURE_HASH,
+                "RequestCreated(uint256,address,uint256,uint256)",
+            ),
+            (
+                ResponseSubmitted::SIGNATURE_HASH,
+                "ResponseSubmitted(uint256,address,bytes32)",
+            ),
+            (
+                RequestValidated::SIGNATURE_HASH,
+                "RequestValidated(uint256,bool,address)",
+            ),
+            (
+                RequestClaimed::SIGNATURE_HASH,
+                "RequestClaimed(uint256,bytes32)",
+            ),
+        ];
+        for (hash, signature) in signatures {
+            assert_eq!(hash, keccak256(signature), "{signature}");
+        }
+    }
+
+    #[test]
+    fn test_decodes_request_created() {
+        let log = fixture(
+            vec![
+                RequestCreated::SIGNATURE_HASH,
+                request_id_topic(7),
+                PARTY.into_word(),
+            ],
+            // price = 5_000_000, deadline = 1_700_000_000
+            bytes!(
+                "00000000000000000000000000000000000000000000000000000000004c4b40"
+                "000000000000000000000000000000000000000000000000000000006553f100"
+            ),
+        );
+        assert_eq!(
+            RequestEvent::decode(&log),
+            Some(RequestEvent::Created {
+                request_id: RequestId(U256::from(7u64)),
+                buyer: PARTY,
+                price_usdc: 5_000_000,
+                deadline: 1_700_000_000,
+            })
+        );
+    }
+
+    #[test]
+    fn test_decodes_response_submitted() {
+        let log = fixture(
+            vec![
+                ResponseSubmitted::SIGNATURE_HASH,
+                request_id_topic(8),
+                PARTY.into_word(),
+            ],
+            bytes!("abababababababababababababababababababababababababababababababab"),
+        );
+        assert_eq!(
+            RequestEvent::decode(&log),
+            Some(RequestEvent::ResponseSubmitted {
+                request_id: RequestId(U256::from(8u64)),
+                seller: PARTY,
+                secret_hash: b256!(
+                    "abababababababababababababababababababababababababababababababab"
+                ),
+            })
+        );
+    }
+
+    #[test]
+    fn test_decodes_request_validated() {
+        let log = fixture(
+            vec![RequestValidated::SIGNATURE_HASH, request_id_topic(9)],
+            // passed = true, validator = PARTY
+            bytes!(
+                "0000000000000000000000000000000000000000000000000000000000000001"
+                "00000000000000000000000071c7656ec7ab88b098defb751b7401b5f6d8976f"
+            ),
+        );
+        assert_eq!(
+            RequestEvent::decode(&log),
+            Some(RequestEvent::Validated {
+                request_id: RequestId(U256::from(9u64)),
+                passed: true,
+                validator: PARTY,
+            })
+        );
+    }
+
+    #[test]
+    fn test_decodes_request_claimed() {
+        let log = fixture(
+            vec![RequestClaimed::SIGNATURE_HASH, request_id_topic(10)],
+            bytes!("00000000000000000000000000000000000000000000000000000000000000ff"),
+        );
+        let event = RequestEvent::decode(&log).unwrap();
+        assert_eq!(event.request_id(), &RequestId(U256::from(10u64)));
+        assert_eq!(
+            event,
+            RequestEvent::Claimed {
+                request_id: RequestId(U256::from(10u64)),
+                secret: B256::with_last_byte(0xff),
+            }
+        );
+    }
+
+    #[test]
+    fn test_round_trips_encoded_events() {
+        let created = RequestCreated {
+            requestId: U256::from(11u64),
+            buyer: PARTY,
+            price: U256::MAX,
+            deadline: U256::from(1u64),
+        };
+        let log = Log {
+            address: REGISTRY,
+            data: created.encode_log_data(),
+        };
+        assert_eq!(
+            RequestEvent::decode(&log),
+            Some(RequestEvent::Created {
+                request_id: RequestId(U256::from(11u64)),
+                buyer: PARTY,
+                price_usdc: u64::MAX,
+                deadline: 1,
+            }),
+            "an out-of-range price saturates"
+        );
+    }
+
+    #[test]
+    fn test_ignores_other_and_malformed_logs() {
+        use crate::chain::contracts::RequestRegistry::RequestCancelled;
+
+        let cancelled = fixture(
+            vec![RequestCancelled::SIGNATURE_HASH, request_id_topic(12)],
+            Bytes::new(),
+        );
+        assert_eq!(RequestEvent::decode(&cancelled), None);
+        assert_eq!(
+            RequestEvent::decode(&fixture(Vec::new(), Bytes::new())),
+            None
+        );
+
+        // Claimed with its 32-byte secret cut short.
+        let truncated = fixture(
+            vec![RequestClaimed::SIGNATURE_HASH, request_id_topic(13)],
+            bytes!("00ff"),
+        );
+        assert_eq!(RequestEvent::decode(&truncated), None);
+
+        // ResponseSubmitted without its indexed seller.
+        let missing_topic = fixture(
+            vec![ResponseSubmitted::SIGNATURE_HASH, request_id_topic(14)],
+            bytes!("abababababababababababababababababababababababababababababababab"),
+        );
+        assert_eq!(RequestEvent::decode(&missing_topic), None);
+    }
+
+    #[test]
+    fn test_filter_covers_the_four_events() {
+        let filter = request_events_filter(REGISTRY);
+        let topics = &filter.topics[0];
+        for hash in [
+            RequestCreated::SIGNATURE_HASH,
+            ResponseSubmitted::SIGNATURE_HASH,
+            RequestValidated::SIGNATURE_HASH,
+            RequestClaimed::SIGNATURE_HASH,
+        ] {
+            assert!(topics.matches(&hash));
+        }
+    }
+
+    #[test]
+    fn test_event_filter_narrows_topics() {
+        let filter = EventFilter {
+            kinds: vec![EventKind::Created, EventKind::Claimed],
+            request_ids: vec![RequestId(U256::from(7u64))],
+            party: Some(PARTY),
+        }
+        .to_filter(REGISTRY);
+        assert!(filter.topics[0].matches(&RequestCreated::SIGNATURE_HASH));
+        assert!(filter.topics[0].matches(&RequestClaimed::SIGNATURE_HASH));
+        assert!(!filter.topics[0].matches(&ResponseSubmitted::SIGNATURE_HASH));
+        assert!(filter.topics[1].matches(&request_id_topic(7)));
+        assert!(!filter.topics[1].matches(&request_id_topic(8)));
+        assert!(filter.topics[2].matches(&PARTY.into_word()));
+
+        let everything = EventFilter::default().to_filter(REGISTRY);
+        assert_eq!(everything, request_events_filter(REGISTRY));
+        assert!(everything.topics[1].is_empty());
+        assert!(everything.topics[2].is_empty());
+    }
+
+    #[test]
+    fn test_scan_windows_cover_the_range() {
+        assert_eq!(
+            scan_windows(100, 25_099, SCAN_WINDOW),
+            [(100, 10_099), (10_100, 20_099), (20_100, 25_099)]
+        );
+        assert_eq!(scan_windows(5, 5, SCAN_WINDOW), [(5, 5)]);
+        assert_eq!(scan_windows(0, 3, 2), [(0, 1), (2, 3)]);
+        assert!(scan_windows(10, 9, SCAN_WINDOW).is_empty());
+        assert_eq!(
+            scan_windows(u64::MAX - 1, u64::MAX, SCAN_WINDOW),
+            [(u64::MAX - 1, u64::MAX)]
+        );
+    }
+
+    #[test]
+    fn test_split_window_halves_until_one_block() {
+        assert_eq!(split_window(0, 9_999), Some(((0, 4_999), (5_000, 9_999))));
+        assert_eq!(split_window(4, 6), Some(((4, 5), (6, 6))));
+        assert_eq!(split_window(4, 5), Some(((4, 4), (5, 5))));
+        assert_eq!(split_window(5, 5), None);
+    }
+
+    #[test]
+    fn test_recognises_range_too_large_errors() {
+        for message in [
+            "server returned an error response: error code -32005: query returned more than 10000 results",
+            "Log response size exceeded. You can make eth_getLogs requests with up to a 2K block range",
+            "eth_getLogs is limited to a 10,000 range",
+            "block range is too wide",
+            "Response too large",
+        ] {
+            assert!(is_range_too_large(message), "{message}");
+        }
+        for message in ["connection refused", "limit exceeded", "execution reverted"] {
+            assert!(!is_range_too_large(message), "{message}");
+        }
+    }
+
+    #[test]
+    fn test_decoded_event_carries_position_and_skips_removed() {
+        let claimed = fixture(
+            vec![RequestClaimed::SIGNATURE_HASH, request_id_topic(15)],
+            bytes!("00000000000000000000000000000000000000000000000000000000000000ff"),
+        );
+        let mut log = RpcLog {
+            inner: claimed,
+            block_number: Some(120),
+            block_hash: Some(B256::repeat_byte(0xab)),
+            transaction_hash: Some(B256::repeat_byte(0xcd)),
+            log_index: Some(3),
+            ..Default::default()
+        };
+        assert_eq!(
+            DecodedEvent::decode(&log),
+            Some(DecodedEvent {
+                block_number: 120,
+                block_hash: B256::repeat_byte(0xab),
+                transaction_hash: B256::repeat_byte(0xcd),
+                log_index: 3,
+                event: RequestEvent::Claimed {
+                    request_id: RequestId(U256::from(15u64)),
+                    secret: B256::with_last_byte(0xff),
+                },
+            })
+        );
+
+        log.removed = true;
+        assert_eq!(DecodedEvent::decode(&log), None);
+        log.removed = false;
+        log.block_number = None;
+        assert_eq!(DecodedEvent::decode(&log), None, "pending logs are skipped");
+    }
+}
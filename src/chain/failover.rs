@@ -0,0 +1,287 @@
+//! Failover between chain RPC endpoints.
+//!
+//! `network.chain_rpc` may list several endpoints, primary first. A
+//! [`Failover`] starts at the first endpoint that is not currently failing
+//! and moves on to the next one (wrapping around) whenever a read through
+//! the active endpoint fails transiently — a timeout, a dropped connection,
+//! a rate limit. An answer the node gave on the merits, such as a revert,
+//! counts as the endpoint working.
+//!
+//! Outcomes are recorded per endpoint for the session, so a client created
+//! later starts at an endpoint known to work, and `doctor` and the daemon's
+//! `/status` can report them with [`endpoint_status`].
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::config::store::RpcEndpoints;
+
+/// Outcomes per endpoint URL for this session.
+static SESSION: Mutex<BTreeMap<String, EndpointHealth>> = Mutex::new(BTreeMap::new());
+
+// ---------------------------------------------------------------------------
+// Endpoint health
+// ---------------------------------------------------------------------------
+
+/// How reads through one endpoint have gone this session.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EndpointHealth {
+    /// Unix timestamp of the last read the endpoint answered.
+    pub last_success_at: Option<u64>,
+    /// Why the last failed read failed.
+    pub last_error: Option<String>,
+    /// Unix timestamp of the last failed read.
+    pub last_error_at: Option<u64>,
+    /// Failed reads since the last answered one.
+    pub consecutive_failures: u32,
+}
+
+impl EndpointHealth {
+    /// Whether the most recent read through the endpoint failed.
+    pub fn is_failing(&self) -> bool {
+        self.consecutive_failures > 0
+    }
+}
+
+/// One configured endpoint and its health, as reported by `doctor` and the
+/// daemon's `/status`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EndpointStatus {
+    pub url: String,
+    /// Whether a client created now would start here.
+    pub active: bool,
+    #[serde(flatten)]
+    pub health: EndpointHealth,
+}
+
+/// Record that `url` answered a read at `now`.
+pub fn record_success(url: &str, now: u64) {
+    let mut session = SESSION.lock().unwrap_or_else(|e| e.into_inner());
+    let health = session.entry(url.to_string()).or_default();
+    health.last_success_at = Some(now);
+    health.consecutive_failures = 0;
+}
+
+/// Record that a read through `url` failed with `error` at `now`.
+pub fn record_failure(url: &str, error: &str, now: u64) {
+    let mut session = SESSION.lock().unwrap_or_else(|e| e.into_inner());
+    let health = session.entry(url.to_string()).or_default();
+    health.last_error = Some(error.to_string());
+    health.last_error_at = Some(now);
+    health.consecutive_failures = health.consecutive_failures.saturating_add(1);
+}
+
+/// This session's health of `url`; all empty if it has not been used.
+pub fn health(url: &str) -> EndpointHealth {
+    SESSION
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(url)
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Health of every endpoint in `endpoints`, in configured order.
+pub fn endpoint_status(endpoints: &RpcEndpoints) -> Vec<EndpointStatus> {
+    let preferred = preferred(endpoints.urls());
+    endpoints
+        .urls()
+        .iter()
+        .enumerate()
+        .map(|(index, url)| EndpointStatus {
+            url: url.clone(),
+            active: index == preferred,
+            health: health(url),
+        })
+        .collect()
+}
+
+/// Index of the first endpoint in `urls` whose last read did not fail, or
+/// the primary if they all failed.
+fn preferred(urls: &[String]) -> usize {
+    urls.iter()
+        .position(|url| !health(url).is_failing())
+        .unwrap_or(0)
+}
+
+// ---------------------------------------------------------------------------
+// Failover
+// ---------------------------------------------------------------------------
+
+/// Which of a client's endpoints reads go to.
+#[derive(Clone, Debug)]
+pub struct Failover {
+    urls: Vec<String>,
+    active: usize,
+}
+
+impl Failover {
+    /// Start at the first endpoint in `endpoints` that is not failing.
+    pub fn new(endpoints: &RpcEndpoints) -> Self {
+        let urls = endpoints.urls().to_vec();
+        let active = preferred(&urls);
+        Self { urls, active }
+    }
+
+    /// Index of the endpoint reads currently go to.
+    pub fn active(&self) -> usize {
+        self.active
+    }
+
+    /// URL of the endpoint reads currently go to.
+    pub fn url(&self) -> &str {
+        &self.urls[self.active]
+    }
+
+    /// Number of configured endpoints.
+    pub fn len(&self) -> usize {
+        self.urls.len()
+    }
+
+    /// Always `false`: a client has at least one endpoint.
+    pub fn is_empty(&self) -> bool {
+        self.urls.is_empty()
+    }
+
+    /// Record that endpoint `used` answered a read at `now`.
+    pub fn succeeded(&mut self, used: usize, now: u64) {
+        record_success(&self.urls[used], now);
+    }
+
+    /// Record that a read through endpoint `used` failed with `error` at
+    /// `now`, and move on to the next endpoint if `used` is still the
+    /// active one. Returns the newly active index when it changed; a read
+    /// that raced with another failover leaves the choice alone.
+    pub fn failed(&mut self, used: usize, error: &str, now: u64) -> Option<usize> {
+        record_failure(&self.urls[used], error, now);
+        if used != self.active || self.urls.len() < 2 {
+            return None;
+        }
+        self.active = (self.active + 1) % self.urls.len();
+        debug!(
+            from = %self.urls[used],
+            to = %self.urls[self.active],
+            error,
+            "failing over to the next RPC endpoint"
+        );
+        Some(self.active)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Endpoints unique to one test, since health is process-wide.
+    fn endpoints(test: &str, count: usize) -> RpcEndpoints {
+        RpcEndpoints::new(
+            (0..count)
+                .map(|i| format!("https://{test}-{i}.example"))
+                .collect(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_rotates_through_endpoints_on_failure() {
+        let endpoints = endpoints("rotate", 3);
+        let mut failover = Failover::new(&endpoints);
+        assert_eq!(failover.active(), 0);
+
+        assert_eq!(failover.failed(0, "timed out", 100), Some(1));
+        assert_eq!(failover.url(), "https://rotate-1.example");
+        assert_eq!(failover.failed(1, "connection reset", 101), Some(2));
+        assert_eq!(
+            failover.failed(2, "429", 102),
+            Some(0),
+            "wraps back to the primary"
+        );
+
+        let health = health("https://rotate-1.example");
+        assert_eq!(health.last_error.as_deref(), Some("connection reset"));
+        assert_eq!(health.last_error_at, Some(101));
+        assert_eq!(health.consecutive_failures, 1);
+    }
+
+    #[test]
+    fn test_stale_failure_does_not_move_the_active_endpoint() {
+        let endpoints = endpoints("stale", 3);
+        let mut failover = Failover::new(&endpoints);
+
+        assert_eq!(failover.failed(0, "timed out", 100), Some(1));
+        // A read that started on the primary before the failover reports late.
+        assert_eq!(failover.failed(0, "timed out", 101), None);
+        assert_eq!(failover.active(), 1);
+        assert_eq!(health("https://stale-0.example").consecutive_failures, 2);
+    }
+
+    #[test]
+    fn test_single_endpoint_stays_put() {
+        let endpoints = endpoints("single", 1);
+        let mut failover = Failover::new(&endpoints);
+        assert_eq!(failover.failed(0, "timed out", 100), None);
+        assert_eq!(failover.active(), 0);
+    }
+
+    #[test]
+    fn test_new_clients_start_at_a_working_endpoint() {
+        let endpoints = endpoints("remember", 3);
+        let mut failover = Failover::new(&endpoints);
+        failover.failed(0, "timed out", 100);
+        failover.succeeded(1, 101);
+
+        assert_eq!(Failover::new(&endpoints).active(), 1);
+        let status = endpoint_status(&endpoints);
+        assert_eq!(
+            status.iter().map(|s| s.active).collect::<Vec<_>>(),
+            [false, true, false]
+        );
+        assert!(status[0].health.is_failing());
+        assert_eq!(status[1].health.last_success_at, Some(101));
+        assert_eq!(status[2].health, EndpointHealth::default());
+
+        // Once the primary answers again, new clients go back to it.
+        failover.succeeded(0, 102);
+        assert_eq!(Failover::new(&endpoints).active(), 0);
+        assert!(!health("https://remember-0.example").is_failing());
+        assert_eq!(
+            health("https://remember-0.example").last_error.as_deref(),
+            Some("timed out"),
+            "the last error stays on record"
+        );
+    }
+
+    #[test]
+    fn test_all_failing_starts_at_the_primary() {
+        let endpoints = endpoints("allbad", 2);
+        let mut failover = Failover::new(&endpoints);
+        failover.failed(0, "timed out", 100);
+        failover.failed(1, "timed out", 101);
+        assert_eq!(Failover::new(&endpoints).active(), 0);
+    }
+
+    #[test]
+    fn test_endpoint_status_json() {
+        let endpoints = endpoints("json", 1);
+        record_failure("https://json-0.example", "timed out", 100);
+        let json = serde_json::to_value(endpoint_status(&endpoints)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([{
+                "url": "https://json-0.example",
+                "active": true,
+                "last_success_at": null,
+                "last_error": "timed out",
+                "last_error_at": 100,
+                "consecutive_failures": 1,
+            }])
+        );
+    }
+}
@@ -0,0 +1,408 @@
+//! EIP-1559 fees for the transactions the CLI sends.
+//!
+//! The `[fees]` settings pick a [`FeeStrategy`] (how far above the current
+//! base fee to bid, so the transaction still fits if the base fee rises
+//! while it waits), the priority fee to tip, and an optional cap on the max
+//! fee per gas. [`compute`] turns those and the current base fee into a
+//! [`FeeQuote`]; it is pure, so every boundary is tested here.
+//! [`ChainClient`] applies it to every transaction it builds.
+//!
+//! A quote over the cap is not paid without asking: interactive commands
+//! confirm first, and the daemon leaves the work for a later cycle (see
+//! [`FeeAboveCap`]).
+//!
+//! A transaction stuck under-priced is replaced by one with the same nonce
+//! that bids at least [`MIN_FEE_BUMP_PERCENT`] more; [`bump`] prices it.
+//!
+//! [`ChainClient`]: super::client::ChainClient
+
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
+
+use crate::config::store::FeesConfig;
+
+/// Wei in one gwei.
+pub const WEI_PER_GWEI: u128 = 1_000_000_000;
+
+// ---------------------------------------------------------------------------
+// Strategy
+// ---------------------------------------------------------------------------
+
+/// How much headroom over the current base fee a transaction's max fee
+/// leaves. The base fee can rise by 12.5% a block, so more headroom keeps a
+/// transaction includable through a longer spike, at a higher worst case.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FeeStrategy {
+    /// 1.25× the base fee: cheapest, but may wait out a rising fee.
+    Slow,
+    /// 2× the base fee, the usual wallet default.
+    #[default]
+    Normal,
+    /// 3× the base fee, for getting in during a spike.
+    Fast,
+}
+
+impl FeeStrategy {
+    pub const ALL: [FeeStrategy; 3] = [Self::Slow, Self::Normal, Self::Fast];
+
+    /// The name used in the config (`fees.strategy`).
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Slow => "slow",
+            Self::Normal => "normal",
+            Self::Fast => "fast",
+        }
+    }
+
+    /// The multiplier over the base fee, as a fraction.
+    fn multiplier(self) -> (u128, u128) {
+        match self {
+            Self::Slow => (5, 4),
+            Self::Normal => (2, 1),
+            Self::Fast => (3, 1),
+        }
+    }
+}
+
+impl fmt::Display for FeeStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+impl FromStr for FeeStrategy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_lowercase();
+        match Self::ALL
+            .into_iter()
+            .find(|strategy| strategy.name() == name)
+        {
+            Some(strategy) => Ok(strategy),
+            None => bail!("unknown fee strategy \"{s}\"; expected one of slow, normal, fast"),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Policy and quote
+// ---------------------------------------------------------------------------
+
+/// The `[fees]` settings, in wei.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FeePolicy {
+    pub strategy: FeeStrategy,
+    /// Tip per gas offered to the block producer.
+    pub max_priority_wei: u128,
+    /// Highest max fee per gas paid without asking; `None` for no cap.
+    pub cap_wei: Option<u128>,
+}
+
+impl FeePolicy {
+    /// The policy set by `fees`. A cap of 0 means no cap.
+    pub fn from_config(fees: &FeesConfig) -> Self {
+        Self {
+            strategy: fees.strategy,
+            max_priority_wei: gwei_to_wei(fees.max_priority_gwei),
+            cap_wei: Some(gwei_to_wei(fees.max_fee_gwei_cap)).filter(|cap| *cap > 0),
+        }
+    }
+}
+
+impl Default for FeePolicy {
+    fn default() -> Self {
+        Self::from_config(&FeesConfig::default())
+    }
+}
+
+/// The fees a transaction would be sent with, per gas, in wei.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct FeeQuote {
+    /// The base fee the quote was computed from.
+    pub base_fee_wei: u128,
+    pub max_fee_per_gas_wei: u128,
+    pub max_priority_fee_per_gas_wei: u128,
+    /// The cap it was checked against, if any.
+    pub cap_wei: Option<u128>,
+}
+
+impl FeeQuote {
+    /// Whether the max fee is above the cap.
+    pub fn over_cap(&self) -> bool {
+        self.cap_wei
+            .is_some_and(|cap| self.max_fee_per_gas_wei > cap)
+    }
+}
+
+/// The fees to send a transaction with when the next block's base fee is
+/// `base_fee_wei`: the strategy's multiple of the base fee (rounded up)
+/// plus the priority fee, checked against the cap.
+pub fn compute(base_fee_wei: u128, policy: &FeePolicy) -> FeeQuote {
+    let (numerator, denominator) = policy.strategy.multiplier();
+    let scaled = base_fee_wei
+        .saturating_mul(numerator)
+        .saturating_add(denominator - 1)
+        / denominator;
+    FeeQuote {
+        base_fee_wei,
+        max_fee_per_gas_wei: scaled.saturating_add(policy.max_priority_wei),
+        max_priority_fee_per_gas_wei: policy.max_priority_wei,
+        cap_wei: policy.cap_wei,
+    }
+}
+
+/// `gwei` in wei, rounded to the nearest wei; negative or non-finite
+/// amounts are 0.
+pub fn gwei_to_wei(gwei: f64) -> u128 {
+    if !gwei.is_finite() || gwei <= 0.0 {
+        return 0;
+    }
+    (gwei * WEI_PER_GWEI as f64).round() as u128
+}
+
+/// `wei` in gwei, with as many decimals as it needs, e.g. `0.0125 gwei`.
+pub fn display_gwei(wei: u128) -> String {
+    let whole = wei / WEI_PER_GWEI;
+    let fraction = wei % WEI_PER_GWEI;
+    if fraction == 0 {
+        return format!("{whole} gwei");
+    }
+    let digits = format!("{fraction:09}");
+    format!("{whole}.{} gwei", digits.trim_end_matches('0'))
+}
+
+// ---------------------------------------------------------------------------
+// Replacement
+// ---------------------------------------------------------------------------
+
+/// The smallest fee increase, in percent, nodes accept from a transaction
+/// replacing a pending one with the same nonce.
+pub const MIN_FEE_BUMP_PERCENT: u64 = 10;
+
+/// The fees for a transaction replacing a pending one that bid
+/// `previous_max_fee_wei` and `previous_priority_wei` per gas: both raised
+/// by `percent` (at least [`MIN_FEE_BUMP_PERCENT`]) and rounded up, or
+/// `current`'s if higher, so the replacement is competitive now too. The
+/// max fee is never below the tip. Checked against `current`'s cap.
+pub fn bump(
+    previous_max_fee_wei: u128,
+    previous_priority_wei: u128,
+    percent: u64,
+    current: &FeeQuote,
+) -> FeeQuote {
+    let percent = u128::from(percent.max(MIN_FEE_BUMP_PERCENT));
+    let raise = |wei: u128| wei.saturating_mul(100 + percent).saturating_add(99) / 100;
+    let priority = raise(previous_priority_wei).max(current.max_priority_fee_per_gas_wei);
+    let max_fee = raise(previous_max_fee_wei)
+        .max(current.max_fee_per_gas_wei)
+        .max(priority);
+    FeeQuote {
+        base_fee_wei: current.base_fee_wei,
+        max_fee_per_gas_wei: max_fee,
+        max_priority_fee_per_gas_wei: priority,
+        cap_wei: current.cap_wei,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// FeeAboveCap
+// ---------------------------------------------------------------------------
+
+/// A transaction was not sent because its max fee is above
+/// `fees.max_fee_gwei_cap`. Unattended callers (the daemon) leave the work
+/// for later when they see it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FeeAboveCap {
+    pub quote: FeeQuote,
+}
+
+impl fmt::Display for FeeAboveCap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the network fee is up to {} per gas, above your cap of {} (fees.max_fee_gwei_cap)",
+            display_gwei(self.quote.max_fee_per_gas_wei),
+            display_gwei(self.quote.cap_wei.unwrap_or_default()),
+        )
+    }
+}
+
+impl std::error::Error for FeeAboveCap {}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GWEI: u128 = WEI_PER_GWEI;
+
+    fn policy(strategy: FeeStrategy, max_priority_wei: u128, cap_wei: Option<u128>) -> FeePolicy {
+        FeePolicy {
+            strategy,
+            max_priority_wei,
+            cap_wei,
+        }
+    }
+
+    #[test]
+    fn test_strategies_scale_the_base_fee() {
+        let base = 10 * GWEI;
+        let tip = GWEI / 100;
+        let fee = |strategy| compute(base, &policy(strategy, tip, None)).max_fee_per_gas_wei;
+        assert_eq!(fee(FeeStrategy::Slow), 12_500_000_000 + tip);
+        assert_eq!(fee(FeeStrategy::Normal), 20 * GWEI + tip);
+        assert_eq!(fee(FeeStrategy::Fast), 30 * GWEI + tip);
+
+        let quote = compute(base, &policy(FeeStrategy::Normal, tip, None));
+        assert_eq!(quote.base_fee_wei, base);
+        assert_eq!(quote.max_priority_fee_per_gas_wei, tip);
+    }
+
+    #[test]
+    fn test_fractions_of_a_wei_round_up() {
+        // 1.25 × 3 wei is 3.75 wei: never bid below the multiple.
+        let quote = compute(3, &policy(FeeStrategy::Slow, 0, None));
+        assert_eq!(quote.max_fee_per_gas_wei, 4);
+        let quote = compute(4, &policy(FeeStrategy::Slow, 0, None));
+        assert_eq!(quote.max_fee_per_gas_wei, 5);
+    }
+
+    #[test]
+    fn test_zero_base_fee_pays_only_the_tip() {
+        for strategy in FeeStrategy::ALL {
+            let quote = compute(0, &policy(strategy, 7, None));
+            assert_eq!(quote.max_fee_per_gas_wei, 7, "{strategy}");
+        }
+        assert_eq!(
+            compute(0, &policy(FeeStrategy::Fast, 0, None)).max_fee_per_gas_wei,
+            0
+        );
+    }
+
+    #[test]
+    fn test_cap_boundaries() {
+        // Normal: 2 × 5 gwei + 1 gwei = 11 gwei.
+        let at = |cap: u128| compute(5 * GWEI, &policy(FeeStrategy::Normal, GWEI, Some(cap)));
+        assert!(!at(11 * GWEI).over_cap(), "exactly the cap is allowed");
+        assert!(at(11 * GWEI - 1).over_cap(), "one wei over is not");
+        assert!(!at(11 * GWEI + 1).over_cap());
+        assert!(!compute(u128::MAX / 4, &policy(FeeStrategy::Fast, 0, None)).over_cap());
+    }
+
+    #[test]
+    fn test_huge_base_fees_saturate() {
+        let quote = compute(u128::MAX, &policy(FeeStrategy::Fast, GWEI, Some(GWEI)));
+        assert!(quote.max_fee_per_gas_wei >= u128::MAX / 3);
+        assert!(quote.over_cap());
+    }
+
+    #[test]
+    fn test_policy_from_config() {
+        let fees = FeesConfig {
+            max_priority_gwei: 0.0015,
+            max_fee_gwei_cap: 2.5,
+            strategy: FeeStrategy::Fast,
+            ..FeesConfig::default()
+        };
+        assert_eq!(
+            FeePolicy::from_config(&fees),
+            policy(FeeStrategy::Fast, 1_500_000, Some(2_500_000_000))
+        );
+
+        // A cap of 0 (the default) means none.
+        let fees = FeesConfig {
+            max_fee_gwei_cap: 0.0,
+            ..fees
+        };
+        assert_eq!(FeePolicy::from_config(&fees).cap_wei, None);
+        assert_eq!(FeePolicy::default().strategy, FeeStrategy::Normal);
+    }
+
+    #[test]
+    fn test_gwei_conversions() {
+        assert_eq!(gwei_to_wei(1.0), GWEI);
+        assert_eq!(gwei_to_wei(0.001), 1_000_000);
+        assert_eq!(gwei_to_wei(-1.0), 0);
+        assert_eq!(gwei_to_wei(f64::NAN), 0);
+        assert_eq!(display_gwei(0), "0 gwei");
+        assert_eq!(display_gwei(3 * GWEI), "3 gwei");
+        assert_eq!(display_gwei(12_500_000), "0.0125 gwei");
+        assert_eq!(display_gwei(1), "0.000000001 gwei");
+    }
+
+    #[test]
+    fn test_strategy_names_round_trip() {
+        for strategy in FeeStrategy::ALL {
+            assert_eq!(strategy.name().parse::<FeeStrategy>().unwrap(), strategy);
+        }
+        assert_eq!(" FAST ".parse::<FeeStrategy>().unwrap(), FeeStrategy::Fast);
+        let err = "turbo".parse::<FeeStrategy>().unwrap_err().to_string();
+        assert!(err.contains("slow, normal, fast"), "{err}");
+    }
+
+    #[test]
+    fn test_bump_raises_both_fees_by_at_least_ten_percent() {
+        // The network has calmed down: the bump alone sets the fees.
+        let current = compute(GWEI / 10, &policy(FeeStrategy::Normal, 1_000, None));
+        let bumped = bump(2 * GWEI, 3_000_000, 10, &current);
+        assert_eq!(bumped.max_fee_per_gas_wei, 2_200_000_000);
+        assert_eq!(bumped.max_priority_fee_per_gas_wei, 3_300_000);
+
+        // Less than the minimum is raised to it; more is honoured.
+        assert_eq!(bump(2 * GWEI, 3_000_000, 5, &current), bumped);
+        let doubled = bump(2 * GWEI, 3_000_000, 100, &current);
+        assert_eq!(doubled.max_fee_per_gas_wei, 4 * GWEI);
+        assert_eq!(doubled.max_priority_fee_per_gas_wei, 6_000_000);
+    }
+
+    #[test]
+    fn test_bump_rounds_up_and_never_repeats_a_fee() {
+        // 110% of 1 wei is 1.1 wei: the replacement must still bid more.
+        let current = compute(0, &policy(FeeStrategy::Normal, 0, None));
+        let bumped = bump(1, 1, 10, &current);
+        assert_eq!(bumped.max_fee_per_gas_wei, 2);
+        assert_eq!(bumped.max_priority_fee_per_gas_wei, 2);
+        assert_eq!(bump(19, 0, 10, &current).max_fee_per_gas_wei, 21);
+        assert_eq!(bump(20, 0, 10, &current).max_fee_per_gas_wei, 22);
+        assert_eq!(
+            bump(u128::MAX, 0, 10, &current).max_fee_per_gas_wei,
+            u128::MAX / 100
+        );
+    }
+
+    #[test]
+    fn test_bump_keeps_up_with_the_network_and_the_cap() {
+        // The base fee rose past what a 10% bump would bid.
+        let current = compute(5 * GWEI, &policy(FeeStrategy::Normal, GWEI, Some(8 * GWEI)));
+        let bumped = bump(2 * GWEI, GWEI / 2, 10, &current);
+        assert_eq!(bumped.max_fee_per_gas_wei, 11 * GWEI);
+        assert_eq!(bumped.max_priority_fee_per_gas_wei, GWEI);
+        assert_eq!(bumped.base_fee_wei, 5 * GWEI);
+        assert!(bumped.over_cap());
+
+        // A tip above the old max fee lifts the max fee with it.
+        let current = compute(0, &policy(FeeStrategy::Normal, 0, None));
+        let bumped = bump(100, 1_000, 10, &current);
+        assert_eq!(bumped.max_priority_fee_per_gas_wei, 1_100);
+        assert_eq!(bumped.max_fee_per_gas_wei, 1_100);
+    }
+
+    #[test]
+    fn test_fee_above_cap_names_the_setting() {
+        let quote = compute(5 * GWEI, &policy(FeeStrategy::Normal, GWEI, Some(2 * GWEI)));
+        let message = FeeAboveCap { quote }.to_string();
+        assert_eq!(
+            message,
+            "the network fee is up to 11 gwei per gas, above your cap of 2 gwei \
+             (fees.max_fee_gwei_cap)"
+        );
+    }
+}
@@ -1,4 +1,11 @@
 pub mod client;
 pub mod contracts;
+pub mod events;
+pub mod failover;
+pub mod fees;
+pub mod nonce;
+pub mod receipt;
+pub mod retry;
 pub mod signer;
+pub mod txlog;
 pub mod types;
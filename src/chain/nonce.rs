@@ -0,0 +1,173 @@
+//! Nonces for transactions sent back to back.
+//!
+//! Every transaction from an address carries the next nonce. Asking the
+//! node for the pending count before each send breaks when two go out
+//! within one block: both get the same nonce and the second fails with
+//! "nonce too low". A [`NonceManager`] asks once per address and session,
+//! then hands out sequential nonces under a lock. When a send fails with a
+//! nonce error (see [`is_nonce_error`]) the address is resynced: its next
+//! nonce is read from the chain again.
+
+use std::collections::HashMap;
+use std::future::Future;
+
+use alloy::primitives::Address;
+use anyhow::Result;
+use tokio::sync::Mutex;
+use tracing::debug;
+
+/// Fragments of node errors meaning a transaction's nonce was wrong, as
+/// worded by geth, reth, and the common RPC providers.
+const NONCE_ERRORS: [&str; 5] = [
+    "nonce too low",
+    "nonce too high",
+    "invalid nonce",
+    "nonce has already been used",
+    "replacement transaction underpriced",
+];
+
+/// Whether `message` is a node rejecting a transaction's nonce.
+pub fn is_nonce_error(message: &str) -> bool {
+    let message = message.to_ascii_lowercase();
+    NONCE_ERRORS
+        .iter()
+        .any(|fragment| message.contains(fragment))
+}
+
+/// The next nonce to hand out, per sending address.
+#[derive(Debug, Default)]
+pub struct NonceManager {
+    next: Mutex<HashMap<Address, u64>>,
+}
+
+impl NonceManager {
+    /// Reserve the next nonce for `address`. The first call for an address,
+    /// and the first after a [`resync`](Self::resync), takes it from `fetch`
+    /// (the chain's pending transaction count); later calls count up from
+    /// there. Callers are served one at a time, so no two get the same
+    /// nonce.
+    pub async fn next<F>(&self, address: Address, fetch: F) -> Result<u64>
+    where
+        F: Future<Output = Result<u64>>,
+    {
+        let mut next = self.next.lock().await;
+        let nonce = match next.get(&address) {
+            Some(nonce) => *nonce,
+            None => {
+                let pending = fetch.await?;
+                debug!(%address, pending, "nonce synced from chain");
+                pending
+            }
+        };
+        next.insert(address, nonce.saturating_add(1));
+        Ok(nonce)
+    }
+
+    /// Forget what was handed out for `address`, so its next nonce is read
+    /// from the chain.
+    pub async fn resync(&self, address: Address) {
+        self.next.lock().await.remove(&address);
+        debug!(%address, "nonce will be resynced");
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    const SENDER: Address = Address::repeat_byte(0x11);
+
+    /// A chain whose pending count is `pending`, counting how often it is
+    /// asked.
+    struct Chain {
+        pending: AtomicU64,
+        fetches: AtomicU64,
+    }
+
+    impl Chain {
+        fn new(pending: u64) -> Self {
+            Self {
+                pending: AtomicU64::new(pending),
+                fetches: AtomicU64::new(0),
+            }
+        }
+
+        async fn fetch(&self) -> Result<u64> {
+            self.fetches.fetch_add(1, Ordering::SeqCst);
+            // Let other senders queue up behind the lock.
+            tokio::task::yield_now().await;
+            Ok(self.pending.load(Ordering::SeqCst))
+        }
+    }
+
+    #[test]
+    fn test_recognises_nonce_errors() {
+        for message in [
+            "server returned an error response: error code -32000: nonce too low",
+            "Nonce too high. Expected nonce to be 4 but got 9",
+            "invalid nonce; got 3, expected 5",
+            "replacement transaction underpriced",
+        ] {
+            assert!(is_nonce_error(message), "{message}");
+        }
+        for message in [
+            "execution reverted",
+            "insufficient funds for gas * price + value",
+            "connection refused",
+        ] {
+            assert!(!is_nonce_error(message), "{message}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_senders_get_sequential_nonces() {
+        let chain = Chain::new(7);
+        let nonces = NonceManager::default();
+        let send = || nonces.next(SENDER, chain.fetch());
+
+        let (a, b, c) = tokio::join!(send(), send(), send());
+        let mut assigned = vec![a.unwrap(), b.unwrap(), c.unwrap()];
+        assigned.sort_unstable();
+        assert_eq!(assigned, [7, 8, 9]);
+        assert_eq!(chain.fetches.load(Ordering::SeqCst), 1);
+
+        assert_eq!(send().await.unwrap(), 10);
+        assert_eq!(chain.fetches.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_resync_reads_the_chain_again() {
+        let chain = Chain::new(3);
+        let nonces = NonceManager::default();
+        assert_eq!(nonces.next(SENDER, chain.fetch()).await.unwrap(), 3);
+        assert_eq!(nonces.next(SENDER, chain.fetch()).await.unwrap(), 4);
+
+        // Another wallet sent from the same address meanwhile.
+        chain.pending.store(9, Ordering::SeqCst);
+        nonces.resync(SENDER).await;
+        assert_eq!(nonces.next(SENDER, chain.fetch()).await.unwrap(), 9);
+        assert_eq!(nonces.next(SENDER, chain.fetch()).await.unwrap(), 10);
+        assert_eq!(chain.fetches.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_addresses_count_separately_and_failed_fetch_is_retried() {
+        let nonces = NonceManager::default();
+        let other = Address::repeat_byte(0x22);
+
+        let err = nonces
+            .next(SENDER, async { anyhow::bail!("connection refused") })
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("connection refused"));
+
+        assert_eq!(nonces.next(SENDER, async { Ok(5) }).await.unwrap(), 5);
+        assert_eq!(nonces.next(other, async { Ok(0) }).await.unwrap(), 0);
+        assert_eq!(nonces.next(SENDER, async { Ok(99) }).await.unwrap(), 6);
+    }
+}
@@ -0,0 +1,373 @@
+//! What became of a sent transaction, and why it reverted.
+//!
+//! [`ChainClient::wait_for_receipt`] polls for a transaction's receipt and
+//! reports a [`TxOutcome`]. A receipt only says *that* a transaction
+//! reverted; the reason comes from replaying it with `eth_call`, whose error
+//! carries the revert data. [`decode_revert`] turns that data into a
+//! message: the string of a `require`/`revert`, the kind of a Solidity
+//! panic, a description of one of the marketplace contracts' custom errors,
+//! or the selector of any other custom error. The same decoding explains
+//! why [`ChainClient::simulate`] refused a transaction before it was sent.
+//!
+//! [`ChainClient::wait_for_receipt`]: super::client::ChainClient::wait_for_receipt
+//! [`ChainClient::simulate`]: super::client::ChainClient::simulate
+
+use alloy::primitives::U256;
+use alloy::sol_types::{Panic, Revert, SolError, SolInterface};
+use alloy::transports::TransportError;
+use serde::Serialize;
+
+use super::contracts::addresses::USDC_DECIMALS;
+use super::contracts::{RequestRegistry, ValidationRegistry, USDC};
+
+/// How a transaction ended up.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum TxOutcome {
+    /// Mined, succeeded, and confirmed by as many blocks as asked for.
+    Success { block_number: u64, gas_used: u64 },
+    /// Mined but reverted; nothing it would have done happened. `reason`
+    /// is the decoded revert message, when the replay recovered one.
+    Reverted {
+        block_number: u64,
+        reason: Option<String>,
+    },
+    /// Not mined, or not confirmed enough, before the wait ran out. It may
+    /// still go through.
+    TimedOut,
+}
+
+/// Decode revert `data` into a message, or `None` if there is none.
+pub fn decode_revert(data: &[u8]) -> Option<String> {
+    if data.is_empty() {
+        return None;
+    }
+    if let Ok(revert) = Revert::abi_decode(data) {
+        return Some(revert.reason).filter(|reason| !reason.is_empty());
+    }
+    if let Ok(panic) = Panic::abi_decode(data) {
+        return Some(match panic.kind() {
+            Some(kind) => format!("panic: {kind}"),
+            None => format!("panic code {}", panic.code),
+        });
+    }
+    if let Some(reason) = describe_custom_error(data) {
+        return Some(reason);
+    }
+    data.get(..4)
+        .map(|selector| format!("custom error 0x{}", hex::encode(selector)))
+}
+
+/// Describe a custom error the marketplace contracts (or USDC) declare, in
+/// the words [`format_error`] recognises.
+///
+/// The descriptions avoid the word "secret" so a failed claim is not taken
+/// for missing local data.
+///
+/// [`format_error`]: crate::output::formatter::format_error
+fn describe_custom_error(data: &[u8]) -> Option<String> {
+    use RequestRegistry::RequestRegistryErrors as Registry;
+    use ValidationRegistry::ValidationRegistryErrors as Validation;
+    use USDC::USDCErrors as Token;
+
+    if let Ok(err) = Registry::abi_decode(data) {
+        return Some(match err {
+            Registry::RequestNotFound(e) => format!("request {} not found", e.requestId),
+            Registry::RequestNotOpen(e) => format!("request {} is not open", e.requestId),
+            Registry::AlreadyResponded(e) => {
+                format!("request {} already responded to", e.requestId)
+            }
+            Registry::DeadlinePassed(e) => format!("request {} has expired", e.requestId),
+            Registry::InvalidSecret(e) => format!(
+                "the claim preimage for request {} does not match its hash lock",
+                e.requestId
+            ),
+            Registry::NotBuyer(e) => format!("unauthorized: {} is not the buyer", e.caller),
+            Registry::NotSeller(e) => format!("unauthorized: {} is not the seller", e.caller),
+            Registry::NotValidator(e) => {
+                format!("unauthorized: {} is not the assigned validator", e.caller)
+            }
+        });
+    }
+    if let Ok(Validation::AlreadyValidated(e)) = Validation::abi_decode(data) {
+        return Some(format!("request {} already has a verdict", e.requestId));
+    }
+    if let Ok(err) = Token::abi_decode(data) {
+        return Some(match err {
+            Token::ERC20InsufficientBalance(e) => format!(
+                "insufficient funds: {} USDC held, {} needed",
+                usdc(e.balance),
+                usdc(e.needed)
+            ),
+            Token::ERC20InsufficientAllowance(e) => format!(
+                "USDC approval too low: {} approved, {} needed",
+                usdc(e.allowance),
+                usdc(e.needed)
+            ),
+        });
+    }
+    None
+}
+
+/// `atomic` USDC units in whole dollars and cents.
+fn usdc(atomic: U256) -> String {
+    let unit = U256::from(10u64.pow(USDC_DECIMALS as u32));
+    let cents = atomic % unit / U256::from(10u64.pow(USDC_DECIMALS as u32 - 2));
+    format!("{}.{cents:0>2}", atomic / unit)
+}
+
+/// Whether an `eth_call` error says the call reverted, as opposed to the
+/// node or the connection failing.
+pub fn is_revert(err: &TransportError) -> bool {
+    err.as_error_resp().is_some_and(|payload| {
+        payload.as_revert_data().is_some() || payload.message.contains("revert")
+    })
+}
+
+/// The revert reason in an `eth_call` error, from its revert data or, for
+/// nodes that only put it in the message, from `execution reverted: ...`.
+pub fn revert_reason(err: &TransportError) -> Option<String> {
+    let payload = err.as_error_resp()?;
+    if let Some(reason) = payload
+        .as_revert_data()
+        .and_then(|data| decode_revert(&data))
+    {
+        return Some(reason);
+    }
+    payload
+        .message
+        .split_once("execution reverted: ")
+        .map(|(_, reason)| reason.trim().to_string())
+        .filter(|reason| !reason.is_empty())
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::{hex, U256};
+    use serde_json::json;
+
+    /// `require(msg.sender == seller, "Not the seller")`, as returned by a
+    /// node.
+    const NOT_THE_SELLER: &str = "0x08c379a0\
+        0000000000000000000000000000000000000000000000000000000000000020\
+        000000000000000000000000000000000000000000000000000000000000000e\
+        4e6f74207468652073656c6c6572000000000000000000000000000000000000";
+
+    /// `Panic(0x11)`: arithmetic overflow.
+    const OVERFLOW_PANIC: &str = "0x4e487b71\
+        0000000000000000000000000000000000000000000000000000000000000011";
+
+    fn rpc_error(payload: serde_json::Value) -> TransportError {
+        TransportError::ErrorResp(serde_json::from_value(payload).unwrap())
+    }
+
+    // -- decode_revert --------------------------------------------------------
+
+    #[test]
+    fn test_decodes_error_string() {
+        let data = hex::decode(NOT_THE_SELLER).unwrap();
+        assert_eq!(decode_revert(&data).as_deref(), Some("Not the seller"));
+
+        let round_trip = Revert::from("ERC20: transfer amount exceeds balance").abi_encode();
+        assert_eq!(
+            decode_revert(&round_trip).as_deref(),
+            Some("ERC20: transfer amount exceeds balance")
+        );
+        assert_eq!(decode_revert(&Revert::from("").abi_encode()), None);
+    }
+
+    #[test]
+    fn test_decodes_panics() {
+        let data = hex::decode(OVERFLOW_PANIC).unwrap();
+        let reason = decode_revert(&data).unwrap();
+        assert!(reason.starts_with("panic: "), "{reason}");
+        assert!(reason.contains("overflow"), "{reason}");
+
+        let unknown = Panic {
+            code: U256::from(0xffu64),
+        }
+        .abi_encode();
+        assert_eq!(decode_revert(&unknown).as_deref(), Some("panic code 255"));
+    }
+
+    #[test]
+    fn test_names_custom_errors_by_selector() {
+        // An error none of the contracts declare, with one argument of 7.
+        let data = hex::decode(
+            "0x3f2a1b0c0000000000000000000000000000000000000000000000000000000000000007",
+        )
+        .unwrap();
+        assert_eq!(
+            decode_revert(&data).as_deref(),
+            Some("custom error 0x3f2a1b0c")
+        );
+    }
+
+    /// Revert data for the custom error `signature` with word-sized `args`,
+    /// encoded by hand.
+    fn custom_error(signature: &str, args: &[u64]) -> Vec<u8> {
+        let mut data = alloy::primitives::keccak256(signature)[..4].to_vec();
+        for arg in args {
+            data.extend_from_slice(&U256::from(*arg).to_be_bytes::<32>());
+        }
+        data
+    }
+
+    #[test]
+    fn test_describes_marketplace_custom_errors() {
+        let cases = [
+            ("RequestNotFound(uint256)", vec![7], "request 7 not found"),
+            ("RequestNotOpen(uint256)", vec![7], "request 7 is not open"),
+            (
+                "AlreadyResponded(uint256)",
+                vec![7],
+                "request 7 already responded to",
+            ),
+            (
+                "DeadlinePassed(uint256,uint256)",
+                vec![7, 1_700_000_000],
+                "request 7 has expired",
+            ),
+            (
+                "InvalidSecret(uint256)",
+                vec![42],
+                "the claim preimage for request 42 does not match its hash lock",
+            ),
+        ];
+        for (signature, args, expected) in cases {
+            let data = custom_error(signature, &args);
+            assert_eq!(
+                decode_revert(&data).as_deref(),
+                Some(expected),
+                "{signature}"
+            );
+        }
+
+        let not_seller = RequestRegistry::NotSeller {
+            caller: alloy::primitives::Address::repeat_byte(0x11),
+        }
+        .abi_encode();
+        let reason = decode_revert(&not_seller).unwrap();
+        assert!(reason.starts_with("unauthorized: 0x1111"), "{reason}");
+        assert!(reason.ends_with("is not the seller"), "{reason}");
+
+        let verdict = ValidationRegistry::AlreadyValidated {
+            requestId: U256::from(3u64),
+        }
+        .abi_encode();
+        assert_eq!(
+            decode_revert(&verdict).as_deref(),
+            Some("request 3 already has a verdict")
+        );
+    }
+
+    #[test]
+    fn test_describes_usdc_shortfalls() {
+        let balance = USDC::ERC20InsufficientBalance {
+            sender: alloy::primitives::Address::ZERO,
+            balance: U256::from(1_500_000u64),
+            needed: U256::from(5_000_000u64),
+        }
+        .abi_encode();
+        assert_eq!(
+            decode_revert(&balance).as_deref(),
+            Some("insufficient funds: 1.50 USDC held, 5.00 needed")
+        );
+
+        let allowance = USDC::ERC20InsufficientAllowance {
+            spender: alloy::primitives::Address::ZERO,
+            allowance: U256::ZERO,
+            needed: U256::from(250_000u64),
+        }
+        .abi_encode();
+        assert_eq!(
+            decode_revert(&allowance).as_deref(),
+            Some("USDC approval too low: 0.00 approved, 0.25 needed")
+        );
+    }
+
+    #[test]
+    fn test_empty_or_short_data_has_no_reason() {
+        assert_eq!(decode_revert(&[]), None);
+        assert_eq!(decode_revert(&[0x08, 0xc3]), None);
+        // A truncated Error(string) is not mistaken for a reason.
+        let data = hex::decode(&NOT_THE_SELLER[..80]).unwrap();
+        assert_eq!(
+            decode_revert(&data).as_deref(),
+            Some("custom error 0x08c379a0")
+        );
+    }
+
+    // -- revert_reason --------------------------------------------------------
+
+    #[test]
+    fn test_reads_reason_from_call_error_data() {
+        let err = rpc_error(json!({
+            "code": 3,
+            "message": "execution reverted",
+            "data": NOT_THE_SELLER,
+        }));
+        assert_eq!(revert_reason(&err).as_deref(), Some("Not the seller"));
+
+        // Some providers nest the data one level down.
+        let err = rpc_error(json!({
+            "code": -32000,
+            "message": "execution reverted",
+            "data": { "originalError": { "data": OVERFLOW_PANIC } },
+        }));
+        assert!(revert_reason(&err).unwrap().contains("overflow"));
+    }
+
+    #[test]
+    fn test_falls_back_to_the_message() {
+        let err = rpc_error(json!({
+            "code": -32000,
+            "message": "execution reverted: Deadline passed",
+        }));
+        assert_eq!(revert_reason(&err).as_deref(), Some("Deadline passed"));
+
+        let err = rpc_error(json!({ "code": -32000, "message": "execution reverted" }));
+        assert_eq!(revert_reason(&err), None);
+        let err = rpc_error(json!({ "code": -32601, "message": "method not found" }));
+        assert_eq!(revert_reason(&err), None);
+    }
+
+    #[test]
+    fn test_tells_reverts_from_other_failures() {
+        assert!(is_revert(&rpc_error(json!({
+            "code": 3,
+            "message": "execution reverted",
+            "data": NOT_THE_SELLER,
+        }))));
+        assert!(is_revert(&rpc_error(
+            json!({ "code": -32000, "message": "execution reverted" })
+        )));
+        assert!(!is_revert(&rpc_error(
+            json!({ "code": -32601, "message": "method not found" })
+        )));
+        assert!(!is_revert(&TransportError::local_usage_str(
+            "connection refused"
+        )));
+    }
+
+    #[test]
+    fn test_outcome_serializes_with_tag() {
+        let outcome = TxOutcome::Reverted {
+            block_number: 12,
+            reason: Some("Not the seller".to_string()),
+        };
+        assert_eq!(
+            serde_json::to_value(&outcome).unwrap(),
+            json!({ "outcome": "reverted", "block_number": 12, "reason": "Not the seller" })
+        );
+        assert_eq!(
+            serde_json::to_value(TxOutcome::TimedOut).unwrap(),
+            json!({ "outcome": "timed_out" })
+        );
+    }
+}
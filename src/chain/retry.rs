@@ -0,0 +1,349 @@
+//! Retries for chain reads.
+//!
+//! Public RPC providers answer bursts with the odd 429 or 502, and a single
+//! one of those should not fail a whole command. [`retry_async`] runs a read
+//! again, with exponential backoff and jitter, when its error is
+//! [`Transient`]: a timeout, a dropped connection, a rate limit, or a
+//! gateway error. Reverts, invalid parameters, and anything else the node
+//! rejected on its merits fail at once.
+//!
+//! The policy comes from `[network]` in `config.toml`
+//! (`rpc_max_retries`, `rpc_retry_base_ms`).
+
+use std::fmt::Display;
+use std::future::Future;
+use std::time::Duration;
+
+use alloy::transports::{TransportError, TransportErrorKind};
+use rand::Rng;
+use tracing::debug;
+
+use crate::config::store::NetworkConfig;
+
+/// Longest wait between two attempts, however many retries came before.
+pub const MAX_RETRY_DELAY: Duration = Duration::from_secs(10);
+
+/// JSON-RPC error codes providers use for "slow down" rather than "no":
+/// "limit exceeded" (EIP-1474) and a bare 429.
+const RATE_LIMIT_CODES: [i64; 2] = [-32005, 429];
+
+/// HTTP statuses worth another try: rate limited, or a gateway in front of
+/// the node failing.
+const TRANSIENT_HTTP_STATUSES: [u16; 4] = [429, 502, 503, 504];
+
+// ---------------------------------------------------------------------------
+// RetryPolicy
+// ---------------------------------------------------------------------------
+
+/// How often, and how patiently, a failed read is retried.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt; 0 turns retrying off.
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for each one after it.
+    pub base_delay: Duration,
+    /// Add up to half the delay again at random, so clients that failed
+    /// together do not retry together.
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::from_config(&NetworkConfig::default())
+    }
+}
+
+impl RetryPolicy {
+    /// The policy configured in `[network]`.
+    pub fn from_config(network: &NetworkConfig) -> Self {
+        Self {
+            max_retries: network.rpc_max_retries,
+            base_delay: Duration::from_millis(network.rpc_retry_base_ms),
+            jitter: true,
+        }
+    }
+
+    /// A policy that never retries.
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            base_delay: Duration::ZERO,
+            jitter: false,
+        }
+    }
+
+    /// Wait before retry number `retry` (1 for the first), without jitter:
+    /// `base_delay * 2^(retry - 1)`, capped at [`MAX_RETRY_DELAY`].
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        self.base_delay.saturating_mul(factor).min(MAX_RETRY_DELAY)
+    }
+
+    /// [`RetryPolicy::backoff`] plus jitter, if enabled.
+    fn delay(&self, retry: u32) -> Duration {
+        let backoff = self.backoff(retry);
+        if !self.jitter || backoff.is_zero() {
+            return backoff;
+        }
+        let extra = rand::thread_rng().gen_range(0..=backoff.as_millis() as u64 / 2);
+        backoff + Duration::from_millis(extra)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Classification
+// ---------------------------------------------------------------------------
+
+/// Errors that can tell whether trying again might succeed.
+pub trait Transient {
+    /// `true` for failures of the connection or the provider (timeouts,
+    /// resets, rate limits, gateway errors); `false` for answers the node
+    /// gave on the merits, such as a revert or invalid parameters.
+    fn is_transient(&self) -> bool;
+}
+
+impl Transient for TransportError {
+    fn is_transient(&self) -> bool {
+        match self {
+            // -32005 also covers "query returned more than 10000 results",
+            // which no amount of waiting fixes.
+            TransportError::ErrorResp(payload) => {
+                RATE_LIMIT_CODES.contains(&payload.code)
+                    && !super::events::is_range_too_large(&payload.message)
+            }
+            TransportError::Transport(kind) => match kind {
+                TransportErrorKind::HttpError(http) => {
+                    TRANSIENT_HTTP_STATUSES.contains(&http.status)
+                }
+                TransportErrorKind::MissingBatchResponse(_)
+                | TransportErrorKind::BackendGone
+                | TransportErrorKind::PubsubUnavailable => true,
+                TransportErrorKind::Custom(err) => match err.downcast_ref::<reqwest::Error>() {
+                    Some(err) => {
+                        err.is_timeout()
+                            || err.is_connect()
+                            || err
+                                .status()
+                                .is_some_and(|s| TRANSIENT_HTTP_STATUSES.contains(&s.as_u16()))
+                    }
+                    None => kind.is_retry_err(),
+                },
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+}
+
+impl Transient for alloy::contract::Error {
+    fn is_transient(&self) -> bool {
+        match self {
+            alloy::contract::Error::TransportError(err) => err.is_transient(),
+            _ => false,
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// retry_async
+// ---------------------------------------------------------------------------
+
+/// Run `attempt` until it succeeds, fails with an error that is not
+/// [`Transient`], or `policy` runs out of retries; return the last result.
+/// `what` names the read in the debug log line written before each retry.
+pub async fn retry_async<T, E, F, Fut>(
+    policy: &RetryPolicy,
+    what: &str,
+    mut attempt: F,
+) -> Result<T, E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    E: Transient + Display,
+{
+    let mut retry = 0;
+    loop {
+        match attempt().await {
+            Err(err) if retry < policy.max_retries && err.is_transient() => {
+                retry += 1;
+                let delay = policy.delay(retry);
+                debug!(
+                    what,
+                    retry,
+                    max_retries = policy.max_retries,
+                    delay_ms = delay.as_millis() as u64,
+                    error = %err,
+                    "transient network error, retrying"
+                );
+                tokio::time::sleep(delay).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::transports::HttpError;
+    use std::cell::Cell;
+
+    fn http_error(status: u16) -> TransportError {
+        TransportErrorKind::HttpError(HttpError {
+            status,
+            body: String::new(),
+        })
+        .into()
+    }
+
+    fn error_resp(code: i64, message: &str) -> TransportError {
+        let payload = serde_json::json!({ "code": code, "message": message });
+        TransportError::ErrorResp(serde_json::from_value(payload).unwrap())
+    }
+
+    fn quick(max_retries: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_retries,
+            base_delay: Duration::from_millis(1),
+            jitter: false,
+        }
+    }
+
+    #[test]
+    fn test_classifies_transient_errors() {
+        for status in [429, 502, 503, 504] {
+            assert!(http_error(status).is_transient(), "{status}");
+        }
+        assert!(!http_error(400).is_transient());
+        assert!(!http_error(401).is_transient());
+        assert!(error_resp(-32005, "limit exceeded").is_transient());
+        assert!(!error_resp(-32005, "query returned more than 10000 results").is_transient());
+        assert!(TransportErrorKind::backend_gone().is_transient());
+
+        assert!(!error_resp(3, "execution reverted").is_transient());
+        assert!(!error_resp(-32602, "invalid params").is_transient());
+        assert!(!alloy::contract::Error::UnknownFunction("f".to_string()).is_transient());
+        assert!(alloy::contract::Error::TransportError(http_error(502)).is_transient());
+    }
+
+    #[tokio::test]
+    async fn test_reqwest_timeouts_are_transient() {
+        // A local server that accepts the connection and never answers.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (_socket, _) = listener.accept().await.unwrap();
+            std::future::pending::<()>().await;
+        });
+
+        let err = reqwest::Client::builder()
+            .timeout(Duration::from_millis(50))
+            .build()
+            .unwrap()
+            .get(url)
+            .send()
+            .await
+            .unwrap_err();
+        server.abort();
+        assert!(err.is_timeout(), "{err}");
+        assert!(TransportErrorKind::custom(err).is_transient());
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_the_cap() {
+        let policy = RetryPolicy {
+            max_retries: 10,
+            base_delay: Duration::from_millis(250),
+            jitter: false,
+        };
+        assert_eq!(policy.backoff(1), Duration::from_millis(250));
+        assert_eq!(policy.backoff(2), Duration::from_millis(500));
+        assert_eq!(policy.backoff(3), Duration::from_millis(1000));
+        assert_eq!(policy.backoff(10), MAX_RETRY_DELAY);
+        assert_eq!(policy.backoff(u32::MAX), MAX_RETRY_DELAY);
+
+        let jittered = RetryPolicy {
+            jitter: true,
+            ..policy
+        };
+        for _ in 0..20 {
+            let delay = jittered.delay(2);
+            assert!(
+                (Duration::from_millis(500)..=Duration::from_millis(750)).contains(&delay),
+                "{delay:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_policy_from_config() {
+        let network = NetworkConfig {
+            rpc_max_retries: 5,
+            rpc_retry_base_ms: 100,
+            ..NetworkConfig::default()
+        };
+        let policy = RetryPolicy::from_config(&network);
+        assert_eq!(policy.max_retries, 5);
+        assert_eq!(policy.base_delay, Duration::from_millis(100));
+        assert_eq!(RetryPolicy::default().max_retries, 3);
+    }
+
+    #[tokio::test]
+    async fn test_retry_async_recovers_from_transient_failures() {
+        let calls = Cell::new(0);
+        let result = retry_async(&quick(3), "block number", || {
+            calls.set(calls.get() + 1);
+            let call = calls.get();
+            async move {
+                match call {
+                    1 => Err(http_error(429)),
+                    2 => Err(http_error(502)),
+                    _ => Ok(42u64),
+                }
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap(), 42);
+        assert_eq!(calls.get(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_retry_async_gives_up_after_max_retries() {
+        let calls = Cell::new(0);
+        let result: Result<u64, _> = retry_async(&quick(2), "block number", || {
+            calls.set(calls.get() + 1);
+            async { Err(http_error(503)) }
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls.get(), 3, "one attempt plus two retries");
+
+        calls.set(0);
+        let result: Result<u64, _> = retry_async(&RetryPolicy::none(), "block number", || {
+            calls.set(calls.get() + 1);
+            async { Err(http_error(503)) }
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_retry_async_never_retries_reverts() {
+        let calls = Cell::new(0);
+        let result: Result<u64, _> = retry_async(&quick(3), "fee rate", || {
+            calls.set(calls.get() + 1);
+            async { Err(error_resp(3, "execution reverted")) }
+        })
+        .await;
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("execution reverted"));
+        assert_eq!(calls.get(), 1);
+    }
+}
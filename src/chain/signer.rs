@@ -28,9 +28,9 @@ pub struct TransactionSigner {
 impl TransactionSigner {
     /// Load the private key from the keystore and build a signer.
     ///
-    /// The passphrase is obtained from the `AGENTMARKET_KEYSTORE_PASSPHRASE`
-    /// environment variable, or via an interactive prompt if the env var is
-    /// not set.
+    /// The passphrase is obtained as by [`keystore::get_passphrase`]: from
+    /// the environment or `--passphrase-file`, otherwise an interactive
+    /// prompt.
     pub fn from_keystore() -> Result<Self> {
         let passphrase =
             keystore::get_passphrase().context("failed to obtain keystore passphrase")?;
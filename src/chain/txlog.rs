@@ -0,0 +1,389 @@
+//! Audit trail of the transactions this CLI sends.
+//!
+//! Every transaction sent through [`ChainClient::send_with_nonce`] is
+//! appended to `~/.agentmarket/txlog.jsonl` as pending, and
+//! [`ChainClient::wait_for_receipt`] appends its outcome once known. Lines
+//! are only ever appended, one JSON object each: a transaction's latest line
+//! is its current state, and earlier lines stay as history. Each line is
+//! written with a single `write` to a file opened for appending, so the
+//! daemon and a command sending at the same time do not interleave.
+//!
+//! A transaction replaced by another with the same nonce (see
+//! [`ChainClient::replace_transaction`]) gets a final line saying which one
+//! was mined instead.
+//!
+//! `agentmarket tx list` shows the log.
+//!
+//! [`ChainClient::send_with_nonce`]: super::client::ChainClient::send_with_nonce
+//! [`ChainClient::wait_for_receipt`]: super::client::ChainClient::wait_for_receipt
+//! [`ChainClient::replace_transaction`]: super::client::ChainClient::replace_transaction
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use alloy::primitives::{Address, B256};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use super::receipt::TxOutcome;
+use crate::config::store::config_dir;
+
+/// Name of the transaction log inside the config directory.
+pub const TXLOG_FILE: &str = "txlog.jsonl";
+
+/// What a transaction did.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TxKind {
+    /// Minted the agent's identity.
+    Register,
+    /// Created a request.
+    Create,
+    /// Submitted a response.
+    Respond,
+    /// Recorded a validation verdict.
+    Validate,
+    /// Claimed payment.
+    Claim,
+    /// Cancelled a request.
+    Cancel,
+    /// Expired an overdue request.
+    Expire,
+    /// Moved USDC or ETH out of the agent.
+    Withdraw,
+    /// Let the Request Registry spend USDC.
+    Approve,
+    /// An empty send to the agent itself, taking a stuck transaction's
+    /// nonce so that it never runs.
+    Void,
+}
+
+impl fmt::Display for TxKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Self::Register => "register",
+            Self::Create => "create",
+            Self::Respond => "respond",
+            Self::Validate => "validate",
+            Self::Claim => "claim",
+            Self::Cancel => "cancel",
+            Self::Expire => "expire",
+            Self::Withdraw => "withdraw",
+            Self::Approve => "approve",
+            Self::Void => "void",
+        })
+    }
+}
+
+/// Where a transaction stands.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TxStatus {
+    /// Sent; no final receipt seen yet.
+    Pending,
+    /// Mined, succeeded, and confirmed.
+    Confirmed,
+    /// Mined but reverted.
+    Reverted,
+    /// Will never be mined: another transaction with the same nonce was.
+    Replaced,
+}
+
+/// One line of the log.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TxRecord {
+    pub hash: B256,
+    pub kind: TxKind,
+    /// The request the transaction belonged to, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    pub from: Address,
+    pub nonce: u64,
+    /// When the transaction was sent (Unix timestamp).
+    pub sent_at: u64,
+    pub status: TxStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gas_used: Option<u64>,
+    /// Why the transaction reverted, when known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// The transaction mined in this one's place, if it was replaced.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replaced_by: Option<B256>,
+    /// When this line was written (Unix timestamp).
+    pub updated_at: u64,
+}
+
+impl TxRecord {
+    /// A transaction just sent.
+    pub fn pending(
+        hash: B256,
+        kind: TxKind,
+        request_id: Option<&str>,
+        from: Address,
+        nonce: u64,
+        now: u64,
+    ) -> Self {
+        Self {
+            hash,
+            kind,
+            request_id: request_id.map(str::to_string),
+            from,
+            nonce,
+            sent_at: now,
+            status: TxStatus::Pending,
+            block_number: None,
+            gas_used: None,
+            reason: None,
+            replaced_by: None,
+            updated_at: now,
+        }
+    }
+
+    /// This transaction, never to be mined because `landed` took its nonce.
+    pub fn replaced(&self, landed: B256, now: u64) -> Self {
+        Self {
+            status: TxStatus::Replaced,
+            replaced_by: Some(landed),
+            updated_at: now,
+            ..self.clone()
+        }
+    }
+
+    /// This transaction with `outcome` applied, or `None` if the outcome is
+    /// not final ([`TxOutcome::TimedOut`]).
+    pub fn settled(&self, outcome: &TxOutcome, now: u64) -> Option<Self> {
+        let mut record = self.clone();
+        match outcome {
+            TxOutcome::Success {
+                block_number,
+                gas_used,
+            } => {
+                record.status = TxStatus::Confirmed;
+                record.block_number = Some(*block_number);
+                record.gas_used = Some(*gas_used);
+            }
+            TxOutcome::Reverted {
+                block_number,
+                reason,
+            } => {
+                record.status = TxStatus::Reverted;
+                record.block_number = Some(*block_number);
+                record.reason = reason.clone();
+            }
+            TxOutcome::TimedOut => return None,
+        }
+        record.updated_at = now;
+        Some(record)
+    }
+}
+
+/// The transaction log file.
+#[derive(Clone, Debug)]
+pub struct TxLog {
+    path: PathBuf,
+}
+
+impl TxLog {
+    /// The log in the config directory.
+    pub fn open() -> Result<Self> {
+        Ok(Self::at(config_dir()?.join(TXLOG_FILE)))
+    }
+
+    /// The log at `path`.
+    pub fn at(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append `record` as one line.
+    pub fn append(&self, record: &TxRecord) -> Result<()> {
+        let mut line =
+            serde_json::to_string(record).context("failed to serialize transaction record")?;
+        line.push('\n');
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("failed to open {}", self.path.display()))?;
+        file.write_all(line.as_bytes())
+            .with_context(|| format!("failed to write to {}", self.path.display()))?;
+        debug!(hash = %record.hash, status = ?record.status, "transaction recorded");
+        Ok(())
+    }
+
+    /// Every transaction in the log as of its latest line, in the order
+    /// they were sent. A missing log is empty; lines that do not parse (a
+    /// write cut short by a crash) are skipped.
+    pub fn records(&self) -> Result<Vec<TxRecord>> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => {
+                return Err(err).with_context(|| format!("failed to read {}", self.path.display()))
+            }
+        };
+
+        let mut records: Vec<TxRecord> = Vec::new();
+        let mut index: HashMap<B256, usize> = HashMap::new();
+        for (number, line) in contents.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record: TxRecord = match serde_json::from_str(line) {
+                Ok(record) => record,
+                Err(err) => {
+                    warn!(line = number + 1, error = %err, "skipping unreadable transaction log line");
+                    continue;
+                }
+            };
+            match index.get(&record.hash) {
+                Some(&at) => records[at] = record,
+                None => {
+                    index.insert(record.hash, records.len());
+                    records.push(record);
+                }
+            }
+        }
+        Ok(records)
+    }
+
+    /// The latest state of transaction `hash`, if it is in the log.
+    pub fn find(&self, hash: B256) -> Result<Option<TxRecord>> {
+        Ok(self.records()?.into_iter().find(|r| r.hash == hash))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FROM: Address = Address::repeat_byte(0x11);
+
+    fn sent(byte: u8, kind: TxKind, request_id: Option<&str>) -> TxRecord {
+        TxRecord::pending(
+            B256::repeat_byte(byte),
+            kind,
+            request_id,
+            FROM,
+            byte as u64,
+            1_700_000_000,
+        )
+    }
+
+    #[test]
+    fn test_missing_log_is_empty() {
+        let tmp = tempfile::tempdir().unwrap();
+        let log = TxLog::at(tmp.path().join(TXLOG_FILE));
+        assert!(log.records().unwrap().is_empty());
+        assert_eq!(log.find(B256::ZERO).unwrap(), None);
+    }
+
+    #[test]
+    fn test_latest_line_wins_and_order_is_kept() {
+        let tmp = tempfile::tempdir().unwrap();
+        let log = TxLog::at(tmp.path().join(TXLOG_FILE));
+
+        let approve = sent(1, TxKind::Approve, None);
+        let create = sent(2, TxKind::Create, Some("local-1700000000"));
+        log.append(&approve).unwrap();
+        log.append(&create).unwrap();
+        let confirmed = approve
+            .settled(
+                &TxOutcome::Success {
+                    block_number: 10,
+                    gas_used: 46_000,
+                },
+                1_700_000_010,
+            )
+            .unwrap();
+        log.append(&confirmed).unwrap();
+
+        let records = log.records().unwrap();
+        assert_eq!(records, [confirmed.clone(), create.clone()]);
+        assert_eq!(records[0].status, TxStatus::Confirmed);
+        assert_eq!(records[0].sent_at, 1_700_000_000);
+        assert_eq!(records[0].updated_at, 1_700_000_010);
+
+        // Appending never rewrites: all three lines are still there.
+        let lines = fs::read_to_string(log.path()).unwrap();
+        assert_eq!(lines.lines().count(), 3);
+    }
+
+    #[test]
+    fn test_settled_applies_outcome() {
+        let record = sent(3, TxKind::Claim, Some("7"));
+        let reverted = record
+            .settled(
+                &TxOutcome::Reverted {
+                    block_number: 12,
+                    reason: Some("Not the seller".to_string()),
+                },
+                1_700_000_020,
+            )
+            .unwrap();
+        assert_eq!(reverted.status, TxStatus::Reverted);
+        assert_eq!(reverted.block_number, Some(12));
+        assert_eq!(reverted.reason.as_deref(), Some("Not the seller"));
+        assert_eq!(reverted.request_id.as_deref(), Some("7"));
+
+        assert_eq!(record.settled(&TxOutcome::TimedOut, 1_700_000_020), None);
+    }
+
+    #[test]
+    fn test_replaced_names_what_landed() {
+        let tmp = tempfile::tempdir().unwrap();
+        let log = TxLog::at(tmp.path().join(TXLOG_FILE));
+        let stuck = sent(7, TxKind::Claim, Some("7"));
+        log.append(&stuck).unwrap();
+        let landed = B256::repeat_byte(8);
+        log.append(&stuck.replaced(landed, 1_700_000_030)).unwrap();
+
+        let record = log.find(stuck.hash).unwrap().unwrap();
+        assert_eq!(record.status, TxStatus::Replaced);
+        assert_eq!(record.replaced_by, Some(landed));
+        assert_eq!(record.nonce, stuck.nonce);
+        assert_eq!(record.sent_at, 1_700_000_000);
+        assert_eq!(record.updated_at, 1_700_000_030);
+    }
+
+    #[test]
+    fn test_unreadable_lines_are_skipped() {
+        let tmp = tempfile::tempdir().unwrap();
+        let log = TxLog::at(tmp.path().join(TXLOG_FILE));
+        let record = sent(4, TxKind::Withdraw, None);
+        log.append(&record).unwrap();
+
+        // A crash cut the next write short.
+        let mut file = OpenOptions::new().append(true).open(log.path()).unwrap();
+        file.write_all(b"{\"hash\":\"0x05\n\n").unwrap();
+
+        assert_eq!(log.records().unwrap(), [record]);
+    }
+
+    #[test]
+    fn test_lines_are_plain_json() {
+        let record = sent(6, TxKind::Respond, Some("9"));
+        let json: serde_json::Value =
+            serde_json::from_str(&serde_json::to_string(&record).unwrap()).unwrap();
+        assert_eq!(json["kind"], "respond");
+        assert_eq!(json["status"], "pending");
+        assert_eq!(json["request_id"], "9");
+        assert!(json.get("block_number").is_none());
+        assert!(json.get("replaced_by").is_none());
+        assert_eq!(TxKind::Respond.to_string(), "respond");
+    }
+}
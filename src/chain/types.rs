@@ -1,8 +1,10 @@
 use std::fmt;
 
-use alloy::primitives::{Address, U256};
+use alloy::primitives::{Address, B256, U256};
 use serde::{Deserialize, Serialize};
 
+use super::receipt::TxOutcome;
+
 // ---------------------------------------------------------------------------
 // Core wrapper types
 // ---------------------------------------------------------------------------
@@ -51,29 +53,31 @@ impl fmt::Display for RequestId {
 // RequestStatus
 // ---------------------------------------------------------------------------
 
-/// Request status on-chain.
+/// Request status on-chain, in the order of the Request Registry's
+/// `RequestStatus` enum.
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum RequestStatus {
     Open,
     Responded,
     Validated,
     Claimed,
-    Expired,
     Cancelled,
+    Expired,
 }
 
 impl RequestStatus {
     /// Map a `u8` discriminant (as stored on-chain) to a [`RequestStatus`].
     ///
-    /// Returns `None` for unrecognised values.
+    /// Returns `None` for unrecognised values, such as a status added by a
+    /// newer registry.
     pub fn from_u8(val: u8) -> Option<Self> {
         match val {
             0 => Some(Self::Open),
             1 => Some(Self::Responded),
             2 => Some(Self::Validated),
             3 => Some(Self::Claimed),
-            4 => Some(Self::Expired),
-            5 => Some(Self::Cancelled),
+            4 => Some(Self::Cancelled),
+            5 => Some(Self::Expired),
             _ => None,
         }
     }
@@ -96,20 +100,114 @@ pub struct Request {
     pub status: RequestStatus,
 }
 
+/// A request as stored in the Request Registry's `requests` mapping, as
+/// returned by [`ChainClient::get_request`]. Amounts are in USDC atomic
+/// units.
+///
+/// [`ChainClient::get_request`]: super::client::ChainClient::get_request
+#[derive(Clone, Debug, Serialize)]
+pub struct OnchainRequest {
+    pub id: RequestId,
+    pub buyer: Address,
+    pub price_usdc: u64,
+    pub deadline: u64,
+    pub target_agent_id: AgentId,
+    pub ipfs_cid: String,
+    /// `None` if the registry reported a status this CLI does not know.
+    pub status: Option<RequestStatus>,
+}
+
 // ---------------------------------------------------------------------------
 // Response
 // ---------------------------------------------------------------------------
 
-/// On-chain response data.
+/// On-chain response data, as returned by [`ChainClient::get_response`].
+///
+/// [`ChainClient::get_response`]: super::client::ChainClient::get_response
 #[derive(Clone, Debug)]
 pub struct Response {
     pub request_id: RequestId,
     pub seller: Address,
-    pub seller_agent_id: AgentId,
+    /// As submitted, normally an `ipfs://` URI.
     pub ipfs_cid: String,
     pub secret_hash: [u8; 32],
 }
 
+// ---------------------------------------------------------------------------
+// Validation
+// ---------------------------------------------------------------------------
+
+/// A verdict recorded in the Validation Registry, as returned by
+/// [`ChainClient::get_validation`].
+///
+/// [`ChainClient::get_validation`]: super::client::ChainClient::get_validation
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Validation {
+    pub request_id: RequestId,
+    pub validator: Address,
+    /// Out of 100.
+    pub score: u8,
+    pub passed: bool,
+    /// When it was recorded (Unix seconds).
+    pub validated_at: u64,
+}
+
+/// What [`ChainClient::submit_validation`] did.
+///
+/// [`ChainClient::submit_validation`]: super::client::ChainClient::submit_validation
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ValidationSubmission {
+    /// A verdict was already recorded, so nothing was sent.
+    AlreadyRecorded(Validation),
+    /// The verdict was sent in this transaction, not yet confirmed.
+    Sent(B256),
+}
+
+/// What [`ChainClient::replace_transaction`] did.
+///
+/// [`ChainClient::replace_transaction`]: super::client::ChainClient::replace_transaction
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Replacement {
+    /// The stuck transaction.
+    pub original: B256,
+    /// The transaction sent in its place, with the same nonce.
+    pub replacement: B256,
+    pub nonce: u64,
+    /// Whether the replacement was an empty send to self.
+    pub cancel: bool,
+    /// The most the replacement pays per gas, in wei.
+    pub max_fee_per_gas_wei: u128,
+    /// Whichever of the two was mined; `None` if neither was before the
+    /// wait ran out.
+    pub landed: Option<B256>,
+    /// How the mined one ended.
+    pub outcome: TxOutcome,
+}
+
+// ---------------------------------------------------------------------------
+// SellerRecord
+// ---------------------------------------------------------------------------
+
+/// One response an agent submitted, as recorded by Request Registry events
+/// and returned by [`ChainClient::get_seller_history`]. Amounts are in USDC
+/// atomic units.
+///
+/// [`ChainClient::get_seller_history`]: super::client::ChainClient::get_seller_history
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SellerRecord {
+    pub request_id: RequestId,
+    /// Price of the request, or 0 if its creation was not found.
+    pub price_usdc: u64,
+    /// The validation verdict, once validated.
+    pub passed: Option<bool>,
+    /// Who gave the verdict.
+    pub validator: Option<Address>,
+    /// When the verdict was recorded, if the node reports block times.
+    pub validated_at: Option<u64>,
+    /// Whether the seller has claimed payment.
+    pub claimed: bool,
+}
+
 // ---------------------------------------------------------------------------
 // Balance
 // ---------------------------------------------------------------------------
@@ -138,16 +236,160 @@ impl Balance {
     ///
     /// Displays exactly 4 decimal places.
     pub fn display_eth(&self) -> String {
+        self.display_eth_with(4)
+    }
+
+    /// Like [`display_eth`](Self::display_eth) with `decimals` decimal
+    /// places (1 to 18), for amounts as small as a fee.
+    pub fn display_eth_with(&self, decimals: u32) -> String {
         const ETH: u128 = 1_000_000_000_000_000_000; // 1e18
+        let decimals = decimals.clamp(1, 18);
 
         // Integer division: whole = wei / 1e18, frac = wei % 1e18
         let whole = self.wei / U256::from(ETH);
         let remainder = self.wei % U256::from(ETH);
 
-        // Scale remainder to 4 decimal digits: remainder * 10_000 / 1e18
-        let frac = (remainder * U256::from(10_000u64)) / U256::from(ETH);
+        // Scale remainder to `decimals` digits: remainder * 10^decimals / 1e18
+        let frac = (remainder * U256::from(10u64.pow(decimals))) / U256::from(ETH);
 
-        format!("{}.{:04} ETH", whole, frac.to::<u64>())
+        format!(
+            "{}.{:0width$} ETH",
+            whole,
+            frac.to::<u64>(),
+            width = decimals as usize
+        )
+    }
+}
+
+/// ETH and USDC balances of one address.
+///
+/// ETH pays network fees ("gas balance"); USDC, in atomic units, is what the
+/// agent has earned or can pay for requests with ("spendable balance").
+#[derive(Clone, Debug)]
+pub struct Balances {
+    pub eth: Balance,
+    pub usdc: u64,
+}
+
+impl Balances {
+    /// e.g. `"Gas balance: 0.0200 ETH"`.
+    pub fn display_gas(&self) -> String {
+        format!("Gas balance: {}", self.eth.display_eth())
+    }
+
+    /// e.g. `"Spendable balance: $14.50"`.
+    pub fn display_spendable(&self) -> String {
+        format!(
+            "Spendable balance: {}",
+            crate::engine::requests::format_price_usd(self.usdc)
+        )
+    }
+}
+
+// ---------------------------------------------------------------------------
+// FeeEstimate
+// ---------------------------------------------------------------------------
+
+/// Decimal places shown for a fee, which is usually far below 0.0001 ETH.
+pub const FEE_DECIMALS: u32 = 8;
+
+/// Estimated network fee for a transaction, shown before it is sent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FeeEstimate {
+    /// Gas units the transaction is expected to use.
+    pub gas: u64,
+    /// Most the transaction pays per unit of gas, in wei.
+    pub max_fee_per_gas_wei: u128,
+}
+
+impl FeeEstimate {
+    /// Most the transaction can cost: gas times the max fee per gas.
+    pub fn cost(&self) -> Balance {
+        Balance {
+            wei: U256::from(self.gas) * U256::from(self.max_fee_per_gas_wei),
+        }
+    }
+
+    /// [`cost`](Self::cost) in US dollars at `eth_usd` dollars per ETH.
+    pub fn approx_usd(&self, eth_usd: f64) -> f64 {
+        const ETH: f64 = 1e18;
+        self.cost().wei.saturating_to::<u128>() as f64 / ETH * eth_usd
+    }
+
+    /// e.g. `"0.00000150 ETH (~$0.01; 150000 gas at up to 0.01 gwei)"`.
+    /// The dollar figure is left out when `eth_usd` is not positive.
+    pub fn display(&self, eth_usd: f64) -> String {
+        let usd = if eth_usd > 0.0 {
+            format!("{}; ", format_approx_usd(self.approx_usd(eth_usd)))
+        } else {
+            String::new()
+        };
+        format!(
+            "{} ({usd}{} gas at up to {} gwei)",
+            self.cost().display_eth_with(FEE_DECIMALS),
+            self.gas,
+            format_gwei(self.max_fee_per_gas_wei)
+        )
+    }
+}
+
+/// `~$1.23`, or `<$0.01` for anything that would round to zero cents.
+fn format_approx_usd(usd: f64) -> String {
+    if usd < 0.005 {
+        "<$0.01".to_string()
+    } else {
+        format!("~${usd:.2}")
+    }
+}
+
+/// `wei` in gwei, without trailing zeros: `10000000` is `"0.01"`.
+fn format_gwei(wei: u128) -> String {
+    const GWEI: u128 = 1_000_000_000;
+    let (whole, frac) = (wei / GWEI, wei % GWEI);
+    if frac == 0 {
+        return whole.to_string();
+    }
+    let frac = format!("{frac:09}");
+    format!("{whole}.{}", frac.trim_end_matches('0'))
+}
+
+// ---------------------------------------------------------------------------
+// AllowanceAction
+// ---------------------------------------------------------------------------
+
+/// How a spender's USDC allowance covers an amount, as decided by
+/// [`AllowanceAction::plan`] and carried out by
+/// [`ChainClient::ensure_usdc_allowance`]. Amounts are in USDC atomic units.
+///
+/// [`ChainClient::ensure_usdc_allowance`]: super::client::ChainClient::ensure_usdc_allowance
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum AllowanceAction {
+    /// The current allowance already covers the amount.
+    Sufficient { allowance: u64 },
+    /// The current allowance falls short, so `amount` is approved. An
+    /// approval replaces the allowance rather than adding to it.
+    Approve { allowance: u64, amount: u64 },
+}
+
+impl AllowanceAction {
+    /// Decide how to cover `needed` given the current `allowance`. A
+    /// shortfall is met by approving `needed`, or `cap` if that is larger,
+    /// so that later spending up to the cap needs no further approval.
+    pub fn plan(allowance: u64, needed: u64, cap: u64) -> Self {
+        if allowance >= needed {
+            Self::Sufficient { allowance }
+        } else {
+            Self::Approve {
+                allowance,
+                amount: needed.max(cap),
+            }
+        }
+    }
+
+    /// Whether an `approve` transaction is (or was) needed.
+    pub fn needs_approval(&self) -> bool {
+        matches!(self, Self::Approve { .. })
     }
 }
 
@@ -167,8 +409,25 @@ mod tests {
         assert_eq!(RequestStatus::from_u8(1), Some(RequestStatus::Responded));
         assert_eq!(RequestStatus::from_u8(2), Some(RequestStatus::Validated));
         assert_eq!(RequestStatus::from_u8(3), Some(RequestStatus::Claimed));
-        assert_eq!(RequestStatus::from_u8(4), Some(RequestStatus::Expired));
-        assert_eq!(RequestStatus::from_u8(5), Some(RequestStatus::Cancelled));
+        assert_eq!(RequestStatus::from_u8(4), Some(RequestStatus::Cancelled));
+        assert_eq!(RequestStatus::from_u8(5), Some(RequestStatus::Expired));
+    }
+
+    #[test]
+    fn request_status_from_u8_matches_contract_enum() {
+        use crate::chain::contracts::RequestRegistry::RequestStatus as Onchain;
+
+        let pairs = [
+            (Onchain::Open, RequestStatus::Open),
+            (Onchain::Responded, RequestStatus::Responded),
+            (Onchain::Validated, RequestStatus::Validated),
+            (Onchain::Claimed, RequestStatus::Claimed),
+            (Onchain::Cancelled, RequestStatus::Cancelled),
+            (Onchain::Expired, RequestStatus::Expired),
+        ];
+        for (onchain, expected) in pairs {
+            assert_eq!(RequestStatus::from_u8(onchain as u8), Some(expected));
+        }
     }
 
     #[test]
@@ -177,6 +436,71 @@ mod tests {
         assert_eq!(RequestStatus::from_u8(255), None);
     }
 
+    // -- Balance::display_eth_with / FeeEstimate ---------------------------
+
+    #[test]
+    fn display_eth_with_shows_small_fees() {
+        let fee = FeeEstimate {
+            gas: 150_000,
+            max_fee_per_gas_wei: 10_000_000, // 0.01 gwei
+        };
+        assert_eq!(fee.cost().wei, U256::from(1_500_000_000_000u64));
+        assert_eq!(fee.cost().display_eth(), "0.0000 ETH");
+        assert_eq!(fee.cost().display_eth_with(8), "0.00000150 ETH");
+        let balance = Balance {
+            wei: U256::from(1_500_000_000_000_000_000u128),
+        };
+        assert_eq!(balance.display_eth_with(18), "1.500000000000000000 ETH");
+    }
+
+    #[test]
+    fn fee_estimate_display_includes_dollars_and_gas() {
+        let fee = FeeEstimate {
+            gas: 200_000,
+            max_fee_per_gas_wei: 1_000_000_000, // 1 gwei
+        };
+        assert!((fee.approx_usd(3000.0) - 0.6).abs() < 1e-9);
+        assert_eq!(
+            fee.display(3000.0),
+            "0.00020000 ETH (~$0.60; 200000 gas at up to 1 gwei)"
+        );
+        assert_eq!(
+            fee.display(0.0),
+            "0.00020000 ETH (200000 gas at up to 1 gwei)",
+            "no conversion rate, no dollar figure"
+        );
+    }
+
+    #[test]
+    fn fee_estimate_display_of_tiny_fees() {
+        let fee = FeeEstimate {
+            gas: 150_000,
+            max_fee_per_gas_wei: 10_000_000,
+        };
+        assert_eq!(
+            fee.display(3000.0),
+            "0.00000150 ETH (<$0.01; 150000 gas at up to 0.01 gwei)"
+        );
+        let fee = FeeEstimate {
+            gas: 21_000,
+            max_fee_per_gas_wei: 1_234_567,
+        };
+        assert_eq!(
+            fee.display(2500.0),
+            "0.00000002 ETH (<$0.01; 21000 gas at up to 0.001234567 gwei)"
+        );
+    }
+
+    #[test]
+    fn fee_estimate_cost_does_not_overflow() {
+        let fee = FeeEstimate {
+            gas: u64::MAX,
+            max_fee_per_gas_wei: u128::MAX,
+        };
+        assert!(fee.cost().wei > U256::from(u128::MAX));
+        assert!(fee.approx_usd(3000.0).is_finite());
+    }
+
     // -- Balance::is_sufficient_for_registration --------------------------
 
     #[test]
@@ -242,6 +566,85 @@ mod tests {
         assert_eq!(balance.display_eth(), "1.5000 ETH");
     }
 
+    // -- Balances ---------------------------------------------------------
+
+    #[test]
+    fn balances_display_both_assets() {
+        let balances = Balances {
+            eth: Balance {
+                wei: U256::from(20_000_000_000_000_000u128), // 0.02 ETH
+            },
+            usdc: 14_500_000,
+        };
+        assert_eq!(balances.display_gas(), "Gas balance: 0.0200 ETH");
+        assert_eq!(balances.display_spendable(), "Spendable balance: $14.50");
+    }
+
+    #[test]
+    fn balances_display_zero() {
+        let balances = Balances {
+            eth: Balance { wei: U256::ZERO },
+            usdc: 0,
+        };
+        assert_eq!(balances.display_gas(), "Gas balance: 0.0000 ETH");
+        assert_eq!(balances.display_spendable(), "Spendable balance: $0.00");
+    }
+
+    // -- AllowanceAction::plan ---------------------------------------------
+
+    #[test]
+    fn allowance_plan_sufficient() {
+        assert_eq!(
+            AllowanceAction::plan(5_000_000, 5_000_000, 0),
+            AllowanceAction::Sufficient {
+                allowance: 5_000_000
+            }
+        );
+        let unlimited = AllowanceAction::plan(u64::MAX, 5_000_000, 100_000_000);
+        assert!(!unlimited.needs_approval());
+    }
+
+    #[test]
+    fn allowance_plan_zero_approves_exact_amount() {
+        let action = AllowanceAction::plan(0, 5_000_000, 0);
+        assert_eq!(
+            action,
+            AllowanceAction::Approve {
+                allowance: 0,
+                amount: 5_000_000
+            }
+        );
+        assert!(action.needs_approval());
+    }
+
+    #[test]
+    fn allowance_plan_partial_approves_up_to_cap() {
+        // An approval replaces the allowance, so the full amount is asked
+        // for, not just the shortfall.
+        assert_eq!(
+            AllowanceAction::plan(2_000_000, 5_000_000, 0),
+            AllowanceAction::Approve {
+                allowance: 2_000_000,
+                amount: 5_000_000
+            }
+        );
+        assert_eq!(
+            AllowanceAction::plan(2_000_000, 5_000_000, 50_000_000),
+            AllowanceAction::Approve {
+                allowance: 2_000_000,
+                amount: 50_000_000
+            }
+        );
+        // A cap below the amount needed is ignored.
+        assert_eq!(
+            AllowanceAction::plan(2_000_000, 5_000_000, 1_000_000),
+            AllowanceAction::Approve {
+                allowance: 2_000_000,
+                amount: 5_000_000
+            }
+        );
+    }
+
     // -- Display impls ----------------------------------------------------
 
     #[test]
@@ -0,0 +1,56 @@
+//! Wall-clock helpers shared across the crate.
+//!
+//! Timestamps are plain Unix seconds throughout (request deadlines, lock
+//! ages, log lines), so one function reads the clock and one renders spans
+//! of seconds for humans.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Current Unix timestamp in seconds; 0 if the clock is before 1970.
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Render a span of seconds with its two most significant units, e.g.
+/// `"3h 20m"`, `"2d"`, or `"45s"`.
+pub fn format_duration(secs: u64) -> String {
+    let (days, hours, minutes) = (secs / 86_400, secs / 3600 % 24, secs / 60 % 60);
+    let (major, minor) = match (days, hours, minutes) {
+        (0, 0, 0) => return format!("{secs}s"),
+        (0, 0, m) => return format!("{m}m"),
+        (0, h, m) => (format!("{h}h"), (m > 0).then(|| format!("{m}m"))),
+        (d, h, _) => (format!("{d}d"), (h > 0).then(|| format!("{h}h"))),
+    };
+    match minor {
+        Some(minor) => format!("{major} {minor}"),
+        None => major,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(0), "0s");
+        assert_eq!(format_duration(5), "5s");
+        assert_eq!(format_duration(125), "2m");
+        assert_eq!(format_duration(7_200), "2h");
+        assert_eq!(format_duration(7_300), "2h 1m");
+        assert_eq!(format_duration(2 * 86_400), "2d");
+        assert_eq!(format_duration(2 * 86_400 + 3 * 3600 + 59), "2d 3h");
+    }
+
+    #[test]
+    fn test_unix_now_is_after_2020() {
+        assert!(unix_now() > 1_577_836_800);
+    }
+}
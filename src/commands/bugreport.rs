@@ -0,0 +1,614 @@
+//! `agentmarket bugreport` — assemble a sanitized diagnostic archive.
+//!
+//! Collects everything usually asked for when triaging a failure (version,
+//! effective config, request state, recent logs, storage versions) into a
+//! single `.tar.gz`. Every artifact passes through [`redact`] before it is
+//! shown for review, and the finished artifacts are re-checked against the
+//! known secrets of this home: a leak aborts the report instead of writing it.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde_json::{json, Value};
+use tracing::debug;
+
+use super::doctor;
+use crate::clock::unix_now;
+use crate::config;
+use crate::engine::requests::{RequestCache, CURRENT_SCHEMA_VERSION};
+use crate::engine::validation;
+use crate::output::redact::{self, RedactionCounts, Redactor};
+use crate::output::{formatter, prompt};
+
+// ---------------------------------------------------------------------------
+// Constants
+// ---------------------------------------------------------------------------
+
+/// Directory inside the config directory holding structured log files.
+const LOG_DIR: &str = "logs";
+
+/// Trace file written when a command is run with tracing capture enabled.
+const TRACE_FILE: &str = "trace.log";
+
+/// Log lines included per log file.
+const LOG_TAIL_LINES: usize = 200;
+
+/// Log lines included per log file with `--verbose`.
+const VERBOSE_LOG_TAIL_LINES: usize = 1000;
+
+/// Top-level directory inside the archive.
+const ARCHIVE_ROOT: &str = "agentmarket-bugreport";
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+/// A single file destined for the archive, already redacted.
+#[derive(Clone, Debug)]
+pub struct Artifact {
+    /// Path inside the archive, relative to the archive root.
+    pub name: String,
+    /// Redacted contents.
+    pub contents: String,
+    /// Replacements made while redacting this file.
+    pub redactions: RedactionCounts,
+}
+
+/// What to include in a report.
+#[derive(Clone, Debug, Default)]
+pub struct ReportOptions {
+    /// Request to dump in detail. Without it only a status summary of the
+    /// cache is included (or every request, with `verbose`).
+    pub request_id: Option<String>,
+    /// Include every cached request and validation result, and longer log
+    /// tails. Redaction is identical either way.
+    pub verbose: bool,
+}
+
+// ---------------------------------------------------------------------------
+// Command
+// ---------------------------------------------------------------------------
+
+/// Run the `bugreport` command.
+pub async fn run(request_id: Option<String>, verbose: bool, output: Option<String>) -> Result<()> {
+    debug!(?request_id, verbose, "starting bugreport command");
+
+    // 1. Collect and redact artifacts
+    let opts = ReportOptions {
+        request_id,
+        verbose,
+    };
+    let redactor = seed_redactor()?;
+    let artifacts = collect(&opts, &redactor)?;
+
+    // 2. Refuse to continue if any known secret survived redaction
+    ensure_no_leaks(&redactor, &artifacts)?;
+
+    // 3. Review
+    let path = output
+        .map(PathBuf::from)
+        .unwrap_or_else(default_output_path);
+    print_review(&artifacts, &path);
+
+    let stdin = io::stdin();
+    if !prompt::confirm("Write bug report?", &mut stdin.lock())? {
+        formatter::print_warning("Bug report not written.");
+        return Ok(());
+    }
+
+    // 4. Write the archive
+    write_archive(&path, &artifacts)?;
+    formatter::print_success(&format!("Bug report written to {}", path.display()));
+    formatter::print_info("Attach this file to your issue. It contains no keys or passphrases.");
+
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Collection
+// ---------------------------------------------------------------------------
+
+/// Build a redactor seeded with every secret this home knows about: the
+/// keystore passphrase (if supplied without a prompt), the decrypted
+/// private key, the keystore's encrypted fields, and cached claim secrets.
+///
+/// Never prompts; secrets that cannot be obtained without the user are still
+/// caught by the rule table.
+pub fn seed_redactor() -> Result<Redactor> {
+    let mut redactor = Redactor::new();
+
+    if let Ok(Some(passphrase)) = config::keystore::preset_passphrase() {
+        redactor.add_known(&passphrase);
+        if config::keystore::exists()? {
+            if let Ok(key) = config::keystore::load_key(&passphrase) {
+                redactor.add_known(&hex::encode(&key));
+            }
+        }
+    }
+
+    let keystore_path = config::store::config_dir()?.join("keystore.enc");
+    if let Ok(contents) = fs::read_to_string(&keystore_path) {
+        if let Ok(Value::Object(fields)) = serde_json::from_str::<Value>(&contents) {
+            for value in fields.values() {
+                if let Value::String(s) = value {
+                    redactor.add_known(s);
+                }
+            }
+        }
+    }
+
+    for request in RequestCache::load_all().unwrap_or_default() {
+        if let Some(secret) = &request.secret {
+            redactor.add_known(secret);
+            redactor.add_known(secret.trim_start_matches("0x"));
+        }
+    }
+
+    Ok(redactor)
+}
+
+/// Collect and redact every artifact for the report.
+pub fn collect(opts: &ReportOptions, redactor: &Redactor) -> Result<Vec<Artifact>> {
+    let dir = config::store::config_dir()?;
+    let mut artifacts = vec![text_artifact(redactor, "version.txt", &version_info())];
+
+    // Effective config (file plus environment overrides).
+    if config::store::exists()? {
+        let cfg = config::store::load()?;
+        let value = serde_json::to_value(&cfg).context("failed to serialise config")?;
+        artifacts.push(json_artifact(redactor, "config.json", value));
+    }
+
+    artifacts.push(json_artifact(
+        redactor,
+        "schema.json",
+        schema_versions(&dir),
+    ));
+    artifacts.push(text_artifact(
+        redactor,
+        "doctor.txt",
+        &doctor::render(&doctor::local_checks()),
+    ));
+
+    // Request state.
+    let requests = RequestCache::load_all().unwrap_or_default();
+    let summary: BTreeMap<String, usize> = requests.iter().fold(BTreeMap::new(), |mut acc, r| {
+        *acc.entry(format!("{:?}", r.status)).or_default() += 1;
+        acc
+    });
+    artifacts.push(json_artifact(
+        redactor,
+        "requests/summary.json",
+        json!({ "total": requests.len(), "by_status": summary }),
+    ));
+
+    let selected: Vec<_> = match &opts.request_id {
+        Some(id) => {
+            let request = RequestCache::load(id)
+                .with_context(|| format!("no cached request with ID {id}"))?;
+            vec![request]
+        }
+        None if opts.verbose => requests,
+        None => Vec::new(),
+    };
+    for request in &selected {
+        let value = serde_json::to_value(request).context("failed to serialise request")?;
+        let name = format!("requests/{}.json", request.request_id);
+        artifacts.push(json_artifact(redactor, &name, value));
+
+        if let Ok(result) = validation::load_result(&request.request_id) {
+            let value = serde_json::to_value(&result).context("failed to serialise result")?;
+            let name = format!("validations/{}.json", request.request_id);
+            artifacts.push(json_artifact(redactor, &name, value));
+        }
+    }
+
+    // Logs and trace.
+    let tail = if opts.verbose {
+        VERBOSE_LOG_TAIL_LINES
+    } else {
+        LOG_TAIL_LINES
+    };
+    for log in log_files(&dir.join(LOG_DIR))? {
+        let contents = fs::read_to_string(&log)
+            .with_context(|| format!("failed to read log file: {}", log.display()))?;
+        let file_name = log.file_name().unwrap_or_default().to_string_lossy();
+        let name = format!("logs/{file_name}");
+        artifacts.push(text_artifact(redactor, &name, &tail_lines(&contents, tail)));
+    }
+
+    let trace = dir.join(TRACE_FILE);
+    if trace.exists() {
+        let contents = fs::read_to_string(&trace)
+            .with_context(|| format!("failed to read trace file: {}", trace.display()))?;
+        artifacts.push(text_artifact(redactor, TRACE_FILE, &contents));
+    }
+
+    debug!(count = artifacts.len(), "collected bug report artifacts");
+    Ok(artifacts)
+}
+
+/// Fail if any seeded secret appears in any artifact.
+pub fn ensure_no_leaks(redactor: &Redactor, artifacts: &[Artifact]) -> Result<()> {
+    for artifact in artifacts {
+        if !redactor.find_leaks(&artifact.contents).is_empty() {
+            bail!(
+                "Refusing to write bug report: {} still contains sensitive data after redaction. \
+                 Please report this without the archive.",
+                artifact.name
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Write artifacts into a gzip-compressed tarball at `path`.
+pub fn write_archive(path: &Path, artifacts: &[Artifact]) -> Result<()> {
+    let file = fs::File::create(path)
+        .with_context(|| format!("failed to create bug report: {}", path.display()))?;
+    let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+
+    for artifact in artifacts {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(artifact.contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(unix_now());
+        header.set_cksum();
+        builder
+            .append_data(
+                &mut header,
+                format!("{ARCHIVE_ROOT}/{}", artifact.name),
+                artifact.contents.as_bytes(),
+            )
+            .with_context(|| format!("failed to add {} to bug report", artifact.name))?;
+    }
+
+    builder
+        .into_inner()
+        .and_then(|gz| gz.finish())
+        .with_context(|| format!("failed to finish bug report: {}", path.display()))?;
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Review
+// ---------------------------------------------------------------------------
+
+/// Print every file that will be written and what was redacted from it.
+fn print_review(artifacts: &[Artifact], path: &Path) {
+    formatter::print_info(&format!(
+        "The bug report will contain {} files:",
+        artifacts.len()
+    ));
+    for artifact in artifacts {
+        let redactions = if artifact.redactions.is_empty() {
+            "no redactions".to_string()
+        } else {
+            artifact
+                .redactions
+                .iter()
+                .map(|(rule, n)| format!("{n} × {rule}"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        formatter::print_info(&format!(
+            "  {:<32} {:>8} bytes  ({redactions})",
+            artifact.name,
+            artifact.contents.len()
+        ));
+    }
+    formatter::print_info(&format!("Output: {}", path.display()));
+}
+
+// ---------------------------------------------------------------------------
+// Internal helpers
+// ---------------------------------------------------------------------------
+
+fn text_artifact(redactor: &Redactor, name: &str, contents: &str) -> Artifact {
+    let (contents, redactions) = redactor.redact_text(contents);
+    Artifact {
+        name: name.to_string(),
+        contents,
+        redactions,
+    }
+}
+
+fn json_artifact(redactor: &Redactor, name: &str, mut value: Value) -> Artifact {
+    let mut redactions = redactor.redact_json(&mut value);
+    let pretty = serde_json::to_string_pretty(&value).unwrap_or_default();
+    // Second pass over the serialised form catches anything the structural
+    // pass could not see (e.g. secrets split across keys and values).
+    let (contents, extra) = redactor.redact_text(&pretty);
+    redact::merge_counts(&mut redactions, extra);
+    Artifact {
+        name: name.to_string(),
+        contents,
+        redactions,
+    }
+}
+
+fn version_info() -> String {
+    format!(
+        "agentmarket {}\nprofile: {}\ntarget: {}-{}\nminimum rust: {}\n",
+        env!("CARGO_PKG_VERSION"),
+        if cfg!(debug_assertions) {
+            "debug"
+        } else {
+            "release"
+        },
+        std::env::consts::ARCH,
+        std::env::consts::OS,
+        env!("CARGO_PKG_RUST_VERSION"),
+    )
+}
+
+/// On-disk schema versions: request files by version, and the keystore
+/// format version.
+fn schema_versions(dir: &Path) -> Value {
+    let mut request_versions: BTreeMap<u64, usize> = BTreeMap::new();
+    if let Ok(entries) = fs::read_dir(dir.join("requests")) {
+        for entry in entries.flatten() {
+            let version = fs::read_to_string(entry.path())
+                .ok()
+                .and_then(|s| serde_json::from_str::<Value>(&s).ok())
+                .map(|v| v["schema_version"].as_u64().unwrap_or(0));
+            if let Some(version) = version {
+                *request_versions.entry(version).or_default() += 1;
+            }
+        }
+    }
+
+    let keystore_version = fs::read_to_string(dir.join("keystore.enc"))
+        .ok()
+        .and_then(|s| serde_json::from_str::<Value>(&s).ok())
+        .and_then(|v| v["version"].as_u64());
+
+    json!({
+        "requests": {
+            "current": CURRENT_SCHEMA_VERSION,
+            "on_disk": request_versions,
+        },
+        "keystore": keystore_version,
+    })
+}
+
+fn log_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("failed to read log directory: {}", dir.display()))?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+fn tail_lines(contents: &str, n: usize) -> String {
+    let lines: Vec<&str> = contents.lines().collect();
+    let start = lines.len().saturating_sub(n);
+    lines[start..].join("\n") + "\n"
+}
+
+fn default_output_path() -> PathBuf {
+    PathBuf::from(format!("agentmarket-bugreport-{}.tar.gz", unix_now()))
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::requests::{LocalRequest, LocalRequestStatus, RequestRole};
+    use std::env;
+    use std::io::Read;
+    use std::sync::Mutex;
+
+    /// Mutex to serialise tests that mutate environment variables.
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    const PASSPHRASE: &str = "planted-passphrase-7f3a";
+    const PRIVATE_KEY: [u8; 32] = [0x5a; 32];
+    const CLAIM_SECRET: &str = "0x9b1c0f8a2e7d46b3a5c8e1f20d3b4a5c6d7e8f9012345678abcdef0123456789";
+    const RPC_API_KEY: &str = "PlantedRpcApiKey42";
+
+    fn with_fixture_home<F: FnOnce(&Path)>(f: F) {
+        let _guard = ENV_LOCK.lock().expect("env lock poisoned");
+
+        let tmp = tempfile::tempdir().expect("failed to create temp dir");
+        let prev_home = env::var("AGENTMARKET_HOME").ok();
+        let prev_pass = env::var("AGENTMARKET_KEYSTORE_PASSPHRASE").ok();
+        env::set_var("AGENTMARKET_HOME", tmp.path());
+        env::set_var("AGENTMARKET_KEYSTORE_PASSPHRASE", PASSPHRASE);
+
+        plant_secrets(tmp.path());
+        f(tmp.path());
+
+        for (key, prev) in [
+            ("AGENTMARKET_HOME", prev_home),
+            ("AGENTMARKET_KEYSTORE_PASSPHRASE", prev_pass),
+        ] {
+            match prev {
+                Some(v) => env::set_var(key, v),
+                None => env::remove_var(key),
+            }
+        }
+    }
+
+    /// Plant the sensitive values in every place a careless report could
+    /// pick them up from.
+    fn plant_secrets(home: &Path) {
+        let key_hex = hex::encode(PRIVATE_KEY);
+
+        config::keystore::save_key(&PRIVATE_KEY, PASSPHRASE).expect("save key");
+
+        let mut cfg = config::store::Config::default();
+        cfg.agent.name = "fixture".to_string();
+        cfg.network.chain_rpc = format!("https://base.example.com/v2/{RPC_API_KEY}").into();
+        config::store::save(&cfg).expect("save config");
+
+        RequestCache::save(&LocalRequest {
+            schema_version: CURRENT_SCHEMA_VERSION,
+            request_id: "7".to_string(),
+            role: RequestRole::Seller,
+            status: LocalRequestStatus::Responded,
+            request_cid: "QmRequest".to_string(),
+            price_usdc: 5_000_000,
+            deadline: 2_000_000_000,
+            response_cid: Some("QmResponse".to_string()),
+            secret: Some(CLAIM_SECRET.to_string()),
+            secret_hash: Some(format!("0x{}", "ab".repeat(32))),
+            counterparty: Some("0x71C7656EC7ab88b098defB751B7401B5f6d8976F".to_string()),
+            created_at: 1_700_000_000,
+            updated_at: 1_700_000_100,
+            previous_status: Some(LocalRequestStatus::Open),
+            tags: Vec::new(),
+            task_summary: None,
+            capability: None,
+            trust_policy: None,
+            history: Vec::new(),
+            payment_check: None,
+            response_attempt: None,
+        })
+        .expect("save request");
+
+        let logs = home.join(LOG_DIR);
+        fs::create_dir_all(&logs).unwrap();
+        fs::write(
+            logs.join("agentmarket.log"),
+            format!(
+                "INFO respond request_id=7 secret={CLAIM_SECRET}\n\
+                 DEBUG unlocked with {PASSPHRASE}\n\
+                 DEBUG key bytes {key_hex}\n\
+                 INFO rpc https://base.example.com/v2/{RPC_API_KEY}\n"
+            ),
+        )
+        .unwrap();
+
+        fs::write(
+            home.join(TRACE_FILE),
+            format!("{{\"private_key\":\"0x{key_hex}\",\"passphrase\":\"{PASSPHRASE}\"}}\n"),
+        )
+        .unwrap();
+    }
+
+    fn forbidden_values() -> Vec<String> {
+        let key_hex = hex::encode(PRIVATE_KEY);
+        vec![
+            PASSPHRASE.to_string(),
+            key_hex.clone(),
+            key_hex.to_uppercase(),
+            CLAIM_SECRET.to_string(),
+            CLAIM_SECRET.trim_start_matches("0x").to_string(),
+            RPC_API_KEY.to_string(),
+        ]
+    }
+
+    fn read_archive(path: &Path) -> Vec<(String, String)> {
+        let file = fs::File::open(path).unwrap();
+        let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(file));
+        archive
+            .entries()
+            .unwrap()
+            .map(|entry| {
+                let mut entry = entry.unwrap();
+                let name = entry.path().unwrap().to_string_lossy().to_string();
+                let mut contents = String::new();
+                entry.read_to_string(&mut contents).unwrap();
+                (name, contents)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_archive_never_contains_planted_secrets() {
+        for verbose in [false, true] {
+            with_fixture_home(|home| {
+                let opts = ReportOptions {
+                    request_id: Some("7".to_string()),
+                    verbose,
+                };
+                let redactor = seed_redactor().expect("seed redactor");
+                let artifacts = collect(&opts, &redactor).expect("collect");
+                ensure_no_leaks(&redactor, &artifacts).expect("no leaks");
+
+                let path = home.join("report.tar.gz");
+                write_archive(&path, &artifacts).expect("write archive");
+
+                let entries = read_archive(&path);
+                let names: Vec<_> = entries.iter().map(|(n, _)| n.as_str()).collect();
+                for expected in [
+                    "version.txt",
+                    "config.json",
+                    "schema.json",
+                    "doctor.txt",
+                    "requests/7.json",
+                    "logs/agentmarket.log",
+                    "trace.log",
+                ] {
+                    let full = format!("{ARCHIVE_ROOT}/{expected}");
+                    assert!(names.contains(&full.as_str()), "missing {expected}");
+                }
+
+                for (name, contents) in &entries {
+                    for forbidden in forbidden_values() {
+                        assert!(
+                            !contents.contains(&forbidden),
+                            "{name} leaks {forbidden:?} (verbose={verbose})"
+                        );
+                    }
+                }
+            });
+        }
+    }
+
+    #[test]
+    fn test_request_dump_keeps_useful_state() {
+        with_fixture_home(|_| {
+            let redactor = seed_redactor().unwrap();
+            let opts = ReportOptions {
+                request_id: Some("7".to_string()),
+                verbose: false,
+            };
+            let artifacts = collect(&opts, &redactor).unwrap();
+            let dump = artifacts
+                .iter()
+                .find(|a| a.name == "requests/7.json")
+                .expect("request dump");
+
+            let value: Value = serde_json::from_str(&dump.contents).unwrap();
+            assert_eq!(value["status"], "Responded");
+            assert_eq!(value["secret"], "[REDACTED:secret]");
+            assert!(dump.redactions.contains_key("claim secret"));
+        });
+    }
+
+    #[test]
+    fn test_leak_check_rejects_unredacted_artifact() {
+        let mut redactor = Redactor::new();
+        redactor.add_known(PASSPHRASE);
+        let artifacts = vec![Artifact {
+            name: "oops.txt".to_string(),
+            contents: format!("pass {PASSPHRASE}"),
+            redactions: RedactionCounts::new(),
+        }];
+
+        let err = ensure_no_leaks(&redactor, &artifacts).unwrap_err();
+        assert!(err.to_string().contains("oops.txt"));
+    }
+
+    #[test]
+    fn test_tail_lines() {
+        assert_eq!(tail_lines("a\nb\nc\n", 2), "b\nc\n");
+        assert_eq!(tail_lines("a\n", 5), "a\n");
+    }
+}
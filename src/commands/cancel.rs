@@ -0,0 +1,292 @@
+//! The `cancel` command: withdraw an open request the buyer no longer wants.
+//!
+//! Only the buyer can cancel, and only while no seller has responded. The
+//! cancellation is simulated before the confirmation prompt, so one the
+//! registry would refuse stops with its reason. The request is marked
+//! cancelled locally only once the cancel transaction is confirmed. If the
+//! Request Registry contract is not yet deployed, only the local cache is
+//! updated.
+
+use alloy::primitives::{Address, U256};
+use anyhow::{anyhow, bail, Context, Result};
+use tracing::debug;
+
+use super::CommandContext;
+use crate::chain::client::ChainClient;
+use crate::chain::contracts::{Contract, RequestRegistry};
+use crate::chain::types::RequestStatus;
+use crate::config::lock;
+use crate::engine::requests::{LocalRequest, LocalRequestStatus, RequestCache, RequestRole};
+use crate::output::{formatter, prompt};
+
+pub async fn run(request_id: String) -> Result<()> {
+    debug!(request_id = %request_id, "starting cancel command");
+
+    // 1. Load config, verify registered.
+    let ctx = CommandContext::load_registered()?;
+
+    // 2. Lock the request so a concurrent respond or sweep cannot race the
+    //    cancellation, then load it from local cache.
+    let _request_lock = lock::acquire(&lock::request_lock(&request_id), "cancel")?;
+    if !RequestCache::contains(&request_id)? {
+        bail!(
+            "Request {request_id} is not tracked on this machine. \
+             Only requests you created can be cancelled."
+        );
+    }
+    let request = RequestCache::load(&request_id)?;
+
+    debug!(
+        request_id = %request.request_id,
+        status = ?request.status,
+        role = ?request.role,
+        "request loaded from cache"
+    );
+
+    // 3. Verify we are the buyer and the request is still open.
+    if !check_cancellable(&request)? {
+        formatter::print_info(&format!("Request {request_id} is already cancelled."));
+        return Ok(());
+    }
+
+    let onchain = if ctx.contracts.request_registry.is_zero() {
+        None
+    } else {
+        let client = ChainClient::from_config(&ctx.cfg).await?;
+        let from: Address = ctx
+            .address
+            .parse()
+            .context("failed to parse agent address")?;
+        let id: U256 = request_id
+            .parse()
+            .with_context(|| format!("{request_id} is not an on-chain request ID"))?;
+        Some((client, from, id))
+    };
+
+    if let Some((client, from, id)) = &onchain {
+        // A cancel an earlier run sent but did not see confirmed only needs
+        // recording.
+        let stored = client
+            .get_request(ctx.contracts.request_registry, *id)
+            .await?;
+        if stored.and_then(|r| r.status) == Some(RequestStatus::Cancelled) {
+            RequestCache::update(&request_id, |r| {
+                r.transition_with_note(
+                    LocalRequestStatus::Cancelled,
+                    Some("cancelled on-chain by an earlier run".to_string()),
+                )
+            })?;
+            formatter::print_success(&format!("Request {request_id} cancelled."));
+            return Ok(());
+        }
+        client
+            .simulate(
+                *from,
+                ctx.contracts.request_registry,
+                &RequestRegistry::cancelCall { requestId: *id },
+            )
+            .await
+            .map_err(|err| explain_refusal(&request_id, err))?;
+        let estimate = super::estimate_fee(
+            "cancel",
+            client.estimate_cancel_cost(ctx.contracts.request_registry, *from, *id),
+        )
+        .await?;
+        super::print_fee(estimate.as_ref(), ctx.cfg.fees.eth_usd);
+    }
+
+    prompt::confirm_funds(&format!(
+        "About to withdraw request {request_id} and release its {} payment",
+        formatter::format_price(request.price_usdc)
+    ))?;
+
+    // 4. Contract deployment gate: check if the Request Registry is deployed.
+    match &onchain {
+        None => formatter::print_warning(&format!(
+            "The request registry contract is not yet deployed on {}. \
+             The request is withdrawn on this machine only. {}",
+            ctx.contracts.network,
+            Contract::RequestRegistry.setup_hint()
+        )),
+        Some((client, _, id)) => {
+            // 5. Contract is deployed — send the cancel transaction and wait
+            //    for it to be confirmed before the cache is touched.
+            formatter::print_info("Cancelling request...");
+            let signer = ctx.transaction_signer()?;
+            let confirmed = async {
+                let tx_hash = client
+                    .cancel(ctx.contracts.request_registry, &signer, *id, |quote| {
+                        super::confirm_fee_over_cap("cancel", quote)
+                    })
+                    .await?;
+                super::await_confirmation(client, &ctx.cfg.network, tx_hash, "cancel").await
+            }
+            .await
+            .map_err(|err| explain_refusal(&request_id, err))?;
+            if !confirmed {
+                return Ok(());
+            }
+        }
+    }
+
+    // 6. Update local request cache status to Cancelled.
+    RequestCache::update(&request_id, |r| r.transition(LocalRequestStatus::Cancelled))?;
+    debug!(request_id = %request_id, "local cache updated to Cancelled");
+
+    formatter::print_success(&format!("Request {request_id} cancelled."));
+    Ok(())
+}
+
+/// Restate the registry's refusal to cancel `request_id` in plain words,
+/// whether it gave a reason string or one of its custom errors (as
+/// described by [`receipt::decode_revert`]). Other errors are returned as
+/// they are.
+///
+/// [`receipt::decode_revert`]: crate::chain::receipt::decode_revert
+fn explain_refusal(request_id: &str, err: anyhow::Error) -> anyhow::Error {
+    let message = format!("{err:#}");
+    let refused = |reasons: &[&str]| reasons.iter().any(|r| message.contains(r));
+    if refused(&[
+        "Can only cancel open requests",
+        "already responded to",
+        "is not open",
+    ]) {
+        anyhow!(
+            "Request {request_id} already has a response or is closed, \
+             and can no longer be withdrawn."
+        )
+    } else if refused(&["Only buyer can cancel", "is not the buyer"]) {
+        anyhow!("Only the buyer can cancel request {request_id}.")
+    } else {
+        err
+    }
+}
+
+/// Check that `request` may be cancelled by this agent.
+///
+/// Returns `Ok(false)` if it is already cancelled (nothing to do) and an
+/// error explaining why if it cannot be cancelled.
+fn check_cancellable(request: &LocalRequest) -> Result<bool> {
+    let request_id = &request.request_id;
+
+    if request.role != RequestRole::Buyer {
+        bail!(
+            "You did not create request {request_id}. \
+             Only the buyer can cancel a request."
+        );
+    }
+
+    match request.status {
+        LocalRequestStatus::Open => Ok(true),
+        LocalRequestStatus::Cancelled => Ok(false),
+        LocalRequestStatus::Responded | LocalRequestStatus::Validated => {
+            bail!("Request {request_id} already has a response and can no longer be withdrawn.")
+        }
+        LocalRequestStatus::Claimed => {
+            bail!("Request {request_id} has been paid and can no longer be withdrawn.")
+        }
+        LocalRequestStatus::Expired => {
+            bail!("Request {request_id} has expired; nothing to cancel.")
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(role: RequestRole, status: LocalRequestStatus) -> LocalRequest {
+        LocalRequest {
+            schema_version: 0,
+            request_id: "42".to_string(),
+            role,
+            status,
+            request_cid: String::new(),
+            price_usdc: 5_000_000,
+            deadline: 10_000,
+            response_cid: None,
+            secret: None,
+            secret_hash: None,
+            counterparty: None,
+            created_at: 1_000,
+            updated_at: 1_000,
+            previous_status: None,
+            tags: Vec::new(),
+            task_summary: None,
+            capability: None,
+            trust_policy: None,
+            history: Vec::new(),
+            payment_check: None,
+            response_attempt: None,
+        }
+    }
+
+    #[test]
+    fn test_only_open_buyer_requests_are_cancellable() {
+        assert!(check_cancellable(&request(RequestRole::Buyer, LocalRequestStatus::Open)).unwrap());
+        assert!(
+            !check_cancellable(&request(RequestRole::Buyer, LocalRequestStatus::Cancelled))
+                .unwrap()
+        );
+
+        let err = check_cancellable(&request(RequestRole::Seller, LocalRequestStatus::Open))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Only the buyer"), "{err}");
+
+        for status in [
+            LocalRequestStatus::Responded,
+            LocalRequestStatus::Validated,
+            LocalRequestStatus::Claimed,
+            LocalRequestStatus::Expired,
+        ] {
+            assert!(
+                check_cancellable(&request(RequestRole::Buyer, status.clone())).is_err(),
+                "{status:?} should not be cancellable"
+            );
+        }
+    }
+
+    #[test]
+    fn test_registry_refusals_are_explained() {
+        let err = explain_refusal(
+            "42",
+            anyhow!(
+                "the transaction would be rejected by the contract: Can only cancel open requests"
+            ),
+        );
+        assert_eq!(
+            err.to_string(),
+            "Request 42 already has a response or is closed, and can no longer be withdrawn."
+        );
+        let err = explain_refusal(
+            "42",
+            anyhow!("the transaction would be rejected by the contract: request 42 already responded to"),
+        );
+        assert!(
+            err.to_string().contains("can no longer be withdrawn"),
+            "{err}"
+        );
+        let err = explain_refusal("42", anyhow!("reverted: Only buyer can cancel"));
+        assert_eq!(err.to_string(), "Only the buyer can cancel request 42.");
+
+        let err = explain_refusal("42", anyhow!("connection refused"));
+        assert_eq!(err.to_string(), "connection refused");
+    }
+
+    #[test]
+    fn test_open_request_transitions_to_cancelled() {
+        let mut open = request(RequestRole::Buyer, LocalRequestStatus::Open);
+        open.transition(LocalRequestStatus::Cancelled).unwrap();
+        assert_eq!(open.status, LocalRequestStatus::Cancelled);
+        assert_eq!(open.previous_status, Some(LocalRequestStatus::Open));
+        assert_eq!(open.history.len(), 1);
+
+        let mut responded = request(RequestRole::Buyer, LocalRequestStatus::Responded);
+        assert!(responded.transition(LocalRequestStatus::Cancelled).is_err());
+    }
+}
@@ -2,22 +2,48 @@
 //!
 //! Reveals the secret S on-chain, which atomically verifies `keccak256(S)`
 //! against the stored hash and triggers `USDC.transferFrom()` to pay the
-//! seller and validator. If the Request Registry contract is not yet
-//! deployed, the local cache is updated and payment settlement is deferred.
+//! seller and validator. The request is marked claimed locally only once
+//! the claim transaction is confirmed. If the Request Registry contract is
+//! not yet deployed, the local cache is updated and payment settlement is
+//! deferred.
+//!
+//! Before anything is sent, the secret is checked against the hash lock
+//! stored locally and, when the registry is deployed, the one in the
+//! registry's copy of the response: a claim with the wrong secret reverts
+//! and wastes gas. The claim itself is then simulated, so one the registry
+//! would refuse for any other reason stops with that reason instead.
+//!
+//! With `--all`, every validated request this agent responded to is claimed
+//! in turn with one key unlock and one confirmation; a failure on one
+//! request does not stop the rest.
 
-use alloy::primitives::Address;
+use alloy::primitives::{Address, B256, U256};
 use anyhow::{bail, Context, Result};
+use serde::Serialize;
 use tracing::debug;
 
 use super::CommandContext;
 use crate::chain::client::ChainClient;
-use crate::chain::contracts::addresses;
-use crate::chain::types::Balance;
-use crate::engine::requests::{format_price_usd, LocalRequestStatus, RequestCache, RequestRole};
-use crate::output::formatter;
+use crate::chain::contracts::{Contract, RequestRegistry};
+use crate::chain::types::{Balance, FeeEstimate, RequestStatus};
+use crate::config::lock;
+use crate::engine::reputation::format_earnings_usd;
+use crate::engine::requests::{
+    verify_secret, LocalRequest, LocalRequestStatus, RequestCache, RequestRole,
+};
+use crate::output::{formatter, prompt};
 
-pub async fn run(request_id: String) -> Result<()> {
-    debug!(request_id = %request_id, "starting claim command");
+/// Outcome of claiming one request with `--all`.
+#[derive(Debug, Serialize)]
+struct ClaimOutcome {
+    request_id: String,
+    price_usdc: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+pub async fn run(request_id: Option<String>, all: bool) -> Result<()> {
+    debug!(?request_id, all, "starting claim command");
 
     // 1. Load config, verify registered, derive address.
     let ctx = CommandContext::load_registered()?;
@@ -25,7 +51,7 @@ pub async fn run(request_id: String) -> Result<()> {
     debug!(address = %ctx.address, "agent address derived");
 
     // 2. Check ETH balance — bail if insufficient for gas.
-    let client = ChainClient::new(&ctx.cfg.network.chain_rpc).await?;
+    let client = ChainClient::from_config(&ctx.cfg).await?;
     let addr: Address = ctx
         .address
         .parse()
@@ -41,8 +67,320 @@ pub async fn run(request_id: String) -> Result<()> {
         bail!("Insufficient funds. Send ETH to your agent address and try again.");
     }
 
-    // 3. Load the request from local cache.
-    let mut request = match RequestCache::load(&request_id) {
+    let Some(request_id) = request_id.filter(|_| !all) else {
+        return claim_all(&ctx, &client).await;
+    };
+
+    // 3. Lock the request so no other process works on it concurrently, then
+    //    load it and check it can be claimed.
+    let _request_lock = lock::acquire(&lock::request_lock(&request_id), "claim")?;
+    let Some(request) = load_claimable(&request_id)? else {
+        formatter::print_info(&format!("Request {request_id} has already been claimed."));
+        return Ok(());
+    };
+
+    // 6. Retrieve the secret S from local cache, re-deriving it from our key
+    //    if it was lost.
+    let secret = match &request.secret {
+        Some(s) if !s.is_empty() => s.clone(),
+        _ => recover_secret(&ctx, &request)?,
+    };
+
+    debug!("secret retrieved");
+    check_hash_lock(&client, ctx.contracts.request_registry, &request, &secret).await?;
+
+    if !ctx.contracts.request_registry.is_zero() {
+        if claimed_onchain(&client, &request).await? {
+            settle(&request)?;
+            formatter::print_success(&format!(
+                "Request {request_id} is already claimed on-chain. Recorded it locally."
+            ));
+            return Ok(());
+        }
+        simulate_claim(&client, addr, &request, &secret).await?;
+        let estimate =
+            super::estimate_fee("claim", claim_cost(&client, addr, &request, &secret)).await?;
+        super::print_fee(estimate.as_ref(), ctx.cfg.fees.eth_usd);
+    }
+    prompt::confirm_funds(&format!(
+        "About to claim {} for request {request_id}",
+        formatter::format_price(request.price_usdc)
+    ))?;
+
+    // 7. Contract deployment gate: check if the Request Registry is deployed.
+    if ctx.contracts.request_registry.is_zero() {
+        formatter::print_warning(&format!(
+            "The request registry contract is not yet deployed on {}. \
+             On-chain settlement will be available after deployment. {}",
+            ctx.contracts.network,
+            Contract::RequestRegistry.setup_hint()
+        ));
+        formatter::print_info("Updating local status to reflect successful claim.");
+
+        settle(&request)?;
+
+        let earned = formatter::format_price(request.price_usdc);
+        formatter::print_success(&format!("Earned {earned} for request {request_id}."));
+        formatter::print_info("Payment will be settled on-chain once the contract is deployed.");
+
+        return Ok(());
+    }
+
+    // If the buyer's payment was not secured when we responded, say so up
+    // front: it is the most likely reason for the claim to fail.
+    if let Some(check) = request.payment_check.as_ref().filter(|c| !c.is_secured()) {
+        formatter::print_warning(&format!(
+            "When you responded, the buyer's payment was not secured. {} \
+             If this claim fails, that is the likely cause.",
+            check.describe()
+        ));
+    }
+
+    formatter::print_info("Submitting claim...");
+
+    // 8-9. Send the claim transaction and, once it is confirmed, update the
+    //      local cache. An unconfirmed claim leaves the request Validated.
+    if !send_claim(&ctx, &client, &request, &secret).await? {
+        return Ok(());
+    }
+    settle(&request)?;
+
+    // 10. Display success with payment details (zero-crypto UX).
+    let earned = formatter::format_price(request.price_usdc);
+    formatter::print_success(&format!("Earned {earned} for request {request_id}."));
+
+    Ok(())
+}
+
+/// `--all`: claim every validated request we responded to, carrying on
+/// past failures.
+async fn claim_all(ctx: &CommandContext, client: &ChainClient) -> Result<()> {
+    let mut candidates: Vec<LocalRequest> =
+        RequestCache::load_by_status(LocalRequestStatus::Validated)?
+            .into_iter()
+            .filter(|r| r.role == RequestRole::Seller)
+            .collect();
+    candidates.sort_by(|a, b| a.request_id.cmp(&b.request_id));
+    debug!(count = candidates.len(), "validated requests found");
+
+    if candidates.is_empty() {
+        if formatter::is_json_mode() {
+            formatter::print_json::<[ClaimOutcome]>(&[])?;
+        } else {
+            formatter::print_info("No validated requests to claim.");
+        }
+        return Ok(());
+    }
+
+    let total: u64 = candidates.iter().map(|r| r.price_usdc).sum();
+    if !ctx.contracts.request_registry.is_zero() {
+        // Claims cost about the same each, so one estimate stands for all.
+        let first = &candidates[0];
+        let from: Address = ctx
+            .address
+            .parse()
+            .context("failed to parse agent address")?;
+        let estimate = super::estimate_fee("claim", async {
+            let secret = match &first.secret {
+                Some(s) if !s.is_empty() => s.clone(),
+                _ => recover_secret(ctx, first)?,
+            };
+            claim_cost(client, from, first, &secret).await
+        })
+        .await?;
+        formatter::print_info(&format!(
+            "Network fee: {} per claim (paid from your ETH balance)",
+            super::describe_fee(estimate.as_ref(), ctx.cfg.fees.eth_usd)
+        ));
+    }
+    prompt::confirm_funds(&format!(
+        "About to claim {} for {} validated request(s)",
+        formatter::format_price(total),
+        candidates.len()
+    ))?;
+    if ctx.contracts.request_registry.is_zero() {
+        formatter::print_warning(&format!(
+            "The request registry contract is not yet deployed on {}. \
+             Requests are marked claimed on this machine only. {}",
+            ctx.contracts.network,
+            Contract::RequestRegistry.setup_hint()
+        ));
+    }
+
+    let mut outcomes = Vec::with_capacity(candidates.len());
+    for request in candidates {
+        let error = claim_in_batch(ctx, client, &request.request_id)
+            .await
+            .err()
+            .map(|e| formatter::format_error(&e));
+        outcomes.push(ClaimOutcome {
+            request_id: request.request_id,
+            price_usdc: request.price_usdc,
+            error,
+        });
+    }
+    let failed = outcomes.iter().filter(|o| o.error.is_some()).count();
+    let claimed: u64 = outcomes
+        .iter()
+        .filter(|o| o.error.is_none())
+        .map(|o| o.price_usdc)
+        .sum();
+
+    if formatter::is_json_mode() {
+        formatter::print_json(&outcomes)?;
+    } else {
+        for outcome in &outcomes {
+            match &outcome.error {
+                None => formatter::print_success(&format!(
+                    "{}: claimed {}",
+                    outcome.request_id,
+                    formatter::format_price(outcome.price_usdc)
+                )),
+                Some(error) => {
+                    formatter::print_warning(&format!("{}: {error}", outcome.request_id))
+                }
+            }
+        }
+        formatter::print_info(&format!(
+            "Claimed {} from {} of {} request(s).",
+            format_earnings_usd(claimed),
+            outcomes.len() - failed,
+            outcomes.len()
+        ));
+    }
+
+    if failed > 0 {
+        bail!("{failed} request(s) could not be claimed.");
+    }
+    Ok(())
+}
+
+/// Claim `request_id` as part of `--all`, after the batch was confirmed.
+async fn claim_in_batch(
+    ctx: &CommandContext,
+    client: &ChainClient,
+    request_id: &str,
+) -> Result<()> {
+    let _request_lock = lock::acquire(&lock::request_lock(request_id), "claim")?;
+    let Some(request) = load_claimable(request_id)? else {
+        bail!("Request {request_id} was claimed by another process.");
+    };
+    let secret = match &request.secret {
+        Some(s) if !s.is_empty() => s.clone(),
+        _ => recover_secret(ctx, &request)?,
+    };
+    check_hash_lock(client, ctx.contracts.request_registry, &request, &secret).await?;
+    if !ctx.contracts.request_registry.is_zero() && !claimed_onchain(client, &request).await? {
+        let from: Address = ctx
+            .address
+            .parse()
+            .context("failed to parse agent address")?;
+        simulate_claim(client, from, &request, &secret).await?;
+        if !send_claim(ctx, client, &request, &secret).await? {
+            bail!("The claim transaction was sent but is not confirmed yet.");
+        }
+    }
+    settle(&request)
+}
+
+/// Estimate the fee for claiming `request` by revealing `secret`.
+async fn claim_cost(
+    client: &ChainClient,
+    from: Address,
+    request: &LocalRequest,
+    secret: &str,
+) -> Result<FeeEstimate> {
+    let call = claim_call(request, secret)?;
+    client
+        .estimate_claim_cost(
+            client.contracts().request_registry,
+            from,
+            call.requestId,
+            call.secret,
+        )
+        .await
+}
+
+/// Run the claim of `request` with `secret` from `from` without sending
+/// it, failing with the registry's reason if it would revert.
+async fn simulate_claim(
+    client: &ChainClient,
+    from: Address,
+    request: &LocalRequest,
+    secret: &str,
+) -> Result<()> {
+    let call = claim_call(request, secret)?;
+    client
+        .simulate(from, client.contracts().request_registry, &call)
+        .await
+}
+
+/// The registry call claiming `request` by revealing `secret`.
+fn claim_call(request: &LocalRequest, secret: &str) -> Result<RequestRegistry::claimCall> {
+    let request_id = &request.request_id;
+    let id: U256 = request_id
+        .parse()
+        .with_context(|| format!("{request_id} is not an on-chain request ID"))?;
+    let secret: B256 = secret
+        .parse()
+        .context("the stored claim preimage is not a 32-byte hex value")?;
+    Ok(RequestRegistry::claimCall {
+        requestId: id,
+        secret,
+    })
+}
+
+/// Refuse to claim `request` unless `secret` hashes to the hash lock
+/// recorded locally and, when `registry` is deployed, the one in the
+/// registry's response.
+///
+/// The messages avoid the word "secret" so the error formatter keeps
+/// which comparison failed.
+async fn check_hash_lock(
+    client: &ChainClient,
+    registry: Address,
+    request: &LocalRequest,
+    secret: &str,
+) -> Result<()> {
+    let request_id = &request.request_id;
+    if let Some(hash) = request.secret_hash.as_deref() {
+        if let Err(err) = verify_secret(secret, hash) {
+            debug!(%request_id, error = %err, "local hash lock check failed");
+            bail!(
+                "Not claiming request {request_id}: the stored claim preimage does not match \
+                 the hash lock recorded locally. Sending it would revert and waste gas."
+            );
+        }
+    }
+
+    if registry == Address::ZERO {
+        return Ok(());
+    }
+    let Ok(id) = request_id.parse::<U256>() else {
+        debug!(%request_id, "not an on-chain request ID -- skipping registry hash check");
+        return Ok(());
+    };
+    match client.get_response(registry, id).await? {
+        Some(response) => {
+            let hash = format!("0x{}", hex::encode(response.secret_hash));
+            if let Err(err) = verify_secret(secret, &hash) {
+                debug!(%request_id, error = %err, "registry hash lock check failed");
+                bail!(
+                    "Not claiming request {request_id}: the stored claim preimage does not \
+                     match the hash lock published on-chain ({hash}). Sending it would \
+                     revert and waste gas."
+                );
+            }
+        }
+        None => debug!(%request_id, "no response in the registry -- skipping its hash check"),
+    }
+    Ok(())
+}
+
+/// Load `request_id` and check we can claim it. Returns `Ok(None)` if it
+/// has already been claimed.
+fn load_claimable(request_id: &str) -> Result<Option<LocalRequest>> {
+    let request = match RequestCache::load(request_id) {
         Ok(r) => r,
         Err(_) => {
             bail!(
@@ -68,113 +406,314 @@ pub async fn run(request_id: String) -> Result<()> {
     }
 
     // 5. Verify the request is in Validated status.
-    if request.status != LocalRequestStatus::Validated {
-        match request.status {
-            LocalRequestStatus::Claimed => {
-                formatter::print_info(&format!("Request {request_id} has already been claimed."));
-                return Ok(());
-            }
-            LocalRequestStatus::Open => {
-                bail!(
-                    "Request {request_id} has not been responded to yet. \
-                     A response must be submitted and validated before claiming."
-                );
-            }
-            LocalRequestStatus::Responded => {
-                bail!(
-                    "Request {request_id} is awaiting validation. \
-                     The response must be validated before you can claim payment."
-                );
-            }
-            LocalRequestStatus::Cancelled => {
-                bail!("Request {request_id} was cancelled. Payment cannot be claimed.");
-            }
-            LocalRequestStatus::Expired => {
-                bail!("Request {request_id} has expired. Payment cannot be claimed.");
-            }
-            _ => {
-                bail!(
-                    "Request {request_id} is not in a claimable state (current: {:?}).",
-                    request.status
-                );
-            }
+    match request.status {
+        LocalRequestStatus::Validated => Ok(Some(request)),
+        LocalRequestStatus::Claimed => Ok(None),
+        LocalRequestStatus::Open => {
+            bail!(
+                "Request {request_id} has not been responded to yet. \
+                 A response must be submitted and validated before claiming."
+            );
         }
-    }
-
-    // 6. Retrieve the secret S from local cache.
-    let secret = match &request.secret {
-        Some(s) if !s.is_empty() => s.clone(),
-        _ => {
+        LocalRequestStatus::Responded => {
             bail!(
-                "CRITICAL: Secret for request {request_id} is missing from local cache. \
-                 The secret is required to claim payment. This should never happen — \
-                 your local data may be corrupted. Contact support."
+                "Request {request_id} is awaiting validation. \
+                 The response must be validated before you can claim payment."
             );
         }
+        LocalRequestStatus::Cancelled => {
+            bail!("Request {request_id} was cancelled. Payment cannot be claimed.");
+        }
+        LocalRequestStatus::Expired => {
+            bail!("Request {request_id} has expired. Payment cannot be claimed.");
+        }
+    }
+}
+
+/// Whether the registry already shows `request` as claimed, by an earlier
+/// claim whose confirmation was not waited for, say.
+async fn claimed_onchain(client: &ChainClient, request: &LocalRequest) -> Result<bool> {
+    let Ok(id) = request.request_id.parse::<U256>() else {
+        return Ok(false);
     };
+    let onchain = client
+        .get_request(client.contracts().request_registry, id)
+        .await?;
+    Ok(onchain.and_then(|r| r.status) == Some(RequestStatus::Claimed))
+}
+
+/// Claim `request` on-chain by revealing `secret`, and wait for the claim
+/// to be confirmed. Returns whether it was; `false` if the transaction is
+/// still pending at the receipt timeout. A fee over the cap is confirmed
+/// with the user first.
+async fn send_claim(
+    ctx: &CommandContext,
+    client: &ChainClient,
+    request: &LocalRequest,
+    secret: &str,
+) -> Result<bool> {
+    let call = claim_call(request, secret)?;
+    let signer = ctx.transaction_signer()?;
+    let tx_hash = client
+        .claim(
+            ctx.contracts.require(Contract::RequestRegistry)?,
+            &signer,
+            call.requestId,
+            call.secret,
+            |quote| super::confirm_fee_over_cap("claim", quote),
+        )
+        .await?;
+    super::await_confirmation(client, &ctx.cfg.network, tx_hash, "claim").await
+}
 
-    debug!("secret retrieved from local cache");
+/// Mark `request` Claimed locally, once its claim is confirmed or when the
+/// registry is not yet deployed.
+fn settle(request: &LocalRequest) -> Result<()> {
+    let request_id = &request.request_id;
+    RequestCache::update(request_id, |r| r.transition(LocalRequestStatus::Claimed))?;
+    debug!(request_id = %request_id, "local cache updated to Claimed");
+    Ok(())
+}
 
-    // 7. Contract deployment gate: check if REQUEST_REGISTRY is deployed.
-    if addresses::REQUEST_REGISTRY == Address::ZERO {
-        formatter::print_warning(
-            "The request registry contract is not yet deployed. \
-             On-chain settlement will be available after deployment.",
-        );
-        formatter::print_info("Updating local status to reflect successful claim.");
+/// Re-derive the secret for `request` from our key after it went missing
+/// from the cache, verify it against the published hash, and save it back.
+fn recover_secret(ctx: &CommandContext, request: &LocalRequest) -> Result<String> {
+    let request_id = &request.request_id;
+    let (secret, hash) = ctx.derive_secret(request_id);
 
-        // Update local cache status to Claimed.
-        request.status = LocalRequestStatus::Claimed;
-        request.updated_at = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_secs();
-        RequestCache::save(&request)?;
+    match request.secret_hash.as_deref() {
+        Some(published) if !published.eq_ignore_ascii_case(&hash) => bail!(
+            "The secret for request {request_id} is missing from local cache and cannot be \
+             re-derived: the response used a random secret (--random-secret). \
+             Restore your agent data from a backup to claim payment."
+        ),
+        Some(_) => debug!(request_id = %request_id, "re-derived secret matches published hash"),
+        None => formatter::print_warning(
+            "No secret hash is recorded for this request; using the secret derived from your key.",
+        ),
+    }
 
-        let earned = format_price_usd(request.price_usdc);
-        formatter::print_success(&format!("Earned {earned} for request {request_id}."));
-        formatter::print_info("Payment will be settled on-chain once the contract is deployed.");
+    RequestCache::update(request_id, |r| {
+        r.secret = Some(secret.clone());
+        r.secret_hash.get_or_insert_with(|| hash.clone());
+        Ok(())
+    })
+    .context("Failed to save the recovered secret to local cache.")?;
 
-        return Ok(());
+    formatter::print_info(&format!(
+        "Recovered the claim secret for request {request_id} from your key."
+    ));
+    Ok(secret)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::sync::Mutex;
+
+    /// Serialises tests that point `AGENTMARKET_HOME` at a temp directory.
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    fn with_temp_home<F: FnOnce()>(f: F) {
+        let _guard = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let tmp = tempfile::tempdir().unwrap();
+        let prev = env::var("AGENTMARKET_HOME").ok();
+        env::set_var("AGENTMARKET_HOME", tmp.path());
+        f();
+        match prev {
+            Some(v) => env::set_var("AGENTMARKET_HOME", v),
+            None => env::remove_var("AGENTMARKET_HOME"),
+        }
     }
 
-    // 8. Contract is deployed — send claim transaction.
-    // TODO: Once alloy provider-with-signer integration is complete,
-    // send the actual claim(requestId, secret) transaction here:
-    //   let signer = TransactionSigner::from_keystore_with_passphrase(&passphrase)?;
-    //   let provider = ProviderBuilder::new()
-    //       .signer(signer.inner().clone())
-    //       .on_http(cfg.network.chain_rpc.parse()?);
-    //   let registry = RequestRegistry::new(addresses::REQUEST_REGISTRY, provider);
-    //   let secret_bytes: B256 = hex::decode(&secret)?.try_into()?;
-    //   let request_id_u256 = U256::from_str(&request_id)?;
-    //   let receipt = registry.claim(request_id_u256, secret_bytes)
-    //       .send().await?.get_receipt().await?;
+    fn save(id: &str, status: LocalRequestStatus, role: RequestRole) {
+        let mut request: LocalRequest = serde_json::from_value(serde_json::json!({
+            "request_id": id,
+            "role": "Seller",
+            "status": "Open",
+            "request_cid": "QmTestCid123",
+            "price_usdc": 5_000_000,
+            "deadline": 1_700_000_000,
+            "response_cid": null,
+            "secret": null,
+            "secret_hash": null,
+            "counterparty": null,
+            "created_at": 1_699_000_000,
+            "updated_at": 1_699_000_000,
+        }))
+        .unwrap();
+        request.status = status;
+        request.role = role;
+        RequestCache::save(&request).unwrap();
+    }
 
-    debug!(
-        request_id = %request_id,
-        contract = %addresses::REQUEST_REGISTRY,
-        "submitting claim transaction (placeholder)"
-    );
+    #[test]
+    fn test_load_claimable() {
+        with_temp_home(|| {
+            save("1", LocalRequestStatus::Validated, RequestRole::Seller);
+            save("2", LocalRequestStatus::Claimed, RequestRole::Seller);
+            save("3", LocalRequestStatus::Responded, RequestRole::Seller);
+            save("4", LocalRequestStatus::Validated, RequestRole::Buyer);
 
-    formatter::print_info("Submitting claim...");
+            assert_eq!(load_claimable("1").unwrap().unwrap().request_id, "1");
+            assert!(load_claimable("2").unwrap().is_none());
 
-    // 9. Update local request cache status to Claimed.
-    request.status = LocalRequestStatus::Claimed;
-    request.updated_at = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs();
-    RequestCache::save(&request)?;
+            let err = load_claimable("3").unwrap_err().to_string();
+            assert!(err.contains("awaiting validation"), "{err}");
+            let err = load_claimable("4").unwrap_err().to_string();
+            assert!(err.contains("not the seller"), "{err}");
+            assert!(load_claimable("5").is_err());
+        });
+    }
 
-    debug!(request_id = %request_id, "local cache updated to Claimed");
+    #[test]
+    fn test_settle_marks_claimed_and_outcome_json() {
+        with_temp_home(|| {
+            save("1", LocalRequestStatus::Validated, RequestRole::Seller);
+            let request = load_claimable("1").unwrap().unwrap();
+            settle(&request).unwrap();
+            assert_eq!(
+                RequestCache::load("1").unwrap().status,
+                LocalRequestStatus::Claimed
+            );
+            // Settling twice is refused by the state machine.
+            assert!(settle(&request).is_err());
+        });
 
-    // 10. Display success with payment details (zero-crypto UX).
-    let earned = format_price_usd(request.price_usdc);
-    formatter::print_success(&format!("Earned {earned} for request {request_id}."));
+        let outcomes = [
+            ClaimOutcome {
+                request_id: "1".to_string(),
+                price_usdc: 5_000_000,
+                error: None,
+            },
+            ClaimOutcome {
+                request_id: "2".to_string(),
+                price_usdc: 1_000_000,
+                error: Some("Request 2 has expired.".to_string()),
+            },
+        ];
+        assert_eq!(
+            serde_json::to_value(outcomes).unwrap(),
+            serde_json::json!([
+                { "request_id": "1", "price_usdc": 5_000_000 },
+                { "request_id": "2", "price_usdc": 1_000_000, "error": "Request 2 has expired." },
+            ])
+        );
+    }
 
-    // Suppress the secret from output (it is sensitive).
-    let _ = secret;
+    #[tokio::test]
+    async fn test_claimed_onchain_reads_registry_status() {
+        use crate::chain::contracts::ContractAddresses;
+        use alloy::primitives::Bytes;
+        use alloy::providers::mock::Asserter;
+        use alloy::sol_types::SolValue;
 
-    Ok(())
+        let stored = |status: u8| {
+            Bytes::from(
+                (
+                    Address::repeat_byte(0x22),
+                    U256::from(5_000_000u64),
+                    U256::from(1_700_000_000u64),
+                    U256::ZERO,
+                    "bafyrequest".to_string(),
+                    U256::from(status),
+                )
+                    .abi_encode_params(),
+            )
+        };
+        let asserter = Asserter::new();
+        asserter.push_success(&stored(3)); // Claimed
+        asserter.push_success(&stored(2)); // Validated
+        let client = ChainClient::mocked(asserter).with_contracts(ContractAddresses {
+            request_registry: Address::repeat_byte(0x11),
+            ..ContractAddresses::default()
+        });
+        let request: LocalRequest = serde_json::from_value(serde_json::json!({
+            "request_id": "7",
+            "role": "Seller",
+            "status": "Validated",
+            "request_cid": "QmTestCid123",
+            "price_usdc": 5_000_000,
+            "deadline": 1_700_000_000,
+            "response_cid": null,
+            "secret": null,
+            "secret_hash": null,
+            "counterparty": null,
+            "created_at": 1_700_000_000,
+            "updated_at": 1_700_000_000,
+        }))
+        .unwrap();
+
+        assert!(claimed_onchain(&client, &request).await.unwrap());
+        assert!(!claimed_onchain(&client, &request).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_check_hash_lock_names_failed_comparison() {
+        use crate::engine::requests::generate_secret;
+        use alloy::primitives::{Bytes, B256};
+        use alloy::providers::mock::Asserter;
+        use alloy::sol_types::SolValue;
+
+        let (secret, hash) = generate_secret();
+        let mut request: LocalRequest = serde_json::from_value(serde_json::json!({
+            "request_id": "7",
+            "role": "Seller",
+            "status": "Validated",
+            "request_cid": "QmTestCid123",
+            "price_usdc": 5_000_000,
+            "deadline": 1_700_000_000,
+            "response_cid": null,
+            "secret": null,
+            "secret_hash": hash,
+            "counterparty": null,
+            "created_at": 1_699_000_000,
+            "updated_at": 1_699_000_000,
+        }))
+        .unwrap();
+
+        let published: B256 = hash.parse().unwrap();
+        let response = |hash: B256| {
+            Bytes::from(
+                (Address::repeat_byte(0x33), "bafyresponse".to_string(), hash).abi_encode_params(),
+            )
+        };
+        let asserter = Asserter::new();
+        asserter.push_success(&response(published));
+        asserter.push_success(&response(B256::repeat_byte(0xee)));
+        let client = ChainClient::mocked(asserter);
+        let registry = Address::repeat_byte(0x11);
+
+        // Matches both the local and the registry hash.
+        check_hash_lock(&client, registry, &request, &secret)
+            .await
+            .unwrap();
+
+        // The registry holds a different hash.
+        let err = check_hash_lock(&client, registry, &request, &secret)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("hash lock published on-chain"), "{err}");
+        assert!(!err.to_lowercase().contains("secret"), "{err}");
+
+        // The local hash is checked first, without touching the chain.
+        request.secret_hash = Some(generate_secret().1);
+        let err = check_hash_lock(&client, registry, &request, &secret)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("hash lock recorded locally"), "{err}");
+
+        // With no registry only the local hash is checked.
+        request.secret_hash = Some(hash);
+        check_hash_lock(&client, Address::ZERO, &request, &secret)
+            .await
+            .unwrap();
+    }
 }
@@ -0,0 +1,105 @@
+//! The `config` command: read and change `config.toml` settings by dotted
+//! key (see [`crate::config::keys`]).
+
+use anyhow::{bail, Result};
+use serde_json::json;
+use tracing::debug;
+
+use crate::chain::contracts::Contract;
+use crate::config;
+use crate::config::keys;
+use crate::output::formatter;
+
+/// `config get`: print one key, or with no key every key of the effective
+/// config, marking values that come from environment variables.
+pub async fn get(key: Option<String>) -> Result<()> {
+    debug!(?key, "starting config get command");
+
+    if !config::store::exists()? {
+        bail!("Agent not initialized. Run `agentmarket init` first.");
+    }
+    let cfg = config::store::load()?;
+
+    let keys = match key {
+        Some(key) => vec![key],
+        None => keys::valid_keys(),
+    };
+    let mut entries = Vec::with_capacity(keys.len());
+    for key in keys {
+        let value = keys::get(&cfg, &key)?;
+        let env_override = config::store::active_env_override(&key);
+        entries.push((key, value, env_override));
+    }
+
+    if formatter::is_json_mode() {
+        let entries: Vec<_> = entries
+            .into_iter()
+            .map(|(key, value, env_override)| {
+                json!({ "key": key, "value": value, "env_override": env_override })
+            })
+            .collect();
+        formatter::print_json(&entries)?;
+        return Ok(());
+    }
+
+    let width = entries.iter().map(|(key, ..)| key.len()).max().unwrap_or(0);
+    for (key, value, env_override) in &entries {
+        let source = env_override
+            .map(|var| format!("  (from {var})"))
+            .unwrap_or_default();
+        formatter::print_info(&format!(
+            "{key:<width$} = {}{source}",
+            keys::display_value(value)
+        ));
+    }
+    Ok(())
+}
+
+/// `config set`: validate `value` for `key` and save it to `config.toml`.
+pub async fn set(key: String, value: String) -> Result<()> {
+    debug!(%key, %value, "starting config set command");
+
+    if !config::store::exists()? {
+        bail!("Agent not initialized. Run `agentmarket init` first.");
+    }
+
+    // Edit the file as written, so environment overrides are not saved.
+    let mut cfg = config::store::load_file()?;
+    keys::set(&mut cfg, &key, &value)?;
+    config::store::save(&cfg)?;
+    debug!(%key, "config saved");
+
+    let saved = keys::display_value(&keys::get(&cfg, &key)?);
+    formatter::print_success(&format!("{key} = {saved}"));
+    if let Some(var) = config::store::active_env_override(&key) {
+        formatter::print_warning(&format!(
+            "{var} is set and takes precedence over this value."
+        ));
+    }
+    if key == "network.name" {
+        report_network_switch(&cfg)?;
+    }
+    Ok(())
+}
+
+/// After `config set network.name`, show what the preset changed and which
+/// `[contracts]` addresses still override it.
+fn report_network_switch(cfg: &config::store::Config) -> Result<()> {
+    formatter::print_info(&format!(
+        "network.expected_chain_id = {}, network.chain_rpc = {}",
+        cfg.network.expected_chain_id, cfg.network.chain_rpc
+    ));
+    for contract in Contract::ALL {
+        let value = keys::display_value(&keys::get(cfg, contract.key())?);
+        if !value.is_empty() {
+            formatter::print_warning(&format!(
+                "{} is still set to {value} and overrides the {} preset. \
+                 Run `agentmarket config set {} \"\"` if it belongs to the previous network.",
+                contract.key(),
+                cfg.network.name,
+                contract.key()
+            ));
+        }
+    }
+    Ok(())
+}
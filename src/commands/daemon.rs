@@ -6,25 +6,181 @@
 //! - **Claimable requests:** requests in `Validated` status where this agent
 //!   is the `Seller`.
 //!
-//! The daemon handles graceful shutdown via `Ctrl+C` (tokio `ctrl_c`).
+//! Housekeeping tasks run on their own cadences from the `[maintenance]`
+//! config table (see [`crate::engine::maintenance`]).
+//!
+//! The chain RPC and the IPFS node are backed off separately when they fail
+//! (see [`crate::engine::backoff`]): a failing dependency is retried after
+//! 1×, 2×, 4×, ... the poll interval, up to 15 minutes, and the warning is
+//! printed only when that delay changes.
+//!
+//! `Ctrl+C` (SIGINT) or SIGTERM asks the daemon to stop: the request being
+//! processed is finished, the loop stops at the next checkpoint between
+//! requests, and a summary of the session is printed before exiting 0. A
+//! second signal exits immediately.
+//!
+//! With `notifications.webhook_url` set, lifecycle events (claims, claim
+//! failures, cycle errors) are POSTed to it (see
+//! [`crate::engine::notifications`]); `--test-webhook` sends a ping.
+//!
+//! The running daemon records its PID in `~/.agentmarket/daemon.pid` (see
+//! [`crate::config::pidfile`]) so a second one refuses to start;
+//! `--status` reports on it and `--stop` sends it SIGTERM.
+//!
+//! With `--health-port`, a local HTTP listener serves `/healthz` and
+//! `/status` for container probes (see [`crate::engine::health`]).
+//!
+//! `daemon install` writes a systemd user unit that runs the daemon with the
+//! given flags (`--uninstall` removes it); under systemd the daemon reports
+//! readiness and watchdog pings (see [`crate::engine::systemd`]).
+//!
+//! Validation handlers run concurrently, up to `--max-concurrent` at a time
+//! (one for the manual handler); verdicts are recorded and submitted one at
+//! a time as handlers finish.
+//!
+//! Progress is saved to `~/.agentmarket/daemon_state.json` every cycle (see
+//! [`crate::engine::daemon_state`]) so a restart skips requests it already
+//! handled today instead of starting over. Failed auto-claims are queued
+//! there and retried with backoff; after too many failures they are
+//! reported as needing manual attention.
+//!
+//! With `network.chain_ws` set, the daemon also subscribes to RequestRegistry
+//! events over WebSocket (see [`crate::chain::events`]) and starts the next
+//! cycle as soon as one concerns a request in the local cache, instead of
+//! waiting out the interval. A dropped subscription is re-opened with
+//! backoff; polling carries on meanwhile.
+//!
+//! Each cycle also applies the request events buried under
+//! `network.event_confirmations` blocks to the local cache, and undoes any
+//! applied from blocks a reorg has replaced (see
+//! [`crate::engine::event_sync`]).
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
 
-use alloy::primitives::Address;
-use anyhow::Result;
+use alloy::primitives::{Address, B256, U256};
+use anyhow::{anyhow, bail, Context, Result};
+use futures::StreamExt;
+use serde_json::json;
 use tokio::signal;
+use tokio::sync::Notify;
+use tokio::task::JoinSet;
 use tokio::time::{sleep, Duration};
+use tokio_util::sync::CancellationToken;
 use tracing::debug;
 
-use crate::chain::contracts::addresses;
-use crate::config::store;
+use crate::chain::client::ChainClient;
+use crate::chain::contracts::{self, ContractAddresses};
+use crate::chain::events::{DecodedEvent, EventFilter};
+use crate::chain::failover;
+use crate::chain::fees::FeeAboveCap;
+use crate::chain::signer::TransactionSigner;
+use crate::chain::types::RequestStatus;
+use crate::clock::format_duration;
+use crate::clock::unix_now;
+use crate::config::pidfile::{self, DaemonState};
+use crate::config::store::{NetworkConfig, RpcEndpoints};
+use crate::config::{keystore, lock, machine, store};
+use crate::engine::backoff::{Backoff, Transition};
+use crate::engine::daemon_state::{self, DaemonProgress};
+use crate::engine::economics;
+use crate::engine::event_sync::{self, ChainView};
+use crate::engine::handlers::HandlerType;
+use crate::engine::health::{self, SharedSnapshot};
+use crate::engine::identity::address_from_public_key;
 use crate::engine::identity::{get_identity_state, IdentityState};
-use crate::engine::requests::{LocalRequestStatus, RequestCache, RequestRole};
+use crate::engine::maintenance::{self, Scheduler, TaskContext};
+use crate::engine::manual_handler;
+use crate::engine::notifications::{EventKind, Notifier};
+use crate::engine::requests::{
+    verify_secret, LocalRequest, LocalRequestStatus, RequestCache, RequestRole,
+};
+use crate::engine::systemd::{self, SdNotify, Unit};
+use crate::engine::validation;
+use crate::ipfs::client::IpfsClient;
 use crate::output::formatter;
 
+/// Process exit code when a second signal forces the daemon to stop.
+const EXIT_FORCED: i32 = 130;
+
+/// Wait before subscribing to request events again after the subscription
+/// failed or its connection closed, doubled per consecutive failure.
+const RESUBSCRIBE_BASE: Duration = Duration::from_secs(5);
+
+/// Work done by the daemon since it started, reported on shutdown.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct DaemonStats {
+    cycles: u64,
+    validations: u64,
+    claims: u64,
+}
+
+impl DaemonStats {
+    /// e.g. "3 cycles run, 0 validations done, 2 claims made".
+    fn summary(&self) -> String {
+        format!(
+            "{} cycle{} run, {} validation{} done, {} claim{} made",
+            self.cycles,
+            plural(self.cycles),
+            self.validations,
+            plural(self.validations),
+            self.claims,
+            plural(self.claims),
+        )
+    }
+}
+
+fn plural(n: u64) -> &'static str {
+    if n == 1 {
+        ""
+    } else {
+        "s"
+    }
+}
+
+/// Backoff state for the daemon's external dependencies, tracked separately
+/// so an outage of one does not hold up work that only needs the other.
+#[derive(Debug, Default)]
+struct Health {
+    chain: Backoff,
+    ipfs: Backoff,
+}
+
+/// What one daemon cycle found and got through.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct TickOutcome {
+    validations: u64,
+    claims: u64,
+    pending_validations: u64,
+    claimable_usdc: u64,
+}
+
+#[allow(clippy::too_many_arguments)]
 pub async fn run(
     interval_secs: u64,
     handler_type: String,
     handler_path: Option<String>,
+    status: bool,
+    stop: bool,
+    test_webhook: bool,
+    health_port: Option<u16>,
+    health_host: String,
+    max_concurrent: usize,
 ) -> Result<()> {
+    if status {
+        return show_status(health_port, &health_host).await;
+    }
+    if stop {
+        return stop_daemon();
+    }
+    if test_webhook {
+        return send_test_webhook().await;
+    }
+
     // 1. Check initialized and registered
     if !store::exists()? {
         anyhow::bail!("Agent not initialized. Run `agentmarket init` first.");
@@ -44,6 +200,38 @@ pub async fn run(
             // Good to go.
         }
     }
+    let handler = HandlerType::from_str(&handler_type, handler_path.as_deref())?;
+    if max_concurrent == 0 {
+        bail!("--max-concurrent must be at least 1.");
+    }
+    if handler == HandlerType::Manual {
+        manual_handler::ensure_available()?;
+    }
+
+    // Only one daemon may drive an agent home. A PID file or lock left
+    // behind by a daemon that crashed is reclaimed here automatically.
+    let (_pid_file, previous) = pidfile::create()?;
+    if let DaemonState::Stale { pid } = previous {
+        formatter::print_warning(&match pid {
+            Some(pid) => format!("Removed a stale PID file (process {pid} is no longer running)."),
+            None => "Removed an unreadable PID file.".to_string(),
+        });
+    }
+    let _daemon_lock = lock::acquire(lock::DAEMON_LOCK, "daemon")?;
+
+    // Remember which install owns the home. If another machine takes over,
+    // the install ID rotates and this daemon must stop.
+    let install_id = machine::load_stamp()?.map(|s| s.install_id);
+
+    let address = address_from_public_key(&cfg.identity.public_key).unwrap_or_default();
+    let notifier = Notifier::from_config(&cfg, &address);
+    let interval = Duration::from_secs(interval_secs);
+
+    // Bind before announcing the start, so a taken port fails fast.
+    let health_listener = match health_port {
+        Some(port) => Some(health::bind(&health_host, port).await?),
+        None => None,
+    };
 
     // 2. Print startup banner
     formatter::print_success("Daemon started");
@@ -52,53 +240,580 @@ pub async fn run(
     if let Some(ref path) = handler_path {
         formatter::print_info(&format!("Handler path: {}", path));
     }
+    if max_concurrent > 1 && handler != HandlerType::Manual {
+        formatter::print_info(&format!("Concurrent validations: {max_concurrent}"));
+    }
+    if notifier.is_enabled() {
+        formatter::print_info(&format!("Webhook: {}", cfg.notifications.webhook_url));
+    }
+    if let Some(ref listener) = health_listener {
+        if let Ok(local) = listener.local_addr() {
+            formatter::print_info(&format!("Health checks: http://{local}/healthz"));
+        }
+    }
     formatter::print_info("Press Ctrl+C to stop.");
-    println!();
+    formatter::print_info("");
 
-    // 3. Main loop
-    loop {
-        tokio::select! {
-            _ = signal::ctrl_c() => {
-                formatter::print_info("Shutting down gracefully...");
-                break;
+    // 3. Main loop, until a signal or a takeover stops it.
+    let shutdown = CancellationToken::new();
+    tokio::spawn(watch_signals(shutdown.clone()));
+
+    let snapshot = SharedSnapshot::default();
+    let health_server = health_listener.map(|listener| {
+        tokio::spawn(health::serve(
+            listener,
+            Arc::clone(&snapshot),
+            interval,
+            shutdown.clone(),
+        ))
+    });
+
+    let mut scheduler = Scheduler::default();
+    let mut progress = load_progress();
+    let result = run_loop(
+        &cfg,
+        &mut scheduler,
+        interval,
+        &handler,
+        max_concurrent,
+        install_id.as_deref(),
+        &notifier,
+        &snapshot,
+        &mut progress,
+        &shutdown,
+    )
+    .await;
+
+    // The loop may also stop on a takeover; the listener goes with it.
+    shutdown.cancel();
+    if let Some(server) = health_server {
+        let _ = server.await;
+    }
+    let stats = result?;
+
+    formatter::print_success("Daemon stopped.");
+    formatter::print_info(&stats.summary());
+    Ok(())
+}
+
+/// Progress saved by the previous run, or a clean start if there is none or
+/// it cannot be read.
+fn load_progress() -> DaemonProgress {
+    match daemon_state::load() {
+        Ok(Some(progress)) => {
+            debug!(?progress, "resuming daemon progress");
+            formatter::print_info(&format!(
+                "Resuming from the previous run ({} cycle{} so far).",
+                progress.cycles,
+                plural(progress.cycles)
+            ));
+            progress
+        }
+        Ok(None) => DaemonProgress::default(),
+        Err(err) => {
+            debug!(error = %format!("{err:#}"), "unreadable daemon state");
+            formatter::print_warning(
+                "Saved daemon progress could not be read; starting with a full scan.",
+            );
+            DaemonProgress::default()
+        }
+    }
+}
+
+/// The daemon flags a generated systemd unit passes on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ServiceFlags {
+    pub interval: u64,
+    pub handler: String,
+    pub handler_path: Option<String>,
+    pub health_port: Option<u16>,
+    pub health_host: String,
+    pub max_concurrent: usize,
+    pub log_file: Option<PathBuf>,
+}
+
+impl ServiceFlags {
+    /// Command-line arguments after the executable. Paths are made
+    /// absolute against `cwd`, since systemd starts the unit elsewhere.
+    fn args(&self, cwd: &std::path::Path) -> Vec<String> {
+        let absolute = |path: &std::path::Path| cwd.join(path).to_string_lossy().into_owned();
+        let mut args = vec![
+            "daemon".to_string(),
+            "--interval".to_string(),
+            self.interval.to_string(),
+            "--handler".to_string(),
+            self.handler.clone(),
+        ];
+        if let Some(path) = &self.handler_path {
+            args.extend(["--handler-path".to_string(), absolute(path.as_ref())]);
+        }
+        args.extend([
+            "--max-concurrent".to_string(),
+            self.max_concurrent.to_string(),
+        ]);
+        if let Some(port) = self.health_port {
+            args.extend([
+                "--health-port".to_string(),
+                port.to_string(),
+                "--health-host".to_string(),
+                self.health_host.clone(),
+            ]);
+        }
+        if let Some(path) = &self.log_file {
+            args.extend(["--log-file".to_string(), absolute(path)]);
+        }
+        args
+    }
+}
+
+/// `daemon install`: write a systemd user unit that runs the daemon with
+/// `flags`, or with `uninstall`, remove it.
+pub fn install(flags: ServiceFlags, uninstall: bool) -> Result<()> {
+    if !cfg!(target_os = "linux") {
+        bail!("`agentmarket daemon install` sets up a systemd unit, which is only available on Linux.");
+    }
+    if !store::exists()? {
+        bail!("Agent not initialized. Run `agentmarket init` first.");
+    }
+    let cfg = store::load()?;
+    let name = systemd::unit_name(&cfg.agent.name);
+    let path = systemd::user_unit_dir()?.join(&name);
+
+    if uninstall {
+        if !path.exists() {
+            formatter::print_info(&format!("No unit installed at {}.", path.display()));
+            return Ok(());
+        }
+        formatter::print_info(&format!(
+            "Stop it first if it is running: systemctl --user disable --now {name}"
+        ));
+        fs::remove_file(&path)
+            .with_context(|| format!("failed to remove unit file: {}", path.display()))?;
+        formatter::print_success(&format!("Removed {}.", path.display()));
+        formatter::print_info("Then run: systemctl --user daemon-reload");
+        return Ok(());
+    }
+
+    HandlerType::from_str(&flags.handler, flags.handler_path.as_deref())?;
+    if flags.max_concurrent == 0 {
+        bail!("--max-concurrent must be at least 1.");
+    }
+    let cwd = std::env::current_dir().context("unable to determine the current directory")?;
+    let home = store::config_dir()?;
+    let unit = Unit {
+        agent_name: cfg.agent.name.clone(),
+        executable: std::env::current_exe()
+            .context("unable to determine the agentmarket executable")?,
+        args: flags.args(&cwd),
+        home: fs::canonicalize(&home).unwrap_or(home),
+    };
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create unit directory: {}", dir.display()))?;
+    }
+    fs::write(&path, systemd::render_unit(&unit))
+        .with_context(|| format!("failed to write unit file: {}", path.display()))?;
+    formatter::print_success(&format!("Wrote {}.", path.display()));
+    formatter::print_info(&format!(
+        "Start it with: systemctl --user daemon-reload && systemctl --user enable --now {name}"
+    ));
+    formatter::print_info("To keep it running after you log out: loginctl enable-linger");
+    Ok(())
+}
+
+/// `daemon --status`: report whether a daemon is running, and for how long.
+/// With `--health-port`, the running daemon's snapshot is included too.
+async fn show_status(health_port: Option<u16>, health_host: &str) -> Result<()> {
+    let state = pidfile::state()?;
+    let now = unix_now();
+    let uptime = match &state {
+        DaemonState::Running {
+            started_at: Some(started_at),
+            ..
+        } => Some(now.saturating_sub(*started_at)),
+        _ => None,
+    };
+    let snapshot = match (&state, health_port) {
+        (DaemonState::Running { .. }, Some(port)) => match health::fetch(health_host, port).await {
+            Ok(snapshot) => Some(snapshot),
+            Err(err) => {
+                debug!(error = %format!("{err:#}"), "could not fetch daemon snapshot");
+                None
             }
-            _ = daemon_tick(&cfg, &handler_type, handler_path.as_deref()) => {
-                // tick completed, sleep before next
+        },
+        _ => None,
+    };
+
+    if formatter::is_json_mode() {
+        let pid = match &state {
+            DaemonState::Running { pid, .. } => Some(*pid),
+            _ => None,
+        };
+        formatter::print_json(&json!({
+            "running": pid.is_some(),
+            "pid": pid,
+            "uptime_secs": uptime,
+            "snapshot": snapshot,
+        }))?;
+        return Ok(());
+    }
+
+    match state {
+        DaemonState::Running { pid, .. } => formatter::print_info(&match uptime {
+            Some(secs) => format!("Daemon running (pid {pid}) for {}.", format_duration(secs)),
+            None => format!("Daemon running (pid {pid})."),
+        }),
+        DaemonState::NotRunning => formatter::print_info("No daemon running."),
+        DaemonState::Stale { .. } => formatter::print_info(
+            "No daemon running (a stale PID file will be cleaned up on the next start).",
+        ),
+    }
+    if let Some(snapshot) = snapshot {
+        formatter::print_info(&format!(
+            "{} cycle{} run; {} pending validation(s), {} waiting to be claimed.",
+            snapshot.cycles,
+            plural(snapshot.cycles),
+            snapshot.pending_validations,
+            formatter::format_price(snapshot.claimable_usdc),
+        ));
+        for endpoint in snapshot
+            .rpc_endpoints
+            .iter()
+            .filter(|e| e.health.is_failing())
+        {
+            formatter::print_warning(&format!(
+                "RPC endpoint {} is failing: {}",
+                endpoint.url,
+                endpoint
+                    .health
+                    .last_error
+                    .as_deref()
+                    .unwrap_or("unknown error"),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// `daemon --test-webhook`: send a ping event to the configured webhook and
+/// report whether it was accepted.
+async fn send_test_webhook() -> Result<()> {
+    if !store::exists()? {
+        bail!("Agent not initialized. Run `agentmarket init` first.");
+    }
+    let cfg = store::load()?;
+    let address = address_from_public_key(&cfg.identity.public_key).unwrap_or_default();
+    let notifier = Notifier::from_config(&cfg, &address);
+
+    notifier.deliver(&notifier.event(EventKind::Ping)).await?;
+    formatter::print_success(&format!(
+        "Webhook at {} accepted a ping event.",
+        cfg.notifications.webhook_url
+    ));
+    Ok(())
+}
+
+/// `daemon --stop`: ask the recorded daemon to shut down gracefully.
+fn stop_daemon() -> Result<()> {
+    let pid = match pidfile::state()? {
+        DaemonState::Running { pid, .. } => pid,
+        _ => bail!("No daemon running."),
+    };
+    send_terminate(pid)?;
+    formatter::print_success(&format!(
+        "Asked the daemon (pid {pid}) to stop. It finishes its current request first."
+    ));
+    Ok(())
+}
+
+#[cfg(unix)]
+fn send_terminate(pid: u32) -> Result<()> {
+    let status = std::process::Command::new("kill")
+        .args(["-TERM", &pid.to_string()])
+        .status()
+        .map_err(|err| anyhow::anyhow!("Could not signal the daemon: {err}"))?;
+    if !status.success() {
+        bail!("Could not signal the daemon (pid {pid}).");
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn send_terminate(pid: u32) -> Result<()> {
+    bail!("Stopping the daemon is not supported on this platform. End process {pid} instead.")
+}
+
+/// Cancel `shutdown` on the first SIGINT or SIGTERM, and exit the process
+/// on the second.
+async fn watch_signals(shutdown: CancellationToken) {
+    if let Err(err) = wait_for_signal().await {
+        debug!(error = %err, "could not listen for signals");
+        return;
+    }
+    formatter::print_info("Shutting down after the current request (signal again to force)...");
+    shutdown.cancel();
+
+    if wait_for_signal().await.is_ok() {
+        formatter::print_warning("Forced shutdown.");
+        std::process::exit(EXIT_FORCED);
+    }
+}
+
+#[cfg(unix)]
+async fn wait_for_signal() -> std::io::Result<()> {
+    let mut terminate = signal::unix::signal(signal::unix::SignalKind::terminate())?;
+    tokio::select! {
+        result = signal::ctrl_c() => result,
+        _ = terminate.recv() => Ok(()),
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signal() -> std::io::Result<()> {
+    signal::ctrl_c().await
+}
+
+/// Run daemon cycles every `interval` until `shutdown` is cancelled or
+/// another machine takes over the home. Cancellation is checked before each
+/// cycle, between requests within a cycle, and wakes the wait between
+/// cycles; a cycle in progress always finishes the request it is on.
+#[allow(clippy::too_many_arguments)]
+async fn run_loop(
+    cfg: &store::Config,
+    scheduler: &mut Scheduler,
+    interval: Duration,
+    handler: &HandlerType,
+    max_concurrent: usize,
+    install_id: Option<&str>,
+    notifier: &Notifier,
+    snapshot: &SharedSnapshot,
+    progress: &mut DaemonProgress,
+    shutdown: &CancellationToken,
+) -> Result<DaemonStats> {
+    let mut stats = DaemonStats::default();
+    let mut health = Health::default();
+    let mut sd_notify = SdNotify::from_env();
+    let wake = Arc::new(Notify::new());
+    let watcher = if cfg.network.chain_ws.is_empty() {
+        None
+    } else if contracts::for_config(cfg)?.request_registry.is_zero() {
+        debug!("request registry not deployed yet, polling instead of subscribing");
+        None
+    } else {
+        Some(tokio::spawn(watch_events(
+            cfg.clone(),
+            wake.clone(),
+            shutdown.clone(),
+        )))
+    };
+    while !shutdown.is_cancelled() {
+        if machine::load_stamp()?.map(|s| s.install_id).as_deref() != install_id {
+            formatter::print_warning(
+                "Another machine took over this agent home. Stopping the daemon.",
+            );
+            break;
+        }
+        machine::record_write("daemon")?;
+        progress.roll_epoch(unix_now());
+
+        let started = Instant::now();
+        let mut found = None;
+        match daemon_tick(
+            cfg,
+            scheduler,
+            handler,
+            max_concurrent,
+            &mut health,
+            interval,
+            notifier,
+            progress,
+            shutdown,
+        )
+        .await
+        {
+            Ok(outcome) => {
+                stats.validations += outcome.validations;
+                stats.claims += outcome.claims;
+                progress.validations += outcome.validations;
+                progress.claims += outcome.claims;
+                found = Some(outcome);
+            }
+            Err(err) => {
+                debug!(error = %format!("{err:#}"), "daemon cycle failed");
+                formatter::print_warning(&format!("Daemon cycle failed: {err}"));
+                notifier.notify(
+                    notifier
+                        .event(EventKind::CycleError)
+                        .with_error(formatter::format_error(&err)),
+                );
             }
         }
+        stats.cycles += 1;
+        progress.cycles += 1;
+        record_cycle(
+            snapshot,
+            stats.cycles,
+            started.elapsed(),
+            found,
+            progress,
+            &cfg.network.chain_rpc,
+        );
+        sd_notify.cycle_finished(found.is_some());
+        if let Err(err) = daemon_state::save(progress) {
+            debug!(error = %format!("{err:#}"), "could not save daemon state");
+        }
 
         tokio::select! {
-            _ = signal::ctrl_c() => {
-                formatter::print_info("Shutting down gracefully...");
-                break;
+            _ = shutdown.cancelled() => {}
+            _ = sleep(interval) => {}
+            _ = wake.notified() => debug!("request event received, starting the next cycle"),
+        }
+    }
+    if let Some(watcher) = watcher {
+        watcher.abort();
+    }
+    sd_notify.stopping();
+    debug!(?stats, "daemon loop stopped");
+    Ok(stats)
+}
+
+/// Follow request events over `network.chain_ws` until `shutdown` is
+/// cancelled, waking the loop through `wake` whenever one concerns a request
+/// in the local cache. A failed subscription or a closed connection is
+/// retried with backoff, warning only when the delay changes; the poll
+/// interval keeps the loop going meanwhile.
+async fn watch_events(cfg: store::Config, wake: Arc<Notify>, shutdown: CancellationToken) {
+    const NAME: &str = "Event subscription";
+    let mut backoff = Backoff::default();
+    while !shutdown.is_cancelled() {
+        let subscribed = match ChainClient::from_config(&cfg).await {
+            Ok(client) => {
+                client
+                    .subscribe_request_events(client.contracts().request_registry)
+                    .await
+            }
+            Err(err) => Err(err),
+        };
+        let closed = match subscribed {
+            Ok(events) => {
+                track(NAME, &mut backoff, Ok(()), RESUBSCRIBE_BASE);
+                let mut events = std::pin::pin!(events);
+                loop {
+                    let event = tokio::select! {
+                        _ = shutdown.cancelled() => return,
+                        event = events.next() => event,
+                    };
+                    let Some(event) = event else {
+                        break;
+                    };
+                    let tracked = RequestCache::load(&event.request_id().to_string()).is_ok();
+                    debug!(?event, tracked, "request event");
+                    if tracked {
+                        wake.notify_one();
+                    }
+                }
+                Err(anyhow!("the connection to {} closed", cfg.network.chain_ws))
             }
-            _ = sleep(Duration::from_secs(interval_secs)) => {}
+            Err(err) => Err(err),
+        };
+        track(NAME, &mut backoff, closed, RESUBSCRIBE_BASE);
+
+        tokio::select! {
+            _ = shutdown.cancelled() => {}
+            _ = sleep(backoff.wait(Instant::now())) => {}
         }
     }
+}
 
-    formatter::print_success("Daemon stopped.");
-    Ok(())
+/// Publish a finished cycle to the shared snapshot. A failed cycle keeps the
+/// previous work counts.
+fn record_cycle(
+    snapshot: &SharedSnapshot,
+    cycles: u64,
+    took: Duration,
+    outcome: Option<TickOutcome>,
+    progress: &DaemonProgress,
+    endpoints: &RpcEndpoints,
+) {
+    let mut current = snapshot.lock().unwrap_or_else(|e| e.into_inner());
+    current.cycles = cycles;
+    current.last_cycle_ms = Some(u64::try_from(took.as_millis()).unwrap_or(u64::MAX));
+    current.last_cycle_at = Some(unix_now());
+    if let Some(outcome) = outcome {
+        current.pending_validations = outcome.pending_validations;
+        current.claimable_usdc = outcome.claimable_usdc;
+    }
+    current.claims_needing_attention = progress.claims_needing_attention();
+    current.rpc_endpoints = failover::endpoint_status(endpoints);
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn daemon_tick(
-    _cfg: &store::Config,
-    _handler_type: &str,
-    _handler_path: Option<&str>,
-) -> Result<()> {
+    cfg: &store::Config,
+    scheduler: &mut Scheduler,
+    handler: &HandlerType,
+    max_concurrent: usize,
+    health: &mut Health,
+    interval: Duration,
+    notifier: &Notifier,
+    progress: &mut DaemonProgress,
+    shutdown: &CancellationToken,
+) -> Result<TickOutcome> {
     debug!("starting daemon tick");
 
+    // Run whichever housekeeping tasks are due (expiry sweep first, so
+    // past-deadline requests are not picked up as work).
+    let now = unix_now();
+    let ctx = TaskContext {
+        cfg,
+        now,
+        dry_run: false,
+    };
+    let report = scheduler.run_due(&maintenance::all_tasks(), &ctx);
+    for task in &report.tasks {
+        if let Some(error) = &task.error {
+            debug!(task = %task.task, %error, "maintenance task failed");
+        }
+        for action in &task.actions {
+            formatter::print_info(&format!("Maintenance ({}): {action}", task.task));
+        }
+    }
+
     // Check for pending validations and claimable requests
-    let all_requests = RequestCache::load_all().unwrap_or_default();
+    let mut all_requests = RequestCache::load_all().unwrap_or_default();
 
-    // Count work items
-    let pending_validations = all_requests
-        .iter()
-        .filter(|r| r.status == LocalRequestStatus::Responded && r.role == RequestRole::Validator)
-        .count();
-    let claimable = all_requests
+    // Collect work items. Validation jobs whose fee does not cover the
+    // configured handler cost are flagged and left for someone else, and
+    // those with a saved result are not run again.
+    let mut jobs = Vec::new();
+    for request in all_requests.iter().filter(|r| {
+        r.status == LocalRequestStatus::Responded
+            && r.role == RequestRole::Validator
+            && !progress.is_handled(&r.request_id)
+    }) {
+        if !validation::needs_validation(&request.request_id, false).unwrap_or(true) {
+            debug!(request_id = %request.request_id, "already validated, skipping");
+            continue;
+        }
+        let estimate = economics::estimate_job(request.price_usdc, &cfg.validation);
+        if estimate.meets_margin() {
+            jobs.push(request.clone());
+        } else {
+            formatter::print_info(&format!(
+                "Request {}: {}",
+                request.request_id,
+                estimate.summary()
+            ));
+        }
+    }
+    let pending_validations = jobs.len() as u64;
+    let (claimable, claimable_usdc) = all_requests
         .iter()
         .filter(|r| r.status == LocalRequestStatus::Validated && r.role == RequestRole::Seller)
-        .count();
+        .fold((0, 0u64), |(count, total), r| {
+            (count + 1, total.saturating_add(r.price_usdc))
+        });
 
     if pending_validations > 0 || claimable > 0 {
         formatter::print_info(&format!(
@@ -106,18 +821,1341 @@ async fn daemon_tick(
             pending_validations, claimable
         ));
     }
+    let stuck = progress.claims_needing_attention();
+    if !stuck.is_empty() {
+        formatter::print_warning(&format!(
+            "{} claim(s) need manual attention: request {}. Run `agentmarket claim -i <id>` to retry.",
+            stuck.len(),
+            stuck.join(", ")
+        ));
+    }
 
     // Contract deployment gate
-    if addresses::REQUEST_REGISTRY == Address::ZERO {
+    let contracts = contracts::for_config(cfg)?;
+    if contracts.request_registry.is_zero() {
         if pending_validations > 0 || claimable > 0 {
             formatter::print_warning(
                 "Network services not yet available. Validation and claims will be processed once ready.",
             );
         }
-        return Ok(());
+        return Ok(TickOutcome {
+            pending_validations,
+            claimable_usdc,
+            ..TickOutcome::default()
+        });
+    }
+
+    // Each dependency is skipped while it is backing off, so work needing
+    // only the other one carries on.
+    let mut outcome = TickOutcome {
+        pending_validations,
+        claimable_usdc,
+        ..TickOutcome::default()
+    };
+    let mut fee_over_cap = None;
+    if !health.chain.ready(Instant::now()) {
+        debug!(
+            failures = health.chain.failures(),
+            "chain backing off, skipping claims"
+        );
+    } else {
+        let reached = refresh_chain(cfg).await;
+        let block = reached.as_ref().ok().copied();
+        if track("Chain", &mut health.chain, reached.map(|_| ()), interval) {
+            if let Some(head) = block {
+                match sync_events(cfg, &contracts, head, &all_requests, progress).await {
+                    Ok(changed) => {
+                        for request in changed {
+                            if let Some(stale) = all_requests
+                                .iter_mut()
+                                .find(|r| r.request_id == request.request_id)
+                            {
+                                *stale = request;
+                            }
+                        }
+                    }
+                    Err(err) => debug!("could not apply request events: {err:#}"),
+                }
+            }
+            fee_over_cap = fees_over_cap(cfg).await;
+            if claimable > 0 || !progress.claim_retries.is_empty() {
+                match Claimer::connect(cfg, &contracts).await {
+                    Ok(claimer) => {
+                        outcome.claims = claim_validated(
+                            &all_requests,
+                            notifier,
+                            progress,
+                            unix_now(),
+                            interval,
+                            fee_over_cap.as_ref(),
+                            shutdown,
+                            |request| claimer.claim(request),
+                        )
+                        .await;
+                    }
+                    Err(err) => {
+                        formatter::print_warning(&format!("{claimable} claim(s) waiting: {err:#}"))
+                    }
+                }
+            }
+        }
+    }
+
+    if pending_validations > 0 && !shutdown.is_cancelled() {
+        if let Some(over_cap) = &fee_over_cap {
+            formatter::print_info(&format!(
+                "Leaving {pending_validations} validation(s) for a later cycle: {over_cap}."
+            ));
+        } else if !health.ipfs.ready(Instant::now()) {
+            debug!(
+                failures = health.ipfs.failures(),
+                "IPFS backing off, skipping validations"
+            );
+        } else if track("IPFS", &mut health.ipfs, check_ipfs(cfg).await, interval) {
+            match validation_submitter(cfg, &contracts).await {
+                Ok(submitter) => {
+                    outcome.validations = run_validations(
+                        jobs,
+                        handler,
+                        submitter.as_ref(),
+                        max_concurrent,
+                        notifier,
+                        progress,
+                        shutdown,
+                    )
+                    .await;
+                }
+                Err(err) => formatter::print_warning(&format!(
+                    "{pending_validations} validation(s) waiting: {err:#}"
+                )),
+            }
+        }
+    }
+
+    Ok(outcome)
+}
+
+/// What verdicts are recorded on-chain with: `None` while no Validation
+/// Registry is deployed, so they are only saved locally. The daemon cannot
+/// prompt, so the key is unlocked with the passphrase from the environment
+/// or `--passphrase-file`; without one, validations wait.
+async fn validation_submitter(
+    cfg: &store::Config,
+    contracts: &ContractAddresses,
+) -> Result<Option<super::validate::Submitter>> {
+    if contracts.validation_registry.is_zero() {
+        return Ok(None);
+    }
+    let Some(passphrase) = keystore::preset_passphrase()? else {
+        bail!(
+            "submitting verdicts needs the keystore passphrase; set {} or pass --passphrase-file",
+            keystore::PASSPHRASE_ENV
+        );
+    };
+    let signer = TransactionSigner::from_keystore_with_passphrase(&passphrase)?;
+    let submitter = super::validate::Submitter::connect(cfg, contracts, signer).await?;
+    Ok(submitter.map(super::validate::Submitter::unattended))
+}
+
+/// Sends the daemon's claims to the Request Registry, signed with the
+/// agent's key.
+struct Claimer {
+    client: Arc<ChainClient>,
+    signer: TransactionSigner,
+    registry: Address,
+    network: NetworkConfig,
+}
+
+impl Claimer {
+    /// A claimer for the Request Registry in `contracts`. As with
+    /// [`validation_submitter`], the key is unlocked with the passphrase
+    /// from the environment or `--passphrase-file`; without one, claims
+    /// wait.
+    async fn connect(cfg: &store::Config, contracts: &ContractAddresses) -> Result<Self> {
+        let Some(passphrase) = keystore::preset_passphrase()? else {
+            bail!(
+                "claiming needs the keystore passphrase; set {} or pass --passphrase-file",
+                keystore::PASSPHRASE_ENV
+            );
+        };
+        Ok(Self {
+            client: ChainClient::shared(cfg).await?,
+            signer: TransactionSigner::from_keystore_with_passphrase(&passphrase)?,
+            registry: contracts.request_registry,
+            network: cfg.network.clone(),
+        })
+    }
+
+    /// Claim `request` on-chain and wait for the claim to be confirmed.
+    /// Returns whether it was; `false` if the transaction is still pending
+    /// at the receipt timeout. A request the registry already shows as
+    /// claimed (by an earlier claim whose confirmation was not waited for,
+    /// say) counts as confirmed, and nothing is sent. Fees over the cap are
+    /// never paid.
+    async fn claim(&self, request: LocalRequest) -> Result<bool> {
+        let request_id = &request.request_id;
+        let id: U256 = request_id
+            .parse()
+            .with_context(|| format!("{request_id} is not an on-chain request ID"))?;
+        let onchain = self.client.get_request(self.registry, id).await?;
+        if onchain.and_then(|r| r.status) == Some(RequestStatus::Claimed) {
+            debug!(%request_id, "already claimed on-chain");
+            return Ok(true);
+        }
+
+        let secret: B256 = request
+            .secret
+            .as_deref()
+            .filter(|s| !s.is_empty())
+            .context("the claim preimage is not stored locally")?
+            .parse()
+            .context("the stored claim preimage is not a 32-byte hex value")?;
+        let tx_hash = self
+            .client
+            .claim(self.registry, &self.signer, id, secret, |quote| {
+                Err(FeeAboveCap { quote: *quote }.into())
+            })
+            .await?;
+        super::await_confirmation(&self.client, &self.network, tx_hash, "claim").await
+    }
+}
+
+/// The current fees, if sending now would bid more than
+/// `fees.max_fee_gwei_cap`. The daemon never pays over the cap: work that
+/// would is left for a later cycle. Fees that cannot be read are left for
+/// the send itself to report.
+async fn fees_over_cap(cfg: &store::Config) -> Option<FeeAboveCap> {
+    if cfg.fees.max_fee_gwei_cap <= 0.0 {
+        return None;
+    }
+    let quote = match ChainClient::shared(cfg).await {
+        Ok(client) => client.fee_quote().await,
+        Err(err) => Err(err),
+    };
+    match quote {
+        Ok(quote) if quote.over_cap() => Some(FeeAboveCap { quote }),
+        Ok(_) => None,
+        Err(err) => {
+            debug!("could not read network fees: {err:#}");
+            None
+        }
     }
+}
+
+/// Run `handler` on each of `jobs`, up to `max_concurrent` at a time (the
+/// manual handler always runs one at a time), and return how many verdicts
+/// were recorded, on-chain through `submitter` if given.
+///
+/// Handlers run on the blocking pool; their verdicts are recorded here, one
+/// at a time in completion order, so result files and submissions never
+/// interleave. Once `shutdown` is cancelled no new handler is started, but
+/// running ones are waited for and recorded.
+async fn run_validations(
+    jobs: Vec<LocalRequest>,
+    handler: &HandlerType,
+    submitter: Option<&super::validate::Submitter>,
+    max_concurrent: usize,
+    notifier: &Notifier,
+    progress: &mut DaemonProgress,
+    shutdown: &CancellationToken,
+) -> u64 {
+    let limit = match handler {
+        HandlerType::Manual => 1,
+        HandlerType::External(_) => max_concurrent.max(1),
+    };
+    let mut queue = jobs.into_iter();
+    let mut running = JoinSet::new();
+    let mut validations = 0;
+    loop {
+        while running.len() < limit && !shutdown.is_cancelled() {
+            let Some(request) = queue.next() else {
+                break;
+            };
+            // Leave requests another process is already working on. The
+            // lock is held until the verdict is recorded.
+            let request_lock = match lock::acquire(
+                &lock::request_lock(&request.request_id),
+                "daemon",
+            ) {
+                Ok(guard) => guard,
+                Err(err) => {
+                    debug!(request_id = %request.request_id, error = %err, "request locked, skipping");
+                    continue;
+                }
+            };
+            let handler = handler.clone();
+            running.spawn_blocking(move || {
+                let input = super::validate::handler_input(&request);
+                let output = super::validate::run_handler(&handler, &input);
+                (request, request_lock, output)
+            });
+        }
 
-    // TODO: Process validations and claims when contract is deployed
+        let Some(joined) = running.join_next().await else {
+            break;
+        };
+        let (request, _request_lock, output) = match joined {
+            Ok(done) => done,
+            Err(err) => {
+                debug!(error = %err, "validation task failed");
+                continue;
+            }
+        };
+        let recorded = match output {
+            Ok(output) => super::validate::record_verdict(&request, &output, None, submitter).await,
+            Err(err) => Err(err),
+        };
+        match recorded {
+            Ok(_) => {
+                validations += 1;
+                progress.mark_handled(&request.request_id);
+                notifier.notify(
+                    notifier
+                        .event(EventKind::ValidationCompleted)
+                        .for_request(&request.request_id, request.price_usdc),
+                );
+            }
+            Err(err) => {
+                debug!(request_id = %request.request_id, error = %format!("{err:#}"), "validation failed");
+                formatter::print_warning(&format!(
+                    "Could not validate request {}: {err}",
+                    request.request_id
+                ));
+            }
+        }
+    }
+    validations
+}
+
+/// Refresh the cached fee rate and return the chain's current block, from
+/// which the confirmed events to apply are worked out.
+async fn refresh_chain(cfg: &store::Config) -> Result<u64> {
+    super::validate::try_refresh_fee_snapshot(cfg).await?;
+    ChainClient::from_config(cfg)
+        .await?
+        .get_block_number()
+        .await
+}
+
+/// The Request Registry's events, read through `client`.
+struct RegistryEvents<'a> {
+    client: &'a ChainClient,
+    registry: Address,
+}
+
+impl ChainView for RegistryEvents<'_> {
+    async fn block_hash(&self, number: u64) -> Result<Option<B256>> {
+        self.client.get_block_hash(number).await
+    }
 
+    async fn events(&self, from: u64, to: u64) -> Result<Vec<DecodedEvent>> {
+        self.client
+            .scan_request_events(self.registry, from, to, EventFilter::default())
+            .await
+    }
+}
+
+/// Apply the request events confirmed by `head` to the local cache, first
+/// undoing any applied from blocks a reorg has since replaced (see
+/// [`event_sync`]). Returns the requests that changed, already saved.
+async fn sync_events(
+    cfg: &store::Config,
+    contracts: &ContractAddresses,
+    head: u64,
+    all_requests: &[LocalRequest],
+    progress: &mut DaemonProgress,
+) -> Result<Vec<LocalRequest>> {
+    let client = ChainClient::shared(cfg).await?;
+    let chain = RegistryEvents {
+        client: &client,
+        registry: contracts.request_registry,
+    };
+    let mut requests: BTreeMap<String, LocalRequest> = all_requests
+        .iter()
+        .map(|r| (r.request_id.clone(), r.clone()))
+        .collect();
+    let outcome = event_sync::sync(
+        &chain,
+        head,
+        cfg.network.event_confirmations,
+        progress,
+        &mut requests,
+    )
+    .await?;
+
+    if let Some((from, to)) = outcome.rewound {
+        formatter::print_warning(&format!(
+            "The chain reorganised below block {from}; re-checking request events from block {}.",
+            to + 1
+        ));
+    }
+    let mut changed = Vec::new();
+    for request in outcome
+        .changed
+        .iter()
+        .filter_map(|request_id| requests.remove(request_id))
+    {
+        RequestCache::save(&request)?;
+        changed.push(request);
+    }
+    Ok(changed)
+}
+
+/// Fail if the IPFS node that deliverables are fetched from is unreachable.
+async fn check_ipfs(cfg: &store::Config) -> Result<()> {
+    if !IpfsClient::from_config(cfg).is_connected().await {
+        bail!("the IPFS node at {} is unreachable", cfg.network.ipfs_api);
+    }
     Ok(())
 }
+
+/// Record the outcome of using dependency `name` in `backoff`, warning only
+/// when the backoff level changes. Returns whether it succeeded.
+fn track(name: &str, backoff: &mut Backoff, result: Result<()>, base: Duration) -> bool {
+    let err = match result {
+        Ok(()) => {
+            if let Transition::Recovered { failures } = backoff.record_success() {
+                formatter::print_info(&format!(
+                    "{name} is reachable again after {failures} failed attempt{}.",
+                    plural(u64::from(failures))
+                ));
+            }
+            return true;
+        }
+        Err(err) => err,
+    };
+
+    debug!(dependency = name, error = %format!("{err:#}"), "dependency failed");
+    let unit = rand::random::<f64>();
+    match backoff.record_failure(Instant::now(), base, unit) {
+        Transition::Degraded { failures, delay } => {
+            formatter::print_warning(&format!(
+                "{name} degraded ({failures} failed attempt{}): {err}. Retrying in about {}.",
+                plural(u64::from(failures)),
+                format_duration(delay.as_secs())
+            ));
+        }
+        Transition::Unchanged | Transition::Recovered { .. } => {}
+    }
+    false
+}
+
+/// Auto-claim the validated requests this agent sold, returning how many
+/// were claimed. Stops before the next request once `shutdown` is
+/// cancelled, so a claim is never cut off between sending and recording.
+/// Requests already claimed this epoch (per `progress`) are skipped.
+///
+/// Each claim is sent with `send_claim`, which resolves to whether the
+/// claim was confirmed on-chain. Only a confirmed claim moves the request
+/// to Claimed; one still pending is checked again after `retry_base`
+/// without counting as a failed attempt.
+///
+/// Failed claims are queued in `progress` and retried once their backoff
+/// (`retry_base`, 2×, 4×, ...) has passed, even if they are no longer in
+/// `requests`; queued requests that are no longer waiting to be claimed
+/// are dropped from the queue. While `fee_over_cap` is set no claim is
+/// sent: due ones are deferred by `retry_base` without counting as failed
+/// attempts.
+///
+/// Each request is reloaded under its lock before it is sent, and going
+/// through `RequestCache::update` re-checks the state machine against the
+/// file on disk, so a request that was claimed or expired since it was
+/// loaded is left alone, and a retry moves it to Claimed only once.
+#[allow(clippy::too_many_arguments)]
+async fn claim_validated<F, Fut>(
+    requests: &[LocalRequest],
+    notifier: &Notifier,
+    progress: &mut DaemonProgress,
+    now: u64,
+    retry_base: Duration,
+    fee_over_cap: Option<&FeeAboveCap>,
+    shutdown: &CancellationToken,
+    mut send_claim: F,
+) -> u64
+where
+    F: FnMut(LocalRequest) -> Fut,
+    Fut: Future<Output = Result<bool>>,
+{
+    let claimable = |r: &LocalRequest| {
+        r.status == LocalRequestStatus::Validated && r.role == RequestRole::Seller
+    };
+    let mut candidates: Vec<LocalRequest> =
+        requests.iter().filter(|r| claimable(r)).cloned().collect();
+    let queued: Vec<String> = progress.claim_retries.keys().cloned().collect();
+    for request_id in queued {
+        if candidates.iter().any(|r| r.request_id == request_id) {
+            continue;
+        }
+        match RequestCache::load(&request_id) {
+            Ok(request) if claimable(&request) => candidates.push(request),
+            _ => {
+                debug!(%request_id, "queued claim no longer needed");
+                progress.clear_claim_retry(&request_id);
+            }
+        }
+    }
+
+    let mut claims = 0;
+    for request in &candidates {
+        if progress.is_handled(&request.request_id) {
+            debug!(request_id = %request.request_id, "already claimed this epoch, skipping");
+            continue;
+        }
+        if !progress.claim_due(&request.request_id, now) {
+            debug!(request_id = %request.request_id, "claim waiting for retry");
+            continue;
+        }
+        if shutdown.is_cancelled() {
+            debug!("shutdown requested, leaving remaining claims");
+            break;
+        }
+        if let Some(over_cap) = fee_over_cap {
+            progress.defer_claim(&request.request_id, &over_cap.to_string(), now, retry_base);
+            formatter::print_info(&format!(
+                "Deferring the claim of request {}: {over_cap}. Retrying in about {}.",
+                request.request_id,
+                format_duration(retry_base.as_secs())
+            ));
+            continue;
+        }
+
+        // Leave requests another process is already working on.
+        let _request_lock = match lock::acquire(&lock::request_lock(&request.request_id), "daemon")
+        {
+            Ok(guard) => guard,
+            Err(err) => {
+                debug!(request_id = %request.request_id, error = %err, "request locked, skipping");
+                continue;
+            }
+        };
+        let request = match RequestCache::load(&request.request_id) {
+            Ok(current) if claimable(&current) => current,
+            _ => {
+                debug!(request_id = %request.request_id, "no longer claimable, skipping");
+                progress.clear_claim_retry(&request.request_id);
+                continue;
+            }
+        };
+
+        // A claim with the wrong secret reverts, so check it against the
+        // hash lock before paying for one.
+        let sent = match (&request.secret, &request.secret_hash) {
+            (Some(secret), Some(hash)) => verify_secret(secret, hash),
+            _ => Ok(()),
+        };
+        let claimed = match sent {
+            Ok(()) => match send_claim(request.clone()).await {
+                Ok(true) => RequestCache::update(&request.request_id, |r| {
+                    r.transition_with_note(
+                        LocalRequestStatus::Claimed,
+                        Some("auto-claimed".to_string()),
+                    )
+                })
+                .map(Some),
+                Ok(false) => Ok(None),
+                Err(err) => Err(err),
+            },
+            Err(err) => Err(err),
+        };
+        match claimed {
+            Ok(Some(claimed)) => {
+                claims += 1;
+                progress.mark_handled(&claimed.request_id);
+                progress.clear_claim_retry(&claimed.request_id);
+                notifier.notify(
+                    notifier
+                        .event(EventKind::ClaimSucceeded)
+                        .for_request(&claimed.request_id, claimed.price_usdc),
+                );
+                formatter::print_success(&format!(
+                    "Earned {} for request {}.",
+                    formatter::format_price(claimed.price_usdc),
+                    claimed.request_id
+                ));
+            }
+            Ok(None) => {
+                progress.defer_claim(
+                    &request.request_id,
+                    "the claim transaction is not confirmed yet",
+                    now,
+                    retry_base,
+                );
+                formatter::print_info(&format!(
+                    "The claim of request {} is not confirmed yet. Checking again in about {}.",
+                    request.request_id,
+                    format_duration(retry_base.as_secs())
+                ));
+            }
+            Err(err) => {
+                debug!(request_id = %request.request_id, error = %format!("{err:#}"), "auto-claim failed");
+                let retry = progress.record_claim_failure(
+                    &request.request_id,
+                    &err.to_string(),
+                    now,
+                    retry_base,
+                );
+                formatter::print_warning(&if retry.needs_attention() {
+                    format!(
+                        "Could not claim request {} after {} attempts: {err}. It needs manual attention.",
+                        request.request_id, retry.attempts
+                    )
+                } else {
+                    format!(
+                        "Could not claim request {}: {err}. Retrying in about {}.",
+                        request.request_id,
+                        format_duration(retry.next_retry_at.saturating_sub(now))
+                    )
+                });
+                notifier.notify(
+                    notifier
+                        .event(EventKind::ClaimFailed)
+                        .for_request(&request.request_id, request.price_usdc)
+                        .with_error(err.to_string()),
+                );
+            }
+        }
+    }
+    claims
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::sync::Mutex;
+
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    /// Claim retry backoff base used in tests.
+    const BASE: Duration = Duration::from_secs(60);
+
+    /// Run `f` on a fresh runtime with `AGENTMARKET_HOME` pointed at a temp
+    /// directory.
+    fn with_temp_home<F: std::future::Future<Output = ()>>(f: F) {
+        let _guard = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let tmp = tempfile::tempdir().expect("create temp dir");
+        let prev = env::var("AGENTMARKET_HOME").ok();
+        env::set_var("AGENTMARKET_HOME", tmp.path());
+
+        tokio::runtime::Runtime::new()
+            .expect("build runtime")
+            .block_on(f);
+
+        match prev {
+            Some(v) => env::set_var("AGENTMARKET_HOME", v),
+            None => env::remove_var("AGENTMARKET_HOME"),
+        }
+    }
+
+    /// A claim sender whose every claim is confirmed.
+    fn confirmed(_: LocalRequest) -> std::future::Ready<Result<bool>> {
+        std::future::ready(Ok(true))
+    }
+
+    fn validated_sale(request_id: &str) -> LocalRequest {
+        LocalRequest {
+            schema_version: 0,
+            request_id: request_id.to_string(),
+            role: RequestRole::Seller,
+            status: LocalRequestStatus::Validated,
+            request_cid: String::new(),
+            price_usdc: 5_000_000,
+            deadline: u64::MAX,
+            response_cid: None,
+            secret: None,
+            secret_hash: None,
+            counterparty: None,
+            created_at: 1_000,
+            updated_at: 1_000,
+            previous_status: None,
+            tags: Vec::new(),
+            task_summary: None,
+            capability: None,
+            trust_policy: None,
+            history: Vec::new(),
+            payment_check: None,
+            response_attempt: None,
+        }
+    }
+
+    fn responded_job(request_id: &str) -> LocalRequest {
+        LocalRequest {
+            role: RequestRole::Validator,
+            status: LocalRequestStatus::Responded,
+            response_cid: Some(format!("bafy{request_id}")),
+            ..validated_sale(request_id)
+        }
+    }
+
+    /// An external handler that takes `secs` seconds and passes everything.
+    #[cfg(unix)]
+    fn slow_handler(dir: &std::path::Path, secs: u32) -> HandlerType {
+        use std::fs;
+        use std::os::unix::fs::PermissionsExt;
+
+        let script = dir.join("handler.sh");
+        fs::write(
+            &script,
+            format!(
+                "#!/bin/sh
+cat > /dev/null
+sleep {secs}
+echo '{{\"score\": 90, \"reason\": \"fine\"}}'\n"
+            ),
+        )
+        .unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+        HandlerType::External(script.to_str().unwrap().to_string())
+    }
+
+    async fn drive(interval: Duration, shutdown: &CancellationToken) -> DaemonStats {
+        let cfg = store::Config::default();
+        let mut scheduler = Scheduler::default();
+        let notifier = Notifier::from_config(&cfg, "");
+        run_loop(
+            &cfg,
+            &mut scheduler,
+            interval,
+            &HandlerType::Manual,
+            1,
+            None,
+            &notifier,
+            &SharedSnapshot::default(),
+            &mut DaemonProgress::default(),
+            shutdown,
+        )
+        .await
+        .expect("daemon loop")
+    }
+
+    // -- run_loop -------------------------------------------------------------
+
+    #[test]
+    fn test_loop_cancelled_before_start_runs_no_cycles() {
+        with_temp_home(async {
+            let shutdown = CancellationToken::new();
+            shutdown.cancel();
+            assert_eq!(
+                drive(Duration::from_secs(3600), &shutdown).await,
+                DaemonStats::default()
+            );
+        });
+    }
+
+    #[test]
+    fn test_loop_cancelled_while_waiting_stops_without_waiting_out_interval() {
+        with_temp_home(async {
+            let shutdown = CancellationToken::new();
+            let canceller = shutdown.clone();
+            // Cancel once the first cycle has started.
+            tokio::spawn(async move {
+                while machine::load_last_writer().unwrap().is_none() {
+                    sleep(Duration::from_millis(5)).await;
+                }
+                canceller.cancel();
+            });
+
+            let stats = tokio::time::timeout(
+                Duration::from_secs(10),
+                drive(Duration::from_secs(3600), &shutdown),
+            )
+            .await
+            .expect("loop should stop on cancellation");
+            assert_eq!(stats.cycles, 1);
+        });
+    }
+
+    #[test]
+    fn test_loop_keeps_cycling_until_cancelled() {
+        with_temp_home(async {
+            let shutdown = CancellationToken::new();
+            let canceller = shutdown.clone();
+            tokio::spawn(async move {
+                while machine::load_last_writer().unwrap().is_none() {
+                    sleep(Duration::from_millis(5)).await;
+                }
+                sleep(Duration::from_millis(200)).await;
+                canceller.cancel();
+            });
+
+            let stats = drive(Duration::from_millis(10), &shutdown).await;
+            assert!(stats.cycles > 1, "{stats:?}");
+
+            let saved = daemon_state::load().unwrap().expect("progress saved");
+            assert_eq!(saved.cycles, stats.cycles);
+        });
+    }
+
+    // -- claim_validated ------------------------------------------------------
+
+    #[test]
+    fn test_claims_stop_at_checkpoint_once_cancelled() {
+        with_temp_home(async {
+            let requests = vec![validated_sale("1"), validated_sale("2")];
+            for request in &requests {
+                RequestCache::save(request).expect("save request");
+            }
+
+            let notifier = Notifier::from_config(&store::Config::default(), "");
+            let mut progress = DaemonProgress::default();
+            let shutdown = CancellationToken::new();
+            shutdown.cancel();
+            assert_eq!(
+                claim_validated(
+                    &requests,
+                    &notifier,
+                    &mut progress,
+                    0,
+                    BASE,
+                    None,
+                    &shutdown,
+                    confirmed
+                )
+                .await,
+                0
+            );
+            for request in &requests {
+                let loaded = RequestCache::load(&request.request_id).unwrap();
+                assert_eq!(loaded.status, LocalRequestStatus::Validated);
+            }
+
+            assert_eq!(
+                claim_validated(
+                    &requests,
+                    &notifier,
+                    &mut progress,
+                    0,
+                    BASE,
+                    None,
+                    &CancellationToken::new(),
+                    confirmed
+                )
+                .await,
+                2
+            );
+            for request in &requests {
+                let loaded = RequestCache::load(&request.request_id).unwrap();
+                assert_eq!(loaded.status, LocalRequestStatus::Claimed);
+            }
+        });
+    }
+
+    #[test]
+    fn test_claims_skip_requests_handled_before_restart() {
+        with_temp_home(async {
+            let requests = vec![validated_sale("1"), validated_sale("2")];
+            for request in &requests {
+                RequestCache::save(request).expect("save request");
+            }
+
+            let notifier = Notifier::from_config(&store::Config::default(), "");
+            let mut progress = DaemonProgress::default();
+            progress.mark_handled("1");
+            assert_eq!(
+                claim_validated(
+                    &requests,
+                    &notifier,
+                    &mut progress,
+                    0,
+                    BASE,
+                    None,
+                    &CancellationToken::new(),
+                    confirmed
+                )
+                .await,
+                1
+            );
+            assert_eq!(
+                RequestCache::load("1").unwrap().status,
+                LocalRequestStatus::Validated
+            );
+            assert!(progress.is_handled("2"));
+        });
+    }
+
+    #[test]
+    fn test_failed_claim_is_retried_after_backoff_even_when_not_listed() {
+        with_temp_home(async {
+            // A Validated request whose secret does not match fails to claim.
+            let mut request = validated_sale("5");
+            request.secret = Some("00".repeat(32));
+            request.secret_hash = Some(format!("0x{}", "11".repeat(32)));
+            RequestCache::save(&request).expect("save request");
+
+            let notifier = Notifier::from_config(&store::Config::default(), "");
+            let mut progress = DaemonProgress::default();
+            let go = CancellationToken::new();
+            assert_eq!(
+                claim_validated(
+                    &[request.clone()],
+                    &notifier,
+                    &mut progress,
+                    1_000,
+                    BASE,
+                    None,
+                    &go,
+                    confirmed
+                )
+                .await,
+                0
+            );
+            assert_eq!(progress.claim_retries["5"].attempts, 1);
+
+            // Not due yet: nothing is attempted.
+            assert_eq!(
+                claim_validated(
+                    &[],
+                    &notifier,
+                    &mut progress,
+                    1_030,
+                    BASE,
+                    None,
+                    &go,
+                    confirmed
+                )
+                .await,
+                0
+            );
+            assert_eq!(progress.claim_retries["5"].attempts, 1);
+
+            // Fix the secret; the retry picks the request up from the queue
+            // alone and claims it exactly once.
+            RequestCache::update("5", |r| {
+                r.secret_hash = None;
+                Ok(())
+            })
+            .unwrap();
+            assert_eq!(
+                claim_validated(
+                    &[],
+                    &notifier,
+                    &mut progress,
+                    1_060,
+                    BASE,
+                    None,
+                    &go,
+                    confirmed
+                )
+                .await,
+                1
+            );
+            assert!(progress.claim_retries.is_empty());
+            assert_eq!(
+                RequestCache::load("5").unwrap().status,
+                LocalRequestStatus::Claimed
+            );
+            assert_eq!(
+                claim_validated(
+                    &[request],
+                    &notifier,
+                    &mut progress,
+                    2_000,
+                    BASE,
+                    None,
+                    &go,
+                    confirmed
+                )
+                .await,
+                0
+            );
+        });
+    }
+
+    #[test]
+    fn test_queued_claim_dropped_once_no_longer_claimable() {
+        with_temp_home(async {
+            let mut progress = DaemonProgress::default();
+            progress.record_claim_failure("missing", "rpc error", 0, BASE);
+
+            let notifier = Notifier::from_config(&store::Config::default(), "");
+            claim_validated(
+                &[],
+                &notifier,
+                &mut progress,
+                10_000,
+                BASE,
+                None,
+                &CancellationToken::new(),
+                confirmed,
+            )
+            .await;
+            assert!(progress.claim_retries.is_empty());
+        });
+    }
+
+    #[test]
+    fn test_claims_over_the_fee_cap_are_deferred_without_using_attempts() {
+        use crate::chain::fees::{self, FeePolicy, FeeStrategy};
+
+        with_temp_home(async {
+            let request = validated_sale("6");
+            RequestCache::save(&request).expect("save request");
+            let over_cap = FeeAboveCap {
+                quote: fees::compute(
+                    10 * fees::WEI_PER_GWEI,
+                    &FeePolicy {
+                        strategy: FeeStrategy::Normal,
+                        max_priority_wei: 0,
+                        cap_wei: Some(fees::WEI_PER_GWEI),
+                    },
+                ),
+            };
+
+            let notifier = Notifier::from_config(&store::Config::default(), "");
+            let mut progress = DaemonProgress::default();
+            let go = CancellationToken::new();
+            for now in [1_000, 1_060, 1_120] {
+                assert_eq!(
+                    claim_validated(
+                        std::slice::from_ref(&request),
+                        &notifier,
+                        &mut progress,
+                        now,
+                        BASE,
+                        Some(&over_cap),
+                        &go,
+                        confirmed
+                    )
+                    .await,
+                    0
+                );
+            }
+            let retry = &progress.claim_retries["6"];
+            assert_eq!(retry.attempts, 0);
+            assert!(retry.last_error.contains("fees.max_fee_gwei_cap"));
+            assert_eq!(
+                RequestCache::load("6").unwrap().status,
+                LocalRequestStatus::Validated
+            );
+
+            // Once fees drop, the deferred claim goes through from the queue.
+            assert_eq!(
+                claim_validated(
+                    &[],
+                    &notifier,
+                    &mut progress,
+                    1_180,
+                    BASE,
+                    None,
+                    &go,
+                    confirmed
+                )
+                .await,
+                1
+            );
+            assert!(progress.claim_retries.is_empty());
+        });
+    }
+
+    #[test]
+    fn test_claims_change_state_only_once_confirmed() {
+        with_temp_home(async {
+            let request = validated_sale("8");
+            RequestCache::save(&request).expect("save request");
+
+            let notifier = Notifier::from_config(&store::Config::default(), "");
+            let mut progress = DaemonProgress::default();
+            let go = CancellationToken::new();
+
+            // A reverted or unsent claim is a failed attempt.
+            let claims = claim_validated(
+                std::slice::from_ref(&request),
+                &notifier,
+                &mut progress,
+                1_000,
+                BASE,
+                None,
+                &go,
+                |_| async { Err(anyhow!("execution reverted: not the seller")) },
+            )
+            .await;
+            assert_eq!(claims, 0);
+            assert_eq!(progress.claim_retries["8"].attempts, 1);
+            assert!(progress.claim_retries["8"]
+                .last_error
+                .contains("not the seller"));
+
+            // A claim still pending at the receipt timeout is checked again
+            // later without using up an attempt.
+            let claims = claim_validated(
+                &[],
+                &notifier,
+                &mut progress,
+                1_060,
+                BASE,
+                None,
+                &go,
+                |_| async { Ok(false) },
+            )
+            .await;
+            assert_eq!(claims, 0);
+            assert_eq!(progress.claim_retries["8"].attempts, 1);
+            assert!(!progress.is_handled("8"));
+            assert_eq!(
+                RequestCache::load("8").unwrap().status,
+                LocalRequestStatus::Validated
+            );
+
+            let claims = claim_validated(
+                &[],
+                &notifier,
+                &mut progress,
+                1_120,
+                BASE,
+                None,
+                &go,
+                confirmed,
+            )
+            .await;
+            assert_eq!(claims, 1);
+            assert!(progress.claim_retries.is_empty());
+            let claimed = RequestCache::load("8").unwrap();
+            assert_eq!(claimed.status, LocalRequestStatus::Claimed);
+            assert_eq!(
+                claimed.history.last().and_then(|h| h.note.as_deref()),
+                Some("auto-claimed")
+            );
+        });
+    }
+
+    #[test]
+    fn test_claimer_sends_nothing_for_a_request_claimed_on_chain() {
+        use alloy::providers::mock::Asserter;
+        use alloy::sol_types::SolValue;
+
+        with_temp_home(async {
+            let stored = (
+                Address::repeat_byte(0x22),
+                U256::from(5_000_000u64),
+                U256::from(1_700_000_000u64),
+                U256::from(42u64),
+                "bafyrequest".to_string(),
+                U256::from(3u8), // Claimed
+            );
+            let asserter = Asserter::new();
+            asserter.push_success(&alloy::primitives::Bytes::from(stored.abi_encode_params()));
+            let claimer = Claimer {
+                client: Arc::new(ChainClient::mocked(asserter)),
+                signer: TransactionSigner::from_bytes(&mut [0x5a; 32]).unwrap(),
+                registry: Address::repeat_byte(0x11),
+                network: NetworkConfig::default(),
+            };
+
+            // No secret is stored, so anything past the status check fails.
+            assert!(claimer.claim(validated_sale("8")).await.unwrap());
+        });
+    }
+
+    // -- run_validations ------------------------------------------------------
+
+    #[cfg(unix)]
+    #[test]
+    fn test_validations_run_concurrently_and_record_every_verdict() {
+        with_temp_home(async {
+            let scripts = tempfile::tempdir().unwrap();
+            let handler = slow_handler(scripts.path(), 1);
+            let jobs: Vec<_> = ["1", "2", "3", "4"].map(responded_job).into();
+            for job in &jobs {
+                RequestCache::save(job).expect("save request");
+            }
+
+            let notifier = Notifier::from_config(&store::Config::default(), "");
+            let mut progress = DaemonProgress::default();
+            let started = Instant::now();
+            let done = run_validations(
+                jobs.clone(),
+                &handler,
+                None,
+                4,
+                &notifier,
+                &mut progress,
+                &CancellationToken::new(),
+            )
+            .await;
+            let took = started.elapsed();
+
+            assert_eq!(done, 4);
+            assert!(took < Duration::from_secs(3), "took {took:?}");
+            assert_eq!(validation::load_all_results().unwrap().len(), 4);
+            for job in &jobs {
+                assert!(progress.is_handled(&job.request_id));
+                assert_eq!(
+                    RequestCache::load(&job.request_id).unwrap().status,
+                    LocalRequestStatus::Validated
+                );
+            }
+        });
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_validations_start_nothing_once_cancelled() {
+        with_temp_home(async {
+            let scripts = tempfile::tempdir().unwrap();
+            let handler = slow_handler(scripts.path(), 0);
+            let jobs = vec![responded_job("1")];
+            RequestCache::save(&jobs[0]).expect("save request");
+
+            let notifier = Notifier::from_config(&store::Config::default(), "");
+            let shutdown = CancellationToken::new();
+            shutdown.cancel();
+            let done = run_validations(
+                jobs,
+                &handler,
+                None,
+                2,
+                &notifier,
+                &mut DaemonProgress::default(),
+                &shutdown,
+            )
+            .await;
+            assert_eq!(done, 0);
+            assert!(!validation::has_result("1").unwrap());
+        });
+    }
+
+    // -- track ----------------------------------------------------------------
+
+    #[test]
+    fn test_track_backs_off_one_dependency_at_a_time() {
+        let mut health = Health::default();
+        let base = Duration::from_secs(60);
+
+        assert!(!track(
+            "IPFS",
+            &mut health.ipfs,
+            Err(anyhow::anyhow!("connection refused")),
+            base
+        ));
+        assert_eq!(health.ipfs.failures(), 1);
+        assert!(!health.ipfs.ready(Instant::now()));
+        assert!(health.chain.ready(Instant::now()));
+
+        assert!(track("Chain", &mut health.chain, Ok(()), base));
+        assert!(track("IPFS", &mut health.ipfs, Ok(()), base));
+        assert_eq!(health.ipfs.failures(), 0);
+    }
+
+    // -- watch_events ---------------------------------------------------------
+
+    #[tokio::test]
+    async fn test_watch_events_backs_off_until_shutdown() {
+        let mut cfg = store::Config::default();
+        cfg.network.chain_ws = "ws://127.0.0.1:1".to_string();
+        let wake = Arc::new(Notify::new());
+        let shutdown = CancellationToken::new();
+        let watcher = tokio::spawn(watch_events(cfg, wake.clone(), shutdown.clone()));
+
+        // The refused connection is retried after a backoff, not in a loop.
+        sleep(Duration::from_millis(300)).await;
+        assert!(!watcher.is_finished());
+        shutdown.cancel();
+        tokio::time::timeout(Duration::from_secs(5), watcher)
+            .await
+            .expect("stops on shutdown")
+            .unwrap();
+    }
+
+    // -- record_cycle ---------------------------------------------------------
+
+    #[test]
+    fn test_record_cycle_keeps_counts_from_last_good_cycle() {
+        let snapshot = SharedSnapshot::default();
+        let found = TickOutcome {
+            pending_validations: 2,
+            claimable_usdc: 7_000_000,
+            ..TickOutcome::default()
+        };
+        let mut progress = DaemonProgress::default();
+        let endpoints = RpcEndpoints::new(vec![
+            "https://record-cycle-0.example".to_string(),
+            "https://record-cycle-1.example".to_string(),
+        ])
+        .unwrap();
+        record_cycle(
+            &snapshot,
+            1,
+            Duration::from_millis(40),
+            Some(found),
+            &progress,
+            &endpoints,
+        );
+        for _ in 0..daemon_state::MAX_CLAIM_ATTEMPTS {
+            progress.record_claim_failure("9", "rpc error", 0, BASE);
+        }
+        failover::record_failure("https://record-cycle-0.example", "timed out", 100);
+        record_cycle(
+            &snapshot,
+            2,
+            Duration::from_millis(90),
+            None,
+            &progress,
+            &endpoints,
+        );
+
+        let current = snapshot.lock().unwrap().clone();
+        assert_eq!(current.cycles, 2);
+        assert_eq!(current.last_cycle_ms, Some(90));
+        assert!(current.last_cycle_at.is_some());
+        assert_eq!(current.pending_validations, 2);
+        assert_eq!(current.claimable_usdc, 7_000_000);
+        assert_eq!(current.claims_needing_attention, vec!["9".to_string()]);
+        assert_eq!(current.rpc_endpoints.len(), 2);
+        assert!(current.rpc_endpoints[0].health.is_failing());
+        assert!(current.rpc_endpoints[1].active);
+    }
+
+    // -- ServiceFlags ---------------------------------------------------------
+
+    #[test]
+    fn test_service_flags_make_paths_absolute() {
+        let flags = ServiceFlags {
+            interval: 30,
+            handler: "external".to_string(),
+            handler_path: Some("handlers/review.sh".to_string()),
+            health_port: Some(8080),
+            health_host: "0.0.0.0".to_string(),
+            max_concurrent: 3,
+            log_file: Some(PathBuf::from("/var/log/agentmarket/daemon.log")),
+        };
+        assert_eq!(
+            flags.args(std::path::Path::new("/srv/agent")),
+            [
+                "daemon",
+                "--interval",
+                "30",
+                "--handler",
+                "external",
+                "--handler-path",
+                "/srv/agent/handlers/review.sh",
+                "--max-concurrent",
+                "3",
+                "--health-port",
+                "8080",
+                "--health-host",
+                "0.0.0.0",
+                "--log-file",
+                "/var/log/agentmarket/daemon.log",
+            ]
+        );
+    }
+
+    // -- DaemonStats ----------------------------------------------------------
+
+    #[test]
+    fn test_stats_summary() {
+        let stats = DaemonStats {
+            cycles: 3,
+            validations: 0,
+            claims: 1,
+        };
+        assert_eq!(
+            stats.summary(),
+            "3 cycles run, 0 validations done, 1 claim made"
+        );
+    }
+}
@@ -0,0 +1,612 @@
+//! The `doctor` command: diagnose common setup problems.
+//!
+//! Each check is a function returning a [`CheckResult`], so the checklist
+//! can be tested without a TTY and reused by `bugreport`. The command exits
+//! non-zero if any check fails; warnings point at things that limit the
+//! agent without stopping it.
+
+use std::fs;
+use std::path::Path;
+
+use alloy::primitives::Address;
+use anyhow::{bail, Result};
+use serde::Serialize;
+use tracing::debug;
+
+use crate::chain::client::ChainClient;
+use crate::chain::contracts::{self, Contract, ContractAddresses};
+use crate::chain::failover;
+use crate::config;
+use crate::config::machine::{self, SharingStatus};
+use crate::config::store::{Config, NetworkConfig};
+use crate::engine::identity;
+use crate::engine::requests::RequestCache;
+use crate::engine::validation;
+use crate::ipfs::client::IpfsClient;
+use crate::ipfs::gateway;
+use crate::output::formatter;
+
+// ---------------------------------------------------------------------------
+// Check results
+// ---------------------------------------------------------------------------
+
+/// Outcome of one check.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+/// One line of the doctor checklist.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct CheckResult {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+    /// What to do about a warning or failure.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+}
+
+impl CheckResult {
+    fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Pass,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    fn warn(name: &'static str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Warn,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Fail,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+}
+
+/// Render results as one line per check, with hints indented below.
+pub fn render(results: &[CheckResult]) -> String {
+    let mut out = String::new();
+    for result in results {
+        let marker = match result.status {
+            CheckStatus::Pass => "ok  ",
+            CheckStatus::Warn => "WARN",
+            CheckStatus::Fail => "FAIL",
+        };
+        out.push_str(&format!("{marker}  {}: {}\n", result.name, result.detail));
+        if let Some(hint) = &result.hint {
+            out.push_str(&format!("      {hint}\n"));
+        }
+    }
+    out
+}
+
+// ---------------------------------------------------------------------------
+// Command
+// ---------------------------------------------------------------------------
+
+/// Run the `doctor` command. With `unlock`, the keystore passphrase is asked
+/// for and the key decrypted; otherwise only its presence is checked.
+pub async fn run(unlock: bool) -> Result<()> {
+    debug!(unlock, "starting doctor command");
+
+    let (config_result, cfg) = check_config();
+    let mut results = vec![config_result];
+    results.push(check_keystore(unlock));
+    results.extend(contract_checks(cfg.as_ref()));
+
+    if let Some(cfg) = &cfg {
+        results.extend(check_chain(&cfg.network).await);
+        let ipfs = IpfsClient::from_config(cfg);
+        results.push(check_ipfs_api(&ipfs).await);
+        results.push(check_gateway(&ipfs).await);
+        results.extend(home_checks(cfg));
+    }
+
+    let failed = results
+        .iter()
+        .filter(|r| r.status == CheckStatus::Fail)
+        .count();
+    debug!(checks = results.len(), failed, "doctor checks complete");
+
+    if formatter::is_json_mode() {
+        formatter::print_json(&results)?;
+    } else {
+        for line in render(&results).lines() {
+            formatter::print_info(line);
+        }
+    }
+
+    if failed > 0 {
+        bail!("{failed} check(s) failed.");
+    }
+    Ok(())
+}
+
+/// Checks that need no network access or passphrase, for `bugreport`.
+pub fn local_checks() -> Vec<CheckResult> {
+    let (config_result, cfg) = check_config();
+    let mut results = vec![config_result, check_keystore(false)];
+    results.extend(contract_checks(cfg.as_ref()));
+    if let Some(cfg) = &cfg {
+        results.extend(home_checks(cfg));
+    }
+    results
+}
+
+/// Directory, cache, home-sharing, and gateway-history checks, which only
+/// make sense once the config loads.
+fn home_checks(cfg: &Config) -> Vec<CheckResult> {
+    let mut results = Vec::new();
+    results.push(match RequestCache::requests_dir() {
+        Ok(dir) => check_writable("requests directory", &dir),
+        Err(err) => CheckResult::fail(
+            "requests directory",
+            format!("{err:#}"),
+            "Check the permissions of the agent home.",
+        ),
+    });
+    results.push(match validation::validations_dir() {
+        Ok(dir) => check_writable("validations directory", &dir),
+        Err(err) => CheckResult::fail(
+            "validations directory",
+            format!("{err:#}"),
+            "Check the permissions of the agent home.",
+        ),
+    });
+    results.push(check_request_cache());
+    results.push(check_sharing(cfg));
+    results.push(check_gateway_history());
+    results
+}
+
+// ---------------------------------------------------------------------------
+// Checks
+// ---------------------------------------------------------------------------
+
+/// `config.toml` exists and parses. Returns the config for later checks.
+pub fn check_config() -> (CheckResult, Option<Config>) {
+    match config::store::exists() {
+        Ok(false) => {
+            return (
+                CheckResult::fail(
+                    "config",
+                    "config.toml is missing",
+                    "Run `agentmarket init` to set up this agent.",
+                ),
+                None,
+            )
+        }
+        Err(err) => {
+            return (
+                CheckResult::fail(
+                    "config",
+                    format!("{err:#}"),
+                    "Check AGENTMARKET_HOME and the permissions of the agent home.",
+                ),
+                None,
+            )
+        }
+        Ok(true) => {}
+    }
+
+    match config::store::load() {
+        Ok(cfg) => (CheckResult::pass("config", "parsed"), Some(cfg)),
+        Err(err) => (
+            CheckResult::fail(
+                "config",
+                format!("{err:#}"),
+                "Fix the reported line in config.toml, or use `agentmarket config set`.",
+            ),
+            None,
+        ),
+    }
+}
+
+/// The keystore exists and, with `unlock`, decrypts with the passphrase.
+pub fn check_keystore(unlock: bool) -> CheckResult {
+    match config::keystore::exists() {
+        Ok(true) => {}
+        Ok(false) => {
+            return CheckResult::fail(
+                "keystore",
+                "keystore.enc is missing",
+                "Run `agentmarket init`, or restore a backup with `agentmarket key import`.",
+            )
+        }
+        Err(err) => {
+            return CheckResult::fail(
+                "keystore",
+                format!("{err:#}"),
+                "Check the permissions of the agent home.",
+            )
+        }
+    }
+    if !unlock {
+        return CheckResult::pass(
+            "keystore",
+            "present (run with --unlock to test the passphrase)",
+        );
+    }
+
+    let unlocked = config::keystore::get_passphrase()
+        .and_then(|passphrase| config::keystore::load_key(&passphrase))
+        .and_then(|key_bytes| identity::address_from_key(&key_bytes));
+    match unlocked {
+        Ok((_, address)) => CheckResult::pass("keystore", format!("unlocks to {address}")),
+        Err(err) => CheckResult::fail(
+            "keystore",
+            format!("{err:#}"),
+            "Check the passphrase, AGENTMARKET_PASSPHRASE, or --passphrase-file.",
+        ),
+    }
+}
+
+/// The contracts for the configured network, or the Base mainnet preset
+/// when there is no config to read.
+fn contract_checks(cfg: Option<&Config>) -> Vec<CheckResult> {
+    match cfg.map_or_else(|| Ok(ContractAddresses::default()), contracts::for_config) {
+        Ok(resolved) => check_contracts(&resolved),
+        Err(err) => vec![CheckResult::fail(
+            "contracts",
+            format!("{err:#}"),
+            "Fix the address with `agentmarket config set`, or set it to \"\" to use the built-in one.",
+        )],
+    }
+}
+
+/// Every contract has an address on the network.
+pub fn check_contracts(resolved: &ContractAddresses) -> Vec<CheckResult> {
+    [
+        ("USDC token", Contract::Usdc, "payments"),
+        ("agent registry", Contract::AgentRegistry, "registration"),
+        (
+            "request registry",
+            Contract::RequestRegistry,
+            "requests and payments",
+        ),
+        (
+            "validation registry",
+            Contract::ValidationRegistry,
+            "validation results",
+        ),
+    ]
+    .into_iter()
+    .map(|(name, contract, feature)| {
+        let address = resolved.get(contract);
+        if address == Address::ZERO {
+            CheckResult::warn(
+                name,
+                format!("not deployed on {}", resolved.network),
+                format!(
+                    "{feature} stay on this machine until it is. {}",
+                    contract.setup_hint()
+                ),
+            )
+        } else {
+            CheckResult::pass(name, address.to_string())
+        }
+    })
+    .collect()
+}
+
+/// Every configured chain RPC endpoint answers with
+/// `network.expected_chain_id`.
+pub async fn check_chain(network: &NetworkConfig) -> Vec<CheckResult> {
+    let endpoints = &network.chain_rpc;
+    let mut results = Vec::new();
+    for rpc_url in endpoints.urls() {
+        results.push(check_endpoint(rpc_url, network.expected_chain_id).await);
+    }
+    // An unreachable endpoint is only a warning while another one answers.
+    if results.iter().any(|r| r.status == CheckStatus::Pass) {
+        for (result, rpc_url) in results.iter_mut().zip(endpoints.urls()) {
+            if result.status == CheckStatus::Fail && failover::health(rpc_url).is_failing() {
+                result.status = CheckStatus::Warn;
+                result.hint = Some(
+                    "Reads fail over to the other endpoints; remove this one from \
+                     network.chain_rpc if it stays down."
+                        .to_string(),
+                );
+            }
+        }
+    }
+    results
+}
+
+/// One endpoint answers, on the right chain.
+async fn check_endpoint(rpc_url: &str, expected_chain_id: u64) -> CheckResult {
+    match ChainClient::new(rpc_url).await {
+        Ok(client) => check_chain_id(&client, expected_chain_id).await,
+        Err(err) => CheckResult::fail(
+            "chain rpc",
+            format!("{err:#}"),
+            "Set a valid endpoint with `agentmarket config set network.chain_rpc URL`.",
+        ),
+    }
+}
+
+/// `client` reports chain `expected`, per [`ChainClient::verify_chain`].
+pub async fn check_chain_id(client: &ChainClient, expected: u64) -> CheckResult {
+    match client.verify_chain(expected).await {
+        Ok(check) if check.matches() => CheckResult::pass(
+            "chain rpc",
+            format!("{} (chain {})", client.rpc_url(), check.actual),
+        ),
+        Ok(check) => CheckResult::fail(
+            "chain rpc",
+            format!(
+                "{} is on chain {}, expected {}",
+                client.rpc_url(),
+                check.actual,
+                check.expected
+            ),
+            "Point network.chain_rpc (or AGENTMARKET_RPC_URL) at the configured network, \
+             or change network.expected_chain_id.",
+        ),
+        Err(err) => CheckResult::fail(
+            "chain rpc",
+            format!("{}: {err:#}", client.rpc_url()),
+            "Check your connection, or switch endpoints with \
+             `agentmarket config set network.chain_rpc URL`.",
+        ),
+    }
+}
+
+/// The IPFS API node is reachable.
+pub async fn check_ipfs_api(client: &IpfsClient) -> CheckResult {
+    if client.is_connected().await {
+        CheckResult::pass("ipfs api", "reachable")
+    } else {
+        CheckResult::fail(
+            "ipfs api",
+            "unreachable",
+            "Start the local node (`ipfs daemon`), or set network.ipfs_api.",
+        )
+    }
+}
+
+/// The primary IPFS gateway responds.
+pub async fn check_gateway(client: &IpfsClient) -> CheckResult {
+    if client.is_gateway_reachable().await {
+        CheckResult::pass("ipfs gateway", client.gateway_url().to_string())
+    } else {
+        CheckResult::warn(
+            "ipfs gateway",
+            format!("{} is unreachable", client.gateway_url()),
+            "Fetches fall back to the other gateways; set network.ipfs_gateway to replace it.",
+        )
+    }
+}
+
+/// A file can be created and removed in `dir`.
+pub fn check_writable(name: &'static str, dir: &Path) -> CheckResult {
+    let probe = dir.join(".doctor-probe");
+    match fs::write(&probe, b"ok").and_then(|()| fs::remove_file(&probe)) {
+        Ok(()) => CheckResult::pass(name, "writable"),
+        Err(err) => CheckResult::fail(
+            name,
+            format!("{}: {err}", dir.display()),
+            "Check the permissions of the agent home.",
+        ),
+    }
+}
+
+/// The request cache can be read.
+fn check_request_cache() -> CheckResult {
+    match RequestCache::load_all() {
+        Ok(requests) => CheckResult::pass("request cache", format!("{} readable", requests.len())),
+        Err(err) => CheckResult::fail(
+            "request cache",
+            format!("{err:#}"),
+            "Check the permissions of the requests directory.",
+        ),
+    }
+}
+
+/// Whether another machine is driving this agent home.
+fn check_sharing(cfg: &Config) -> CheckResult {
+    match machine::sharing_status(cfg.sharing.recent_window_secs) {
+        Ok(status) => sharing_result(&status),
+        Err(err) => CheckResult::warn(
+            "home sharing",
+            format!("{err:#}"),
+            "Check the permissions of the agent home.",
+        ),
+    }
+}
+
+fn sharing_result(status: &SharingStatus) -> CheckResult {
+    match status {
+        SharingStatus::Unstamped => CheckResult::pass("home sharing", "not yet stamped"),
+        SharingStatus::Exclusive => CheckResult::pass("home sharing", "this machine only"),
+        SharingStatus::Foreign { owner } => CheckResult::warn(
+            "home sharing",
+            format!("owned by {}", owner.hostname),
+            "Pass --takeover to a command to claim this home for this machine.",
+        ),
+        SharingStatus::Conflict { writer } => CheckResult::warn(
+            "home sharing",
+            format!("recently written by {}", writer.hostname),
+            "Run the agent on one machine at a time to avoid conflicting writes.",
+        ),
+    }
+}
+
+/// Recorded fetch outcomes per gateway; degraded gateways warn.
+fn check_gateway_history() -> CheckResult {
+    let totals = match gateway::load() {
+        Ok(totals) => totals,
+        Err(err) => {
+            return CheckResult::warn(
+                "gateway health",
+                format!("{err:#}"),
+                "Delete the gateway health file to reset it.",
+            )
+        }
+    };
+    if totals.is_empty() {
+        return CheckResult::pass("gateway health", "no fetches recorded");
+    }
+
+    let detail = totals
+        .iter()
+        .map(|(endpoint, health)| {
+            format!(
+                "{endpoint} {}/{} ok, {} interference",
+                health.successes, health.attempts, health.interference
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("; ");
+    if totals.values().any(|health| health.is_degraded()) {
+        CheckResult::warn(
+            "gateway health",
+            detail,
+            "Degraded gateways are tried last; replace them in network.ipfs_fallback_gateways.",
+        )
+    } else {
+        CheckResult::pass("gateway health", detail)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::contracts::addresses;
+    use crate::config::machine::MachineStamp;
+
+    #[test]
+    fn test_contracts_warn_when_unset() {
+        let resolved = ContractAddresses {
+            request_registry: Address::repeat_byte(0x11),
+            ..Default::default()
+        };
+        let results = check_contracts(&resolved);
+        assert_eq!(results[0].status, CheckStatus::Pass);
+        assert_eq!(results[1].status, CheckStatus::Warn);
+        assert_eq!(results[1].detail, "not deployed on base-mainnet");
+        assert!(results[1]
+            .hint
+            .as_deref()
+            .unwrap()
+            .contains("contracts.agent_registry"));
+        assert_eq!(results[2].status, CheckStatus::Pass);
+        assert_eq!(results[2].detail, Address::repeat_byte(0x11).to_string());
+    }
+
+    #[test]
+    fn test_contracts_fail_on_bad_address() {
+        let mut cfg = Config::default();
+        cfg.contracts.usdc = "0x1234".to_string();
+        let results = contract_checks(Some(&cfg));
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].status, CheckStatus::Fail);
+        assert!(
+            results[0].detail.contains("contracts.usdc"),
+            "{}",
+            results[0].detail
+        );
+        assert_eq!(contract_checks(None).len(), 4);
+    }
+
+    #[test]
+    fn test_check_writable() {
+        let tmp = tempfile::tempdir().unwrap();
+        assert_eq!(check_writable("dir", tmp.path()).status, CheckStatus::Pass);
+        assert_eq!(
+            tmp.path().read_dir().unwrap().count(),
+            0,
+            "probe left behind"
+        );
+
+        let missing = tmp.path().join("missing");
+        let result = check_writable("dir", &missing);
+        assert_eq!(result.status, CheckStatus::Fail);
+        assert!(result.hint.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_check_chain_id() {
+        use alloy::providers::mock::Asserter;
+
+        let asserter = Asserter::new();
+        asserter.push_success(&format!("{:#x}", addresses::CHAIN_ID));
+        let client = ChainClient::mocked(asserter);
+        let base = addresses::CHAIN_ID;
+        assert_eq!(
+            check_chain_id(&client, base).await.status,
+            CheckStatus::Pass
+        );
+        // Answered from the client's cached check; the mock is empty.
+        assert_eq!(
+            check_chain_id(&client, base).await.status,
+            CheckStatus::Pass
+        );
+
+        let asserter = Asserter::new();
+        asserter.push_success(&"0x1");
+        let client = ChainClient::mocked(asserter);
+        let wrong = check_chain_id(&client, base).await;
+        assert_eq!(wrong.status, CheckStatus::Fail);
+        assert!(
+            wrong.detail.contains("chain 1, expected 8453"),
+            "{}",
+            wrong.detail
+        );
+        // A different expectation is checked again, and the mock has no more
+        // responses, like an unreachable endpoint.
+        assert_eq!(
+            check_chain_id(&client, 84532).await.status,
+            CheckStatus::Fail
+        );
+    }
+
+    #[test]
+    fn test_sharing_result() {
+        assert_eq!(
+            sharing_result(&SharingStatus::Exclusive).status,
+            CheckStatus::Pass
+        );
+        let foreign = sharing_result(&SharingStatus::Foreign {
+            owner: MachineStamp {
+                hostname: "laptop".to_string(),
+                install_id: "abc".to_string(),
+                created_at: 0,
+            },
+        });
+        assert_eq!(foreign.status, CheckStatus::Warn);
+        assert_eq!(foreign.detail, "owned by laptop");
+    }
+
+    #[test]
+    fn test_render_marks_status_and_hints() {
+        let text = render(&[
+            CheckResult::pass("config", "parsed"),
+            CheckResult::fail("ipfs api", "unreachable", "Start the node."),
+        ]);
+        assert_eq!(
+            text,
+            "ok    config: parsed\nFAIL  ipfs api: unreachable\n      Start the node.\n"
+        );
+    }
+}
@@ -0,0 +1,332 @@
+//! The `expire` command: close overdue requests so committed funds are
+//! released.
+//!
+//! A request can be expired once its deadline has passed (for validated
+//! work, once the claim grace period has passed too). With `--local-only`
+//! only the local cache is updated; otherwise the expiry is also submitted
+//! to the Request Registry, when it is deployed, and the request is marked
+//! expired locally only once that is confirmed.
+
+use alloy::primitives::{Address, U256};
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use tracing::debug;
+
+use super::CommandContext;
+use crate::chain::client::ChainClient;
+use crate::chain::contracts::{self, Contract};
+use crate::chain::signer::TransactionSigner;
+use crate::chain::types::RequestStatus;
+use crate::clock;
+use crate::config;
+use crate::config::lock;
+use crate::config::store::NetworkConfig;
+use crate::engine::requests::{LocalRequest, LocalRequestStatus, RequestCache};
+use crate::output::formatter;
+
+/// Outcome of expiring one request with `--all`.
+#[derive(Debug, Serialize)]
+struct ExpireOutcome {
+    request_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+pub async fn run(request_id: Option<String>, all: bool, local_only: bool) -> Result<()> {
+    debug!(?request_id, all, local_only, "starting expire command");
+
+    // 1. Load config. Submitting to the network needs a registered agent.
+    let (cfg, ctx) = if local_only {
+        if !config::store::exists()? {
+            bail!("Agent not initialized. Run `agentmarket init` first.");
+        }
+        (config::store::load()?, None)
+    } else {
+        let ctx = CommandContext::load_registered()?;
+        (ctx.cfg.clone(), Some(ctx))
+    };
+    let grace = cfg.requests.claim_grace_secs;
+    let now = clock::unix_now();
+    let contracts = contracts::for_config(&cfg)?;
+
+    // 2. Connect to the registry, or warn once if only local state can
+    //    change.
+    let expirer = match &ctx {
+        Some(ctx) if !contracts.request_registry.is_zero() => Some(Expirer {
+            client: ChainClient::from_config(&cfg).await?,
+            signer: ctx.transaction_signer()?,
+            registry: contracts.request_registry,
+            network: cfg.network.clone(),
+        }),
+        Some(_) => {
+            formatter::print_warning(&format!(
+                "The request registry contract is not yet deployed on {}. \
+                 Requests are expired on this machine only. {}",
+                contracts.network,
+                Contract::RequestRegistry.setup_hint()
+            ));
+            None
+        }
+        None => None,
+    };
+    let note = match (local_only, &expirer) {
+        (true, _) => "expired locally",
+        (false, Some(_)) => "expired on-chain",
+        (false, None) => "expired by user",
+    };
+
+    // 3. Expire the one request named.
+    let Some(request_id) = request_id.filter(|_| !all) else {
+        return expire_all(expirer.as_ref(), now, grace, note).await;
+    };
+    match expire_one(expirer.as_ref(), &request_id, now, grace, note).await? {
+        Expiry::Expired => {
+            formatter::print_success(&format!("Request {request_id} expired."));
+        }
+        Expiry::AlreadyExpired => {
+            formatter::print_info(&format!("Request {request_id} has already expired."));
+        }
+        // `await_confirmation` has said how to follow it up.
+        Expiry::Unconfirmed => {}
+    }
+    Ok(())
+}
+
+/// `--all`: expire every overdue request, carrying on past failures. An
+/// expiry sent but not confirmed in time counts as a failure.
+async fn expire_all(expirer: Option<&Expirer>, now: u64, grace: u64, note: &str) -> Result<()> {
+    let candidates = RequestCache::expired_candidates(now, grace)?;
+    debug!(count = candidates.len(), "overdue requests found");
+
+    let mut outcomes = Vec::with_capacity(candidates.len());
+    for request_id in candidates {
+        let error = match expire_one(expirer, &request_id, now, grace, note).await {
+            Ok(Expiry::Expired | Expiry::AlreadyExpired) => None,
+            Ok(Expiry::Unconfirmed) => {
+                Some("the expiry was sent but is not confirmed yet".to_string())
+            }
+            Err(e) => Some(formatter::format_error(&e)),
+        };
+        outcomes.push(ExpireOutcome { request_id, error });
+    }
+    let failed = outcomes.iter().filter(|o| o.error.is_some()).count();
+
+    if formatter::is_json_mode() {
+        formatter::print_json(&outcomes)?;
+    } else if outcomes.is_empty() {
+        formatter::print_info("No overdue requests.");
+    } else {
+        for outcome in &outcomes {
+            match &outcome.error {
+                None => formatter::print_success(&format!("{}: expired", outcome.request_id)),
+                Some(error) => {
+                    formatter::print_warning(&format!("{}: {error}", outcome.request_id))
+                }
+            }
+        }
+        formatter::print_info(&format!(
+            "{} of {} overdue request(s) expired.",
+            outcomes.len() - failed,
+            outcomes.len()
+        ));
+    }
+
+    if failed > 0 {
+        bail!("{failed} request(s) could not be expired.");
+    }
+    Ok(())
+}
+
+/// How [`expire_one`] ended.
+#[derive(Debug, PartialEq, Eq)]
+enum Expiry {
+    /// The request is now Expired locally (and on-chain, if sent).
+    Expired,
+    /// It had already expired; nothing was done.
+    AlreadyExpired,
+    /// The expiry was sent but not confirmed in time; the local cache is
+    /// unchanged.
+    Unconfirmed,
+}
+
+/// Expire `request_id`, on-chain through `expirer` if given, and record it
+/// locally with `note` once that is confirmed.
+async fn expire_one(
+    expirer: Option<&Expirer>,
+    request_id: &str,
+    now: u64,
+    grace: u64,
+    note: &str,
+) -> Result<Expiry> {
+    let _request_lock = lock::acquire(&lock::request_lock(request_id), "expire")?;
+    if !RequestCache::contains(request_id)? {
+        bail!("Request {request_id} is not tracked on this machine.");
+    }
+    let request = RequestCache::load(request_id)?;
+    if !check_expirable(&request, now, grace)? {
+        return Ok(Expiry::AlreadyExpired);
+    }
+
+    if let Some(expirer) = expirer {
+        if !expirer.expire(request_id).await? {
+            return Ok(Expiry::Unconfirmed);
+        }
+    }
+
+    RequestCache::update(request_id, |r| {
+        r.transition_with_note(LocalRequestStatus::Expired, Some(note.to_string()))
+    })?;
+    debug!(request_id = %request_id, "local cache updated to Expired");
+    Ok(Expiry::Expired)
+}
+
+/// Sends expiries to the Request Registry, signed with the agent's key.
+struct Expirer {
+    client: ChainClient,
+    signer: TransactionSigner,
+    registry: Address,
+    network: NetworkConfig,
+}
+
+impl Expirer {
+    /// Expire `request_id` on-chain and wait for the expiry to be
+    /// confirmed. Returns whether it was; `false` if the transaction is
+    /// still pending at the receipt timeout. A request the registry already
+    /// shows as expired (by an earlier run whose confirmation was not
+    /// waited for, say) counts as confirmed, and nothing is sent. A fee over
+    /// the cap is confirmed with the user first.
+    async fn expire(&self, request_id: &str) -> Result<bool> {
+        let id: U256 = request_id
+            .parse()
+            .with_context(|| format!("{request_id} is not an on-chain request ID"))?;
+        let onchain = self.client.get_request(self.registry, id).await?;
+        if onchain.and_then(|r| r.status) == Some(RequestStatus::Expired) {
+            debug!(%request_id, "already expired on-chain");
+            return Ok(true);
+        }
+
+        let tx_hash = self
+            .client
+            .expire(self.registry, &self.signer, id, |quote| {
+                super::confirm_fee_over_cap("expiry", quote)
+            })
+            .await?;
+        super::await_confirmation(&self.client, &self.network, tx_hash, "expiry").await
+    }
+}
+
+/// Check that `request` is overdue. Returns `Ok(false)` if it already
+/// expired and an error explaining why if it cannot be expired.
+fn check_expirable(request: &LocalRequest, now: u64, grace: u64) -> Result<bool> {
+    let request_id = &request.request_id;
+
+    match request.status {
+        LocalRequestStatus::Expired => return Ok(false),
+        LocalRequestStatus::Claimed => bail!("Request {request_id} has already been paid."),
+        LocalRequestStatus::Cancelled => bail!("Request {request_id} was cancelled."),
+        _ => {}
+    }
+
+    if !request.is_expirable(now, grace) {
+        let until = if request.status == LocalRequestStatus::Validated {
+            "its deadline and claim grace period have passed"
+        } else {
+            "its deadline has passed"
+        };
+        bail!(
+            "Request {request_id} is not overdue ({}); it can be closed once {until}.",
+            request.format_deadline(now)
+        );
+    }
+    Ok(true)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::requests::RequestRole;
+
+    const DEADLINE: u64 = 10_000;
+
+    fn request(status: LocalRequestStatus) -> LocalRequest {
+        LocalRequest {
+            schema_version: 0,
+            request_id: "42".to_string(),
+            role: RequestRole::Buyer,
+            status,
+            request_cid: String::new(),
+            price_usdc: 5_000_000,
+            deadline: DEADLINE,
+            response_cid: None,
+            secret: None,
+            secret_hash: None,
+            counterparty: None,
+            created_at: 1_000,
+            updated_at: 1_000,
+            previous_status: None,
+            tags: Vec::new(),
+            task_summary: None,
+            capability: None,
+            trust_policy: None,
+            history: Vec::new(),
+            payment_check: None,
+            response_attempt: None,
+        }
+    }
+
+    #[test]
+    fn test_only_overdue_requests_are_expirable() {
+        let open = request(LocalRequestStatus::Open);
+        assert!(check_expirable(&open, DEADLINE + 1, 0).unwrap());
+        let err = check_expirable(&open, DEADLINE, 0).unwrap_err().to_string();
+        assert!(err.contains("not overdue (due now)"), "{err}");
+
+        let validated = request(LocalRequestStatus::Validated);
+        let err = check_expirable(&validated, DEADLINE + 1, 3_600)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("claim grace period"), "{err}");
+        assert!(check_expirable(&validated, DEADLINE + 3_601, 3_600).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_expirer_sends_nothing_for_a_request_expired_on_chain() {
+        use alloy::primitives::Bytes;
+        use alloy::providers::mock::Asserter;
+        use alloy::sol_types::SolValue;
+
+        let stored = (
+            Address::repeat_byte(0x22),
+            U256::from(5_000_000u64),
+            U256::from(DEADLINE),
+            U256::ZERO,
+            "bafyrequest".to_string(),
+            U256::from(5u8), // Expired
+        );
+        let asserter = Asserter::new();
+        asserter.push_success(&Bytes::from(stored.abi_encode_params()));
+        let expirer = Expirer {
+            client: ChainClient::mocked(asserter),
+            signer: TransactionSigner::from_bytes(&mut [0x5a; 32]).unwrap(),
+            registry: Address::repeat_byte(0x11),
+            network: NetworkConfig::default(),
+        };
+
+        // The mock has nothing more to answer, so a send would fail.
+        assert!(expirer.expire("42").await.unwrap());
+        assert!(expirer.expire("not-a-number").await.is_err());
+    }
+
+    #[test]
+    fn test_terminal_requests() {
+        let expired = request(LocalRequestStatus::Expired);
+        assert!(!check_expirable(&expired, DEADLINE + 1, 0).unwrap());
+        for status in [LocalRequestStatus::Claimed, LocalRequestStatus::Cancelled] {
+            assert!(check_expirable(&request(status), DEADLINE + 1, 0).is_err());
+        }
+    }
+}
@@ -1,18 +1,66 @@
 //! The `fund` command: display wallet address and check balance.
 //!
 //! Shows the agent's wallet address for funding and reports the current
-//! ETH balance. Indicates whether the agent has enough gas to register.
+//! ETH balance (which pays network fees) and USDC balance (earnings, and
+//! what requests are paid with). Indicates whether the agent has enough gas
+//! to register. `--qr` also draws the address as a QR code for phone
+//! wallets.
+//!
+//! On a test network the output is badged TESTNET (see
+//! [`formatter::print_testnet_banner`]), and the JSON says which network
+//! the balances are on.
+//!
+//! With `--watch`, keeps checking the ETH and USDC balances every
+//! `--interval` seconds until the agent can register, or until `--timeout`
+//! runs out. A failed check is reported and retried on the next cycle; in
+//! JSON mode each check is printed as its own object.
 
-use anyhow::{Context, Result};
+use std::time::Duration;
+
+use alloy::primitives::Address;
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use serde_json::json;
+use tokio::time::{sleep, Instant};
 use tracing::debug;
 
 use super::CommandContext;
 use crate::chain::client::ChainClient;
-use crate::chain::types::Balance;
+use crate::chain::types::Balances;
+use crate::engine::requests::format_price_usd;
 use crate::output::formatter;
 
-pub async fn run() -> Result<()> {
-    debug!("starting fund command");
+/// One balance check made by `fund --watch`, printed per cycle in JSON mode.
+#[derive(Debug, Serialize)]
+struct FundPoll {
+    /// Seconds since watching started.
+    elapsed_secs: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    eth_wei: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    usdc: Option<u64>,
+    funded: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+pub async fn run(
+    watch_funds: bool,
+    interval_secs: u64,
+    timeout_secs: Option<u64>,
+    qr: bool,
+) -> Result<()> {
+    debug!(
+        watch_funds,
+        qr,
+        interval_secs,
+        ?timeout_secs,
+        "starting fund command"
+    );
+
+    if watch_funds && interval_secs == 0 {
+        bail!("--interval must be at least 1 second.");
+    }
 
     // 1. Load config and derive address from keystore.
     let ctx = CommandContext::load_initialized()?;
@@ -20,26 +68,60 @@ pub async fn run() -> Result<()> {
     debug!(address = %ctx.address, "agent address derived");
 
     // 2. Display wallet address.
+    formatter::print_testnet_banner();
     formatter::print_info("Agent wallet address:");
-    formatter::print_wallet_address(&ctx.address);
-    println!();
+    if qr {
+        formatter::print_address_qr(&ctx.address);
+    } else {
+        formatter::print_wallet_address(&ctx.address);
+    }
+    formatter::print_info("");
 
     // 3. Check balance via RPC.
-    let client = ChainClient::new(&ctx.cfg.network.chain_rpc).await?;
+    let client = ChainClient::from_config(&ctx.cfg).await?;
 
     // Parse address for alloy.
-    let addr: alloy::primitives::Address = ctx
+    let addr: Address = ctx
         .address
         .parse()
         .context("failed to parse agent address")?;
 
-    let balance_wei = client.get_eth_balance(addr).await?;
-    let balance = Balance { wei: balance_wei };
+    if watch_funds {
+        formatter::print_info(&format!(
+            "Waiting for funds (checking every {interval_secs}s, Ctrl+C to stop)..."
+        ));
+        watch(
+            &client,
+            addr,
+            Duration::from_secs(interval_secs),
+            timeout_secs.map(Duration::from_secs),
+        )
+        .await?;
+        formatter::print_success("Agent has sufficient funds for registration.");
+        formatter::print_info("Run `agentmarket register` to join the network.");
+        return Ok(());
+    }
+
+    let balances = client.get_balances(addr).await?;
+    let sufficient = balances.eth.is_sufficient_for_registration();
 
-    // 4. Display balance and registration readiness.
-    formatter::print_info(&format!("Balance: {}", balance.display_eth()));
+    // 4. Display balances and registration readiness.
+    if formatter::is_json_mode() {
+        formatter::print_json(&json!({
+            "address": ctx.address,
+            "network": ctx.contracts.network,
+            "testnet": ctx.contracts.network.is_testnet(),
+            "eth_wei": balances.eth.wei.to_string(),
+            "eth": balances.eth.display_eth(),
+            "usdc": balances.usdc,
+            "usdc_display": format_price_usd(balances.usdc),
+            "sufficient_for_registration": sufficient,
+        }))?;
+    }
+    formatter::print_info(&balances.display_gas());
+    formatter::print_info(&balances.display_spendable());
 
-    if balance.is_sufficient_for_registration() {
+    if sufficient {
         formatter::print_success("Agent has sufficient funds for registration.");
         formatter::print_info("Run `agentmarket register` to join the network.");
     } else {
@@ -49,3 +131,138 @@ pub async fn run() -> Result<()> {
 
     Ok(())
 }
+
+/// Check `addr`'s balances every `interval` until the ETH balance covers a
+/// registration, printing a line per check. Failed checks are warned about
+/// and retried. Fails once `timeout` has passed without funds arriving.
+async fn watch(
+    client: &ChainClient,
+    addr: Address,
+    interval: Duration,
+    timeout: Option<Duration>,
+) -> Result<Balances> {
+    let start = Instant::now();
+    loop {
+        let mut poll = FundPoll {
+            elapsed_secs: start.elapsed().as_secs(),
+            eth_wei: None,
+            usdc: None,
+            funded: false,
+            error: None,
+        };
+        let balances = match client.get_balances(addr).await {
+            Ok(balances) => {
+                poll.eth_wei = Some(balances.eth.wei.to_string());
+                poll.usdc = Some(balances.usdc);
+                poll.funded = balances.eth.is_sufficient_for_registration();
+                Some(balances)
+            }
+            Err(err) => {
+                debug!(error = %format!("{err:#}"), "balance check failed");
+                poll.error = Some(formatter::format_error(&err));
+                None
+            }
+        };
+
+        if formatter::is_json_mode() {
+            formatter::print_json(&poll)?;
+        }
+        match &balances {
+            Some(balances) => formatter::print_info(&format!(
+                "{} | {}{}",
+                balances.display_gas(),
+                balances.display_spendable(),
+                if poll.funded { "" } else { " (waiting)" }
+            )),
+            None => formatter::print_warning(&format!(
+                "Could not check the balance ({}). Retrying.",
+                poll.error.as_deref().unwrap_or("unknown error")
+            )),
+        }
+
+        if let Some(balances) = balances.filter(|_| poll.funded) {
+            return Ok(balances);
+        }
+        if let Some(timeout) = timeout {
+            if start.elapsed() + interval > timeout {
+                bail!(
+                    "Still waiting for funds after {}s. Run `agentmarket fund --watch` again \
+                     once the deposit has been sent.",
+                    timeout.as_secs()
+                );
+            }
+        }
+        sleep(interval).await;
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::U256;
+    use alloy::providers::mock::Asserter;
+
+    /// ABI-encoded `uint256` return value for an `eth_call`.
+    fn word(value: u64) -> alloy::primitives::Bytes {
+        U256::from(value).to_be_bytes::<32>().to_vec().into()
+    }
+
+    #[tokio::test]
+    async fn watch_retries_failures_until_funded() {
+        let asserter = Asserter::new();
+        asserter.push_failure_msg("connection refused");
+        asserter.push_success(&"0x0"); // no ETH yet
+        asserter.push_success(&word(0));
+        asserter.push_success(&"0x5af3107a4000"); // 0.0001 ETH
+        asserter.push_success(&word(14_500_000));
+        let client = ChainClient::mocked(asserter);
+
+        let balances = watch(&client, Address::repeat_byte(0x11), Duration::ZERO, None)
+            .await
+            .unwrap();
+        assert_eq!(balances.display_gas(), "Gas balance: 0.0001 ETH");
+        assert_eq!(balances.display_spendable(), "Spendable balance: $14.50");
+    }
+
+    #[tokio::test]
+    async fn watch_gives_up_after_timeout() {
+        let asserter = Asserter::new();
+        asserter.push_success(&"0x0");
+        asserter.push_success(&word(0));
+        let client = ChainClient::mocked(asserter);
+
+        let err = watch(
+            &client,
+            Address::repeat_byte(0x11),
+            Duration::from_secs(5),
+            Some(Duration::from_secs(1)),
+        )
+        .await
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("Still waiting for funds after 1s"), "{err}");
+    }
+
+    #[test]
+    fn fund_poll_json_omits_missing_fields() {
+        let poll = FundPoll {
+            elapsed_secs: 10,
+            eth_wei: None,
+            usdc: None,
+            funded: false,
+            error: Some("Network error.".into()),
+        };
+        assert_eq!(
+            serde_json::to_value(&poll).unwrap(),
+            serde_json::json!({
+                "elapsed_secs": 10,
+                "funded": false,
+                "error": "Network error.",
+            })
+        );
+    }
+}
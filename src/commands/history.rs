@@ -0,0 +1,86 @@
+//! The `history` command: earnings over time.
+
+use anyhow::{bail, Result};
+use tracing::debug;
+
+use crate::config;
+use crate::engine::reputation::format_earnings_usd;
+use crate::engine::requests::{LocalRequestStatus, RequestCache, RequestRole};
+use crate::engine::stats::{self, EarningsBucket, Period};
+use crate::output::formatter;
+
+/// Run the `history` command: completed work and earnings per day, week,
+/// or month, from the local request cache.
+pub async fn run(period: String) -> Result<()> {
+    debug!(%period, "starting history command");
+
+    // 1. Check initialized
+    if !config::store::exists()? {
+        bail!("Agent not initialized. Run `agentmarket init` first.");
+    }
+    let period = Period::parse(&period)?;
+
+    // 2. Group claimed work by period
+    let claimed = RequestCache::query(
+        Some(LocalRequestStatus::Claimed),
+        Some(RequestRole::Seller),
+        None,
+    )?;
+    let buckets = stats::earnings_by_period(&claimed, period);
+    debug!(
+        requests = claimed.len(),
+        buckets = buckets.len(),
+        "earnings grouped"
+    );
+
+    // 3. Print
+    if formatter::is_json_mode() {
+        formatter::print_json(&buckets)?;
+    } else {
+        print_table(&buckets, period);
+    }
+    Ok(())
+}
+
+/// Print one row per period and a total row.
+fn print_table(buckets: &[EarningsBucket], period: Period) {
+    if buckets.is_empty() {
+        formatter::print_info("No completed requests yet.");
+        return;
+    }
+
+    let header = match period {
+        Period::Week => "Week of",
+        Period::Day | Period::Month => "Period",
+    };
+    let width = buckets
+        .iter()
+        .map(|b| b.period.len())
+        .max()
+        .unwrap_or(0)
+        .max(header.len());
+
+    formatter::print_info(&format!("{header:<width$}  Completed  Earnings"));
+    formatter::print_info(&format!(
+        "{:<width$}  ---------  --------",
+        "-".repeat(header.len())
+    ));
+    for bucket in buckets {
+        formatter::print_info(&format!(
+            "{:<width$}  {:>9}  {}",
+            bucket.period,
+            bucket.completed,
+            format_earnings_usd(bucket.earnings_usdc)
+        ));
+    }
+
+    let completed: usize = buckets.iter().map(|b| b.completed).sum();
+    let earnings = buckets
+        .iter()
+        .fold(0u64, |sum, b| sum.saturating_add(b.earnings_usdc));
+    formatter::print_info(&format!(
+        "{:<width$}  {completed:>9}  {}",
+        "Total",
+        format_earnings_usd(earnings)
+    ));
+}
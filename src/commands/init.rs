@@ -1,11 +1,28 @@
-use std::io::{self, BufRead, Write};
+use std::fs;
+use std::io;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
 
 use anyhow::{bail, Context, Result};
 use tracing::debug;
+use zeroize::Zeroizing;
 
+use crate::chain::contracts::Network;
+use crate::clock;
 use crate::config;
-use crate::engine::identity;
-use crate::output::formatter;
+use crate::engine::identity::{self, IdentityState};
+use crate::engine::requests::parse_usd_amount;
+use crate::output::{formatter, prompt};
+
+/// What must be typed to confirm `init --force`.
+const REINIT_CONFIRMATION: &str = "reinitialize";
+
+/// Files that make up an agent identity, moved aside by `init --force`.
+const IDENTITY_FILES: [&str; 3] = [
+    config::store::CONFIG_FILE,
+    identity::PROFILE_FILE,
+    config::keystore::KEYSTORE_FILENAME,
+];
 
 /// Run the `init` command: generate an agent identity and save local config.
 ///
@@ -17,61 +34,117 @@ use crate::output::formatter;
 /// Each parameter, when `Some`, skips the corresponding interactive prompt.
 /// This allows fully non-interactive usage (all four flags) or partial
 /// non-interactive usage (e.g., only `--name` supplied).
+///
+/// With `import_key_file`, the agent uses the hex private key in that file
+/// instead of generating a new one. With `mnemonic`, the key is derived from
+/// a new recovery phrase, which is printed once.
+///
+/// With `force`, an existing identity is moved to a `backup-<timestamp>/`
+/// directory first, after a typed confirmation unless `--yes` was given. A
+/// registered identity is only replaced with `abandon_registration`.
+///
+/// The config is set up for `network` (see [`Network::apply`]); on a test
+/// network the output is badged and points to faucets for funds.
+#[allow(clippy::too_many_arguments)]
 pub async fn run(
     name: Option<String>,
     description: Option<String>,
     capabilities: Option<String>,
-    price: Option<f64>,
+    price: Option<String>,
+    import_key_file: Option<PathBuf>,
+    mnemonic: bool,
+    force: bool,
+    abandon_registration: bool,
+    qr: bool,
+    network: Network,
 ) -> Result<()> {
-    debug!("starting init command");
-
-    // 1. Check if already initialized.
-    if config::store::exists()? {
-        formatter::print_warning(
-            "Agent already initialized. To re-initialize, delete ~/.agentmarket/ first.",
-        );
-        return Ok(());
-    }
+    debug!(
+        ?import_key_file,
+        force,
+        abandon_registration,
+        %network,
+        "starting init command"
+    );
+    formatter::set_testnet(network.is_testnet().then(|| network.name()));
 
-    // 2. Collect user input: use flag values or fall through to interactive prompts.
     let stdin = io::stdin();
     let mut reader = stdin.lock();
 
+    // 1. Check if already initialized; with --force, set the old identity aside.
+    if config::store::exists()? || config::keystore::exists()? {
+        if !force {
+            formatter::print_warning(
+                "Agent already initialized. To start over, re-run with --force; \
+                 the current identity is backed up first.",
+            );
+            return Ok(());
+        }
+
+        let state = config::store::load()
+            .map(|cfg| identity::get_identity_state(&cfg))
+            .map_err(|err| debug!(error = %format!("{err:#}"), "existing config unreadable"))
+            .ok();
+        check_reinit_allowed(state.as_ref(), abandon_registration)?;
+
+        if !prompt::assume_yes() {
+            formatter::print_warning(
+                "This replaces the agent's key. Requests and funds tied to the current \
+                 key stay with it; the backup is the only way back.",
+            );
+            let typed = prompt::ask_line(
+                &mut reader,
+                &format!("Type '{REINIT_CONFIRMATION}' to continue: "),
+                "Pass --yes to replace the identity without asking.",
+            )?;
+            if typed != REINIT_CONFIRMATION {
+                formatter::print_warning("Not reinitialized.");
+                return Ok(());
+            }
+        }
+
+        let backup = backup_identity(&config::store::config_dir()?, clock::unix_now())?;
+        formatter::print_info(&format!(
+            "Previous identity backed up to {}",
+            backup.display()
+        ));
+    }
+
+    // 2. Read an imported key before asking anything else, so a bad file
+    //    fails fast.
+    let imported_key = match &import_key_file {
+        Some(path) => Some(read_key_file(path)?),
+        None => None,
+    };
+
+    // 3. Collect user input: use flag values or fall through to interactive prompts.
+
     let name = match name {
         Some(v) => v,
-        None => prompt_line(&mut reader, "Agent name: ")?,
+        None => prompt::ask_line(&mut reader, "Agent name: ", "Pass --name.")?,
     };
 
     let description = match description {
         Some(v) => v,
-        None => prompt_line(&mut reader, "Description: ")?,
+        None => prompt::ask_line(&mut reader, "Description: ", "Pass --description.")?,
     };
 
     let capabilities: Vec<String> = match capabilities {
-        Some(v) => v
-            .split(',')
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty())
-            .collect(),
+        Some(v) => identity::parse_capabilities(&v),
         None => {
-            let capabilities_raw = prompt_line(&mut reader, "Capabilities (comma-separated): ")?;
-            capabilities_raw
-                .split(',')
-                .map(|s| s.trim().to_string())
-                .filter(|s| !s.is_empty())
-                .collect()
+            let capabilities_raw = prompt::ask_line(
+                &mut reader,
+                "Capabilities (comma-separated): ",
+                "Pass --capabilities.",
+            )?;
+            identity::parse_capabilities(&capabilities_raw)
         }
     };
 
-    let pricing_usd: f64 = match price {
+    let price = match price {
         Some(v) => v,
-        None => {
-            let price_str = prompt_line(&mut reader, "Price per task (USD): ")?;
-            price_str
-                .parse()
-                .context("invalid price — please enter a number (e.g. 5.00)")?
-        }
+        None => prompt::ask_line(&mut reader, "Price per task (USD): ", "Pass --price.")?,
     };
+    let pricing_usd = parse_usd_amount(&price)? as f64 / 1_000_000.0;
 
     debug!(
         name = %name,
@@ -81,35 +154,46 @@ pub async fn run(
         "collected agent configuration"
     );
 
-    // 3. Get keystore passphrase (with confirmation unless env var is set).
-    let passphrase = config::keystore::get_passphrase()?;
+    // 4. Get keystore passphrase (with confirmation unless preset).
+    let passphrase = config::keystore::get_new_passphrase()?;
 
-    // If the passphrase came from interactive input (no env var), confirm it.
-    if std::env::var("AGENTMARKET_KEYSTORE_PASSPHRASE").is_err() {
-        let confirm = rpassword::prompt_password_stdout("Confirm passphrase: ")
-            .context("failed to read passphrase confirmation")?;
-        if passphrase != confirm {
-            bail!("Passphrases do not match.");
+    // 5. Generate a keypair, or derive the identity from the imported key.
+    let mut phrase = None;
+    let (private_key_bytes, public_key_hex, address) = match imported_key {
+        Some(key_bytes) => {
+            let (public_key_hex, address) = identity::address_from_key(&key_bytes)?;
+            debug!(address = %address, "using imported key");
+            (key_bytes, public_key_hex, address)
         }
-    }
-
-    // 4. Generate keypair.
-    debug!("generating agent keypair");
-    let (private_key_bytes, public_key_hex, address) = identity::generate_keypair()?;
-    debug!(address = %address, "keypair generated");
+        None if mnemonic => {
+            debug!("generating agent keypair from a recovery phrase");
+            let (words, key_bytes, public_key_hex, address) =
+                identity::generate_mnemonic_keypair()?;
+            phrase = Some(words);
+            (Zeroizing::new(key_bytes), public_key_hex, address)
+        }
+        None => {
+            debug!("generating agent keypair");
+            let (key_bytes, public_key_hex, address) = identity::generate_keypair()?;
+            debug!(address = %address, "keypair generated");
+            (Zeroizing::new(key_bytes), public_key_hex, address)
+        }
+    };
 
-    // 5. Save encrypted keystore.
+    // 6. Save encrypted keystore.
     debug!("saving encrypted keystore");
     config::keystore::save_key(&private_key_bytes, &passphrase)?;
 
-    // 6. Build and save config.
+    // 7. Build and save config.
+    let mut network_cfg = config::store::NetworkConfig::default();
+    network.apply(&mut network_cfg);
     let cfg = config::store::Config {
         agent: config::store::AgentConfig {
             name: name.clone(),
             description: description.clone(),
             ..Default::default()
         },
-        network: config::store::NetworkConfig::default(),
+        network: network_cfg,
         identity: config::store::IdentityConfig {
             public_key: public_key_hex.clone(),
             agent_id: String::new(),
@@ -119,11 +203,12 @@ pub async fn run(
             capabilities: capabilities.clone(),
             pricing_usd,
         },
+        ..Default::default()
     };
     debug!("saving configuration");
     config::store::save(&cfg)?;
 
-    // 7. Build and save profile.
+    // 8. Build and save profile.
     let profile = identity::create_profile(
         &name,
         &description,
@@ -135,30 +220,214 @@ pub async fn run(
     debug!("saving agent profile");
     identity::save_profile(&profile)?;
 
-    // 8. Display results.
-    formatter::print_success("Agent identity created");
+    // 9. Display results.
+    formatter::print_success(if import_key_file.is_some() {
+        "Agent identity created from the imported key"
+    } else {
+        "Agent identity created"
+    });
     formatter::print_success("Configuration saved to ~/.agentmarket/config.toml");
-    println!();
-    formatter::print_info(
-        "To join the network, fund your agent's wallet with a small amount of ETH on Base:",
-    );
-    formatter::print_wallet_address(&address);
-    println!();
+    if let Some(phrase) = &phrase {
+        formatter::print_info("");
+        formatter::print_warning(
+            "Write down this recovery phrase and keep it offline. Anyone who has it \
+             controls this agent and its funds. It will not be shown again.",
+        );
+        formatter::print_info("");
+        formatter::print_info(&format!("    {}", phrase.as_str()));
+        formatter::print_info("");
+        formatter::print_info("Restore it later with `agentmarket key recover`.");
+    }
+    formatter::print_info("");
+    if network.is_testnet() {
+        formatter::print_info(&format!(
+            "To join the test network, fund your agent's wallet with test ETH on {network} \
+             (free from a faucet):"
+        ));
+    } else {
+        formatter::print_info(
+            "To join the network, fund your agent's wallet with a small amount of ETH on Base:",
+        );
+    }
+    if qr {
+        formatter::print_address_qr(&address);
+    } else {
+        formatter::print_wallet_address(&address);
+    }
+    formatter::print_info("");
     formatter::print_info("Then run `agentmarket register` to complete setup.");
 
+    if let Some(path) = &import_key_file {
+        formatter::print_warning(&format!(
+            "The key is now stored encrypted. Delete {} so the plaintext copy does not linger.",
+            path.display()
+        ));
+    }
+
     Ok(())
 }
 
-/// Print a prompt to stderr (so it appears even when stdout is redirected) and
-/// read a single trimmed line from the provided reader.
-fn prompt_line<R: BufRead>(reader: &mut R, prompt: &str) -> Result<String> {
-    eprint!("{}", prompt);
-    io::stderr().flush().context("failed to flush stderr")?;
+/// Read a hex private key from `path` for `--import-key-file`, warning if
+/// the file is readable by anyone but its owner.
+fn read_key_file(path: &Path) -> Result<Zeroizing<Vec<u8>>> {
+    let metadata = fs::metadata(path)
+        .with_context(|| format!("failed to read key file: {}", path.display()))?;
+    if key_file_exposed(metadata.permissions().mode()) {
+        formatter::print_warning(&format!(
+            "{} is readable by other users of this machine (mode {:o}). Anyone who \
+             read it can spend from this key.",
+            path.display(),
+            metadata.permissions().mode() & 0o777
+        ));
+    }
+
+    let contents = Zeroizing::new(
+        fs::read_to_string(path)
+            .with_context(|| format!("failed to read key file: {}", path.display()))?,
+    );
+    identity::parse_private_key_hex(&contents)
+        .with_context(|| format!("invalid key in {}", path.display()))
+}
+
+/// Whether a file mode grants any access to group or others.
+fn key_file_exposed(mode: u32) -> bool {
+    mode & 0o077 != 0
+}
+
+/// Refuse to replace a registered identity unless the caller accepts
+/// abandoning it. `state` is `None` when the existing config is unreadable,
+/// which is treated like a registration that cannot be ruled out.
+fn check_reinit_allowed(state: Option<&IdentityState>, abandon_registration: bool) -> Result<()> {
+    if abandon_registration {
+        return Ok(());
+    }
+    match state {
+        Some(IdentityState::Registered { agent_id, .. }) => bail!(
+            "This agent is registered on the network as agent {agent_id}. Reinitializing \
+             creates a new key and abandons that registration. Re-run with \
+             --abandon-registration if that is what you want."
+        ),
+        None => bail!(
+            "The existing config could not be read, so it is unknown whether this agent \
+             is registered. Re-run with --abandon-registration to reinitialize anyway."
+        ),
+        Some(_) => Ok(()),
+    }
+}
+
+/// Move the identity files in `home` into a new `backup-<timestamp>/`
+/// directory, readable only by the owner. Returns the directory.
+fn backup_identity(home: &Path, timestamp: u64) -> Result<PathBuf> {
+    let backup = home.join(format!("backup-{timestamp}"));
+    fs::create_dir(&backup)
+        .with_context(|| format!("failed to create backup directory: {}", backup.display()))?;
+    fs::set_permissions(&backup, fs::Permissions::from_mode(0o700))
+        .with_context(|| format!("failed to set permissions on {}", backup.display()))?;
+
+    for file in IDENTITY_FILES {
+        let from = home.join(file);
+        if !from.exists() {
+            continue;
+        }
+        fs::rename(&from, backup.join(file))
+            .with_context(|| format!("failed to back up {}", from.display()))?;
+        debug!(file, backup = %backup.display(), "identity file backed up");
+    }
+    Ok(backup)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    let mut line = String::new();
-    reader
-        .read_line(&mut line)
-        .context("failed to read input")?;
+    #[test]
+    fn test_registered_identity_needs_abandon_flag() {
+        let local = IdentityState::Local {
+            address: String::new(),
+            public_key: "02ab".to_string(),
+        };
+        let registered = IdentityState::Registered {
+            address: String::new(),
+            public_key: "02ab".to_string(),
+            agent_id: "7".to_string(),
+        };
 
-    Ok(line.trim().to_string())
+        assert!(check_reinit_allowed(Some(&local), false).is_ok());
+        assert!(check_reinit_allowed(Some(&IdentityState::Uninitialized), false).is_ok());
+
+        let err = check_reinit_allowed(Some(&registered), false)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("agent 7"), "{err}");
+        assert!(err.contains("--abandon-registration"), "{err}");
+        assert!(check_reinit_allowed(None, false).is_err());
+
+        assert!(check_reinit_allowed(Some(&registered), true).is_ok());
+        assert!(check_reinit_allowed(None, true).is_ok());
+    }
+
+    #[test]
+    fn test_read_key_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("key.txt");
+
+        fs::write(&path, format!("0x{}\n", "11".repeat(32))).unwrap();
+        assert_eq!(read_key_file(&path).unwrap().as_slice(), [0x11; 32]);
+
+        fs::write(&path, "11".repeat(31)).unwrap();
+        let err = format!("{:#}", read_key_file(&path).unwrap_err());
+        assert!(err.contains("must be exactly 32 bytes"), "{err}");
+
+        assert!(read_key_file(&dir.path().join("missing.txt")).is_err());
+    }
+
+    #[test]
+    fn test_key_file_exposed() {
+        assert!(!key_file_exposed(0o100600));
+        assert!(!key_file_exposed(0o400));
+        assert!(key_file_exposed(0o644));
+        assert!(key_file_exposed(0o640));
+        assert!(key_file_exposed(0o604));
+    }
+
+    #[test]
+    fn test_backup_moves_identity_files() {
+        let home = tempfile::tempdir().unwrap();
+        fs::write(home.path().join("config.toml"), "config").unwrap();
+        fs::write(home.path().join("keystore.enc"), "keystore").unwrap();
+        fs::create_dir(home.path().join("requests")).unwrap();
+        // No profile.json: missing files are skipped.
+
+        let backup = backup_identity(home.path(), 1_700_000_000).unwrap();
+        assert_eq!(backup, home.path().join("backup-1700000000"));
+
+        let mut backed_up: Vec<String> = fs::read_dir(&backup)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        backed_up.sort();
+        assert_eq!(backed_up, ["config.toml", "keystore.enc"]);
+        assert_eq!(
+            fs::read_to_string(backup.join("keystore.enc")).unwrap(),
+            "keystore"
+        );
+        assert_eq!(
+            fs::metadata(&backup).unwrap().permissions().mode() & 0o777,
+            0o700
+        );
+
+        assert!(!home.path().join("config.toml").exists());
+        assert!(!home.path().join("keystore.enc").exists());
+        assert!(
+            home.path().join("requests").exists(),
+            "request cache is kept"
+        );
+
+        // A second backup in the same second must not merge into the first.
+        assert!(backup_identity(home.path(), 1_700_000_000).is_err());
+    }
 }
@@ -0,0 +1,163 @@
+//! The `key` command: back up, restore, or replace the agent's private key,
+//! or rebuild it from a recovery phrase.
+
+use std::io::{self, BufRead, IsTerminal};
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+use tracing::debug;
+use zeroize::Zeroizing;
+
+use super::CommandContext;
+use crate::config;
+use crate::engine::identity;
+use crate::output::{formatter, prompt};
+
+/// `key export`: write an encrypted backup of the private key to `output`.
+///
+/// Asks for the keystore passphrase to unlock the key, then for a separate
+/// passphrase that protects the backup.
+pub async fn export(output: String) -> Result<()> {
+    debug!(%output, "starting key export command");
+
+    // 1. Unlock the key
+    let ctx = CommandContext::load_initialized()?;
+
+    // 2. Refuse to overwrite before asking for another passphrase
+    let path = PathBuf::from(&output);
+    if path.exists() {
+        bail!(
+            "{} already exists. Choose another --output path.",
+            path.display()
+        );
+    }
+
+    // 3. Encrypt under the backup passphrase and write
+    let backup_passphrase = config::keystore::get_backup_passphrase(true)?;
+    config::keystore::export_backup(
+        &ctx.key_bytes,
+        &backup_passphrase,
+        &ctx.public_key,
+        &ctx.address,
+        &path,
+    )?;
+
+    formatter::print_success(&format!("Key backup written to {}.", path.display()));
+    formatter::print_info(
+        "Store it somewhere safe, away from this machine. \
+         It can only be restored with the backup passphrase.",
+    );
+    Ok(())
+}
+
+/// `key import`: replace the keystore with a key from a backup written by
+/// `key export`, or with a raw hex private key read from stdin (`--hex`).
+///
+/// The hex key is never taken from the command line, where it would end up
+/// in shell history and process listings.
+pub async fn import(file: Option<String>, hex: bool, force: bool) -> Result<()> {
+    debug!(?file, hex, force, "starting key import command");
+
+    // 1. Refuse to replace a key before asking for anything
+    check_replace_allowed("Importing", force)?;
+
+    // 2. Read and validate the key
+    let key_bytes = match file {
+        Some(path) => {
+            let backup_passphrase = config::keystore::get_backup_passphrase(false)?;
+            config::keystore::import_backup(&PathBuf::from(path), &backup_passphrase)?.key_bytes
+        }
+        None => identity::parse_private_key_hex(&read_secret_line(
+            "Private key (hex): ",
+            "private key",
+        )?)?,
+    };
+
+    // 3. Install it
+    let address = install_key(&key_bytes, force)?;
+    formatter::print_success(&format!("Key imported. Agent address: {address}"));
+    Ok(())
+}
+
+/// `key recover`: replace the keystore with the key derived from a BIP-39
+/// recovery phrase read from stdin, such as one printed by `init --mnemonic`.
+pub async fn recover(force: bool) -> Result<()> {
+    debug!(force, "starting key recover command");
+
+    // 1. Refuse to replace a key before asking for anything
+    check_replace_allowed("Recovering", force)?;
+
+    // 2. Derive the key from the phrase
+    let phrase = read_secret_line("Recovery phrase: ", "recovery phrase")?;
+    let (key_bytes, _, _) = identity::keypair_from_mnemonic(&phrase)?;
+    let key_bytes = Zeroizing::new(key_bytes);
+
+    // 3. Install it
+    let address = install_key(&key_bytes, force)?;
+    formatter::print_success(&format!("Key recovered. Agent address: {address}"));
+    Ok(())
+}
+
+fn check_replace_allowed(action: &str, force: bool) -> Result<()> {
+    if config::keystore::exists()? && !force {
+        bail!(
+            "A keystore already exists. {action} would replace the current key; \
+             back it up with `agentmarket key export` and re-run with --force."
+        );
+    }
+    Ok(())
+}
+
+/// Encrypt `key_bytes` under a new local passphrase and point config and
+/// profile at it. Returns the agent address.
+fn install_key(key_bytes: &[u8], force: bool) -> Result<String> {
+    let (public_key, address) = identity::address_from_key(key_bytes)?;
+    debug!(%address, "key validated");
+
+    // Encrypt under a new local passphrase
+    let passphrase = config::keystore::get_new_passphrase()?;
+    config::keystore::import_key(key_bytes, &passphrase, force)?;
+    debug!("keystore written");
+
+    // Point config and profile at the new key
+    let mut cfg = if config::store::exists()? {
+        config::store::load()?
+    } else {
+        config::store::Config::default()
+    };
+    let changed = cfg.identity.public_key != public_key;
+    if changed && !cfg.identity.agent_id.is_empty() {
+        formatter::print_warning(
+            "The new key does not match the registered agent. \
+             Run `agentmarket register` to register it.",
+        );
+        cfg.identity.agent_id.clear();
+        cfg.identity.ipfs_profile_cid.clear();
+    }
+    cfg.identity.public_key = public_key.clone();
+    config::store::save(&cfg)?;
+
+    if let Ok(mut profile) = identity::load_profile() {
+        profile.public_key = public_key;
+        profile.address = address.clone();
+        identity::save_profile(&profile)?;
+        debug!("profile updated with new key");
+    }
+    Ok(address)
+}
+
+/// Read one line of secret input (`what`) from stdin, prompting without
+/// echo when stdin is a terminal.
+fn read_secret_line(prompt: &str, what: &str) -> Result<Zeroizing<String>> {
+    if io::stdin().is_terminal() {
+        return prompt::ask_secret(prompt, &format!("Pipe the {what} in on stdin."))
+            .map(Zeroizing::new)
+            .with_context(|| format!("failed to read {what}"));
+    }
+    let mut line = Zeroizing::new(String::new());
+    io::stdin()
+        .lock()
+        .read_line(&mut line)
+        .with_context(|| format!("failed to read {what} from stdin"))?;
+    Ok(line)
+}
@@ -0,0 +1,172 @@
+//! The `list` command: browse locally cached requests.
+
+use anyhow::{bail, Result};
+use tracing::debug;
+
+use crate::clock;
+use crate::config;
+use crate::engine::requests::{
+    normalize_tags, LocalRequest, LocalRequestStatus, RequestCache, RequestRole,
+};
+use crate::output::formatter;
+
+/// Sort orders accepted by `--sort`. The first is the default.
+const SORT_KEYS: [&str; 4] = ["created", "updated", "deadline", "price"];
+
+/// Run the `list` command.
+///
+/// Filters the request cache by status, role, and tag, sorts the result,
+/// and prints at most `limit` rows. In JSON mode the full requests are
+/// printed as an array, with seller secrets removed.
+pub async fn run(
+    status: Option<String>,
+    role: Option<String>,
+    tag: Option<String>,
+    limit: Option<usize>,
+    sort: String,
+) -> Result<()> {
+    debug!(?status, ?role, ?tag, ?limit, %sort, "starting list command");
+
+    // 1. Check initialized
+    if !config::store::exists()? {
+        bail!("Agent not initialized. Run `agentmarket init` first.");
+    }
+
+    // 2. Parse filters before touching the cache so typos fail fast
+    let status = status
+        .map(|s| parse_choice(&s, &LocalRequestStatus::ALL, "status"))
+        .transpose()?;
+    let role = role
+        .map(|r| parse_choice(&r, &RequestRole::ALL, "role"))
+        .transpose()?;
+    let sort = sort.trim().to_lowercase();
+    if !SORT_KEYS.contains(&sort.as_str()) {
+        bail!(
+            "Unknown sort order '{sort}'. Use one of: {}.",
+            SORT_KEYS.join(", ")
+        );
+    }
+
+    // 3. Load matching requests
+    let mut requests = RequestCache::query(status, role, None)?;
+    if let Some(tag) = tag {
+        let tag = tag.trim().to_lowercase();
+        requests.retain(|r| normalize_tags(&r.tags).contains(&tag));
+    }
+
+    // 4. Sort and truncate
+    sort_requests(&mut requests, &sort);
+    if let Some(limit) = limit {
+        requests.truncate(limit);
+    }
+    debug!(count = requests.len(), "requests selected");
+
+    // 5. Print
+    if formatter::is_json_mode() {
+        let public: Vec<LocalRequest> = requests.iter().map(LocalRequest::without_secret).collect();
+        formatter::print_json(&public)?;
+    } else {
+        formatter::print_local_request_list(&requests, clock::unix_now());
+    }
+    Ok(())
+}
+
+/// Match `input` case-insensitively against the `Debug` names of `choices`.
+fn parse_choice<T: Clone + std::fmt::Debug>(input: &str, choices: &[T], what: &str) -> Result<T> {
+    let input = input.trim();
+    if let Some(found) = choices
+        .iter()
+        .find(|c| format!("{c:?}").eq_ignore_ascii_case(input))
+    {
+        return Ok(found.clone());
+    }
+    let names: Vec<String> = choices
+        .iter()
+        .map(|c| format!("{c:?}").to_lowercase())
+        .collect();
+    bail!(
+        "Unknown {what} '{input}'. Use one of: {}.",
+        names.join(", ")
+    )
+}
+
+/// Sort by `key`: newest first for timestamps, soonest first for
+/// deadlines, highest first for price. Ties keep request ID order.
+fn sort_requests(requests: &mut [LocalRequest], key: &str) {
+    requests.sort_by(|a, b| a.request_id.cmp(&b.request_id));
+    match key {
+        "updated" => requests.sort_by_key(|r| std::cmp::Reverse(r.updated_at)),
+        "deadline" => requests.sort_by_key(|r| r.deadline),
+        "price" => requests.sort_by_key(|r| std::cmp::Reverse(r.price_usdc)),
+        _ => requests.sort_by_key(|r| std::cmp::Reverse(r.created_at)),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(id: &str, created_at: u64, deadline: u64, price_usdc: u64) -> LocalRequest {
+        LocalRequest {
+            schema_version: 0,
+            request_id: id.to_string(),
+            role: RequestRole::Seller,
+            status: LocalRequestStatus::Open,
+            request_cid: String::new(),
+            price_usdc,
+            deadline,
+            response_cid: None,
+            secret: Some("ab".repeat(32)),
+            secret_hash: None,
+            counterparty: None,
+            created_at,
+            updated_at: created_at,
+            previous_status: None,
+            tags: Vec::new(),
+            task_summary: None,
+            capability: None,
+            trust_policy: None,
+            history: Vec::new(),
+            payment_check: None,
+            response_attempt: None,
+        }
+    }
+
+    fn ids(requests: &[LocalRequest]) -> Vec<&str> {
+        requests.iter().map(|r| r.request_id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_parse_choice_is_case_insensitive() {
+        let status = parse_choice(" VALIDATED ", &LocalRequestStatus::ALL, "status").unwrap();
+        assert_eq!(status, LocalRequestStatus::Validated);
+        let role = parse_choice("seller", &RequestRole::ALL, "role").unwrap();
+        assert_eq!(role, RequestRole::Seller);
+
+        let err = parse_choice("done", &LocalRequestStatus::ALL, "status")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Unknown status 'done'"), "{err}");
+        assert!(err.contains("open, responded"), "{err}");
+    }
+
+    #[test]
+    fn test_sort_orders() {
+        let mut requests = vec![
+            request("1", 100, 900, 5),
+            request("2", 300, 700, 1),
+            request("3", 200, 800, 9),
+        ];
+
+        sort_requests(&mut requests, "created");
+        assert_eq!(ids(&requests), ["2", "3", "1"]);
+        sort_requests(&mut requests, "deadline");
+        assert_eq!(ids(&requests), ["2", "3", "1"]);
+        sort_requests(&mut requests, "price");
+        assert_eq!(ids(&requests), ["3", "1", "2"]);
+    }
+}
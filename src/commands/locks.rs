@@ -0,0 +1,82 @@
+//! The `locks` command: inspect and recover daemon and request locks.
+//!
+//! Stale locks are normally reclaimed automatically the next time someone
+//! needs them. This command shows what is currently locked and, with
+//! `--break`, removes a lock by hand after confirmation — for the rare case
+//! where automatic detection cannot tell (e.g. a lock taken on another
+//! machine sharing this agent home).
+
+use std::io;
+
+use anyhow::{bail, Result};
+use tracing::debug;
+
+use crate::clock;
+use crate::config::lock::{self, LockState};
+use crate::output::{formatter, prompt};
+
+pub async fn run(break_lock: Option<String>) -> Result<()> {
+    debug!(?break_lock, "starting locks command");
+
+    match break_lock {
+        Some(name) => run_break(&name),
+        None => run_list(),
+    }
+}
+
+fn run_list() -> Result<()> {
+    let locks = lock::list()?;
+    if locks.is_empty() {
+        formatter::print_info("No locks held.");
+        return Ok(());
+    }
+
+    let now = clock::unix_now();
+    for (name, info, state) in &locks {
+        let state = match state {
+            LockState::Held => "held".to_string(),
+            LockState::Stale(reason) => format!("stale: {reason}"),
+        };
+        formatter::print_info(&format!(
+            "{name:<24} `{}` pid {} on {}, {} ago ({state})",
+            info.command,
+            info.pid,
+            info.hostname,
+            clock::format_duration(now.saturating_sub(info.acquired_at)),
+        ));
+    }
+
+    if locks
+        .iter()
+        .any(|(_, _, state)| matches!(state, LockState::Stale(_)))
+    {
+        formatter::print_info(
+            "Stale locks are reclaimed automatically the next time they are needed.",
+        );
+    }
+    Ok(())
+}
+
+fn run_break(name: &str) -> Result<()> {
+    let Some(info) = lock::load(name)? else {
+        bail!("No lock named {name}. Run `agentmarket locks` to see current locks.");
+    };
+
+    if lock::assess(&info) == LockState::Held {
+        formatter::print_warning(&format!(
+            "The {name} lock looks active (`{}`, pid {} on {}). \
+             Breaking it lets two processes work on the same thing.",
+            info.command, info.pid, info.hostname
+        ));
+    }
+
+    let stdin = io::stdin();
+    if !prompt::confirm(&format!("Break the {name} lock?"), &mut stdin.lock())? {
+        formatter::print_info("Lock left in place.");
+        return Ok(());
+    }
+
+    lock::break_lock(name)?;
+    formatter::print_success(&format!("Lock {name} removed."));
+    Ok(())
+}
@@ -0,0 +1,93 @@
+//! The `maintenance` command: run housekeeping tasks on demand.
+//!
+//! See [`crate::engine::maintenance`] for the tasks and how the daemon
+//! schedules them.
+
+use anyhow::{bail, Result};
+use tracing::debug;
+
+use crate::clock;
+use crate::config;
+use crate::engine::maintenance::{self, MaintenanceReport, Scheduler, TaskContext};
+use crate::output::formatter;
+
+/// `maintenance run`: run the selected tasks once and report what they did.
+pub async fn run(tasks: Vec<String>, all: bool, dry_run: bool) -> Result<()> {
+    debug!(?tasks, all, dry_run, "starting maintenance run command");
+
+    if !config::store::exists()? {
+        bail!("Agent not initialized. Run `agentmarket init` first.");
+    }
+    let cfg = config::store::load()?;
+
+    let available = maintenance::all_tasks();
+    let selected = maintenance::select(&available, &tasks, all)?;
+    let ctx = TaskContext {
+        cfg: &cfg,
+        now: clock::unix_now(),
+        dry_run,
+    };
+    let report = maintenance::run_tasks(&selected, &ctx);
+
+    if formatter::is_json_mode() {
+        formatter::print_json(&report)?;
+    } else {
+        print_report(&report);
+    }
+
+    let failed: Vec<_> = report.failures().map(|t| t.task.as_str()).collect();
+    if !failed.is_empty() {
+        bail!("Maintenance task(s) failed: {}.", failed.join(", "));
+    }
+    Ok(())
+}
+
+/// `maintenance list`: show each task and how often the daemon runs it.
+pub async fn list() -> Result<()> {
+    debug!("starting maintenance list command");
+
+    let cfg = if config::store::exists()? {
+        config::store::load()?
+    } else {
+        config::store::Config::default()
+    };
+
+    for task in maintenance::all_tasks() {
+        let every = Scheduler::every_secs(task.as_ref(), &cfg);
+        let cadence = if every == 0 {
+            "not run by the daemon".to_string()
+        } else {
+            format!("every {}", clock::format_duration(every))
+        };
+        formatter::print_info(&format!(
+            "{:<10} {} ({cadence})",
+            task.name(),
+            task.description()
+        ));
+    }
+    Ok(())
+}
+
+/// Print a report as one section per task.
+fn print_report(report: &MaintenanceReport) {
+    let verb = if report.dry_run { "would" } else { "did" };
+    for task in &report.tasks {
+        if let Some(error) = &task.error {
+            formatter::print_warning(&format!("{}: failed: {error}", task.task));
+        } else if task.actions.is_empty() {
+            formatter::print_info(&format!("{}: nothing to do", task.task));
+        } else {
+            formatter::print_info(&format!("{}: {verb}", task.task));
+            for action in &task.actions {
+                formatter::print_info(&format!("  - {action}"));
+            }
+        }
+    }
+
+    let total = report.total_actions();
+    if report.dry_run {
+        formatter::print_info(&format!("Dry run: {total} action(s) would be taken."));
+    } else {
+        formatter::print_success(&format!("Maintenance complete: {total} action(s) taken."));
+    }
+}
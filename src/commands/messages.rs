@@ -0,0 +1,284 @@
+//! The `messages` command: read the agent's encrypted mailbox.
+//!
+//! Listens briefly on the mailbox topic for newly announced messages,
+//! fetches and decrypts them into the local inbox, then lists the inbox or
+//! shows one message. Listed and shown messages are marked read. A shown
+//! quote has its signature checked against the sender.
+
+use std::time::Duration;
+
+use alloy::sol_types::Eip712Domain;
+use anyhow::Result;
+use tracing::debug;
+
+use super::CommandContext;
+use crate::clock;
+use crate::engine::identity;
+use crate::engine::inbox::{self, StoredMessage};
+use crate::engine::orders::{self, SignedQuote};
+use crate::engine::requests::format_price_usd;
+use crate::engine::template::format_date;
+use crate::ipfs::client::IpfsClient;
+use crate::ipfs::mailbox::{self, Mailbox, MailboxMessage};
+use crate::output::formatter;
+
+/// Characters of a message ID shown in listings; `--id` accepts them.
+const SHORT_ID_CHARS: usize = 8;
+
+/// Run the `messages` command.
+pub async fn run(id: Option<String>, unread: bool, wait: u64) -> Result<()> {
+    debug!(?id, unread, wait, "starting messages command");
+
+    // 1. Unlock the key that opens the messages
+    let ctx = CommandContext::load_initialized()?;
+
+    // 2. Fetch newly announced messages into the inbox
+    if wait > 0 {
+        let ipfs = IpfsClient::from_config(&ctx.cfg);
+        if let Err(err) = fetch_new(&ctx, &ipfs, Duration::from_secs(wait)).await {
+            debug!(error = %format!("{err:#}"), "mailbox fetch failed");
+            formatter::print_warning(
+                "Could not check for new messages. Showing messages already received.",
+            );
+        }
+    }
+    let messages = inbox::load_all()?;
+
+    // 3. Show one message in full
+    if let Some(id) = id {
+        let message = inbox::find(&messages, &id)?;
+        if formatter::is_json_mode() {
+            formatter::print_json(message)?;
+        } else {
+            print_message(message, &ctx.quote_domain(), clock::unix_now());
+        }
+        inbox::mark_read([message.id.as_str()])?;
+        return Ok(());
+    }
+
+    // 4. List the inbox
+    let shown: Vec<&StoredMessage> = messages.iter().filter(|m| !unread || !m.read).collect();
+    if formatter::is_json_mode() {
+        formatter::print_json(&shown)?;
+    } else {
+        print_list(&shown, unread);
+    }
+    inbox::mark_read(shown.iter().map(|m| m.id.as_str()))?;
+    Ok(())
+}
+
+/// Fetch and open every message announced during `wait` that is not yet
+/// in the inbox. Messages that cannot be fetched or opened are saved as
+/// unreadable.
+async fn fetch_new(ctx: &CommandContext, ipfs: &IpfsClient, wait: Duration) -> Result<()> {
+    let mailbox = Mailbox::new(&ctx.cfg.identity.public_key)?;
+    let now = clock::unix_now();
+
+    for cid in mailbox::fetch_announced(ipfs, &mailbox, wait).await? {
+        if inbox::contains(&cid)? {
+            continue;
+        }
+        let stored = match mailbox::retrieve_message(ipfs, &ctx.key_bytes, &cid).await {
+            Ok(message) => StoredMessage::opened(&cid, now, message),
+            Err(err) => {
+                debug!(cid = %cid, error = %format!("{err:#}"), "message unreadable");
+                StoredMessage::unreadable(&cid, now, format!("{err:#}"))
+            }
+        };
+        inbox::save(&stored)?;
+    }
+    Ok(())
+}
+
+fn short_id(id: &str) -> &str {
+    &id[id.len().saturating_sub(SHORT_ID_CHARS)..]
+}
+
+fn short_sender(message: &StoredMessage) -> String {
+    match &message.message {
+        Some(m) if m.sender.len() > 12 => format!("{}...", &m.sender[..10]),
+        Some(m) => m.sender.clone(),
+        None => "-".to_string(),
+    }
+}
+
+fn print_list(messages: &[&StoredMessage], unread: bool) {
+    if messages.is_empty() {
+        formatter::print_info(if unread {
+            "No unread messages."
+        } else {
+            "No messages."
+        });
+        return;
+    }
+
+    formatter::print_info(&format!(
+        "{:<8}  {:<13}  {:<12}  {:<16}  Preview",
+        "ID", "From", "Type", "Sent"
+    ));
+    for message in messages {
+        let kind = message
+            .message
+            .as_ref()
+            .map_or("unreadable", |m| m.message_type.as_str());
+        let marker = if message.read { ' ' } else { '*' };
+        formatter::print_info(&format!(
+            "{:<8}  {:<13}  {:<12}  {:<16}  {marker}{}",
+            short_id(&message.id),
+            short_sender(message),
+            kind,
+            format_date(message.sent_at(), "%Y-%m-%d %H:%M").unwrap_or_default(),
+            message.preview()
+        ));
+    }
+}
+
+fn print_message(stored: &StoredMessage, quote_domain: &Eip712Domain, now: u64) {
+    formatter::print_info(&format!("ID:       {}", stored.id));
+    let Some(message) = &stored.message else {
+        formatter::print_warning(&format!(
+            "This message could not be read: {}",
+            stored.error.as_deref().unwrap_or("unknown")
+        ));
+        return;
+    };
+    formatter::print_info(&format!("From:     {}", message.sender));
+    formatter::print_info(&format!("Type:     {}", message.message_type));
+    formatter::print_info(&format!(
+        "Sent:     {}",
+        format_date(message.timestamp, "%Y-%m-%d %H:%M:%S UTC").unwrap_or_default()
+    ));
+    formatter::print_info("");
+    match stored.payload_text() {
+        Some(text) => formatter::print_info(text),
+        None => formatter::print_info(&format!(
+            "<{} bytes of binary data> {}",
+            message.payload.len(),
+            hex::encode(&message.payload)
+        )),
+    }
+    if message.message_type == orders::QUOTE_MESSAGE_TYPE {
+        formatter::print_info("");
+        match describe_quote(message, quote_domain, now) {
+            (line, true) => formatter::print_success(&line),
+            (line, false) => formatter::print_warning(&line),
+        }
+    }
+}
+
+/// One line on a quote message: what it offers and whether it can be
+/// relied on. The flag is `true` only for a current quote signed by the
+/// agent that sent it.
+fn describe_quote(message: &MailboxMessage, domain: &Eip712Domain, now: u64) -> (String, bool) {
+    let Ok(signed) = SignedQuote::from_json(&message.payload) else {
+        return ("This quote could not be read.".to_string(), false);
+    };
+    let quote = &signed.quote;
+    let offer = format!(
+        "Quote: {} from {} until {}",
+        format_price_usd(quote.price_usdc),
+        quote.seller,
+        format_date(quote.deadline, "%Y-%m-%d %H:%M UTC").unwrap_or_default()
+    );
+
+    if !orders::verify_quote(&signed, domain).unwrap_or(false) {
+        return (
+            format!("{offer}. Its signature does not match the seller; do not rely on it."),
+            false,
+        );
+    }
+    let from_sender = identity::address_from_public_key(&message.sender)
+        .is_ok_and(|sender| sender == quote.seller.to_string());
+    if !from_sender {
+        return (
+            format!("{offer}. Signed by the seller, but sent by another agent."),
+            false,
+        );
+    }
+    if quote.is_expired(now) {
+        return (
+            format!("{offer}. Signed by the sender, but expired."),
+            false,
+        );
+    }
+    (format!("{offer}. Signed by the sender."), true)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::sink;
+
+    #[test]
+    fn test_describe_quote_checks_signature_sender_and_expiry() {
+        let key = hex::decode("ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80")
+            .unwrap();
+        let (public_key, address) = identity::address_from_key(&key).unwrap();
+        let domain = orders::domain(8453, alloy::primitives::Address::ZERO);
+        let quote = orders::Quote {
+            request_hash: orders::request_hash("42"),
+            price_usdc: 5_000_000,
+            deadline: 1_800_000_000,
+            seller: address.parse().unwrap(),
+        };
+        let signed = orders::sign_quote(&key, &quote, &domain).unwrap();
+        let message = |sender: &str, payload: Vec<u8>| MailboxMessage {
+            sender: sender.to_string(),
+            timestamp: 0,
+            message_type: orders::QUOTE_MESSAGE_TYPE.to_string(),
+            payload,
+        };
+        let good = message(&public_key, signed.to_json().unwrap().into_bytes());
+
+        let (line, ok) = describe_quote(&good, &domain, 1_700_000_000);
+        assert!(ok, "{line}");
+        assert!(line.starts_with("Quote: $5.00 from 0xf39F"), "{line}");
+        assert!(line.ends_with("Signed by the sender."), "{line}");
+
+        let (line, ok) = describe_quote(&good, &domain, 1_900_000_000);
+        assert!(!ok && line.ends_with("but expired."), "{line}");
+
+        let (line, ok) = describe_quote(&good, &orders::domain(84532, quote.seller), 0);
+        assert!(!ok && line.contains("does not match"), "{line}");
+
+        let (other_key, _) = identity::address_from_key(&[7u8; 32]).unwrap();
+        let relayed = message(&other_key, good.payload.clone());
+        let (line, ok) = describe_quote(&relayed, &domain, 0);
+        assert!(!ok && line.contains("sent by another agent"), "{line}");
+
+        let (line, ok) = describe_quote(&message(&public_key, b"5 dollars".to_vec()), &domain, 0);
+        assert!(!ok && line.contains("could not be read"), "{line}");
+    }
+
+    #[test]
+    fn test_list_marks_unread_and_unreadable() {
+        let mut seen = StoredMessage::opened(
+            "bafkreiabcdefgh12345678",
+            0,
+            MailboxMessage {
+                sender: "02abcdef0123456789".to_string(),
+                timestamp: 1_710_331_200,
+                message_type: "note".to_string(),
+                payload: b"hello".to_vec(),
+            },
+        );
+        seen.read = true;
+        let bad = StoredMessage::unreadable("bafkreizzzzzzzzzbadbadba", 1_710_331_260, "x".into());
+
+        let (_, capture) = sink::capture(|| print_list(&[&seen, &bad], false));
+        let lines = capture.out();
+        assert_eq!(lines.len(), 3);
+        assert!(
+            lines[1].starts_with("12345678  02abcdef01...  note"),
+            "{}",
+            lines[1]
+        );
+        assert!(lines[1].ends_with(" hello"), "{}", lines[1]);
+        assert!(lines[2].contains("unreadable"), "{}", lines[2]);
+        assert!(lines[2].ends_with("*unreadable: x"), "{}", lines[2]);
+    }
+}
@@ -1,18 +1,63 @@
+use std::future::Future;
+use std::io::{self, IsTerminal};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use alloy::primitives::B256;
+use alloy::sol_types::Eip712Domain;
+
 use anyhow::{bail, Result};
+use serde_json::json;
+use tracing::debug;
+use zeroize::Zeroizing;
 
+use crate::chain::client::ChainClient;
+use crate::chain::contracts::{self, ContractAddresses};
+use crate::chain::fees::{self, FeeQuote};
+use crate::chain::receipt::TxOutcome;
+use crate::chain::signer::TransactionSigner;
+use crate::chain::types::{FeeEstimate, FEE_DECIMALS};
+use crate::clock::{format_duration, unix_now};
 use crate::config;
+use crate::config::lock;
+use crate::config::machine::{self, SharingStatus};
+use crate::config::store::NetworkConfig;
+use crate::engine::archive;
+use crate::engine::attachments;
 use crate::engine::identity::{self, IdentityState};
+use crate::engine::orders::{self, Quote, SignedQuote};
+use crate::engine::requests;
+use crate::output::{formatter, prompt};
 
+pub mod bugreport;
+pub mod cancel;
 pub mod claim;
+pub mod config_cmd;
 pub mod daemon;
+pub mod doctor;
+pub mod expire;
 pub mod fund;
+pub mod history;
 pub mod init;
+pub mod key;
+pub mod list;
+pub mod locks;
+pub mod maintenance;
+pub mod messages;
+pub mod profile;
 pub mod register;
+pub mod reputation;
 pub mod request;
 pub mod respond;
 pub mod search;
+pub mod send;
+pub mod show;
+pub mod sign;
 pub mod status;
+pub mod trust;
+pub mod tx;
 pub mod validate;
+pub mod whoami;
 pub mod withdraw;
 
 /// Shared setup for commands that require an initialized and/or registered agent.
@@ -21,8 +66,13 @@ pub mod withdraw;
 /// loading the keystore, and deriving the agent address.
 pub struct CommandContext {
     pub cfg: config::store::Config,
+    /// The contracts on the configured network.
+    pub contracts: ContractAddresses,
     pub public_key: String,
     pub address: String,
+    /// Private key, kept so per-request secrets can be derived. Zeroed on
+    /// drop.
+    key_bytes: Zeroizing<Vec<u8>>,
 }
 
 impl CommandContext {
@@ -46,13 +96,16 @@ impl CommandContext {
         }
 
         let passphrase = config::keystore::get_passphrase()?;
-        let key_bytes = config::keystore::load_key(&passphrase)?;
+        let key_bytes = Zeroizing::new(config::keystore::load_key(&passphrase)?);
         let (public_key, address) = identity::address_from_key(&key_bytes)?;
+        let contracts = contracts::for_config(&cfg)?;
 
         Ok(Self {
             cfg,
+            contracts,
             public_key,
             address,
+            key_bytes,
         })
     }
 
@@ -65,13 +118,477 @@ impl CommandContext {
 
         let cfg = config::store::load()?;
         let passphrase = config::keystore::get_passphrase()?;
-        let key_bytes = config::keystore::load_key(&passphrase)?;
+        let key_bytes = Zeroizing::new(config::keystore::load_key(&passphrase)?);
         let (public_key, address) = identity::address_from_key(&key_bytes)?;
+        let contracts = contracts::for_config(&cfg)?;
 
         Ok(Self {
             cfg,
+            contracts,
             public_key,
             address,
+            key_bytes,
+        })
+    }
+
+    /// Derive the hash-lock secret for `request_id` from this agent's key
+    /// (see [`requests::derive_secret`]).
+    pub fn derive_secret(&self, request_id: &str) -> (String, String) {
+        requests::derive_secret(&self.key_bytes, request_id)
+    }
+
+    /// Sign `message` with this agent's key (see
+    /// [`identity::sign_message`]).
+    pub fn sign_message(&self, message: &[u8]) -> Result<String> {
+        identity::sign_message(&self.key_bytes, message)
+    }
+
+    /// Sign `quote` with this agent's key under the AgentMarket EIP-712
+    /// domain of the configured network (see [`orders::sign_quote`]).
+    pub fn sign_quote(&self, quote: &Quote) -> Result<SignedQuote> {
+        orders::sign_quote(&self.key_bytes, quote, &self.quote_domain())
+    }
+
+    /// A signer for transactions from this agent.
+    pub fn transaction_signer(&self) -> Result<TransactionSigner> {
+        TransactionSigner::from_bytes(&mut Zeroizing::new(self.key_bytes.to_vec()))
+    }
+
+    /// The EIP-712 domain quotes are signed and checked under: the
+    /// configured chain and its Request Registry.
+    pub fn quote_domain(&self) -> Eip712Domain {
+        orders::domain(
+            self.cfg.network.expected_chain_id,
+            self.contracts.request_registry,
+        )
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Shared-home guard
+// ---------------------------------------------------------------------------
+
+/// How a mutating command was allowed to proceed by [`guard_shared_home`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum HomeGuard {
+    /// This machine owns the agent home (or just claimed it on first use).
+    Owned,
+    /// Another machine owns or recently used the home; the user was warned.
+    Warned,
+    /// Ownership was taken over from another machine.
+    TookOver,
+}
+
+/// Check that no other machine is driving this agent home before a mutating
+/// command runs, then record this machine as the last writer.
+///
+/// When another machine owns the home or wrote to it recently, a warning is
+/// printed — or, with `sharing.strict` enabled, the command is refused.
+/// `takeover` makes this machine the owner by rotating the install ID, which
+/// also stops a daemon running on the other machine at its next cycle, and
+/// releases the locks the other machine held so they do not block this one.
+pub fn guard_shared_home(command: &str, takeover: bool) -> Result<HomeGuard> {
+    let cfg = config::store::load()?;
+    let status = machine::sharing_status(cfg.sharing.recent_window_secs)?;
+    let strict = cfg.sharing.strict;
+
+    let outcome = match status {
+        SharingStatus::Unstamped => {
+            machine::claim_home()?;
+            HomeGuard::Owned
+        }
+        SharingStatus::Exclusive => HomeGuard::Owned,
+        SharingStatus::Foreign { .. } | SharingStatus::Conflict { .. } if takeover => {
+            machine::claim_home()?;
+            for (name, info) in lock::break_foreign()? {
+                debug!(lock = %name, host = %info.hostname, "released lock held by the previous owner");
+            }
+            formatter::print_warning(
+                "This machine now owns the agent home. \
+                 A daemon running on the other machine will stop at its next cycle.",
+            );
+            HomeGuard::TookOver
+        }
+        SharingStatus::Foreign { owner } => {
+            let msg = format!(
+                "This agent home was set up on another machine ({}). \
+                 Running one agent from two machines can corrupt local state.",
+                owner.hostname
+            );
+            if strict {
+                bail!("{msg} Re-run with --takeover to make this machine the owner.");
+            }
+            formatter::print_warning(&format!(
+                "{msg} Re-run with --takeover to make this machine the owner."
+            ));
+            HomeGuard::Warned
+        }
+        SharingStatus::Conflict { writer } => {
+            let msg = format!(
+                "Another machine ({}) ran `{}` on this agent home {} ago. \
+                 Running one agent from two machines can corrupt local state.",
+                writer.hostname,
+                writer.command,
+                format_duration(unix_now().saturating_sub(writer.timestamp)),
+            );
+            if strict {
+                bail!("{msg} Stop it there, or re-run with --takeover.");
+            }
+            formatter::print_warning(&format!("{msg} Stop it there, or re-run with --takeover."));
+            HomeGuard::Warned
+        }
+    };
+
+    machine::record_write(command)?;
+    Ok(outcome)
+}
+
+/// Whether `--strict-fees` was passed.
+static STRICT_FEES: AtomicBool = AtomicBool::new(false);
+
+/// Abort money-moving commands whose network fee cannot be estimated (the
+/// global `--strict-fees` flag).
+pub fn set_strict_fees(enabled: bool) {
+    STRICT_FEES.store(enabled, Ordering::Relaxed);
+}
+
+/// Estimate the network fee for `what` (e.g. "claim"). If that fails the
+/// command warns and carries on without an estimate, unless
+/// `--strict-fees` was passed.
+async fn estimate_fee(
+    what: &str,
+    estimate: impl Future<Output = Result<FeeEstimate>>,
+) -> Result<Option<FeeEstimate>> {
+    match estimate.await {
+        Ok(estimate) => Ok(Some(estimate)),
+        Err(err) => {
+            debug!(what, error = %format!("{err:#}"), "fee estimate failed");
+            if STRICT_FEES.load(Ordering::Relaxed) {
+                return Err(err.context(format!(
+                    "Could not estimate the {what} fee, and --strict-fees is set"
+                )));
+            }
+            formatter::print_warning(&format!("Could not estimate the {what} fee."));
+            Ok(None)
+        }
+    }
+}
+
+/// The fee with its approximate dollar figure at `eth_usd`, or
+/// "unavailable".
+fn describe_fee(estimate: Option<&FeeEstimate>, eth_usd: f64) -> String {
+    estimate.map_or_else(|| "unavailable".to_string(), |e| e.display(eth_usd))
+}
+
+/// Show the fee before a confirmation prompt or a transaction is sent.
+fn print_fee(estimate: Option<&FeeEstimate>, eth_usd: f64) {
+    formatter::print_info(&format!(
+        "Network fee: {} (paid from your ETH balance)",
+        describe_fee(estimate, eth_usd)
+    ));
+}
+
+/// The fee for `--dry-run --json` output; `null` when it is unavailable.
+fn fee_json(estimate: Option<&FeeEstimate>, eth_usd: f64) -> serde_json::Value {
+    let Some(estimate) = estimate else {
+        return serde_json::Value::Null;
+    };
+    json!({
+        "gas": estimate.gas,
+        "max_fee_per_gas_wei": estimate.max_fee_per_gas_wei.to_string(),
+        "total_wei": estimate.cost().wei.to_string(),
+        "total_eth": estimate.cost().display_eth_with(FEE_DECIMALS).trim_end_matches(" ETH"),
+        "approx_usd": (eth_usd > 0.0).then(|| estimate.approx_usd(eth_usd)),
+    })
+}
+
+/// Ask before sending the `what` transaction at fees over
+/// `fees.max_fee_gwei_cap`: the `approve_fee` of interactive commands.
+fn confirm_fee_over_cap(what: &str, quote: &FeeQuote) -> Result<()> {
+    prompt::confirm_funds(&format!(
+        "The {what} may cost up to {} per gas, above your cap of {}",
+        fees::display_gwei(quote.max_fee_per_gas_wei),
+        fees::display_gwei(quote.cap_wei.unwrap_or_default()),
+    ))
+}
+
+/// Wait for the `what` transaction `tx_hash` to be confirmed as configured
+/// in `[network]`, before local state is updated to match it. Returns
+/// `Ok(true)` once it succeeded; `Ok(false)` if it is still unconfirmed at
+/// the timeout, after saying how to check on it later; and an error with
+/// the revert reason if it reverted. Local state must be left alone unless
+/// this returns `Ok(true)`.
+pub async fn await_confirmation(
+    client: &ChainClient,
+    network: &NetworkConfig,
+    tx_hash: B256,
+    what: &str,
+) -> Result<bool> {
+    let timeout = Duration::from_secs(network.receipt_timeout_secs);
+    match client
+        .wait_for_receipt(tx_hash, network.confirmations, timeout)
+        .await?
+    {
+        TxOutcome::Success { block_number, .. } => {
+            debug!(%tx_hash, block_number, "{what} transaction confirmed");
+            Ok(true)
+        }
+        TxOutcome::Reverted { reason, .. } => {
+            let reason = reason.map_or_else(String::new, |r| format!(": {r}"));
+            bail!(
+                "The {what} transaction {tx_hash} was rejected by the contract{reason}. \
+                 Nothing was changed locally."
+            )
+        }
+        TxOutcome::TimedOut => {
+            formatter::print_warning(&format!(
+                "The {what} transaction {tx_hash} was sent but not confirmed within {}s. \
+                 Nothing was changed locally; run this command again later to record it, \
+                 or look the transaction up on a block explorer.",
+                network.receipt_timeout_secs
+            ));
+            Ok(false)
+        }
+    }
+}
+
+/// An `ipfs://` URI as long as a real CIDv1 in base32, for estimating a
+/// fee before anything is uploaded.
+fn placeholder_ipfs_uri() -> String {
+    const TYPICAL_CID_LEN: usize = 59;
+    format!("ipfs://{}", "b".repeat(TYPICAL_CID_LEN))
+}
+
+/// Pack `dir` for sending, warning about any symlinks left out.
+fn pack_dir(dir: &str, max_bytes: u64) -> Result<archive::DirArchive> {
+    let packed = archive::archive_dir(std::path::Path::new(dir), max_bytes)?;
+    if !packed.skipped_symlinks.is_empty() {
+        let names: Vec<String> = packed
+            .skipped_symlinks
+            .iter()
+            .map(|p| p.display().to_string())
+            .collect();
+        formatter::print_warning(&format!(
+            "Skipped {} symlink{} in {dir}: {}",
+            names.len(),
+            if names.len() == 1 { "" } else { "s" },
+            names.join(", ")
+        ));
+    }
+    formatter::print_info(&format!(
+        "Packed {dir}: {} files, {} ({} compressed).",
+        packed.info.file_count,
+        attachments::format_size(packed.info.uncompressed_size),
+        attachments::format_size(packed.bytes.len() as u64)
+    ));
+    Ok(packed)
+}
+
+/// Offer to extract an archive received from another agent into a new
+/// temporary directory. Only asked on a terminal.
+fn offer_extract(what: &str, archive: &[u8], label: &str) -> Result<()> {
+    let stdin = io::stdin();
+    if formatter::is_json_mode() || prompt::non_interactive() || !stdin.is_terminal() {
+        return Ok(());
+    }
+    if prompt::confirm(
+        &format!("Extract {what} into a temporary directory?"),
+        &mut stdin.lock(),
+    )? {
+        let dest = archive::extract_to_temp(archive, label)?;
+        formatter::print_success(&format!("Extracted to {}", dest.display()));
+    }
+    Ok(())
+}
+
+/// How long a submitted transaction may take to confirm before a re-run of
+/// `register` or `respond` submits it again.
+const RESUBMIT_AFTER_SECS: u64 = 10 * 60;
+
+/// The submission time of a step checkpointed at `submitted_at`, if it is
+/// recent enough that a re-run should wait for it rather than submit again.
+fn awaiting_confirmation(submitted_at: Option<u64>, now: u64) -> Option<u64> {
+    submitted_at.filter(|since| now.saturating_sub(*since) < RESUBMIT_AFTER_SECS)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::machine::{MachineStamp, WriterMarker};
+    use std::env;
+    use std::sync::Mutex;
+
+    /// Mutex to serialise tests that mutate environment variables.
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    /// Helper: point `AGENTMARKET_HOME` at a temp dir containing a default
+    /// config for the duration of the closure.
+    fn with_temp_home<F: FnOnce()>(f: F) {
+        let _guard = ENV_LOCK.lock().expect("env lock poisoned");
+
+        let tmp = tempfile::tempdir().expect("failed to create temp dir");
+        let prev = env::var("AGENTMARKET_HOME").ok();
+
+        env::set_var("AGENTMARKET_HOME", tmp.path());
+        config::store::save(&config::store::Config::default()).expect("save config");
+        f();
+
+        match prev {
+            Some(v) => env::set_var("AGENTMARKET_HOME", v),
+            None => env::remove_var("AGENTMARKET_HOME"),
+        }
+    }
+
+    /// Simulate a second machine by overwriting the stamp and marker.
+    fn stamp_other_machine(recent: bool) {
+        machine::save_stamp(&MachineStamp {
+            hostname: "other-machine".to_string(),
+            install_id: "feedface".to_string(),
+            created_at: 1_000,
         })
+        .expect("save stamp");
+
+        let timestamp = if recent { unix_now() - 30 } else { 1_000 };
+        let marker = WriterMarker {
+            hostname: "other-machine".to_string(),
+            install_id: "feedface".to_string(),
+            command: "daemon".to_string(),
+            timestamp,
+        };
+        let path = config::store::config_dir()
+            .expect("config dir")
+            .join("last_writer.json");
+        std::fs::write(path, serde_json::to_string(&marker).unwrap()).expect("write marker");
+    }
+
+    fn set_strict(strict: bool) {
+        let mut cfg = config::store::load().expect("load config");
+        cfg.sharing.strict = strict;
+        config::store::save(&cfg).expect("save config");
+    }
+
+    #[test]
+    fn test_awaiting_confirmation_window() {
+        let now = 1_700_000_000;
+        assert_eq!(awaiting_confirmation(None, now), None);
+        assert_eq!(awaiting_confirmation(Some(now - 60), now), Some(now - 60));
+        assert_eq!(
+            awaiting_confirmation(Some(now - RESUBMIT_AFTER_SECS), now),
+            None
+        );
+    }
+
+    #[test]
+    fn test_first_use_stamps_home() {
+        with_temp_home(|| {
+            assert_eq!(
+                guard_shared_home("request", false).unwrap(),
+                HomeGuard::Owned
+            );
+
+            let stamp = machine::load_stamp().unwrap().expect("stamp written");
+            assert_eq!(stamp.hostname, machine::current_hostname());
+
+            let writer = machine::load_last_writer()
+                .unwrap()
+                .expect("marker written");
+            assert_eq!(writer.command, "request");
+            assert_eq!(writer.install_id, stamp.install_id);
+
+            // Subsequent runs on the same machine are quiet.
+            assert_eq!(guard_shared_home("claim", false).unwrap(), HomeGuard::Owned);
+        });
+    }
+
+    #[test]
+    fn test_recent_other_machine_warns() {
+        with_temp_home(|| {
+            stamp_other_machine(true);
+            assert_eq!(
+                guard_shared_home("respond", false).unwrap(),
+                HomeGuard::Warned
+            );
+        });
+    }
+
+    #[test]
+    fn test_quiet_other_machine_warns() {
+        with_temp_home(|| {
+            stamp_other_machine(false);
+            assert_eq!(
+                guard_shared_home("respond", false).unwrap(),
+                HomeGuard::Warned
+            );
+        });
+    }
+
+    #[test]
+    fn test_strict_mode_refuses() {
+        with_temp_home(|| {
+            set_strict(true);
+            stamp_other_machine(true);
+
+            let err = guard_shared_home("respond", false).expect_err("strict must refuse");
+            assert!(err.to_string().contains("--takeover"));
+
+            // Refusal does not overwrite the other machine's marker.
+            let writer = machine::load_last_writer().unwrap().unwrap();
+            assert_eq!(writer.hostname, "other-machine");
+        });
+    }
+
+    #[test]
+    fn test_takeover_rotates_install_id() {
+        with_temp_home(|| {
+            set_strict(true);
+            stamp_other_machine(true);
+
+            assert_eq!(
+                guard_shared_home("daemon", true).unwrap(),
+                HomeGuard::TookOver
+            );
+
+            let stamp = machine::load_stamp().unwrap().unwrap();
+            assert_eq!(stamp.hostname, machine::current_hostname());
+            assert_ne!(stamp.install_id, "feedface");
+
+            // After the takeover this machine is the owner and last writer.
+            assert_eq!(guard_shared_home("claim", false).unwrap(), HomeGuard::Owned);
+        });
+    }
+
+    #[test]
+    fn test_takeover_releases_the_other_machines_daemon_lock() {
+        with_temp_home(|| {
+            stamp_other_machine(true);
+            // The other machine's daemon took its lock under its install ID.
+            let path = config::store::config_dir()
+                .unwrap()
+                .join("locks")
+                .join("daemon.lock");
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            let foreign = lock::LockInfo {
+                pid: 1,
+                start_time: None,
+                hostname: "other-machine".to_string(),
+                install_id: Some("feedface".to_string()),
+                command: "daemon".to_string(),
+                acquired_at: 1_000,
+                nonce: "00".to_string(),
+            };
+            std::fs::write(&path, serde_json::to_string(&foreign).unwrap()).unwrap();
+            let err = lock::acquire(lock::DAEMON_LOCK, "daemon").unwrap_err();
+            assert!(err.to_string().contains("other-machine"), "{err}");
+
+            assert_eq!(
+                guard_shared_home("daemon", true).unwrap(),
+                HomeGuard::TookOver
+            );
+            let _daemon = lock::acquire(lock::DAEMON_LOCK, "daemon").expect("daemon starts");
+        });
     }
 }
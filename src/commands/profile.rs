@@ -0,0 +1,134 @@
+//! The `profile` command: change the agent's published profile.
+//!
+//! `profile update` rewrites the description, capabilities, or price in
+//! `config.toml` and `profile.json`. For a registered agent the new profile
+//! is uploaded to IPFS and its CID recorded in `identity.ipfs_profile_cid`.
+//! The AgentRegistry has no way to change an agent's URI after
+//! registration yet, so the on-chain record keeps pointing at the profile
+//! published at registration time.
+
+use std::io;
+
+use anyhow::{bail, Context, Result};
+use tracing::debug;
+
+use super::CommandContext;
+use crate::config;
+use crate::engine::identity::{self, FieldChange, IdentityState, ProfileChanges};
+use crate::engine::requests::parse_usd_amount;
+use crate::ipfs::client::IpfsClient;
+use crate::ipfs::pin::PinningService;
+use crate::output::{formatter, prompt};
+
+/// `profile update`: apply the given changes after showing a diff, asking
+/// first unless `--yes` was given.
+pub async fn update(
+    description: Option<String>,
+    capabilities: Option<String>,
+    price: Option<String>,
+) -> Result<()> {
+    debug!("starting profile update command");
+
+    // 1. Check initialized
+    if !config::store::exists()? {
+        bail!("Agent not initialized. Run `agentmarket init` first.");
+    }
+    let mut cfg = config::store::load()?;
+
+    // 2. Parse the requested changes
+    let changes = ProfileChanges {
+        description,
+        capabilities: capabilities.as_deref().map(identity::parse_capabilities),
+        pricing_usd: price
+            .map(|p| parse_usd_amount(&p).map(|usdc| usdc as f64 / 1_000_000.0))
+            .transpose()?,
+    };
+    if changes.is_empty() {
+        bail!("Nothing to update. Pass --description, --capabilities, or --price.");
+    }
+
+    // 3. Load the current profile, rebuilding it from config if the file is
+    //    missing.
+    let current = match identity::load_profile() {
+        Ok(profile) => profile,
+        Err(err) => {
+            debug!(error = %err, "profile file unavailable — rebuilding from config");
+            let ctx = CommandContext::load_initialized()?;
+            identity::create_profile(
+                &cfg.agent.name,
+                &cfg.agent.description,
+                cfg.services.capabilities.clone(),
+                cfg.services.pricing_usd,
+                &ctx.public_key,
+                &ctx.address,
+            )
+        }
+    };
+    let updated = identity::apply_profile_changes(&current, &changes);
+
+    // 4. Show the diff and confirm
+    let diff = identity::diff_profiles(&current, &updated);
+    if diff.is_empty() {
+        formatter::print_info("Profile is already up to date.");
+        return Ok(());
+    }
+    print_diff(&diff);
+    let stdin = io::stdin();
+    if !prompt::confirm("Apply these changes?", &mut stdin.lock())? {
+        formatter::print_warning("Profile not changed.");
+        return Ok(());
+    }
+
+    // 5. Save locally
+    cfg.agent.description = updated.description.clone();
+    cfg.services.capabilities = updated.capabilities.clone();
+    cfg.services.pricing_usd = updated.pricing_usd;
+    config::store::save(&cfg)?;
+    identity::save_profile(&updated)?;
+    debug!("config and profile saved");
+
+    // 6. Publish, if the agent has already published a profile
+    if !matches!(
+        identity::get_identity_state(&cfg),
+        IdentityState::Registered { .. }
+    ) {
+        formatter::print_success("Profile updated.");
+        formatter::print_info("It will be published when you run `agentmarket register`.");
+        return Ok(());
+    }
+
+    let profile_json =
+        serde_json::to_string_pretty(&updated).context("failed to serialize agent profile")?;
+    let cid = IpfsClient::from_config(&cfg)
+        .add(profile_json.as_bytes())
+        .await
+        .context("failed to upload profile to content network")?;
+    debug!(cid = %cid, "profile uploaded to IPFS");
+
+    if let Some(pinner) = PinningService::from_env() {
+        if let Err(err) = pinner.pin_by_hash(&cid).await {
+            debug!(error = %err, "remote pinning failed (non-fatal)");
+            formatter::print_warning(
+                "Could not pin profile remotely. It is still available on the local node.",
+            );
+        }
+    }
+
+    cfg.identity.ipfs_profile_cid = cid.clone();
+    config::store::save(&cfg)?;
+    debug!("config saved with new ipfs_profile_cid");
+
+    formatter::print_success(&format!("Profile updated. CID: {cid}"));
+    Ok(())
+}
+
+/// Print each changed field as `field: old -> new`.
+fn print_diff(diff: &[FieldChange]) {
+    formatter::print_info("Profile changes:");
+    for change in diff {
+        formatter::print_info(&format!(
+            "  {}: \"{}\" -> \"{}\"",
+            change.field, change.old, change.new
+        ));
+    }
+}
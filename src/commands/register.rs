@@ -5,24 +5,34 @@
 //! If the AgentRegistry contract is not yet deployed (address is zero),
 //! the profile is still uploaded and the CID is saved to config so the
 //! user does not have to re-upload later.
+//!
+//! Re-running is safe: an address that is already registered is adopted
+//! into config, and a checkpoint skips steps an interrupted run finished.
+//!
+//! With `--dry-run`, nothing is uploaded, sent, or saved: the command shows
+//! the profile that would be published, the registry, the estimated fee,
+//! and whether the balance is enough, failing if it is not.
 
 use alloy::primitives::Address;
 use anyhow::{bail, Context, Result};
+use serde_json::json;
 use tracing::debug;
 
 use crate::chain::client::ChainClient;
-use crate::chain::contracts::addresses;
-use crate::chain::contracts::AgentRegistry;
+use crate::chain::contracts::Contract;
 use crate::chain::signer::TransactionSigner;
-use crate::chain::types::Balance;
+use crate::chain::types::{Balance, FeeEstimate};
+use crate::clock;
 use crate::config;
-use crate::engine::identity::{self, IdentityState};
+use crate::config::store::Config;
+use crate::engine::identity::{self, AgentProfile, IdentityState};
+use crate::engine::registration::{self, Checkpoint};
 use crate::ipfs::client::IpfsClient;
 use crate::ipfs::pin::PinningService;
 use crate::output::formatter;
 
-pub async fn run() -> Result<()> {
-    debug!("starting register command");
+pub async fn run(dry_run: bool) -> Result<()> {
+    debug!(dry_run, "starting register command");
 
     // 1. Check that agent is initialized (config exists).
     if !config::store::exists()? {
@@ -46,30 +56,19 @@ pub async fn run() -> Result<()> {
         }
     }
 
+    if dry_run {
+        return preview(&cfg).await;
+    }
+
     // 3. Load keystore, derive address.
     let passphrase = config::keystore::get_passphrase()?;
     let key_bytes = config::keystore::load_key(&passphrase)?;
     let (public_key, address) = identity::address_from_key(&key_bytes)?;
+    let signer = TransactionSigner::from_keystore_with_passphrase(&passphrase)?;
 
     debug!(address = %address, "agent address derived");
 
-    // 4. Check ETH balance — if insufficient, show funding instructions and bail.
-    let client = ChainClient::new(&cfg.network.chain_rpc).await?;
-    let addr: Address = address.parse().context("failed to parse agent address")?;
-    let balance_wei = client.get_eth_balance(addr).await?;
-    let balance = Balance { wei: balance_wei };
-
-    debug!(balance = %balance.display_eth(), "balance retrieved");
-
-    if !balance.is_sufficient_for_registration() {
-        formatter::print_warning("Insufficient funds for registration.");
-        formatter::print_funding_instructions(&address, "0.0001 ETH");
-        bail!("Insufficient funds. Send ETH to your agent address and try again.");
-    }
-
-    formatter::print_info("Preparing agent profile...");
-
-    // 5. Build and upload agent profile to IPFS.
+    // 4. Build the profile and keep a local copy for reference.
     let profile = identity::create_profile(
         &cfg.agent.name,
         &cfg.agent.description,
@@ -78,16 +77,216 @@ pub async fn run() -> Result<()> {
         &public_key,
         &address,
     );
-
-    // Save profile locally for reference.
     identity::save_profile(&profile)?;
     debug!("profile saved locally");
 
+    // 5. Run the remaining steps, then record what they produced.
+    let client = ChainClient::from_config(&cfg).await?;
+    let ipfs_client = IpfsClient::from_config(&cfg);
+    let outcome = register_steps(
+        &mut cfg,
+        &client,
+        &ipfs_client,
+        client.contracts().agent_registry,
+        &signer,
+        &profile,
+    )
+    .await?;
+    config::store::save(&cfg)?;
+    debug!(?outcome, "config saved");
+
+    // 6. Display the result (zero-crypto UX).
+    match outcome {
+        Outcome::Adopted { agent_id } => formatter::print_success(&format!(
+            "Agent is already registered (ID: {agent_id}). Saved to config."
+        )),
+        Outcome::Deferred => {
+            formatter::print_warning(&format!(
+                "Registration is not yet available on {}. \
+                 Registration will be available soon. {}",
+                client.contracts().network,
+                Contract::AgentRegistry.setup_hint()
+            ));
+            formatter::print_info(
+                "Your profile has been saved and will be used when registration opens.",
+            );
+            formatter::print_success(&format!(
+                "Profile ready. CID: {}",
+                cfg.identity.ipfs_profile_cid
+            ));
+        }
+        Outcome::Registered { agent_id } => formatter::print_success(&format!(
+            "Agent \"{}\" registered (ID: {agent_id}).",
+            cfg.agent.name,
+        )),
+        Outcome::Submitted => formatter::print_success(&format!(
+            "Agent \"{}\" profile uploaded. Registration pending confirmation.",
+            cfg.agent.name,
+        )),
+        Outcome::Pending { since } => formatter::print_info(&format!(
+            "Registration was submitted {} ago and is awaiting confirmation. \
+             Run `agentmarket register` again later.",
+            clock::format_duration(clock::unix_now().saturating_sub(since))
+        )),
+    }
+
+    Ok(())
+}
+
+/// How [`register_steps`] ended.
+#[derive(Debug, PartialEq, Eq)]
+enum Outcome {
+    /// The address was already registered; its ID is now in config.
+    Adopted { agent_id: String },
+    /// The registry is not yet deployed; the uploaded profile is in config.
+    Deferred,
+    /// The registration was confirmed; its ID is now in config.
+    Registered { agent_id: String },
+    /// The registration was submitted and awaits confirmation.
+    Submitted,
+    /// An earlier run submitted the registration recently; it was not sent
+    /// again.
+    Pending { since: u64 },
+}
+
+/// Register `profile` for the signer's address, skipping steps an earlier
+/// run finished (see [`registration`]). Updates `cfg` but does not save it.
+///
+/// Steps, in order:
+/// 1. Adopt an existing registration of the address.
+/// 2. Wait on a registration submitted less than
+///    [`RESUBMIT_AFTER_SECS`](super::RESUBMIT_AFTER_SECS) ago.
+/// 3. Check the balance covers registration.
+/// 4. Upload (and pin) the profile, unless already uploaded.
+/// 5. Submit the registration, unless the registry is not yet deployed,
+///    and wait for it to be confirmed.
+async fn register_steps(
+    cfg: &mut Config,
+    chain: &ChainClient,
+    ipfs: &IpfsClient,
+    registry: Address,
+    signer: &TransactionSigner,
+    profile: &AgentProfile,
+) -> Result<Outcome> {
+    let registry_deployed = registry != Address::ZERO;
+
+    // 1. A registration that completed after an earlier run failed (or was
+    //    made elsewhere) only needs recording.
+    if registry_deployed {
+        if let Some(agent_id) = chain.get_agent_of(registry, signer.address()).await? {
+            let uri = chain.get_agent_uri(registry, agent_id).await?;
+            debug!(%agent_id, ?uri, "address already registered; adopting");
+            cfg.identity.agent_id = agent_id.to_string();
+            if let Some(cid) = uri.as_deref().and_then(|u| u.strip_prefix("ipfs://")) {
+                cfg.identity.ipfs_profile_cid = cid.to_string();
+            }
+            registration::clear()?;
+            return Ok(Outcome::Adopted {
+                agent_id: agent_id.to_string(),
+            });
+        }
+    }
+
     let profile_json =
-        serde_json::to_string_pretty(&profile).context("failed to serialize agent profile")?;
+        serde_json::to_string_pretty(profile).context("failed to serialize agent profile")?;
+    let mut checkpoint = Checkpoint::resume(
+        registration::load()?,
+        &profile.public_key,
+        &registration::profile_digest(&profile_json),
+    );
 
-    let ipfs_client = IpfsClient::from_config(&cfg);
-    let cid = ipfs_client
+    // 2. Don't submit twice while the first is still being confirmed.
+    if let Some(since) = super::awaiting_confirmation(checkpoint.submitted_at, clock::unix_now()) {
+        if registry_deployed {
+            if let Some(cid) = &checkpoint.profile_cid {
+                cfg.identity.ipfs_profile_cid = cid.clone();
+            }
+            return Ok(Outcome::Pending { since });
+        }
+    }
+
+    // 3. Check ETH balance — if insufficient, show funding instructions and bail.
+    let balance = Balance {
+        wei: chain.get_eth_balance(signer.address()).await?,
+    };
+    debug!(balance = %balance.display_eth(), "balance retrieved");
+
+    if !balance.is_sufficient_for_registration() {
+        formatter::print_warning("Insufficient funds for registration.");
+        formatter::print_funding_instructions(&profile.address, "0.0001 ETH");
+        bail!("Insufficient funds. Send ETH to your agent address and try again.");
+    }
+
+    // 4. Upload the profile to IPFS and pin it, once per profile.
+    let cid = match checkpoint.profile_cid.clone() {
+        Some(cid) => {
+            debug!(cid = %cid, "profile uploaded by an earlier run");
+            formatter::print_info("Profile already uploaded.");
+            cid
+        }
+        None => {
+            formatter::print_info("Preparing agent profile...");
+            let cid = upload_profile(ipfs, &profile_json).await?;
+            checkpoint.profile_cid = Some(cid.clone());
+            registration::save(&checkpoint)?;
+            cid
+        }
+    };
+    cfg.identity.ipfs_profile_cid = cid.clone();
+
+    if !registry_deployed {
+        // Contract is not yet deployed — the CID in config and the
+        // checkpoint mean the profile is not uploaded again once it is.
+        debug!("registry not yet deployed; profile ready");
+        return Ok(Outcome::Deferred);
+    }
+
+    // 5. On-chain registration via AgentRegistry contract.
+    let agent_uri = format!("ipfs://{cid}");
+    let estimate = super::estimate_fee(
+        "registration",
+        chain.estimate_register_cost(registry, signer.address(), &agent_uri),
+    )
+    .await?;
+    super::print_fee(estimate.as_ref(), cfg.fees.eth_usd);
+
+    debug!(
+        contract = %registry,
+        agent_uri = %agent_uri,
+        "submitting registration transaction"
+    );
+    formatter::print_info("Submitting registration...");
+    let tx_hash = chain
+        .register(registry, signer, &agent_uri, |quote| {
+            super::confirm_fee_over_cap("registration", quote)
+        })
+        .await?;
+
+    // The agent ID is adopted by step 1 of a later run if this one gives up
+    // waiting; the checkpoint stops that run from submitting again.
+    checkpoint.submitted_at = Some(clock::unix_now());
+    registration::save(&checkpoint)?;
+    if !super::await_confirmation(chain, &cfg.network, tx_hash, "registration").await? {
+        return Ok(Outcome::Submitted);
+    }
+
+    let agent_id = chain
+        .get_agent_of(registry, signer.address())
+        .await?
+        .context(
+            "the registration was confirmed, but the registry has no agent for this address",
+        )?;
+    cfg.identity.agent_id = agent_id.to_string();
+    registration::clear()?;
+    Ok(Outcome::Registered {
+        agent_id: agent_id.to_string(),
+    })
+}
+
+/// Upload the profile and pin it via the remote pinning service, if one is
+/// configured. Returns the CID.
+async fn upload_profile(ipfs: &IpfsClient, profile_json: &str) -> Result<String> {
+    let cid = ipfs
         .add(profile_json.as_bytes())
         .await
         .context("failed to upload profile to content network")?;
@@ -95,7 +294,6 @@ pub async fn run() -> Result<()> {
     debug!(cid = %cid, "profile uploaded to IPFS");
     formatter::print_info("Profile uploaded to content network.");
 
-    // 6. Optionally pin via remote pinning service (if configured).
     if let Some(pinner) = PinningService::from_env() {
         debug!("remote pinning service configured — pinning profile");
         match pinner.pin_by_hash(&cid).await {
@@ -113,66 +311,289 @@ pub async fn run() -> Result<()> {
     } else {
         debug!("no remote pinning service configured — skipping remote pin");
     }
+    Ok(cid)
+}
 
-    // 7. On-chain registration via AgentRegistry contract.
-    let agent_uri = format!("ipfs://{cid}");
+/// `register --dry-run`: show what registering would publish and cost,
+/// without unlocking the key or writing anything. Fails if the balance is
+/// too low to register.
+async fn preview(cfg: &Config) -> Result<()> {
+    // 1. Build the profile from the public identity alone.
+    let address = identity::address_from_public_key(&cfg.identity.public_key)?;
+    let profile = identity::create_profile(
+        &cfg.agent.name,
+        &cfg.agent.description,
+        cfg.services.capabilities.clone(),
+        cfg.services.pricing_usd,
+        &cfg.identity.public_key,
+        &address,
+    );
+    let profile_json =
+        serde_json::to_value(&profile).context("failed to serialize agent profile")?;
 
-    if addresses::AGENT_REGISTRY == Address::ZERO {
-        // Contract is not yet deployed — save the profile CID to config
-        // so the user does not have to re-upload once it is available.
-        formatter::print_warning(
-            "Registration is not yet available. \
-             Registration will be available soon.",
-        );
+    // 2. Check the balance.
+    let client = ChainClient::from_config(cfg).await?;
+    let addr: Address = address.parse().context("failed to parse agent address")?;
+    let balance = Balance {
+        wei: client.get_eth_balance(addr).await?,
+    };
+    let sufficient = balance.is_sufficient_for_registration();
+    debug!(balance = %balance.display_eth(), sufficient, "balance retrieved");
+
+    // 3. Estimate the fee, once the registry exists to estimate against.
+    let registry = client.contracts().agent_registry;
+    let registry_deployed = !registry.is_zero();
+    let estimate = if registry_deployed {
+        let uri = super::placeholder_ipfs_uri();
+        super::estimate_fee(
+            "registration",
+            client.estimate_register_cost(registry, addr, &uri),
+        )
+        .await?
+    } else {
+        None
+    };
+
+    // 4. Report.
+    if formatter::is_json_mode() {
+        formatter::print_json(&json!({
+            "dry_run": true,
+            "profile": profile_json,
+            "registry": registry_deployed.then(|| registry.to_string()),
+            "estimated_gas": estimate.map(|e| e.gas),
+            "estimated_cost_wei": estimate.map(|e| e.cost().wei.to_string()),
+            "estimated_fee": super::fee_json(estimate.as_ref(), cfg.fees.eth_usd),
+            "balance_wei": balance.wei.to_string(),
+            "balance_sufficient": sufficient,
+        }))?;
+    } else {
+        print_preview(
+            &profile_json,
+            registry,
+            estimate.as_ref(),
+            cfg.fees.eth_usd,
+            &balance,
+            sufficient,
+        )?;
+    }
+
+    if !sufficient {
+        bail!("Insufficient funds for registration. Send ETH to your agent address first.");
+    }
+    Ok(())
+}
+
+fn print_preview(
+    profile: &serde_json::Value,
+    registry: Address,
+    estimate: Option<&FeeEstimate>,
+    eth_usd: f64,
+    balance: &Balance,
+    sufficient: bool,
+) -> Result<()> {
+    formatter::print_info("Dry run: nothing will be uploaded or sent.");
+    formatter::print_info("");
+    formatter::print_info("Profile to publish:");
+    formatter::print_info(&serde_json::to_string_pretty(profile)?);
+    formatter::print_info("");
+
+    if !registry.is_zero() {
+        formatter::print_info(&format!("Registry:       {registry}"));
+    } else {
         formatter::print_info(
-            "Your profile has been saved and will be used when registration opens.",
+            "Registry:       not yet available (the profile would be saved for later)",
         );
+    }
+    formatter::print_info(&format!(
+        "Estimated fee:  {}",
+        super::describe_fee(estimate, eth_usd)
+    ));
+    formatter::print_info(&format!(
+        "Balance:        {} ({})",
+        balance.display_eth(),
+        if sufficient {
+            "enough to register"
+        } else {
+            "not enough to register"
+        }
+    ));
+    Ok(())
+}
 
-        cfg.identity.ipfs_profile_cid = cid.clone();
-        config::store::save(&cfg)?;
-        debug!("config saved with ipfs_profile_cid (contract not yet deployed)");
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
 
-        formatter::print_success(&format!("Profile ready. CID: {cid}"));
-        return Ok(());
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::{Bytes, B256, U256};
+    use alloy::providers::mock::Asserter;
+    use alloy::sol_types::SolValue;
+    use std::sync::atomic::Ordering;
+    use tokio::sync::Mutex;
+
+    /// Serialises tests that point `AGENTMARKET_HOME` at a temp directory.
+    /// Async-aware, as it is held across awaits.
+    static ENV_LOCK: Mutex<()> = Mutex::const_new(());
+
+    const REGISTRY: Address = Address::repeat_byte(0x11);
+    /// An IPFS API nothing listens on: any upload attempt fails.
+    const NO_IPFS: &str = "http://127.0.0.1:9";
+
+    fn signer() -> TransactionSigner {
+        TransactionSigner::from_bytes(&mut [0x5a; 32]).unwrap()
     }
 
-    // Build the register call data (for future transaction submission).
-    let _register_call = AgentRegistry::registerCall {
-        agentURI: agent_uri.clone(),
-    };
+    fn profile() -> AgentProfile {
+        identity::create_profile("summarizer", "", vec![], 5.0, "02ab", "0xabc")
+    }
 
-    // Build the transaction signer for sending the registration tx.
-    let _signer = TransactionSigner::from_keystore_with_passphrase(&passphrase)?;
+    fn word(value: u64) -> Bytes {
+        Bytes::from((U256::from(value),).abi_encode_params())
+    }
 
-    debug!(
-        contract = %addresses::AGENT_REGISTRY,
-        agent_uri = %agent_uri,
-        "submitting registration transaction"
-    );
+    fn one_eth() -> U256 {
+        U256::from(1_000_000_000_000_000_000u128)
+    }
 
-    // TODO: Once alloy provider-with-signer integration is complete,
-    // send the actual transaction here:
-    //   let provider = ProviderBuilder::new()
-    //       .signer(signer.inner().clone())
-    //       .on_http(cfg.network.chain_rpc.parse()?);
-    //   let registry = AgentRegistry::new(addresses::AGENT_REGISTRY, provider);
-    //   let receipt = registry.register(agent_uri).send().await?.get_receipt().await?;
-    //   let agent_id = extract_agent_id_from_receipt(&receipt);
-    //
-    // For now, we save the CID and mark registration as pending.
-    formatter::print_info("Submitting registration...");
+    fn checkpoint_for(profile: &AgentProfile) -> Checkpoint {
+        let json = serde_json::to_string_pretty(profile).unwrap();
+        Checkpoint::resume(
+            None,
+            &profile.public_key,
+            &registration::profile_digest(&json),
+        )
+    }
 
-    // 8–9. Update config with profile CID (agent_id will be set once the
-    //       transaction is confirmed and the event is parsed).
-    cfg.identity.ipfs_profile_cid = cid.clone();
-    config::store::save(&cfg)?;
-    debug!("config saved with ipfs_profile_cid");
+    #[tokio::test]
+    async fn test_adopts_existing_registration() {
+        let _guard = ENV_LOCK.lock().await;
+        let tmp = tempfile::tempdir().unwrap();
+        std::env::set_var("AGENTMARKET_HOME", tmp.path());
+        registration::save(&checkpoint_for(&profile())).unwrap();
 
-    // 10. Display success message (zero-crypto UX).
-    formatter::print_success(&format!(
-        "Agent \"{}\" profile uploaded. Registration pending confirmation.",
-        cfg.agent.name,
-    ));
+        let asserter = Asserter::new();
+        asserter.push_success(&word(7));
+        asserter.push_success(&Bytes::from(
+            ("ipfs://bafyonchain".to_string(),).abi_encode_params(),
+        ));
+        let chain = ChainClient::mocked(asserter);
+        let mut cfg = Config::default();
 
-    Ok(())
+        let outcome = register_steps(
+            &mut cfg,
+            &chain,
+            &IpfsClient::new(NO_IPFS, NO_IPFS),
+            REGISTRY,
+            &signer(),
+            &profile(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            outcome,
+            Outcome::Adopted {
+                agent_id: "7".to_string()
+            }
+        );
+        assert_eq!(cfg.identity.agent_id, "7");
+        assert_eq!(cfg.identity.ipfs_profile_cid, "bafyonchain");
+        assert_eq!(registration::load().unwrap(), None);
+
+        std::env::remove_var("AGENTMARKET_HOME");
+    }
+
+    #[tokio::test]
+    async fn test_resume_skips_finished_upload() {
+        let _guard = ENV_LOCK.lock().await;
+        let tmp = tempfile::tempdir().unwrap();
+        std::env::set_var("AGENTMARKET_HOME", tmp.path());
+        let mut checkpoint = checkpoint_for(&profile());
+        checkpoint.profile_cid = Some("bafydone".to_string());
+        registration::save(&checkpoint).unwrap();
+
+        // Registry not deployed: only the balance is read, and the upload
+        // would fail if attempted.
+        let asserter = Asserter::new();
+        asserter.push_success(&one_eth());
+        let mut cfg = Config::default();
+
+        let outcome = register_steps(
+            &mut cfg,
+            &ChainClient::mocked(asserter),
+            &IpfsClient::new(NO_IPFS, NO_IPFS),
+            Address::ZERO,
+            &signer(),
+            &profile(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(outcome, Outcome::Deferred);
+        assert_eq!(cfg.identity.ipfs_profile_cid, "bafydone");
+
+        std::env::remove_var("AGENTMARKET_HOME");
+    }
+
+    #[tokio::test]
+    async fn test_submit_then_rerun_waits_without_uploading() {
+        let _guard = ENV_LOCK.lock().await;
+        let tmp = tempfile::tempdir().unwrap();
+        std::env::set_var("AGENTMARKET_HOME", tmp.path());
+        // A stale checkpoint for an older profile is discarded.
+        let mut stale = checkpoint_for(&profile());
+        stale.profile_digest = "older".to_string();
+        stale.profile_cid = Some("bafyold".to_string());
+        registration::save(&stale).unwrap();
+
+        let (api, uploads) =
+            crate::ipfs::client::mock_gateway("application/json", br#"{"Hash":"bafynew"}"#).await;
+        let ipfs = IpfsClient::new(&api, &api);
+        let mut cfg = Config::default();
+        cfg.network.receipt_timeout_secs = 0;
+
+        let asserter = Asserter::new();
+        asserter.push_success(&word(0)); // not registered yet
+        asserter.push_success(&one_eth());
+        asserter.push_success(&"0x249f0"); // 150_000 gas
+        asserter.push_success(&"0x989680"); // 0.01 gwei
+        asserter.push_success(&Bytes::new()); // simulation
+        asserter.push_success(&"0x249f0");
+        asserter.push_success(&serde_json::json!({
+            "oldestBlock": "0x10",
+            "baseFeePerGas": ["0x989680", "0x989680"],
+            "gasUsedRatio": [0.5],
+        }));
+        asserter.push_success(&"0x2105"); // chain 8453
+        asserter.push_success(&"0x0"); // pending nonce
+        asserter.push_success(&B256::repeat_byte(0xab));
+        asserter.push_success(&serde_json::Value::Null); // no receipt yet
+        let chain = ChainClient::mocked(asserter);
+        let first = register_steps(&mut cfg, &chain, &ipfs, REGISTRY, &signer(), &profile())
+            .await
+            .unwrap();
+        assert_eq!(first, Outcome::Submitted);
+        assert_eq!(cfg.identity.ipfs_profile_cid, "bafynew");
+        assert_eq!(uploads.load(Ordering::SeqCst), 1);
+
+        let saved = registration::load().unwrap().unwrap();
+        assert_eq!(saved.profile_cid.as_deref(), Some("bafynew"));
+        let since = saved.submitted_at.unwrap();
+
+        // Still unconfirmed: nothing is uploaded or submitted again.
+        let asserter = Asserter::new();
+        asserter.push_success(&word(0));
+        let chain = ChainClient::mocked(asserter);
+        let mut cfg = Config::default();
+        let second = register_steps(&mut cfg, &chain, &ipfs, REGISTRY, &signer(), &profile())
+            .await
+            .unwrap();
+        assert_eq!(second, Outcome::Pending { since });
+        assert_eq!(cfg.identity.ipfs_profile_cid, "bafynew");
+        assert_eq!(uploads.load(Ordering::SeqCst), 1);
+
+        std::env::remove_var("AGENTMARKET_HOME");
+    }
 }
@@ -0,0 +1,315 @@
+//! The `reputation` command: inspect another agent before hiring it.
+//!
+//! Looks the agent up by ID or address with [`fetch_remote_reputation`]
+//! and prints its profile, score, tier, and earnings. Works without a local
+//! identity: only the network settings are read from config.
+
+use std::str::FromStr;
+
+use alloy::primitives::Address;
+use anyhow::{anyhow, bail, Context, Result};
+use tracing::debug;
+
+use crate::chain::client::ChainClient;
+use crate::chain::types::SellerRecord;
+use crate::config;
+use crate::engine::identity::AgentProfile;
+use crate::engine::reputation::{
+    self, AgentRef, RemoteReputation, ReputationScore, ValidationRecord,
+};
+use crate::ipfs::client::IpfsClient;
+use crate::output::formatter;
+
+/// Run the `reputation` command for `agent`, an agent ID or address.
+pub async fn run(agent: String) -> Result<()> {
+    debug!(%agent, "starting reputation command");
+
+    // 1. Parse before touching the network so typos fail fast.
+    let agent = AgentRef::parse(&agent)?;
+
+    // 2. Look the agent up.
+    let cfg = config::store::load().unwrap_or_else(|_| config::store::Config::default());
+    let chain = ChainClient::shared(&cfg).await?;
+    let ipfs = IpfsClient::from_config(&cfg);
+    let remote = fetch_remote_reputation(&chain, &ipfs, &agent).await?;
+    debug!(agent_id = %remote.agent_id, tier = %remote.tier, "reputation fetched");
+
+    // 3. Print
+    if formatter::is_json_mode() {
+        return formatter::print_json(&remote);
+    }
+    for line in summary_lines(&remote) {
+        formatter::print_info(&line);
+    }
+    Ok(())
+}
+
+/// Look up `agent` on the network: resolve its profile URI in the Agent
+/// Registry, fetch the profile, and score its validation history.
+pub(crate) async fn fetch_remote_reputation(
+    chain: &ChainClient,
+    ipfs: &IpfsClient,
+    agent: &AgentRef,
+) -> Result<RemoteReputation> {
+    lookup(
+        chain,
+        ipfs,
+        agent,
+        chain.contracts().agent_registry,
+        chain.contracts().request_registry,
+    )
+    .await
+}
+
+async fn lookup(
+    chain: &ChainClient,
+    ipfs: &IpfsClient,
+    agent: &AgentRef,
+    agent_registry: Address,
+    request_registry: Address,
+) -> Result<RemoteReputation> {
+    debug!(?agent, "looking up remote reputation");
+    if agent_registry == Address::ZERO {
+        bail!("The agent registry contract is not yet deployed, so agents cannot be looked up.");
+    }
+
+    // 1. Resolve the agent ID and its profile URI.
+    let agent_id = match agent {
+        AgentRef::Id(id) => *id,
+        AgentRef::Address(address) => chain
+            .get_agent_of(agent_registry, *address)
+            .await?
+            .ok_or_else(|| anyhow!("No agent is registered at {address}."))?,
+    };
+    let uri = chain
+        .get_agent_uri(agent_registry, agent_id)
+        .await?
+        .ok_or_else(|| anyhow!("No agent with ID {agent_id} is registered."))?;
+
+    // 2. Fetch the published profile.
+    let cid = uri.strip_prefix("ipfs://").unwrap_or(&uri);
+    let profile: AgentProfile =
+        serde_json::from_slice(&ipfs.cat(cid).await.with_context(|| {
+            format!("failed to fetch the profile of agent {agent_id} from IPFS")
+        })?)
+        .with_context(|| format!("Agent {agent_id} published an unreadable profile."))?;
+
+    // 3. Score the responses its address submitted.
+    let address = match agent {
+        AgentRef::Address(address) => *address,
+        AgentRef::Id(_) => Address::from_str(&profile.address)
+            .with_context(|| format!("Agent {agent_id}'s profile has no usable address."))?,
+    };
+    let history = if request_registry == Address::ZERO {
+        Vec::new()
+    } else {
+        chain.get_seller_history(request_registry, address).await?
+    };
+    let reputation = score_history(&agent_id.to_string(), &history);
+
+    Ok(RemoteReputation {
+        agent_id: agent_id.to_string(),
+        address: address.to_checksum(None),
+        profile,
+        tier: reputation::reputation_tier(&reputation).to_string(),
+        reputation,
+    })
+}
+
+/// Score an agent from its response history: validated responses count
+/// as records, and claimed ones as earnings.
+fn score_history(agent_id: &str, history: &[SellerRecord]) -> ReputationScore {
+    let records: Vec<ValidationRecord> = history
+        .iter()
+        .filter_map(|record| {
+            Some(ValidationRecord {
+                request_id: record.request_id.to_string(),
+                passed: record.passed?,
+                timestamp: record.validated_at.unwrap_or(0),
+                validator: record
+                    .validator
+                    .map(|v| v.to_checksum(None))
+                    .unwrap_or_default(),
+            })
+        })
+        .collect();
+    let earnings = history
+        .iter()
+        .filter(|record| record.claimed)
+        .map(|record| record.price_usdc)
+        .sum();
+    reputation::compute_reputation(agent_id, &records, earnings, 0)
+}
+
+/// The human-readable report for `remote`.
+fn summary_lines(remote: &RemoteReputation) -> Vec<String> {
+    let profile = &remote.profile;
+    let score = &remote.reputation;
+    let mut lines = vec![format!("Agent #{}: {}", remote.agent_id, profile.name)];
+    if !profile.description.is_empty() {
+        lines.push(profile.description.clone());
+    }
+    lines.push(String::new());
+    lines.push(format!(
+        "Reputation:         {} ({})",
+        reputation::format_reputation(score),
+        remote.tier
+    ));
+    lines.push(format!("Completed requests: {}", score.completed_requests));
+    lines.push(format!("Failed validations: {}", score.failed_validations));
+    lines.push(format!(
+        "Earnings:           {}",
+        reputation::format_earnings_usd(score.total_earnings_usdc)
+    ));
+    lines.push(format!("Price per task:     ${:.2}", profile.pricing_usd));
+    lines.push(format!(
+        "Capabilities:       {}",
+        if profile.capabilities.is_empty() {
+            "(none listed)".to_string()
+        } else {
+            profile.capabilities.join(", ")
+        }
+    ));
+    lines
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::identity;
+    use alloy::primitives::U256;
+
+    #[test]
+    fn test_summary_lines() {
+        let reputation = reputation::compute_reputation("42", &[], 12_500_000, 0);
+        let remote = RemoteReputation {
+            agent_id: "42".to_string(),
+            address: "0x1111111111111111111111111111111111111111".to_string(),
+            profile: identity::create_profile(
+                "summarizer",
+                "Summarises long documents",
+                vec!["summarize".to_string(), "translate".to_string()],
+                5.0,
+                "02aa",
+                "0x1111111111111111111111111111111111111111",
+            ),
+            tier: reputation::reputation_tier(&reputation).to_string(),
+            reputation,
+        };
+
+        assert_eq!(
+            summary_lines(&remote),
+            [
+                "Agent #42: summarizer",
+                "Summarises long documents",
+                "",
+                "Reputation:         N/A (Unrated)",
+                "Completed requests: 0",
+                "Failed validations: 0",
+                "Earnings:           $12.50",
+                "Price per task:     $5.00",
+                "Capabilities:       summarize, translate",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_score_history_counts_verdicts_and_claimed_earnings() {
+        use crate::chain::types::RequestId;
+
+        let record = |id: u64, passed: Option<bool>, claimed: bool| SellerRecord {
+            request_id: RequestId(U256::from(id)),
+            price_usdc: 5_000_000,
+            passed,
+            validator: passed.map(|_| Address::repeat_byte(0x44)),
+            validated_at: passed.map(|_| 1_700_000_000),
+            claimed,
+        };
+        let score = score_history(
+            "42",
+            &[
+                record(1, Some(true), true),
+                record(2, Some(true), false),
+                record(3, Some(false), false),
+                record(4, None, false),
+            ],
+        );
+        assert_eq!(score.agent_id, "42");
+        assert_eq!(score.completed_requests, 2);
+        assert_eq!(score.failed_validations, 1);
+        assert_eq!(score.total_earnings_usdc, 5_000_000);
+        assert_eq!(reputation::format_reputation(&score), "66.7");
+    }
+
+    async fn lookup_error(asserter: alloy::providers::mock::Asserter, agent: &str) -> String {
+        let chain = ChainClient::mocked(asserter);
+        // Nothing listens on these ports.
+        let ipfs = IpfsClient::new("http://127.0.0.1:19997", "http://127.0.0.1:19996");
+        let err = lookup(
+            &chain,
+            &ipfs,
+            &AgentRef::parse(agent).unwrap(),
+            Address::repeat_byte(0x11),
+            Address::ZERO,
+        )
+        .await
+        .unwrap_err();
+        crate::output::formatter::format_error(&err)
+    }
+
+    #[tokio::test]
+    async fn test_lookup_unknown_agent() {
+        use alloy::sol_types::SolValue;
+
+        let asserter = alloy::providers::mock::Asserter::new();
+        asserter.push_success(&alloy::primitives::Bytes::from(
+            (String::new(),).abi_encode_params(),
+        ));
+        assert_eq!(
+            lookup_error(asserter, "7").await,
+            "Operation failed: No agent with ID 7 is registered."
+        );
+
+        let asserter = alloy::providers::mock::Asserter::new();
+        asserter.push_success(&alloy::primitives::Bytes::from(
+            U256::ZERO.to_be_bytes::<32>().to_vec(),
+        ));
+        let err = lookup_error(asserter, "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913").await;
+        assert!(err.contains("No agent is registered at 0x8335"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_lookup_unreachable_gateway() {
+        use alloy::sol_types::SolValue;
+
+        let asserter = alloy::providers::mock::Asserter::new();
+        asserter.push_success(&alloy::primitives::Bytes::from(
+            ("ipfs://bafyprofile".to_string(),).abi_encode_params(),
+        ));
+        assert_eq!(
+            lookup_error(asserter, "7").await,
+            "Content network unavailable. Please try again later."
+        );
+    }
+
+    #[tokio::test]
+    async fn test_lookup_needs_deployed_registry() {
+        let chain = ChainClient::mocked(Default::default());
+        let ipfs = IpfsClient::new("http://127.0.0.1:19997", "http://127.0.0.1:19996");
+        let err = lookup(
+            &chain,
+            &ipfs,
+            &AgentRef::Id(U256::from(7)),
+            Address::ZERO,
+            Address::ZERO,
+        )
+        .await
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("not yet deployed"), "{err}");
+    }
+}
@@ -1,92 +1,328 @@
 //! The `request` command: create a service request for another agent.
 //!
-//! Builds a request payload (task description + optional file attachment),
+//! Builds a request payload (task description + optional file attachments),
 //! encrypts it with ECIES, uploads to IPFS, optionally pins it remotely,
 //! and either submits the request on-chain or saves it locally if the
-//! Request Registry contract is not yet deployed.
-
-use std::time::{SystemTime, UNIX_EPOCH};
+//! Request Registry contract is not yet deployed. `--dry-run` stops after
+//! encryption and reports what would be published and what it would cost.
+//!
+//! The registry pulls the price from the buyer's USDC when the request is
+//! created, so the buyer must first have approved it to. If the current
+//! approval falls short, the command asks to approve the price (or
+//! `requests.approval_cap_usd`, if larger) before creating the request.
 
-use alloy::primitives::Address;
+use alloy::primitives::{Address, U256};
 use anyhow::{bail, Context, Result};
+use serde_json::json;
 use tracing::debug;
 
 use super::CommandContext;
 use crate::chain::client::ChainClient;
-use crate::chain::contracts::addresses;
-use crate::chain::types::Balance;
+use crate::chain::contracts::{Contract, RequestRegistry};
+use crate::chain::types::{AllowanceAction, Balance, FeeEstimate};
+use crate::clock;
+use crate::clock::format_duration;
+use crate::config::store::Config;
+use crate::engine::attachments::{self, Attachment, ManifestEntry};
 use crate::engine::requests::{
-    dollars_to_usdc, format_price_usd, LocalRequest, LocalRequestStatus, RequestCache, RequestRole,
+    dollars_to_usdc, normalize_tags, parse_deadline, parse_usd_amount, summarize_task,
+    LocalRequest, LocalRequestStatus, RequestCache, RequestRole, CURRENT_SCHEMA_VERSION,
 };
+use crate::engine::template::{self, format_date, Rendered, TemplateContext};
+use crate::engine::trust::{self, Coverage, PolicyAction, TrustStore};
 use crate::ipfs::client::IpfsClient;
 use crate::ipfs::encryption;
 use crate::ipfs::pin::PinningService;
-use crate::output::formatter;
+use crate::output::{formatter, prompt};
 
+/// A request built and encrypted locally, ready to publish.
+#[derive(Debug)]
+struct PreparedRequest {
+    task: String,
+    price_usdc: u64,
+    deadline_ts: u64,
+    target_agent_id: u64,
+    tags: Vec<String>,
+    /// Capability the task needs, as given with `--capability`.
+    capability: Option<String>,
+    /// Seller whose standing approval confirms this request.
+    trust_policy: Option<String>,
+    attachments: Vec<ManifestEntry>,
+    /// Size of the payload before encryption, in bytes.
+    payload_len: usize,
+    ciphertext: Vec<u8>,
+}
+
+/// Run the `request` command. With `dry_run`, the request is built,
+/// encrypted, and priced, and a summary printed instead of publishing it.
+#[allow(clippy::too_many_arguments)]
 pub async fn run(
     task: String,
-    price: f64,
-    deadline_hours: u64,
+    price: String,
+    deadline: String,
     target_agent_id: u64,
-    file_path: Option<String>,
+    file_paths: Vec<String>,
+    dir: Option<String>,
+    tags: Vec<String>,
+    capability: Option<String>,
+    dry_run: bool,
 ) -> Result<()> {
-    debug!("starting request command");
+    debug!(dry_run, "starting request command");
 
     // 1. Load config, verify registered, derive address and public key.
     let ctx = CommandContext::load_registered()?;
-
     debug!(address = %ctx.address, "agent address derived");
 
-    // 2. Check ETH balance — if insufficient, show funding instructions and bail.
-    let client = ChainClient::new(&ctx.cfg.network.chain_rpc).await?;
+    // 2. Build and encrypt the request before any network work.
+    let prepared = prepare(
+        &ctx,
+        task,
+        &price,
+        &deadline,
+        target_agent_id,
+        &file_paths,
+        dir.as_deref(),
+        tags,
+        capability,
+    )?;
+
+    // 3. Check ETH balance.
+    let client = ChainClient::from_config(&ctx.cfg).await?;
     let addr: Address = ctx
         .address
         .parse()
         .context("failed to parse agent address")?;
-    let balance_wei = client.get_eth_balance(addr).await?;
-    let balance = Balance { wei: balance_wei };
+    let balance = Balance {
+        wei: client.get_eth_balance(addr).await?,
+    };
+    let sufficient = balance.is_sufficient_for_registration();
+    debug!(balance = %balance.display_eth(), sufficient, "balance retrieved");
 
-    debug!(balance = %balance.display_eth(), "balance retrieved");
+    let eth_usd = ctx.cfg.fees.eth_usd;
+    if dry_run {
+        return preview(&prepared, &client, addr, &ctx.cfg, &balance, sufficient).await;
+    }
 
-    if !balance.is_sufficient_for_registration() {
+    // 4. If insufficient, show funding instructions and bail.
+    if !sufficient {
         formatter::print_warning("Insufficient funds to submit request.");
         formatter::print_funding_instructions(&ctx.address, "0.0001 ETH");
         bail!("Insufficient funds. Send ETH to your agent address and try again.");
     }
 
-    // 3. Build request payload JSON (task description + optional file content).
-    formatter::print_info("Preparing request...");
+    // 5. Show the fee, then confirm unless a standing approval already
+    //    covers the request.
+    if !ctx.contracts.request_registry.is_zero() {
+        let estimate = estimate_fee(&prepared, &client, addr).await?;
+        super::print_fee(estimate.as_ref(), eth_usd);
+    }
+    if prepared.trust_policy.is_none() {
+        prompt::confirm_funds(&commit_summary(&prepared))?;
+    }
+
+    // 6. Make sure the registry may pull the price before creating the
+    //    request, which would otherwise revert, then check it would accept
+    //    the request before anything is uploaded.
+    if !ctx.contracts.request_registry.is_zero() {
+        ensure_allowance(&client, &ctx, prepared.price_usdc).await?;
+        simulate_create(&prepared, &client, addr).await?;
+    }
 
-    let file_content = if let Some(ref path) = file_path {
-        let content = std::fs::read_to_string(path)
-            .with_context(|| format!("failed to read attachment file: {path}"))?;
-        debug!(path = %path, size = content.len(), "attachment file loaded");
-        Some(content)
+    submit(&ctx, prepared).await
+}
+
+/// What publishing the request commits, for the confirmation prompt.
+fn commit_summary(prepared: &PreparedRequest) -> String {
+    let price = formatter::format_price(prepared.price_usdc);
+    if prepared.target_agent_id > 0 {
+        format!(
+            "About to commit {price} to agent #{}",
+            prepared.target_agent_id
+        )
     } else {
-        None
-    };
+        format!("About to commit {price} to an open request")
+    }
+}
+
+/// What approving the registry commits, for the confirmation prompt.
+fn approval_summary(price_usdc: u64, allowance: u64, amount: u64) -> String {
+    format!(
+        "The request registry may spend {} of your USDC, but this request needs {}. \
+         About to approve {}",
+        formatter::format_price(allowance),
+        formatter::format_price(price_usdc),
+        formatter::format_price(amount)
+    )
+}
+
+/// Make sure the Request Registry may pull `price_usdc` from this agent,
+/// asking to approve it first if not. The approval must be confirmed
+/// before the request is created.
+async fn ensure_allowance(
+    client: &ChainClient,
+    ctx: &CommandContext,
+    price_usdc: u64,
+) -> Result<()> {
+    let registry = client.contracts().require(Contract::RequestRegistry)?;
+    let owner: Address = ctx
+        .address
+        .parse()
+        .context("failed to parse agent address")?;
+    let cap = dollars_to_usdc(ctx.cfg.requests.approval_cap_usd);
+    let action = client
+        .ensure_usdc_allowance(
+            owner,
+            registry,
+            price_usdc,
+            cap,
+            |allowance, amount| async move {
+                prompt::confirm_funds(&approval_summary(price_usdc, allowance, amount))?;
+                formatter::print_info("Approving USDC...");
+                let signer = ctx.transaction_signer()?;
+                let tx_hash = client
+                    .approve_usdc(&signer, registry, amount, |quote| {
+                        super::confirm_fee_over_cap("approval", quote)
+                    })
+                    .await?;
+                if !super::await_confirmation(client, &ctx.cfg.network, tx_hash, "approval").await?
+                {
+                    bail!("The USDC approval is not confirmed yet. Try the request again shortly.");
+                }
+                Ok(())
+            },
+        )
+        .await?;
+    if let AllowanceAction::Approve { amount, .. } = action {
+        formatter::print_info(&format!(
+            "The request registry may now spend {} of your USDC.",
+            formatter::format_price(amount)
+        ));
+    }
+    Ok(())
+}
 
-    let payload = if let Some(ref content) = file_content {
-        serde_json::json!({
-            "task": task,
-            "attachment": content,
-        })
+/// Parse the arguments, read attachments, and build and encrypt the
+/// request payload. Nothing leaves the machine.
+#[allow(clippy::too_many_arguments)]
+fn prepare(
+    ctx: &CommandContext,
+    task: String,
+    price: &str,
+    deadline: &str,
+    target_agent_id: u64,
+    file_paths: &[String],
+    dir: Option<&str>,
+    tags: Vec<String>,
+    capability: Option<String>,
+) -> Result<PreparedRequest> {
+    // a. Parse the price and deadline and resolve template variables in the
+    //    task text.
+    let price_usdc = parse_usd_amount(price)?;
+    let deadline_ts = parse_deadline(deadline, clock::unix_now())?;
+
+    let rendered = if template::has_template_syntax(&task) {
+        template::render(&task, &TemplateContext::now())?
     } else {
-        serde_json::json!({
-            "task": task,
-        })
+        Rendered {
+            text: task,
+            values: Default::default(),
+        }
     };
+    let task = rendered.text;
+    let capability = capability
+        .map(|c| c.trim().to_lowercase())
+        .filter(|c| !c.is_empty());
+
+    // b. A targeted request within a standing approval for the seller is
+    //    confirmed by that policy.
+    let trust_policy =
+        standing_approval(target_agent_id, price_usdc, capability.as_deref(), &tags)?;
+
+    // c. Read attachments, refusing an oversized set up front.
+    let max_bytes = ctx.cfg.requests.max_attachment_bytes;
+    let mut files = attachments::read_files(file_paths, max_bytes)?;
+    if let Some(dir) = dir {
+        let used: u64 = files.iter().map(|f| f.content.len() as u64).sum();
+        let packed = super::pack_dir(dir, max_bytes.saturating_sub(used))?;
+        if files.iter().any(|f| f.name == packed.name) {
+            bail!(
+                "Two attachments are named {}. Rename one so the seller can tell them apart.",
+                packed.name
+            );
+        }
+        files.push(Attachment::from_archive(packed));
+    }
+
+    // d. Build request payload JSON (task description + attachment bundle).
+    formatter::print_info("Preparing request...");
+
+    let mut payload = serde_json::json!({
+        "task": task,
+    });
+    if let Some(capability) = &capability {
+        payload["capability"] = serde_json::json!(capability);
+    }
+    if !files.is_empty() {
+        let total: u64 = files.iter().map(|f| f.content.len() as u64).sum();
+        formatter::print_info(&format!(
+            "Attaching {} file{} ({}).",
+            files.len(),
+            if files.len() == 1 { "" } else { "s" },
+            attachments::format_size(total)
+        ));
+        payload["attachments"] = serde_json::json!(attachments::bundle(&files));
+    }
+    // Record resolved template values so the payload shows exactly what was
+    // substituted.
+    if !rendered.values.is_empty() {
+        payload["template_values"] = serde_json::json!(rendered.values);
+    }
 
     let payload_bytes =
         serde_json::to_vec(&payload).context("failed to serialize request payload")?;
 
-    // 4. Encrypt payload with ECIES using the agent's own public key.
+    // e. Encrypt payload with ECIES using the agent's own public key.
     //    (For a targeted request, the target agent's public key would be used;
     //     that requires a lookup that is not yet implemented.)
     let ciphertext = encryption::encrypt(&ctx.public_key, &payload_bytes)
         .context("failed to encrypt request payload")?;
 
-    debug!(ciphertext_len = ciphertext.len(), "payload encrypted");
+    debug!(
+        price_usdc,
+        deadline_ts,
+        target_agent_id,
+        ciphertext_len = ciphertext.len(),
+        "request prepared"
+    );
+
+    Ok(PreparedRequest {
+        task,
+        price_usdc,
+        deadline_ts,
+        target_agent_id,
+        tags,
+        capability,
+        trust_policy,
+        attachments: files.iter().map(Attachment::entry).collect(),
+        payload_len: payload_bytes.len(),
+        ciphertext,
+    })
+}
+
+/// Upload a prepared request and create it on-chain, or save it locally
+/// while the Request Registry is not deployed.
+async fn submit(ctx: &CommandContext, prepared: PreparedRequest) -> Result<()> {
+    let PreparedRequest {
+        task,
+        price_usdc,
+        deadline_ts,
+        target_agent_id,
+        tags,
+        capability,
+        trust_policy,
+        ciphertext,
+        ..
+    } = prepared;
 
     // 5. Upload encrypted payload to IPFS.
     let ipfs_client = IpfsClient::from_config(&ctx.cfg);
@@ -117,37 +353,26 @@ pub async fn run(
         debug!("no remote pinning service configured — skipping remote pin");
     }
 
-    // 7. Convert price from USD to USDC units (6 decimals).
-    let price_usdc = dollars_to_usdc(price);
-
-    // 8. Calculate deadline as Unix timestamp (now + hours).
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .context("system clock error")?
-        .as_secs();
-    let deadline_ts = now + (deadline_hours * 3600);
-
-    debug!(
-        price_usdc = price_usdc,
-        deadline_ts = deadline_ts,
-        target_agent_id = target_agent_id,
-        "request parameters computed"
-    );
+    // 7. Timestamp the request; the deadline was resolved up front.
+    let now = clock::unix_now();
 
-    // 9. Generate a local request ID (timestamp-based, will be replaced by
+    // 8. Generate a local request ID (timestamp-based, will be replaced by
     //     the on-chain ID after contract submission).
     let local_request_id = format!("local-{now}");
 
-    // 10. Contract deployment gate: check if REQUEST_REGISTRY address is ZERO.
-    if addresses::REQUEST_REGISTRY == Address::ZERO {
+    // 9. Contract deployment gate: check if the Request Registry address is ZERO.
+    if ctx.contracts.request_registry.is_zero() {
         // Contract not yet deployed — save request locally.
-        formatter::print_warning(
-            "The request registry is not yet deployed. \
-             Your request has been saved locally and will be submitted once the contract goes live.",
-        );
+        formatter::print_warning(&format!(
+            "The request registry is not yet deployed on {}. \
+             Your request has been saved locally and will be submitted once the contract goes live. {}",
+            ctx.contracts.network,
+            Contract::RequestRegistry.setup_hint()
+        ));
 
         let local_request = LocalRequest {
             request_id: local_request_id.clone(),
+            schema_version: CURRENT_SCHEMA_VERSION,
             role: RequestRole::Buyer,
             status: LocalRequestStatus::Open,
             request_cid: cid.clone(),
@@ -159,14 +384,23 @@ pub async fn run(
             counterparty: None,
             created_at: now,
             updated_at: now,
+            previous_status: None,
+            tags: normalize_tags(&tags),
+            task_summary: Some(summarize_task(&task)),
+            capability: capability.clone(),
+            trust_policy: trust_policy.clone(),
+            history: Vec::new(),
+            payment_check: None,
+            response_attempt: None,
         };
 
         RequestCache::save(&local_request)?;
         debug!(request_id = %local_request_id, "request saved to local cache");
+        record_confirmation(&local_request)?;
 
         formatter::print_success(&format!(
             "Request saved (ID: {local_request_id}). Task: \"{task}\" for {}",
-            format_price_usd(price_usdc),
+            formatter::format_price(price_usdc),
         ));
 
         if target_agent_id > 0 {
@@ -175,37 +409,49 @@ pub async fn run(
             formatter::print_info("Open request — any agent can respond.");
         }
 
-        formatter::print_info(&format!("Deadline: {deadline_hours} hours from now."));
+        formatter::print_info(&format!(
+            "Deadline: {} (in {}).",
+            format_date(deadline_ts, "%Y-%m-%d %H:%M UTC")?,
+            format_duration(deadline_ts.saturating_sub(now))
+        ));
 
         return Ok(());
     }
 
     // Contract is deployed — submit on-chain (placeholder with TODO).
+    let registry = ctx.contracts.require(Contract::RequestRegistry)?;
     // TODO: Once alloy provider-with-signer integration is complete,
     // send the actual createRequest transaction here:
     //   let signer = TransactionSigner::from_keystore_with_passphrase(&passphrase)?;
     //   let provider = ProviderBuilder::new()
     //       .signer(signer.inner().clone())
     //       .on_http(cfg.network.chain_rpc.parse()?);
-    //   let registry = RequestRegistry::new(addresses::REQUEST_REGISTRY, provider);
-    //   let receipt = registry
-    //       .createRequest(
-    //           format!("ipfs://{cid}"),
-    //           U256::from(price_usdc),
-    //           U256::from(deadline_ts),
-    //           U256::from(target_agent_id),
-    //       )
-    //       .send()
-    //       .await?
-    //       .get_receipt()
-    //       .await?;
-    //   let request_id = extract_request_id_from_receipt(&receipt);
+    //   let registry = RequestRegistry::new(registry, provider);
+    //   let tx_hash = client.send_with_nonce(addr, TxKind::Create, Some(&local_request_id), |nonce| async move {
+    //       Ok(*registry
+    //           .createRequest(
+    //               format!("ipfs://{cid}"),
+    //               U256::from(price_usdc),
+    //               U256::from(deadline_ts),
+    //               U256::from(target_agent_id),
+    //           )
+    //           .nonce(nonce)
+    //           .send()
+    //           .await?
+    //           .tx_hash())
+    //   }).await?;
+    //   if !super::await_confirmation(&client, &ctx.cfg.network, tx_hash, "request").await? {
+    //       return Ok(());
+    //   }
+    //   let request_id = extract_request_id_from_receipt(&client, tx_hash).await?;
+    debug!(contract = %registry, cid = %cid, "submitting createRequest transaction (placeholder)");
 
     formatter::print_info("Submitting request...");
 
-    // 11. Save to local request cache.
+    // 10. Save to local request cache.
     let local_request = LocalRequest {
         request_id: local_request_id.clone(),
+        schema_version: CURRENT_SCHEMA_VERSION,
         role: RequestRole::Buyer,
         status: LocalRequestStatus::Open,
         request_cid: cid.clone(),
@@ -217,15 +463,24 @@ pub async fn run(
         counterparty: None,
         created_at: now,
         updated_at: now,
+        previous_status: None,
+        tags: normalize_tags(&tags),
+        task_summary: Some(summarize_task(&task)),
+        capability,
+        trust_policy: trust_policy.clone(),
+        history: Vec::new(),
+        payment_check: None,
+        response_attempt: None,
     };
 
     RequestCache::save(&local_request)?;
     debug!(request_id = %local_request_id, "request saved to local cache");
+    record_confirmation(&local_request)?;
 
-    // 12. Display success with request details (zero-crypto UX).
+    // 11. Display success with request details (zero-crypto UX).
     formatter::print_success(&format!(
         "Request created (ID: {local_request_id}). Task: \"{task}\" for {}",
-        format_price_usd(price_usdc),
+        formatter::format_price(price_usdc),
     ));
 
     if target_agent_id > 0 {
@@ -234,7 +489,447 @@ pub async fn run(
         formatter::print_info("Open request — any agent can respond.");
     }
 
-    formatter::print_info(&format!("Deadline: {deadline_hours} hours from now."));
+    formatter::print_info(&format!(
+        "Deadline: {} (in {}).",
+        format_date(deadline_ts, "%Y-%m-%d %H:%M UTC")?,
+        format_duration(deadline_ts.saturating_sub(now))
+    ));
 
     Ok(())
 }
+
+/// Estimate the fee for creating `prepared` from `from`, before its
+/// payload is uploaded.
+async fn estimate_fee(
+    prepared: &PreparedRequest,
+    client: &ChainClient,
+    from: Address,
+) -> Result<Option<FeeEstimate>> {
+    super::estimate_fee(
+        "request",
+        client.estimate_create_request_cost(
+            client.contracts().request_registry,
+            from,
+            &super::placeholder_ipfs_uri(),
+            prepared.price_usdc,
+            prepared.deadline_ts,
+            prepared.target_agent_id,
+        ),
+    )
+    .await
+}
+
+/// Run creating `prepared` from `from` without sending it, failing with the
+/// registry's reason if it would revert. The payload is not uploaded yet,
+/// so a placeholder URI of the same length stands in for it.
+async fn simulate_create(
+    prepared: &PreparedRequest,
+    client: &ChainClient,
+    from: Address,
+) -> Result<()> {
+    let call = RequestRegistry::createRequestCall {
+        ipfsCid: super::placeholder_ipfs_uri(),
+        price: U256::from(prepared.price_usdc),
+        deadline: U256::from(prepared.deadline_ts),
+        targetAgentId: U256::from(prepared.target_agent_id),
+    };
+    client
+        .simulate(from, client.contracts().request_registry, &call)
+        .await
+}
+
+/// Price the prepared request and print what would be published.
+async fn preview(
+    prepared: &PreparedRequest,
+    client: &ChainClient,
+    from: Address,
+    cfg: &Config,
+    balance: &Balance,
+    sufficient: bool,
+) -> Result<()> {
+    let eth_usd = cfg.fees.eth_usd;
+    let registry = client.contracts().request_registry;
+    let (estimate, approval) = if !registry.is_zero() {
+        let allowance = client.get_usdc_allowance(from, registry).await?;
+        let cap = dollars_to_usdc(cfg.requests.approval_cap_usd);
+        (
+            estimate_fee(prepared, client, from).await?,
+            Some(AllowanceAction::plan(allowance, prepared.price_usdc, cap)),
+        )
+    } else {
+        (None, None)
+    };
+
+    if formatter::is_json_mode() {
+        formatter::print_json(&preview_json(
+            prepared,
+            registry,
+            estimate.as_ref(),
+            eth_usd,
+            approval.as_ref(),
+            balance,
+            sufficient,
+        ))?;
+    } else {
+        print_preview(
+            prepared,
+            registry,
+            estimate.as_ref(),
+            eth_usd,
+            approval.as_ref(),
+            balance,
+            sufficient,
+            clock::unix_now(),
+        )?;
+    }
+    Ok(())
+}
+
+fn preview_json(
+    prepared: &PreparedRequest,
+    registry: Address,
+    estimate: Option<&FeeEstimate>,
+    eth_usd: f64,
+    approval: Option<&AllowanceAction>,
+    balance: &Balance,
+    sufficient: bool,
+) -> serde_json::Value {
+    json!({
+        "dry_run": true,
+        "task": prepared.task,
+        "price_usdc": prepared.price_usdc,
+        "deadline": prepared.deadline_ts,
+        "target_agent_id": (prepared.target_agent_id > 0).then_some(prepared.target_agent_id),
+        "tags": normalize_tags(&prepared.tags),
+        "capability": prepared.capability,
+        "standing_approval": prepared.trust_policy,
+        "attachments": prepared.attachments,
+        "payload_bytes": prepared.payload_len,
+        "encrypted_bytes": prepared.ciphertext.len(),
+        "registry": (!registry.is_zero()).then(|| registry.to_string()),
+        "estimated_gas": estimate.map(|e| e.gas),
+        "estimated_cost_wei": estimate.map(|e| e.cost().wei.to_string()),
+        "estimated_fee": super::fee_json(estimate, eth_usd),
+        "usdc_approval": approval,
+        "balance_wei": balance.wei.to_string(),
+        "balance_sufficient": sufficient,
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn print_preview(
+    prepared: &PreparedRequest,
+    registry: Address,
+    estimate: Option<&FeeEstimate>,
+    eth_usd: f64,
+    approval: Option<&AllowanceAction>,
+    balance: &Balance,
+    sufficient: bool,
+    now: u64,
+) -> Result<()> {
+    formatter::print_info("Dry run: nothing will be uploaded or sent.");
+    formatter::print_info("");
+    formatter::print_info(&format!("Task:           {}", prepared.task));
+    formatter::print_info(&format!(
+        "Price:          {} ({} USDC units)",
+        formatter::format_price(prepared.price_usdc),
+        prepared.price_usdc
+    ));
+    formatter::print_info(&format!(
+        "Deadline:       {} (in {}; timestamp {})",
+        format_date(prepared.deadline_ts, "%Y-%m-%d %H:%M UTC")?,
+        format_duration(prepared.deadline_ts.saturating_sub(now)),
+        prepared.deadline_ts
+    ));
+    if prepared.target_agent_id > 0 {
+        formatter::print_info(&format!(
+            "Target:         agent #{}",
+            prepared.target_agent_id
+        ));
+    } else {
+        formatter::print_info("Target:         open to any agent");
+    }
+    if let Some(capability) = &prepared.capability {
+        formatter::print_info(&format!("Capability:     {capability}"));
+    }
+    for entry in &prepared.attachments {
+        formatter::print_info(&format!(
+            "Attachment:     {} ({})",
+            entry.name,
+            attachments::format_size(entry.size)
+        ));
+    }
+    formatter::print_info(&format!(
+        "Payload:        {} ({} encrypted)",
+        attachments::format_size(prepared.payload_len as u64),
+        attachments::format_size(prepared.ciphertext.len() as u64)
+    ));
+    formatter::print_info("");
+
+    if !registry.is_zero() {
+        formatter::print_info(&format!("Registry:       {registry}"));
+    } else {
+        formatter::print_info(
+            "Registry:       not yet available (the request would be saved locally)",
+        );
+    }
+    formatter::print_info(&format!(
+        "Estimated fee:  {}",
+        super::describe_fee(estimate, eth_usd)
+    ));
+    match approval {
+        Some(AllowanceAction::Sufficient { allowance }) => {
+            formatter::print_info(&format!(
+                "USDC approval:  not needed (the registry may spend {})",
+                formatter::format_price(*allowance)
+            ));
+        }
+        Some(AllowanceAction::Approve { allowance, amount }) => {
+            formatter::print_info(&format!(
+                "USDC approval:  needed first: {} (the registry may spend {} now)",
+                formatter::format_price(*amount),
+                formatter::format_price(*allowance)
+            ));
+        }
+        None => {}
+    }
+    formatter::print_info(&format!(
+        "Balance:        {} ({})",
+        balance.display_eth(),
+        if sufficient {
+            "enough to submit"
+        } else {
+            "not enough to submit"
+        }
+    ));
+    Ok(())
+}
+
+/// Consult the buyer's standing approvals for a targeted request. Returns
+/// the seller whose policy covers it, or `None` to continue as usual.
+fn standing_approval(
+    target_agent_id: u64,
+    price_usdc: u64,
+    capability: Option<&str>,
+    tags: &[String],
+) -> Result<Option<String>> {
+    if target_agent_id == 0 {
+        return Ok(None);
+    }
+
+    let seller = trust::agent_seller(target_agent_id);
+    let store = TrustStore::load()?;
+    let now = clock::unix_now();
+
+    match store.check_request(&seller, price_usdc, capability, &normalize_tags(tags), now) {
+        Coverage::Approved(policy) => {
+            formatter::print_info(&format!(
+                "Confirmed under your standing approval for {seller} ({} of {} used this month).",
+                formatter::format_price(store.spent_this_month(&seller, now) + price_usdc),
+                formatter::format_price(policy.monthly_usdc),
+            ));
+            Ok(Some(seller))
+        }
+        Coverage::NoPolicy => Ok(None),
+        Coverage::Declined(reason) => {
+            formatter::print_warning(&format!("{reason} Continuing without standing approval."));
+            Ok(None)
+        }
+    }
+}
+
+/// Count a policy-confirmed request against its seller's monthly cap.
+fn record_confirmation(request: &LocalRequest) -> Result<()> {
+    let Some(ref seller) = request.trust_policy else {
+        return Ok(());
+    };
+    trust::record_action(
+        &request.request_id,
+        seller,
+        PolicyAction::Confirm,
+        request.price_usdc,
+    )
+    .context("Failed to record the request against your standing approval.")
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::sink;
+    use alloy::primitives::U256;
+
+    const REGISTRY: Address = Address::repeat_byte(0x22);
+
+    fn prepared() -> PreparedRequest {
+        PreparedRequest {
+            task: "Summarise the spec".to_string(),
+            price_usdc: 5_250_000,
+            deadline_ts: 1_760_115_600,
+            target_agent_id: 42,
+            tags: vec!["Docs".to_string()],
+            capability: Some("summarize".to_string()),
+            trust_policy: None,
+            attachments: vec![Attachment {
+                name: "spec.md".to_string(),
+                content: vec![b'x'; 1536],
+                archive: None,
+            }
+            .entry()],
+            payload_len: 2100,
+            ciphertext: vec![0; 2197],
+        }
+    }
+
+    #[test]
+    fn test_preview_json_is_machine_readable() {
+        let balance = Balance {
+            wei: U256::from(10u64).pow(U256::from(15)),
+        };
+        let estimate = FeeEstimate {
+            gas: 200_000,
+            max_fee_per_gas_wei: 1_000_000_000,
+        };
+        let approval = AllowanceAction::plan(1_000_000, 5_250_000, 0);
+        let json = preview_json(
+            &prepared(),
+            REGISTRY,
+            Some(&estimate),
+            3000.0,
+            Some(&approval),
+            &balance,
+            true,
+        );
+
+        assert_eq!(json["dry_run"], true);
+        assert_eq!(json["price_usdc"], 5_250_000);
+        assert_eq!(json["deadline"], 1_760_115_600u64);
+        assert_eq!(json["target_agent_id"], 42);
+        assert_eq!(json["tags"], json!(["docs"]));
+        assert_eq!(json["capability"], "summarize");
+        assert_eq!(json["attachments"][0]["name"], "spec.md");
+        assert_eq!(json["attachments"][0]["size"], 1536);
+        assert_eq!(json["payload_bytes"], 2100);
+        assert_eq!(json["encrypted_bytes"], 2197);
+        assert_eq!(json["estimated_gas"], 200_000);
+        assert_eq!(json["estimated_cost_wei"], "200000000000000");
+        assert_eq!(json["estimated_fee"]["gas"], 200_000);
+        assert_eq!(json["estimated_fee"]["max_fee_per_gas_wei"], "1000000000");
+        assert_eq!(json["estimated_fee"]["total_eth"], "0.00020000");
+        assert!((json["estimated_fee"]["approx_usd"].as_f64().unwrap() - 0.6).abs() < 1e-9);
+        assert_eq!(
+            json["usdc_approval"],
+            json!({ "action": "approve", "allowance": 1_000_000, "amount": 5_250_000 })
+        );
+        assert_eq!(json["registry"], REGISTRY.to_string());
+        assert_eq!(json["balance_sufficient"], true);
+
+        let json = preview_json(
+            &prepared(),
+            Address::ZERO,
+            None,
+            3000.0,
+            None,
+            &balance,
+            true,
+        );
+        assert!(json["registry"].is_null());
+        assert!(json["estimated_gas"].is_null());
+        assert!(json["estimated_fee"].is_null());
+        assert!(json["usdc_approval"].is_null());
+    }
+
+    #[test]
+    fn test_commit_summary() {
+        let mut request = prepared();
+        assert_eq!(
+            commit_summary(&request),
+            "About to commit $5.25 to agent #42"
+        );
+        request.target_agent_id = 0;
+        assert_eq!(
+            commit_summary(&request),
+            "About to commit $5.25 to an open request"
+        );
+    }
+
+    #[test]
+    fn test_approval_summary() {
+        assert_eq!(
+            approval_summary(5_250_000, 0, 5_250_000),
+            "The request registry may spend $0.00 of your USDC, but this request needs $5.25. \
+             About to approve $5.25"
+        );
+        assert!(approval_summary(5_250_000, 1_000_000, 50_000_000)
+            .ends_with("may spend $1.00 of your USDC, but this request needs $5.25. About to approve $50.00"));
+    }
+
+    #[test]
+    fn test_print_preview() {
+        let balance = Balance { wei: U256::ZERO };
+        let (result, capture) = sink::capture(|| {
+            print_preview(
+                &prepared(),
+                Address::ZERO,
+                None,
+                3000.0,
+                None,
+                &balance,
+                false,
+                1_760_000_000,
+            )
+        });
+        result.unwrap();
+        let out = capture.out().join("\n");
+        assert!(!out.contains("USDC approval"), "{out}");
+
+        assert!(
+            out.contains("Price:          $5.25 (5250000 USDC units)"),
+            "{out}"
+        );
+        assert!(
+            out.contains("Deadline:       2025-10-10 17:00 UTC (in 1d 8h; timestamp 1760115600)"),
+            "{out}"
+        );
+        assert!(out.contains("Target:         agent #42"), "{out}");
+        assert!(out.contains("Attachment:     spec.md (1.5 KiB)"), "{out}");
+        assert!(
+            out.contains("Payload:        2.1 KiB (2.1 KiB encrypted)"),
+            "{out}"
+        );
+        assert!(out.contains("Estimated fee:  unavailable"), "{out}");
+        assert!(out.contains("not enough to submit"), "{out}");
+    }
+
+    #[test]
+    fn test_print_preview_states_approval() {
+        let balance = Balance { wei: U256::ZERO };
+        let preview_with = |approval: AllowanceAction| {
+            let (result, capture) = sink::capture(|| {
+                print_preview(
+                    &prepared(),
+                    REGISTRY,
+                    None,
+                    3000.0,
+                    Some(&approval),
+                    &balance,
+                    true,
+                    1_760_000_000,
+                )
+            });
+            result.unwrap();
+            capture.out().join("\n")
+        };
+
+        let out = preview_with(AllowanceAction::plan(u64::MAX, 5_250_000, 0));
+        assert!(out.contains("USDC approval:  not needed"), "{out}");
+        let out = preview_with(AllowanceAction::plan(0, 5_250_000, 0));
+        assert!(
+            out.contains("USDC approval:  needed first: $5.25 (the registry may spend $0.00 now)"),
+            "{out}"
+        );
+    }
+}
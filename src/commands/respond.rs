@@ -3,57 +3,86 @@
 //! Builds a deliverable payload from a file and/or message, generates a
 //! hash-lock secret, encrypts the deliverable with ECIES using the buyer's
 //! public key, uploads it to IPFS, and (when the Request Registry contract
-//! is deployed) submits a `submitResponse` transaction on-chain.
+//! is deployed) submits a `submitResponse` transaction on-chain, marking the
+//! request responded only once it is confirmed.
 //!
-//! The secret S is stored locally in the request cache -- losing it means
-//! losing the ability to claim payment. The keccak256(S) hash is published
-//! on-chain as part of the response.
+//! The secret S is stored locally in the request cache. By default it is
+//! derived from the agent's key and the request ID, so `claim` can recover
+//! it from the keystore if the cache is lost; with `--random-secret`,
+//! losing it means losing the ability to claim payment. The keccak256(S)
+//! hash is published on-chain as part of the response.
+//!
+//! Re-running is safe: a deliverable uploaded by a run that failed later is
+//! reused rather than uploaded again, and a response the registry already
+//! holds from this agent is adopted into the local cache. `--replace`
+//! attaches a corrected deliverable to a response that has not been
+//! validated yet.
 
 use std::fs;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::io::{self, Read};
 
-use alloy::primitives::Address;
+use alloy::primitives::{Address, U256};
 use anyhow::{bail, Context, Result};
 use tracing::debug;
 
 use super::CommandContext;
 use crate::chain::client::ChainClient;
-use crate::chain::contracts::addresses;
-use crate::chain::types::Balance;
+use crate::chain::contracts::{Contract, RequestRegistry};
+use crate::chain::types::{Balance, Response};
+use crate::clock;
+use crate::config::lock;
+use crate::engine::archive;
+use crate::engine::attachments::{self, Attachment};
+use crate::engine::payment::{self, PaymentCheck};
 use crate::engine::requests::{
-    format_price_usd, generate_secret, LocalRequestStatus, RequestCache, RequestRole,
+    deliverable_digest, generate_secret, normalize_tags, summarize_task, LocalRequest,
+    LocalRequestStatus, RequestCache, RequestRole, ResponseAttempt,
 };
 use crate::ipfs::client::IpfsClient;
 use crate::ipfs::encryption;
 use crate::ipfs::pin::PinningService;
 use crate::output::formatter;
 
+#[allow(clippy::too_many_arguments)]
 pub async fn run(
     request_id: String,
     file_path: Option<String>,
+    dir: Option<String>,
+    stdin: bool,
     message: Option<String>,
+    tags: Vec<String>,
+    accept_unsecured: bool,
+    random_secret: bool,
+    replace: bool,
 ) -> Result<()> {
-    debug!("starting respond command");
+    debug!(stdin, replace, "starting respond command");
 
-    // 0. Validate that at least one of file or message is provided.
-    if file_path.is_none() && message.is_none() {
-        bail!("Provide a file (--file) and/or a message (--message) for the response.");
-    }
-
-    // 1. If a file path was given, verify it exists before doing heavy setup.
-    if let Some(ref path) = file_path {
-        if !std::path::Path::new(path).exists() {
-            bail!("File not found: {path}");
-        }
-    }
+    // 0-1. Validate the deliverable sources before doing heavy setup.
+    check_sources(
+        file_path.as_deref(),
+        dir.is_some(),
+        stdin,
+        message.is_some(),
+    )?;
 
     // 2. Load config, verify registered, derive address.
     let ctx = CommandContext::load_registered()?;
 
     debug!(address = %ctx.address, "agent address derived");
 
+    // 2b. Read a piped deliverable now, so an empty or oversized one is
+    //     refused before any network work.
+    let piped = if stdin {
+        Some(read_deliverable(
+            &mut io::stdin().lock(),
+            ctx.cfg.requests.max_attachment_bytes,
+        )?)
+    } else {
+        None
+    };
+
     // 3. Check ETH balance -- bail with funding instructions if insufficient.
-    let client = ChainClient::new(&ctx.cfg.network.chain_rpc).await?;
+    let client = ChainClient::shared(&ctx.cfg).await?;
     let addr: Address = ctx
         .address
         .parse()
@@ -69,25 +98,85 @@ pub async fn run(
         bail!("Insufficient funds. Send ETH to your agent address and try again.");
     }
 
-    // 4. Load the local request from cache to verify it exists and is Open.
-    let mut local_request = RequestCache::load(&request_id)
+    // 4. Lock the request so no other process works on it concurrently, then
+    //    load it from cache to verify it exists and is Open (or Responded,
+    //    with --replace).
+    let _request_lock = lock::acquire(&lock::request_lock(&request_id), "respond")?;
+    let local_request = RequestCache::load(&request_id)
         .with_context(|| format!("Request {request_id} not found in local cache."))?;
 
-    if local_request.status != LocalRequestStatus::Open {
-        bail!(
-            "Request {} is not open for responses (current status: {:?}).",
-            request_id,
-            local_request.status,
-        );
+    let replacing = match local_request.status {
+        LocalRequestStatus::Open => false,
+        LocalRequestStatus::Responded if replace => true,
+        LocalRequestStatus::Responded => {
+            formatter::print_success(&format!("Request {request_id} already has your response."));
+            if let Some(cid) = &local_request.response_cid {
+                formatter::print_info(&format!("  Content ID: {cid}"));
+            }
+            formatter::print_info(
+                "To send a corrected deliverable before it is validated, run again with --replace.",
+            );
+            return Ok(());
+        }
+        ref status if replace => bail!(
+            "The response to request {request_id} can no longer be replaced \
+             (current status: {status:?})."
+        ),
+        ref status => {
+            bail!("Request {request_id} is not open for responses (current status: {status:?}).")
+        }
+    };
+
+    // 4b. A response from this address that reached the registry after an
+    //     earlier run failed only needs recording.
+    if !replacing && !ctx.contracts.request_registry.is_zero() {
+        if let Some(response) =
+            our_onchain_response(&client, ctx.contracts.request_registry, &request_id, addr).await?
+        {
+            return adopt_response(&ctx, &request_id, response, tags);
+        }
     }
 
+    // 5. Make sure the buyer can actually pay before doing the work of
+    //    uploading a response. A replacement was checked the first time.
+    let payment_check = if replacing {
+        local_request.payment_check.clone()
+    } else {
+        check_payment(&client, &local_request, accept_unsecured).await?
+    };
+
     formatter::print_info(&format!(
         "Preparing response to request {} ({})...",
         request_id,
-        format_price_usd(local_request.price_usdc),
+        formatter::format_price(local_request.price_usdc),
     ));
 
-    // 5. Build deliverable payload (file content and/or message).
+    // 5b. Read the buyer's request: note its task text on the cached request
+    //     and list the files attached. The request payload may not be
+    //     readable with our key, so this is best-effort.
+    let ipfs_client = IpfsClient::from_config(&ctx.cfg);
+    match read_request(&ipfs_client, &ctx.key_bytes, &local_request.request_cid).await {
+        Ok(request) => {
+            if let (None, Some(task)) = (&local_request.task_summary, &request.task) {
+                RequestCache::update(&request_id, |r| {
+                    r.task_summary = Some(summarize_task(task));
+                    Ok(())
+                })?;
+            }
+            print_attachments(&request.attachments);
+            for file in request
+                .attachments
+                .iter()
+                .filter(|f| archive::is_archive(&f.content))
+            {
+                super::offer_extract(&file.name, &file.content, &request_id)?;
+            }
+        }
+        Err(err) => debug!(error = %format!("{err:#}"), "request payload unavailable"),
+    }
+
+    // 6. Build deliverable payload (file content and/or message). Only the
+    //    task text above goes into `task_summary`, never the deliverable.
     let mut payload = Vec::new();
 
     if let Some(ref msg) = message {
@@ -103,27 +192,212 @@ pub async fn run(
         payload.extend_from_slice(&file_content);
     }
 
-    debug!(payload_size = payload.len(), "deliverable payload built");
+    if let Some(piped) = piped {
+        payload.extend_from_slice(b"--- FILE ---\n");
+        payload.extend_from_slice(&piped);
+    }
 
-    // 6. Generate secret S and compute keccak256(S) for the hash-lock.
-    let (secret_hex, secret_hash_hex) = generate_secret();
-    debug!("secret and hash generated for hash-lock pattern");
+    if let Some(ref dir) = dir {
+        let packed = super::pack_dir(dir, ctx.cfg.requests.max_attachment_bytes)?;
+        archive::write_deliverable(&mut payload, &packed.bytes);
+    }
 
-    // 7. Encrypt deliverable with ECIES using our own public key.
-    //    In a full implementation, the buyer's public key would be used so
-    //    only the buyer can decrypt it. For now we use our own public key
-    //    since the buyer's key is not yet available in the local cache.
+    let digest = deliverable_digest(&payload);
+    debug!(payload_size = payload.len(), %digest, "deliverable payload built");
+
+    // 6b. Resume an earlier attempt that uploaded this same deliverable.
+    let resumed = resume_attempt(&local_request, &digest, replace)?;
+    if let Some(attempt) = &resumed {
+        if !ctx.contracts.request_registry.is_zero() {
+            if let Some(since) =
+                super::awaiting_confirmation(attempt.submitted_at, clock::unix_now())
+            {
+                formatter::print_info(&format!(
+                    "Your response to request {request_id} was submitted {} ago and is \
+                     awaiting confirmation. Run this again later to record it.",
+                    clock::format_duration(clock::unix_now().saturating_sub(since))
+                ));
+                return Ok(());
+            }
+        }
+    }
+
+    // 7. Generate secret S and compute keccak256(S) for the hash-lock. By
+    //    default S is derived from our key so it can be recovered from the
+    //    keystore if the local cache is lost. A secret saved by an earlier
+    //    attempt, or for the response being replaced, is kept.
+    let (secret_hex, secret_hash_hex) = match (&local_request.secret, &local_request.secret_hash) {
+        (Some(secret), Some(hash)) => (secret.clone(), hash.clone()),
+        _ if random_secret => generate_secret(),
+        _ => ctx.derive_secret(&request_id),
+    };
+    debug!(
+        random_secret,
+        "secret and hash prepared for hash-lock pattern"
+    );
+
+    // 8-10. Encrypt, upload, and pin the deliverable, once per deliverable.
+    let cid = match resumed {
+        Some(attempt) => {
+            debug!(cid = %attempt.cid, "deliverable uploaded by an earlier run");
+            formatter::print_info("Response already uploaded.");
+            attempt.cid
+        }
+        None => {
+            let cid = upload_deliverable(&ipfs_client, &ctx.public_key, &payload).await?;
+            if !replacing {
+                // Checkpoint the upload so a failure below does not repeat it.
+                RequestCache::update(&request_id, |r| {
+                    r.secret = Some(secret_hex.clone());
+                    r.secret_hash = Some(secret_hash_hex.clone());
+                    r.response_attempt = Some(ResponseAttempt {
+                        digest: digest.clone(),
+                        cid: cid.clone(),
+                        submitted_at: None,
+                    });
+                    Ok(())
+                })
+                .context("Failed to save response progress to local cache.")?;
+            }
+            cid
+        }
+    };
+
+    // 11. Contract deployment gate: check if REQUEST_REGISTRY is ZERO.
+    if replacing {
+        // The registry keeps the first response; the corrected deliverable
+        // is what the buyer is pointed at from here on.
+        // TODO: Submit it on-chain if the registry gains a way to replace a
+        // response before validation.
+        debug!(cid = %cid, "response replaced locally");
+    } else if ctx.contracts.request_registry.is_zero() {
+        formatter::print_warning(&format!(
+            "The request registry is not yet deployed on {}. \
+             Your response has been saved locally and will be submitted \
+             when the contract goes live. {}",
+            ctx.contracts.network,
+            Contract::RequestRegistry.setup_hint()
+        ));
+    } else {
+        let registry = ctx.contracts.require(Contract::RequestRegistry)?;
+        let call = RequestRegistry::submitResponseCall {
+            requestId: request_id
+                .parse()
+                .with_context(|| format!("{request_id} is not an on-chain request ID"))?,
+            ipfsCid: format!("ipfs://{cid}"),
+            secretHash: secret_hash_hex
+                .parse()
+                .context("the hash lock is not a 32-byte hex value")?,
+        };
+        // A response the registry would refuse (the request was taken or
+        // has expired meanwhile) stops here, before any gas is spent.
+        client.simulate(addr, registry, &call).await?;
+        let estimate = super::estimate_fee(
+            "response",
+            client.estimate_submit_response_cost(
+                registry,
+                addr,
+                call.requestId,
+                &call.ipfsCid,
+                call.secretHash,
+            ),
+        )
+        .await?;
+        super::print_fee(estimate.as_ref(), ctx.cfg.fees.eth_usd);
+        formatter::print_info("Submitting response on-chain...");
+        let signer = ctx.transaction_signer()?;
+        let tx_hash = client
+            .submit_response(
+                registry,
+                &signer,
+                call.requestId,
+                &call.ipfsCid,
+                call.secretHash,
+                |quote| super::confirm_fee_over_cap("response", quote),
+            )
+            .await?;
+        debug!(%tx_hash, request_id = %request_id, cid = %cid, "response submitted");
+        RequestCache::update(&request_id, |r| {
+            if let Some(attempt) = r.response_attempt.as_mut() {
+                attempt.submitted_at = Some(clock::unix_now());
+            }
+            Ok(())
+        })
+        .context("Failed to save response progress to local cache.")?;
+        // Unconfirmed: the request stays Open locally, and a re-run adopts
+        // the response from the registry once it lands.
+        if !super::await_confirmation(&client, &ctx.cfg.network, tx_hash, "response").await? {
+            return Ok(());
+        }
+    }
+
+    // 12. Save secret S locally -- a random S cannot be recovered if lost.
+    let local_request = RequestCache::update(&request_id, |r| {
+        if !replacing {
+            r.transition(LocalRequestStatus::Responded)?;
+        }
+        r.response_cid = Some(cid.clone());
+        r.secret = Some(secret_hex);
+        r.secret_hash = Some(secret_hash_hex);
+        r.role = RequestRole::Seller;
+        r.tags.extend(tags);
+        r.tags = normalize_tags(&r.tags);
+        r.payment_check = payment_check.clone();
+        r.response_attempt = None;
+        Ok(())
+    })
+    .context("Failed to save response to local cache.")?;
+    debug!(request_id = %request_id, "local request cache updated with response");
+
+    // 13. Display success with response details (zero-crypto UX).
+    if replacing {
+        formatter::print_success(&format!("Response to request {request_id} replaced."));
+    } else {
+        formatter::print_success(&format!("Response submitted for request {request_id}."));
+    }
+    formatter::print_info(&format!(
+        "  Price: {}",
+        formatter::format_price(local_request.price_usdc)
+    ));
+    formatter::print_info(&format!("  Content ID: {cid}"));
+
+    if replacing && !ctx.contracts.request_registry.is_zero() {
+        formatter::print_info(
+            "  Status: Replaced locally. The registry still lists your first response.",
+        );
+    } else if ctx.contracts.request_registry.is_zero() {
+        formatter::print_info("  Status: Saved locally (pending contract deployment).");
+    } else {
+        formatter::print_info("  Status: Confirmed on-chain.");
+    }
+
+    if random_secret {
+        formatter::print_warning(
+            "Your claim secret is random and stored only locally. Do not delete \
+             your agent data before claiming payment.",
+        );
+    }
+
+    Ok(())
+}
+
+/// Encrypt the deliverable, upload it, and pin it via the remote pinning
+/// service if one is configured. Returns the CID.
+async fn upload_deliverable(ipfs: &IpfsClient, public_key: &str, payload: &[u8]) -> Result<String> {
+    // Encrypt with ECIES using our own public key. In a full implementation
+    // the buyer's public key would be used so only the buyer can decrypt it.
+    // For now we use our own public key since the buyer's key is not yet
+    // available in the local cache.
     let encrypted_payload =
-        encryption::encrypt(&ctx.public_key, &payload).context("Failed to encrypt deliverable.")?;
+        encryption::encrypt(public_key, payload).context("Failed to encrypt deliverable.")?;
 
     debug!(
         encrypted_size = encrypted_payload.len(),
         "deliverable encrypted"
     );
 
-    // 8. Upload encrypted deliverable to IPFS.
-    let ipfs_client = IpfsClient::from_config(&ctx.cfg);
-    let cid = ipfs_client
+    // Upload the encrypted deliverable to IPFS.
+    let cid = ipfs
         .add(&encrypted_payload)
         .await
         .context("Failed to upload response to content network.")?;
@@ -131,7 +405,7 @@ pub async fn run(
     debug!(cid = %cid, "encrypted deliverable uploaded to IPFS");
     formatter::print_info("Response uploaded to content network.");
 
-    // 9. Optionally pin via remote pinning service.
+    // Optionally pin via remote pinning service.
     if let Some(pinner) = PinningService::from_env() {
         debug!("remote pinning service configured -- pinning response");
         match pinner.pin_by_hash(&cid).await {
@@ -150,72 +424,273 @@ pub async fn run(
         debug!("no remote pinning service configured -- skipping remote pin");
     }
 
-    // 10. Contract deployment gate: check if REQUEST_REGISTRY is ZERO.
-    if addresses::REQUEST_REGISTRY == Address::ZERO {
+    Ok(cid)
+}
+
+/// The earlier attempt to reuse, if `request` has one for the deliverable
+/// with `digest`. A different deliverable is refused unless `replace` is
+/// set, so an edited file is not sent by accident.
+fn resume_attempt(
+    request: &LocalRequest,
+    digest: &str,
+    replace: bool,
+) -> Result<Option<ResponseAttempt>> {
+    match &request.response_attempt {
+        Some(attempt) if attempt.digest == digest => Ok(Some(attempt.clone())),
+        Some(_) if !replace => bail!(
+            "An earlier attempt to respond to request {} uploaded a different deliverable. \
+             Run again with --replace to send this one instead.",
+            request.request_id
+        ),
+        _ => Ok(None),
+    }
+}
+
+/// The registry's response to `request_id`, if this agent submitted it.
+async fn our_onchain_response(
+    client: &ChainClient,
+    registry: Address,
+    request_id: &str,
+    seller: Address,
+) -> Result<Option<Response>> {
+    let Ok(id) = request_id.parse::<U256>() else {
+        debug!(
+            request_id,
+            "not an on-chain request ID -- skipping response lookup"
+        );
+        return Ok(None);
+    };
+    let response = client.get_response(registry, id).await?;
+    Ok(response.filter(|r| r.seller == seller))
+}
+
+/// Record a response found in the registry as this agent's response.
+fn adopt_response(
+    ctx: &CommandContext,
+    request_id: &str,
+    response: Response,
+    tags: Vec<String>,
+) -> Result<()> {
+    let cid = response
+        .ipfs_cid
+        .strip_prefix("ipfs://")
+        .unwrap_or(&response.ipfs_cid)
+        .to_string();
+    let hash_hex = format!("0x{}", hex::encode(response.secret_hash));
+    let derived = ctx.derive_secret(request_id);
+    debug!(request_id, cid = %cid, "response already in the registry; adopting");
+
+    let local_request = RequestCache::update(request_id, |r| {
+        r.transition_with_note(
+            LocalRequestStatus::Responded,
+            Some("adopted from registry".to_string()),
+        )?;
+        r.secret = secret_for(r, &hash_hex, derived.clone());
+        r.secret_hash = Some(hash_hex.clone());
+        r.response_cid = Some(cid.clone());
+        r.role = RequestRole::Seller;
+        r.tags.extend(tags);
+        r.tags = normalize_tags(&r.tags);
+        r.response_attempt = None;
+        Ok(())
+    })
+    .context("Failed to save response to local cache.")?;
+
+    formatter::print_success(&format!(
+        "Your response to request {request_id} is already in the registry. Recorded it locally."
+    ));
+    formatter::print_info(&format!("  Content ID: {cid}"));
+    if local_request.secret.is_none() {
         formatter::print_warning(
-            "The request registry is not yet deployed. \
-             Your response has been saved locally and will be submitted \
-             when the contract goes live.",
+            "The claim secret for this response is not stored on this machine and \
+             does not match your key, so payment cannot be claimed from here.",
         );
-    } else {
-        // TODO: Submit the submitResponse transaction on-chain once the
-        // alloy provider-with-signer integration is complete:
-        //   let signer = TransactionSigner::from_keystore_with_passphrase(&passphrase)?;
-        //   let provider = ProviderBuilder::new()
-        //       .signer(signer.inner().clone())
-        //       .on_http(cfg.network.chain_rpc.parse()?);
-        //   let registry = RequestRegistry::new(addresses::REQUEST_REGISTRY, provider);
-        //   let secret_hash_bytes: B256 = secret_hash_hex.parse()?;
-        //   registry.submitResponse(
-        //       U256::from_str(&request_id)?,
-        //       format!("ipfs://{cid}"),
-        //       secret_hash_bytes,
-        //   ).send().await?.get_receipt().await?;
-        formatter::print_info("Submitting response on-chain...");
-        debug!(
-            contract = %addresses::REQUEST_REGISTRY,
-            request_id = %request_id,
-            cid = %cid,
-            "would submit submitResponse transaction (placeholder)"
+    }
+    Ok(())
+}
+
+/// The secret behind a response published with `hash_hex`: the one saved
+/// by an earlier run if it matches, else the `derived` (secret, hash) pair
+/// if it matches, else none.
+fn secret_for(request: &LocalRequest, hash_hex: &str, derived: (String, String)) -> Option<String> {
+    let matches = |hash: &str| hash.eq_ignore_ascii_case(hash_hex);
+    match (&request.secret, &request.secret_hash) {
+        (Some(secret), Some(hash)) if matches(hash) => Some(secret.clone()),
+        _ => {
+            let (secret, hash) = derived;
+            matches(&hash).then_some(secret)
+        }
+    }
+}
+
+/// Check that the deliverable comes from somewhere, from at most one of
+/// `--file`, `--dir`, and `--stdin`, and that a given file exists.
+fn check_sources(
+    file_path: Option<&str>,
+    has_dir: bool,
+    stdin: bool,
+    has_message: bool,
+) -> Result<()> {
+    let sources = usize::from(file_path.is_some()) + usize::from(has_dir) + usize::from(stdin);
+    if sources == 0 && !has_message {
+        bail!(
+            "Provide a file (--file), directory (--dir), or piped input (--stdin) \
+             and/or a message (--message) for the response."
         );
     }
+    if sources > 1 {
+        bail!("Use only one of --file, --dir, and --stdin for the deliverable.");
+    }
+    if let Some(path) = file_path {
+        if !std::path::Path::new(path).exists() {
+            bail!("File not found: {path}");
+        }
+    }
+    Ok(())
+}
 
-    // 11. Save secret S locally -- CRITICAL: losing S means losing payment.
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs();
+/// Read a deliverable piped to stdin, refusing an empty one or one larger
+/// than `max_bytes`.
+fn read_deliverable<R: Read>(reader: &mut R, max_bytes: u64) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    reader
+        .take(max_bytes.saturating_add(1))
+        .read_to_end(&mut data)
+        .context("failed to read the deliverable from stdin")?;
+
+    if data.is_empty() {
+        bail!("Nothing was piped to --stdin. Refusing to submit an empty deliverable.");
+    }
+    if data.len() as u64 > max_bytes {
+        bail!(
+            "The deliverable on stdin is larger than the {} limit. Raise it with \
+             `agentmarket config set requests.max_attachment_bytes <BYTES>`.",
+            attachments::format_size(max_bytes)
+        );
+    }
+    debug!(size = data.len(), "deliverable read from stdin");
+    Ok(data)
+}
 
-    local_request.status = LocalRequestStatus::Responded;
-    local_request.response_cid = Some(cid.clone());
-    local_request.secret = Some(secret_hex);
-    local_request.secret_hash = Some(secret_hash_hex);
-    local_request.role = RequestRole::Seller;
-    local_request.updated_at = now;
+/// Fetch and decrypt the request payload at `request_cid` and unpack its
+/// attachments.
+/// The parts of a buyer's decrypted request payload that `respond` uses.
+struct RequestContents {
+    task: Option<String>,
+    attachments: Vec<Attachment>,
+}
 
-    RequestCache::save(&local_request).context("Failed to save response to local cache.")?;
-    debug!(request_id = %request_id, "local request cache updated with response");
+async fn read_request(
+    ipfs: &IpfsClient,
+    key_bytes: &[u8],
+    request_cid: &str,
+) -> Result<RequestContents> {
+    let ciphertext = ipfs.cat(request_cid).await?;
+    request_contents(key_bytes, &ciphertext)
+}
 
-    // 12. Display success with response details (zero-crypto UX).
-    formatter::print_success(&format!("Response submitted for request {}.", request_id,));
+fn request_contents(key_bytes: &[u8], ciphertext: &[u8]) -> Result<RequestContents> {
+    let plaintext = encryption::decrypt(key_bytes, ciphertext)?;
+    let payload: serde_json::Value =
+        serde_json::from_slice(&plaintext).context("request payload is not JSON")?;
+    Ok(RequestContents {
+        task: payload["task"].as_str().map(str::to_string),
+        attachments: attachments::from_request_payload(&payload)?,
+    })
+}
+
+fn print_attachments(files: &[Attachment]) {
+    if files.is_empty() {
+        return;
+    }
     formatter::print_info(&format!(
-        "  Price: {}",
-        format_price_usd(local_request.price_usdc)
+        "The buyer attached {} file{}:",
+        files.len(),
+        if files.len() == 1 { "" } else { "s" }
     ));
-    formatter::print_info(&format!("  Content ID: {cid}"));
+    for file in files {
+        let entry = file.entry();
+        let contents = entry
+            .archive
+            .map(|info| {
+                format!(
+                    "  ({} files, {} unpacked)",
+                    info.file_count,
+                    attachments::format_size(info.uncompressed_size)
+                )
+            })
+            .unwrap_or_default();
+        formatter::print_info(&format!(
+            "  {}  {}  sha256 {}{contents}",
+            entry.name,
+            attachments::format_size(entry.size),
+            &entry.sha256[..12]
+        ));
+    }
+}
 
-    if addresses::REQUEST_REGISTRY == Address::ZERO {
-        formatter::print_info("  Status: Saved locally (pending contract deployment).");
-    } else {
-        formatter::print_info("  Status: Pending on-chain confirmation.");
+/// Check that the buyer's payment is secured: read their USDC balance and
+/// approval toward the registry and judge them with [`payment::assess`].
+///
+/// An unsecured or unverifiable payment is an error unless
+/// `accept_unsecured` is set, in which case it is reported and the result
+/// returned for recording. Returns `None` when nothing could be checked.
+async fn check_payment(
+    client: &ChainClient,
+    request: &LocalRequest,
+    accept_unsecured: bool,
+) -> Result<Option<PaymentCheck>> {
+    let registry = client.contracts().request_registry;
+    if registry.is_zero() {
+        debug!("request registry not deployed -- skipping payment check");
+        return Ok(None);
     }
 
-    formatter::print_warning(
-        "Your claim secret is stored locally. Do not delete your agent data \
-         before claiming payment.",
-    );
+    let checked: Result<PaymentCheck> = async {
+        let buyer: Address = request
+            .counterparty
+            .as_deref()
+            .context("the buyer of this request is not known locally")?
+            .parse()
+            .context("the buyer address stored for this request is invalid")?;
+        let balance = client.get_usdc_balance(buyer).await?;
+        let allowance = client.get_usdc_allowance(buyer, registry).await?;
+        debug!(request_id = %request.request_id, %buyer, balance, allowance, "buyer funds read");
+        Ok(payment::assess(
+            request.price_usdc,
+            balance,
+            allowance,
+            clock::unix_now(),
+        ))
+    }
+    .await;
 
-    Ok(())
+    match checked {
+        Ok(check) if check.is_secured() => {
+            formatter::print_info(&check.describe());
+            Ok(Some(check))
+        }
+        Ok(check) => {
+            formatter::print_warning(&check.describe());
+            if !accept_unsecured {
+                bail!(
+                    "Not responding: a claim on request {} would likely fail. \
+                     Pass --accept-unsecured to respond anyway.",
+                    request.request_id
+                );
+            }
+            formatter::print_warning("Responding anyway (--accept-unsecured).");
+            Ok(Some(check))
+        }
+        Err(err) => {
+            formatter::print_warning(&format!("Could not verify the buyer's payment: {err:#}"));
+            if !accept_unsecured {
+                bail!("Not responding without a payment check. Pass --accept-unsecured to respond anyway.");
+            }
+            formatter::print_warning("Responding anyway (--accept-unsecured).");
+            Ok(None)
+        }
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -225,6 +700,273 @@ pub async fn run(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::engine::archive::ArchiveInfo;
+    use crate::output::sink;
+
+    #[test]
+    fn test_check_sources() {
+        assert!(check_sources(None, false, true, false).is_ok());
+        assert!(check_sources(None, false, false, true).is_ok());
+        assert!(check_sources(None, true, false, true).is_ok());
+
+        let err = check_sources(None, false, false, false)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("piped input (--stdin)"), "{err}");
+
+        let err = check_sources(Some("deliverable.txt"), false, true, false)
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("only one of --file, --dir, and --stdin"),
+            "{err}"
+        );
+        assert!(check_sources(None, true, true, false).is_err());
+
+        let err = check_sources(Some("/nonexistent/deliverable.txt"), false, false, false)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("File not found"), "{err}");
+    }
+
+    #[test]
+    fn test_read_deliverable_from_stdin() {
+        assert_eq!(
+            read_deliverable(&mut "report body".as_bytes(), 11).unwrap(),
+            b"report body"
+        );
+
+        let err = read_deliverable(&mut "report body!".as_bytes(), 11)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("larger than the 11 B limit"), "{err}");
+
+        let err = read_deliverable(&mut "".as_bytes(), 11)
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("Refusing to submit an empty deliverable"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_print_attachments() {
+        let files = attachments::unpack(&attachments::bundle(&[
+            Attachment {
+                name: "spec.md".to_string(),
+                content: vec![b'x'; 2048],
+                archive: None,
+            },
+            Attachment {
+                name: "project.tar.gz".to_string(),
+                content: b"a,b\n".to_vec(),
+                archive: Some(ArchiveInfo {
+                    file_count: 3,
+                    uncompressed_size: 5120,
+                }),
+            },
+        ]))
+        .unwrap();
+
+        let (_, capture) = sink::capture(|| print_attachments(&files));
+        let lines = capture.out();
+        assert_eq!(lines[0], "The buyer attached 2 files:");
+        assert!(
+            lines[1].starts_with("  spec.md  2.0 KiB  sha256 "),
+            "{}",
+            lines[1]
+        );
+        assert!(
+            lines[2].starts_with("  project.tar.gz  4 B  sha256 "),
+            "{}",
+            lines[2]
+        );
+        assert!(
+            lines[2].ends_with("  (3 files, 5.0 KiB unpacked)"),
+            "{}",
+            lines[2]
+        );
+
+        let (_, capture) = sink::capture(|| print_attachments(&[]));
+        assert!(capture.out().is_empty());
+    }
+
+    fn open_request(attempt: Option<ResponseAttempt>) -> LocalRequest {
+        LocalRequest {
+            schema_version: crate::engine::requests::CURRENT_SCHEMA_VERSION,
+            request_id: "7".to_string(),
+            role: RequestRole::Seller,
+            status: LocalRequestStatus::Open,
+            request_cid: "QmTestCid123".to_string(),
+            price_usdc: 5_000_000,
+            deadline: 1_700_000_000,
+            response_cid: None,
+            secret: None,
+            secret_hash: None,
+            counterparty: None,
+            created_at: 1_699_000_000,
+            updated_at: 1_699_000_000,
+            previous_status: None,
+            tags: Vec::new(),
+            task_summary: None,
+            capability: None,
+            trust_policy: None,
+            history: Vec::new(),
+            payment_check: None,
+            response_attempt: attempt,
+        }
+    }
+
+    #[test]
+    fn test_request_contents_reads_task_and_attachments() {
+        let (key_bytes, public_key, _) = crate::engine::identity::generate_keypair().unwrap();
+        let payload = serde_json::json!({
+            "task": "Summarise the spec",
+            "attachment": "a,b\n",
+        });
+        let ciphertext = encryption::encrypt(&public_key, payload.to_string().as_bytes()).unwrap();
+
+        let request = request_contents(&key_bytes, &ciphertext).unwrap();
+        assert_eq!(request.task.as_deref(), Some("Summarise the spec"));
+        assert_eq!(request.attachments.len(), 1);
+
+        let (other_key, _, _) = crate::engine::identity::generate_keypair().unwrap();
+        assert!(request_contents(&other_key, &ciphertext).is_err());
+    }
+
+    #[test]
+    fn test_resume_attempt_reuses_same_deliverable_only() {
+        let digest = deliverable_digest(b"--- FILE ---\nreport");
+        let attempt = ResponseAttempt {
+            digest: digest.clone(),
+            cid: "bafyearlier".to_string(),
+            submitted_at: None,
+        };
+        let request = open_request(Some(attempt.clone()));
+
+        assert_eq!(
+            resume_attempt(&request, &digest, false).unwrap(),
+            Some(attempt)
+        );
+
+        let edited = deliverable_digest(b"--- FILE ---\nreport v2");
+        let err = resume_attempt(&request, &edited, false)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("uploaded a different deliverable"), "{err}");
+        assert!(err.contains("--replace"), "{err}");
+        assert_eq!(resume_attempt(&request, &edited, true).unwrap(), None);
+
+        assert_eq!(
+            resume_attempt(&open_request(None), &digest, false).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_secret_for_adopted_response() {
+        let (secret, hash) = generate_secret();
+        let derived = generate_secret();
+
+        // The secret saved by the failed run wins.
+        let mut request = open_request(None);
+        request.secret = Some(secret.clone());
+        request.secret_hash = Some(hash.clone());
+        assert_eq!(
+            secret_for(
+                &request,
+                &hash.to_uppercase().replace("0X", "0x"),
+                derived.clone()
+            ),
+            Some(secret)
+        );
+
+        // Otherwise the derived secret, if it is the published one.
+        let request = open_request(None);
+        assert_eq!(
+            secret_for(&request, &derived.1, derived.clone()),
+            Some(derived.0.clone())
+        );
+        assert_eq!(secret_for(&request, &hash, derived), None);
+    }
+
+    #[tokio::test]
+    async fn test_our_onchain_response_matches_seller() {
+        use alloy::primitives::{Bytes, B256};
+        use alloy::providers::mock::Asserter;
+        use alloy::sol_types::SolValue;
+
+        let me = Address::repeat_byte(0x33);
+        let stored = (me, "ipfs://bafyresponse".to_string(), B256::repeat_byte(1));
+        let asserter = Asserter::new();
+        asserter.push_success(&Bytes::from(stored.abi_encode_params()));
+        asserter.push_success(&Bytes::from(stored.abi_encode_params()));
+        let client = ChainClient::mocked(asserter);
+        let registry = Address::repeat_byte(0x11);
+
+        let response = our_onchain_response(&client, registry, "7", me)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(response.ipfs_cid, "ipfs://bafyresponse");
+
+        let other = Address::repeat_byte(0x44);
+        assert!(our_onchain_response(&client, registry, "7", other)
+            .await
+            .unwrap()
+            .is_none());
+
+        // A local-only ID never reaches the chain.
+        assert!(our_onchain_response(&client, registry, "local-1", me)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_check_payment_reads_buyer_funds() {
+        use crate::chain::contracts::ContractAddresses;
+        use alloy::primitives::Bytes;
+        use alloy::providers::mock::Asserter;
+
+        let word = |value: u64| Bytes::from(U256::from(value).to_be_bytes::<32>().to_vec());
+        let client = |balance: u64, allowance: u64| {
+            let asserter = Asserter::new();
+            asserter.push_success(&word(balance));
+            asserter.push_success(&word(allowance));
+            ChainClient::mocked(asserter).with_contracts(ContractAddresses {
+                request_registry: Address::repeat_byte(0x11),
+                ..ContractAddresses::default()
+            })
+        };
+        let mut request = open_request(None);
+        request.counterparty = Some(format!("{:#x}", Address::repeat_byte(0x22)));
+
+        let check = check_payment(&client(50_000_000, 5_000_000), &request, false)
+            .await
+            .unwrap()
+            .expect("checked");
+        assert!(check.is_secured());
+        assert_eq!(check.balance_usdc, 50_000_000);
+
+        let err = check_payment(&client(50_000_000, 1_000_000), &request, false)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("--accept-unsecured"), "{err}");
+        let check = check_payment(&client(50_000_000, 1_000_000), &request, true)
+            .await
+            .unwrap()
+            .expect("checked");
+        assert!(!check.is_secured());
+
+        // A failed read is not mistaken for a verdict.
+        let unreachable = ChainClient::mocked(Asserter::new()).with_contracts(ContractAddresses {
+            request_registry: Address::repeat_byte(0x11),
+            ..ContractAddresses::default()
+        });
+        assert!(check_payment(&unreachable, &request, false).await.is_err());
+    }
 
     #[test]
     fn test_generate_secret_produces_valid_pair() {
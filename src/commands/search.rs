@@ -1,82 +1,269 @@
 //! The `search` command: discover agents and requests on the network.
 //!
 //! Queries on-chain event logs via `eth_getLogs` to find registered agents
-//! and (in future) open requests. Supports filtering by capability.
+//! and (in future) open requests. Results are filtered, sorted, and limited
+//! by [`crate::engine::discovery`]. In JSON mode the results are printed as
+//! a JSON array, empty when nothing matched.
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use tracing::debug;
 
 use crate::chain::client::ChainClient;
-use crate::chain::contracts;
+use crate::clock;
 use crate::config;
+use crate::engine::discovery::{
+    self, AgentListing, AgentQuery, AgentSort, RequestListing, RequestQuery,
+};
+use crate::engine::reputation::AgentRef;
+use crate::engine::requests::parse_usd_amount;
+use crate::ipfs::client::IpfsClient;
 use crate::output::formatter;
 
-/// Search mode: what to look for.
+/// Search mode: what to look for, and how to narrow it.
 pub enum SearchMode {
     /// Search for registered agents (default).
-    Agents,
+    Agents(AgentQuery),
     /// Search for open requests.
-    Requests,
+    Requests(RequestQuery),
 }
 
-pub async fn run(capability: Option<String>, search_requests: bool) -> Result<()> {
-    debug!("starting search command");
+/// Run the `search` command.
+///
+/// `sort`, `min_reputation`, and `max_price` apply to agents;
+/// `min_price`, `deadline_within` (hours), and `mine` to requests. With
+/// `mine`, only requests this agent can serve are shown (see
+/// [`discovery::can_serve`]).
+#[allow(clippy::too_many_arguments)]
+pub async fn run(
+    capability: Option<String>,
+    search_requests: bool,
+    sort: String,
+    limit: Option<usize>,
+    min_reputation: Option<f64>,
+    max_price: Option<String>,
+    min_price: Option<String>,
+    deadline_within: Option<u64>,
+    mine: bool,
+) -> Result<()> {
+    debug!(
+        ?capability,
+        search_requests,
+        %sort,
+        ?limit,
+        ?min_reputation,
+        ?max_price,
+        ?min_price,
+        ?deadline_within,
+        mine,
+        "starting search command"
+    );
 
+    // 1. Parse filters before touching the network so typos fail fast.
     let mode = if search_requests {
-        SearchMode::Requests
+        let services = if mine {
+            if !config::store::exists()? {
+                bail!("Agent not initialized. Run `agentmarket init` first.");
+            }
+            Some(config::store::load()?.services)
+        } else {
+            None
+        };
+        SearchMode::Requests(RequestQuery {
+            capability,
+            min_price_usdc: min_price.as_deref().map(parse_usd_amount).transpose()?,
+            deadline_within_secs: deadline_within.map(|hours| hours.saturating_mul(3600)),
+            services,
+            limit,
+        })
     } else {
-        SearchMode::Agents
+        if min_reputation.is_some_and(|r| !(0.0..=100.0).contains(&r)) {
+            bail!("--min-reputation must be between 0 and 100.");
+        }
+        SearchMode::Agents(AgentQuery {
+            capability,
+            min_reputation,
+            max_price_usd: max_price
+                .as_deref()
+                .map(|p| parse_usd_amount(p).map(|usdc| usdc as f64 / 1_000_000.0))
+                .transpose()?,
+            sort: AgentSort::parse(&sort)?,
+            limit,
+        })
     };
 
-    // Load config for RPC endpoint.
+    // 2. Load config for RPC endpoint.
     let cfg = config::store::load().unwrap_or_else(|_| config::store::Config::default());
 
-    let client = ChainClient::new(&cfg.network.chain_rpc).await?;
+    let client = ChainClient::from_config(&cfg).await?;
 
     match mode {
-        SearchMode::Agents => search_agents(&client, capability.as_deref()).await,
-        SearchMode::Requests => search_requests_fn(&client, capability.as_deref()).await,
+        SearchMode::Agents(query) => {
+            search_agents(&client, &IpfsClient::from_config(&cfg), &query).await
+        }
+        SearchMode::Requests(query) => search_requests_fn(&client, &query).await,
     }
 }
 
-async fn search_agents(_client: &ChainClient, _capability: Option<&str>) -> Result<()> {
+async fn search_agents(client: &ChainClient, ipfs: &IpfsClient, query: &AgentQuery) -> Result<()> {
     formatter::print_info("Searching for registered agents...");
 
     // For MVP: Query AgentRegistered events from the Agent Registry.
-    // The Agent Registry address is a placeholder (zero address) until deployment.
-    let registry_addr = contracts::addresses::AGENT_REGISTRY;
+    // The Agent Registry address is zero until deployed on the network.
+    let registry_addr = client.contracts().agent_registry;
 
-    if registry_addr == alloy::primitives::Address::ZERO {
+    if registry_addr.is_zero() {
         formatter::print_warning(
             "Agent Registry not yet deployed. Search will be available after contract deployment.",
         );
+        if formatter::is_json_mode() {
+            formatter::print_json::<[AgentListing]>(&[])?;
+        }
         return Ok(());
     }
 
-    // TODO: Query eth_getLogs for AgentRegistered events
-    // For each event, fetch the agentURI from IPFS, parse the profile,
-    // and filter by capability if specified.
-    // This will be fully implemented once the contract is deployed (Phase 3).
+    let mut agents = fetch_agents(client).await?;
+    if query.min_reputation.is_some() {
+        fill_reputation(client, ipfs, &mut agents).await;
+    }
+    let agents = discovery::select_agents(agents, query);
+    debug!(count = agents.len(), "agents selected");
 
-    formatter::print_info("No agents found matching your criteria.");
+    if formatter::is_json_mode() {
+        formatter::print_json(&agents)?;
+    } else if agents.is_empty() {
+        formatter::print_info("No agents found matching your criteria.");
+    } else {
+        formatter::print_agent_list(&agents);
+    }
     Ok(())
 }
 
-async fn search_requests_fn(_client: &ChainClient, _capability: Option<&str>) -> Result<()> {
+async fn search_requests_fn(client: &ChainClient, query: &RequestQuery) -> Result<()> {
     formatter::print_info("Searching for open requests...");
 
-    let registry_addr = contracts::addresses::REQUEST_REGISTRY;
+    let registry_addr = client.contracts().request_registry;
 
-    if registry_addr == alloy::primitives::Address::ZERO {
+    if registry_addr.is_zero() {
         formatter::print_warning(
             "Request Registry not yet deployed. Request search will be available after contract deployment.",
         );
+        if formatter::is_json_mode() {
+            formatter::print_json::<[RequestListing]>(&[])?;
+        }
         return Ok(());
     }
 
-    // TODO: Query eth_getLogs for RequestCreated events
-    // This will be implemented in Phase 3 after contract deployment.
+    let requests =
+        discovery::select_requests(fetch_requests(client).await?, query, clock::unix_now());
+    debug!(count = requests.len(), "requests selected");
 
-    formatter::print_info("No open requests found matching your criteria.");
+    if formatter::is_json_mode() {
+        formatter::print_json(&requests)?;
+    } else if requests.is_empty() {
+        formatter::print_info("No open requests found matching your criteria.");
+    } else {
+        formatter::print_request_list(&requests, clock::unix_now());
+    }
     Ok(())
 }
+
+async fn fetch_agents(_client: &ChainClient) -> Result<Vec<AgentListing>> {
+    // TODO: Query eth_getLogs for AgentRegistered events
+    // For each event, fetch the agentURI from IPFS, parse the profile,
+    // and look up the agent's reputation.
+    // This will be fully implemented once the contract is deployed (Phase 3).
+    debug!("agent listing (placeholder)");
+    Ok(Vec::new())
+}
+
+/// Look up the reputation of listed agents that have none yet, so
+/// `--min-reputation` compares against their validation history. An agent
+/// that cannot be looked up stays unrated and is filtered out.
+async fn fill_reputation(client: &ChainClient, ipfs: &IpfsClient, agents: &mut [AgentListing]) {
+    for agent in agents.iter_mut().filter(|a| a.reputation.is_none()) {
+        let Ok(agent_ref) = AgentRef::parse(&agent.agent_id) else {
+            continue;
+        };
+        match super::reputation::fetch_remote_reputation(client, ipfs, &agent_ref).await {
+            Ok(remote) => agent.reputation = remote.rating(),
+            Err(err) => debug!(
+                agent_id = %agent.agent_id,
+                error = %format!("{err:#}"),
+                "reputation lookup failed"
+            ),
+        }
+    }
+}
+
+async fn fetch_requests(_client: &ChainClient) -> Result<Vec<RequestListing>> {
+    // TODO: Query eth_getLogs for RequestCreated events and read each
+    // request's metadata from IPFS.
+    // This will be implemented in Phase 3 after contract deployment.
+    debug!("request listing (placeholder)");
+    Ok(Vec::new())
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::sink;
+    use serde_json::Value;
+
+    fn printed_json<T: serde::Serialize + ?Sized>(value: &T) -> Value {
+        let (result, captured) = sink::capture(|| formatter::print_json(value));
+        result.unwrap();
+        let lines = captured.out();
+        assert_eq!(lines.len(), 1, "{lines:?}");
+        serde_json::from_str(&lines[0]).unwrap()
+    }
+
+    #[test]
+    fn test_agent_results_json() {
+        let agents = [AgentListing {
+            agent_id: "42".to_string(),
+            name: "summarizer".to_string(),
+            description: "Summarizes documents".to_string(),
+            capabilities: vec!["summarize".to_string()],
+            pricing_usd: 5.0,
+            reputation: None,
+        }];
+
+        let json = printed_json(&agents[..]);
+        let agent = &json.as_array().unwrap()[0];
+        assert_eq!(agent["id"], "42");
+        assert_eq!(agent["name"], "summarizer");
+        assert_eq!(agent["description"], "Summarizes documents");
+        assert_eq!(agent["capabilities"], serde_json::json!(["summarize"]));
+        assert_eq!(agent["price_usd"], 5.0);
+        assert_eq!(agent["reputation"], Value::Null);
+
+        assert_eq!(printed_json::<[AgentListing]>(&[]), serde_json::json!([]));
+    }
+
+    #[test]
+    fn test_request_results_json() {
+        let requests = [RequestListing {
+            request_id: "7".to_string(),
+            task_summary: "Review a PR".to_string(),
+            buyer: "0x1234567890abcdef1234567890abcdef12345678".to_string(),
+            capabilities: vec!["code-review".to_string()],
+            price_usdc: 5_000_000,
+            deadline: 1_700_000_000,
+        }];
+
+        let json = printed_json(&requests[..]);
+        let request = &json.as_array().unwrap()[0];
+        assert_eq!(request["id"], "7");
+        assert_eq!(request["task_summary"], "Review a PR");
+        assert_eq!(request["price_usdc"], 5_000_000);
+        assert_eq!(request["deadline"], 1_700_000_000);
+        assert_eq!(
+            request["buyer"],
+            "0x1234567890abcdef1234567890abcdef12345678"
+        );
+    }
+}
@@ -0,0 +1,287 @@
+//! The `send` command: send an encrypted message to another agent's
+//! mailbox.
+//!
+//! The recipient is given as an agent ID, resolved through the Agent
+//! Registry to its published profile, or directly as a compressed public
+//! key. Only the recipient can decrypt the message.
+//!
+//! With `--quote`, the message is instead a price offer for a request,
+//! signed as EIP-712 typed data (see [`crate::engine::orders`]) so the
+//! recipient can check it came from this agent.
+
+use std::fs;
+use std::str::FromStr;
+
+use alloy::primitives::U256;
+use anyhow::{bail, Context, Result};
+use serde_json::json;
+use tracing::debug;
+
+use crate::chain::client::ChainClient;
+use crate::chain::contracts;
+use crate::clock;
+use crate::config;
+use crate::config::store::Config;
+use crate::engine::identity::AgentProfile;
+use crate::engine::orders::{self, Quote};
+use crate::engine::requests::{parse_deadline, parse_usd_amount};
+use crate::ipfs::client::IpfsClient;
+use crate::ipfs::mailbox::{self, MailboxMessage};
+use crate::output::formatter;
+
+/// Largest payload accepted, in bytes.
+pub const MAX_PAYLOAD_BYTES: usize = 64 * 1024;
+
+/// How long a quote stands when `--deadline` is not given: one hour.
+const DEFAULT_QUOTE_DEADLINE: &str = "1";
+
+/// Who a message is addressed to, as given on the command line.
+#[derive(Debug, PartialEq, Eq)]
+enum Recipient {
+    /// Hex-encoded compressed public key, lowercase, without `0x`.
+    PublicKey(String),
+    AgentId(U256),
+}
+
+impl Recipient {
+    fn parse(to: &str) -> Result<Self> {
+        let to = to.trim();
+        if !to.is_empty() && to.chars().all(|c| c.is_ascii_digit()) {
+            let id = U256::from_str(to).with_context(|| format!("agent ID {to} is too large"))?;
+            return Ok(Recipient::AgentId(id));
+        }
+
+        let hex_str = to.strip_prefix("0x").unwrap_or(to).to_lowercase();
+        let is_compressed_key = hex_str.len() == 66
+            && (hex_str.starts_with("02") || hex_str.starts_with("03"))
+            && hex::decode(&hex_str).is_ok();
+        if !is_compressed_key {
+            bail!(
+                "'{to}' is neither an agent ID nor a compressed public key \
+                 (66 hex characters starting with 02 or 03)."
+            );
+        }
+        Ok(Recipient::PublicKey(hex_str))
+    }
+
+    /// How the recipient is shown in output.
+    fn label(&self) -> String {
+        match self {
+            Recipient::AgentId(id) => format!("agent {id}"),
+            Recipient::PublicKey(key) => format!("{}...", &key[..10]),
+        }
+    }
+}
+
+/// Run the `send` command.
+pub async fn run(
+    to: String,
+    message: Option<String>,
+    file: Option<String>,
+    message_type: String,
+    quote: Option<String>,
+    price: Option<String>,
+    deadline: Option<String>,
+) -> Result<()> {
+    debug!(%to, ?file, %message_type, ?quote, "starting send command");
+
+    // 1. Check initialized; the sender is identified by its public key
+    if !config::store::exists()? {
+        bail!("Agent not initialized. Run `agentmarket init` first.");
+    }
+    let cfg = config::store::load()?;
+
+    // 2. Build the payload before any network access. A quote needs the key
+    //    to sign it; a plain message does not.
+    let (payload, message_type) = match quote {
+        Some(request_id) => {
+            let ctx = super::CommandContext::load_initialized()?;
+            let quote = build_quote(
+                &ctx.address,
+                &request_id,
+                price.as_deref().unwrap_or_default(),
+                deadline.as_deref().unwrap_or(DEFAULT_QUOTE_DEADLINE),
+                clock::unix_now(),
+            )?;
+            let signed = ctx.sign_quote(&quote)?;
+            (
+                signed.to_json()?.into_bytes(),
+                orders::QUOTE_MESSAGE_TYPE.to_string(),
+            )
+        }
+        None => {
+            let payload = match (message, file) {
+                (_, Some(path)) => fs::read(&path)
+                    .with_context(|| format!("failed to read message file: {path}"))?,
+                (Some(text), None) => text.into_bytes(),
+                (None, None) => bail!("Nothing to send. Pass a message or --file."),
+            };
+            (payload, message_type)
+        }
+    };
+    check_payload_size(payload.len())?;
+
+    // 3. Resolve the recipient's public key
+    let recipient = Recipient::parse(&to)?;
+    let ipfs = IpfsClient::from_config(&cfg);
+    let public_key = resolve_public_key(&cfg, &ipfs, &recipient).await?;
+    debug!(recipient = %public_key, "recipient resolved");
+
+    // 4. Seal, publish, and announce
+    let message = MailboxMessage {
+        sender: cfg.identity.public_key.clone(),
+        timestamp: clock::unix_now(),
+        message_type,
+        payload,
+    };
+    let cid = mailbox::send_message(&ipfs, &public_key, &message)
+        .await
+        .context("failed to deliver message to the mailbox")?;
+    debug!(cid = %cid, "message sent");
+
+    if formatter::is_json_mode() {
+        formatter::print_json(&json!({
+            "recipient": public_key,
+            "message_type": message.message_type,
+            "id": cid,
+        }))?;
+    } else {
+        formatter::print_success(&format!(
+            "Message sent to {} (type: {}).",
+            recipient.label(),
+            message.message_type
+        ));
+    }
+    Ok(())
+}
+
+/// The quote this agent (at `seller`) offers for `request_id`, from the
+/// `--price` and `--deadline` given on the command line.
+fn build_quote(
+    seller: &str,
+    request_id: &str,
+    price: &str,
+    deadline: &str,
+    now: u64,
+) -> Result<Quote> {
+    let price_usdc = parse_usd_amount(price)?;
+    if price_usdc == 0 {
+        bail!("A quote needs a price above $0.");
+    }
+    Ok(Quote {
+        request_hash: orders::request_hash(request_id),
+        price_usdc,
+        deadline: parse_deadline(deadline, now)?,
+        seller: seller
+            .parse()
+            .with_context(|| format!("invalid seller address {seller}"))?,
+    })
+}
+
+fn check_payload_size(len: usize) -> Result<()> {
+    if len > MAX_PAYLOAD_BYTES {
+        bail!(
+            "Message is {len} bytes; the limit is {MAX_PAYLOAD_BYTES} bytes ({} KiB). \
+             Share larger files through a request instead.",
+            MAX_PAYLOAD_BYTES / 1024
+        );
+    }
+    Ok(())
+}
+
+/// The compressed public key to encrypt for. Agent IDs are looked up in the
+/// Agent Registry and their published profile read.
+async fn resolve_public_key(
+    cfg: &Config,
+    ipfs: &IpfsClient,
+    recipient: &Recipient,
+) -> Result<String> {
+    let agent_id = match recipient {
+        Recipient::PublicKey(key) => return Ok(key.clone()),
+        Recipient::AgentId(id) => *id,
+    };
+
+    let registry = contracts::for_config(cfg)?.agent_registry;
+    if registry.is_zero() {
+        bail!(
+            "The agent registry contract is not yet deployed, so agent IDs cannot be \
+             looked up. Pass the recipient's public key instead."
+        );
+    }
+
+    let client = ChainClient::shared(cfg).await?;
+    let Some(uri) = client.get_agent_uri(registry, agent_id).await? else {
+        bail!("No agent with ID {agent_id} is registered.");
+    };
+
+    let cid = uri.strip_prefix("ipfs://").unwrap_or(&uri);
+    let profile: AgentProfile = serde_json::from_slice(
+        &ipfs
+            .cat(cid)
+            .await
+            .with_context(|| format!("failed to fetch the profile of agent {agent_id}"))?,
+    )
+    .with_context(|| format!("agent {agent_id} published an unreadable profile"))?;
+
+    match Recipient::parse(&profile.public_key) {
+        Ok(Recipient::PublicKey(key)) => Ok(key),
+        _ => bail!("Agent {agent_id}'s profile has no usable public key."),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "02a1633cafcc01ebfb6d78e39f687a1f0995c62fc95f51ead10a02ee0be551b5dc";
+
+    #[test]
+    fn test_parse_recipient() {
+        assert_eq!(
+            Recipient::parse("42").unwrap(),
+            Recipient::AgentId(U256::from(42))
+        );
+        assert_eq!(
+            Recipient::parse(KEY).unwrap(),
+            Recipient::PublicKey(KEY.to_string())
+        );
+        assert_eq!(
+            Recipient::parse(&format!("0x{}", KEY.to_uppercase())).unwrap(),
+            Recipient::PublicKey(KEY.to_string())
+        );
+        assert_eq!(Recipient::parse(KEY).unwrap().label(), "02a1633caf...");
+
+        // Uncompressed keys, addresses, and names are rejected.
+        assert!(Recipient::parse(&format!("04{}", &KEY[2..])).is_err());
+        assert!(Recipient::parse("0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913").is_err());
+        assert!(Recipient::parse("summarizer").is_err());
+        assert!(Recipient::parse("").is_err());
+    }
+
+    #[test]
+    fn test_build_quote() {
+        let seller = "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266";
+        let quote = build_quote(seller, "42", "$5.25", "2", 1_000).unwrap();
+        assert_eq!(quote.request_hash, orders::request_hash("42"));
+        assert_eq!(quote.price_usdc, 5_250_000);
+        assert_eq!(quote.deadline, 1_000 + 7_200);
+        assert_eq!(quote.seller.to_string(), seller);
+
+        assert!(build_quote(seller, "42", "0", "2", 1_000).is_err());
+        assert!(build_quote(seller, "42", "five", "2", 1_000).is_err());
+        assert!(build_quote(seller, "42", "5", "yesterday", 1_000).is_err());
+    }
+
+    #[test]
+    fn test_payload_size_cap() {
+        assert!(check_payload_size(MAX_PAYLOAD_BYTES).is_ok());
+        let err = check_payload_size(MAX_PAYLOAD_BYTES + 1)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("the limit is 65536 bytes (64 KiB)"), "{err}");
+    }
+}
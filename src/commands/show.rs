@@ -0,0 +1,262 @@
+//! The `show` command: inspect a single request in detail.
+
+use alloy::primitives::U256;
+use anyhow::{bail, Context, Result};
+use serde_json::json;
+use tracing::debug;
+
+use crate::chain::client::ChainClient;
+use crate::chain::contracts;
+use crate::chain::types::OnchainRequest;
+use crate::clock;
+use crate::config;
+use crate::engine::identity::{self, IdentityState};
+use crate::engine::requests::{LocalRequest, RequestCache};
+use crate::engine::validation::{self, ValidationResult};
+use crate::output::formatter;
+
+/// Run the `show` command.
+///
+/// Prints everything known locally about `request_id`: the cached request,
+/// its transition history, and the validation result if this agent
+/// validated it. Requests not tracked on this machine are looked up on the
+/// network when the agent is registered. The seller's secret is only
+/// printed with `reveal_secret`.
+pub async fn run(request_id: String, reveal_secret: bool) -> Result<()> {
+    debug!(%request_id, reveal_secret, "starting show command");
+
+    // 1. Check initialized
+    if !config::store::exists()? {
+        bail!("Agent not initialized. Run `agentmarket init` first.");
+    }
+    let cfg = config::store::load()?;
+
+    // 2. Not tracked locally: fall back to the network's record
+    if !RequestCache::contains(&request_id)? {
+        return show_onchain(&cfg, &request_id).await;
+    }
+
+    // 3. Load the request and any validation result
+    let mut request = RequestCache::load(&request_id)?;
+    if !reveal_secret {
+        request = request.without_secret();
+    }
+    let validation = validation::load_result(&request_id).ok();
+    debug!(has_validation = validation.is_some(), "request loaded");
+
+    // 4. Print
+    if formatter::is_json_mode() {
+        let mut value = serde_json::to_value(&request)?;
+        value["validation"] = serde_json::to_value(&validation)?;
+        value["source"] = json!("local");
+        formatter::print_json(&value)?;
+    } else {
+        print_local(&request, validation.as_ref(), clock::unix_now());
+    }
+    Ok(())
+}
+
+/// Look `request_id` up in the Request Registry, for requests this machine
+/// never saw.
+async fn show_onchain(cfg: &config::store::Config, request_id: &str) -> Result<()> {
+    let registered = matches!(
+        identity::get_identity_state(cfg),
+        IdentityState::Registered { .. }
+    );
+    let registry = contracts::for_config(cfg)?.request_registry;
+    if !registered || registry.is_zero() {
+        bail!("Request {request_id} is not tracked on this machine.");
+    }
+
+    let id: U256 = request_id
+        .parse()
+        .with_context(|| format!("Request {request_id} is not tracked on this machine."))?;
+    let client = ChainClient::shared(cfg).await?;
+    let Some(request) = client.get_request(registry, id).await? else {
+        bail!("Request {request_id} was not found locally or on the network.");
+    };
+
+    if formatter::is_json_mode() {
+        let mut value = serde_json::to_value(&request)?;
+        value["source"] = json!("network");
+        formatter::print_json(&value)?;
+    } else {
+        print_onchain(&request, clock::unix_now());
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Rendering
+// ---------------------------------------------------------------------------
+
+/// Print one `Label: value` line with the values aligned.
+fn field(label: &str, value: &str) {
+    formatter::print_info(&format!("{:<14}{value}", format!("{label}:")));
+}
+
+fn print_local(request: &LocalRequest, validation: Option<&ValidationResult>, now: u64) {
+    field("Request", &request.request_id);
+    field("Role", &format!("{:?}", request.role));
+    let status = match &request.previous_status {
+        Some(previous) => format!("{:?} (was {previous:?})", request.status),
+        None => format!("{:?}", request.status),
+    };
+    field("Status", &status);
+    field("Price", &formatter::format_price(request.price_usdc));
+    field("Deadline", &request.format_deadline(now));
+    if let Some(counterparty) = &request.counterparty {
+        field("Counterparty", counterparty);
+    }
+    if let Some(summary) = &request.task_summary {
+        field("Task", summary);
+    }
+    if !request.tags.is_empty() {
+        field("Tags", &request.tags.join(", "));
+    }
+    field("Request CID", &request.request_cid);
+    if let Some(cid) = &request.response_cid {
+        field("Response CID", cid);
+    }
+    if let Some(hash) = &request.secret_hash {
+        field("Secret hash", hash);
+    }
+    if let Some(secret) = &request.secret {
+        field("Secret", secret);
+    }
+    if let Some(check) = &request.payment_check {
+        field("Payment", &check.describe());
+    }
+    if let Some(result) = validation {
+        let verdict = if result.passed { "passed" } else { "failed" };
+        field(
+            "Validation",
+            &format!("{verdict}, score {}: {}", result.score, result.reason),
+        );
+    }
+
+    if !request.history.is_empty() {
+        formatter::print_info("History:");
+        for entry in &request.history {
+            let mut line = format!(
+                "  {:?} -> {:?}, {} ago",
+                entry.from,
+                entry.to,
+                clock::format_duration(now.saturating_sub(entry.timestamp))
+            );
+            if let Some(note) = &entry.note {
+                line.push_str(&format!(" ({note})"));
+            }
+            formatter::print_info(&line);
+        }
+    }
+}
+
+fn print_onchain(request: &OnchainRequest, now: u64) {
+    formatter::print_info("Not tracked on this machine; showing the network's record.");
+    field("Request", &request.id.to_string());
+    let status = match &request.status {
+        Some(status) => format!("{status:?}"),
+        None => "Unknown".to_string(),
+    };
+    field("Status", &status);
+    field("Price", &formatter::format_price(request.price_usdc));
+    let deadline = match request.deadline.checked_sub(now) {
+        Some(left) => format!("in {}", clock::format_duration(left)),
+        None => format!(
+            "passed {} ago",
+            clock::format_duration(now - request.deadline)
+        ),
+    };
+    field("Deadline", &deadline);
+    field("Buyer", &request.buyer.to_string());
+    field("Request CID", &request.ipfs_cid);
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::requests::{LocalRequestStatus, RequestRole, TransitionEntry};
+    use crate::output::sink;
+
+    fn request() -> LocalRequest {
+        LocalRequest {
+            schema_version: 0,
+            request_id: "42".to_string(),
+            role: RequestRole::Seller,
+            status: LocalRequestStatus::Validated,
+            request_cid: "QmRequest".to_string(),
+            price_usdc: 5_000_000,
+            deadline: 10_000,
+            response_cid: Some("QmResponse".to_string()),
+            secret: Some("ab".repeat(32)),
+            secret_hash: Some("cd".repeat(32)),
+            counterparty: Some("0xbuyer".to_string()),
+            created_at: 1_000,
+            updated_at: 2_000,
+            previous_status: Some(LocalRequestStatus::Responded),
+            tags: vec!["rust".to_string()],
+            task_summary: Some("Review a PR".to_string()),
+            capability: None,
+            trust_policy: None,
+            history: vec![TransitionEntry {
+                from: LocalRequestStatus::Responded,
+                to: LocalRequestStatus::Validated,
+                timestamp: 2_000,
+                note: Some("validation score 85".to_string()),
+            }],
+            payment_check: None,
+            response_attempt: None,
+        }
+    }
+
+    /// Every line printed by `f`, whichever stream the output mode chose.
+    fn printed(f: impl FnOnce()) -> String {
+        let ((), captured) = sink::capture(f);
+        let mut lines = captured.out();
+        lines.extend(captured.err());
+        lines.join("\n")
+    }
+
+    #[test]
+    fn test_print_local_shows_details_and_history() {
+        let validation = ValidationResult {
+            request_id: "42".to_string(),
+            passed: true,
+            score: 85,
+            reason: "meets the brief".to_string(),
+            timestamp: 2_000,
+        };
+        let text = printed(|| print_local(&request(), Some(&validation), 2_600));
+
+        assert!(
+            text.contains("Status:       Validated (was Responded)"),
+            "{text}"
+        );
+        assert!(text.contains("Response CID: QmResponse"), "{text}");
+        assert!(
+            text.contains("Validation:   passed, score 85: meets the brief"),
+            "{text}"
+        );
+        assert!(
+            text.contains("Responded -> Validated, 10m ago (validation score 85)"),
+            "{text}"
+        );
+    }
+
+    #[test]
+    fn test_secret_is_only_printed_when_kept() {
+        let secret = "ab".repeat(32);
+
+        let text = printed(|| print_local(&request().without_secret(), None, 2_600));
+        assert!(!text.contains(&secret), "{text}");
+        assert!(text.contains(&"cd".repeat(32)), "{text}");
+
+        let text = printed(|| print_local(&request(), None, 2_600));
+        assert!(text.contains(&format!("Secret:       {secret}")), "{text}");
+    }
+}
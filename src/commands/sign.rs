@@ -0,0 +1,121 @@
+//! The `sign` and `verify` commands: prove that a message came from this
+//! agent's key, and check such proofs from other agents.
+//!
+//! Signatures use EIP-191 (`personal_sign`), so any Ethereum wallet or
+//! library can check them against the agent's address.
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use tracing::debug;
+
+use super::CommandContext;
+use crate::engine::identity;
+use crate::output::formatter;
+
+/// The result of `sign`, emitted as a flat JSON object.
+#[derive(Debug, Serialize)]
+struct Signed {
+    address: String,
+    signature: String,
+}
+
+/// The result of `verify`, emitted as a flat JSON object.
+#[derive(Debug, Serialize)]
+struct Verified {
+    address: String,
+    valid: bool,
+}
+
+/// Run the `sign` command: sign the `--message` text or the bytes of
+/// `--file` with the agent's key.
+pub async fn sign(message: Option<String>, file: Option<String>) -> Result<()> {
+    debug!(from_file = file.is_some(), "starting sign command");
+    let bytes = read_message(message, file)?;
+
+    let ctx = CommandContext::load_initialized()?;
+    let signed = Signed {
+        signature: ctx.sign_message(&bytes)?,
+        address: ctx.address,
+    };
+
+    if formatter::is_json_mode() {
+        formatter::print_json(&signed)?;
+        return Ok(());
+    }
+    formatter::print_info(&format!("Address:   {}", signed.address));
+    formatter::print_info(&format!("Signature: {}", signed.signature));
+    Ok(())
+}
+
+/// Run the `verify` command: check that `signature` over the `--message`
+/// text or the bytes of `--file` was made by `address`. Fails when it was
+/// not, so scripts can rely on the exit code.
+pub async fn verify(
+    address: String,
+    signature: String,
+    message: Option<String>,
+    file: Option<String>,
+) -> Result<()> {
+    debug!(%address, from_file = file.is_some(), "starting verify command");
+    let bytes = read_message(message, file)?;
+
+    let verified = Verified {
+        valid: identity::verify_message(&address, &bytes, &signature)?,
+        address,
+    };
+
+    if formatter::is_json_mode() {
+        formatter::print_json(&verified)?;
+    } else if verified.valid {
+        formatter::print_success(&format!("The signature was made by {}.", verified.address));
+    }
+    if !verified.valid {
+        bail!(
+            "The signature was not made by {} over this message.",
+            verified.address
+        );
+    }
+    Ok(())
+}
+
+/// The bytes to sign or verify: the `--message` text as UTF-8, or the
+/// contents of `--file`. Clap makes sure exactly one is given.
+fn read_message(message: Option<String>, file: Option<String>) -> Result<Vec<u8>> {
+    match (message, file) {
+        (Some(message), None) => Ok(message.into_bytes()),
+        (None, Some(path)) => {
+            std::fs::read(&path).with_context(|| format!("could not read message file {path}"))
+        }
+        _ => bail!("Provide the message with exactly one of --message and --file."),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_message_takes_text_or_file_bytes() {
+        assert_eq!(
+            read_message(Some("hello".to_string()), None).unwrap(),
+            b"hello"
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("quote.bin");
+        std::fs::write(&path, [0u8, 159, 146, 150]).unwrap();
+        let path = path.to_string_lossy().into_owned();
+        assert_eq!(
+            read_message(None, Some(path)).unwrap(),
+            vec![0u8, 159, 146, 150]
+        );
+
+        assert!(read_message(None, None).is_err());
+        let missing = dir.path().join("missing").to_string_lossy().into_owned();
+        assert!(read_message(None, Some(missing)).is_err());
+    }
+}
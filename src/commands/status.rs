@@ -1,19 +1,29 @@
 use anyhow::{bail, Result};
 use tracing::debug;
 
+use crate::chain::contracts::Network;
+use crate::clock;
 use crate::config;
 use crate::engine::identity::{self, IdentityState};
 use crate::engine::reputation;
-use crate::engine::requests::{LocalRequestStatus, RequestCache};
+use crate::engine::requests::{LocalRequestStatus, RequestCache, RequestSummary};
+use crate::engine::status::StatusReport;
 use crate::output::formatter;
 
+/// Number of upcoming deadlines listed under the active request count.
+const MAX_DEADLINES_SHOWN: usize = 5;
+
 /// Run the `status` command: display agent status, earnings, and reputation.
 ///
 /// Reads the local configuration and request cache to determine the agent's
 /// current identity state (Uninitialized / Local / Registered) and displays
 /// a summary including reputation score, earnings, and active request counts.
-pub async fn run() -> Result<()> {
-    debug!("starting status command");
+/// In JSON mode the summary is one [`StatusReport`] object instead.
+///
+/// With `verbose`, also lists the requests that need attention, each with
+/// the command to run, and the most recently finished requests.
+pub async fn run(verbose: bool) -> Result<()> {
+    debug!(verbose, "starting status command");
 
     // 1. Check initialized
     if !config::store::exists()? {
@@ -23,8 +33,17 @@ pub async fn run() -> Result<()> {
     // 2. Load config
     let cfg = config::store::load()?;
     debug!(agent_name = %cfg.agent.name, "config loaded");
+    let network = Network::for_config(&cfg.network)?;
+
+    // 3. Expire requests whose deadline has passed so the counts below are
+    //    accurate.
+    let now = clock::unix_now();
+    let expired = RequestCache::sweep_expired_with_grace(now, cfg.requests.claim_grace_secs)?;
+    if !expired.is_empty() {
+        debug!(count = expired.len(), "expired stale requests");
+    }
 
-    // 3. Determine identity state
+    // 4. Determine identity state
     let state = identity::get_identity_state(&cfg);
     debug!(?state, "identity state determined");
 
@@ -32,63 +51,128 @@ pub async fn run() -> Result<()> {
         IdentityState::Uninitialized => {
             formatter::print_warning("Agent not initialized. Run `agentmarket init` first.");
         }
+        IdentityState::Local { .. } if formatter::is_json_mode() => {
+            let summary = RequestCache::summary()?;
+            let rep = reputation::compute_reputation("", &[], summary.earned_usdc, 0);
+            formatter::print_status(&StatusReport::new(
+                &cfg.agent.name,
+                network,
+                &state,
+                &rep,
+                &summary,
+            ))?;
+        }
         IdentityState::Local { .. } => {
+            formatter::print_testnet_banner();
             formatter::print_info(&format!("Agent: {}", cfg.agent.name));
             formatter::print_warning(
                 "Not yet registered. Run `agentmarket register` to join the network.",
             );
         }
-        IdentityState::Registered { agent_id, .. } => {
+        IdentityState::Registered { ref agent_id, .. } => {
             // Load local request cache for summary
-            let all_requests = RequestCache::load_all().unwrap_or_default();
-            let active = all_requests
-                .iter()
-                .filter(|r| {
-                    matches!(
-                        r.status,
-                        LocalRequestStatus::Open
-                            | LocalRequestStatus::Responded
-                            | LocalRequestStatus::Validated
-                    )
-                })
-                .count();
-            let completed = all_requests
-                .iter()
-                .filter(|r| r.status == LocalRequestStatus::Claimed)
-                .count();
+            let loaded = RequestCache::load_all_checked().unwrap_or_default();
+            let all_requests = loaded.requests;
+            let summary = RequestSummary::from_requests(&all_requests);
+            let completed = summary.count(&LocalRequestStatus::Claimed);
 
             debug!(
-                total = all_requests.len(),
-                active, completed, "request cache loaded"
+                total = summary.total,
+                active = summary.active(),
+                completed,
+                "request cache loaded"
             );
 
             // Compute reputation (from local records for now).
             // In a full implementation, this would query on-chain event logs.
             let rep = reputation::compute_reputation(
-                &agent_id,
+                agent_id,
                 &[], // No validation records from chain yet
-                0,   // earnings from chain
-                0,   // avg response time
+                summary.earned_usdc,
+                0, // avg response time
             );
 
             // Display status summary
-            formatter::print_status(&cfg.agent.name, &agent_id, 0.0, rep.score);
+            let mut report = StatusReport::new(&cfg.agent.name, network, &state, &rep, &summary);
+            if verbose {
+                report = report.with_activity(&all_requests, now);
+            }
+            formatter::print_testnet_banner();
+            formatter::print_status(&report)?;
+            if formatter::is_json_mode() {
+                warn_quarantined(loaded.quarantined.len());
+                return Ok(());
+            }
 
-            println!();
+            formatter::print_info("");
             formatter::print_info(&format!(
                 "Reputation: {} ({})",
                 reputation::format_reputation(&rep),
                 reputation::reputation_tier(&rep)
             ));
-            formatter::print_info(&format!("Active requests: {}", active));
+            formatter::print_info(&format!("Active requests: {}", summary.active()));
+            let mut upcoming: Vec<_> = all_requests
+                .iter()
+                .filter(|r| {
+                    matches!(
+                        r.status,
+                        LocalRequestStatus::Open | LocalRequestStatus::Responded
+                    )
+                })
+                .collect();
+            upcoming.sort_by_key(|r| r.deadline);
+            for request in upcoming.iter().take(MAX_DEADLINES_SHOWN) {
+                formatter::print_info(&format!(
+                    "  {} ({:?}): {}",
+                    request.request_id,
+                    request.status,
+                    request.format_deadline(now),
+                ));
+            }
+            if upcoming.len() > MAX_DEADLINES_SHOWN {
+                formatter::print_info(&format!(
+                    "  ...and {} more",
+                    upcoming.len() - MAX_DEADLINES_SHOWN
+                ));
+            }
             formatter::print_info(&format!("Completed requests: {}", completed));
+            warn_quarantined(loaded.quarantined.len());
+            if summary.claimable_usdc > 0 {
+                formatter::print_info(&format!(
+                    "Claimable: {}",
+                    formatter::format_price(summary.claimable_usdc)
+                ));
+            }
+            if summary.committed_usdc > 0 {
+                formatter::print_info(&format!(
+                    "Committed to open requests: {}",
+                    formatter::format_price(summary.committed_usdc)
+                ));
+            }
 
             if !cfg.identity.ipfs_profile_cid.is_empty() {
                 formatter::print_info(&format!("Profile: {}", cfg.identity.ipfs_profile_cid));
             }
+
+            if let (Some(actions), Some(recent)) =
+                (&report.pending_actions, &report.recent_requests)
+            {
+                formatter::print_info("");
+                formatter::print_pending_actions(actions, now);
+                formatter::print_info("");
+                formatter::print_recent_requests(recent, now);
+            }
         }
     }
 
     debug!("status command complete");
     Ok(())
 }
+
+fn warn_quarantined(count: usize) {
+    if count > 0 {
+        formatter::print_warning(&format!(
+            "{count} corrupted request file(s) were moved to the quarantine folder."
+        ));
+    }
+}
@@ -0,0 +1,135 @@
+//! The `trust` command: manage standing approvals for repeat sellers.
+//!
+//! See [`crate::engine::trust`] for what a standing approval covers.
+
+use anyhow::{bail, Result};
+use tracing::debug;
+
+use crate::clock;
+use crate::engine::requests::{normalize_tags, parse_usd_amount};
+use crate::engine::trust::{self, PolicyAction, TrustPolicy, TrustStore};
+use crate::output::formatter;
+
+/// `trust add`: create or replace the standing approval for a seller.
+pub async fn add(
+    seller: String,
+    per_request: String,
+    monthly: String,
+    capabilities: Vec<String>,
+    alias: Option<String>,
+) -> Result<()> {
+    debug!(seller = %seller, ?alias, "starting trust add command");
+
+    let mut store = TrustStore::load()?;
+    let seller = store.resolve_seller(&seller)?;
+    let per_request_usdc = parse_usd_amount(&per_request)?;
+    let monthly_usdc = parse_usd_amount(&monthly)?;
+    if per_request_usdc == 0 || monthly_usdc == 0 {
+        bail!("Caps must be greater than zero.");
+    }
+    if per_request_usdc > monthly_usdc {
+        bail!("The per-request cap cannot be larger than the monthly cap.");
+    }
+
+    let existing = store.policy(&seller);
+    let replaced = existing.is_some();
+    let alias = match alias {
+        Some(alias) => {
+            let alias = trust::parse_alias(&alias)?;
+            if let Some(other) = store
+                .policies
+                .iter()
+                .find(|p| p.seller != seller && p.alias.as_ref() == Some(&alias))
+            {
+                bail!(
+                    "@{alias} already names {}. Choose another alias.",
+                    other.seller
+                );
+            }
+            Some(alias)
+        }
+        // Updating a policy keeps its alias.
+        None => existing.and_then(|p| p.alias.clone()),
+    };
+    let label = seller_label(&seller, alias.as_deref());
+
+    store.upsert(TrustPolicy {
+        seller: seller.clone(),
+        alias,
+        per_request_usdc,
+        monthly_usdc,
+        capabilities: normalize_tags(&capabilities),
+        created_at: clock::unix_now(),
+    });
+    store.save()?;
+
+    let verb = if replaced { "updated" } else { "added" };
+    formatter::print_success(&format!(
+        "Standing approval for {label} {verb}: up to {} per request, {} per month.",
+        formatter::format_price(per_request_usdc),
+        formatter::format_price(monthly_usdc),
+    ));
+    formatter::print_info(
+        "Requests to this seller within these caps are confirmed, accepted, and paid \
+         automatically. Everything else works as usual.",
+    );
+    Ok(())
+}
+
+/// `trust list`: show standing approvals and this month's use.
+pub async fn list() -> Result<()> {
+    debug!("starting trust list command");
+
+    let store = TrustStore::load()?;
+    if store.policies.is_empty() {
+        formatter::print_info("No standing approvals. Add one with `agentmarket trust add`.");
+        return Ok(());
+    }
+
+    let now = clock::unix_now();
+    for policy in &store.policies {
+        let scope = if policy.capabilities.is_empty() {
+            "any request".to_string()
+        } else {
+            policy.capabilities.join(", ")
+        };
+        let automatic = store
+            .ledger
+            .iter()
+            .filter(|e| e.seller == policy.seller && e.action != PolicyAction::Confirm)
+            .count();
+        formatter::print_info(&format!(
+            "{:<44} {} per request, {} of {} used this month ({scope}; {automatic} automatic actions)",
+            seller_label(&policy.seller, policy.alias.as_deref()),
+            formatter::format_price(policy.per_request_usdc),
+            formatter::format_price(store.spent_this_month(&policy.seller, now)),
+            formatter::format_price(policy.monthly_usdc),
+        ));
+    }
+    Ok(())
+}
+
+/// `trust remove`: delete a seller's standing approval.
+pub async fn remove(seller: String) -> Result<()> {
+    debug!(seller = %seller, "starting trust remove command");
+
+    let mut store = TrustStore::load()?;
+    let seller = store.resolve_seller(&seller)?;
+    if !store.remove(&seller) {
+        bail!("No standing approval for {seller}. Run `agentmarket trust list` to see them.");
+    }
+    store.save()?;
+
+    formatter::print_success(&format!(
+        "Standing approval for {seller} removed. Future requests to them work as usual."
+    ));
+    Ok(())
+}
+
+/// `@alias (#42)` for a policy with an alias, else just the seller.
+fn seller_label(seller: &str, alias: Option<&str>) -> String {
+    match alias {
+        Some(alias) => format!("@{alias} ({seller})"),
+        None => seller.to_string(),
+    }
+}
@@ -0,0 +1,408 @@
+//! The `tx` command: the transactions this agent has sent.
+//!
+//! `tx list` shows the transaction log (see [`crate::chain::txlog`]). Before
+//! listing, transactions still recorded as pending are looked up once more
+//! on the network, so ones that have since been mined show their outcome.
+//!
+//! `tx bump` replaces a transaction stuck under-priced with one taking the
+//! same nonce at a higher fee (see [`ChainClient::replace_transaction`]),
+//! and says which of the two was mined.
+
+use std::time::Duration;
+
+use alloy::primitives::B256;
+use anyhow::{anyhow, bail, Result};
+use tracing::debug;
+
+use super::CommandContext;
+use crate::chain::client::ChainClient;
+use crate::chain::fees::{self, MIN_FEE_BUMP_PERCENT};
+use crate::chain::receipt::TxOutcome;
+use crate::chain::txlog::{TxLog, TxRecord, TxStatus};
+use crate::chain::types::Replacement;
+use crate::config;
+use crate::engine::template::format_date;
+use crate::output::{formatter, prompt};
+
+/// `tx list`: show sent transactions, oldest first, optionally only those
+/// for `request_id` or still pending.
+pub async fn list(request_id: Option<String>, pending: bool) -> Result<()> {
+    debug!(?request_id, pending, "starting tx list command");
+
+    if !config::store::exists()? {
+        bail!("Agent not initialized. Run `agentmarket init` first.");
+    }
+    let log = TxLog::open()?;
+    let mut records = log.records()?;
+
+    // Settle what can be settled before showing anything as pending.
+    let unresolved = records
+        .iter()
+        .filter(|r| r.status == TxStatus::Pending)
+        .count();
+    if unresolved > 0 {
+        resolve_pending(&records).await;
+        records = log.records()?;
+    }
+
+    let records = select(records, request_id.as_deref(), pending);
+    if formatter::is_json_mode() {
+        return formatter::print_json(&records);
+    }
+    if records.is_empty() {
+        formatter::print_info("No transactions recorded.");
+        return Ok(());
+    }
+    for record in &records {
+        formatter::print_info(&format_row(record)?);
+    }
+    Ok(())
+}
+
+/// `tx bump`: replace the pending transaction `hash` with one bidding at
+/// least `percent` more — the same call, or with `cancel` an empty send to
+/// this agent — wait for one of the two to be mined, and say which.
+pub async fn bump(hash: String, cancel: bool, percent: u64) -> Result<()> {
+    debug!(%hash, cancel, percent, "starting tx bump command");
+
+    if percent < MIN_FEE_BUMP_PERCENT {
+        bail!(
+            "The fee must be raised by at least {MIN_FEE_BUMP_PERCENT}%; \
+             the network ignores smaller bumps."
+        );
+    }
+    let tx_hash: B256 = hash.trim().parse().map_err(|_| {
+        anyhow!("\"{hash}\" is not a transaction hash (0x followed by 64 hex digits).")
+    })?;
+    if let Some(record) = TxLog::open()?.find(tx_hash)? {
+        if record.status != TxStatus::Pending {
+            bail!(
+                "Transaction {tx_hash} is already {}; there is nothing to bump.",
+                describe_status(&record)
+            );
+        }
+    }
+
+    let ctx = CommandContext::load_initialized()?;
+    let signer = ctx.transaction_signer()?;
+    let client = ChainClient::from_config(&ctx.cfg).await?;
+    formatter::print_info(&format!(
+        "{} transaction {tx_hash}...",
+        if cancel { "Cancelling" } else { "Speeding up" }
+    ));
+    let replacement = client
+        .replace_transaction(
+            &signer,
+            tx_hash,
+            percent,
+            cancel,
+            ctx.cfg.network.confirmations,
+            Duration::from_secs(ctx.cfg.network.receipt_timeout_secs),
+            |quote| {
+                prompt::confirm_funds(&format!(
+                    "The replacement may cost up to {} per gas, above your cap of {}",
+                    fees::display_gwei(quote.max_fee_per_gas_wei),
+                    fees::display_gwei(quote.cap_wei.unwrap_or_default()),
+                ))
+            },
+        )
+        .await?;
+
+    if formatter::is_json_mode() {
+        return formatter::print_json(&replacement);
+    }
+    match describe_replacement(&replacement, ctx.cfg.network.receipt_timeout_secs) {
+        (true, message) => formatter::print_success(&message),
+        (false, message) => formatter::print_warning(&message),
+    }
+    Ok(())
+}
+
+/// Which transaction `replacement` ended with, and whether that is what
+/// was asked for: the call went through, for a speed-up, or nothing ran,
+/// for a cancel.
+fn describe_replacement(replacement: &Replacement, timeout_secs: u64) -> (bool, String) {
+    let Replacement {
+        original,
+        replacement: sent,
+        ..
+    } = replacement;
+    let Some(landed) = replacement.landed else {
+        return (
+            false,
+            format!(
+                "Replacement {sent} was sent with nonce {}, but neither it nor {original} \
+                 was mined within {timeout_secs}s. Check on both with `agentmarket tx list`.",
+                replacement.nonce
+            ),
+        );
+    };
+    let (winner, loser) = if landed == *sent {
+        ("Replacement", *original)
+    } else {
+        ("The original transaction", *sent)
+    };
+    match &replacement.outcome {
+        TxOutcome::Success { block_number, .. } if landed == *sent => (
+            true,
+            format!("{winner} {landed} was mined in block {block_number}; {loser} will never run."),
+        ),
+        TxOutcome::Success { block_number, .. } => (
+            !replacement.cancel,
+            format!(
+                "{winner} {landed} was mined in block {block_number} before its replacement; \
+                 {loser} will never run."
+            ),
+        ),
+        TxOutcome::Reverted {
+            block_number,
+            reason,
+        } => {
+            let reason = reason
+                .as_deref()
+                .map_or_else(String::new, |r| format!(": {r}"));
+            (
+                false,
+                format!(
+                    "{winner} {landed} was mined in block {block_number} but rejected by the \
+                     contract{reason}; {loser} will never run."
+                ),
+            )
+        }
+        TxOutcome::TimedOut => (false, format!("{winner} {landed} was not confirmed.")),
+    }
+}
+
+/// Look once for the receipt of each pending transaction in `records`. The
+/// client records any outcome it finds in the log. Failing to reach the
+/// network leaves them pending, with a warning.
+async fn resolve_pending(records: &[TxRecord]) {
+    let cfg = match config::store::load() {
+        Ok(cfg) => cfg,
+        Err(err) => {
+            debug!(error = %format!("{err:#}"), "no config; pending transactions left as they are");
+            return;
+        }
+    };
+    let client = match ChainClient::from_config(&cfg).await {
+        Ok(client) => client,
+        Err(err) => {
+            debug!(error = %format!("{err:#}"), "could not connect");
+            formatter::print_warning(
+                "Could not reach the network to check pending transactions; \
+                 they are shown as last recorded.",
+            );
+            return;
+        }
+    };
+    for record in records.iter().filter(|r| r.status == TxStatus::Pending) {
+        if let Err(err) = client
+            .wait_for_receipt(record.hash, cfg.network.confirmations, Duration::ZERO)
+            .await
+        {
+            debug!(hash = %record.hash, error = %format!("{err:#}"), "receipt check failed");
+        }
+    }
+}
+
+/// The records for `request_id`, if given, and only pending ones if
+/// `pending`.
+fn select(records: Vec<TxRecord>, request_id: Option<&str>, pending: bool) -> Vec<TxRecord> {
+    records
+        .into_iter()
+        .filter(|r| request_id.map_or(true, |id| r.request_id.as_deref() == Some(id)))
+        .filter(|r| !pending || r.status == TxStatus::Pending)
+        .collect()
+}
+
+/// `"confirmed in block 10"`, `"reverted in block 12: Not the seller"`,
+/// `"replaced by 0x…"`, or `"pending"`.
+fn describe_status(record: &TxRecord) -> String {
+    let block = record
+        .block_number
+        .map(|block| format!(" in block {block}"))
+        .unwrap_or_default();
+    match record.status {
+        TxStatus::Pending => "pending".to_string(),
+        TxStatus::Confirmed => format!("confirmed{block}"),
+        TxStatus::Reverted => match &record.reason {
+            Some(reason) => format!("reverted{block}: {reason}"),
+            None => format!("reverted{block}"),
+        },
+        TxStatus::Replaced => match record.replaced_by {
+            Some(landed) => format!("replaced by {landed}"),
+            None => "replaced".to_string(),
+        },
+    }
+}
+
+/// One line per transaction: when, what, which request, the hash, and how
+/// it ended.
+fn format_row(record: &TxRecord) -> Result<String> {
+    Ok(format!(
+        "{}  {:<8}  {:<16}  {}  {}",
+        format_date(record.sent_at, "%Y-%m-%d %H:%M")?,
+        record.kind,
+        record.request_id.as_deref().unwrap_or("-"),
+        record.hash,
+        describe_status(record)
+    ))
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::receipt::TxOutcome;
+    use crate::chain::txlog::TxKind;
+    use alloy::primitives::{Address, B256};
+
+    /// 2024-03-05 14:07:09 UTC.
+    const SENT_AT: u64 = 1_709_647_629;
+
+    fn record(byte: u8, kind: TxKind, request_id: Option<&str>) -> TxRecord {
+        TxRecord::pending(
+            B256::repeat_byte(byte),
+            kind,
+            request_id,
+            Address::repeat_byte(0x11),
+            0,
+            SENT_AT,
+        )
+    }
+
+    fn records() -> Vec<TxRecord> {
+        let approve = record(1, TxKind::Approve, None)
+            .settled(
+                &TxOutcome::Success {
+                    block_number: 10,
+                    gas_used: 46_000,
+                },
+                SENT_AT,
+            )
+            .unwrap();
+        vec![
+            approve,
+            record(2, TxKind::Create, Some("local-1709647629")),
+            record(3, TxKind::Claim, Some("7")),
+        ]
+    }
+
+    #[test]
+    fn test_select_filters_by_request_and_pending() {
+        let hashes =
+            |records: Vec<TxRecord>| -> Vec<u8> { records.iter().map(|r| r.hash.0[0]).collect() };
+        assert_eq!(hashes(select(records(), None, false)), [1, 2, 3]);
+        assert_eq!(hashes(select(records(), Some("7"), false)), [3]);
+        assert_eq!(hashes(select(records(), None, true)), [2, 3]);
+        assert_eq!(
+            hashes(select(records(), Some("local-1709647629"), true)),
+            [2]
+        );
+        assert!(select(records(), Some("8"), false).is_empty());
+    }
+
+    #[test]
+    fn test_describe_status() {
+        let all = records();
+        assert_eq!(describe_status(&all[0]), "confirmed in block 10");
+        assert_eq!(describe_status(&all[1]), "pending");
+
+        let reverted = all[2]
+            .settled(
+                &TxOutcome::Reverted {
+                    block_number: 12,
+                    reason: Some("Not the seller".to_string()),
+                },
+                SENT_AT,
+            )
+            .unwrap();
+        assert_eq!(
+            describe_status(&reverted),
+            "reverted in block 12: Not the seller"
+        );
+    }
+
+    #[test]
+    fn test_format_row() {
+        let row = format_row(&records()[2]).unwrap();
+        assert!(row.starts_with("2024-03-05 14:07  claim     7   "), "{row}");
+        assert!(row.contains(&B256::repeat_byte(3).to_string()), "{row}");
+        assert!(row.ends_with("pending"), "{row}");
+
+        let row = format_row(&records()[0]).unwrap();
+        assert!(row.contains("approve   -  "), "{row}");
+    }
+
+    fn replacement(landed: Option<u8>, outcome: TxOutcome, cancel: bool) -> Replacement {
+        Replacement {
+            original: B256::repeat_byte(1),
+            replacement: B256::repeat_byte(2),
+            nonce: 4,
+            cancel,
+            max_fee_per_gas_wei: 1_100_000_000,
+            landed: landed.map(B256::repeat_byte),
+            outcome,
+        }
+    }
+
+    #[test]
+    fn test_describe_replacement_names_what_landed() {
+        let mined = TxOutcome::Success {
+            block_number: 11,
+            gas_used: 21_000,
+        };
+        let (original, sent) = (B256::repeat_byte(1), B256::repeat_byte(2));
+
+        let (ok, message) = describe_replacement(&replacement(Some(2), mined.clone(), true), 120);
+        assert!(ok);
+        assert_eq!(
+            message,
+            format!("Replacement {sent} was mined in block 11; {original} will never run.")
+        );
+
+        // The original beating its replacement is fine for a speed-up, not
+        // for a cancel.
+        let (ok, message) = describe_replacement(&replacement(Some(1), mined.clone(), false), 120);
+        assert!(ok);
+        assert!(
+            message.starts_with(&format!("The original transaction {original} was mined")),
+            "{message}"
+        );
+        assert!(
+            message.ends_with(&format!("{sent} will never run.")),
+            "{message}"
+        );
+        assert!(!describe_replacement(&replacement(Some(1), mined, true), 120).0);
+
+        let reverted = TxOutcome::Reverted {
+            block_number: 11,
+            reason: Some("Not the seller".to_string()),
+        };
+        let (ok, message) = describe_replacement(&replacement(Some(2), reverted, false), 120);
+        assert!(!ok);
+        assert!(
+            message.contains("rejected by the contract: Not the seller"),
+            "{message}"
+        );
+
+        let (ok, message) =
+            describe_replacement(&replacement(None, TxOutcome::TimedOut, false), 120);
+        assert!(!ok);
+        assert!(message.contains("nonce 4"), "{message}");
+        assert!(message.contains("within 120s"), "{message}");
+    }
+
+    #[test]
+    fn test_describe_status_names_the_replacement() {
+        let stuck = record(4, TxKind::Claim, Some("7"));
+        let replaced = stuck.replaced(B256::repeat_byte(5), SENT_AT);
+        assert_eq!(
+            describe_status(&replaced),
+            format!("replaced by {}", B256::repeat_byte(5))
+        );
+    }
+}
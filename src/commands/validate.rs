@@ -6,38 +6,98 @@
 //! verdict on stdout.
 //!
 //! When the Request Registry contract is deployed, validation results are
-//! submitted on-chain via `submitValidation`. Until then, results are saved
-//! locally and a "coming soon" message is displayed.
+//! submitted on-chain via the Validation Registry's `submitValidation`, and
+//! a passing request only becomes Validated locally once that transaction is
+//! confirmed. A request that already has a verdict on-chain is not submitted
+//! again. Until the contracts are deployed, results are saved locally and a
+//! "coming soon" message is displayed.
+//!
+//! `--filter` limits the loop to requests whose declared capability matches
+//! a pattern such as `code-*`; requests that declare none are included unless
+//! `--strict-filter` is set.
+//!
+//! `--list` prints the responses waiting for review (after the expiry
+//! sweep) and exits without running a handler. It finds them the same way
+//! the loop does, so the list is what `--auto` would work through.
+//!
+//! `--request-id` validates one known request and exits: 0 when it passes,
+//! [`EXIT_VALIDATION_FAILED`] when it fails, and the error's exit code
+//! (see [`formatter::error_exit_code`]) on any error.
 
+use std::fmt;
 use std::time::Duration;
 
-use alloy::primitives::Address;
-use anyhow::{bail, Result};
+use std::sync::Arc;
+
+use alloy::primitives::{Address, U256};
+use anyhow::{bail, Context, Result};
 use tracing::debug;
 
-use crate::chain::contracts::addresses;
-use crate::config::{keystore, store};
+use crate::chain::client::ChainClient;
+use crate::chain::contracts::{self, Contract, ContractAddresses};
+use crate::chain::fees::{self, FeeAboveCap};
+use crate::chain::signer::TransactionSigner;
+use crate::chain::types::ValidationSubmission;
+use crate::clock;
+use crate::config::store::{Config, NetworkConfig, ValidationConfig};
+use crate::config::{keystore, lock, store};
+use crate::engine::archive;
+use crate::engine::economics::{self, ContractSnapshot};
 use crate::engine::handlers::{self, HandlerType};
 use crate::engine::identity::{self, IdentityState};
 use crate::engine::manual_handler;
-use crate::engine::requests::{format_price_usd, LocalRequestStatus, RequestCache};
-use crate::engine::validation::{self, HandlerInput};
-use crate::output::formatter;
+use crate::engine::requests::{self, LocalRequest, LocalRequestStatus, RequestCache, RequestRole};
+use crate::engine::trust::{self, Coverage, PolicyAction, TrustStore};
+use crate::engine::validation::{
+    self, HandlerInput, HandlerOutput, PendingValidation, ValidationResult,
+};
+use crate::output::{formatter, prompt};
 
 /// Polling interval for auto-mode (seconds between checks for pending validations).
 const POLL_INTERVAL_SECS: u64 = 30;
 
+/// Process exit code of `validate --request-id` when the response fails
+/// validation.
+pub const EXIT_VALIDATION_FAILED: i32 = formatter::EXIT_VALIDATION_FAILED;
+
+/// Error returned by `validate --request-id` when the response fails
+/// validation. The verdict has already been shown; `main` exits with
+/// [`EXIT_VALIDATION_FAILED`] instead of reporting an error.
+#[derive(Debug)]
+pub struct ValidationFailed {
+    pub request_id: String,
+}
+
+impl fmt::Display for ValidationFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Request {} did not pass validation.", self.request_id)
+    }
+}
+
+impl std::error::Error for ValidationFailed {}
+
+#[allow(clippy::too_many_arguments)]
 pub async fn run(
     handler_type: String,
     handler_path: Option<String>,
     auto_mode: bool,
     filter: Option<String>,
+    strict_filter: bool,
+    accept_unprofitable: bool,
+    request_id: Option<String>,
+    revalidate: bool,
+    list: bool,
 ) -> Result<()> {
     debug!(
         handler_type = %handler_type,
         handler_path = ?handler_path,
         auto_mode = auto_mode,
         filter = ?filter,
+        strict_filter,
+        accept_unprofitable,
+        request_id = ?request_id,
+        revalidate,
+        list,
         "starting validate command"
     );
 
@@ -62,24 +122,77 @@ pub async fn run(
         }
     }
 
+    let contracts = contracts::for_config(&cfg)?;
+    let query = PendingQuery {
+        filter: filter.as_deref(),
+        strict_filter,
+        revalidate,
+        claim_grace_secs: cfg.requests.claim_grace_secs,
+    };
+
+    // Preview the queue without unlocking the key or running a handler.
+    if list {
+        return list_pending(&query, &cfg.validation);
+    }
+
     // 3. Load keystore and derive address.
     let passphrase = keystore::get_passphrase()?;
-    let key_bytes = keystore::load_key(&passphrase)?;
+    let mut key_bytes = keystore::load_key(&passphrase)?;
     let (_public_key, address) = identity::address_from_key(&key_bytes)?;
+    let signer = TransactionSigner::from_bytes(&mut key_bytes)?;
 
     debug!(address = %address, "agent address derived");
 
+    // Verdicts go on-chain only alongside a deployed Request Registry.
+    let submitter = if contracts.request_registry.is_zero() {
+        None
+    } else {
+        let submitter = Submitter::connect(&cfg, &contracts, signer).await?;
+        if submitter.is_none() {
+            formatter::print_warning(&format!(
+                "No Validation Registry is configured for {}; verdicts will be saved locally only.",
+                contracts.network
+            ));
+            formatter::print_info(&Contract::ValidationRegistry.setup_hint());
+        }
+        submitter
+    };
+
     // 4. Resolve handler type from CLI args.
     let resolved_handler = HandlerType::from_str(&handler_type, handler_path.as_deref())?;
 
     debug!(handler = ?resolved_handler, "handler type resolved");
 
-    // 5. Contract deployment gate: check if REQUEST_REGISTRY is deployed.
-    if addresses::REQUEST_REGISTRY == Address::ZERO {
+    // One named request: validate it and exit with its verdict.
+    if let Some(request_id) = request_id {
+        if !contracts.request_registry.is_zero() {
+            refresh_fee_snapshot(&cfg).await;
+        }
+        let result = validate_one(
+            &request_id,
+            &resolved_handler,
+            &address,
+            submitter.as_ref(),
+            &cfg.validation,
+            accept_unprofitable,
+        )
+        .await?;
+        if !result.passed {
+            return Err(ValidationFailed { request_id }.into());
+        }
+        return Ok(());
+    }
+
+    // 5. Contract deployment gate: check if the Request Registry is deployed.
+    if contracts.request_registry.is_zero() {
         formatter::print_info("Validation");
         formatter::print_info("");
-        formatter::print_info("The network validation service is not yet available.");
+        formatter::print_info(&format!(
+            "The network validation service is not yet available on {}.",
+            contracts.network
+        ));
         formatter::print_info("Validation will become available soon.");
+        formatter::print_info(&Contract::RequestRegistry.setup_hint());
         formatter::print_info("");
         formatter::print_info("In the meantime, you can:");
         formatter::print_info("  - Run `agentmarket search --requests` to discover open requests");
@@ -92,10 +205,22 @@ pub async fn run(
         // Even though contracts are not deployed, process any local
         // "Responded" requests that the user might want to validate
         // locally for testing/dry-run purposes.
-        let responded = RequestCache::load_by_status(LocalRequestStatus::Responded)?;
+        // Requests validated in an earlier session, and those outside the
+        // capability filter, are left out.
+        let Pending {
+            requests: responded,
+            filtered_out,
+            ..
+        } = find_pending(&query)?;
 
         if responded.is_empty() {
-            formatter::print_info("No pending validations found locally.");
+            match filter.as_deref() {
+                Some(pattern) if filtered_out > 0 => formatter::print_info(&format!(
+                    "No pending validations match capability {pattern} \
+                     ({filtered_out} found locally for other capabilities)."
+                )),
+                _ => formatter::print_info("No pending validations found locally."),
+            }
             return Ok(());
         }
 
@@ -108,7 +233,7 @@ pub async fn run(
             formatter::print_info(&format!(
                 "  Request {}: {}",
                 req.request_id,
-                format_price_usd(req.price_usdc),
+                formatter::format_price(req.price_usdc),
             ));
         }
 
@@ -118,20 +243,9 @@ pub async fn run(
         );
 
         // Process the first pending validation as a dry run.
-        let target = if let Some(ref cap_filter) = filter {
-            // Filter is a placeholder -- in a full implementation it would
-            // match against request metadata / capabilities. For now, try to
-            // match against the request_id as a simple filter.
-            responded
-                .iter()
-                .find(|r| r.request_id.contains(cap_filter))
-                .or(responded.first())
-        } else {
-            responded.first()
-        };
-
-        if let Some(req) = target {
-            process_validation(req, &resolved_handler, &address)?;
+        if let Some(req) = responded.first() {
+            ensure_worthwhile(req, &cfg.validation, accept_unprofitable)?;
+            process_validation(req, &resolved_handler, &address, None).await?;
         }
 
         return Ok(());
@@ -141,6 +255,8 @@ pub async fn run(
     // Contract is deployed -- full on-chain validation flow.
     // -----------------------------------------------------------------------
 
+    refresh_fee_snapshot(&cfg).await;
+
     if auto_mode {
         formatter::print_info("Entering validation loop (auto mode). Press Ctrl+C to stop.");
         formatter::print_info(&format!(
@@ -149,17 +265,32 @@ pub async fn run(
         ));
 
         if let Some(ref cap_filter) = filter {
-            formatter::print_info(&format!("Filtering by capability: {cap_filter}"));
+            formatter::print_info(&format!(
+                "Filtering by capability: {cap_filter}{}",
+                if strict_filter {
+                    " (requests without one are skipped)"
+                } else {
+                    ""
+                }
+            ));
         }
 
         loop {
-            match poll_and_validate(&resolved_handler, &address, filter.as_deref()) {
-                Ok(found) => {
-                    if found {
-                        debug!("processed a validation in auto mode");
-                    } else {
-                        debug!("no pending validations found, sleeping");
-                    }
+            match poll_and_validate(
+                &resolved_handler,
+                &address,
+                submitter.as_ref(),
+                &query,
+                &cfg.validation,
+                accept_unprofitable,
+            )
+            .await
+            {
+                Ok(summary) if summary.pending > 0 => {
+                    formatter::print_info(&summary.describe());
+                }
+                Ok(_) => {
+                    debug!("no pending validations found, sleeping");
                 }
                 Err(err) => {
                     debug!(error = %err, "error during validation poll");
@@ -171,13 +302,24 @@ pub async fn run(
         }
     } else {
         // Single-shot mode: check for one pending validation and process it.
-        match poll_and_validate(&resolved_handler, &address, filter.as_deref())? {
-            true => {
-                formatter::print_success("Validation complete.");
-            }
-            false => {
-                formatter::print_info("No pending validations found.");
-            }
+        let summary = poll_and_validate(
+            &resolved_handler,
+            &address,
+            submitter.as_ref(),
+            &query,
+            &cfg.validation,
+            accept_unprofitable,
+        )
+        .await?;
+        if summary.processed > 0 {
+            formatter::print_success("Validation complete.");
+        } else if summary.skipped > 0 {
+            formatter::print_info(&format!(
+                "No new validations: {}. Pass --revalidate to review them again.",
+                summary.describe()
+            ));
+        } else {
+            formatter::print_info("No pending validations found.");
         }
     }
 
@@ -185,47 +327,364 @@ pub async fn run(
     Ok(())
 }
 
+/// What one pass over the pending validations did.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct PollSummary {
+    /// Responses waiting for validation.
+    pending: usize,
+    /// Already validated in an earlier session.
+    skipped: usize,
+    /// Not worth running at the configured margin.
+    below_margin: usize,
+    processed: usize,
+}
+
+impl PollSummary {
+    /// e.g. "3 pending, 2 skipped (already validated), 1 processed".
+    fn describe(&self) -> String {
+        let mut text = format!(
+            "{} pending, {} skipped (already validated), ",
+            self.pending, self.skipped
+        );
+        if self.below_margin > 0 {
+            text.push_str(&format!("{} below margin, ", self.below_margin));
+        }
+        text.push_str(&format!("{} processed", self.processed));
+        text
+    }
+}
+
+/// Which responses the loop considers.
+struct PendingQuery<'a> {
+    /// `--filter` capability pattern.
+    filter: Option<&'a str>,
+    /// With `filter`, also leave out requests that declare no capability.
+    strict_filter: bool,
+    /// Include requests that already have a saved result.
+    revalidate: bool,
+    /// How long validated requests stay claimable past their deadline.
+    claim_grace_secs: u64,
+}
+
+/// Responses waiting for this agent's review, from [`find_pending`].
+#[derive(Debug, Default)]
+struct Pending {
+    /// Waiting for review, in cache order.
+    requests: Vec<LocalRequest>,
+    /// Already validated in an earlier session.
+    skipped: usize,
+    /// Outside the capability filter.
+    filtered_out: usize,
+}
+
+/// Find the responses waiting for this agent's review. Overdue requests
+/// are expired first so they are never presented as work; sellers' own
+/// responses are left out, since sellers cannot validate their own work.
+///
+/// Both the loop and `--list` go through here, so the list always shows
+/// what the loop would process.
+fn find_pending(query: &PendingQuery) -> Result<Pending> {
+    let expired =
+        RequestCache::sweep_expired_with_grace(clock::unix_now(), query.claim_grace_secs)?;
+    if !expired.is_empty() {
+        debug!(
+            count = expired.len(),
+            "expired overdue requests before validating"
+        );
+    }
+
+    let mut pending = Pending::default();
+    for req in RequestCache::load_by_status(LocalRequestStatus::Responded)? {
+        if req.role == RequestRole::Seller {
+            continue;
+        }
+        if !passes_filter(&req, query.filter, query.strict_filter) {
+            pending.filtered_out += 1;
+            continue;
+        }
+        if !validation::needs_validation(&req.request_id, query.revalidate)? {
+            debug!(request_id = %req.request_id, "already validated, skipping");
+            pending.skipped += 1;
+            continue;
+        }
+        pending.requests.push(req);
+    }
+    Ok(pending)
+}
+
+/// Print the responses waiting for review (`--list`), each flagged when its
+/// fee falls below the configured margin. In JSON mode, prints them as an
+/// array.
+fn list_pending(query: &PendingQuery, validation_cfg: &ValidationConfig) -> Result<()> {
+    let pending = find_pending(query)?;
+    let listing: Vec<PendingValidation> = pending
+        .requests
+        .iter()
+        .map(|req| {
+            let estimate = economics::estimate_job(req.price_usdc, validation_cfg);
+            PendingValidation::new(req, !estimate.meets_margin())
+        })
+        .collect();
+    debug!(count = listing.len(), "pending validations listed");
+
+    if formatter::is_json_mode() {
+        return formatter::print_json(&listing);
+    }
+
+    formatter::print_pending_validations(&listing, clock::unix_now());
+    let below_margin = listing.iter().filter(|p| p.below_margin).count();
+    if below_margin > 0 {
+        formatter::print_info(&format!(
+            "{below_margin} below your configured margin: the loop skips them \
+             unless --accept-unprofitable is set."
+        ));
+    }
+    if pending.skipped > 0 {
+        formatter::print_info(&format!(
+            "{} already validated. Pass --revalidate to include them.",
+            pending.skipped
+        ));
+    }
+    if let Some(pattern) = query.filter.filter(|_| pending.filtered_out > 0) {
+        formatter::print_info(&format!(
+            "{} more waiting for capabilities other than {pattern}.",
+            pending.filtered_out
+        ));
+    }
+    Ok(())
+}
+
+/// Whether `req` passes the `--filter` capability pattern. Requests that
+/// declare no capability pass unless `strict` is set.
+fn passes_filter(req: &LocalRequest, filter: Option<&str>, strict: bool) -> bool {
+    filter.map_or(true, |pattern| {
+        requests::matches_capability(pattern, req.capability.as_deref(), strict)
+    })
+}
+
 /// Poll for pending validations and process one if found.
 ///
-/// Returns `true` if a validation was processed, `false` if none were found.
-fn poll_and_validate(handler: &HandlerType, address: &str, _filter: Option<&str>) -> Result<bool> {
+/// Only requests passing the capability filter in `query` are considered.
+/// Requests with a saved result are skipped unless it sets `revalidate`, so
+/// a restarted loop does not present them again. Jobs whose expected fee
+/// does not cover the configured cost and margin are skipped (with the math
+/// shown) unless `accept_unprofitable` is set.
+async fn poll_and_validate(
+    handler: &HandlerType,
+    address: &str,
+    submitter: Option<&Submitter>,
+    query: &PendingQuery<'_>,
+    validation_cfg: &ValidationConfig,
+    accept_unprofitable: bool,
+) -> Result<PollSummary> {
     debug!("polling for pending validations");
 
     // TODO: When the contract is live, query on-chain for requests in
     // Responded status that need validation. For now, check local cache.
-    let responded = RequestCache::load_by_status(LocalRequestStatus::Responded)?;
+    let pending = find_pending(query)?;
+    let mut summary = PollSummary {
+        pending: pending.requests.len() + pending.skipped,
+        skipped: pending.skipped,
+        ..PollSummary::default()
+    };
 
-    if responded.is_empty() {
-        return Ok(false);
+    // Process the first pending validation that is worth running.
+    for req in &pending.requests {
+        if summary.processed > 0 {
+            continue;
+        }
+
+        let estimate = economics::estimate_job(req.price_usdc, validation_cfg);
+        if !estimate.meets_margin() && !accept_unprofitable {
+            formatter::print_info(&format!(
+                "Request {}: {}",
+                req.request_id,
+                estimate.summary()
+            ));
+            summary.below_margin += 1;
+            continue;
+        }
+
+        process_validation(req, handler, address, submitter).await?;
+        summary.processed += 1;
     }
 
-    // Process the first pending validation.
-    if let Some(req) = responded.first() {
-        process_validation(req, handler, address)?;
-        return Ok(true);
+    debug!(?summary, "validation poll complete");
+    Ok(summary)
+}
+
+/// Validate the single request `request_id`: check it is waiting for this
+/// agent's review, then run it through the same path as the loop.
+async fn validate_one(
+    request_id: &str,
+    handler: &HandlerType,
+    address: &str,
+    submitter: Option<&Submitter>,
+    validation_cfg: &ValidationConfig,
+    accept_unprofitable: bool,
+) -> Result<ValidationResult> {
+    if !RequestCache::contains(request_id)? {
+        bail!(
+            "Request {request_id} not found in local cache. \
+             Run `agentmarket list --status responded` to see requests awaiting validation."
+        );
     }
+    let req = RequestCache::load(request_id)?;
+
+    debug!(
+        request_id = %req.request_id,
+        status = ?req.status,
+        role = ?req.role,
+        "request loaded from cache"
+    );
 
-    Ok(false)
+    check_validatable(&req)?;
+    ensure_worthwhile(&req, validation_cfg, accept_unprofitable)?;
+    process_validation(&req, handler, address, submitter).await
+}
+
+/// Check that `req` has a response waiting for this agent's review. Sellers
+/// never validate their own work.
+fn check_validatable(req: &LocalRequest) -> Result<()> {
+    let request_id = &req.request_id;
+
+    if req.role == RequestRole::Seller {
+        bail!(
+            "Request {request_id} is not assigned to you for validation: \
+             you are its seller, and sellers cannot validate their own work."
+        );
+    }
+
+    match req.status {
+        LocalRequestStatus::Responded => Ok(()),
+        LocalRequestStatus::Open => bail!(
+            "Request {request_id} has no response yet. \
+             It can be validated once the seller responds."
+        ),
+        LocalRequestStatus::Validated | LocalRequestStatus::Claimed => {
+            bail!("Request {request_id} has already been validated.")
+        }
+        LocalRequestStatus::Cancelled => {
+            bail!("Request {request_id} was cancelled; there is nothing to validate.")
+        }
+        LocalRequestStatus::Expired => {
+            bail!("Request {request_id} has expired; there is nothing to validate.")
+        }
+    }
+}
+
+/// Show the fee-versus-cost estimate for a job and refuse to run it when it
+/// falls below the configured margin, unless `accept_unprofitable` is set.
+fn ensure_worthwhile(
+    req: &LocalRequest,
+    validation_cfg: &ValidationConfig,
+    accept_unprofitable: bool,
+) -> Result<()> {
+    let estimate = economics::estimate_job(req.price_usdc, validation_cfg);
+    debug!(
+        request_id = %req.request_id,
+        fee_usdc = estimate.fee_usdc,
+        cost_usdc = estimate.cost_usdc,
+        "validation job estimate"
+    );
+
+    if estimate.meets_margin() {
+        formatter::print_info(&format!("Estimate: {}", estimate.math()));
+        return Ok(());
+    }
+
+    if !accept_unprofitable {
+        bail!(
+            "Validating request {} is below your configured margin ({}). \
+             Re-run with --accept-unprofitable to validate it anyway.",
+            req.request_id,
+            estimate.math()
+        );
+    }
+
+    formatter::print_warning(&format!(
+        "Validating below your configured margin ({}).",
+        estimate.math()
+    ));
+    Ok(())
+}
+
+/// Refresh the cached validator fee rate from the Request Registry.
+///
+/// Failures are logged and ignored; estimates fall back to the previous
+/// snapshot or the default rate.
+pub(crate) async fn refresh_fee_snapshot(cfg: &Config) {
+    if let Err(err) = try_refresh_fee_snapshot(cfg).await {
+        debug!(error = %format!("{err:#}"), "could not refresh validator fee rate");
+    }
+}
+
+/// Like [`refresh_fee_snapshot`], but reports a chain failure to the caller
+/// (the daemon uses it to back off from an unreachable RPC endpoint).
+pub(crate) async fn try_refresh_fee_snapshot(cfg: &Config) -> Result<()> {
+    let client = ChainClient::from_config(cfg).await?;
+    let bps = client
+        .get_validator_fee_bps(client.contracts().request_registry)
+        .await?;
+    if let Err(err) = economics::save_snapshot(&ContractSnapshot::now(bps)) {
+        debug!(error = %err, "could not cache validator fee rate");
+    }
+    Ok(())
 }
 
 /// Process a single validation: retrieve deliverable, run handler, save result.
-fn process_validation(
-    req: &crate::engine::requests::LocalRequest,
+///
+/// Shared by the loop and `--request-id`; returns the saved result. With a
+/// `submitter`, the verdict is also recorded on-chain.
+async fn process_validation(
+    req: &LocalRequest,
     handler: &HandlerType,
     _address: &str,
-) -> Result<()> {
+    submitter: Option<&Submitter>,
+) -> Result<ValidationResult> {
     debug!(request_id = %req.request_id, "processing validation");
 
+    let _request_lock = lock::acquire(&lock::request_lock(&req.request_id), "validate")?;
+
     formatter::print_info(&format!(
         "Validating request {} ({})",
         req.request_id,
-        format_price_usd(req.price_usdc),
+        formatter::format_price(req.price_usdc),
     ));
 
     // a. Build HandlerInput.
-    //    In a full implementation, the deliverable would be retrieved from IPFS
-    //    and decrypted. For now, use a placeholder since we don't have the
-    //    encrypted content available locally without IPFS retrieval.
+    let handler_input = handler_input(req);
+
+    if let (HandlerType::Manual, Some(archive)) =
+        (handler, archive::locate(&handler_input.deliverable))
+    {
+        super::offer_extract("the deliverable archive", archive, &req.request_id)?;
+    }
+
+    // b. Run the handler (manual prompt or external process). A response to
+    //    our own policy-confirmed request from the trusted seller is still
+    //    reviewed like any other.
+    let policy_seller = standing_approval(req, submitter).await;
+    if let Some(seller) = &policy_seller {
+        trust::record_action(
+            &req.request_id,
+            seller,
+            PolicyAction::Accept,
+            req.price_usdc,
+        )?;
+    }
+    let handler_output = run_handler(handler, &handler_input)?;
+
+    record_verdict(req, &handler_output, policy_seller.as_deref(), submitter).await
+}
+
+/// The input handed to the handler for `req`. The task description is the
+/// `task_summary` recorded when the request was created.
+///
+/// In a full implementation, the deliverable would be retrieved from IPFS
+/// and decrypted. For now, use a placeholder since we don't have the
+/// encrypted content available locally without IPFS retrieval.
+pub(crate) fn handler_input(req: &LocalRequest) -> HandlerInput {
     let deliverable = if let Some(ref cid) = req.response_cid {
         // TODO: Retrieve from IPFS and decrypt:
         //   let ipfs_client = IpfsClient::from_config(&cfg);
@@ -237,34 +696,59 @@ fn process_validation(
         b"[No deliverable attached]".to_vec()
     };
 
-    let handler_input = HandlerInput {
+    HandlerInput {
         request_id: req.request_id.clone(),
-        task_description: format!("Request {}", req.request_id),
+        task_description: req
+            .task_summary
+            .clone()
+            .unwrap_or_else(|| format!("Request {}", req.request_id)),
         deliverable,
         seller: req.counterparty.clone().unwrap_or_default(),
         price_usdc: req.price_usdc,
         deadline: req.deadline,
-    };
+    }
+}
 
-    // b. Run the handler (manual prompt or external process).
-    let handler_output = match handler {
-        HandlerType::Manual => manual_handler::run_manual_review(&handler_input)?,
+/// Run `handler` on `input` and return its verdict. Blocks until the
+/// manual prompt is answered or the external process exits.
+pub(crate) fn run_handler(handler: &HandlerType, input: &HandlerInput) -> Result<HandlerOutput> {
+    match handler {
+        HandlerType::Manual => manual_handler::run_manual_review(input),
         HandlerType::External(executable) => {
             let raw_output = handlers::execute_handler(
                 executable,
-                &handler_input.deliverable,
-                &handler_input.request_id,
-                &handler_input.seller,
-                handler_input.deadline,
-                handler_input.price_usdc,
+                &input.deliverable,
+                &input.request_id,
+                &input.seller,
+                input.deadline,
+                input.price_usdc,
                 60, // default timeout
             )?;
-            validation::parse_handler_output(&raw_output)?
+            validation::parse_handler_output(&raw_output)
         }
-    };
+    }
+}
 
+/// Save the handler's verdict on `req`, record it on-chain through
+/// `submitter` (if any), move a passing request to Validated, and report
+/// it. `policy_seller` is set when the response was accepted under a
+/// standing approval.
+///
+/// On-chain, the request only moves to Validated once the verdict is
+/// confirmed; one still unconfirmed at the timeout leaves the request as it
+/// was, with the result saved. If the request already has a verdict
+/// on-chain, that one is kept rather than submitting another.
+///
+/// Callers running handlers in parallel must call this one verdict at a
+/// time, so result files and submissions never interleave.
+pub(crate) async fn record_verdict(
+    req: &LocalRequest,
+    handler_output: &HandlerOutput,
+    policy_seller: Option<&str>,
+    submitter: Option<&Submitter>,
+) -> Result<ValidationResult> {
     // c. Create ValidationResult.
-    let result = validation::create_result(&req.request_id, &handler_output);
+    let mut result = validation::create_result(&req.request_id, handler_output);
 
     debug!(
         request_id = %result.request_id,
@@ -276,29 +760,27 @@ fn process_validation(
     // d. Save result locally.
     validation::save_result(&result)?;
 
-    // e. Submit validation on-chain (if contract deployed).
-    if addresses::REQUEST_REGISTRY != Address::ZERO {
-        // TODO: Submit submitValidation transaction on-chain:
-        //   let signer = TransactionSigner::from_keystore_with_passphrase(&passphrase)?;
-        //   let provider = ProviderBuilder::new()
-        //       .signer(signer.inner().clone())
-        //       .on_http(cfg.network.chain_rpc.parse()?);
-        //   let registry = RequestRegistry::new(addresses::REQUEST_REGISTRY, provider);
-        //   registry.submitValidation(
-        //       U256::from_str(&req.request_id)?,
-        //       result.passed,
-        //       addr,
-        //   ).send().await?.get_receipt().await?;
-        formatter::print_info("Submitting validation...");
-        debug!(
-            contract = %addresses::REQUEST_REGISTRY,
-            request_id = %req.request_id,
-            passed = result.passed,
-            "would submit submitValidation transaction (placeholder)"
-        );
+    // e. Submit validation on-chain (if the registry is deployed).
+    if let Some(submitter) = submitter {
+        match submitter.submit(&result).await? {
+            Some(passed) => result.passed = passed,
+            None => return Ok(result),
+        }
+    }
+
+    // f. A passing verdict moves the request to Validated so the seller can
+    //    claim. Failed responses stay in Responded until they expire.
+    if result.passed {
+        let note = match policy_seller {
+            Some(seller) => format!("accepted under standing approval for {seller}"),
+            None => format!("validation score {}", result.score),
+        };
+        RequestCache::update(&req.request_id, |r| {
+            r.transition_with_note(LocalRequestStatus::Validated, Some(note))
+        })?;
     }
 
-    // f. Display result to user.
+    // g. Display result to user.
     let status_label = if result.passed { "PASSED" } else { "FAILED" };
     formatter::print_success(&format!(
         "Validation {}: {} (score: {}/100)",
@@ -317,7 +799,175 @@ fn process_validation(
         ));
     }
 
-    Ok(())
+    Ok(result)
+}
+
+/// Records verdicts in the Validation Registry, signed with the agent's key.
+pub(crate) struct Submitter {
+    client: Arc<ChainClient>,
+    signer: TransactionSigner,
+    registry: Address,
+    network: NetworkConfig,
+    /// Refuse fees over the cap instead of asking (the daemon).
+    unattended: bool,
+}
+
+impl Submitter {
+    /// A submitter for the Validation Registry in `contracts`, signing with
+    /// `signer`; `None` if none is deployed.
+    pub(crate) async fn connect(
+        cfg: &Config,
+        contracts: &ContractAddresses,
+        signer: TransactionSigner,
+    ) -> Result<Option<Self>> {
+        if contracts.validation_registry.is_zero() {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            client: ChainClient::shared(cfg).await?,
+            signer,
+            registry: contracts.validation_registry,
+            network: cfg.network.clone(),
+            unattended: false,
+        }))
+    }
+
+    /// Never ask before paying a fee over `fees.max_fee_gwei_cap`: such
+    /// submissions fail with [`FeeAboveCap`] instead.
+    pub(crate) fn unattended(self) -> Self {
+        Self {
+            unattended: true,
+            ..self
+        }
+    }
+
+    /// Record `result` on-chain and wait for it to be confirmed. Returns
+    /// whether the recorded verdict passed — `result`'s, or the one already
+    /// on-chain if there was one — or `None` if the transaction is not
+    /// confirmed yet. A fee over the cap is confirmed with the user first.
+    async fn submit(&self, result: &ValidationResult) -> Result<Option<bool>> {
+        let request_id = result
+            .request_id
+            .parse::<U256>()
+            .with_context(|| format!("request {} has no on-chain ID", result.request_id))?;
+
+        formatter::print_info("Submitting validation...");
+        let submission = self
+            .client
+            .submit_validation(
+                self.registry,
+                &self.signer,
+                request_id,
+                result.score,
+                result.passed,
+                |quote| {
+                    if self.unattended {
+                        return Err(FeeAboveCap { quote: *quote }.into());
+                    }
+                    prompt::confirm_funds(&format!(
+                        "Submitting this validation may cost up to {} per gas, above your cap of {}",
+                        fees::display_gwei(quote.max_fee_per_gas_wei),
+                        fees::display_gwei(quote.cap_wei.unwrap_or_default()),
+                    ))
+                },
+            )
+            .await?;
+        match submission {
+            ValidationSubmission::AlreadyRecorded(recorded) => {
+                formatter::print_info(&format!(
+                    "  Request {} already has a verdict on-chain ({}, score {}); not submitting again.",
+                    result.request_id,
+                    if recorded.passed { "passed" } else { "failed" },
+                    recorded.score,
+                ));
+                Ok(Some(recorded.passed))
+            }
+            ValidationSubmission::Sent(tx_hash) => {
+                let confirmed =
+                    super::await_confirmation(&self.client, &self.network, tx_hash, "validation")
+                        .await?;
+                Ok(confirmed.then_some(result.passed))
+            }
+        }
+    }
+}
+
+/// The seller whose standing approval covers paying for this response once
+/// it passes, if any. Only our own requests confirmed under a policy
+/// qualify, and only when the on-chain responder is that seller; anything
+/// else is reviewed and paid for normally.
+async fn standing_approval(req: &LocalRequest, submitter: Option<&Submitter>) -> Option<String> {
+    let seller = req.trust_policy.as_deref()?;
+    if req.role != RequestRole::Buyer {
+        return None;
+    }
+
+    let responder = match responder_of(req, seller, submitter).await {
+        Ok(responder) => responder,
+        Err(err) => {
+            formatter::print_warning(&format!(
+                "Could not check who answered request {} ({err:#}). Reviewing normally.",
+                req.request_id
+            ));
+            return None;
+        }
+    };
+
+    let store = match TrustStore::load() {
+        Ok(store) => store,
+        Err(err) => {
+            formatter::print_warning(&format!(
+                "Could not read your standing approvals ({err:#}). Reviewing normally."
+            ));
+            return None;
+        }
+    };
+
+    match store.check_response(&req.request_id, seller, &responder, req.price_usdc) {
+        Coverage::Approved(_) => Some(seller.to_string()),
+        Coverage::NoPolicy => {
+            formatter::print_info(&format!(
+                "Your standing approval for {seller} was removed. Reviewing normally."
+            ));
+            None
+        }
+        Coverage::Declined(reason) => {
+            formatter::print_warning(&format!("{reason} Reviewing normally."));
+            None
+        }
+    }
+}
+
+/// Who submitted the on-chain response to `req`, written the way `seller`
+/// is: `#<agent ID>` if the policy names an agent and the responder is
+/// registered, the lowercase address otherwise.
+async fn responder_of(
+    req: &LocalRequest,
+    seller: &str,
+    submitter: Option<&Submitter>,
+) -> Result<String> {
+    let client = &submitter
+        .context("no Validation Registry is deployed on this network")?
+        .client;
+    let contracts = client.contracts();
+    let request_id = req
+        .request_id
+        .parse::<U256>()
+        .with_context(|| format!("request {} has no on-chain ID", req.request_id))?;
+    let response = client
+        .get_response(contracts.request_registry, request_id)
+        .await?
+        .context("no response has been submitted on-chain")?;
+
+    if seller.starts_with('#') && !contracts.agent_registry.is_zero() {
+        if let Some(agent_id) = client
+            .get_agent_of(contracts.agent_registry, response.seller)
+            .await?
+        {
+            return Ok(trust::agent_seller(agent_id.saturating_to()));
+        }
+    }
+    Ok(response.seller.to_string().to_ascii_lowercase())
 }
 
 // ---------------------------------------------------------------------------
@@ -328,6 +978,10 @@ fn process_validation(
 mod tests {
     use super::*;
     use crate::engine::handlers::HandlerType;
+    use std::sync::Mutex;
+
+    /// Mutex to serialise tests that mutate environment variables.
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
 
     #[test]
     fn test_handler_type_resolution_manual() {
@@ -353,7 +1007,291 @@ mod tests {
         assert!(result.is_err());
     }
 
+    fn request(role: RequestRole, status: LocalRequestStatus) -> LocalRequest {
+        LocalRequest {
+            schema_version: 0,
+            request_id: "42".to_string(),
+            role,
+            status,
+            request_cid: String::new(),
+            price_usdc: 5_000_000,
+            deadline: 10_000,
+            response_cid: None,
+            secret: None,
+            secret_hash: None,
+            counterparty: None,
+            created_at: 1_000,
+            updated_at: 1_000,
+            previous_status: None,
+            tags: Vec::new(),
+            task_summary: None,
+            capability: None,
+            trust_policy: None,
+            history: Vec::new(),
+            payment_check: None,
+            response_attempt: None,
+        }
+    }
+
+    #[test]
+    fn test_only_responded_requests_are_validatable() {
+        for role in [RequestRole::Validator, RequestRole::Buyer] {
+            assert!(check_validatable(&request(role, LocalRequestStatus::Responded)).is_ok());
+        }
+
+        for (status, expected) in [
+            (LocalRequestStatus::Open, "no response yet"),
+            (LocalRequestStatus::Validated, "already been validated"),
+            (LocalRequestStatus::Claimed, "already been validated"),
+            (LocalRequestStatus::Cancelled, "was cancelled"),
+            (LocalRequestStatus::Expired, "has expired"),
+        ] {
+            let err = check_validatable(&request(RequestRole::Validator, status))
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains(expected), "{err}");
+        }
+    }
+
+    #[test]
+    fn test_seller_is_not_assigned_to_validate() {
+        let err = check_validatable(&request(RequestRole::Seller, LocalRequestStatus::Responded))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("not assigned to you"), "{err}");
+    }
+
+    #[test]
+    fn test_validation_failed_is_recognisable() {
+        let err: anyhow::Error = ValidationFailed {
+            request_id: "42".to_string(),
+        }
+        .into();
+        assert!(err.downcast_ref::<ValidationFailed>().is_some());
+        assert_eq!(err.to_string(), "Request 42 did not pass validation.");
+    }
+
+    #[test]
+    fn test_poll_summary_describe() {
+        let summary = PollSummary {
+            pending: 3,
+            skipped: 2,
+            below_margin: 0,
+            processed: 1,
+        };
+        assert_eq!(
+            summary.describe(),
+            "3 pending, 2 skipped (already validated), 1 processed"
+        );
+
+        let summary = PollSummary {
+            pending: 2,
+            skipped: 0,
+            below_margin: 2,
+            processed: 0,
+        };
+        assert_eq!(
+            summary.describe(),
+            "2 pending, 0 skipped (already validated), 2 below margin, 0 processed"
+        );
+    }
+
+    #[test]
+    fn test_passes_filter() {
+        let mut req = request(RequestRole::Validator, LocalRequestStatus::Responded);
+        assert!(passes_filter(&req, None, false));
+        assert!(passes_filter(&req, Some("code-*"), false));
+        assert!(!passes_filter(&req, Some("code-*"), true));
+
+        req.capability = Some("code-review".to_string());
+        assert!(passes_filter(&req, Some("code-review"), true));
+        assert!(passes_filter(&req, Some("code-*"), true));
+        assert!(!passes_filter(&req, Some("data-labeling"), false));
+    }
+
+    #[test]
+    fn test_find_pending_matches_the_loop() {
+        let _guard = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let tmp = tempfile::tempdir().expect("failed to create temp dir");
+        let prev = std::env::var("AGENTMARKET_HOME").ok();
+        std::env::set_var("AGENTMARKET_HOME", tmp.path());
+
+        let now = super::clock::unix_now();
+        let save = |id: &str, role: RequestRole, capability: Option<&str>, deadline: u64| {
+            let mut req = request(role, LocalRequestStatus::Responded);
+            req.request_id = id.to_string();
+            req.capability = capability.map(str::to_string);
+            req.deadline = deadline;
+            RequestCache::save(&req).unwrap();
+        };
+        save("1", RequestRole::Validator, Some("code-review"), now + 3600);
+        save("2", RequestRole::Buyer, None, now + 3600);
+        save("3", RequestRole::Seller, Some("code-review"), now + 3600);
+        save("4", RequestRole::Validator, Some("code-review"), now - 10);
+        save("5", RequestRole::Validator, Some("translate"), now + 3600);
+        save("6", RequestRole::Validator, Some("code-review"), now + 3600);
+        validation::save_result(&validation::create_result(
+            "6",
+            &HandlerOutput {
+                score: 90,
+                reason: "fine".to_string(),
+            },
+        ))
+        .unwrap();
+
+        let query = PendingQuery {
+            filter: Some("code-*"),
+            strict_filter: false,
+            revalidate: false,
+            claim_grace_secs: 0,
+        };
+        let pending = find_pending(&query).unwrap();
+        let mut ids: Vec<_> = pending
+            .requests
+            .iter()
+            .map(|r| r.request_id.as_str())
+            .collect();
+        ids.sort();
+        assert_eq!(ids, ["1", "2"]);
+        assert_eq!(pending.skipped, 1);
+        assert_eq!(pending.filtered_out, 1);
+        assert_eq!(
+            RequestCache::load("4").unwrap().status,
+            LocalRequestStatus::Expired,
+            "overdue requests are swept first"
+        );
+
+        let pending = find_pending(&PendingQuery {
+            strict_filter: true,
+            revalidate: true,
+            ..query
+        })
+        .unwrap();
+        assert_eq!(
+            pending.requests.len(),
+            2,
+            "request 2 declares no capability"
+        );
+        assert_eq!(pending.skipped, 0);
+
+        match prev {
+            Some(v) => std::env::set_var("AGENTMARKET_HOME", v),
+            None => std::env::remove_var("AGENTMARKET_HOME"),
+        }
+    }
+
+    // -- On-chain verdicts ----------------------------------------------------
+
+    fn mocked_submitter(asserter: alloy::providers::mock::Asserter) -> Submitter {
+        Submitter {
+            client: Arc::new(ChainClient::mocked(asserter)),
+            signer: TransactionSigner::from_bytes(&mut [0x5a; 32]).unwrap(),
+            registry: Address::repeat_byte(0x33),
+            network: NetworkConfig::default(),
+            unattended: false,
+        }
+    }
+
+    fn recorded(validator: Address, passed: bool) -> alloy::primitives::Bytes {
+        use crate::chain::contracts::ValidationRegistry;
+        use alloy::sol_types::SolValue;
+        ValidationRegistry::Validation {
+            validator,
+            score: if passed { 80 } else { 20 },
+            passed,
+            timestamp: U256::from(1_700_000_000u64),
+        }
+        .abi_encode()
+        .into()
+    }
+
+    #[test]
+    fn test_responder_is_read_from_chain() {
+        use alloy::primitives::B256;
+        use alloy::sol_types::SolValue;
+        let runtime = tokio::runtime::Runtime::new().expect("build runtime");
+        let mut req = request(RequestRole::Buyer, LocalRequestStatus::Responded);
+        req.trust_policy = Some("#7".to_string());
+        let seller = Address::repeat_byte(0xab);
+
+        let asserter = alloy::providers::mock::Asserter::new();
+        let stored = (seller, "ipfs://bafyresponse".to_string(), B256::ZERO);
+        asserter.push_success(&alloy::primitives::Bytes::from(stored.abi_encode_params()));
+        let submitter = mocked_submitter(asserter);
+        let responder = runtime
+            .block_on(responder_of(&req, "#7", Some(&submitter)))
+            .unwrap();
+        assert_eq!(responder, format!("{seller:#x}"));
+
+        // Without a chain to ask, nobody is vouched for.
+        let err = runtime
+            .block_on(responder_of(&req, "#7", None))
+            .unwrap_err();
+        assert!(
+            format!("{err:#}").contains("Validation Registry"),
+            "{err:#}"
+        );
+    }
+
+    #[test]
+    fn test_verdict_is_validated_only_once_recorded_on_chain() {
+        let _guard = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let tmp = tempfile::tempdir().expect("failed to create temp dir");
+        let prev = std::env::var("AGENTMARKET_HOME").ok();
+        std::env::set_var("AGENTMARKET_HOME", tmp.path());
+        let runtime = tokio::runtime::Runtime::new().expect("build runtime");
+        let passing = HandlerOutput {
+            score: 90,
+            reason: "fine".to_string(),
+        };
+
+        // The submission is refused: the result is kept, the request is not
+        // moved on.
+        let req = request(RequestRole::Validator, LocalRequestStatus::Responded);
+        RequestCache::save(&req).unwrap();
+        let asserter = alloy::providers::mock::Asserter::new();
+        asserter.push_success(&recorded(Address::ZERO, false));
+        asserter.push_failure_msg("execution reverted: not the validator");
+        let err = runtime
+            .block_on(record_verdict(
+                &req,
+                &passing,
+                None,
+                Some(&mocked_submitter(asserter)),
+            ))
+            .unwrap_err();
+        assert!(format!("{err:#}").contains("not the validator"), "{err:#}");
+        assert!(validation::has_result(&req.request_id).unwrap());
+        assert_eq!(
+            RequestCache::load(&req.request_id).unwrap().status,
+            LocalRequestStatus::Responded
+        );
+
+        // A verdict already on-chain is adopted without sending another.
+        let asserter = alloy::providers::mock::Asserter::new();
+        asserter.push_success(&recorded(Address::repeat_byte(0x44), true));
+        let result = runtime
+            .block_on(record_verdict(
+                &req,
+                &passing,
+                None,
+                Some(&mocked_submitter(asserter)),
+            ))
+            .unwrap();
+        assert!(result.passed);
+        assert_eq!(
+            RequestCache::load(&req.request_id).unwrap().status,
+            LocalRequestStatus::Validated
+        );
+
+        match prev {
+            Some(v) => std::env::set_var("AGENTMARKET_HOME", v),
+            None => std::env::remove_var("AGENTMARKET_HOME"),
+        }
+    }
+
     #[test]
+    #[allow(clippy::assertions_on_constants)]
     fn test_poll_interval_is_reasonable() {
         // Sanity check: polling interval should be between 5 and 300 seconds.
         assert!(POLL_INTERVAL_SECS >= 5);
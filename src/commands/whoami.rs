@@ -0,0 +1,134 @@
+//! The `whoami` command: print the current agent's identity.
+//!
+//! A cheap alternative to `status` for scripts: nothing is read from the
+//! chain or the request cache.
+
+use anyhow::{bail, Result};
+use serde::Serialize;
+use tracing::debug;
+
+use super::CommandContext;
+use crate::config;
+use crate::config::store::Config;
+use crate::engine::identity::{self, IdentityState};
+use crate::output::formatter;
+
+/// The identity fields printed by `whoami`, emitted as a flat JSON object.
+#[derive(Debug, PartialEq, Serialize)]
+struct Whoami {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    address: Option<String>,
+    public_key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    agent_id: Option<String>,
+    state: &'static str,
+}
+
+impl Whoami {
+    fn new(cfg: &Config, address: Option<String>) -> Self {
+        let state = match identity::get_identity_state(cfg) {
+            IdentityState::Uninitialized => "Uninitialized",
+            IdentityState::Local { .. } => "Local",
+            IdentityState::Registered { .. } => "Registered",
+        };
+        Self {
+            name: cfg.agent.name.clone(),
+            address,
+            public_key: cfg.identity.public_key.clone(),
+            agent_id: Some(cfg.identity.agent_id.clone()).filter(|id| !id.is_empty()),
+            state,
+        }
+    }
+}
+
+/// Run the `whoami` command.
+///
+/// Unlocks the keystore to derive the address unless `no_unlock` is set, in
+/// which case the address recorded in `profile.json` is used, if it belongs
+/// to the configured key.
+pub async fn run(no_unlock: bool) -> Result<()> {
+    debug!(no_unlock, "starting whoami command");
+
+    // 1. Load identity, unlocking the keystore only if asked to
+    let (cfg, address) = if no_unlock {
+        if !config::store::exists()? {
+            bail!("Agent not initialized. Run `agentmarket init` first.");
+        }
+        let cfg = config::store::load()?;
+        let address = cached_address(&cfg);
+        (cfg, address)
+    } else {
+        let ctx = CommandContext::load_initialized()?;
+        (ctx.cfg, Some(ctx.address))
+    };
+    let whoami = Whoami::new(&cfg, address);
+    debug!(?whoami, "identity loaded");
+
+    // 2. Print
+    if formatter::is_json_mode() {
+        formatter::print_json(&whoami)?;
+        return Ok(());
+    }
+    formatter::print_info(&format!("Name:       {}", whoami.name));
+    formatter::print_info(&format!(
+        "Address:    {}",
+        whoami.address.as_deref().unwrap_or("(locked)")
+    ));
+    formatter::print_info(&format!("Public key: {}", whoami.public_key));
+    formatter::print_info(&format!(
+        "Agent ID:   {}",
+        whoami.agent_id.as_deref().unwrap_or("(not registered)")
+    ));
+    formatter::print_info(&format!("State:      {}", whoami.state));
+    Ok(())
+}
+
+/// The address saved in `profile.json`, if the profile was written for the
+/// key in `cfg`.
+fn cached_address(cfg: &Config) -> Option<String> {
+    identity::load_profile()
+        .ok()
+        .filter(|profile| profile.public_key == cfg.identity.public_key)
+        .map(|profile| profile.address)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_is_flat_and_omits_missing_fields() {
+        let mut cfg = Config::default();
+        cfg.agent.name = "summarizer".to_string();
+        cfg.identity.public_key = "02abc".to_string();
+
+        let local = Whoami::new(&cfg, None);
+        assert_eq!(local.state, "Local");
+        assert_eq!(
+            serde_json::to_value(&local).unwrap(),
+            serde_json::json!({
+                "name": "summarizer",
+                "public_key": "02abc",
+                "state": "Local",
+            })
+        );
+
+        cfg.identity.agent_id = "7".to_string();
+        let registered = Whoami::new(&cfg, Some("0x1234".to_string()));
+        assert_eq!(
+            serde_json::to_value(&registered).unwrap(),
+            serde_json::json!({
+                "name": "summarizer",
+                "address": "0x1234",
+                "public_key": "02abc",
+                "agent_id": "7",
+                "state": "Registered",
+            })
+        );
+    }
+}
@@ -1,40 +1,116 @@
-//! The `withdraw` command: move earned USDC to an external address.
+//! The `withdraw` command: move USDC earnings or ETH to an external address.
 //!
-//! Transfers USDC from the agent's on-chain address to an external
-//! destination. Validates the destination format, checks balances,
-//! and gates on USDC contract availability. Once the contract
-//! interaction is wired, builds a `USDC.transfer()` transaction
-//! and displays the result in zero-crypto UX.
+//! `--asset usdc` (the default) transfers earned USDC with the token's
+//! `transfer()`; `--asset eth` sends ETH from the balance that pays network
+//! fees as a plain value transfer. The destination must carry a valid EIP-55
+//! checksum (see [`identity::validate_address`]) and the amount must fit the
+//! balance of the asset being moved. The estimated network fee is paid in
+//! ETH, and the command refuses if paying it would leave less than
+//! `withdraw.fee_reserve_eth` for later actions. The fee and what the
+//! recipient receives are shown before the confirmation prompt, and both
+//! balances are reported once the transfer is done.
+//!
+//! On a test network the destination's Base mainnet balances are checked
+//! too, with a warning if it holds real funds there: test assets sent to it
+//! are worthless, however the address looks in a wallet.
 
-use alloy::primitives::Address;
+use alloy::primitives::{Address, U256};
 use anyhow::{bail, Context, Result};
+use serde::Serialize;
 use tracing::debug;
 
 use super::CommandContext;
 use crate::chain::client::ChainClient;
-use crate::chain::contracts::addresses;
-use crate::chain::types::Balance;
-use crate::engine::requests::{dollars_to_usdc, format_price_usd};
-use crate::output::formatter;
+use crate::chain::contracts::Network;
+use crate::chain::fees::FeeQuote;
+use crate::chain::retry::RetryPolicy;
+use crate::chain::types::{self, Balance};
+use crate::engine::identity;
+use crate::engine::requests::{parse_eth_amount, parse_usd_amount};
+use crate::output::{formatter, prompt};
+
+/// Decimal places used to show a transfer fee or an amount of ETH.
+const FEE_DISPLAY_DECIMALS: u32 = 8;
+
+/// Which balance `withdraw` moves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Asset {
+    /// USDC earnings, amounts in dollars.
+    Usdc,
+    /// ETH that pays network fees, amounts in ETH.
+    Eth,
+}
+
+impl Asset {
+    /// Parse `usdc` or `eth` (case-insensitive).
+    pub fn parse(input: &str) -> Result<Self> {
+        match input.trim().to_lowercase().as_str() {
+            "usdc" => Ok(Asset::Usdc),
+            "eth" => Ok(Asset::Eth),
+            _ => bail!("Unknown asset '{input}'. Use one of: usdc, eth."),
+        }
+    }
+
+    /// `amount` (atomic units of this asset) for display.
+    fn display(self, amount: U256) -> String {
+        match self {
+            Asset::Usdc => formatter::format_price(amount.saturating_to()),
+            Asset::Eth => Balance { wei: amount }.display_eth_with(FEE_DISPLAY_DECIMALS),
+        }
+    }
+}
+
+/// Both balances of the agent address.
+#[derive(Debug, Serialize)]
+struct Balances {
+    usdc: u64,
+    eth_wei: String,
+}
+
+/// Result of `withdraw`, printed in JSON mode.
+#[derive(Debug, Serialize)]
+struct WithdrawOutcome {
+    asset: Asset,
+    /// Atomic units: USDC's six decimals, or wei.
+    amount: String,
+    destination: String,
+    /// `confirmed`; `pending` if the transfer was sent but not confirmed in
+    /// time; or `unavailable` while the contracts are not deployed.
+    status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    balances_after: Option<Balances>,
+}
 
 /// Run the `withdraw` command.
 ///
-/// Transfers earned USDC from the agent's address to an external destination.
-/// If `amount` is `None`, withdraws the entire USDC balance.
-pub async fn run(destination: String, amount: Option<f64>) -> Result<()> {
-    debug!(destination = %destination, amount = ?amount, "starting withdraw command");
+/// Transfers `asset` from the agent's address to an external destination.
+/// If `amount` is `None`, withdraws the whole USDC balance, or for ETH
+/// everything above the fee and the configured reserve. `no_checksum`
+/// accepts a destination written in a single case.
+pub async fn run(
+    destination: String,
+    amount: Option<String>,
+    asset: String,
+    no_checksum: bool,
+) -> Result<()> {
+    debug!(
+        destination = %destination,
+        amount = ?amount,
+        %asset,
+        no_checksum,
+        "starting withdraw command"
+    );
+
+    let asset = Asset::parse(&asset)?;
 
     // 1. Load config, verify registered, derive address.
     let ctx = CommandContext::load_registered()?;
 
     debug!(agent_address = %ctx.address, "agent address derived");
 
-    // 2. Validate destination address format.
-    validate_destination(&destination)?;
-
-    let dest_addr: Address = destination
-        .parse()
-        .context("failed to parse destination address")?;
+    // 2. Validate the destination, including its checksum.
+    let dest_addr = identity::validate_address(&destination, !no_checksum)?;
 
     debug!(destination = %dest_addr, "destination address validated");
 
@@ -48,60 +124,108 @@ pub async fn run(destination: String, amount: Option<f64>) -> Result<()> {
         bail!("Destination is the same as your agent address. Nothing to transfer.");
     }
 
-    // 3. Check ETH balance for gas.
-    let client = ChainClient::new(&ctx.cfg.network.chain_rpc).await?;
-    let balance_wei = client.get_eth_balance(agent_addr).await?;
-    let balance = Balance { wei: balance_wei };
+    // 3. Parse the amount in the asset's own unit.
+    let requested = match (asset, amount.as_deref()) {
+        (_, None) => None,
+        (Asset::Usdc, Some(amount)) => Some(U256::from(parse_usd_amount(amount)?)),
+        (Asset::Eth, Some(amount)) => Some(U256::from(parse_eth_amount(amount)?)),
+    };
 
+    // 4. Read balances and estimate the fee.
+    let client = ChainClient::from_config(&ctx.cfg).await?;
+    let balance = Balance {
+        wei: client.get_eth_balance(agent_addr).await?,
+    };
     debug!(balance = %balance.display_eth(), "ETH balance retrieved");
 
-    if !balance.is_sufficient_for_registration() {
-        formatter::print_warning("Insufficient funds to cover transfer fees.");
-        formatter::print_funding_instructions(&ctx.address, "0.0001 ETH");
-        bail!("Insufficient funds. Send ETH to your agent address and try again.");
-    }
-
-    // 4. Validate the withdrawal amount (if specified).
-    if let Some(dollars) = amount {
-        if dollars <= 0.0 {
-            bail!("Withdrawal amount must be greater than zero.");
+    let (amount, fee) = match asset {
+        Asset::Usdc => {
+            let usdc_balance = client.get_usdc_balance(agent_addr).await?;
+            let amount_usdc = check_amount(requested.map(|a| a.saturating_to()), usdc_balance)?;
+            debug!(amount_usdc, usdc_balance, "withdrawal amount checked");
+            let fee = super::estimate_fee(
+                "transfer",
+                client.estimate_usdc_transfer_cost(agent_addr, dest_addr, amount_usdc),
+            )
+            .await?;
+            (U256::from(amount_usdc), fee)
         }
-        if !dollars.is_finite() {
-            bail!("Withdrawal amount must be a valid number.");
+        Asset::Eth => {
+            // A plain transfer costs the same whatever the value, so the
+            // fee for "everything" can be estimated before the amount is known.
+            let fee = super::estimate_fee(
+                "transfer",
+                client.estimate_eth_transfer_cost(
+                    agent_addr,
+                    dest_addr,
+                    requested.unwrap_or_default(),
+                ),
+            )
+            .await?;
+            (requested.unwrap_or_default(), fee)
         }
-    }
+    };
 
-    // 5. Contract deployment gate: check if USDC contract interaction is
-    //    available. The USDC address on Base is always set (it is a pre-deployed
-    //    token), so we gate on the Request Registry to determine whether our
-    //    full contract stack is live. For now, display the intended action.
-    //
-    //    TODO: Once USDC.transfer() is added to the sol! interface and the
-    //    alloy provider-with-signer integration is complete, perform the
-    //    actual on-chain transfer here.
-
-    let withdraw_display = match amount {
-        Some(dollars) => {
-            let usdc_amount = dollars_to_usdc(dollars);
-            format_price_usd(usdc_amount)
+    // 5. Keep the configured reserve of ETH for later actions.
+    let reserve = Balance {
+        wei: eth_to_wei(ctx.cfg.withdraw.fee_reserve_eth),
+    };
+    let fee_wei = fee.map_or(U256::ZERO, |fee| fee.cost().wei);
+    let amount = match asset {
+        Asset::Usdc => check_fee_reserve(&balance, fee_wei, &reserve).map(|()| amount),
+        Asset::Eth => check_eth_amount(requested, &balance, fee_wei, &reserve),
+    };
+    let amount = match amount {
+        Ok(amount) => amount,
+        Err(err) => {
+            // ETH being withdrawn has to be there on top of the fee.
+            let moved = match asset {
+                Asset::Usdc => U256::ZERO,
+                Asset::Eth => requested.unwrap_or_default(),
+            };
+            let needed = Balance {
+                wei: reserve.wei.saturating_add(fee_wei).saturating_add(moved),
+            };
+            formatter::print_funding_instructions(
+                &ctx.address,
+                &needed.display_eth_with(FEE_DISPLAY_DECIMALS),
+            );
+            return Err(err);
         }
-        None => "full balance".to_string(),
     };
 
+    // 6. Show what the transfer costs before asking.
+    let withdraw_display = asset.display(amount);
+    super::print_fee(fee.as_ref(), ctx.cfg.fees.eth_usd);
+    formatter::print_info(&format!("Recipient receives: {withdraw_display}"));
+    warn_if_mainnet_funds(ctx.contracts.network, dest_addr).await;
+
+    prompt::confirm_funds(&format!(
+        "About to transfer {withdraw_display} to {destination}"
+    ))?;
+
     formatter::print_info(&format!(
         "Preparing to transfer {} to {}...",
         withdraw_display,
         short_destination(&destination),
     ));
 
-    // TODO: Query USDC.balanceOf(agent_addr) to check on-chain USDC balance.
-    //   let usdc_contract = USDC::new(addresses::USDC, &provider);
-    //   let usdc_balance = usdc_contract.balanceOf(agent_addr).call().await?;
-    //
-    // For now, we cannot query the USDC balance without a provider-with-signer,
-    // so we proceed with the deployment gate check.
+    let mut outcome = WithdrawOutcome {
+        asset,
+        amount: amount.to_string(),
+        destination: dest_addr.to_checksum(None),
+        status: "unavailable",
+        balances_after: None,
+    };
 
-    if addresses::REQUEST_REGISTRY == Address::ZERO {
+    // 7. Contract deployment gate: the USDC address on Base is always set
+    //    (it is a pre-deployed token), so we gate on the Request Registry to
+    //    determine whether our full contract stack is live. Until it is,
+    //    display the intended action.
+    if ctx.contracts.request_registry.is_zero() {
+        if formatter::is_json_mode() {
+            return formatter::print_json(&outcome);
+        }
         formatter::print_warning(
             "On-chain transfers are not yet available. \
              The contract infrastructure is still being deployed.",
@@ -118,47 +242,65 @@ pub async fn run(destination: String, amount: Option<f64>) -> Result<()> {
         return Ok(());
     }
 
-    // 6. Contracts are deployed — display confirmation prompt.
+    // 8. Contracts are deployed.
     formatter::print_info(&format!(
         "Transferring {} to {}...",
         withdraw_display,
         short_destination(&destination),
     ));
 
-    // 7. Build and execute the USDC.transfer() transaction.
-    //
-    // TODO: Wire up the actual transfer once USDC.transfer() is in the sol!
-    // interface and alloy provider-with-signer is integrated:
-    //
-    //   let signer = TransactionSigner::from_keystore_with_passphrase(&passphrase)?;
-    //   let provider = ProviderBuilder::new()
-    //       .signer(signer.inner().clone())
-    //       .on_http(cfg.network.chain_rpc.parse()?);
-    //   let usdc_contract = USDC::new(addresses::USDC, provider);
-    //
-    //   // Determine amount: if None, query balanceOf first.
-    //   let transfer_amount = match amount {
-    //       Some(dollars) => U256::from(dollars_to_usdc(dollars)),
-    //       None => usdc_contract.balanceOf(agent_addr).call().await?,
-    //   };
-    //
-    //   let receipt = usdc_contract
-    //       .transfer(dest_addr, transfer_amount)
-    //       .send().await?
-    //       .get_receipt().await?;
-
-    debug!(
-        destination = %dest_addr,
-        usdc_contract = %addresses::USDC,
-        "submitting USDC transfer (placeholder)"
-    );
+    // 9. Send the transfer: USDC.transfer() for earnings, a plain value
+    //    transfer for ETH. Balances are reported only once it is confirmed.
+    let signer = ctx.transaction_signer()?;
+    let approve_fee = |quote: &FeeQuote| super::confirm_fee_over_cap("transfer", quote);
+    let tx_hash = match asset {
+        Asset::Usdc => {
+            client
+                .transfer_usdc(&signer, dest_addr, amount.saturating_to(), approve_fee)
+                .await?
+        }
+        Asset::Eth => {
+            client
+                .transfer_eth(&signer, dest_addr, amount, approve_fee)
+                .await?
+        }
+    };
+    debug!(destination = %dest_addr, ?asset, %amount, %tx_hash, "transfer sent");
+    if !super::await_confirmation(&client, &ctx.cfg.network, tx_hash, "transfer").await? {
+        outcome.status = "pending";
+        if formatter::is_json_mode() {
+            return formatter::print_json(&outcome);
+        }
+        return Ok(());
+    }
+    outcome.status = "confirmed";
 
-    // 8. Display success in zero-crypto UX.
-    formatter::print_success(&format!(
-        "Transferred {} to {}.",
-        withdraw_display,
-        short_destination(&destination),
-    ));
+    // 10. Report the transfer and the balances left behind.
+    let usdc_after = client.get_usdc_balance(agent_addr).await?;
+    let eth_after = Balance {
+        wei: client.get_eth_balance(agent_addr).await?,
+    };
+    debug!(usdc_after, eth_after = %eth_after.display_eth(), "balances after withdraw");
+
+    if formatter::is_json_mode() {
+        outcome.balances_after = Some(Balances {
+            usdc: usdc_after,
+            eth_wei: eth_after.wei.to_string(),
+        });
+        formatter::print_json(&outcome)?;
+    } else {
+        // Display success in zero-crypto UX.
+        formatter::print_success(&format!(
+            "Transferred {} to {}.",
+            withdraw_display,
+            short_destination(&destination),
+        ));
+        formatter::print_info(&format!(
+            "Remaining: {} in earnings, {} for network fees.",
+            formatter::format_price(usdc_after),
+            eth_after.display_eth_with(FEE_DISPLAY_DECIMALS),
+        ));
+    }
 
     debug!("withdraw command complete");
     Ok(())
@@ -168,36 +310,79 @@ pub async fn run(destination: String, amount: Option<f64>) -> Result<()> {
 // Helpers
 // ---------------------------------------------------------------------------
 
-/// Validate that a destination address is well-formed:
-/// - Must start with "0x"
-/// - Must be exactly 42 characters long
-/// - Characters after "0x" must be valid hexadecimal
-fn validate_destination(address: &str) -> Result<()> {
-    if !address.starts_with("0x") {
-        bail!(
-            "Invalid destination address: must start with \"0x\". \
-             Got: \"{address}\""
-        );
+/// The amount to withdraw, in USDC atomic units: `requested`, or the whole
+/// `balance` if none was given. Refuses zero and anything over the balance.
+fn check_amount(requested: Option<u64>, balance: u64) -> Result<u64> {
+    match requested {
+        Some(0) => bail!("Withdrawal amount must be greater than zero."),
+        Some(amount) if amount > balance => bail!(
+            "Cannot withdraw {}: only {} is available.",
+            formatter::format_price(amount),
+            formatter::format_price(balance)
+        ),
+        Some(amount) => Ok(amount),
+        None if balance == 0 => bail!("Nothing to withdraw: your balance is $0.00."),
+        None => Ok(balance),
     }
+}
 
-    if address.len() != 42 {
+/// Refuse if paying `fee_wei` out of `balance` would leave less than
+/// `reserve` for later actions.
+fn check_fee_reserve(balance: &Balance, fee_wei: U256, reserve: &Balance) -> Result<()> {
+    let left = Balance {
+        wei: balance.wei.saturating_sub(fee_wei),
+    };
+    if balance.wei < fee_wei || left.wei < reserve.wei {
         bail!(
-            "Invalid destination address: must be 42 characters (0x + 40 hex digits). \
-             Got {} characters.",
-            address.len()
+            "This transfer would leave {} for network fees, below the {} reserve. \
+             Add ETH to your agent address, or lower the reserve with \
+             `agentmarket config set withdraw.fee_reserve_eth <ETH>`.",
+            left.display_eth_with(FEE_DISPLAY_DECIMALS),
+            reserve.display_eth_with(FEE_DISPLAY_DECIMALS)
         );
     }
+    Ok(())
+}
 
-    // Verify the hex portion (characters after "0x").
-    let hex_part = &address[2..];
-    if !hex_part.chars().all(|c| c.is_ascii_hexdigit()) {
-        bail!(
-            "Invalid destination address: contains non-hexadecimal characters. \
-             Got: \"{address}\""
-        );
+/// The amount of ETH to withdraw, in wei: `requested`, or everything above
+/// the fee and the reserve if none was given. Refuses zero and anything
+/// that would leave less than `reserve` once the fee is paid.
+fn check_eth_amount(
+    requested: Option<U256>,
+    balance: &Balance,
+    fee_wei: U256,
+    reserve: &Balance,
+) -> Result<U256> {
+    let available = Balance {
+        wei: balance
+            .wei
+            .saturating_sub(fee_wei)
+            .saturating_sub(reserve.wei),
+    };
+    match requested {
+        Some(amount) if amount.is_zero() => {
+            bail!("Withdrawal amount must be greater than zero.")
+        }
+        Some(amount) if amount > available.wei => bail!(
+            "Cannot withdraw {}: only {} is available after the network fee and the {} reserve. \
+             Lower the reserve with `agentmarket config set withdraw.fee_reserve_eth <ETH>`.",
+            Balance { wei: amount }.display_eth_with(FEE_DISPLAY_DECIMALS),
+            available.display_eth_with(FEE_DISPLAY_DECIMALS),
+            reserve.display_eth_with(FEE_DISPLAY_DECIMALS)
+        ),
+        Some(amount) => Ok(amount),
+        None if available.wei.is_zero() => bail!(
+            "Nothing to withdraw: {} does not cover the network fee and the {} reserve.",
+            balance.display_eth_with(FEE_DISPLAY_DECIMALS),
+            reserve.display_eth_with(FEE_DISPLAY_DECIMALS)
+        ),
+        None => Ok(available.wei),
     }
+}
 
-    Ok(())
+/// Convert a configured ETH amount to wei. Negative amounts count as zero.
+fn eth_to_wei(eth: f64) -> U256 {
+    U256::from((eth.max(0.0) * 1e18).round() as u128)
 }
 
 /// Shorten a destination address for display: show first 6 and last 4 chars.
@@ -209,6 +394,48 @@ fn short_destination(address: &str) -> String {
     }
 }
 
+/// On test network `network`, warn if `destination` also holds funds on
+/// Base mainnet. Best effort: if mainnet cannot be reached the transfer
+/// goes ahead without the check.
+async fn warn_if_mainnet_funds(network: Network, destination: Address) {
+    if !network.is_testnet() {
+        return;
+    }
+    let mainnet = Network::BaseMainnet;
+    let balances = match ChainClient::new(mainnet.public_rpc()).await {
+        Ok(client) => {
+            client
+                .with_retry(RetryPolicy::none())
+                .with_contracts(mainnet.preset())
+                .get_balances(destination)
+                .await
+        }
+        Err(err) => Err(err),
+    };
+    match balances {
+        Ok(balances) => {
+            if let Some(warning) = mainnet_funds_warning(network, &balances) {
+                formatter::print_warning(&warning);
+            }
+        }
+        Err(err) => debug!(error = %format!("{err:#}"), "mainnet balance check failed"),
+    }
+}
+
+/// The warning for sending test assets on `network` to an address holding
+/// `mainnet` balances, if it holds any.
+fn mainnet_funds_warning(network: Network, mainnet: &types::Balances) -> Option<String> {
+    if mainnet.eth.wei.is_zero() && mainnet.usdc == 0 {
+        return None;
+    }
+    Some(format!(
+        "This address also holds real funds on Base mainnet ({} and {} in USDC). \
+         You are sending TESTNET assets on {network}, which have no real value.",
+        mainnet.eth.display_eth(),
+        formatter::format_price(mainnet.usdc),
+    ))
+}
+
 // ---------------------------------------------------------------------------
 // Tests
 // ---------------------------------------------------------------------------
@@ -217,52 +444,187 @@ fn short_destination(address: &str) -> String {
 mod tests {
     use super::*;
 
-    // -- validate_destination -------------------------------------------------
+    const ETH: u128 = 1_000_000_000_000_000_000;
+
+    fn wei(amount: u128) -> Balance {
+        Balance {
+            wei: U256::from(amount),
+        }
+    }
+
+    // -- Mainnet funds --------------------------------------------------------
 
     #[test]
-    fn valid_address_passes() {
-        let addr = "0x1234567890abcdef1234567890abcdef12345678";
-        assert!(validate_destination(addr).is_ok());
+    fn mainnet_funds_are_warned_about_only_when_held() {
+        let empty = types::Balances {
+            eth: wei(0),
+            usdc: 0,
+        };
+        assert_eq!(mainnet_funds_warning(Network::BaseSepolia, &empty), None);
+
+        let funded = types::Balances {
+            eth: wei(ETH / 50),
+            usdc: 12_500_000,
+        };
+        let warning = mainnet_funds_warning(Network::BaseSepolia, &funded).unwrap();
+        assert!(
+            warning.contains("0.0200 ETH and $12.50 in USDC"),
+            "{warning}"
+        );
+        assert!(
+            warning.contains("TESTNET assets on base-sepolia"),
+            "{warning}"
+        );
+
+        let usdc_only = types::Balances {
+            eth: wei(0),
+            usdc: 1,
+        };
+        assert!(mainnet_funds_warning(Network::BaseSepolia, &usdc_only).is_some());
     }
 
+    // -- Asset ----------------------------------------------------------------
+
     #[test]
-    fn valid_address_uppercase_hex() {
-        let addr = "0xABCDEF1234567890ABCDEF1234567890ABCDEF12";
-        assert!(validate_destination(addr).is_ok());
+    fn asset_parse_accepts_both_assets() {
+        assert_eq!(Asset::parse("usdc").unwrap(), Asset::Usdc);
+        assert_eq!(Asset::parse(" ETH ").unwrap(), Asset::Eth);
+
+        let err = Asset::parse("btc").unwrap_err().to_string();
+        assert!(err.contains("usdc, eth"), "{err}");
     }
 
     #[test]
-    fn valid_address_mixed_case() {
-        let addr = "0xaBcDeF1234567890AbCdEf1234567890aBcDeF12";
-        assert!(validate_destination(addr).is_ok());
+    fn asset_display_uses_each_unit() {
+        assert_eq!(Asset::Usdc.display(U256::from(12_500_000u64)), "$12.50");
+        assert_eq!(Asset::Eth.display(U256::from(ETH / 100)), "0.01000000 ETH");
     }
 
     #[test]
-    fn missing_0x_prefix() {
-        let addr = "1234567890abcdef1234567890abcdef12345678";
-        let err = validate_destination(addr).unwrap_err();
-        assert!(err.to_string().contains("must start with \"0x\""));
+    fn withdraw_outcome_json_shape() {
+        let outcome = WithdrawOutcome {
+            asset: Asset::Eth,
+            amount: (ETH / 100).to_string(),
+            destination: "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".into(),
+            status: "confirmed",
+            balances_after: Some(Balances {
+                usdc: 1_000_000,
+                eth_wei: "5".into(),
+            }),
+        };
+        assert_eq!(
+            serde_json::to_value(&outcome).unwrap(),
+            serde_json::json!({
+                "asset": "eth",
+                "amount": "10000000000000000",
+                "destination": "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+                "status": "confirmed",
+                "balances_after": { "usdc": 1_000_000, "eth_wei": "5" },
+            })
+        );
     }
 
+    // -- check_amount ---------------------------------------------------------
+
     #[test]
-    fn too_short() {
-        let addr = "0x1234";
-        let err = validate_destination(addr).unwrap_err();
-        assert!(err.to_string().contains("42 characters"));
+    fn check_amount_defaults_to_full_balance() {
+        assert_eq!(check_amount(None, 12_500_000).unwrap(), 12_500_000);
+        assert_eq!(
+            check_amount(Some(12_500_000), 12_500_000).unwrap(),
+            12_500_000
+        );
+        assert_eq!(check_amount(Some(1), 12_500_000).unwrap(), 1);
+    }
+
+    #[test]
+    fn check_amount_rejects_zero() {
+        let err = check_amount(Some(0), 5_000_000).unwrap_err();
+        assert!(err.to_string().contains("greater than zero"));
+
+        let err = check_amount(None, 0).unwrap_err();
+        assert!(err.to_string().contains("Nothing to withdraw"));
+    }
+
+    #[test]
+    fn check_amount_rejects_over_balance() {
+        let err = check_amount(Some(10_500_000), 10_000_000)
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("Cannot withdraw $10.50: only $10.00 is available"),
+            "{err}"
+        );
+        assert!(check_amount(Some(1), 0).is_err());
+    }
+
+    // -- check_fee_reserve ----------------------------------------------------
+
+    #[test]
+    fn check_fee_reserve_keeps_reserve_after_fee() {
+        let reserve = wei(ETH / 10_000);
+        let fee = U256::from(ETH / 1_000_000);
+
+        assert!(check_fee_reserve(&wei(ETH / 1_000), fee, &reserve).is_ok());
+        // Exactly the reserve left over is enough.
+        assert!(check_fee_reserve(&wei(ETH / 10_000 + ETH / 1_000_000), fee, &reserve).is_ok());
+
+        let err = check_fee_reserve(&wei(ETH / 10_000), fee, &reserve)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("leave 0.00009900 ETH"), "{err}");
+        assert!(err.contains("withdraw.fee_reserve_eth"), "{err}");
+
+        // A fee larger than the balance never underflows.
+        assert!(check_fee_reserve(&wei(10), fee, &wei(0)).is_err());
+    }
+
+    // -- check_eth_amount -----------------------------------------------------
+
+    #[test]
+    fn check_eth_amount_defaults_to_everything_above_fee_and_reserve() {
+        let reserve = wei(ETH / 10_000);
+        let fee = U256::from(ETH / 1_000_000);
+        let balance = wei(ETH / 100);
+
+        assert_eq!(
+            check_eth_amount(None, &balance, fee, &reserve).unwrap(),
+            U256::from(ETH / 100 - ETH / 10_000 - ETH / 1_000_000)
+        );
+        assert_eq!(
+            check_eth_amount(Some(U256::from(ETH / 1_000)), &balance, fee, &reserve).unwrap(),
+            U256::from(ETH / 1_000)
+        );
     }
 
     #[test]
-    fn too_long() {
-        let addr = "0x1234567890abcdef1234567890abcdef1234567890";
-        let err = validate_destination(addr).unwrap_err();
-        assert!(err.to_string().contains("42 characters"));
+    fn check_eth_amount_rejects_zero_and_over_balance() {
+        let reserve = wei(ETH / 10_000);
+        let fee = U256::from(ETH / 1_000_000);
+        let balance = wei(ETH / 100);
+
+        let err = check_eth_amount(Some(U256::ZERO), &balance, fee, &reserve)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("greater than zero"), "{err}");
+
+        // The whole balance leaves nothing for the fee or the reserve.
+        let err = check_eth_amount(Some(U256::from(ETH / 100)), &balance, fee, &reserve)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Cannot withdraw 0.01000000 ETH"), "{err}");
+        assert!(err.contains("only 0.00989900 ETH is available"), "{err}");
+
+        let err = check_eth_amount(None, &wei(ETH / 10_000), fee, &reserve)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Nothing to withdraw"), "{err}");
     }
 
     #[test]
-    fn non_hex_characters() {
-        let addr = "0xGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGG";
-        let err = validate_destination(addr).unwrap_err();
-        assert!(err.to_string().contains("non-hexadecimal"));
+    fn eth_to_wei_converts_config_amounts() {
+        assert_eq!(eth_to_wei(0.0001), U256::from(ETH / 10_000));
+        assert_eq!(eth_to_wei(1.0), U256::from(ETH));
+        assert_eq!(eth_to_wei(-1.0), U256::ZERO);
     }
 
     // -- short_destination ----------------------------------------------------
@@ -0,0 +1,549 @@
+//! Dotted-path access to [`Config`] for `agentmarket config get/set`.
+//!
+//! Every settable key is listed in [`KEYS`] with the kind of value it takes,
+//! so input is parsed and validated before anything is written. Keys under
+//! `[maintenance]` are the names of the built-in maintenance tasks.
+
+use alloy::primitives::Address;
+use anyhow::{bail, Context, Result};
+use toml::Value;
+
+use super::store::Config;
+use crate::chain::contracts::Network;
+use crate::chain::fees::FeeStrategy;
+use crate::engine::identity::parse_capabilities;
+use crate::engine::maintenance;
+use crate::engine::requests::parse_usd_amount;
+
+/// What a key holds, and so how a new value for it is parsed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Kind {
+    Text,
+    Url,
+    /// A URL, or empty to turn the feature off.
+    OptionalUrl,
+    UrlList,
+    /// One URL, or a comma-separated list tried in order; saved as a plain
+    /// string when there is only one.
+    Urls,
+    /// A `ws://` or `wss://` URL, or empty to turn the feature off.
+    OptionalWsUrl,
+    /// Comma-separated list, split like `init` does for capabilities.
+    List,
+    /// Dollar amount, e.g. `5` or `$5.25`.
+    Usd,
+    /// Amount of ETH, e.g. `0.0001`.
+    Eth,
+    /// Fee per gas in gwei, e.g. `0.05`.
+    Gwei,
+    /// `slow`, `normal`, or `fast`.
+    FeeStrategy,
+    /// A preset network name, e.g. `base-sepolia`.
+    Network,
+    Seconds,
+    Millis,
+    Bytes,
+    /// A whole number of things, e.g. files to keep.
+    Count,
+    /// How many times to retry.
+    Retries,
+    /// An EIP-155 chain ID, e.g. `8453`.
+    ChainId,
+    /// Blocks to wait for, at least one.
+    Confirmations,
+    /// A `0x` contract address, or empty for the network's built-in one.
+    OptionalAddress,
+    Bool,
+    /// Written by `init` / `register`; changing it by hand would break the
+    /// link to the keystore or the on-chain record.
+    ReadOnly,
+}
+
+/// Every key outside `[maintenance]`, in `config.toml` order.
+const KEYS: &[(&str, Kind)] = &[
+    ("agent.name", Kind::Text),
+    ("agent.description", Kind::Text),
+    ("agent.version", Kind::Text),
+    ("network.name", Kind::Network),
+    ("network.chain_rpc", Kind::Urls),
+    ("network.expected_chain_id", Kind::ChainId),
+    ("network.ipfs_gateway", Kind::Url),
+    ("network.ipfs_api", Kind::Url),
+    ("network.ipfs_fallback_gateways", Kind::UrlList),
+    ("network.rpc_max_retries", Kind::Retries),
+    ("network.rpc_retry_base_ms", Kind::Millis),
+    ("network.chain_ws", Kind::OptionalWsUrl),
+    ("network.confirmations", Kind::Confirmations),
+    ("network.receipt_timeout_secs", Kind::Seconds),
+    ("network.event_confirmations", Kind::Confirmations),
+    ("identity.agent_id", Kind::ReadOnly),
+    ("identity.ipfs_profile_cid", Kind::ReadOnly),
+    ("identity.public_key", Kind::ReadOnly),
+    ("services.capabilities", Kind::List),
+    ("services.pricing_usd", Kind::Usd),
+    ("sharing.strict", Kind::Bool),
+    ("sharing.recent_window_secs", Kind::Seconds),
+    ("requests.claim_grace_secs", Kind::Seconds),
+    ("requests.max_attachment_bytes", Kind::Bytes),
+    ("requests.approval_cap_usd", Kind::Usd),
+    ("validation.estimated_cost_usd", Kind::Usd),
+    ("validation.min_margin_usd", Kind::Usd),
+    ("withdraw.fee_reserve_eth", Kind::Eth),
+    ("fees.eth_usd", Kind::Usd),
+    ("fees.max_priority_gwei", Kind::Gwei),
+    ("fees.max_fee_gwei_cap", Kind::Gwei),
+    ("fees.strategy", Kind::FeeStrategy),
+    ("contracts.usdc", Kind::OptionalAddress),
+    ("contracts.agent_registry", Kind::OptionalAddress),
+    ("contracts.request_registry", Kind::OptionalAddress),
+    ("contracts.validation_registry", Kind::OptionalAddress),
+    ("notifications.webhook_url", Kind::OptionalUrl),
+    ("logging.file", Kind::Text),
+    ("logging.keep_files", Kind::Count),
+];
+
+/// All valid keys, including one per maintenance task.
+pub fn valid_keys() -> Vec<String> {
+    let mut keys: Vec<String> = KEYS.iter().map(|(key, _)| key.to_string()).collect();
+    keys.extend(
+        maintenance::all_tasks()
+            .iter()
+            .map(|task| format!("maintenance.{}", task.name())),
+    );
+    keys
+}
+
+fn kind_of(key: &str) -> Result<Kind> {
+    if let Some((_, kind)) = KEYS.iter().find(|(k, _)| *k == key) {
+        return Ok(*kind);
+    }
+    if let Some(task) = key.strip_prefix("maintenance.") {
+        if maintenance::all_tasks().iter().any(|t| t.name() == task) {
+            return Ok(Kind::Seconds);
+        }
+    }
+    bail!(
+        "Unknown config key '{key}'. Valid keys: {}",
+        valid_keys().join(", ")
+    );
+}
+
+/// The value of `key` in `cfg`. For a maintenance task without an entry in
+/// `[maintenance]`, this is the task's built-in cadence.
+pub fn get(cfg: &Config, key: &str) -> Result<Value> {
+    kind_of(key)?;
+
+    if let Some(task) = key.strip_prefix("maintenance.") {
+        let every = maintenance::all_tasks()
+            .iter()
+            .find(|t| t.name() == task)
+            .map(|t| maintenance::Scheduler::every_secs(t.as_ref(), cfg))
+            .unwrap_or_default();
+        return Ok(Value::Integer(every as i64));
+    }
+
+    let (section, field) = key.split_once('.').expect("listed keys are dotted");
+    Value::try_from(cfg)
+        .context("failed to serialise config")?
+        .get(section)
+        .and_then(|s| s.get(field))
+        .cloned()
+        .with_context(|| format!("config has no value for '{key}'"))
+}
+
+/// Parse `input` for `key` and store it in `cfg`. Nothing is changed if
+/// the key is unknown or read-only or the value is invalid for it. Setting
+/// `network.name` also sets the rest of that network's preset (see
+/// [`Network::apply`]).
+pub fn set(cfg: &mut Config, key: &str, input: &str) -> Result<()> {
+    let value = parse_value(kind_of(key)?, key, input)?;
+
+    let (section, field) = key.split_once('.').expect("listed keys are dotted");
+    let mut tree = Value::try_from(&*cfg).context("failed to serialise config")?;
+    let table = tree
+        .as_table_mut()
+        .context("config is not a table")?
+        .entry(section)
+        .or_insert_with(|| Value::Table(Default::default()));
+    table
+        .as_table_mut()
+        .with_context(|| format!("config section [{section}] is not a table"))?
+        .insert(field.to_string(), value);
+
+    *cfg = tree
+        .try_into()
+        .with_context(|| format!("invalid value for '{key}'"))?;
+
+    // Switching networks brings the preset's chain ID and endpoint along.
+    if key == "network.name" {
+        let network: Network = cfg.network.name.parse()?;
+        network.apply(&mut cfg.network);
+    }
+    Ok(())
+}
+
+fn parse_value(kind: Kind, key: &str, input: &str) -> Result<Value> {
+    let input = input.trim();
+    let value = match kind {
+        Kind::ReadOnly => bail!(
+            "'{key}' is managed by `agentmarket init` and `agentmarket register` \
+             and cannot be set by hand."
+        ),
+        Kind::Text => {
+            if key == "agent.name" && input.is_empty() {
+                bail!("agent.name cannot be empty.");
+            }
+            Value::String(input.to_string())
+        }
+        Kind::Url => Value::String(parse_url(key, input)?),
+        Kind::OptionalUrl if input.is_empty() => Value::String(String::new()),
+        Kind::OptionalUrl => Value::String(parse_url(key, input)?),
+        Kind::OptionalWsUrl if input.is_empty() => Value::String(String::new()),
+        Kind::OptionalWsUrl => {
+            if !(input.starts_with("ws://") || input.starts_with("wss://")) || input.len() <= 6 {
+                bail!("'{key}' takes a ws:// or wss:// URL, got \"{input}\"");
+            }
+            Value::String(input.trim_end_matches('/').to_string())
+        }
+        Kind::UrlList => Value::Array(
+            split_list(input)
+                .map(|url| parse_url(key, url).map(Value::String))
+                .collect::<Result<_>>()?,
+        ),
+        Kind::Urls => {
+            let mut urls = split_list(input)
+                .map(|url| parse_url(key, url).map(Value::String))
+                .collect::<Result<Vec<_>>>()?;
+            match urls.len() {
+                0 => bail!("'{key}' takes at least one http:// or https:// URL"),
+                1 => urls.remove(0),
+                _ => Value::Array(urls),
+            }
+        }
+        Kind::List => Value::Array(
+            parse_capabilities(input)
+                .into_iter()
+                .map(Value::String)
+                .collect(),
+        ),
+        Kind::Usd => Value::Float(parse_usd_amount(input)? as f64 / 1_000_000.0),
+        Kind::Eth => Value::Float(
+            input
+                .parse::<f64>()
+                .ok()
+                .filter(|eth| eth.is_finite() && *eth >= 0.0)
+                .with_context(|| format!("'{key}' takes an amount of ETH, got \"{input}\""))?,
+        ),
+        Kind::Gwei => Value::Float(
+            input
+                .trim_end_matches("gwei")
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|gwei| gwei.is_finite() && *gwei >= 0.0)
+                .with_context(|| format!("'{key}' takes a fee in gwei, got \"{input}\""))?,
+        ),
+        Kind::Network => Value::String(
+            input
+                .parse::<Network>()
+                .with_context(|| format!("'{key}' takes a network name"))?
+                .name()
+                .to_string(),
+        ),
+        Kind::FeeStrategy => Value::String(
+            input
+                .parse::<FeeStrategy>()
+                .with_context(|| format!("'{key}' takes slow, normal, or fast"))?
+                .name()
+                .to_string(),
+        ),
+        Kind::Seconds | Kind::Millis | Kind::Bytes | Kind::Count | Kind::Retries => {
+            let unit = match kind {
+                Kind::Millis => "milliseconds",
+                Kind::Bytes => "bytes",
+                Kind::Count => "files",
+                Kind::Retries => "retries",
+                _ => "seconds",
+            };
+            let count: i64 = input
+                .parse()
+                .ok()
+                .filter(|count| *count >= 0)
+                .with_context(|| {
+                    format!("'{key}' takes a whole number of {unit}, got \"{input}\"")
+                })?;
+            Value::Integer(count)
+        }
+        Kind::ChainId => Value::Integer(
+            input
+                .parse::<i64>()
+                .ok()
+                .filter(|id| *id > 0)
+                .with_context(|| {
+                    format!("'{key}' takes a chain ID such as 8453, got \"{input}\"")
+                })?,
+        ),
+        Kind::Confirmations => Value::Integer(
+            input
+                .parse::<i64>()
+                .ok()
+                .filter(|blocks| *blocks >= 1)
+                .with_context(|| {
+                    format!("'{key}' takes a number of blocks of at least 1, got \"{input}\"")
+                })?,
+        ),
+        Kind::OptionalAddress if input.is_empty() => Value::String(String::new()),
+        Kind::OptionalAddress => {
+            let address: Address = input
+                .parse()
+                .ok()
+                .filter(|address: &Address| !address.is_zero())
+                .with_context(|| {
+                    format!(
+                        "'{key}' takes a contract address (0x and 40 hex digits), got \"{input}\""
+                    )
+                })?;
+            Value::String(address.to_checksum(None))
+        }
+        Kind::Bool => match input.to_lowercase().as_str() {
+            "true" | "yes" | "on" => Value::Boolean(true),
+            "false" | "no" | "off" => Value::Boolean(false),
+            _ => bail!("'{key}' takes true or false, got \"{input}\""),
+        },
+    };
+    Ok(value)
+}
+
+fn split_list(input: &str) -> impl Iterator<Item = &str> {
+    input.split(',').map(str::trim).filter(|s| !s.is_empty())
+}
+
+fn parse_url(key: &str, input: &str) -> Result<String> {
+    if !(input.starts_with("http://") || input.starts_with("https://")) || input.len() <= 8 {
+        bail!("'{key}' takes an http:// or https:// URL, got \"{input}\"");
+    }
+    Ok(input.trim_end_matches('/').to_string())
+}
+
+/// Render a value the way it is typed on the command line: lists
+/// comma-separated, strings unquoted.
+pub fn display_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Array(items) => items
+            .iter()
+            .map(display_value)
+            .collect::<Vec<_>>()
+            .join(", "),
+        other => other.to_string(),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keys_cover_every_field() {
+        let tree = Value::try_from(Config::default()).unwrap();
+        let mut fields = Vec::new();
+        for (section, table) in tree.as_table().unwrap() {
+            for field in table.as_table().unwrap().keys() {
+                fields.push(format!("{section}.{field}"));
+            }
+        }
+        let listed: Vec<&str> = KEYS.iter().map(|(key, _)| *key).collect();
+        for field in &fields {
+            assert!(
+                listed.contains(&field.as_str()),
+                "{field} missing from KEYS"
+            );
+        }
+        assert_eq!(fields.len(), KEYS.len());
+    }
+
+    #[test]
+    fn test_set_parses_by_kind() {
+        let mut cfg = Config::default();
+
+        set(&mut cfg, "agent.name", " summarizer ").unwrap();
+        set(&mut cfg, "services.pricing_usd", "$2.50").unwrap();
+        set(&mut cfg, "services.capabilities", "Summarize, translate,,").unwrap();
+        set(&mut cfg, "network.chain_rpc", "https://rpc.example.org/").unwrap();
+        set(&mut cfg, "sharing.strict", "yes").unwrap();
+        set(&mut cfg, "requests.claim_grace_secs", "600").unwrap();
+        set(&mut cfg, "requests.approval_cap_usd", "$50").unwrap();
+        set(&mut cfg, "network.rpc_max_retries", "0").unwrap();
+        set(&mut cfg, "network.rpc_retry_base_ms", "500").unwrap();
+        set(&mut cfg, "network.chain_ws", "wss://ws.example.org/").unwrap();
+        set(&mut cfg, "network.expected_chain_id", "84532").unwrap();
+        set(&mut cfg, "network.confirmations", "3").unwrap();
+        set(&mut cfg, "network.receipt_timeout_secs", "300").unwrap();
+        set(&mut cfg, "network.event_confirmations", "12").unwrap();
+        set(&mut cfg, "withdraw.fee_reserve_eth", "0.0005").unwrap();
+        set(&mut cfg, "fees.eth_usd", "$2650.50").unwrap();
+        set(&mut cfg, "fees.max_priority_gwei", "0.002").unwrap();
+        set(&mut cfg, "fees.max_fee_gwei_cap", "0.5 gwei").unwrap();
+        set(&mut cfg, "fees.strategy", "Fast").unwrap();
+        set(
+            &mut cfg,
+            "contracts.request_registry",
+            " 0x5fbdb2315678afecb367f032d93f642f64180aa3 ",
+        )
+        .unwrap();
+        set(
+            &mut cfg,
+            "notifications.webhook_url",
+            "https://hooks.example.org/agent",
+        )
+        .unwrap();
+        set(&mut cfg, "maintenance.sweep", "0").unwrap();
+
+        assert_eq!(cfg.agent.name, "summarizer");
+        assert_eq!(cfg.services.pricing_usd, 2.5);
+        assert_eq!(cfg.services.capabilities, ["Summarize", "translate"]);
+        assert_eq!(cfg.network.chain_rpc.urls(), ["https://rpc.example.org"]);
+        set(
+            &mut cfg,
+            "network.chain_rpc",
+            "https://rpc.example.org, https://fallback.example.org/",
+        )
+        .unwrap();
+        assert_eq!(
+            cfg.network.chain_rpc.urls(),
+            ["https://rpc.example.org", "https://fallback.example.org"]
+        );
+        assert_eq!(
+            get(&cfg, "network.chain_rpc")
+                .map(|v| display_value(&v))
+                .unwrap(),
+            "https://rpc.example.org, https://fallback.example.org"
+        );
+        assert!(cfg.sharing.strict);
+        assert_eq!(cfg.requests.claim_grace_secs, 600);
+        assert_eq!(cfg.requests.approval_cap_usd, 50.0);
+        assert_eq!(cfg.network.rpc_max_retries, 0);
+        assert_eq!(cfg.network.chain_ws, "wss://ws.example.org");
+        assert_eq!(cfg.network.expected_chain_id, 84532);
+        assert_eq!(cfg.network.confirmations, 3);
+        assert_eq!(cfg.network.receipt_timeout_secs, 300);
+        assert_eq!(cfg.network.event_confirmations, 12);
+        assert_eq!(cfg.network.rpc_retry_base_ms, 500);
+        assert_eq!(cfg.withdraw.fee_reserve_eth, 0.0005);
+        assert_eq!(cfg.fees.eth_usd, 2650.5);
+        assert_eq!(cfg.fees.max_priority_gwei, 0.002);
+        assert_eq!(cfg.fees.max_fee_gwei_cap, 0.5);
+        assert_eq!(cfg.fees.strategy, FeeStrategy::Fast);
+        assert_eq!(display_value(&get(&cfg, "fees.strategy").unwrap()), "fast");
+        assert_eq!(
+            cfg.contracts.request_registry,
+            "0x5FbDB2315678afecb367f032d93F642f64180aa3"
+        );
+        assert_eq!(
+            cfg.notifications.webhook_url,
+            "https://hooks.example.org/agent"
+        );
+        assert_eq!(cfg.maintenance.every_secs.get("sweep"), Some(&0));
+
+        set(&mut cfg, "logging.keep_files", "14").unwrap();
+        assert_eq!(cfg.logging.keep_files, 14);
+
+        // An empty address goes back to the built-in one.
+        set(&mut cfg, "contracts.request_registry", "").unwrap();
+        assert_eq!(cfg.contracts.request_registry, "");
+
+        // An empty URL turns webhooks back off.
+        set(&mut cfg, "notifications.webhook_url", "").unwrap();
+        assert_eq!(cfg.notifications.webhook_url, "");
+
+        assert_eq!(
+            display_value(&get(&cfg, "services.capabilities").unwrap()),
+            "Summarize, translate"
+        );
+        assert_eq!(get(&cfg, "maintenance.sweep").unwrap(), Value::Integer(0));
+    }
+
+    #[test]
+    fn test_network_name_applies_the_preset() {
+        let mut cfg = Config::default();
+        set(&mut cfg, "network.chain_ws", "wss://ws.example.org").unwrap();
+        set(&mut cfg, "requests.claim_grace_secs", "600").unwrap();
+
+        set(&mut cfg, "network.name", " base-sepolia ").unwrap();
+        assert_eq!(cfg.network.name, "base-sepolia");
+        assert_eq!(cfg.network.expected_chain_id, 84532);
+        assert_eq!(cfg.network.chain_rpc.urls(), ["https://sepolia.base.org"]);
+        assert_eq!(cfg.network.chain_ws, "");
+        // Settings outside the preset are kept.
+        assert_eq!(cfg.requests.claim_grace_secs, 600);
+
+        set(&mut cfg, "network.name", "base-mainnet").unwrap();
+        assert_eq!(cfg.network.expected_chain_id, 8453);
+        assert_eq!(cfg.network.chain_rpc.urls(), ["https://mainnet.base.org"]);
+    }
+
+    #[test]
+    fn test_set_rejects_invalid_input() {
+        let mut cfg = Config::default();
+        let before = toml::to_string(&cfg).unwrap();
+
+        let err = set(&mut cfg, "agent.nmae", "x").unwrap_err().to_string();
+        assert!(err.contains("Valid keys: agent.name"), "{err}");
+        assert!(err.contains("maintenance.sweep"), "{err}");
+
+        assert!(set(&mut cfg, "maintenance.nope", "1").is_err());
+        assert!(set(&mut cfg, "identity.public_key", "02ab").is_err());
+        assert!(set(&mut cfg, "agent.name", " ").is_err());
+        assert!(set(&mut cfg, "services.pricing_usd", "-1").is_err());
+        assert!(set(&mut cfg, "network.ipfs_api", "localhost:5001").is_err());
+        assert!(set(&mut cfg, "network.chain_rpc", " , ").is_err());
+        assert!(set(&mut cfg, "contracts.usdc", "0x1234").is_err());
+        assert!(set(
+            &mut cfg,
+            "contracts.agent_registry",
+            "0x0000000000000000000000000000000000000000"
+        )
+        .is_err());
+        assert!(set(
+            &mut cfg,
+            "network.chain_rpc",
+            "https://ok.example, rpc:8545"
+        )
+        .is_err());
+        assert!(set(&mut cfg, "sharing.recent_window_secs", "soon").is_err());
+        assert!(set(&mut cfg, "sharing.strict", "maybe").is_err());
+        assert!(set(&mut cfg, "withdraw.fee_reserve_eth", "-0.1").is_err());
+        assert!(set(&mut cfg, "logging.keep_files", "a week").is_err());
+        assert!(set(&mut cfg, "fees.max_fee_gwei_cap", "-1").is_err());
+        let err = set(&mut cfg, "network.name", "base-goerli")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("takes a network name"), "{err}");
+        assert!(set(&mut cfg, "fees.max_priority_gwei", "cheap").is_err());
+        let err = set(&mut cfg, "fees.strategy", "turbo")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("slow, normal, or fast"), "{err}");
+        let err = set(&mut cfg, "network.rpc_max_retries", "-1")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("whole number of retries"), "{err}");
+        assert!(set(&mut cfg, "notifications.webhook_url", "hooks.example.org").is_err());
+        let err = set(&mut cfg, "network.chain_ws", "https://rpc.example.org")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("ws:// or wss://"), "{err}");
+        assert!(set(&mut cfg, "network.expected_chain_id", "0").is_err());
+        assert!(set(&mut cfg, "network.expected_chain_id", "base").is_err());
+        let err = set(&mut cfg, "network.confirmations", "0")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("at least 1"), "{err}");
+
+        assert_eq!(toml::to_string(&cfg).unwrap(), before);
+    }
+}
@@ -6,9 +6,11 @@
 //! parameters (64 MB memory, 3 iterations).
 
 use std::fs;
+use std::io::{IsTerminal, Write};
 #[cfg(unix)]
-use std::os::unix::fs::PermissionsExt;
-use std::path::PathBuf;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 
 use aes_gcm::aead::{Aead, KeyInit};
 use aes_gcm::{Aes256Gcm, Nonce};
@@ -17,15 +19,28 @@ use argon2::Argon2;
 use rand::RngCore;
 use serde::{Deserialize, Serialize};
 use tracing::debug;
-use zeroize::Zeroize;
+use zeroize::{Zeroize, Zeroizing};
 
 use super::store::config_dir;
+use crate::output::prompt;
 
 /// Current keystore format version.
 const KEYSTORE_VERSION: u32 = 1;
 
 /// Filename for the encrypted keystore within the config directory.
-const KEYSTORE_FILENAME: &str = "keystore.enc";
+pub const KEYSTORE_FILENAME: &str = "keystore.enc";
+
+/// Environment variable holding the keystore passphrase.
+pub const PASSPHRASE_ENV: &str = "AGENTMARKET_PASSPHRASE";
+
+/// Earlier name of [`PASSPHRASE_ENV`], still honoured.
+pub const LEGACY_PASSPHRASE_ENV: &str = "AGENTMARKET_KEYSTORE_PASSPHRASE";
+
+/// How to give the backup passphrase without a prompt.
+const BACKUP_PASSPHRASE_HINT: &str = "Set AGENTMARKET_BACKUP_PASSPHRASE.";
+
+/// File given with the global `--passphrase-file` flag.
+static PASSPHRASE_FILE: Mutex<Option<PathBuf>> = Mutex::new(None);
 
 /// Argon2id parameters.
 const ARGON2_MEMORY_KIB: u32 = 64 * 1024; // 64 MB
@@ -57,10 +72,15 @@ fn keystore_path() -> Result<PathBuf> {
 
 /// Derives a 256-bit encryption key from a passphrase and salt using Argon2id.
 fn derive_key(passphrase: &str, salt: &[u8]) -> Result<[u8; KEY_LEN]> {
+    derive_key_with(passphrase, salt, &KdfParams::default())
+}
+
+/// Like [`derive_key`], with explicit Argon2id cost parameters.
+fn derive_key_with(passphrase: &str, salt: &[u8], kdf: &KdfParams) -> Result<[u8; KEY_LEN]> {
     let params = argon2::Params::new(
-        ARGON2_MEMORY_KIB,
-        ARGON2_ITERATIONS,
-        ARGON2_PARALLELISM,
+        kdf.memory_kib,
+        kdf.iterations,
+        kdf.parallelism,
         Some(KEY_LEN),
     )
     .map_err(|e| anyhow::anyhow!("failed to build Argon2id parameters: {}", e))?;
@@ -204,36 +224,345 @@ pub fn load_key(passphrase: &str) -> Result<Vec<u8>> {
     plaintext
 }
 
-/// Returns the passphrase for keystore operations.
+/// Set the file the keystore passphrase is read from (the global
+/// `--passphrase-file` flag), or clear it with `None`.
+pub fn set_passphrase_file(path: Option<PathBuf>) {
+    *PASSPHRASE_FILE.lock().unwrap_or_else(|e| e.into_inner()) = path;
+}
+
+/// Returns the keystore passphrase if it was supplied without a prompt.
 ///
 /// Resolution order:
-/// 1. `AGENTMARKET_KEYSTORE_PASSPHRASE` environment variable
-/// 2. Interactive prompt via hidden stdin input
+/// 1. `AGENTMARKET_PASSPHRASE` environment variable
+/// 2. `AGENTMARKET_KEYSTORE_PASSPHRASE` environment variable
+/// 3. The `--passphrase-file` file, with surrounding whitespace trimmed
+pub fn preset_passphrase() -> Result<Option<String>> {
+    for var in [PASSPHRASE_ENV, LEGACY_PASSPHRASE_ENV] {
+        if let Ok(passphrase) = std::env::var(var) {
+            debug!(var, "using passphrase from env var");
+            return Ok(Some(passphrase));
+        }
+    }
+
+    let file = PASSPHRASE_FILE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    let Some(path) = file else {
+        return Ok(None);
+    };
+    let contents = Zeroizing::new(
+        fs::read_to_string(&path)
+            .with_context(|| format!("failed to read passphrase file: {}", path.display()))?,
+    );
+    let passphrase = contents.trim();
+    if passphrase.is_empty() {
+        bail!("Passphrase file {} is empty.", path.display());
+    }
+    debug!(path = %path.display(), "using passphrase from file");
+    Ok(Some(passphrase.to_string()))
+}
+
+/// Returns the passphrase for keystore operations: the preset passphrase
+/// (see [`preset_passphrase`]), otherwise an interactive prompt via hidden
+/// stdin input.
 pub fn get_passphrase() -> Result<String> {
-    if let Ok(passphrase) = std::env::var("AGENTMARKET_KEYSTORE_PASSPHRASE") {
-        debug!("using passphrase from AGENTMARKET_KEYSTORE_PASSPHRASE env var");
+    if let Some(passphrase) = preset_passphrase()? {
         return Ok(passphrase);
     }
+    prompt_passphrase("Enter passphrase: ")
+}
 
-    debug!("prompting for passphrase via stdin");
-    let passphrase = rpassword::prompt_password_stdout("Enter passphrase: ")
-        .context("failed to read passphrase")?;
+/// Returns the passphrase for a new keystore.
+///
+/// Like [`get_passphrase`], but an interactively entered passphrase must be
+/// typed twice.
+pub fn get_new_passphrase() -> Result<String> {
+    if let Some(passphrase) = preset_passphrase()? {
+        return Ok(passphrase);
+    }
 
+    let passphrase = prompt_passphrase("Enter passphrase: ")?;
+    let confirm = prompt_passphrase("Confirm passphrase: ")?;
+    if passphrase != confirm {
+        bail!("Passphrases do not match.");
+    }
     Ok(passphrase)
 }
 
+fn prompt_passphrase(prompt: &str) -> Result<String> {
+    let supply = format!("Set {PASSPHRASE_ENV} or pass --passphrase-file.");
+    prompt::ensure_interactive(prompt, &supply)?;
+    if !std::io::stdin().is_terminal() {
+        bail!(
+            "A passphrase is required and there is no terminal to prompt on. \
+             Set {PASSPHRASE_ENV} or pass --passphrase-file."
+        );
+    }
+    debug!("prompting for passphrase via stdin");
+    prompt::ask_secret(prompt, &supply)
+}
+
 /// Checks whether the keystore file exists on disk.
 pub fn exists() -> Result<bool> {
     let path = keystore_path()?;
     Ok(path.exists())
 }
 
+/// Save an existing private key as this agent's keystore.
+///
+/// Refuses to replace an existing keystore unless `force` is set, since the
+/// key it holds would be lost.
+pub fn import_key(key_bytes: &[u8], passphrase: &str, force: bool) -> Result<()> {
+    if exists()? && !force {
+        bail!(
+            "A keystore already exists. Importing would replace the current key; \
+             back it up with `agentmarket key export` and re-run with --force."
+        );
+    }
+    save_key(key_bytes, passphrase)
+}
+
+// ---------------------------------------------------------------------------
+// Backups
+// ---------------------------------------------------------------------------
+
+/// First line of every backup file, followed by the format version.
+const BACKUP_MAGIC: &str = "AGENTMARKET-KEY-BACKUP";
+
+/// Current backup format version.
+const BACKUP_VERSION: u32 = 1;
+
+/// Argon2id cost parameters, stored in backups so that a later change to
+/// the defaults does not make old backups unreadable.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct KdfParams {
+    algorithm: String,
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self {
+            algorithm: "argon2id".to_string(),
+            memory_kib: ARGON2_MEMORY_KIB,
+            iterations: ARGON2_ITERATIONS,
+            parallelism: ARGON2_PARALLELISM,
+        }
+    }
+}
+
+/// Body of a backup file, after the magic line.
+#[derive(Serialize, Deserialize)]
+struct BackupFile {
+    kdf: KdfParams,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+    /// Hex-encoded compressed public key, so the backup can be identified
+    /// without decrypting it.
+    public_key: String,
+    /// Address of the key, checked again after decryption.
+    address: String,
+}
+
+/// A private key recovered by [`import_backup`].
+pub struct BackupContents {
+    /// Raw private key. Zeroed on drop.
+    pub key_bytes: Zeroizing<Vec<u8>>,
+    pub public_key: String,
+    pub address: String,
+}
+
+/// Write `key_bytes` to `path` as a standalone backup encrypted with
+/// `backup_passphrase`.
+///
+/// The file starts with a magic line naming the format and its version,
+/// followed by JSON holding the Argon2id parameters, salt, nonce,
+/// ciphertext, public key, and address. The plaintext key is never written.
+/// An existing file at `path` is not overwritten; on Unix the file is
+/// created with `0600` permissions.
+pub fn export_backup(
+    key_bytes: &[u8],
+    backup_passphrase: &str,
+    public_key: &str,
+    address: &str,
+    path: &Path,
+) -> Result<()> {
+    if backup_passphrase.is_empty() {
+        bail!("The backup passphrase must not be empty.");
+    }
+
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce_bytes = [0u8; NONCE_LEN];
+    let mut rng = rand::thread_rng();
+    rng.fill_bytes(&mut salt);
+    rng.fill_bytes(&mut nonce_bytes);
+
+    debug!("deriving backup encryption key via Argon2id");
+    let kdf = KdfParams::default();
+    let mut derived_key = derive_key_with(backup_passphrase, &salt, &kdf)?;
+    let cipher = Aes256Gcm::new_from_slice(&derived_key)
+        .map_err(|e| anyhow::anyhow!("failed to create AES-256-GCM cipher: {}", e))?;
+    #[allow(deprecated)] // upstream aes-gcm uses deprecated generic-array API
+    let nonce = Nonce::from_slice(&nonce_bytes);
+    let ciphertext = cipher
+        .encrypt(nonce, key_bytes)
+        .map_err(|e| anyhow::anyhow!("AES-256-GCM encryption failed: {}", e))?;
+    derived_key.zeroize();
+
+    let backup = BackupFile {
+        kdf,
+        salt: hex::encode(salt),
+        nonce: hex::encode(nonce_bytes),
+        ciphertext: hex::encode(ciphertext),
+        public_key: public_key.to_string(),
+        address: address.to_string(),
+    };
+    let body = serde_json::to_string_pretty(&backup).context("failed to serialize backup")?;
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options
+        .open(path)
+        .with_context(|| format!("failed to create backup file: {}", path.display()))?;
+    write!(file, "{BACKUP_MAGIC} v{BACKUP_VERSION}\n{body}\n")
+        .with_context(|| format!("failed to write backup file: {}", path.display()))?;
+
+    debug!(path = %path.display(), "key backup written");
+    Ok(())
+}
+
+/// Read and decrypt a backup written by [`export_backup`].
+///
+/// Fails if the file is not a backup, was written by a newer version of
+/// the CLI, the passphrase is wrong, or the decrypted key does not match
+/// the address recorded in the file.
+pub fn import_backup(path: &Path, backup_passphrase: &str) -> Result<BackupContents> {
+    debug!(path = %path.display(), "reading key backup");
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("failed to read backup file: {}", path.display()))?;
+
+    let (header, body) = contents.split_once('\n').unwrap_or((&contents, ""));
+    let version = header
+        .strip_prefix(BACKUP_MAGIC)
+        .and_then(|rest| rest.trim().strip_prefix('v'))
+        .and_then(|v| v.parse::<u32>().ok())
+        .with_context(|| format!("{} is not an agent key backup", path.display()))?;
+    if version > BACKUP_VERSION {
+        bail!(
+            "backup format version {version} is newer than this CLI supports \
+             ({BACKUP_VERSION}); upgrade agentmarket to import it"
+        );
+    }
+
+    let backup: BackupFile = serde_json::from_str(body).context("failed to parse backup")?;
+    if backup.kdf.algorithm != "argon2id" {
+        bail!(
+            "unsupported backup key derivation '{}'",
+            backup.kdf.algorithm
+        );
+    }
+    let salt = hex::decode(&backup.salt).context("invalid hex in backup salt")?;
+    let nonce_bytes = hex::decode(&backup.nonce).context("invalid hex in backup nonce")?;
+    let ciphertext = hex::decode(&backup.ciphertext).context("invalid hex in backup ciphertext")?;
+    if nonce_bytes.len() != NONCE_LEN {
+        bail!(
+            "invalid nonce length: expected {} bytes, got {}",
+            NONCE_LEN,
+            nonce_bytes.len()
+        );
+    }
+
+    debug!("deriving backup decryption key via Argon2id");
+    let mut derived_key = derive_key_with(backup_passphrase, &salt, &backup.kdf)?;
+    let cipher = Aes256Gcm::new_from_slice(&derived_key)
+        .map_err(|e| anyhow::anyhow!("failed to create AES-256-GCM cipher: {}", e))?;
+    #[allow(deprecated)] // upstream aes-gcm uses deprecated generic-array API
+    let nonce = Nonce::from_slice(&nonce_bytes);
+    let plaintext = cipher.decrypt(nonce, ciphertext.as_ref());
+    derived_key.zeroize();
+    let key_bytes = Zeroizing::new(plaintext.map_err(|_| {
+        anyhow::anyhow!("decryption failed — wrong passphrase or corrupted backup")
+    })?);
+
+    let (public_key, address) = crate::engine::identity::address_from_key(&key_bytes)?;
+    if !address.eq_ignore_ascii_case(&backup.address) {
+        bail!("backup is corrupted: the key does not match the recorded address");
+    }
+
+    debug!(address = %address, "key backup decrypted");
+    Ok(BackupContents {
+        key_bytes,
+        public_key,
+        address,
+    })
+}
+
+/// Returns the passphrase protecting a backup.
+///
+/// Resolution order:
+/// 1. `AGENTMARKET_BACKUP_PASSPHRASE` environment variable
+/// 2. Interactive prompt via hidden stdin input, entered twice when
+///    `confirm` is set (i.e. when creating a backup)
+pub fn get_backup_passphrase(confirm: bool) -> Result<String> {
+    if let Ok(passphrase) = std::env::var("AGENTMARKET_BACKUP_PASSPHRASE") {
+        debug!("using backup passphrase from AGENTMARKET_BACKUP_PASSPHRASE env var");
+        return Ok(passphrase);
+    }
+
+    let passphrase = prompt::ask_secret("Backup passphrase: ", BACKUP_PASSPHRASE_HINT)?;
+    if confirm {
+        let again = prompt::ask_secret("Confirm backup passphrase: ", BACKUP_PASSPHRASE_HINT)?;
+        if passphrase != again {
+            bail!("Passphrases do not match.");
+        }
+    }
+    Ok(passphrase)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::{Mutex, MutexGuard};
+
+    /// Serialises tests that point `AGENTMARKET_HOME` at a temp directory.
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    fn env_lock() -> MutexGuard<'static, ()> {
+        ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Run `f` with `AGENTMARKET_HOME` pointed at a temp directory and no
+    /// passphrase source set, restoring both afterwards.
+    fn with_temp_home<F: FnOnce(&Path)>(f: F) {
+        let _lock = env_lock();
+        let tmp = tempfile::tempdir().unwrap();
+        let vars = ["AGENTMARKET_HOME", PASSPHRASE_ENV, LEGACY_PASSPHRASE_ENV];
+        let prev: Vec<_> = vars.iter().map(|var| std::env::var(var).ok()).collect();
+
+        std::env::set_var("AGENTMARKET_HOME", tmp.path());
+        std::env::remove_var(PASSPHRASE_ENV);
+        std::env::remove_var(LEGACY_PASSPHRASE_ENV);
+        set_passphrase_file(None);
+        f(tmp.path());
+
+        set_passphrase_file(None);
+        for (var, prev) in vars.iter().zip(prev) {
+            match prev {
+                Some(v) => std::env::set_var(var, v),
+                None => std::env::remove_var(var),
+            }
+        }
+    }
 
     #[test]
     fn round_trip_save_and_load() {
+        let _lock = env_lock();
         // Use a temporary directory so we don't touch the real keystore.
         let tmp = tempfile::tempdir().unwrap();
         std::env::set_var("AGENTMARKET_HOME", tmp.path());
@@ -257,8 +586,121 @@ mod tests {
         std::env::remove_var("AGENTMARKET_HOME");
     }
 
+    #[test]
+    fn backup_round_trip_recovers_same_address() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("agent.backup");
+        let (key, public_key, address) = crate::engine::identity::generate_keypair().unwrap();
+
+        export_backup(&key, "backup-pass", &public_key, &address, &path).unwrap();
+
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(contents.starts_with("AGENTMARKET-KEY-BACKUP v1\n"));
+        assert!(
+            !contents.contains(&hex::encode(&key)),
+            "plaintext key leaked"
+        );
+        #[cfg(unix)]
+        assert_eq!(
+            fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+            0o600
+        );
+
+        // Refuses to overwrite an existing backup.
+        assert!(export_backup(&key, "backup-pass", &public_key, &address, &path).is_err());
+
+        let restored = import_backup(&path, "backup-pass").unwrap();
+        assert_eq!(*restored.key_bytes, key);
+        assert_eq!(restored.address, address);
+        assert_eq!(restored.public_key, public_key);
+
+        let err = import_backup(&path, "wrong").err().unwrap().to_string();
+        assert!(err.contains("wrong passphrase"), "{err}");
+    }
+
+    #[test]
+    fn import_rejects_foreign_and_newer_files() {
+        let tmp = tempfile::tempdir().unwrap();
+
+        let foreign = tmp.path().join("notes.txt");
+        fs::write(&foreign, "hello\n{}").unwrap();
+        let err = import_backup(&foreign, "x").err().unwrap().to_string();
+        assert!(err.contains("not an agent key backup"), "{err}");
+
+        let newer = tmp.path().join("newer.backup");
+        fs::write(&newer, "AGENTMARKET-KEY-BACKUP v99\n{}").unwrap();
+        let err = import_backup(&newer, "x").err().unwrap().to_string();
+        assert!(err.contains("newer than this CLI supports"), "{err}");
+    }
+
+    #[test]
+    fn import_key_requires_force_to_replace() {
+        let _lock = env_lock();
+        let tmp = tempfile::tempdir().unwrap();
+        std::env::set_var("AGENTMARKET_HOME", tmp.path());
+
+        let first = [1u8; 32];
+        let second = [2u8; 32];
+
+        // No keystore yet: import goes through without --force.
+        import_key(&first, "pass", false).unwrap();
+        assert_eq!(load_key("pass").unwrap(), first);
+
+        let err = import_key(&second, "pass", false).unwrap_err().to_string();
+        assert!(err.contains("--force"), "{err}");
+        assert_eq!(
+            load_key("pass").unwrap(),
+            first,
+            "refused import changed the key"
+        );
+
+        import_key(&second, "new-pass", true).unwrap();
+        assert_eq!(load_key("new-pass").unwrap(), second);
+
+        std::env::remove_var("AGENTMARKET_HOME");
+    }
+
+    #[test]
+    fn passphrase_from_env_unlocks_keystore() {
+        with_temp_home(|_| {
+            std::env::set_var(PASSPHRASE_ENV, "from-env");
+            save_key(&[0x11; 32], &get_new_passphrase().unwrap()).unwrap();
+            assert_eq!(load_key(&get_passphrase().unwrap()).unwrap(), [0x11; 32]);
+
+            // The new name wins over the old one; the old one still works alone.
+            std::env::set_var(LEGACY_PASSPHRASE_ENV, "legacy");
+            assert_eq!(get_passphrase().unwrap(), "from-env");
+            std::env::remove_var(PASSPHRASE_ENV);
+            assert_eq!(get_passphrase().unwrap(), "legacy");
+        });
+    }
+
+    #[test]
+    fn passphrase_from_file_is_trimmed_and_env_wins() {
+        with_temp_home(|home| {
+            let path = home.join("passphrase.txt");
+            fs::write(&path, "  from-file\n").unwrap();
+            set_passphrase_file(Some(path.clone()));
+
+            save_key(&[0x22; 32], &get_new_passphrase().unwrap()).unwrap();
+            assert_eq!(load_key("from-file").unwrap(), [0x22; 32]);
+
+            std::env::set_var(PASSPHRASE_ENV, "from-env");
+            assert_eq!(get_passphrase().unwrap(), "from-env");
+            std::env::remove_var(PASSPHRASE_ENV);
+
+            fs::write(&path, "\n").unwrap();
+            let err = get_passphrase().unwrap_err().to_string();
+            assert!(err.contains("is empty"), "{err}");
+
+            set_passphrase_file(Some(home.join("missing.txt")));
+            assert!(get_passphrase().is_err());
+        });
+    }
+
     #[test]
     fn exists_returns_false_when_missing() {
+        let _lock = env_lock();
         let tmp = tempfile::tempdir().unwrap();
         std::env::set_var("AGENTMARKET_HOME", tmp.path());
 
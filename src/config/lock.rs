@@ -0,0 +1,688 @@
+//! Lock files for the daemon and for individual requests.
+//!
+//! Locks live in `~/.agentmarket/locks/` as `{name}.lock` and record who
+//! holds them (pid, process start time, hostname, install ID, command). A
+//! lock whose owner is gone — the process exited without cleaning up, or
+//! the pid has since been reused by an unrelated program — is *stale* and is
+//! reclaimed automatically on the next acquisition. So is a lock from
+//! another machine once this one has taken the home over (see
+//! [`super::machine`]).
+//!
+//! Reclamation is race-safe: a waiter must first create the lock's
+//! `.reclaim` guard file (exclusive create), then re-read the lock and only
+//! move it aside if it is still the exact stale lock it judged (matched by a
+//! random nonce). Acquisition itself is an atomic hard link of a fully
+//! written temporary file, so a lock file is never observed half-written
+//! and two processes can never both create it.
+
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use super::machine::{self, current_hostname};
+use super::store::config_dir;
+use crate::clock::unix_now;
+
+// ---------------------------------------------------------------------------
+// Constants
+// ---------------------------------------------------------------------------
+
+/// Directory inside the config directory holding lock files.
+const LOCKS_DIR: &str = "locks";
+
+/// Lock file extension.
+const LOCK_EXT: &str = "lock";
+
+/// Name of the lock held by a running daemon.
+pub const DAEMON_LOCK: &str = "daemon";
+
+/// A reclaim guard older than this is assumed abandoned by a reclaimer that
+/// died mid-way. Reclaiming takes microseconds, so this is generous.
+const RECLAIM_GUARD_TIMEOUT_SECS: u64 = 30;
+
+/// How often a waiter retries after losing a reclamation race.
+const MAX_RECLAIM_RETRIES: u32 = 50;
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+/// Contents of a lock file.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockInfo {
+    /// Process ID of the holder.
+    pub pid: u32,
+    /// Holder's start time (clock ticks since boot, from `/proc`), used to
+    /// detect pid reuse. `None` where the platform does not expose it.
+    pub start_time: Option<u64>,
+    /// Hostname of the holder. Liveness can only be checked for locks taken
+    /// on this machine.
+    pub hostname: String,
+    /// Install ID of the agent home when the lock was taken. A lock from
+    /// another machine stops being held once the home's ID has rotated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub install_id: Option<String>,
+    /// Command that took the lock.
+    pub command: String,
+    /// Unix timestamp when the lock was taken.
+    pub acquired_at: u64,
+    /// Random value distinguishing this acquisition from any other.
+    pub nonce: String,
+}
+
+/// Whether a lock's holder is still around.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LockState {
+    /// The holder is alive (or cannot be checked from this machine).
+    Held,
+    /// The holder is gone; the reason is shown to the user.
+    Stale(String),
+}
+
+/// A held lock, released when dropped.
+#[derive(Debug)]
+pub struct LockGuard {
+    path: PathBuf,
+    nonce: String,
+}
+
+impl LockGuard {
+    /// Path of the lock file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for LockGuard {
+    fn drop(&mut self) {
+        // Only remove the file if it is still ours; a lock broken by the user
+        // and re-acquired by someone else must survive our release.
+        match read_info(&self.path) {
+            Ok(Some(info)) if info.nonce == self.nonce => {
+                if let Err(err) = fs::remove_file(&self.path) {
+                    debug!(path = %self.path.display(), error = %err, "failed to release lock");
+                }
+            }
+            _ => debug!(path = %self.path.display(), "lock no longer ours, not removing"),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Public API
+// ---------------------------------------------------------------------------
+
+/// Name of the per-request lock for `request_id`.
+pub fn request_lock(request_id: &str) -> String {
+    format!("request-{request_id}")
+}
+
+/// Take the lock `name` for `command`, reclaiming it first if its holder is
+/// gone. Fails immediately if a live process holds it.
+pub fn acquire(name: &str, command: &str) -> Result<LockGuard> {
+    let path = lock_path(name)?;
+    let info = LockInfo {
+        pid: std::process::id(),
+        start_time: process_start_time(std::process::id()),
+        hostname: current_hostname(),
+        install_id: current_install_id(),
+        command: command.to_string(),
+        acquired_at: unix_now(),
+        nonce: generate_nonce(),
+    };
+
+    // Each pass either takes the lock, finds it held, or tries to reclaim it.
+    // A successful reclamation is followed by another create attempt; a lost
+    // race against another reclaimer is retried a bounded number of times.
+    let mut contended = 0;
+    loop {
+        if try_create(&path, &info)? {
+            debug!(lock = %name, "lock acquired");
+            return Ok(LockGuard {
+                path,
+                nonce: info.nonce,
+            });
+        }
+
+        let Some(current) = read_info(&path)? else {
+            // Released between our create attempt and the read.
+            continue;
+        };
+
+        let reason = match assess(&current) {
+            LockState::Held => bail!("{}", held_message(name, &current)),
+            LockState::Stale(reason) => reason,
+        };
+
+        if reclaim(&path, &current)? {
+            warn!(
+                lock = %name,
+                pid = current.pid,
+                command = %current.command,
+                reason = %reason,
+                "reclaimed stale lock"
+            );
+            continue;
+        }
+
+        contended += 1;
+        if contended >= MAX_RECLAIM_RETRIES {
+            bail!("{}", held_message(name, &current));
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+}
+
+/// List every lock with its current state, sorted by name.
+pub fn list() -> Result<Vec<(String, LockInfo, LockState)>> {
+    let dir = locks_dir()?;
+    let mut locks = Vec::new();
+
+    for entry in fs::read_dir(&dir)
+        .with_context(|| format!("failed to read locks directory: {}", dir.display()))?
+    {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some(LOCK_EXT) {
+            continue;
+        }
+        let Some(name) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        if let Ok(Some(info)) = read_info(&path) {
+            let state = assess(&info);
+            locks.push((name.to_string(), info, state));
+        }
+    }
+
+    locks.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(locks)
+}
+
+/// Read the lock `name`, if it exists.
+pub fn load(name: &str) -> Result<Option<LockInfo>> {
+    read_info(&lock_path(name)?)
+}
+
+/// Forcibly remove the lock `name`, returning what it contained.
+///
+/// For manual recovery only: if the holder is in fact alive, it loses its
+/// exclusivity.
+pub fn break_lock(name: &str) -> Result<LockInfo> {
+    let path = lock_path(name)?;
+    let Some(info) = read_info(&path)? else {
+        bail!("No lock named {name}.");
+    };
+    fs::remove_file(&path).with_context(|| format!("failed to remove lock: {}", path.display()))?;
+    warn!(lock = %name, pid = info.pid, command = %info.command, "lock broken manually");
+    Ok(info)
+}
+
+/// Remove every lock taken on another machine under an install ID other
+/// than the home's current one, returning their names and contents.
+///
+/// Run when this machine takes the home over: the other machine's processes
+/// stop at their next check, and their locks would otherwise block this one
+/// for good, since their liveness cannot be checked from here.
+pub fn break_foreign() -> Result<Vec<(String, LockInfo)>> {
+    let current = current_install_id();
+    let mut broken = Vec::new();
+    for (name, info, _) in list()? {
+        if info.hostname == current_hostname()
+            || (info.install_id.is_some() && info.install_id == current)
+        {
+            continue;
+        }
+        broken.push((name.clone(), break_lock(&name)?));
+    }
+    Ok(broken)
+}
+
+/// Decide whether a lock's holder is still alive.
+///
+/// A lock from another machine is treated as held while the home's install
+/// ID is the one it was taken under, and as stale once the home has been
+/// taken over. On this machine the lock is stale when the pid no longer
+/// exists, when its start time differs from the recorded one (the pid was
+/// reused), or when `/proc` shows the process is not agentmarket.
+pub fn assess(info: &LockInfo) -> LockState {
+    if info.hostname != current_hostname() {
+        let current = current_install_id();
+        return match (&info.install_id, current) {
+            (Some(taken_under), Some(current)) if *taken_under != current => {
+                LockState::Stale(format!("{} no longer owns this agent home", info.hostname))
+            }
+            _ => LockState::Held,
+        };
+    }
+
+    if !process_alive(info.pid) {
+        return LockState::Stale(format!("process {} is no longer running", info.pid));
+    }
+
+    if let (Some(recorded), Some(actual)) = (info.start_time, process_start_time(info.pid)) {
+        if recorded != actual {
+            return LockState::Stale(format!(
+                "process {} was restarted as a different program",
+                info.pid
+            ));
+        }
+    }
+
+    if let Some(cmdline) = process_cmdline(info.pid) {
+        if !cmdline.contains("agentmarket") {
+            return LockState::Stale(format!("process {} is not agentmarket", info.pid));
+        }
+    }
+
+    LockState::Held
+}
+
+// ---------------------------------------------------------------------------
+// Acquisition and reclamation
+// ---------------------------------------------------------------------------
+
+/// Atomically create the lock file with `info`. Returns `false` if it
+/// already exists.
+fn try_create(path: &Path, info: &LockInfo) -> Result<bool> {
+    let tmp = unique_sibling(path, "tmp");
+    write_info(&tmp, info)?;
+
+    let result = fs::hard_link(&tmp, path);
+    let _ = fs::remove_file(&tmp);
+
+    match result {
+        Ok(()) => Ok(true),
+        Err(err) if err.kind() == ErrorKind::AlreadyExists => Ok(false),
+        Err(err) => Err(err).with_context(|| format!("failed to create lock: {}", path.display())),
+    }
+}
+
+/// Move the stale lock `judged` out of the way. Returns `true` if this call
+/// removed it, `false` if another waiter got there first or the lock changed.
+fn reclaim(path: &Path, judged: &LockInfo) -> Result<bool> {
+    let guard_path = path.with_extension(format!("{LOCK_EXT}.reclaim"));
+    clear_abandoned_guard(&guard_path);
+
+    let guard = LockInfo {
+        nonce: generate_nonce(),
+        acquired_at: unix_now(),
+        ..judged.clone()
+    };
+    if !try_create(&guard_path, &guard)? {
+        debug!(path = %path.display(), "another process is reclaiming this lock");
+        return Ok(false);
+    }
+
+    // Holding the guard, nobody else can move the lock. Only remove it if it
+    // is still the lock we judged stale: it may have been released and
+    // re-acquired by a live process in the meantime.
+    let removed = match read_info(path)? {
+        Some(current) if current.nonce == judged.nonce => {
+            let aside = unique_sibling(path, "stale");
+            fs::rename(path, &aside)
+                .with_context(|| format!("failed to move stale lock: {}", path.display()))?;
+            let _ = fs::remove_file(&aside);
+            true
+        }
+        _ => false,
+    };
+
+    let _ = fs::remove_file(&guard_path);
+    Ok(removed)
+}
+
+/// Remove a reclaim guard left behind by a reclaimer that died.
+fn clear_abandoned_guard(guard_path: &Path) {
+    let age = fs::metadata(guard_path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.elapsed().ok())
+        .map(|d| d.as_secs());
+
+    if age.is_some_and(|age| age > RECLAIM_GUARD_TIMEOUT_SECS) {
+        debug!(path = %guard_path.display(), "removing abandoned reclaim guard");
+        let _ = fs::remove_file(guard_path);
+    }
+}
+
+fn held_message(name: &str, info: &LockInfo) -> String {
+    let host = if info.hostname == current_hostname() {
+        String::new()
+    } else {
+        format!(" on {}", info.hostname)
+    };
+    let holder = match name.strip_prefix("request-") {
+        _ if name == DAEMON_LOCK => format!("A daemon is already running (pid {}{host})", info.pid),
+        Some(id) => format!(
+            "Another agentmarket process (`{}`, pid {}{host}) is working on request {id}",
+            info.command, info.pid
+        ),
+        None => format!(
+            "Another agentmarket process (`{}`, pid {}{host}) holds the {name} lock",
+            info.command, info.pid
+        ),
+    };
+    format!("{holder}. If it is no longer running, use `agentmarket locks --break {name}`.")
+}
+
+// ---------------------------------------------------------------------------
+// Process inspection
+// ---------------------------------------------------------------------------
+
+/// Returns `true` if `pid` is a live agentmarket process: it exists and,
+/// where `/proc` shows its command line, that command is agentmarket.
+pub fn is_agentmarket_process(pid: u32) -> bool {
+    process_alive(pid) && process_cmdline(pid).map_or(true, |cmd| cmd.contains("agentmarket"))
+}
+
+/// Returns `true` if a process with `pid` exists.
+fn process_alive(pid: u32) -> bool {
+    if Path::new("/proc/self").exists() {
+        return Path::new(&format!("/proc/{pid}")).exists();
+    }
+
+    #[cfg(unix)]
+    {
+        std::process::Command::new("kill")
+            .args(["-0", &pid.to_string()])
+            .stderr(std::process::Stdio::null())
+            .status()
+            .map(|s| s.success())
+            .unwrap_or(true)
+    }
+
+    #[cfg(not(unix))]
+    {
+        // No cheap liveness check: never reclaim automatically.
+        true
+    }
+}
+
+/// Start time of `pid` in clock ticks since boot (field 22 of
+/// `/proc/{pid}/stat`), where available.
+fn process_start_time(pid: u32) -> Option<u64> {
+    let stat = fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+    // The command name (field 2) may contain spaces; fields resume after the
+    // last ')'. Field 3 is at index 0, so field 22 is at index 19.
+    let rest = &stat[stat.rfind(')')? + 1..];
+    rest.split_whitespace().nth(19)?.parse().ok()
+}
+
+/// Command line of `pid`, where available.
+fn process_cmdline(pid: u32) -> Option<String> {
+    let raw = fs::read(format!("/proc/{pid}/cmdline")).ok()?;
+    if raw.is_empty() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&raw).replace('\0', " "))
+}
+
+// ---------------------------------------------------------------------------
+// Internal helpers
+// ---------------------------------------------------------------------------
+
+fn locks_dir() -> Result<PathBuf> {
+    let dir = config_dir()?.join(LOCKS_DIR);
+    if !dir.exists() {
+        debug!(path = %dir.display(), "creating locks directory");
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create locks directory: {}", dir.display()))?;
+    }
+    Ok(dir)
+}
+
+fn lock_path(name: &str) -> Result<PathBuf> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))
+    {
+        bail!("Invalid lock name: {name:?}");
+    }
+    Ok(locks_dir()?.join(format!("{name}.{LOCK_EXT}")))
+}
+
+fn unique_sibling(path: &Path, kind: &str) -> PathBuf {
+    path.with_extension(format!(
+        "{LOCK_EXT}.{kind}-{}-{}",
+        std::process::id(),
+        generate_nonce()
+    ))
+}
+
+fn read_info(path: &Path) -> Result<Option<LockInfo>> {
+    let contents = match fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(err) => {
+            return Err(err).with_context(|| format!("failed to read lock: {}", path.display()))
+        }
+    };
+    let info = serde_json::from_str(&contents)
+        .with_context(|| format!("failed to parse lock: {}", path.display()))?;
+    Ok(Some(info))
+}
+
+fn write_info(path: &Path, info: &LockInfo) -> Result<()> {
+    let json = serde_json::to_string_pretty(info).context("failed to serialise lock")?;
+    fs::write(path, json).with_context(|| format!("failed to write lock: {}", path.display()))
+}
+
+/// The home's install ID, if it has been stamped.
+fn current_install_id() -> Option<String> {
+    machine::load_stamp().ok().flatten().map(|s| s.install_id)
+}
+
+fn generate_nonce() -> String {
+    let bytes: [u8; 8] = rand::thread_rng().gen();
+    hex::encode(bytes)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::sync::{Arc, Barrier, Mutex};
+
+    /// Mutex to serialise tests that mutate environment variables.
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    /// A pid above Linux's maximum `pid_max`, so never a live process.
+    const DEAD_PID: u32 = 4_194_305;
+
+    fn with_temp_home<F: FnOnce()>(f: F) {
+        let _guard = ENV_LOCK.lock().expect("env lock poisoned");
+
+        let tmp = tempfile::tempdir().expect("failed to create temp dir");
+        let prev = env::var("AGENTMARKET_HOME").ok();
+
+        env::set_var("AGENTMARKET_HOME", tmp.path());
+        f();
+
+        match prev {
+            Some(v) => env::set_var("AGENTMARKET_HOME", v),
+            None => env::remove_var("AGENTMARKET_HOME"),
+        }
+    }
+
+    fn plant(name: &str, pid: u32, start_time: Option<u64>) -> LockInfo {
+        let info = LockInfo {
+            pid,
+            start_time,
+            hostname: current_hostname(),
+            install_id: None,
+            command: "daemon".to_string(),
+            acquired_at: 1_000,
+            nonce: generate_nonce(),
+        };
+        write_info(&lock_path(name).unwrap(), &info).unwrap();
+        info
+    }
+
+    #[test]
+    fn test_acquire_and_release() {
+        with_temp_home(|| {
+            let guard = acquire("daemon", "daemon").expect("acquire");
+            let info = load("daemon").unwrap().expect("lock file written");
+            assert_eq!(info.pid, std::process::id());
+            assert_eq!(assess(&info), LockState::Held);
+
+            drop(guard);
+            assert!(load("daemon").unwrap().is_none());
+        });
+    }
+
+    #[test]
+    fn test_live_lock_is_refused() {
+        with_temp_home(|| {
+            let _guard = acquire(&request_lock("7"), "respond").expect("first acquire");
+            let err = acquire(&request_lock("7"), "claim").unwrap_err();
+            assert!(err.to_string().contains("working on request 7"), "{err}");
+
+            let _daemon = acquire(DAEMON_LOCK, "daemon").expect("daemon acquire");
+            let err = acquire(DAEMON_LOCK, "daemon").unwrap_err();
+            assert!(err.to_string().contains("already running"), "{err}");
+        });
+    }
+
+    #[test]
+    fn test_dead_owner_lock_is_reclaimed() {
+        with_temp_home(|| {
+            let stale = plant("daemon", DEAD_PID, Some(1));
+            assert!(matches!(assess(&stale), LockState::Stale(_)));
+
+            let _guard = acquire("daemon", "daemon").expect("reclaim and acquire");
+            let info = load("daemon").unwrap().unwrap();
+            assert_eq!(info.pid, std::process::id());
+        });
+    }
+
+    #[test]
+    fn test_reused_pid_is_stale() {
+        with_temp_home(|| {
+            let own = std::process::id();
+            let Some(actual) = process_start_time(own) else {
+                return; // No /proc on this platform.
+            };
+            let info = plant("daemon", own, Some(actual + 1));
+            assert!(matches!(assess(&info), LockState::Stale(_)));
+        });
+    }
+
+    fn foreign(install_id: Option<&str>) -> LockInfo {
+        LockInfo {
+            pid: DEAD_PID,
+            start_time: None,
+            hostname: "some-other-machine".to_string(),
+            install_id: install_id.map(str::to_string),
+            command: "daemon".to_string(),
+            acquired_at: 1_000,
+            nonce: "00".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_foreign_host_lock_is_held_until_takeover() {
+        with_temp_home(|| {
+            let stamp = machine::claim_home().unwrap();
+            assert_eq!(assess(&foreign(None)), LockState::Held);
+            assert_eq!(assess(&foreign(Some(&stamp.install_id))), LockState::Held);
+
+            machine::claim_home().unwrap();
+            assert!(matches!(
+                assess(&foreign(Some(&stamp.install_id))),
+                LockState::Stale(_)
+            ));
+        });
+    }
+
+    #[test]
+    fn test_break_foreign_leaves_local_locks() {
+        with_temp_home(|| {
+            let stamp = machine::claim_home().unwrap();
+            let _own = acquire(&request_lock("7"), "respond").unwrap();
+            write_info(&lock_path(DAEMON_LOCK).unwrap(), &foreign(None)).unwrap();
+            write_info(
+                &lock_path(&request_lock("8")).unwrap(),
+                &foreign(Some(&stamp.install_id)),
+            )
+            .unwrap();
+
+            let broken = break_foreign().unwrap();
+            let names: Vec<&str> = broken.iter().map(|(name, _)| name.as_str()).collect();
+            assert_eq!(names, [DAEMON_LOCK]);
+            assert!(load(&request_lock("7")).unwrap().is_some());
+            assert!(load(&request_lock("8")).unwrap().is_some());
+        });
+    }
+
+    #[test]
+    fn test_concurrent_reclamation_has_one_winner() {
+        with_temp_home(|| {
+            plant("daemon", DEAD_PID, None);
+
+            let waiters = 8;
+            let barrier = Arc::new(Barrier::new(waiters));
+            let handles: Vec<_> = (0..waiters)
+                .map(|_| {
+                    let barrier = Arc::clone(&barrier);
+                    std::thread::spawn(move || {
+                        barrier.wait();
+                        acquire("daemon", "daemon").ok()
+                    })
+                })
+                .collect();
+
+            let guards: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+            let winners: Vec<_> = guards.iter().flatten().collect();
+            assert_eq!(winners.len(), 1, "exactly one waiter may take the lock");
+
+            let info = load("daemon").unwrap().unwrap();
+            assert_eq!(info.nonce, winners[0].nonce);
+        });
+    }
+
+    #[test]
+    fn test_release_does_not_remove_someone_elses_lock() {
+        with_temp_home(|| {
+            let guard = acquire("daemon", "daemon").unwrap();
+            break_lock("daemon").unwrap();
+            let replacement = plant("daemon", std::process::id(), None);
+
+            drop(guard);
+            assert_eq!(load("daemon").unwrap(), Some(replacement));
+        });
+    }
+
+    #[test]
+    fn test_list_reports_state() {
+        with_temp_home(|| {
+            let _live = acquire("daemon", "daemon").unwrap();
+            plant(&request_lock("9"), DEAD_PID, None);
+
+            let locks = list().unwrap();
+            assert_eq!(locks.len(), 2);
+            assert_eq!(locks[0].0, "daemon");
+            assert_eq!(locks[0].2, LockState::Held);
+            assert_eq!(locks[1].0, "request-9");
+            assert!(matches!(locks[1].2, LockState::Stale(_)));
+        });
+    }
+
+    #[test]
+    fn test_invalid_lock_names_rejected() {
+        with_temp_home(|| {
+            assert!(acquire("../config", "x").is_err());
+            assert!(acquire("", "x").is_err());
+        });
+    }
+}
@@ -0,0 +1,286 @@
+//! Detection of an agent home shared between machines.
+//!
+//! Users sometimes keep `~/.agentmarket` in a synced folder (Dropbox,
+//! syncthing, ...). Two machines driving the same home fight over nonces,
+//! daemon state, and the request cache. To catch this, the home carries:
+//!
+//! - `machine.json` — a stamp naming the machine that owns the home
+//!   (hostname + random install ID), written on first use.
+//! - `last_writer.json` — a marker refreshed by every mutating command and
+//!   every daemon cycle, naming the machine that wrote last.
+//!
+//! Comparing both against the local hostname tells us whether another
+//! machine is (or was recently) active. A takeover rotates the install ID,
+//! which a daemon running on the other machine notices and stops.
+
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use super::store::config_dir;
+use crate::clock::unix_now;
+
+// ---------------------------------------------------------------------------
+// Constants
+// ---------------------------------------------------------------------------
+
+/// Name of the machine stamp file inside the config directory.
+const MACHINE_FILE: &str = "machine.json";
+
+/// Name of the last-writer marker file inside the config directory.
+const LAST_WRITER_FILE: &str = "last_writer.json";
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+/// Identifies the machine that owns an agent home.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MachineStamp {
+    /// Hostname of the owning machine.
+    pub hostname: String,
+    /// Random ID generated on first use and rotated on takeover.
+    pub install_id: String,
+    /// Unix timestamp when the stamp was written.
+    pub created_at: u64,
+}
+
+/// Records which machine last mutated the agent home.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WriterMarker {
+    /// Hostname of the writing machine.
+    pub hostname: String,
+    /// Install ID the writer believed was current.
+    pub install_id: String,
+    /// Command that performed the write.
+    pub command: String,
+    /// Unix timestamp of the write.
+    pub timestamp: u64,
+}
+
+/// Outcome of comparing the home's stamp and marker against this machine.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SharingStatus {
+    /// No stamp has been written yet.
+    Unstamped,
+    /// The home belongs to this machine and nobody else wrote recently.
+    Exclusive,
+    /// The home was stamped by another machine, which has been quiet.
+    Foreign { owner: MachineStamp },
+    /// Another machine wrote to the home within the recent window.
+    Conflict { writer: WriterMarker },
+}
+
+// ---------------------------------------------------------------------------
+// Public API
+// ---------------------------------------------------------------------------
+
+/// Returns this machine's hostname, or `"unknown-host"` if it cannot be
+/// determined.
+pub fn current_hostname() -> String {
+    let from_file = |path: &str| {
+        fs::read_to_string(path)
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+    };
+
+    from_file("/proc/sys/kernel/hostname")
+        .or_else(|| from_file("/etc/hostname"))
+        .or_else(|| std::env::var("HOSTNAME").ok().filter(|s| !s.is_empty()))
+        .or_else(|| {
+            std::process::Command::new("hostname")
+                .output()
+                .ok()
+                .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+                .filter(|s| !s.is_empty())
+        })
+        .unwrap_or_else(|| "unknown-host".to_string())
+}
+
+/// Reads the machine stamp, if one has been written.
+pub fn load_stamp() -> Result<Option<MachineStamp>> {
+    read_json(&config_dir()?.join(MACHINE_FILE))
+}
+
+/// Writes the machine stamp.
+pub fn save_stamp(stamp: &MachineStamp) -> Result<()> {
+    write_json(&config_dir()?.join(MACHINE_FILE), stamp)
+}
+
+/// Reads the last-writer marker, if one has been written.
+pub fn load_last_writer() -> Result<Option<WriterMarker>> {
+    read_json(&config_dir()?.join(LAST_WRITER_FILE))
+}
+
+/// Writes a fresh stamp claiming the home for this machine with a new
+/// install ID. Used on first use and on takeover.
+pub fn claim_home() -> Result<MachineStamp> {
+    let stamp = MachineStamp {
+        hostname: current_hostname(),
+        install_id: generate_install_id(),
+        created_at: unix_now(),
+    };
+    debug!(hostname = %stamp.hostname, "claiming agent home for this machine");
+    save_stamp(&stamp)?;
+    Ok(stamp)
+}
+
+/// Records that this machine just mutated the home via `command`.
+pub fn record_write(command: &str) -> Result<()> {
+    let install_id = load_stamp()?.map(|s| s.install_id).unwrap_or_default();
+    let marker = WriterMarker {
+        hostname: current_hostname(),
+        install_id,
+        command: command.to_string(),
+        timestamp: unix_now(),
+    };
+    debug!(command = %command, "recording last writer");
+    write_json(&config_dir()?.join(LAST_WRITER_FILE), &marker)
+}
+
+/// Compares the on-disk stamp and marker against this machine.
+pub fn sharing_status(recent_window_secs: u64) -> Result<SharingStatus> {
+    Ok(assess(
+        load_stamp()?.as_ref(),
+        load_last_writer()?.as_ref(),
+        &current_hostname(),
+        unix_now(),
+        recent_window_secs,
+    ))
+}
+
+/// Pure decision logic behind [`sharing_status`].
+///
+/// A write by another machine within `recent_window_secs` of `now` is a
+/// conflict regardless of who owns the stamp; otherwise ownership is decided
+/// by the stamp's hostname.
+pub fn assess(
+    stamp: Option<&MachineStamp>,
+    writer: Option<&WriterMarker>,
+    hostname: &str,
+    now: u64,
+    recent_window_secs: u64,
+) -> SharingStatus {
+    if let Some(writer) = writer {
+        let recent = now.saturating_sub(writer.timestamp) < recent_window_secs;
+        if writer.hostname != hostname && recent {
+            return SharingStatus::Conflict {
+                writer: writer.clone(),
+            };
+        }
+    }
+
+    match stamp {
+        None => SharingStatus::Unstamped,
+        Some(s) if s.hostname == hostname => SharingStatus::Exclusive,
+        Some(s) => SharingStatus::Foreign { owner: s.clone() },
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Internal helpers
+// ---------------------------------------------------------------------------
+
+fn generate_install_id() -> String {
+    let bytes: [u8; 8] = rand::thread_rng().gen();
+    hex::encode(bytes)
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(path: &PathBuf) -> Result<Option<T>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let value = serde_json::from_str(&contents)
+        .with_context(|| format!("failed to parse {}", path.display()))?;
+    Ok(Some(value))
+}
+
+fn write_json<T: Serialize>(path: &PathBuf, value: &T) -> Result<()> {
+    let json = serde_json::to_string_pretty(value).context("failed to serialise to JSON")?;
+    fs::write(path, json).with_context(|| format!("failed to write {}", path.display()))
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stamp(hostname: &str) -> MachineStamp {
+        MachineStamp {
+            hostname: hostname.to_string(),
+            install_id: "00112233".to_string(),
+            created_at: 1_000,
+        }
+    }
+
+    fn writer(hostname: &str, timestamp: u64) -> WriterMarker {
+        WriterMarker {
+            hostname: hostname.to_string(),
+            install_id: "00112233".to_string(),
+            command: "respond".to_string(),
+            timestamp,
+        }
+    }
+
+    #[test]
+    fn test_assess_unstamped() {
+        assert_eq!(
+            assess(None, None, "me", 5_000, 600),
+            SharingStatus::Unstamped
+        );
+    }
+
+    #[test]
+    fn test_assess_own_stamp_is_exclusive() {
+        let s = stamp("me");
+        let w = writer("me", 4_900);
+        assert_eq!(
+            assess(Some(&s), Some(&w), "me", 5_000, 600),
+            SharingStatus::Exclusive
+        );
+    }
+
+    #[test]
+    fn test_assess_recent_foreign_write_is_conflict() {
+        let s = stamp("me");
+        let w = writer("laptop", 4_900);
+        assert_eq!(
+            assess(Some(&s), Some(&w), "me", 5_000, 600),
+            SharingStatus::Conflict { writer: w.clone() }
+        );
+    }
+
+    #[test]
+    fn test_assess_old_foreign_write_is_not_conflict() {
+        let s = stamp("me");
+        let w = writer("laptop", 1_000);
+        assert_eq!(
+            assess(Some(&s), Some(&w), "me", 5_000, 600),
+            SharingStatus::Exclusive
+        );
+    }
+
+    #[test]
+    fn test_assess_foreign_stamp_quiet() {
+        let s = stamp("laptop");
+        assert_eq!(
+            assess(Some(&s), None, "me", 5_000, 600),
+            SharingStatus::Foreign { owner: s.clone() }
+        );
+    }
+
+    #[test]
+    fn test_current_hostname_is_non_empty() {
+        assert!(!current_hostname().is_empty());
+    }
+}
@@ -1,2 +1,6 @@
+pub mod keys;
 pub mod keystore;
+pub mod lock;
+pub mod machine;
+pub mod pidfile;
 pub mod store;
@@ -0,0 +1,234 @@
+//! The daemon's PID file, `~/.agentmarket/daemon.pid`.
+//!
+//! A running daemon records its process ID here so that a second daemon
+//! (say, a systemd restart racing a manual run) refuses to start, and so
+//! `daemon --status` and `daemon --stop` can find it. The file holds just
+//! the PID; its modification time is when the daemon started. A file whose
+//! process is gone is *stale* and is reclaimed by the next daemon.
+
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use anyhow::{bail, Context, Result};
+use tracing::debug;
+
+use super::lock;
+use super::store::config_dir;
+
+/// Name of the PID file inside the config directory.
+const PID_FILE: &str = "daemon.pid";
+
+/// What the PID file says about the daemon.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DaemonState {
+    /// No PID file.
+    NotRunning,
+    /// The recorded process is alive.
+    Running {
+        pid: u32,
+        /// Unix timestamp when the daemon started, if known.
+        started_at: Option<u64>,
+    },
+    /// The recorded process is gone (or the file cannot be read as a PID).
+    Stale { pid: Option<u32> },
+}
+
+/// The PID file of the running daemon, removed when dropped.
+#[derive(Debug)]
+pub struct PidFile {
+    path: PathBuf,
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        // Only remove the file if it still names us; one reclaimed by another
+        // daemon must survive our exit.
+        if read_pid(&self.path).ok().flatten() == Some(std::process::id()) {
+            if let Err(err) = fs::remove_file(&self.path) {
+                debug!(path = %self.path.display(), error = %err, "failed to remove pid file");
+            }
+        }
+    }
+}
+
+/// Path of the PID file.
+pub fn path() -> Result<PathBuf> {
+    Ok(config_dir()?.join(PID_FILE))
+}
+
+/// Read the PID file and check whether its process is still alive.
+pub fn state() -> Result<DaemonState> {
+    let path = path()?;
+    let pid = match read_pid(&path) {
+        Ok(Some(pid)) => pid,
+        Ok(None) => return Ok(DaemonState::NotRunning),
+        Err(err) => {
+            debug!(error = %format!("{err:#}"), "unreadable pid file");
+            return Ok(DaemonState::Stale { pid: None });
+        }
+    };
+    if !lock::is_agentmarket_process(pid) {
+        return Ok(DaemonState::Stale { pid: Some(pid) });
+    }
+    let started_at = fs::metadata(&path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs());
+    Ok(DaemonState::Running { pid, started_at })
+}
+
+/// Record this process as the running daemon. Fails if another daemon is
+/// alive; a stale file is replaced and reported in the returned state so the
+/// caller can warn about it.
+pub fn create() -> Result<(PidFile, DaemonState)> {
+    let previous = state()?;
+    let path = path()?;
+    match previous {
+        DaemonState::NotRunning => {}
+        DaemonState::Running { pid, .. } => bail!(
+            "A daemon is already running (pid {pid}). \
+             Check it with `agentmarket daemon --status` or stop it with `agentmarket daemon --stop`."
+        ),
+        DaemonState::Stale { .. } => {
+            fs::remove_file(&path).with_context(|| {
+                format!("failed to remove stale pid file: {}", path.display())
+            })?;
+        }
+    }
+
+    let mut file = match OpenOptions::new().write(true).create_new(true).open(&path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == ErrorKind::AlreadyExists => {
+            bail!("Another daemon started at the same time. Check it with `agentmarket daemon --status`.")
+        }
+        Err(err) => {
+            return Err(err)
+                .with_context(|| format!("failed to create pid file: {}", path.display()))
+        }
+    };
+    writeln!(file, "{}", std::process::id())
+        .with_context(|| format!("failed to write pid file: {}", path.display()))?;
+    debug!(path = %path.display(), "pid file created");
+
+    Ok((PidFile { path }, previous))
+}
+
+/// The PID recorded in the file at `path`, or `None` if there is no file.
+fn read_pid(path: &std::path::Path) -> Result<Option<u32>> {
+    let contents = match fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(err) => {
+            return Err(err).with_context(|| format!("failed to read pid file: {}", path.display()))
+        }
+    };
+    let pid = contents
+        .trim()
+        .parse()
+        .with_context(|| format!("pid file does not hold a pid: {}", path.display()))?;
+    Ok(Some(pid))
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::sync::Mutex;
+
+    /// Mutex to serialise tests that mutate environment variables.
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    fn with_temp_home<F: FnOnce()>(f: F) {
+        let _guard = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let tmp = tempfile::tempdir().expect("create temp dir");
+        let prev = env::var("AGENTMARKET_HOME").ok();
+        env::set_var("AGENTMARKET_HOME", tmp.path());
+        f();
+        match prev {
+            Some(v) => env::set_var("AGENTMARKET_HOME", v),
+            None => env::remove_var("AGENTMARKET_HOME"),
+        }
+    }
+
+    /// A PID no process can have (above Linux's maximum `pid_max`).
+    const DEAD_PID: u32 = 999_999_999;
+
+    #[test]
+    fn test_no_pid_file_is_not_running() {
+        with_temp_home(|| {
+            assert_eq!(state().unwrap(), DaemonState::NotRunning);
+        });
+    }
+
+    #[test]
+    fn test_create_writes_pid_and_drop_removes_it() {
+        with_temp_home(|| {
+            let (pidfile, previous) = create().expect("create pid file");
+            assert_eq!(previous, DaemonState::NotRunning);
+            let contents = fs::read_to_string(path().unwrap()).unwrap();
+            assert_eq!(contents.trim(), std::process::id().to_string());
+
+            drop(pidfile);
+            assert!(!path().unwrap().exists());
+        });
+    }
+
+    #[test]
+    fn test_stale_pid_file_is_reclaimed() {
+        with_temp_home(|| {
+            fs::write(path().unwrap(), format!("{DEAD_PID}\n")).unwrap();
+            assert_eq!(
+                state().unwrap(),
+                DaemonState::Stale {
+                    pid: Some(DEAD_PID)
+                }
+            );
+
+            let (_pidfile, previous) = create().expect("reclaim stale pid file");
+            assert_eq!(
+                previous,
+                DaemonState::Stale {
+                    pid: Some(DEAD_PID)
+                }
+            );
+            let contents = fs::read_to_string(path().unwrap()).unwrap();
+            assert_eq!(contents.trim(), std::process::id().to_string());
+        });
+    }
+
+    #[test]
+    fn test_garbage_pid_file_is_stale() {
+        with_temp_home(|| {
+            fs::write(path().unwrap(), "not a pid").unwrap();
+            assert_eq!(state().unwrap(), DaemonState::Stale { pid: None });
+            assert!(create().is_ok());
+        });
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_live_daemon_blocks_a_second_one() {
+        with_temp_home(|| {
+            // The test binary is itself an agentmarket process.
+            let pid = std::process::id();
+            fs::write(path().unwrap(), format!("{pid}\n")).unwrap();
+            assert!(matches!(
+                state().unwrap(),
+                DaemonState::Running { pid: p, started_at: Some(_) } if p == pid
+            ));
+
+            let err = create().unwrap_err().to_string();
+            assert!(err.contains("already running (pid"), "{err}");
+            // The live daemon's file is left alone.
+            let contents = fs::read_to_string(path().unwrap()).unwrap();
+            assert_eq!(contents.trim(), pid.to_string());
+        });
+    }
+}
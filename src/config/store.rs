@@ -1,21 +1,26 @@
 //! Configuration store for AgentMarket CLI.
 //!
 //! Manages reading and writing `~/.agentmarket/config.toml` (or the path
-//! specified by `AGENTMARKET_HOME`). Environment variable overrides are
+//! given by the global `--home` flag or `AGENTMARKET_HOME`). Environment variable overrides are
 //! applied on every `load()` call following the precedence chain:
 //!
 //!   config.toml < AGENTMARKET_* env vars < CLI flags
 //!
 //! CLI-flag overrides are handled at the command layer, not here.
 
+use std::collections::BTreeMap;
 use std::fs;
 use std::os::unix::fs::PermissionsExt;
 use std::path::PathBuf;
+use std::sync::Mutex;
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use tracing::debug;
 
+use crate::chain::contracts::Network;
+use crate::chain::fees::FeeStrategy;
+
 // ---------------------------------------------------------------------------
 // Config structs
 // ---------------------------------------------------------------------------
@@ -27,6 +32,24 @@ pub struct Config {
     pub network: NetworkConfig,
     pub identity: IdentityConfig,
     pub services: ServicesConfig,
+    #[serde(default)]
+    pub sharing: SharingConfig,
+    #[serde(default)]
+    pub requests: RequestsConfig,
+    #[serde(default)]
+    pub validation: ValidationConfig,
+    #[serde(default)]
+    pub withdraw: WithdrawConfig,
+    #[serde(default)]
+    pub fees: FeesConfig,
+    #[serde(default)]
+    pub contracts: ContractsConfig,
+    #[serde(default)]
+    pub maintenance: MaintenanceConfig,
+    #[serde(default)]
+    pub notifications: NotificationsConfig,
+    #[serde(default)]
+    pub logging: LoggingConfig,
 }
 
 /// Basic agent metadata.
@@ -40,9 +63,117 @@ pub struct AgentConfig {
 /// Network endpoints for Base L2 and IPFS.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct NetworkConfig {
-    pub chain_rpc: String,
+    /// The preset network (`base-mainnet`, `base-sepolia`, or `local`) whose
+    /// contracts are used. Empty in configs written before presets existed,
+    /// which follow `expected_chain_id` instead (see
+    /// [`crate::chain::contracts::Network::for_config`]).
+    #[serde(default)]
+    pub name: String,
+    /// Base L2 RPC endpoint, or a list tried in order (see
+    /// [`crate::chain::failover`]).
+    pub chain_rpc: RpcEndpoints,
+    /// Chain ID `chain_rpc` must report; clients refuse to connect to any
+    /// other network unless `--allow-chain-mismatch` is passed.
+    #[serde(default = "default_expected_chain_id")]
+    pub expected_chain_id: u64,
     pub ipfs_gateway: String,
     pub ipfs_api: String,
+    /// Gateways tried, in order, when `ipfs_gateway` fails or interferes.
+    #[serde(default = "default_fallback_gateways")]
+    pub ipfs_fallback_gateways: Vec<String>,
+    /// How many times a chain read is retried after a transient failure
+    /// (timeout, dropped connection, rate limit). 0 turns retries off.
+    #[serde(default = "default_rpc_max_retries")]
+    pub rpc_max_retries: u32,
+    /// Delay before the first retry, in milliseconds; doubled for each
+    /// further attempt.
+    #[serde(default = "default_rpc_retry_base_ms")]
+    pub rpc_retry_base_ms: u64,
+    /// WebSocket endpoint the daemon subscribes to for request events, so
+    /// it reacts as they happen. Empty to poll instead.
+    #[serde(default)]
+    pub chain_ws: String,
+    /// Blocks a transaction must be buried under, its own included, before
+    /// commands treat it as done and update local state.
+    #[serde(default = "default_confirmations")]
+    pub confirmations: u64,
+    /// How long commands wait for those confirmations before leaving the
+    /// transaction to be checked later, in seconds.
+    #[serde(default = "default_receipt_timeout_secs")]
+    pub receipt_timeout_secs: u64,
+    /// Blocks a request event must be buried under before the daemon treats
+    /// it as final and updates local state from it. A reorg shallower than
+    /// this never reaches local state; a deeper one is rewound (see
+    /// [`crate::engine::event_sync`]).
+    #[serde(default = "default_event_confirmations")]
+    pub event_confirmations: u64,
+}
+
+/// One or more chain RPC URLs, primary first. Written to `config.toml` as a
+/// plain string when there is only one, so existing files keep their shape.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RpcEndpoints(Vec<String>);
+
+impl RpcEndpoints {
+    /// Endpoints in `urls`, in order. Fails if there are none.
+    pub fn new(urls: Vec<String>) -> Result<Self> {
+        if urls.is_empty() {
+            anyhow::bail!("network.chain_rpc needs at least one URL");
+        }
+        Ok(Self(urls))
+    }
+
+    /// The endpoint tried first.
+    pub fn primary(&self) -> &str {
+        &self.0[0]
+    }
+
+    /// Every endpoint, primary first.
+    pub fn urls(&self) -> &[String] {
+        &self.0
+    }
+}
+
+impl From<&str> for RpcEndpoints {
+    fn from(url: &str) -> Self {
+        Self(vec![url.to_string()])
+    }
+}
+
+impl From<String> for RpcEndpoints {
+    fn from(url: String) -> Self {
+        Self(vec![url])
+    }
+}
+
+impl std::fmt::Display for RpcEndpoints {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0.join(", "))
+    }
+}
+
+impl Serialize for RpcEndpoints {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0.as_slice() {
+            [url] => url.serialize(serializer),
+            urls => urls.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for RpcEndpoints {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum OneOrMany {
+            One(String),
+            Many(Vec<String>),
+        }
+        match OneOrMany::deserialize(deserializer)? {
+            OneOrMany::One(url) => Ok(url.into()),
+            OneOrMany::Many(urls) => Self::new(urls).map_err(serde::de::Error::custom),
+        }
+    }
 }
 
 /// On-chain and off-chain identity references.
@@ -62,6 +193,114 @@ pub struct ServicesConfig {
     pub pricing_usd: f64,
 }
 
+/// Behaviour when the agent home appears to be shared between machines
+/// (e.g. synced via Dropbox or syncthing).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SharingConfig {
+    /// Refuse to run mutating commands while another machine is active,
+    /// instead of only warning. `--takeover` overrides the refusal.
+    pub strict: bool,
+    /// How recently another machine must have written for the home to be
+    /// considered actively shared, in seconds.
+    pub recent_window_secs: u64,
+}
+
+/// Local request lifecycle settings.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RequestsConfig {
+    /// Seconds past the deadline before a `Validated` request is expired
+    /// locally. Gives the seller a window to claim if the chain still
+    /// accepts it; other non-terminal requests expire at the deadline.
+    pub claim_grace_secs: u64,
+    /// Largest combined size of the files attached to one request, in
+    /// bytes.
+    pub max_attachment_bytes: u64,
+    /// USDC to approve for the Request Registry when a request needs more
+    /// than it may already spend, in USD. 0 approves exactly the request's
+    /// price; a larger cap saves an approval on later requests.
+    pub approval_cap_usd: f64,
+}
+
+/// Validator economics used to decide whether a validation job is worth
+/// running.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ValidationConfig {
+    /// Estimated cost of running the handler once, in USD.
+    pub estimated_cost_usd: f64,
+    /// Minimum expected profit (fee minus cost) to accept a job, in USD.
+    pub min_margin_usd: f64,
+}
+
+/// Limits applied by `agentmarket withdraw`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WithdrawConfig {
+    /// ETH that must remain after a withdrawal's network fee is paid, so
+    /// the agent can still respond and claim afterwards.
+    pub fee_reserve_eth: f64,
+}
+
+/// How network fees are shown before a transaction is sent, and how much
+/// it may bid (see [`crate::chain::fees`]).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FeesConfig {
+    /// Dollars per ETH, used to show an approximate USD figure next to
+    /// each fee estimate. 0 shows fees in ETH only.
+    pub eth_usd: f64,
+    /// Priority fee (tip) per gas, in gwei.
+    pub max_priority_gwei: f64,
+    /// Highest max fee per gas, in gwei, paid without asking. 0 is no cap.
+    pub max_fee_gwei_cap: f64,
+    /// Headroom over the base fee: slow, normal, or fast.
+    pub strategy: FeeStrategy,
+}
+
+/// Contract addresses used instead of the built-in ones for the network
+/// (see [`crate::chain::contracts::resolve`]), for testnet or local
+/// deployments. Empty uses the built-in address.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContractsConfig {
+    pub usdc: String,
+    pub agent_registry: String,
+    pub request_registry: String,
+    pub validation_registry: String,
+}
+
+/// How often the daemon runs each housekeeping task (see
+/// [`crate::engine::maintenance`]), as `task = seconds`. Tasks not listed
+/// use their built-in cadence; `0` turns a task off in the daemon.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MaintenanceConfig {
+    #[serde(flatten)]
+    pub every_secs: BTreeMap<String, u64>,
+}
+
+/// Where the daemon reports lifecycle events (see
+/// [`crate::engine::notifications`]).
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationsConfig {
+    /// URL the daemon POSTs JSON events to; empty turns webhooks off.
+    pub webhook_url: String,
+}
+
+/// Where the daemon writes its log, in addition to the console.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoggingConfig {
+    /// Log file path; rotated daily to `<file>.YYYY-MM-DD`. Empty keeps
+    /// logs on the console only. `daemon --log-file` overrides it.
+    pub file: String,
+    /// Rotated log files to keep; older ones are deleted.
+    pub keep_files: usize,
+}
+
 // ---------------------------------------------------------------------------
 // Defaults
 // ---------------------------------------------------------------------------
@@ -79,13 +318,50 @@ impl Default for AgentConfig {
 impl Default for NetworkConfig {
     fn default() -> Self {
         Self {
-            chain_rpc: "https://mainnet.base.org".to_string(),
+            name: String::new(),
+            chain_rpc: Network::BaseMainnet.public_rpc().into(),
+            expected_chain_id: default_expected_chain_id(),
             ipfs_gateway: "https://gateway.pinata.cloud".to_string(),
             ipfs_api: "http://localhost:5001".to_string(),
+            ipfs_fallback_gateways: default_fallback_gateways(),
+            rpc_max_retries: default_rpc_max_retries(),
+            rpc_retry_base_ms: default_rpc_retry_base_ms(),
+            chain_ws: String::new(),
+            confirmations: default_confirmations(),
+            receipt_timeout_secs: default_receipt_timeout_secs(),
+            event_confirmations: default_event_confirmations(),
         }
     }
 }
 
+fn default_expected_chain_id() -> u64 {
+    crate::chain::contracts::addresses::CHAIN_ID
+}
+
+fn default_fallback_gateways() -> Vec<String> {
+    vec!["https://ipfs.io".to_string()]
+}
+
+fn default_rpc_max_retries() -> u32 {
+    3
+}
+
+fn default_rpc_retry_base_ms() -> u64 {
+    250
+}
+
+fn default_confirmations() -> u64 {
+    1
+}
+
+fn default_event_confirmations() -> u64 {
+    5
+}
+
+fn default_receipt_timeout_secs() -> u64 {
+    120
+}
+
 impl Default for ServicesConfig {
     fn default() -> Self {
         Self {
@@ -95,12 +371,59 @@ impl Default for ServicesConfig {
     }
 }
 
+impl Default for RequestsConfig {
+    fn default() -> Self {
+        Self {
+            claim_grace_secs: crate::engine::requests::DEFAULT_CLAIM_GRACE_SECS,
+            max_attachment_bytes: crate::engine::attachments::DEFAULT_MAX_ATTACHMENT_BYTES,
+            approval_cap_usd: 0.0,
+        }
+    }
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            file: String::new(),
+            keep_files: 7,
+        }
+    }
+}
+
+impl Default for WithdrawConfig {
+    fn default() -> Self {
+        Self {
+            fee_reserve_eth: 0.0001,
+        }
+    }
+}
+
+impl Default for FeesConfig {
+    fn default() -> Self {
+        Self {
+            eth_usd: 3000.0,
+            max_priority_gwei: 0.001,
+            max_fee_gwei_cap: 0.0,
+            strategy: FeeStrategy::default(),
+        }
+    }
+}
+
+impl Default for SharingConfig {
+    fn default() -> Self {
+        Self {
+            strict: false,
+            recent_window_secs: 15 * 60,
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Constants
 // ---------------------------------------------------------------------------
 
 /// Name of the configuration file inside the config directory.
-const CONFIG_FILE: &str = "config.toml";
+pub const CONFIG_FILE: &str = "config.toml";
 
 /// Default directory name under the user home directory.
 const DEFAULT_DIR_NAME: &str = ".agentmarket";
@@ -108,25 +431,44 @@ const DEFAULT_DIR_NAME: &str = ".agentmarket";
 /// Unix permission mode for the config directory (owner-only rwx).
 const DIR_PERMISSIONS: u32 = 0o700;
 
+/// Directory given with the global `--home` flag.
+static HOME_OVERRIDE: Mutex<Option<PathBuf>> = Mutex::new(None);
+
 // ---------------------------------------------------------------------------
 // Public API
 // ---------------------------------------------------------------------------
 
+/// Set the configuration directory for this process (the global `--home`
+/// flag), or clear it with `None`. Takes precedence over `AGENTMARKET_HOME`
+/// without touching the process environment.
+pub fn set_home(path: Option<PathBuf>) {
+    *HOME_OVERRIDE.lock().unwrap_or_else(|e| e.into_inner()) = path;
+}
+
 /// Returns the configuration directory path.
 ///
 /// Resolution order:
-/// 1. `AGENTMARKET_HOME` environment variable (if set and non-empty).
-/// 2. `~/.agentmarket/` (using the `dirs` crate for home directory lookup).
+/// 1. The `--home` flag (see [`set_home`]).
+/// 2. `AGENTMARKET_HOME` environment variable (if set and non-empty).
+/// 3. `~/.agentmarket/` (using the `dirs` crate for home directory lookup).
 ///
 /// The directory is created with `0700` permissions if it does not already
 /// exist.
 pub fn config_dir() -> Result<PathBuf> {
-    let dir = match std::env::var("AGENTMARKET_HOME") {
-        Ok(val) if !val.is_empty() => {
+    let home_override = HOME_OVERRIDE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    let dir = match (home_override, std::env::var("AGENTMARKET_HOME")) {
+        (Some(path), _) => {
+            debug!(path = %path.display(), "using --home for config directory");
+            path
+        }
+        (None, Ok(val)) if !val.is_empty() => {
             debug!(path = %val, "using AGENTMARKET_HOME for config directory");
             PathBuf::from(val)
         }
-        _ => {
+        (None, _) => {
             let home = dirs::home_dir().context("unable to determine home directory")?;
             let path = home.join(DEFAULT_DIR_NAME);
             debug!(path = %path.display(), "using default config directory");
@@ -152,26 +494,61 @@ pub fn config_dir() -> Result<PathBuf> {
 /// After deserialisation the following environment variable overrides are
 /// applied (when the variable is set and non-empty):
 ///
-/// | Env var                    | Overrides               |
-/// |----------------------------|-------------------------|
-/// | `AGENTMARKET_RPC_URL`      | `network.chain_rpc`     |
-/// | `AGENTMARKET_IPFS_API`     | `network.ipfs_api`      |
-/// | `AGENTMARKET_IPFS_GATEWAY` | `network.ipfs_gateway`  |
+/// | Env var                           | Overrides                       |
+/// |-----------------------------------|---------------------------------|
+/// | `AGENTMARKET_RPC_URL`             | `network.chain_rpc`             |
+/// | `AGENTMARKET_IPFS_API`            | `network.ipfs_api`              |
+/// | `AGENTMARKET_IPFS_GATEWAY`        | `network.ipfs_gateway`          |
+/// | `AGENTMARKET_USDC`                | `contracts.usdc`                |
+/// | `AGENTMARKET_AGENT_REGISTRY`      | `contracts.agent_registry`      |
+/// | `AGENTMARKET_REQUEST_REGISTRY`    | `contracts.request_registry`    |
+/// | `AGENTMARKET_VALIDATION_REGISTRY` | `contracts.validation_registry` |
+///
+/// `AGENTMARKET_RPC_URL` may list several endpoints, comma-separated.
 pub fn load() -> Result<Config> {
+    let mut config = load_file()?;
+
+    // Apply environment variable overrides.
+    apply_env_overrides(&mut config);
+
+    debug!(?config, "config loaded");
+    Ok(config)
+}
+
+/// Loads `config.toml` exactly as written, without environment variable
+/// overrides. Use this when the config will be saved again, so overrides
+/// are not persisted.
+pub fn load_file() -> Result<Config> {
     let path = config_dir()?.join(CONFIG_FILE);
     debug!(path = %path.display(), "loading config");
 
     let contents = fs::read_to_string(&path)
         .with_context(|| format!("failed to read config file: {}", path.display()))?;
 
-    let mut config: Config = toml::from_str(&contents)
-        .with_context(|| format!("failed to parse config file: {}", path.display()))?;
-
-    // Apply environment variable overrides.
-    apply_env_overrides(&mut config);
+    toml::from_str(&contents)
+        .with_context(|| format!("failed to parse config file: {}", path.display()))
+}
 
-    debug!(?config, "config loaded");
-    Ok(config)
+/// Environment variables that override config keys, as `(variable, key)`.
+pub const ENV_OVERRIDES: &[(&str, &str)] = &[
+    ("AGENTMARKET_RPC_URL", "network.chain_rpc"),
+    ("AGENTMARKET_IPFS_API", "network.ipfs_api"),
+    ("AGENTMARKET_IPFS_GATEWAY", "network.ipfs_gateway"),
+    ("AGENTMARKET_USDC", "contracts.usdc"),
+    ("AGENTMARKET_AGENT_REGISTRY", "contracts.agent_registry"),
+    ("AGENTMARKET_REQUEST_REGISTRY", "contracts.request_registry"),
+    (
+        "AGENTMARKET_VALIDATION_REGISTRY",
+        "contracts.validation_registry",
+    ),
+];
+
+/// The environment variable currently overriding `key`, if any.
+pub fn active_env_override(key: &str) -> Option<&'static str> {
+    ENV_OVERRIDES
+        .iter()
+        .find(|(var, k)| *k == key && std::env::var(var).is_ok_and(|v| !v.is_empty()))
+        .map(|(var, _)| *var)
 }
 
 /// Serialises and writes the configuration to `config.toml`.
@@ -208,7 +585,15 @@ fn apply_env_overrides(config: &mut Config) {
     if let Ok(val) = std::env::var("AGENTMARKET_RPC_URL") {
         if !val.is_empty() {
             debug!(chain_rpc = %val, "overriding network.chain_rpc from AGENTMARKET_RPC_URL");
-            config.network.chain_rpc = val;
+            let urls: Vec<String> = val
+                .split(',')
+                .map(str::trim)
+                .filter(|url| !url.is_empty())
+                .map(str::to_string)
+                .collect();
+            if let Ok(endpoints) = RpcEndpoints::new(urls) {
+                config.network.chain_rpc = endpoints;
+            }
         }
     }
 
@@ -225,6 +610,27 @@ fn apply_env_overrides(config: &mut Config) {
             config.network.ipfs_gateway = val;
         }
     }
+
+    let contracts = &mut config.contracts;
+    for (var, field) in [
+        ("AGENTMARKET_USDC", &mut contracts.usdc),
+        ("AGENTMARKET_AGENT_REGISTRY", &mut contracts.agent_registry),
+        (
+            "AGENTMARKET_REQUEST_REGISTRY",
+            &mut contracts.request_registry,
+        ),
+        (
+            "AGENTMARKET_VALIDATION_REGISTRY",
+            &mut contracts.validation_registry,
+        ),
+    ] {
+        if let Ok(val) = std::env::var(var) {
+            if !val.is_empty() {
+                debug!(address = %val, "overriding contract address from {var}");
+                *field = val;
+            }
+        }
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -300,7 +706,10 @@ mod tests {
             assert_eq!(loaded.agent.name, "test-agent");
             assert_eq!(loaded.agent.description, "A test agent");
             assert_eq!(loaded.agent.version, "0.1.0");
-            assert_eq!(loaded.network.chain_rpc, "https://mainnet.base.org");
+            assert_eq!(
+                loaded.network.chain_rpc.primary(),
+                "https://mainnet.base.org"
+            );
             assert_eq!(loaded.network.ipfs_gateway, "https://gateway.pinata.cloud");
             assert_eq!(loaded.network.ipfs_api, "http://localhost:5001");
             assert_eq!(loaded.identity.public_key, "0xabc123");
@@ -317,19 +726,43 @@ mod tests {
             save(&Config::default()).expect("save failed");
 
             // Set env overrides.
-            env::set_var("AGENTMARKET_RPC_URL", "https://custom-rpc.example.com");
+            env::set_var(
+                "AGENTMARKET_RPC_URL",
+                "https://custom-rpc.example.com, https://fallback-rpc.example.com",
+            );
             env::set_var("AGENTMARKET_IPFS_API", "http://custom-ipfs:5001");
             env::set_var("AGENTMARKET_IPFS_GATEWAY", "https://custom-gw.example.com");
+            env::set_var(
+                "AGENTMARKET_REQUEST_REGISTRY",
+                "0x5FbDB2315678afecb367f032d93F642f64180aa3",
+            );
 
             let loaded = load().expect("load failed");
-            assert_eq!(loaded.network.chain_rpc, "https://custom-rpc.example.com");
+            assert_eq!(
+                loaded.network.chain_rpc.urls(),
+                [
+                    "https://custom-rpc.example.com",
+                    "https://fallback-rpc.example.com"
+                ]
+            );
             assert_eq!(loaded.network.ipfs_api, "http://custom-ipfs:5001");
             assert_eq!(loaded.network.ipfs_gateway, "https://custom-gw.example.com");
+            assert_eq!(
+                loaded.contracts.request_registry,
+                "0x5FbDB2315678afecb367f032d93F642f64180aa3"
+            );
+            assert_eq!(loaded.contracts.usdc, "");
+            assert_eq!(
+                active_env_override("contracts.request_registry"),
+                Some("AGENTMARKET_REQUEST_REGISTRY")
+            );
+            assert_eq!(load_file().unwrap().contracts.request_registry, "");
 
             // Clean up.
             env::remove_var("AGENTMARKET_RPC_URL");
             env::remove_var("AGENTMARKET_IPFS_API");
             env::remove_var("AGENTMARKET_IPFS_GATEWAY");
+            env::remove_var("AGENTMARKET_REQUEST_REGISTRY");
         });
     }
 
@@ -346,7 +779,7 @@ mod tests {
 
         assert_eq!(cfg.agent.name, "");
         assert_eq!(cfg.agent.version, "0.1.0");
-        assert_eq!(cfg.network.chain_rpc, "https://mainnet.base.org");
+        assert_eq!(cfg.network.chain_rpc.primary(), "https://mainnet.base.org");
         assert_eq!(cfg.network.ipfs_gateway, "https://gateway.pinata.cloud");
         assert_eq!(cfg.network.ipfs_api, "http://localhost:5001");
         assert_eq!(cfg.identity.agent_id, "");
@@ -354,5 +787,80 @@ mod tests {
         assert_eq!(cfg.identity.public_key, "");
         assert!(cfg.services.capabilities.is_empty());
         assert!((cfg.services.pricing_usd - 0.0).abs() < f64::EPSILON);
+        assert!(!cfg.sharing.strict);
+        assert_eq!(cfg.requests.claim_grace_secs, 3600);
+    }
+
+    #[test]
+    fn config_without_optional_sections_parses() {
+        // Config files written before `[sharing]` / `[requests]` existed.
+        let toml = r#"
+[agent]
+name = "old"
+description = ""
+version = "0.1.0"
+
+[network]
+chain_rpc = "https://mainnet.base.org"
+ipfs_gateway = "https://gateway.pinata.cloud"
+ipfs_api = "http://localhost:5001"
+
+[identity]
+agent_id = ""
+ipfs_profile_cid = ""
+public_key = ""
+
+[services]
+capabilities = []
+pricing_usd = 0.0
+"#;
+        let cfg: Config = toml::from_str(toml).expect("old config should parse");
+        assert_eq!(cfg.agent.name, "old");
+        assert_eq!(cfg.requests.claim_grace_secs, 3600);
+        assert_eq!(cfg.sharing.recent_window_secs, 900);
+        assert!(cfg.maintenance.every_secs.is_empty());
+    }
+
+    #[test]
+    fn chain_rpc_accepts_one_url_or_a_list() {
+        let mut cfg = Config::default();
+        let text = toml::to_string(&cfg).unwrap();
+        assert!(
+            text.contains("chain_rpc = \"https://mainnet.base.org\""),
+            "a single endpoint stays a plain string: {text}"
+        );
+
+        cfg.network.chain_rpc = RpcEndpoints::new(vec![
+            "https://primary.example".to_string(),
+            "https://fallback.example".to_string(),
+        ])
+        .unwrap();
+        let text = toml::to_string(&cfg).unwrap();
+        let parsed: Config = toml::from_str(&text).unwrap();
+        assert_eq!(parsed.network.chain_rpc, cfg.network.chain_rpc);
+        assert_eq!(
+            parsed.network.chain_rpc.primary(),
+            "https://primary.example"
+        );
+        assert_eq!(parsed.network.chain_rpc.urls().len(), 2);
+
+        let empty = text.replace(
+            "chain_rpc = [\"https://primary.example\", \"https://fallback.example\"]",
+            "chain_rpc = []",
+        );
+        assert_ne!(empty, text);
+        let err = toml::from_str::<Config>(&empty).unwrap_err().to_string();
+        assert!(err.contains("at least one URL"), "{err}");
+    }
+
+    #[test]
+    fn maintenance_table_parses_as_task_map() {
+        let mut cfg = Config::default();
+        cfg.maintenance.every_secs.insert("sweep".to_string(), 120);
+        let text = toml::to_string(&cfg).unwrap();
+        assert!(text.contains("[maintenance]\nsweep = 120"), "{text}");
+
+        let parsed: Config = toml::from_str(&text).unwrap();
+        assert_eq!(parsed.maintenance, cfg.maintenance);
     }
 }
@@ -0,0 +1,319 @@
+//! Directory archives for `--dir` attachments and deliverables.
+//!
+//! A directory is packed into a gzip-compressed tarball in memory. The
+//! archive is deterministic: entries are sorted by path and carry no
+//! timestamps, owners, or host-specific modes, so the same tree always
+//! produces the same bytes (and the same SHA-256 in the attachment
+//! manifest). Build and dependency directories in [`IGNORED_NAMES`] are
+//! left out and symlinks are skipped.
+
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use super::attachments::format_size;
+
+/// Directory entries never included in an archive.
+pub const IGNORED_NAMES: &[&str] = &[".git", "target", "node_modules"];
+
+/// Section marker in a deliverable that carries a message before the
+/// archive.
+pub const ARCHIVE_MARKER: &[u8] = b"--- ARCHIVE ---\n";
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// What an archive holds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveInfo {
+    pub file_count: u64,
+    /// Total size of the archived files before compression, in bytes.
+    pub uncompressed_size: u64,
+}
+
+/// A directory packed as a `.tar.gz`.
+#[derive(Debug)]
+pub struct DirArchive {
+    /// `<directory name>.tar.gz`.
+    pub name: String,
+    pub bytes: Vec<u8>,
+    pub info: ArchiveInfo,
+    /// Symlinks that were left out, relative to the directory.
+    pub skipped_symlinks: Vec<PathBuf>,
+}
+
+// ---------------------------------------------------------------------------
+// Packing
+// ---------------------------------------------------------------------------
+
+/// Pack the directory at `dir`. Fails before compressing anything if the
+/// files add up to more than `max_bytes`.
+pub fn archive_dir(dir: &Path, max_bytes: u64) -> Result<DirArchive> {
+    let meta = fs::metadata(dir)
+        .with_context(|| format!("failed to read directory: {}", dir.display()))?;
+    if !meta.is_dir() {
+        bail!("{} is not a directory.", dir.display());
+    }
+    let root = dir
+        .canonicalize()
+        .with_context(|| format!("failed to resolve directory: {}", dir.display()))?;
+    let name = root
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "archive".to_string());
+
+    let mut files = Vec::new();
+    let mut skipped_symlinks = Vec::new();
+    collect_files(&root, Path::new(""), &mut files, &mut skipped_symlinks)?;
+
+    let uncompressed_size = files.iter().map(|(_, size, _)| size).sum();
+    if uncompressed_size > max_bytes {
+        bail!(
+            "{} holds {} of files but the limit is {}. Remove large files from it, \
+             or raise the limit with `agentmarket config set requests.max_attachment_bytes <BYTES>`.",
+            dir.display(),
+            format_size(uncompressed_size),
+            format_size(max_bytes)
+        );
+    }
+
+    let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+    for (relative, _, executable) in &files {
+        let contents = fs::read(root.join(relative))
+            .with_context(|| format!("failed to read {}", relative.display()))?;
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(if *executable { 0o755 } else { 0o644 });
+        header.set_mtime(0);
+        header.set_uid(0);
+        header.set_gid(0);
+        builder
+            .append_data(
+                &mut header,
+                Path::new(&name).join(relative),
+                contents.as_slice(),
+            )
+            .with_context(|| format!("failed to archive {}", relative.display()))?;
+    }
+    let bytes = builder
+        .into_inner()
+        .and_then(|gz| gz.finish())
+        .context("failed to finish archive")?;
+
+    debug!(
+        dir = %root.display(),
+        files = files.len(),
+        uncompressed_size,
+        compressed = bytes.len(),
+        "directory archived"
+    );
+    Ok(DirArchive {
+        name: format!("{name}.tar.gz"),
+        bytes,
+        info: ArchiveInfo {
+            file_count: files.len() as u64,
+            uncompressed_size,
+        },
+        skipped_symlinks,
+    })
+}
+
+/// Regular files under `root/relative`, as (path relative to `root`, size,
+/// executable), sorted by path.
+fn collect_files(
+    root: &Path,
+    relative: &Path,
+    files: &mut Vec<(PathBuf, u64, bool)>,
+    skipped_symlinks: &mut Vec<PathBuf>,
+) -> Result<()> {
+    let dir = root.join(relative);
+    let mut entries = fs::read_dir(&dir)
+        .with_context(|| format!("failed to read directory: {}", dir.display()))?
+        .collect::<std::io::Result<Vec<_>>>()
+        .with_context(|| format!("failed to read directory: {}", dir.display()))?;
+    entries.sort_by_key(|e| e.file_name());
+
+    for entry in entries {
+        let file_name = entry.file_name();
+        if IGNORED_NAMES.iter().any(|ignored| file_name == *ignored) {
+            continue;
+        }
+        let path = relative.join(&file_name);
+        let meta = entry
+            .metadata()
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        if meta.file_type().is_symlink() {
+            skipped_symlinks.push(path);
+        } else if meta.is_dir() {
+            collect_files(root, &path, files, skipped_symlinks)?;
+        } else if meta.is_file() {
+            files.push((path, meta.len(), is_executable(&meta)));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn is_executable(meta: &fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    meta.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+fn is_executable(_meta: &fs::Metadata) -> bool {
+    false
+}
+
+// ---------------------------------------------------------------------------
+// Unpacking
+// ---------------------------------------------------------------------------
+
+/// Whether `bytes` is a gzip-compressed tarball.
+pub fn is_archive(bytes: &[u8]) -> bool {
+    if !bytes.starts_with(&GZIP_MAGIC) {
+        return false;
+    }
+    let mut header = [0u8; 512];
+    GzDecoder::new(bytes).read_exact(&mut header).is_ok() && &header[257..262] == b"ustar"
+}
+
+/// The archive in a deliverable: the whole payload, or what follows an
+/// [`ARCHIVE_MARKER`] section.
+pub fn locate(payload: &[u8]) -> Option<&[u8]> {
+    if is_archive(payload) {
+        return Some(payload);
+    }
+    let at = payload
+        .windows(ARCHIVE_MARKER.len())
+        .position(|w| w == ARCHIVE_MARKER)?;
+    let archive = &payload[at + ARCHIVE_MARKER.len()..];
+    is_archive(archive).then_some(archive)
+}
+
+/// Extract `archive` into a new private directory under the system temp
+/// directory and return its path. Entries that would land outside it are
+/// refused by the tar reader.
+pub fn extract_to_temp(archive: &[u8], label: &str) -> Result<PathBuf> {
+    let mut suffix = [0u8; 4];
+    rand::thread_rng().fill_bytes(&mut suffix);
+    let dest = std::env::temp_dir().join(format!("agentmarket-{label}-{}", hex::encode(suffix)));
+
+    let mut builder = fs::DirBuilder::new();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder
+        .create(&dest)
+        .with_context(|| format!("failed to create {}", dest.display()))?;
+
+    tar::Archive::new(GzDecoder::new(archive))
+        .unpack(&dest)
+        .with_context(|| format!("failed to extract archive into {}", dest.display()))?;
+    debug!(dest = %dest.display(), "archive extracted");
+    Ok(dest)
+}
+
+/// Write a deliverable: the archive alone, or `message` followed by the
+/// archive under an [`ARCHIVE_MARKER`].
+pub fn write_deliverable(out: &mut Vec<u8>, archive: &[u8]) {
+    if !out.is_empty() {
+        out.extend_from_slice(ARCHIVE_MARKER);
+    }
+    out.extend_from_slice(archive);
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project() -> tempfile::TempDir {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().join("project");
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("target/debug")).unwrap();
+        fs::create_dir_all(root.join(".git")).unwrap();
+        fs::write(root.join("README.md"), "# demo\n").unwrap();
+        fs::write(root.join("src/main.rs"), "fn main() {}\n").unwrap();
+        fs::write(root.join("target/debug/big"), vec![0u8; 4096]).unwrap();
+        fs::write(root.join(".git/HEAD"), "ref: refs/heads/main\n").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(root.join("README.md"), root.join("link.md")).unwrap();
+        tmp
+    }
+
+    #[test]
+    fn test_archive_dir_is_deterministic_and_skips_ignored() {
+        let tmp = project();
+        let dir = tmp.path().join("project");
+
+        let first = archive_dir(&dir, 1024).unwrap();
+        assert_eq!(first.name, "project.tar.gz");
+        assert_eq!(
+            first.info,
+            ArchiveInfo {
+                file_count: 2,
+                uncompressed_size: 20
+            }
+        );
+        #[cfg(unix)]
+        assert_eq!(first.skipped_symlinks, [PathBuf::from("link.md")]);
+        assert!(is_archive(&first.bytes));
+
+        let second = archive_dir(&dir, 1024).unwrap();
+        assert_eq!(first.bytes, second.bytes);
+
+        let out = extract_to_temp(&first.bytes, "test").unwrap();
+        assert_eq!(
+            fs::read_to_string(out.join("project/src/main.rs")).unwrap(),
+            "fn main() {}\n"
+        );
+        assert!(!out.join("project/target").exists());
+        assert!(!out.join("project/link.md").exists());
+        fs::remove_dir_all(out).unwrap();
+    }
+
+    #[test]
+    fn test_archive_dir_enforces_limit_before_compressing() {
+        let tmp = project();
+        let err = archive_dir(&tmp.path().join("project"), 19)
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("holds 20 B of files but the limit is 19 B"),
+            "{err}"
+        );
+        assert!(archive_dir(&tmp.path().join("project/README.md"), 1024).is_err());
+    }
+
+    #[test]
+    fn test_locate_archive_in_deliverable() {
+        let tmp = project();
+        let archive = archive_dir(&tmp.path().join("project"), 1024)
+            .unwrap()
+            .bytes;
+
+        let mut alone = Vec::new();
+        write_deliverable(&mut alone, &archive);
+        assert_eq!(locate(&alone), Some(archive.as_slice()));
+
+        let mut with_message = b"--- MESSAGE ---\nDone\n".to_vec();
+        write_deliverable(&mut with_message, &archive);
+        assert_eq!(locate(&with_message), Some(archive.as_slice()));
+
+        assert_eq!(locate(b"--- FILE ---\nplain text"), None);
+        assert!(!is_archive(&[0x1f, 0x8b, 0, 0]));
+    }
+}
@@ -0,0 +1,327 @@
+//! Files attached to a service request.
+//!
+//! Attachments travel inside the encrypted request payload under
+//! `"attachments"`: a manifest giving each file's name, size, and SHA-256,
+//! and the files' contents concatenated in manifest order as base64. The
+//! seller splits the contents by the manifest sizes and checks each hash.
+//!
+//! Requests created before multiple attachments were supported carry a
+//! single text file as a plain `"attachment"` string; it is read back as
+//! one file named [`LEGACY_ATTACHMENT_NAME`].
+//!
+//! A directory attached with `--dir` is one `.tar.gz` file whose manifest
+//! entry also records how many files it holds and their size (see
+//! [`crate::engine::archive`]).
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::debug;
+
+use super::archive::{ArchiveInfo, DirArchive};
+
+/// Default limit on the combined size of a request's attachments, in bytes.
+pub const DEFAULT_MAX_ATTACHMENT_BYTES: u64 = 5 * 1024 * 1024;
+
+/// Name given to the single attachment of an older request payload.
+pub const LEGACY_ATTACHMENT_NAME: &str = "attachment";
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+/// One file as listed in the manifest.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// File name without its directory.
+    pub name: String,
+    pub size: u64,
+    /// Hex-encoded SHA-256 of the contents.
+    pub sha256: String,
+    /// Set for a directory packed with `--dir`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archive: Option<ArchiveInfo>,
+}
+
+/// The `"attachments"` value of a request payload.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bundle {
+    pub manifest: Vec<ManifestEntry>,
+    /// Base64 of every file's contents, in manifest order.
+    pub data: String,
+}
+
+/// A file and its contents.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Attachment {
+    pub name: String,
+    pub content: Vec<u8>,
+    pub archive: Option<ArchiveInfo>,
+}
+
+impl Attachment {
+    /// A directory packed with [`crate::engine::archive::archive_dir`].
+    pub fn from_archive(archive: DirArchive) -> Self {
+        Self {
+            name: archive.name,
+            content: archive.bytes,
+            archive: Some(archive.info),
+        }
+    }
+
+    /// The manifest entry describing this file.
+    pub fn entry(&self) -> ManifestEntry {
+        ManifestEntry {
+            name: self.name.clone(),
+            size: self.content.len() as u64,
+            sha256: hex::encode(Sha256::digest(&self.content)),
+            archive: self.archive,
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Packing
+// ---------------------------------------------------------------------------
+
+/// Read the files at `paths`, refusing before anything is read if together
+/// they exceed `max_total_bytes`. File names must be distinct, since they
+/// are all the seller sees.
+pub fn read_files(paths: &[String], max_total_bytes: u64) -> Result<Vec<Attachment>> {
+    let mut names = Vec::with_capacity(paths.len());
+    let mut total: u64 = 0;
+    for path in paths {
+        let meta = fs::metadata(path)
+            .with_context(|| format!("failed to read attachment file: {path}"))?;
+        if !meta.is_file() {
+            bail!("Attachment {path} is not a regular file.");
+        }
+        let name = Path::new(path)
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .with_context(|| format!("attachment path {path} has no file name"))?;
+        if names.contains(&name) {
+            bail!(
+                "Two attachments are named {name}. Rename one so the seller can tell them apart."
+            );
+        }
+        names.push(name);
+        total = total.saturating_add(meta.len());
+    }
+    check_total_size(total, max_total_bytes)?;
+
+    let mut files = Vec::with_capacity(paths.len());
+    for (path, name) in paths.iter().zip(names) {
+        let content =
+            fs::read(path).with_context(|| format!("failed to read attachment file: {path}"))?;
+        debug!(path = %path, size = content.len(), "attachment file loaded");
+        files.push(Attachment {
+            name,
+            content,
+            archive: None,
+        });
+    }
+    // Files can grow between the size check and the read.
+    check_total_size(
+        files.iter().map(|f| f.content.len() as u64).sum(),
+        max_total_bytes,
+    )?;
+    Ok(files)
+}
+
+fn check_total_size(total: u64, max_total_bytes: u64) -> Result<()> {
+    if total > max_total_bytes {
+        bail!(
+            "Attachments total {} but the limit is {}. Send fewer or smaller files, \
+             or raise it with `agentmarket config set requests.max_attachment_bytes <BYTES>`.",
+            format_size(total),
+            format_size(max_total_bytes)
+        );
+    }
+    Ok(())
+}
+
+/// Bundle `files` for the request payload.
+pub fn bundle(files: &[Attachment]) -> Bundle {
+    let mut data = Vec::with_capacity(files.iter().map(|f| f.content.len()).sum());
+    for file in files {
+        data.extend_from_slice(&file.content);
+    }
+    Bundle {
+        manifest: files.iter().map(Attachment::entry).collect(),
+        data: STANDARD.encode(data),
+    }
+}
+
+/// Split a bundle back into its files, checking every size and hash.
+pub fn unpack(bundle: &Bundle) -> Result<Vec<Attachment>> {
+    let data = STANDARD
+        .decode(&bundle.data)
+        .context("attachment data is not valid base64")?;
+    let listed = bundle
+        .manifest
+        .iter()
+        .try_fold(0u64, |total, e| total.checked_add(e.size));
+    if listed != Some(data.len() as u64) {
+        bail!(
+            "Attachment manifest lists {} bytes but {} were sent.",
+            listed.map_or("too many".to_string(), |n| n.to_string()),
+            data.len()
+        );
+    }
+
+    let mut files = Vec::with_capacity(bundle.manifest.len());
+    let mut offset = 0;
+    for entry in &bundle.manifest {
+        let end = offset + entry.size as usize;
+        let file = Attachment {
+            name: entry.name.clone(),
+            content: data[offset..end].to_vec(),
+            archive: entry.archive,
+        };
+        if file.entry().sha256 != entry.sha256.to_lowercase() {
+            bail!("Attachment {} does not match its SHA-256.", entry.name);
+        }
+        files.push(file);
+        offset = end;
+    }
+    Ok(files)
+}
+
+/// The attachments of a decrypted request payload: the bundle if there is
+/// one, the legacy single attachment, or none.
+pub fn from_request_payload(payload: &serde_json::Value) -> Result<Vec<Attachment>> {
+    if let Some(value) = payload.get("attachments") {
+        let bundle: Bundle = serde_json::from_value(value.clone())
+            .context("request payload has a malformed attachment manifest")?;
+        return unpack(&bundle);
+    }
+    Ok(payload
+        .get("attachment")
+        .and_then(|v| v.as_str())
+        .map(|text| {
+            vec![Attachment {
+                name: LEGACY_ATTACHMENT_NAME.to_string(),
+                content: text.as_bytes().to_vec(),
+                archive: None,
+            }]
+        })
+        .unwrap_or_default())
+}
+
+/// Render a byte count for people, e.g. `"512 B"`, `"1.5 KiB"`, `"5.0 MiB"`.
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 3] = ["KiB", "MiB", "GiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(name: &str, content: &[u8]) -> Attachment {
+        Attachment {
+            name: name.to_string(),
+            content: content.to_vec(),
+            archive: None,
+        }
+    }
+
+    #[test]
+    fn test_bundle_round_trip() {
+        let files = vec![
+            file("spec.md", b"# Summarise\n"),
+            file("empty.txt", b""),
+            file("sample.bin", &[0, 159, 146, 150]),
+        ];
+        let packed = bundle(&files);
+        assert_eq!(
+            packed.manifest.iter().map(|e| e.size).collect::<Vec<_>>(),
+            [12, 0, 4]
+        );
+        assert_eq!(
+            packed.manifest[1].sha256,
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+
+        let json = serde_json::json!({ "task": "t", "attachments": packed });
+        assert_eq!(from_request_payload(&json).unwrap(), files);
+    }
+
+    #[test]
+    fn test_unpack_rejects_tampered_bundles() {
+        let mut packed = bundle(&[file("a.txt", b"hello"), file("b.txt", b"world")]);
+        packed.manifest[0].sha256 = packed.manifest[1].sha256.clone();
+        let err = unpack(&packed).unwrap_err().to_string();
+        assert!(err.contains("a.txt does not match"), "{err}");
+
+        let mut packed = bundle(&[file("a.txt", b"hello")]);
+        packed.manifest[0].size = 6;
+        let err = unpack(&packed).unwrap_err().to_string();
+        assert!(err.contains("lists 6 bytes but 5"), "{err}");
+    }
+
+    #[test]
+    fn test_legacy_and_missing_attachments() {
+        let legacy = serde_json::json!({ "task": "t", "attachment": "notes" });
+        assert_eq!(
+            from_request_payload(&legacy).unwrap(),
+            [file(LEGACY_ATTACHMENT_NAME, b"notes")]
+        );
+        let none = serde_json::json!({ "task": "t" });
+        assert!(from_request_payload(&none).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_read_files_enforces_limit_and_unique_names() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = |name: &str| tmp.path().join(name).to_string_lossy().into_owned();
+        fs::write(path("spec.md"), b"0123456789").unwrap();
+        fs::write(path("data.csv"), b"a,b\n").unwrap();
+        fs::create_dir(tmp.path().join("sub")).unwrap();
+        fs::write(tmp.path().join("sub/spec.md"), b"x").unwrap();
+
+        let files = read_files(&[path("spec.md"), path("data.csv")], 14).unwrap();
+        assert_eq!(files[0].name, "spec.md");
+        assert_eq!(files[1].content, b"a,b\n");
+
+        let err = read_files(&[path("spec.md"), path("data.csv")], 13)
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("Attachments total 14 B but the limit is 13 B"),
+            "{err}"
+        );
+
+        let err = read_files(&[path("spec.md"), path("sub/spec.md")], 100)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Two attachments are named spec.md"), "{err}");
+        assert!(read_files(&[path("sub")], 100).is_err());
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(DEFAULT_MAX_ATTACHMENT_BYTES), "5.0 MiB");
+    }
+}
@@ -0,0 +1,218 @@
+//! Retry backoff for the daemon's external dependencies.
+//!
+//! When the chain RPC or the IPFS node is down, the daemon should not retry
+//! it every cycle. Each dependency gets its own [`Backoff`]: consecutive
+//! failures double the wait (`base`, 2×, 4×, ... capped at [`MAX_DELAY`]),
+//! with a little jitter so restarted daemons do not retry in lockstep, and
+//! the first success resets it. Tracking dependencies separately means an
+//! IPFS outage never delays work that only needs the chain.
+
+use std::time::{Duration, Instant};
+
+// ---------------------------------------------------------------------------
+// Constants
+// ---------------------------------------------------------------------------
+
+/// Longest wait between retries of a failing dependency.
+pub const MAX_DELAY: Duration = Duration::from_secs(15 * 60);
+
+/// Jitter spreads each delay over ±this fraction of itself.
+const JITTER_FRACTION: f64 = 0.1;
+
+// ---------------------------------------------------------------------------
+// Delay math
+// ---------------------------------------------------------------------------
+
+/// Delay before the next attempt after `failures` consecutive failures:
+/// `base` for none or one, then doubling per failure, capped at
+/// [`MAX_DELAY`].
+pub fn next_delay(failures: u32, base: Duration) -> Duration {
+    let factor = 1u32
+        .checked_shl(failures.saturating_sub(1))
+        .unwrap_or(u32::MAX);
+    base.checked_mul(factor).unwrap_or(MAX_DELAY).min(MAX_DELAY)
+}
+
+/// Spread `delay` by up to ±10%; `unit` is a random draw from `[0, 1)`.
+pub fn jitter(delay: Duration, unit: f64) -> Duration {
+    let unit = unit.clamp(0.0, 1.0);
+    delay.mul_f64(1.0 - JITTER_FRACTION + 2.0 * JITTER_FRACTION * unit)
+}
+
+// ---------------------------------------------------------------------------
+// Per-dependency state
+// ---------------------------------------------------------------------------
+
+/// What a recorded outcome changed, so callers can log only transitions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Transition {
+    /// Nothing worth reporting: still healthy, or still failing at the same
+    /// (capped) level.
+    Unchanged,
+    /// The dependency failed and the backoff level moved to this delay.
+    Degraded { failures: u32, delay: Duration },
+    /// The dependency succeeded after this many consecutive failures.
+    Recovered { failures: u32 },
+}
+
+/// Consecutive-failure count and retry time for one dependency.
+#[derive(Clone, Debug, Default)]
+pub struct Backoff {
+    failures: u32,
+    retry_at: Option<Instant>,
+}
+
+impl Backoff {
+    /// Consecutive failures since the last success.
+    pub fn failures(&self) -> u32 {
+        self.failures
+    }
+
+    /// Whether the dependency may be tried at `now`.
+    pub fn ready(&self, now: Instant) -> bool {
+        self.retry_at.map_or(true, |at| now >= at)
+    }
+
+    /// How long from `now` until the dependency may be tried again; zero
+    /// once it is ready.
+    pub fn wait(&self, now: Instant) -> Duration {
+        self.retry_at
+            .map_or(Duration::ZERO, |at| at.saturating_duration_since(now))
+    }
+
+    /// Record a success, resetting the backoff.
+    pub fn record_success(&mut self) -> Transition {
+        let failures = std::mem::take(&mut self.failures);
+        self.retry_at = None;
+        if failures > 0 {
+            Transition::Recovered { failures }
+        } else {
+            Transition::Unchanged
+        }
+    }
+
+    /// Record a failure at `now` and schedule the next attempt, jittered by
+    /// `unit` (a random draw from `[0, 1)`).
+    pub fn record_failure(&mut self, now: Instant, base: Duration, unit: f64) -> Transition {
+        let previous = (self.failures > 0).then(|| next_delay(self.failures, base));
+        self.failures = self.failures.saturating_add(1);
+        let delay = next_delay(self.failures, base);
+        self.retry_at = Some(now + jitter(delay, unit));
+
+        if previous == Some(delay) {
+            Transition::Unchanged
+        } else {
+            Transition::Degraded {
+                failures: self.failures,
+                delay,
+            }
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    // -- next_delay -----------------------------------------------------------
+
+    #[test]
+    fn test_next_delay_doubles_per_failure() {
+        assert_eq!(next_delay(0, MINUTE), MINUTE);
+        assert_eq!(next_delay(1, MINUTE), MINUTE);
+        assert_eq!(next_delay(2, MINUTE), 2 * MINUTE);
+        assert_eq!(next_delay(3, MINUTE), 4 * MINUTE);
+        assert_eq!(next_delay(4, MINUTE), 8 * MINUTE);
+    }
+
+    #[test]
+    fn test_next_delay_is_capped() {
+        assert_eq!(next_delay(5, MINUTE), MAX_DELAY);
+        assert_eq!(next_delay(32, MINUTE), MAX_DELAY);
+        assert_eq!(next_delay(u32::MAX, MINUTE), MAX_DELAY);
+        assert_eq!(next_delay(0, 20 * MINUTE), MAX_DELAY);
+    }
+
+    // -- jitter ---------------------------------------------------------------
+
+    #[test]
+    fn test_jitter_stays_within_ten_percent() {
+        let delay = Duration::from_secs(100);
+        assert_eq!(jitter(delay, 0.0), Duration::from_secs(90));
+        assert_eq!(jitter(delay, 0.5), delay);
+        assert_eq!(jitter(delay, 1.0), Duration::from_secs(110));
+        assert_eq!(jitter(delay, 7.0), Duration::from_secs(110));
+    }
+
+    // -- Backoff --------------------------------------------------------------
+
+    #[test]
+    fn test_backoff_reports_only_level_changes() {
+        let base = 4 * MINUTE;
+        let now = Instant::now();
+        let mut backoff = Backoff::default();
+        assert!(backoff.ready(now));
+
+        let levels: Vec<Transition> = (0..5)
+            .map(|_| backoff.record_failure(now, base, 0.5))
+            .collect();
+        assert_eq!(
+            levels,
+            vec![
+                Transition::Degraded {
+                    failures: 1,
+                    delay: base
+                },
+                Transition::Degraded {
+                    failures: 2,
+                    delay: 2 * base
+                },
+                Transition::Degraded {
+                    failures: 3,
+                    delay: MAX_DELAY
+                },
+                Transition::Unchanged,
+                Transition::Unchanged,
+            ]
+        );
+        assert_eq!(backoff.failures(), 5);
+    }
+
+    #[test]
+    fn test_backoff_waits_then_resets_on_success() {
+        let now = Instant::now();
+        let mut backoff = Backoff::default();
+        backoff.record_failure(now, MINUTE, 0.5);
+        assert!(!backoff.ready(now));
+        assert!(!backoff.ready(now + MINUTE / 2));
+        assert!(backoff.ready(now + 2 * MINUTE));
+        assert_eq!(backoff.wait(now), MINUTE);
+        assert_eq!(backoff.wait(now + MINUTE / 2), MINUTE / 2);
+        assert_eq!(backoff.wait(now + 2 * MINUTE), Duration::ZERO);
+
+        assert_eq!(
+            backoff.record_success(),
+            Transition::Recovered { failures: 1 }
+        );
+        assert_eq!(backoff.failures(), 0);
+        assert!(backoff.ready(now));
+        assert_eq!(backoff.wait(now), Duration::ZERO);
+        assert_eq!(backoff.record_success(), Transition::Unchanged);
+    }
+
+    #[test]
+    fn test_backoffs_are_independent() {
+        let now = Instant::now();
+        let mut chain = Backoff::default();
+        let ipfs = Backoff::default();
+        chain.record_failure(now, MINUTE, 0.5);
+        assert!(!chain.ready(now));
+        assert!(ipfs.ready(now));
+    }
+}
@@ -0,0 +1,530 @@
+//! Progress the daemon carries across restarts.
+//!
+//! Each cycle the daemon saves `~/.agentmarket/daemon_state.json`: the last
+//! chain block it processed (where the next event scan starts), the request
+//! IDs it already validated or claimed this epoch, and its running counters.
+//! On startup the file is loaded so a restart resumes instead of re-checking
+//! everything. An epoch is one UTC day; the handled set is cleared when a
+//! new one starts so the file stays small.
+//!
+//! Failed auto-claims are queued here too (see [`ClaimRetry`]) and retried
+//! with the same doubling backoff as failing dependencies. After
+//! [`MAX_CLAIM_ATTEMPTS`] failures a claim is left for the user and reported
+//! as needing manual attention. Claims deferred because network fees are
+//! above the configured cap wait in the same queue without using up
+//! attempts.
+//!
+//! The last block is the cursor of the daemon's event scan. Alongside it
+//! are the hashes of recently processed blocks and the local status changes
+//! made from their events, so that when a reorg replaces one of those
+//! blocks the cursor can be rewound and the changes undone (see
+//! [`crate::engine::event_sync`]). Both are kept for [`REORG_HORIZON`]
+//! blocks.
+//!
+//! A missing or unreadable file is never fatal: the caller falls back to a
+//! clean scan.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use alloy::primitives::B256;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::config::store::config_dir;
+use crate::engine::backoff;
+use crate::engine::requests::LocalRequestStatus;
+
+// ---------------------------------------------------------------------------
+// Constants
+// ---------------------------------------------------------------------------
+
+/// Name of the state file inside the config directory.
+pub const STATE_FILE: &str = "daemon_state.json";
+
+/// Length of an epoch, in seconds.
+const EPOCH_SECS: u64 = 24 * 60 * 60;
+
+/// Failed attempts after which a claim is no longer retried automatically.
+pub const MAX_CLAIM_ATTEMPTS: u32 = 5;
+
+/// Blocks behind the cursor whose hashes and applied events are kept. A
+/// reorg deeper than this is not detected.
+pub const REORG_HORIZON: u64 = 128;
+
+// ---------------------------------------------------------------------------
+// State
+// ---------------------------------------------------------------------------
+
+/// What the daemon has already done.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DaemonProgress {
+    /// Last chain block processed, once the chain has been reached.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_block: Option<u64>,
+    /// Epoch the `handled` set belongs to (days since the Unix epoch).
+    #[serde(default)]
+    pub epoch: u64,
+    /// Request IDs validated or claimed during `epoch`.
+    #[serde(default)]
+    pub handled: BTreeSet<String>,
+    /// Cycles run, across restarts.
+    #[serde(default)]
+    pub cycles: u64,
+    /// Validations done, across restarts.
+    #[serde(default)]
+    pub validations: u64,
+    /// Claims made, across restarts.
+    #[serde(default)]
+    pub claims: u64,
+    /// Failed auto-claims, by request ID.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub claim_retries: BTreeMap<String, ClaimRetry>,
+    /// Hashes of recently processed blocks, by number: the cursor block of
+    /// each scan and every block whose events were applied.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub block_hashes: BTreeMap<u64, B256>,
+    /// Local status changes made from chain events, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub applied_events: Vec<AppliedEvent>,
+}
+
+/// A local status change made from a chain event, kept while the event's
+/// block could still be reorged away.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppliedEvent {
+    pub block_number: u64,
+    pub request_id: String,
+    pub from: LocalRequestStatus,
+    pub to: LocalRequestStatus,
+}
+
+/// A failed auto-claim waiting to be retried.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClaimRetry {
+    /// Failed attempts so far.
+    pub attempts: u32,
+    /// Error from the latest attempt.
+    pub last_error: String,
+    /// Unix timestamp after which the claim may be tried again.
+    pub next_retry_at: u64,
+}
+
+impl ClaimRetry {
+    /// Whether the claim has failed too often to be retried automatically.
+    pub fn needs_attention(&self) -> bool {
+        self.attempts >= MAX_CLAIM_ATTEMPTS
+    }
+}
+
+impl DaemonProgress {
+    /// Epoch containing the Unix timestamp `now`.
+    pub fn epoch_at(now: u64) -> u64 {
+        now / EPOCH_SECS
+    }
+
+    /// Move to the epoch containing `now`, forgetting the requests handled in
+    /// an earlier one.
+    pub fn roll_epoch(&mut self, now: u64) {
+        let epoch = Self::epoch_at(now);
+        if epoch != self.epoch {
+            debug!(from = self.epoch, to = epoch, "daemon epoch changed");
+            self.epoch = epoch;
+            self.handled.clear();
+        }
+    }
+
+    /// Whether `request_id` was already validated or claimed this epoch.
+    pub fn is_handled(&self, request_id: &str) -> bool {
+        self.handled.contains(request_id)
+    }
+
+    /// Record that `request_id` was validated or claimed.
+    pub fn mark_handled(&mut self, request_id: &str) {
+        self.handled.insert(request_id.to_string());
+    }
+
+    /// Whether an auto-claim of `request_id` may be attempted at `now`:
+    /// it has not failed, or its retry time has come and it has attempts
+    /// left.
+    pub fn claim_due(&self, request_id: &str, now: u64) -> bool {
+        self.claim_retries.get(request_id).map_or(true, |retry| {
+            !retry.needs_attention() && retry.next_retry_at <= now
+        })
+    }
+
+    /// Record a failed auto-claim of `request_id` at `now`, scheduling the
+    /// next attempt `base`, 2×, 4×, ... later.
+    pub fn record_claim_failure(
+        &mut self,
+        request_id: &str,
+        error: &str,
+        now: u64,
+        base: Duration,
+    ) -> &ClaimRetry {
+        let retry = self
+            .claim_retries
+            .entry(request_id.to_string())
+            .or_default();
+        retry.attempts += 1;
+        retry.last_error = error.to_string();
+        retry.next_retry_at =
+            now.saturating_add(backoff::next_delay(retry.attempts, base).as_secs());
+        retry
+    }
+
+    /// Put off claiming `request_id` until `base` after `now` because of
+    /// `reason` (fees over the cap, say). Unlike a failure this does not
+    /// count as an attempt, so a claim can be deferred indefinitely.
+    pub fn defer_claim(&mut self, request_id: &str, reason: &str, now: u64, base: Duration) {
+        let retry = self
+            .claim_retries
+            .entry(request_id.to_string())
+            .or_default();
+        retry.last_error = reason.to_string();
+        retry.next_retry_at = now.saturating_add(base.as_secs());
+    }
+
+    /// Forget any failed attempts at claiming `request_id`, once it has been
+    /// claimed or no longer needs claiming.
+    pub fn clear_claim_retry(&mut self, request_id: &str) {
+        self.claim_retries.remove(request_id);
+    }
+
+    /// Request IDs whose claims need manual attention.
+    pub fn claims_needing_attention(&self) -> Vec<String> {
+        self.claim_retries
+            .iter()
+            .filter(|(_, retry)| retry.needs_attention())
+            .map(|(request_id, _)| request_id.clone())
+            .collect()
+    }
+
+    /// Record the chain block reached, never moving backwards.
+    pub fn advance_block(&mut self, block: u64) {
+        self.last_block = Some(self.last_block.map_or(block, |last| last.max(block)));
+    }
+
+    /// Remember that block `number` had hash `hash` when it was processed.
+    pub fn record_block_hash(&mut self, number: u64, hash: B256) {
+        self.block_hashes.insert(number, hash);
+    }
+
+    /// Where the cursor must go back to, given the hashes the chain now has
+    /// for the recorded blocks (`None` for a block that no longer exists),
+    /// or `None` if every recorded block is still on the chain.
+    ///
+    /// The cursor goes back to the newest recorded block below the first
+    /// replaced one that is still on the chain, or to the block just before
+    /// the replaced one when none is.
+    pub fn fork_cursor(&self, canonical: &BTreeMap<u64, Option<B256>>) -> Option<u64> {
+        let replaced = |number: &u64, hash: &B256| {
+            canonical
+                .get(number)
+                .is_some_and(|now| now.as_ref() != Some(hash))
+        };
+        let (&fork, _) = self
+            .block_hashes
+            .iter()
+            .find(|(number, hash)| replaced(number, hash))?;
+        let kept = self
+            .block_hashes
+            .range(..fork)
+            .next_back()
+            .map(|(&number, _)| number);
+        Some(kept.unwrap_or(fork.saturating_sub(1)))
+    }
+
+    /// Move the cursor back to `cursor`, forgetting the blocks after it.
+    /// Returns the status changes made from events in those blocks, newest
+    /// first, for the caller to undo.
+    pub fn rewind(&mut self, cursor: u64) -> Vec<AppliedEvent> {
+        debug!(from = ?self.last_block, to = cursor, "rewinding event cursor");
+        self.last_block = Some(self.last_block.map_or(cursor, |last| last.min(cursor)));
+        self.block_hashes.retain(|&number, _| number <= cursor);
+        let (kept, mut undone): (Vec<_>, Vec<_>) = std::mem::take(&mut self.applied_events)
+            .into_iter()
+            .partition(|applied| applied.block_number <= cursor);
+        self.applied_events = kept;
+        undone.reverse();
+        undone
+    }
+
+    /// Forget block hashes and applied events more than [`REORG_HORIZON`]
+    /// blocks behind the cursor.
+    pub fn prune_reorg_history(&mut self) {
+        let Some(cursor) = self.last_block else {
+            return;
+        };
+        let oldest = cursor.saturating_sub(REORG_HORIZON);
+        self.block_hashes.retain(|&number, _| number >= oldest);
+        self.applied_events
+            .retain(|applied| applied.block_number >= oldest);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Persistence
+// ---------------------------------------------------------------------------
+
+fn state_path() -> Result<PathBuf> {
+    Ok(config_dir()?.join(STATE_FILE))
+}
+
+/// The saved progress, or `None` if there is none yet. Fails if the file
+/// exists but cannot be read or parsed.
+pub fn load() -> Result<Option<DaemonProgress>> {
+    let path = state_path()?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    let contents = fs::read_to_string(&path)
+        .with_context(|| format!("failed to read daemon state: {}", path.display()))?;
+    let progress = serde_json::from_str(&contents)
+        .with_context(|| format!("failed to parse daemon state: {}", path.display()))?;
+    Ok(Some(progress))
+}
+
+/// Save `progress`, replacing the earlier state. Written to a temporary
+/// sibling and renamed into place, so a crash mid-write leaves the previous
+/// state intact.
+pub fn save(progress: &DaemonProgress) -> Result<()> {
+    let path = state_path()?;
+    let json =
+        serde_json::to_string_pretty(progress).context("failed to serialise daemon state")?;
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, json)
+        .with_context(|| format!("failed to write daemon state: {}", tmp_path.display()))?;
+    fs::rename(&tmp_path, &path)
+        .with_context(|| format!("failed to write daemon state: {}", path.display()))?;
+    debug!(path = %path.display(), cycles = progress.cycles, "daemon state saved");
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::sync::Mutex;
+
+    /// Mutex to serialise tests that mutate environment variables.
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    fn with_temp_home<F: FnOnce()>(f: F) {
+        let _guard = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+        let tmp = tempfile::tempdir().expect("failed to create temp dir");
+        let prev = env::var("AGENTMARKET_HOME").ok();
+
+        env::set_var("AGENTMARKET_HOME", tmp.path());
+        f();
+
+        match prev {
+            Some(v) => env::set_var("AGENTMARKET_HOME", v),
+            None => env::remove_var("AGENTMARKET_HOME"),
+        }
+    }
+
+    #[test]
+    fn test_save_load_roundtrip() {
+        with_temp_home(|| {
+            assert_eq!(load().unwrap(), None);
+
+            let mut progress = DaemonProgress {
+                epoch: 20_000,
+                cycles: 12,
+                validations: 3,
+                claims: 2,
+                ..Default::default()
+            };
+            progress.advance_block(1_234);
+            progress.mark_handled("42");
+            progress.record_block_hash(1_234, hash(0x12));
+            progress.applied_events.push(applied(1_230, "42"));
+            save(&progress).unwrap();
+
+            assert_eq!(load().unwrap(), Some(progress));
+        });
+    }
+
+    #[test]
+    fn test_load_fails_on_corrupt_state() {
+        with_temp_home(|| {
+            fs::write(state_path().unwrap(), "{ not json").unwrap();
+            assert!(load().is_err());
+        });
+    }
+
+    #[test]
+    fn test_roll_epoch_forgets_handled_requests() {
+        let mut progress = DaemonProgress::default();
+        progress.roll_epoch(EPOCH_SECS * 3 + 5);
+        progress.mark_handled("1");
+
+        progress.roll_epoch(EPOCH_SECS * 4 - 1);
+        assert!(progress.is_handled("1"));
+
+        progress.roll_epoch(EPOCH_SECS * 4);
+        assert!(!progress.is_handled("1"));
+        assert_eq!(progress.epoch, 4);
+    }
+
+    #[test]
+    fn test_claim_failures_count_attempts_and_back_off() {
+        let base = Duration::from_secs(60);
+        let mut progress = DaemonProgress::default();
+        assert!(progress.claim_due("7", 1_000));
+
+        let retry = progress.record_claim_failure("7", "nonce too low", 1_000, base);
+        assert_eq!(retry.attempts, 1);
+        assert_eq!(retry.next_retry_at, 1_060);
+        assert!(!progress.claim_due("7", 1_059));
+        assert!(progress.claim_due("7", 1_060));
+
+        let retry = progress.record_claim_failure("7", "connection reset", 1_060, base);
+        assert_eq!(retry.attempts, 2);
+        assert_eq!(retry.next_retry_at, 1_180);
+        assert_eq!(retry.last_error, "connection reset");
+        assert!(
+            progress.claim_due("8", 1_060),
+            "other requests are unaffected"
+        );
+    }
+
+    #[test]
+    fn test_claim_needs_attention_after_max_attempts() {
+        let base = Duration::from_secs(60);
+        let mut progress = DaemonProgress::default();
+        for attempt in 1..MAX_CLAIM_ATTEMPTS {
+            progress.record_claim_failure("7", "rpc error", 0, base);
+            assert!(progress.claims_needing_attention().is_empty(), "{attempt}");
+        }
+        progress.record_claim_failure("7", "rpc error", 0, base);
+        assert_eq!(progress.claims_needing_attention(), vec!["7".to_string()]);
+        assert!(!progress.claim_due("7", u64::MAX));
+
+        progress.clear_claim_retry("7");
+        assert!(progress.claim_due("7", 0));
+        assert!(progress.claims_needing_attention().is_empty());
+    }
+
+    #[test]
+    fn test_deferred_claims_wait_without_using_attempts() {
+        let base = Duration::from_secs(60);
+        let mut progress = DaemonProgress::default();
+        for round in 0..MAX_CLAIM_ATTEMPTS as u64 * 2 {
+            progress.defer_claim("7", "fees above cap", round * 60, base);
+        }
+        let retry = &progress.claim_retries["7"];
+        assert_eq!(retry.attempts, 0);
+        assert_eq!(retry.last_error, "fees above cap");
+        assert!(progress.claims_needing_attention().is_empty());
+        assert!(!progress.claim_due("7", retry.next_retry_at - 1));
+        assert!(progress.claim_due("7", retry.next_retry_at));
+
+        // A deferral after real failures keeps their count.
+        progress.record_claim_failure("7", "nonce too low", 0, base);
+        progress.defer_claim("7", "fees above cap", 0, base);
+        assert_eq!(progress.claim_retries["7"].attempts, 1);
+    }
+
+    fn hash(byte: u8) -> B256 {
+        B256::repeat_byte(byte)
+    }
+
+    fn applied(block_number: u64, request_id: &str) -> AppliedEvent {
+        AppliedEvent {
+            block_number,
+            request_id: request_id.to_string(),
+            from: LocalRequestStatus::Responded,
+            to: LocalRequestStatus::Validated,
+        }
+    }
+
+    #[test]
+    fn test_fork_cursor_finds_the_first_replaced_block() {
+        let mut progress = DaemonProgress::default();
+        for (number, byte) in [(100, 0xa0), (105, 0xa5), (110, 0xaa)] {
+            progress.record_block_hash(number, hash(byte));
+        }
+        let canonical = |entries: &[(u64, Option<u8>)]| {
+            entries
+                .iter()
+                .map(|&(number, byte)| (number, byte.map(hash)))
+                .collect::<BTreeMap<_, _>>()
+        };
+
+        let unchanged = canonical(&[(100, Some(0xa0)), (105, Some(0xa5)), (110, Some(0xaa))]);
+        assert_eq!(progress.fork_cursor(&unchanged), None);
+
+        // Block 105 was replaced: go back to 100, the newest block kept.
+        let forked = canonical(&[(100, Some(0xa0)), (105, Some(0xb5)), (110, Some(0xba))]);
+        assert_eq!(progress.fork_cursor(&forked), Some(100));
+
+        // A block that no longer exists counts as replaced.
+        let shorter = canonical(&[(100, Some(0xa0)), (105, Some(0xa5)), (110, None)]);
+        assert_eq!(progress.fork_cursor(&shorter), Some(105));
+
+        // With no older block known, go back to just before the fork.
+        let deep = canonical(&[(100, Some(0xb0)), (105, Some(0xb5)), (110, Some(0xba))]);
+        assert_eq!(progress.fork_cursor(&deep), Some(99));
+
+        // Blocks the caller did not look up are not judged.
+        assert_eq!(progress.fork_cursor(&BTreeMap::new()), None);
+    }
+
+    #[test]
+    fn test_rewind_returns_applied_events_newest_first() {
+        let mut progress = DaemonProgress::default();
+        progress.advance_block(110);
+        for number in [100, 105, 110] {
+            progress.record_block_hash(number, hash(number as u8));
+        }
+        progress.applied_events = vec![applied(100, "1"), applied(105, "2"), applied(108, "3")];
+
+        let undone = progress.rewind(100);
+        assert_eq!(undone, vec![applied(108, "3"), applied(105, "2")]);
+        assert_eq!(progress.last_block, Some(100));
+        assert_eq!(
+            progress.block_hashes.keys().copied().collect::<Vec<_>>(),
+            [100]
+        );
+        assert_eq!(progress.applied_events, vec![applied(100, "1")]);
+
+        // Rewinding again to the same place changes nothing.
+        assert!(progress.rewind(100).is_empty());
+        assert_eq!(progress.last_block, Some(100));
+    }
+
+    #[test]
+    fn test_prune_keeps_the_reorg_horizon_and_the_cursor() {
+        let mut progress = DaemonProgress::default();
+        progress.advance_block(1_000);
+        for number in [500, 1_000 - REORG_HORIZON, 1_000] {
+            progress.record_block_hash(number, hash(1));
+        }
+        progress.applied_events = vec![applied(500, "1"), applied(900, "2")];
+
+        progress.prune_reorg_history();
+        assert_eq!(
+            progress.block_hashes.keys().copied().collect::<Vec<_>>(),
+            [1_000 - REORG_HORIZON, 1_000]
+        );
+        assert_eq!(progress.applied_events, vec![applied(900, "2")]);
+    }
+
+    #[test]
+    fn test_advance_block_never_moves_backwards() {
+        let mut progress = DaemonProgress::default();
+        progress.advance_block(100);
+        progress.advance_block(90);
+        assert_eq!(progress.last_block, Some(100));
+    }
+}
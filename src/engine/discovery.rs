@@ -0,0 +1,413 @@
+//! Filtering and ordering of `search` results.
+//!
+//! `search` fetches agent profiles and open requests from the network into
+//! [`AgentListing`]s and [`RequestListing`]s; the functions here narrow and
+//! order them. They are pure so the rules can be tested without a network.
+
+use std::cmp::Ordering;
+
+use anyhow::{bail, Result};
+use serde::Serialize;
+
+use super::requests::dollars_to_usdc;
+use crate::clock::format_duration;
+use crate::config::store::ServicesConfig;
+
+// ---------------------------------------------------------------------------
+// Agents
+// ---------------------------------------------------------------------------
+
+/// A registered agent as shown by `search`, and as serialised for `--json`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct AgentListing {
+    #[serde(rename = "id")]
+    pub agent_id: String,
+    pub name: String,
+    pub description: String,
+    pub capabilities: Vec<String>,
+    #[serde(rename = "price_usd")]
+    pub pricing_usd: f64,
+    /// Reputation score (0.0 to 100.0), or `None` if the agent is unrated.
+    pub reputation: Option<f64>,
+}
+
+/// Orders accepted by `search --sort`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AgentSort {
+    /// Cheapest first.
+    Price,
+    /// Highest reputation first; unrated agents last.
+    Reputation,
+    /// Alphabetical, ignoring case.
+    Name,
+}
+
+impl AgentSort {
+    pub const ALL: [AgentSort; 3] = [AgentSort::Price, AgentSort::Reputation, AgentSort::Name];
+
+    /// Parse a `--sort` value, case-insensitively.
+    pub fn parse(input: &str) -> Result<Self> {
+        let input = input.trim();
+        match Self::ALL
+            .into_iter()
+            .find(|sort| format!("{sort:?}").eq_ignore_ascii_case(input))
+        {
+            Some(sort) => Ok(sort),
+            None => bail!(
+                "Unknown sort order '{input}'. Use one of: {}.",
+                Self::ALL
+                    .map(|s| format!("{s:?}").to_lowercase())
+                    .join(", ")
+            ),
+        }
+    }
+}
+
+/// Which agents `search` shows, and in what order.
+#[derive(Clone, Debug)]
+pub struct AgentQuery {
+    /// Only agents offering this capability (case-insensitive).
+    pub capability: Option<String>,
+    /// Only agents rated at least this; unrated agents are excluded.
+    pub min_reputation: Option<f64>,
+    /// Only agents charging at most this per task.
+    pub max_price_usd: Option<f64>,
+    pub sort: AgentSort,
+    pub limit: Option<usize>,
+}
+
+/// Apply `query` to `agents`: filter, sort, then truncate to the limit.
+/// Ties are broken by agent ID so the order is stable across runs.
+pub fn select_agents(mut agents: Vec<AgentListing>, query: &AgentQuery) -> Vec<AgentListing> {
+    agents.retain(|agent| {
+        let capable = query
+            .capability
+            .as_ref()
+            .map_or(true, |wanted| offers(&agent.capabilities, wanted));
+        let reputable = query
+            .min_reputation
+            .map_or(true, |min| agent.reputation.is_some_and(|r| r >= min));
+        let affordable = query
+            .max_price_usd
+            .map_or(true, |max| agent.pricing_usd <= max);
+        capable && reputable && affordable
+    });
+
+    agents.sort_by(|a, b| {
+        let primary = match query.sort {
+            AgentSort::Price => a.pricing_usd.total_cmp(&b.pricing_usd),
+            AgentSort::Reputation => match (a.reputation, b.reputation) {
+                (Some(a), Some(b)) => b.total_cmp(&a),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            },
+            AgentSort::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+        };
+        primary.then_with(|| a.agent_id.cmp(&b.agent_id))
+    });
+
+    if let Some(limit) = query.limit {
+        agents.truncate(limit);
+    }
+    agents
+}
+
+// ---------------------------------------------------------------------------
+// Requests
+// ---------------------------------------------------------------------------
+
+/// An open request as shown by `search --requests`, and as serialised for
+/// `--json`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct RequestListing {
+    #[serde(rename = "id")]
+    pub request_id: String,
+    pub task_summary: String,
+    /// Address of the agent that posted the request.
+    pub buyer: String,
+    /// Capabilities the request asks for; empty if the buyer named none,
+    /// in which case any agent may take it.
+    pub capabilities: Vec<String>,
+    /// Price in USDC atomic units (6 decimals).
+    pub price_usdc: u64,
+    /// Unix timestamp after which the request expires.
+    pub deadline: u64,
+}
+
+impl RequestListing {
+    /// The deadline relative to `now`, e.g. `"in 3h 20m"`.
+    pub fn format_deadline(&self, now: u64) -> String {
+        if self.deadline > now {
+            format!("in {}", format_duration(self.deadline - now))
+        } else {
+            "expired".to_string()
+        }
+    }
+}
+
+/// Which open requests `search --requests` shows.
+#[derive(Clone, Debug, Default)]
+pub struct RequestQuery {
+    /// Only requests asking for this capability (case-insensitive).
+    pub capability: Option<String>,
+    /// Only requests paying at least this, in USDC atomic units.
+    pub min_price_usdc: Option<u64>,
+    /// Only requests due within this many seconds of `now`.
+    pub deadline_within_secs: Option<u64>,
+    /// Only requests this agent can serve, see [`can_serve`].
+    pub services: Option<ServicesConfig>,
+    pub limit: Option<usize>,
+}
+
+/// Whether an agent offering `services` can take `request`: it asks for at
+/// least one of the agent's capabilities (or names none), and pays at least
+/// the agent's price. Capabilities compare case-insensitively.
+pub fn can_serve(request: &RequestListing, services: &ServicesConfig) -> bool {
+    let capable = request.capabilities.is_empty()
+        || request
+            .capabilities
+            .iter()
+            .any(|wanted| offers(&services.capabilities, wanted));
+    capable && request.price_usdc >= dollars_to_usdc(services.pricing_usd)
+}
+
+fn offers(capabilities: &[String], wanted: &str) -> bool {
+    capabilities
+        .iter()
+        .any(|c| c.trim().eq_ignore_ascii_case(wanted.trim()))
+}
+
+/// Apply `query` to `requests` as of `now`, dropping expired ones, soonest
+/// deadline first, then truncate to the limit.
+pub fn select_requests(
+    mut requests: Vec<RequestListing>,
+    query: &RequestQuery,
+    now: u64,
+) -> Vec<RequestListing> {
+    requests.retain(|request| {
+        let open = request.deadline > now;
+        let capable = query
+            .capability
+            .as_ref()
+            .map_or(true, |wanted| offers(&request.capabilities, wanted));
+        let servable = query
+            .services
+            .as_ref()
+            .map_or(true, |services| can_serve(request, services));
+        let paying = query
+            .min_price_usdc
+            .map_or(true, |min| request.price_usdc >= min);
+        let due_soon = query.deadline_within_secs.map_or(true, |within| {
+            request.deadline <= now.saturating_add(within)
+        });
+        open && capable && servable && paying && due_soon
+    });
+
+    requests.sort_by(|a, b| {
+        a.deadline
+            .cmp(&b.deadline)
+            .then_with(|| a.request_id.cmp(&b.request_id))
+    });
+
+    if let Some(limit) = query.limit {
+        requests.truncate(limit);
+    }
+    requests
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn agent(id: &str, name: &str, price: f64, reputation: Option<f64>) -> AgentListing {
+        AgentListing {
+            agent_id: id.to_string(),
+            name: name.to_string(),
+            description: String::new(),
+            capabilities: vec!["Summarize".to_string()],
+            pricing_usd: price,
+            reputation,
+        }
+    }
+
+    fn ids(agents: &[AgentListing]) -> Vec<&str> {
+        agents.iter().map(|a| a.agent_id.as_str()).collect()
+    }
+
+    fn query(sort: AgentSort) -> AgentQuery {
+        AgentQuery {
+            capability: None,
+            min_reputation: None,
+            max_price_usd: None,
+            sort,
+            limit: None,
+        }
+    }
+
+    #[test]
+    fn test_agent_sort_orders() {
+        let agents = vec![
+            agent("1", "carol", 5.0, None),
+            agent("2", "alice", 9.0, Some(80.0)),
+            agent("3", "Bob", 1.0, Some(97.5)),
+            agent("4", "dave", 5.0, Some(80.0)),
+        ];
+
+        let by_price = select_agents(agents.clone(), &query(AgentSort::Price));
+        assert_eq!(ids(&by_price), ["3", "1", "4", "2"]);
+
+        let by_reputation = select_agents(agents.clone(), &query(AgentSort::Reputation));
+        assert_eq!(ids(&by_reputation), ["3", "2", "4", "1"]);
+
+        let by_name = select_agents(agents, &query(AgentSort::Name));
+        assert_eq!(ids(&by_name), ["2", "3", "1", "4"]);
+    }
+
+    #[test]
+    fn test_agent_filters_and_limit() {
+        let mut translator = agent("5", "eve", 2.0, Some(99.0));
+        translator.capabilities = vec!["translate".to_string()];
+        let agents = vec![
+            agent("1", "carol", 5.0, None),
+            agent("2", "alice", 9.0, Some(80.0)),
+            agent("3", "bob", 1.0, Some(97.5)),
+            translator,
+        ];
+
+        let mut q = query(AgentSort::Reputation);
+        q.capability = Some(" summarize ".to_string());
+        q.min_reputation = Some(80.0);
+        assert_eq!(ids(&select_agents(agents.clone(), &q)), ["3", "2"]);
+
+        q.max_price_usd = Some(5.0);
+        assert_eq!(ids(&select_agents(agents.clone(), &q)), ["3"]);
+
+        let mut q = query(AgentSort::Price);
+        q.limit = Some(2);
+        assert_eq!(ids(&select_agents(agents, &q)), ["3", "5"]);
+    }
+
+    #[test]
+    fn test_agent_sort_parse() {
+        assert_eq!(AgentSort::parse("Price").unwrap(), AgentSort::Price);
+        assert_eq!(AgentSort::parse(" name ").unwrap(), AgentSort::Name);
+        let err = AgentSort::parse("cost").unwrap_err().to_string();
+        assert!(err.contains("price, reputation, name"), "{err}");
+    }
+
+    fn listing(id: &str, capabilities: &[&str], price_usdc: u64) -> RequestListing {
+        RequestListing {
+            request_id: id.to_string(),
+            task_summary: String::new(),
+            buyer: String::new(),
+            capabilities: capabilities.iter().map(|c| c.to_string()).collect(),
+            price_usdc,
+            deadline: u64::MAX,
+        }
+    }
+
+    fn services(capabilities: &[&str], pricing_usd: f64) -> ServicesConfig {
+        ServicesConfig {
+            capabilities: capabilities.iter().map(|c| c.to_string()).collect(),
+            pricing_usd,
+        }
+    }
+
+    #[test]
+    fn test_can_serve_capability_overlap() {
+        let mine = services(&["summarize", "Translate"], 5.0);
+
+        // One shared capability is enough.
+        assert!(can_serve(
+            &listing("a", &["code-review", "translate"], 5_000_000),
+            &mine
+        ));
+        assert!(!can_serve(
+            &listing("b", &["code-review", "audit"], 5_000_000),
+            &mine
+        ));
+
+        // A request naming no capability is open to anyone; an agent with
+        // no capabilities can only take those.
+        assert!(can_serve(&listing("c", &[], 5_000_000), &mine));
+        let none = services(&[], 5.0);
+        assert!(can_serve(&listing("c", &[], 5_000_000), &none));
+        assert!(!can_serve(&listing("a", &["translate"], 5_000_000), &none));
+    }
+
+    #[test]
+    fn test_can_serve_price_floor() {
+        let mine = services(&["summarize"], 5.0);
+        assert!(can_serve(&listing("a", &["summarize"], 5_000_000), &mine));
+        assert!(!can_serve(&listing("b", &["summarize"], 4_999_999), &mine));
+        assert!(can_serve(&listing("c", &["summarize"], 50_000_000), &mine));
+
+        let q = RequestQuery {
+            services: Some(mine),
+            ..Default::default()
+        };
+        let selected = select_requests(
+            vec![
+                listing("a", &["summarize"], 5_000_000),
+                listing("b", &["summarize"], 4_999_999),
+            ],
+            &q,
+            0,
+        );
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].request_id, "a");
+    }
+
+    #[test]
+    fn test_select_requests() {
+        let now = 1_000_000;
+        let request = |id: &str, capability: Option<&str>, price: u64, hours: i64| RequestListing {
+            deadline: (now as i64 + hours * 3600) as u64,
+            ..listing(id, &Vec::from_iter(capability), price)
+        };
+        let requests = vec![
+            request("a", Some("summarize"), 5_000_000, 48),
+            request("b", None, 20_000_000, 2),
+            request("c", Some("Summarize"), 1_000_000, 6),
+            request("d", Some("summarize"), 50_000_000, -1),
+        ];
+        let ids = |selected: Vec<RequestListing>| {
+            selected
+                .into_iter()
+                .map(|r| r.request_id)
+                .collect::<Vec<_>>()
+        };
+
+        // Expired requests are never shown; soonest deadline first.
+        assert_eq!(
+            ids(select_requests(
+                requests.clone(),
+                &RequestQuery::default(),
+                now
+            )),
+            ["b", "c", "a"]
+        );
+
+        let q = RequestQuery {
+            capability: Some("summarize".to_string()),
+            min_price_usdc: Some(2_000_000),
+            ..Default::default()
+        };
+        assert_eq!(ids(select_requests(requests.clone(), &q, now)), ["a"]);
+
+        let q = RequestQuery {
+            deadline_within_secs: Some(6 * 3600),
+            limit: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(ids(select_requests(requests.clone(), &q, now)), ["b"]);
+
+        assert_eq!(requests[0].format_deadline(now), "in 2d");
+        assert_eq!(requests[3].format_deadline(now), "expired");
+    }
+}
@@ -0,0 +1,267 @@
+//! Validator economics for AgentMarket CLI.
+//!
+//! Validators are paid `price × validatorFeeBps / 10000` out of each request
+//! they validate. Running a handler is not free (model calls, compute), so
+//! before accepting a validation job we compare the expected fee against the
+//! configured per-job cost and skip jobs that fall below the desired margin.
+//!
+//! The fee rate is read from a cached snapshot of the Request Registry's
+//! parameters (`~/.agentmarket/contract_snapshot.json`), refreshed by the
+//! command layer whenever the registry is reachable.
+
+use std::fs;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::clock;
+use crate::config::store::{config_dir, ValidationConfig};
+use crate::engine::requests::{dollars_to_usdc, format_price_usd};
+
+// ---------------------------------------------------------------------------
+// Constants
+// ---------------------------------------------------------------------------
+
+/// Basis-point denominator used by the Request Registry.
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// Validator fee assumed when no contract snapshot has been cached yet (5%).
+pub const DEFAULT_VALIDATOR_FEE_BPS: u64 = 500;
+
+/// Name of the contract snapshot file inside the config directory.
+const SNAPSHOT_FILE: &str = "contract_snapshot.json";
+
+// ---------------------------------------------------------------------------
+// Fee math
+// ---------------------------------------------------------------------------
+
+/// Compute the validator fee for a request, mirroring the contract's
+/// `price * validatorFeeBps / 10000` (integer division, rounds down).
+pub fn validator_fee(price_usdc: u64, fee_bps: u64) -> u64 {
+    (u128::from(price_usdc) * u128::from(fee_bps) / u128::from(BPS_DENOMINATOR)) as u64
+}
+
+/// Expected economics of a single validation job (all amounts in USDC
+/// atomic units).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JobEstimate {
+    /// Fee the validator would earn.
+    pub fee_usdc: u64,
+    /// Estimated cost of running the handler once.
+    pub cost_usdc: u64,
+    /// Minimum acceptable `fee - cost`.
+    pub min_margin_usdc: u64,
+}
+
+impl JobEstimate {
+    /// Build an estimate for a request of `price_usdc` at `fee_bps`.
+    pub fn new(price_usdc: u64, fee_bps: u64, cost_usdc: u64, min_margin_usdc: u64) -> Self {
+        Self {
+            fee_usdc: validator_fee(price_usdc, fee_bps),
+            cost_usdc,
+            min_margin_usdc,
+        }
+    }
+
+    /// Expected profit (`fee - cost`); negative when the job loses money.
+    pub fn net_usdc(&self) -> i128 {
+        i128::from(self.fee_usdc) - i128::from(self.cost_usdc)
+    }
+
+    /// Returns `true` if the expected profit meets the configured margin.
+    pub fn meets_margin(&self) -> bool {
+        self.net_usdc() >= i128::from(self.min_margin_usdc)
+    }
+
+    /// The fee-versus-cost comparison, e.g. `"fee $0.25 vs est. cost $0.40"`.
+    pub fn math(&self) -> String {
+        format!(
+            "fee {} vs est. cost {}",
+            format_price_usd(self.fee_usdc),
+            format_price_usd(self.cost_usdc),
+        )
+    }
+
+    /// One-line explanation, e.g. `"fee $0.25 vs est. cost $0.40 — skipped"`.
+    pub fn summary(&self) -> String {
+        let verdict = if self.meets_margin() {
+            "accepted"
+        } else {
+            "skipped"
+        };
+        format!("{} — {verdict}", self.math())
+    }
+}
+
+/// Estimate a validation job using the configured cost and margin and the
+/// cached validator fee rate.
+pub fn estimate_job(price_usdc: u64, cfg: &ValidationConfig) -> JobEstimate {
+    JobEstimate::new(
+        price_usdc,
+        validator_fee_bps(),
+        dollars_to_usdc(cfg.estimated_cost_usd),
+        dollars_to_usdc(cfg.min_margin_usd),
+    )
+}
+
+// ---------------------------------------------------------------------------
+// Contract snapshot
+// ---------------------------------------------------------------------------
+
+/// Cached Request Registry parameters relevant to validator economics.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContractSnapshot {
+    /// `validatorFeeBps` as read from the contract.
+    pub validator_fee_bps: u64,
+    /// Unix timestamp when the snapshot was taken.
+    pub fetched_at: u64,
+}
+
+impl ContractSnapshot {
+    /// Snapshot taken now with the given fee rate.
+    pub fn now(validator_fee_bps: u64) -> Self {
+        let fetched_at = clock::unix_now();
+        Self {
+            validator_fee_bps,
+            fetched_at,
+        }
+    }
+}
+
+/// Read the cached contract snapshot, if any.
+pub fn load_snapshot() -> Result<Option<ContractSnapshot>> {
+    let path = config_dir()?.join(SNAPSHOT_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let contents = fs::read_to_string(&path)
+        .with_context(|| format!("failed to read contract snapshot: {}", path.display()))?;
+    let snapshot = serde_json::from_str(&contents)
+        .with_context(|| format!("failed to parse contract snapshot: {}", path.display()))?;
+    Ok(Some(snapshot))
+}
+
+/// Write the contract snapshot cache.
+pub fn save_snapshot(snapshot: &ContractSnapshot) -> Result<()> {
+    let path = config_dir()?.join(SNAPSHOT_FILE);
+    debug!(path = %path.display(), bps = snapshot.validator_fee_bps, "saving contract snapshot");
+
+    let json =
+        serde_json::to_string_pretty(snapshot).context("failed to serialise contract snapshot")?;
+    fs::write(&path, json)
+        .with_context(|| format!("failed to write contract snapshot: {}", path.display()))
+}
+
+/// Validator fee rate from the cached snapshot, falling back to
+/// [`DEFAULT_VALIDATOR_FEE_BPS`] when no snapshot is available.
+pub fn validator_fee_bps() -> u64 {
+    match load_snapshot() {
+        Ok(Some(snapshot)) => snapshot.validator_fee_bps,
+        Ok(None) => DEFAULT_VALIDATOR_FEE_BPS,
+        Err(err) => {
+            debug!(error = %err, "unreadable contract snapshot, using default fee rate");
+            DEFAULT_VALIDATOR_FEE_BPS
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::sync::Mutex;
+
+    /// Mutex to serialise tests that mutate environment variables.
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    fn with_temp_home<F: FnOnce()>(f: F) {
+        let _guard = ENV_LOCK.lock().expect("env lock poisoned");
+
+        let tmp = tempfile::tempdir().expect("failed to create temp dir");
+        let prev = env::var("AGENTMARKET_HOME").ok();
+
+        env::set_var("AGENTMARKET_HOME", tmp.path());
+        f();
+
+        match prev {
+            Some(v) => env::set_var("AGENTMARKET_HOME", v),
+            None => env::remove_var("AGENTMARKET_HOME"),
+        }
+    }
+
+    #[test]
+    fn test_validator_fee_matches_contract_math() {
+        assert_eq!(validator_fee(5_000_000, 500), 250_000);
+        assert_eq!(validator_fee(1, 500), 0, "integer division rounds down");
+        assert_eq!(validator_fee(u64::MAX, 10_000), u64::MAX);
+        assert_eq!(validator_fee(5_000_000, 0), 0);
+    }
+
+    #[test]
+    fn test_profitable_job() {
+        // $10 request at 5% = $0.50 fee vs $0.10 cost.
+        let est = JobEstimate::new(10_000_000, 500, 100_000, 0);
+        assert_eq!(est.net_usdc(), 400_000);
+        assert!(est.meets_margin());
+        assert_eq!(est.summary(), "fee $0.50 vs est. cost $0.10 — accepted");
+    }
+
+    #[test]
+    fn test_borderline_job_exactly_at_margin_is_accepted() {
+        // $0.50 fee - $0.30 cost = $0.20, margin $0.20.
+        let est = JobEstimate::new(10_000_000, 500, 300_000, 200_000);
+        assert_eq!(est.net_usdc(), 200_000);
+        assert!(est.meets_margin());
+
+        // One micro-dollar more cost tips it under.
+        let est = JobEstimate::new(10_000_000, 500, 300_001, 200_000);
+        assert!(!est.meets_margin());
+    }
+
+    #[test]
+    fn test_unprofitable_job() {
+        // $5 request at 5% = $0.25 fee vs $0.40 cost.
+        let est = JobEstimate::new(5_000_000, 500, 400_000, 0);
+        assert_eq!(est.net_usdc(), -150_000);
+        assert!(!est.meets_margin());
+        assert_eq!(est.summary(), "fee $0.25 vs est. cost $0.40 — skipped");
+    }
+
+    #[test]
+    fn test_zero_fee_marketplace() {
+        // No fee and no cost: breaking even is acceptable with no margin.
+        let est = JobEstimate::new(5_000_000, 0, 0, 0);
+        assert_eq!(est.fee_usdc, 0);
+        assert!(est.meets_margin());
+
+        // No fee but a real cost: always unprofitable.
+        let est = JobEstimate::new(5_000_000, 0, 1, 0);
+        assert!(!est.meets_margin());
+    }
+
+    #[test]
+    fn test_fee_bps_falls_back_then_reads_snapshot() {
+        with_temp_home(|| {
+            assert_eq!(validator_fee_bps(), DEFAULT_VALIDATOR_FEE_BPS);
+
+            save_snapshot(&ContractSnapshot::now(250)).expect("save snapshot");
+            assert_eq!(validator_fee_bps(), 250);
+
+            let cfg = ValidationConfig {
+                estimated_cost_usd: 0.10,
+                min_margin_usd: 0.0,
+            };
+            // $10 at 2.5% = $0.25 fee.
+            let est = estimate_job(10_000_000, &cfg);
+            assert_eq!(est.fee_usdc, 250_000);
+            assert_eq!(est.cost_usdc, 100_000);
+            assert!(est.meets_margin());
+        });
+    }
+}
@@ -0,0 +1,439 @@
+//! Reorg-safe application of request events to the local cache.
+//!
+//! Each cycle the daemon scans the Request Registry's events from its cursor
+//! (see [`crate::engine::daemon_state`]) up to the newest block buried under
+//! `network.event_confirmations` blocks, and moves tracked requests forward
+//! to match: a submitted response makes an `Open` request `Responded`, a
+//! passing validation makes it `Validated`, and a claim makes it `Claimed`.
+//! Every change is journaled with its block.
+//!
+//! Before scanning, the hashes recorded for earlier blocks are compared with
+//! the chain. If a reorg replaced one of them, the cursor is rewound to the
+//! last block still on the chain, the changes made from the replaced blocks
+//! are undone, and those blocks are scanned again.
+//!
+//! Applying an event that is already reflected locally, or undoing a change
+//! that was already undone, is a no-op, so overlapping or repeated scans are
+//! harmless.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::future::Future;
+
+use alloy::primitives::B256;
+use anyhow::{Context, Result};
+use tracing::debug;
+
+use crate::chain::events::{DecodedEvent, RequestEvent};
+use crate::engine::daemon_state::{AppliedEvent, DaemonProgress};
+use crate::engine::requests::{LocalRequest, LocalRequestStatus};
+
+/// What [`sync`] reads from the chain.
+pub trait ChainView {
+    /// Hash of block `number` as the chain now has it, or `None` if there is
+    /// no such block.
+    fn block_hash(&self, number: u64) -> impl Future<Output = Result<Option<B256>>> + Send;
+
+    /// Request events in blocks `from` through `to` (inclusive), oldest
+    /// first.
+    fn events(&self, from: u64, to: u64) -> impl Future<Output = Result<Vec<DecodedEvent>>> + Send;
+}
+
+/// What one [`sync`] did.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SyncOutcome {
+    /// Requests whose status changed and need saving.
+    pub changed: BTreeSet<String>,
+    /// Where the cursor was rewound from and to after a reorg.
+    pub rewound: Option<(u64, u64)>,
+    /// Confirmed events read.
+    pub events: usize,
+}
+
+/// The newest block with at least `confirmations` blocks on top of it, its
+/// own included, when the chain head is `head`. `None` while the chain is
+/// shorter than that.
+pub fn confirmed_block(head: u64, confirmations: u64) -> Option<u64> {
+    (head + 1).checked_sub(confirmations.max(1))
+}
+
+/// The local status `event` moves a request to, if any. A failed
+/// validation leaves the request where it is.
+pub fn status_after(event: &RequestEvent) -> Option<LocalRequestStatus> {
+    match event {
+        RequestEvent::Created { .. } => None,
+        RequestEvent::ResponseSubmitted { .. } => Some(LocalRequestStatus::Responded),
+        RequestEvent::Validated { passed, .. } => passed.then_some(LocalRequestStatus::Validated),
+        RequestEvent::Claimed { .. } => Some(LocalRequestStatus::Claimed),
+    }
+}
+
+/// Move `request` forward to match `event`. Returns the change made, or
+/// `None` if the request is already there or cannot move there from its
+/// current status.
+pub fn apply_event(request: &mut LocalRequest, event: &DecodedEvent) -> Option<AppliedEvent> {
+    let to = status_after(&event.event)?;
+    if request.status == to || !request.status.can_transition_to(&to) {
+        return None;
+    }
+    let from = request.status.clone();
+    request
+        .transition_with_note(
+            to.clone(),
+            Some(format!("chain event in block {}", event.block_number)),
+        )
+        .ok()?;
+    Some(AppliedEvent {
+        block_number: event.block_number,
+        request_id: request.request_id.clone(),
+        from,
+        to,
+    })
+}
+
+/// Undo `applied` on `request` because its block was reorged away. Returns
+/// whether anything changed: a request that has since moved on, or was
+/// already put back, is left alone.
+pub fn undo_event(request: &mut LocalRequest, applied: &AppliedEvent) -> bool {
+    if request.status != applied.to {
+        return false;
+    }
+    request.revert_to(
+        applied.from.clone(),
+        format!("block {} was reorged away", applied.block_number),
+    );
+    true
+}
+
+/// Bring `requests` (the tracked requests, by ID) up to date with the
+/// confirmed events on `chain`, whose head is `head`, rewinding first if a
+/// reorg replaced a block processed earlier. `progress` carries the cursor
+/// between calls.
+pub async fn sync(
+    chain: &impl ChainView,
+    head: u64,
+    confirmations: u64,
+    progress: &mut DaemonProgress,
+    requests: &mut BTreeMap<String, LocalRequest>,
+) -> Result<SyncOutcome> {
+    let mut outcome = SyncOutcome::default();
+    let Some(confirmed) = confirmed_block(head, confirmations) else {
+        return Ok(outcome);
+    };
+
+    // 1. Check that the blocks processed earlier are still on the chain.
+    let mut canonical = BTreeMap::new();
+    for &number in progress.block_hashes.keys() {
+        canonical.insert(number, chain.block_hash(number).await?);
+    }
+    if let Some(cursor) = progress.fork_cursor(&canonical) {
+        let from = progress.last_block.unwrap_or(cursor);
+        for applied in progress.rewind(cursor) {
+            if let Some(request) = requests.get_mut(&applied.request_id) {
+                if undo_event(request, &applied) {
+                    outcome.changed.insert(applied.request_id.clone());
+                }
+            }
+        }
+        outcome.rewound = Some((from, cursor));
+    }
+
+    // 2. Apply the events confirmed since the cursor.
+    let start = progress.last_block.map_or(confirmed, |cursor| cursor + 1);
+    if start > confirmed {
+        return Ok(outcome);
+    }
+    let confirmed_hash = chain
+        .block_hash(confirmed)
+        .await?
+        .with_context(|| format!("block {confirmed} is missing from the chain"))?;
+    let events = chain.events(start, confirmed).await?;
+    debug!(
+        start,
+        confirmed,
+        count = events.len(),
+        "applying confirmed events"
+    );
+    outcome.events = events.len();
+    for event in &events {
+        let Some(request) = requests.get_mut(&event.event.request_id().to_string()) else {
+            continue;
+        };
+        if let Some(applied) = apply_event(request, event) {
+            progress.record_block_hash(event.block_number, event.block_hash);
+            outcome.changed.insert(applied.request_id.clone());
+            progress.applied_events.push(applied);
+        }
+    }
+    progress.advance_block(confirmed);
+    progress.record_block_hash(confirmed, confirmed_hash);
+    progress.prune_reorg_history();
+    Ok(outcome)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::types::RequestId;
+    use crate::engine::requests::RequestRole;
+    use alloy::primitives::{Address, U256};
+
+    /// A chain as a list of blocks, each with its hash and request events.
+    struct FakeChain {
+        blocks: Vec<(B256, Vec<RequestEvent>)>,
+    }
+
+    impl FakeChain {
+        /// Blocks `0..len`, hashed with `fork` so two chains built with
+        /// different forks differ from `fork_at` on.
+        fn new(len: u64, fork_at: u64, fork: u8) -> Self {
+            let blocks = (0..len)
+                .map(|number| {
+                    let tag = if number >= fork_at { fork } else { 0 };
+                    let mut hash = B256::with_last_byte(tag);
+                    hash.0[..8].copy_from_slice(&number.to_be_bytes());
+                    (hash, Vec::new())
+                })
+                .collect();
+            Self { blocks }
+        }
+
+        fn with_event(mut self, number: u64, event: RequestEvent) -> Self {
+            self.blocks[number as usize].1.push(event);
+            self
+        }
+
+        fn head(&self) -> u64 {
+            self.blocks.len() as u64 - 1
+        }
+    }
+
+    impl ChainView for FakeChain {
+        async fn block_hash(&self, number: u64) -> Result<Option<B256>> {
+            Ok(self.blocks.get(number as usize).map(|(hash, _)| *hash))
+        }
+
+        async fn events(&self, from: u64, to: u64) -> Result<Vec<DecodedEvent>> {
+            Ok((from..=to)
+                .filter_map(|number| self.blocks.get(number as usize).map(|b| (number, b)))
+                .flat_map(|(number, (hash, events))| {
+                    events.iter().map(move |event| DecodedEvent {
+                        block_number: number,
+                        block_hash: *hash,
+                        transaction_hash: B256::ZERO,
+                        log_index: 0,
+                        event: event.clone(),
+                    })
+                })
+                .collect())
+        }
+    }
+
+    fn tracked(id: u64, status: LocalRequestStatus) -> (String, LocalRequest) {
+        let request = LocalRequest {
+            schema_version: 0,
+            request_id: id.to_string(),
+            role: RequestRole::Seller,
+            status,
+            request_cid: String::new(),
+            price_usdc: 1_000_000,
+            deadline: u64::MAX,
+            response_cid: None,
+            secret: None,
+            secret_hash: None,
+            counterparty: None,
+            created_at: 0,
+            updated_at: 0,
+            previous_status: None,
+            tags: Vec::new(),
+            task_summary: None,
+            capability: None,
+            trust_policy: None,
+            history: Vec::new(),
+            payment_check: None,
+            response_attempt: None,
+        };
+        (id.to_string(), request)
+    }
+
+    fn validated(id: u64, passed: bool) -> RequestEvent {
+        RequestEvent::Validated {
+            request_id: RequestId(U256::from(id)),
+            passed,
+            validator: Address::ZERO,
+        }
+    }
+
+    fn claimed(id: u64) -> RequestEvent {
+        RequestEvent::Claimed {
+            request_id: RequestId(U256::from(id)),
+            secret: B256::ZERO,
+        }
+    }
+
+    fn status(requests: &BTreeMap<String, LocalRequest>, id: &str) -> LocalRequestStatus {
+        requests[id].status.clone()
+    }
+
+    #[test]
+    fn test_confirmed_block_counts_the_head() {
+        assert_eq!(confirmed_block(100, 5), Some(96));
+        assert_eq!(confirmed_block(100, 1), Some(100));
+        assert_eq!(confirmed_block(100, 0), Some(100));
+        assert_eq!(confirmed_block(3, 5), None);
+        assert_eq!(confirmed_block(4, 5), Some(0));
+    }
+
+    #[test]
+    fn test_apply_event_only_moves_forward_and_is_idempotent() {
+        let (_, mut request) = tracked(7, LocalRequestStatus::Responded);
+        let event = DecodedEvent {
+            block_number: 12,
+            block_hash: B256::ZERO,
+            transaction_hash: B256::ZERO,
+            log_index: 0,
+            event: validated(7, true),
+        };
+
+        let applied = apply_event(&mut request, &event).unwrap();
+        assert_eq!(applied.from, LocalRequestStatus::Responded);
+        assert_eq!(request.status, LocalRequestStatus::Validated);
+        assert_eq!(
+            request.history.last().unwrap().note.as_deref(),
+            Some("chain event in block 12")
+        );
+        assert_eq!(apply_event(&mut request, &event), None, "already applied");
+
+        // A failed validation changes nothing; a claim cannot skip ahead.
+        let (_, mut open) = tracked(7, LocalRequestStatus::Open);
+        let failed = DecodedEvent {
+            event: validated(7, false),
+            ..event.clone()
+        };
+        assert_eq!(apply_event(&mut open, &failed), None);
+        let claim = DecodedEvent {
+            event: claimed(7),
+            ..event
+        };
+        assert_eq!(apply_event(&mut open, &claim), None);
+        assert_eq!(open.status, LocalRequestStatus::Open);
+
+        // Undoing twice puts the request back once.
+        assert!(undo_event(&mut request, &applied));
+        assert_eq!(request.status, LocalRequestStatus::Responded);
+        assert!(!undo_event(&mut request, &applied));
+        assert_eq!(request.status, LocalRequestStatus::Responded);
+    }
+
+    #[tokio::test]
+    async fn test_events_wait_for_confirmations() {
+        let mut progress = DaemonProgress::default();
+        progress.advance_block(9);
+        let mut requests: BTreeMap<_, _> = [tracked(7, LocalRequestStatus::Responded)].into();
+
+        // Validated in block 12; with 5 confirmations it is final at head 16.
+        let chain = FakeChain::new(16, u64::MAX, 0).with_event(12, validated(7, true));
+        let outcome = sync(&chain, chain.head(), 5, &mut progress, &mut requests)
+            .await
+            .unwrap();
+        assert!(outcome.changed.is_empty());
+        assert_eq!(progress.last_block, Some(11));
+        assert_eq!(status(&requests, "7"), LocalRequestStatus::Responded);
+
+        let chain = FakeChain::new(17, u64::MAX, 0).with_event(12, validated(7, true));
+        let outcome = sync(&chain, chain.head(), 5, &mut progress, &mut requests)
+            .await
+            .unwrap();
+        assert_eq!(outcome.changed, BTreeSet::from(["7".to_string()]));
+        assert_eq!(status(&requests, "7"), LocalRequestStatus::Validated);
+        assert_eq!(progress.last_block, Some(12));
+
+        // Syncing the same chain again is a no-op.
+        let outcome = sync(&chain, chain.head(), 5, &mut progress, &mut requests)
+            .await
+            .unwrap();
+        assert_eq!(outcome, SyncOutcome::default());
+    }
+
+    #[tokio::test]
+    async fn test_fork_rewinds_and_reprocesses() {
+        let mut progress = DaemonProgress::default();
+        progress.advance_block(9);
+        let mut requests: BTreeMap<_, _> = [
+            tracked(7, LocalRequestStatus::Responded),
+            tracked(8, LocalRequestStatus::Validated),
+        ]
+        .into();
+
+        // Chain A: request 7 passes validation in block 12, request 8 is
+        // claimed in block 14. Both are final at head 20.
+        let chain_a = FakeChain::new(21, u64::MAX, 0)
+            .with_event(12, validated(7, true))
+            .with_event(14, claimed(8));
+        sync(&chain_a, chain_a.head(), 5, &mut progress, &mut requests)
+            .await
+            .unwrap();
+        assert_eq!(status(&requests, "7"), LocalRequestStatus::Validated);
+        assert_eq!(status(&requests, "8"), LocalRequestStatus::Claimed);
+        assert_eq!(progress.last_block, Some(16));
+
+        // Chain B forks at block 13, deeper than the confirmations: the
+        // validation survives, the claim now lands in block 18 instead.
+        let chain_b = FakeChain::new(22, 13, 0xbb)
+            .with_event(12, validated(7, true))
+            .with_event(18, claimed(8));
+        let outcome = sync(&chain_b, chain_b.head(), 5, &mut progress, &mut requests)
+            .await
+            .unwrap();
+        assert_eq!(outcome.rewound, Some((16, 12)));
+        assert_eq!(outcome.changed, BTreeSet::from(["8".to_string()]));
+        assert_eq!(status(&requests, "7"), LocalRequestStatus::Validated);
+        assert_eq!(
+            status(&requests, "8"),
+            LocalRequestStatus::Validated,
+            "the claim is not final on chain B yet"
+        );
+        assert_eq!(progress.last_block, Some(17));
+        assert_eq!(
+            requests["8"].history.last().unwrap().note.as_deref(),
+            Some("block 14 was reorged away")
+        );
+
+        // Once chain B's claim is confirmed it is applied again, once.
+        let chain_b = FakeChain::new(23, 13, 0xbb)
+            .with_event(12, validated(7, true))
+            .with_event(18, claimed(8));
+        for _ in 0..2 {
+            sync(&chain_b, chain_b.head(), 5, &mut progress, &mut requests)
+                .await
+                .unwrap();
+        }
+        assert_eq!(status(&requests, "8"), LocalRequestStatus::Claimed);
+        assert_eq!(
+            progress
+                .applied_events
+                .iter()
+                .map(|applied| (applied.block_number, applied.request_id.as_str()))
+                .collect::<Vec<_>>(),
+            [(12, "7"), (18, "8")]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_untracked_requests_are_ignored() {
+        let mut progress = DaemonProgress::default();
+        let mut requests = BTreeMap::new();
+        let chain = FakeChain::new(10, u64::MAX, 0).with_event(5, claimed(99));
+        progress.advance_block(0);
+        let outcome = sync(&chain, chain.head(), 1, &mut progress, &mut requests)
+            .await
+            .unwrap();
+        assert_eq!(outcome.events, 1);
+        assert!(outcome.changed.is_empty());
+        assert!(progress.applied_events.is_empty());
+        assert_eq!(progress.last_block, Some(9));
+    }
+}
@@ -0,0 +1,287 @@
+//! Local health endpoint for the daemon.
+//!
+//! With `daemon --health-port <port>`, a tiny HTTP listener serves:
+//!
+//! - `GET /healthz` — `200 ok` when the last cycle completed within twice
+//!   the poll interval, `503` otherwise (including before the first cycle).
+//! - `GET /status` — the current [`DaemonSnapshot`] as JSON.
+//!
+//! It is meant for container liveness/readiness probes, so it binds to
+//! localhost unless told otherwise, answers one request per connection, and
+//! stops when the daemon does. `daemon --status` reads the same snapshot
+//! from `/status`, so both views stay in step.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_util::sync::CancellationToken;
+use tracing::debug;
+
+use crate::chain::failover::EndpointStatus;
+use crate::clock::unix_now;
+
+// ---------------------------------------------------------------------------
+// Constants
+// ---------------------------------------------------------------------------
+
+/// Default address the listener binds to.
+pub const DEFAULT_HOST: &str = "127.0.0.1";
+
+/// Largest request head read before giving up on a connection.
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+
+/// How long a client gets to send its request.
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Timeout for `daemon --status` fetching the snapshot.
+const FETCH_TIMEOUT: Duration = Duration::from_secs(3);
+
+// ---------------------------------------------------------------------------
+// Snapshot
+// ---------------------------------------------------------------------------
+
+/// What the daemon has been doing, as served on `/status` and shown by
+/// `daemon --status --json`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DaemonSnapshot {
+    /// Cycles completed since the daemon started.
+    pub cycles: u64,
+    /// How long the last cycle took, in milliseconds.
+    pub last_cycle_ms: Option<u64>,
+    /// Unix timestamp when the last cycle completed.
+    pub last_cycle_at: Option<u64>,
+    /// Validation jobs found in the last cycle.
+    pub pending_validations: u64,
+    /// Total price of the sales waiting to be claimed, in USDC base units.
+    pub claimable_usdc: u64,
+    /// Requests whose auto-claim failed too often and needs manual
+    /// attention.
+    #[serde(default)]
+    pub claims_needing_attention: Vec<String>,
+    /// Health of each configured chain RPC endpoint, in configured order.
+    #[serde(default)]
+    pub rpc_endpoints: Vec<EndpointStatus>,
+}
+
+impl DaemonSnapshot {
+    /// Whether the last cycle completed within `2 × interval` of `now`.
+    pub fn is_healthy(&self, now: u64, interval: Duration) -> bool {
+        let window = interval.as_secs().saturating_mul(2).max(1);
+        match self.last_cycle_at {
+            Some(at) => now.saturating_sub(at) <= window,
+            None => false,
+        }
+    }
+}
+
+/// Snapshot shared between the daemon loop and the listener.
+pub type SharedSnapshot = Arc<Mutex<DaemonSnapshot>>;
+
+// ---------------------------------------------------------------------------
+// Listener
+// ---------------------------------------------------------------------------
+
+/// Bind the health listener on `host:port`.
+pub async fn bind(host: &str, port: u16) -> Result<TcpListener> {
+    TcpListener::bind((host, port))
+        .await
+        .with_context(|| format!("could not listen for health checks on {host}:{port}"))
+}
+
+/// Answer health requests on `listener` until `shutdown` is cancelled.
+pub async fn serve(
+    listener: TcpListener,
+    snapshot: SharedSnapshot,
+    interval: Duration,
+    shutdown: CancellationToken,
+) {
+    loop {
+        let stream = tokio::select! {
+            _ = shutdown.cancelled() => break,
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(err) => {
+                    debug!(error = %err, "health listener accept failed");
+                    continue;
+                }
+            },
+        };
+        let snapshot = snapshot.clone();
+        tokio::spawn(async move {
+            if let Err(err) = handle(stream, &snapshot, interval).await {
+                debug!(error = %format!("{err:#}"), "health request failed");
+            }
+        });
+    }
+    debug!("health listener stopped");
+}
+
+async fn handle(
+    mut stream: TcpStream,
+    snapshot: &SharedSnapshot,
+    interval: Duration,
+) -> Result<()> {
+    let head = tokio::time::timeout(READ_TIMEOUT, read_head(&mut stream))
+        .await
+        .context("timed out reading request")??;
+    let current = snapshot.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    let (status, content_type, body) = respond(&head, &current, unix_now(), interval);
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// Read up to the end of the request head.
+async fn read_head(stream: &mut TcpStream) -> Result<String> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 1024];
+    while !buf.windows(4).any(|w| w == b"\r\n\r\n") && buf.len() < MAX_REQUEST_BYTES {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+    Ok(String::from_utf8_lossy(&buf).into_owned())
+}
+
+/// Status line, content type, and body for the request whose head is
+/// `head`.
+fn respond(
+    head: &str,
+    snapshot: &DaemonSnapshot,
+    now: u64,
+    interval: Duration,
+) -> (&'static str, &'static str, String) {
+    let mut parts = head.lines().next().unwrap_or_default().split_whitespace();
+    let (method, target) = (parts.next(), parts.next().unwrap_or_default());
+    let path = target.split('?').next().unwrap_or_default();
+
+    if method != Some("GET") {
+        return (
+            "405 Method Not Allowed",
+            "text/plain",
+            "method not allowed\n".into(),
+        );
+    }
+    match path {
+        "/healthz" if snapshot.is_healthy(now, interval) => ("200 OK", "text/plain", "ok\n".into()),
+        "/healthz" => ("503 Service Unavailable", "text/plain", "stale\n".into()),
+        "/status" => (
+            "200 OK",
+            "application/json",
+            serde_json::to_string(snapshot).unwrap_or_default(),
+        ),
+        _ => ("404 Not Found", "text/plain", "not found\n".into()),
+    }
+}
+
+/// Fetch the snapshot from a daemon listening on `host:port`.
+pub async fn fetch(host: &str, port: u16) -> Result<DaemonSnapshot> {
+    let client = reqwest::Client::builder().timeout(FETCH_TIMEOUT).build()?;
+    let snapshot = client
+        .get(format!("http://{host}:{port}/status"))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    Ok(snapshot)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INTERVAL: Duration = Duration::from_secs(60);
+
+    fn completed_at(at: u64) -> DaemonSnapshot {
+        DaemonSnapshot {
+            cycles: 4,
+            last_cycle_ms: Some(250),
+            last_cycle_at: Some(at),
+            pending_validations: 1,
+            claimable_usdc: 5_000_000,
+            claims_needing_attention: Vec::new(),
+            rpc_endpoints: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_healthy_within_twice_the_interval() {
+        let snapshot = completed_at(1_000);
+        assert!(snapshot.is_healthy(1_000, INTERVAL));
+        assert!(snapshot.is_healthy(1_120, INTERVAL));
+        assert!(!snapshot.is_healthy(1_121, INTERVAL));
+        assert!(!DaemonSnapshot::default().is_healthy(1_000, INTERVAL));
+    }
+
+    #[test]
+    fn test_respond_routes() {
+        let healthy = completed_at(1_000);
+        let get = |path: &str| format!("GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n");
+
+        assert_eq!(
+            respond(&get("/healthz"), &healthy, 1_010, INTERVAL).0,
+            "200 OK"
+        );
+        assert_eq!(
+            respond(&get("/healthz"), &healthy, 9_999, INTERVAL).0,
+            "503 Service Unavailable"
+        );
+        assert_eq!(
+            respond(&get("/nope"), &healthy, 1_010, INTERVAL).0,
+            "404 Not Found"
+        );
+        assert_eq!(
+            respond("POST /healthz HTTP/1.1\r\n\r\n", &healthy, 1_010, INTERVAL).0,
+            "405 Method Not Allowed"
+        );
+
+        let (status, content_type, body) = respond(&get("/status?x=1"), &healthy, 1_010, INTERVAL);
+        assert_eq!((status, content_type), ("200 OK", "application/json"));
+        let parsed: DaemonSnapshot = serde_json::from_str(&body).unwrap();
+        assert_eq!(parsed, healthy);
+    }
+
+    #[tokio::test]
+    async fn test_serve_answers_until_shutdown() {
+        let listener = bind(DEFAULT_HOST, 0).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let snapshot = SharedSnapshot::default();
+        let shutdown = CancellationToken::new();
+        let server = tokio::spawn(serve(
+            listener,
+            snapshot.clone(),
+            INTERVAL,
+            shutdown.clone(),
+        ));
+
+        let healthz = format!("http://{DEFAULT_HOST}:{port}/healthz");
+        let response = reqwest::get(&healthz).await.unwrap();
+        assert_eq!(response.status().as_u16(), 503);
+
+        *snapshot.lock().unwrap() = completed_at(unix_now());
+        let response = reqwest::get(&healthz).await.unwrap();
+        assert_eq!(response.status().as_u16(), 200);
+        assert_eq!(fetch(DEFAULT_HOST, port).await.unwrap().cycles, 4);
+
+        shutdown.cancel();
+        tokio::time::timeout(Duration::from_secs(5), server)
+            .await
+            .expect("listener should stop on shutdown")
+            .unwrap();
+    }
+}
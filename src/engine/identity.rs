@@ -1,18 +1,23 @@
 //! Agent identity management for AgentMarket CLI.
 //!
 //! Handles keypair generation (secp256k1), public key / Ethereum address
-//! derivation, and the agent profile schema used for ERC-8004 registration.
+//! derivation, message signing, and the agent profile schema used for
+//! ERC-8004 registration.
 //!
 //! All cryptographic operations delegate to the `alloy` crate. No blockchain
 //! or IPFS terminology is exposed beyond this module.
 
 use std::fs;
 
-use alloy::signers::local::PrivateKeySigner;
-use anyhow::{Context, Result};
+use alloy::primitives::{Address, Signature};
+use alloy::signers::k256::ecdsa::VerifyingKey;
+use alloy::signers::local::coins_bip39::{English, Mnemonic};
+use alloy::signers::local::{MnemonicBuilder, PrivateKeySigner};
+use alloy::signers::SignerSync;
+use anyhow::{bail, Context, Result};
 use serde::{Deserialize, Serialize};
 use tracing::debug;
-use zeroize::Zeroize;
+use zeroize::{Zeroize, Zeroizing};
 
 use crate::config::store::{config_dir, Config};
 
@@ -63,8 +68,14 @@ pub enum IdentityState {
 // Constants
 // ---------------------------------------------------------------------------
 
+/// BIP-44 path of the first Ethereum account, used by common wallets.
+pub const ETHEREUM_DERIVATION_PATH: &str = "m/44'/60'/0'/0/0";
+
+/// Words in a generated recovery phrase.
+pub const MNEMONIC_WORDS: usize = 24;
+
 /// Name of the profile file inside the config directory.
-const PROFILE_FILE: &str = "profile.json";
+pub const PROFILE_FILE: &str = "profile.json";
 
 /// Current profile schema version.
 const PROFILE_VERSION: &str = "0.1.0";
@@ -84,9 +95,51 @@ const PROFILE_VERSION: &str = "0.1.0";
 ///   (`0x`-prefixed).
 pub fn generate_keypair() -> Result<(Vec<u8>, String, String)> {
     let signer = PrivateKeySigner::random();
+    debug!(address = %signer.address(), "generated new keypair");
+    Ok(keypair_from_signer(&signer))
+}
 
+/// Generate a new BIP-39 recovery phrase of [`MNEMONIC_WORDS`] words and the
+/// keypair it derives at [`ETHEREUM_DERIVATION_PATH`].
+///
+/// Returns `(phrase, private_key_bytes, hex_public_key, checksummed_address)`;
+/// the last three are as for [`generate_keypair`].
+pub fn generate_mnemonic_keypair() -> Result<(Zeroizing<String>, Vec<u8>, String, String)> {
+    let mnemonic = Mnemonic::<English>::new_with_count(&mut rand::thread_rng(), MNEMONIC_WORDS)
+        .context("failed to generate recovery phrase")?;
+    let phrase = Zeroizing::new(mnemonic.to_phrase());
+
+    let (private_key_bytes, public_key_hex, address) = keypair_from_mnemonic(&phrase)?;
+    debug!(address = %address, "generated new keypair from recovery phrase");
+    Ok((phrase, private_key_bytes, public_key_hex, address))
+}
+
+/// Derive the keypair for a BIP-39 recovery phrase at
+/// [`ETHEREUM_DERIVATION_PATH`], the same key other Ethereum wallets derive
+/// from it.
+///
+/// Case and extra whitespace in `phrase` are ignored. Returns the same tuple
+/// as [`generate_keypair`].
+pub fn keypair_from_mnemonic(phrase: &str) -> Result<(Vec<u8>, String, String)> {
+    let normalized = Zeroizing::new(
+        phrase
+            .split_whitespace()
+            .map(str::to_lowercase)
+            .collect::<Vec<_>>()
+            .join(" "),
+    );
+    let signer = MnemonicBuilder::<English>::default()
+        .phrase(normalized.as_str())
+        .derivation_path(ETHEREUM_DERIVATION_PATH)?
+        .build()
+        .map_err(|e| anyhow::anyhow!("invalid recovery phrase: {e}"))?;
+    Ok(keypair_from_signer(&signer))
+}
+
+/// Extract `(private_key_bytes, hex_public_key, checksummed_address)` from a
+/// signer.
+fn keypair_from_signer(signer: &PrivateKeySigner) -> (Vec<u8>, String, String) {
     let address = format!("{}", signer.address());
-    debug!(address = %address, "generated new keypair");
 
     // Extract private key bytes — the to_bytes() -> to_vec() chain creates an
     // intermediate GenericArray on the stack that is copied into the Vec.  We
@@ -108,7 +161,7 @@ pub fn generate_keypair() -> Result<(Vec<u8>, String, String)> {
         "derived compressed public key"
     );
 
-    Ok((private_key_bytes, public_key_hex, address))
+    (private_key_bytes, public_key_hex, address)
 }
 
 /// Derive the hex-encoded compressed public key and checksummed Ethereum
@@ -145,6 +198,142 @@ pub fn address_from_key(private_key_bytes: &[u8]) -> Result<(String, String)> {
     result
 }
 
+/// Parse a hex-encoded private key, as exported by common wallets.
+///
+/// Accepts an optional `0x` prefix and surrounding whitespace. The key must
+/// be 32 bytes and a valid secp256k1 scalar.
+pub fn parse_private_key_hex(input: &str) -> Result<Zeroizing<Vec<u8>>> {
+    let trimmed = input.trim();
+    let digits = trimmed
+        .strip_prefix("0x")
+        .or_else(|| trimmed.strip_prefix("0X"))
+        .unwrap_or(trimmed);
+
+    let key_bytes = Zeroizing::new(
+        hex::decode(digits).map_err(|_| anyhow::anyhow!("private key is not valid hex"))?,
+    );
+    if key_bytes.len() != 32 {
+        bail!(
+            "private key must be exactly 32 bytes (64 hex characters), got {} bytes",
+            key_bytes.len()
+        );
+    }
+    address_from_key(&key_bytes)?;
+    Ok(key_bytes)
+}
+
+/// Derive the checksummed Ethereum address from a hex-encoded compressed
+/// public key, without needing the private key.
+pub fn address_from_public_key(public_key_hex: &str) -> Result<String> {
+    let bytes = hex::decode(public_key_hex.trim_start_matches("0x"))
+        .map_err(|_| anyhow::anyhow!("public key is not valid hex"))?;
+    let key = VerifyingKey::from_sec1_bytes(&bytes)
+        .map_err(|_| anyhow::anyhow!("public key is not a valid secp256k1 point"))?;
+    Ok(format!("{}", Address::from_public_key(&key)))
+}
+
+/// Parse an address typed by the operator, e.g. a withdrawal destination.
+///
+/// The address must be `0x` followed by 40 hex digits and must not be the
+/// zero address. Mixed case must match its EIP-55 checksum, which catches
+/// most typos. An address in a single case carries no checksum and is
+/// refused unless `require_checksum` is `false` (`--no-checksum`).
+pub fn validate_address(address: &str, require_checksum: bool) -> Result<Address> {
+    let Some(hex_part) = address.strip_prefix("0x") else {
+        bail!("Invalid address: must start with \"0x\". Got: \"{address}\"");
+    };
+    if address.len() != 42 {
+        bail!(
+            "Invalid address: must be 42 characters (0x + 40 hex digits). Got {} characters.",
+            address.len()
+        );
+    }
+    if !hex_part.chars().all(|c| c.is_ascii_hexdigit()) {
+        bail!("Invalid address: contains non-hexadecimal characters. Got: \"{address}\"");
+    }
+
+    let parsed: Address = address
+        .parse()
+        .with_context(|| format!("invalid address: {address}"))?;
+    if parsed == Address::ZERO {
+        bail!("Invalid address: {address} is the zero address; anything sent there is lost.");
+    }
+
+    let has_lower = hex_part.chars().any(|c| c.is_ascii_lowercase());
+    let has_upper = hex_part.chars().any(|c| c.is_ascii_uppercase());
+    if has_lower && has_upper {
+        if parsed.to_checksum(None) != address {
+            bail!(
+                "Address {address} fails its EIP-55 checksum, so it probably has a typo. \
+                 Copy it again from its source."
+            );
+        }
+    } else if require_checksum && (has_lower || has_upper) {
+        bail!(
+            "Address {address} has no EIP-55 checksum, so a typo would go unnoticed. \
+             Copy the mixed-case form from its source, or pass --no-checksum."
+        );
+    }
+    Ok(parsed)
+}
+
+// ---------------------------------------------------------------------------
+// Message signing
+// ---------------------------------------------------------------------------
+
+/// Sign `message` with the agent's key the way wallets do for
+/// `personal_sign`: the EIP-191 prefix
+/// (`"\x19Ethereum Signed Message:\n" + len`) is added before hashing, so
+/// the signature can never be replayed as a transaction.
+///
+/// Returns the 65-byte `r || s || v` signature as `0x`-prefixed hex, with
+/// `v` as 27 or 28.
+pub fn sign_message(private_key_bytes: &[u8], message: &[u8]) -> Result<String> {
+    let signer = PrivateKeySigner::from_slice(private_key_bytes)
+        .context("failed to construct signer from private key bytes")?;
+    let signature = signer
+        .sign_message_sync(message)
+        .context("failed to sign message")?;
+    debug!(
+        address = %signer.address(),
+        message_len = message.len(),
+        "signed message"
+    );
+    Ok(format!("0x{}", hex::encode(signature.as_bytes())))
+}
+
+/// Check that `signature` (as produced by [`sign_message`] or a wallet's
+/// `personal_sign`) was made over `message` by the key behind `address`.
+///
+/// Returns `Ok(false)` when the signature is well-formed but recovers to
+/// another address, which is also what a tampered message looks like. A
+/// malformed address or signature is an error.
+pub fn verify_message(address: &str, message: &[u8], signature: &str) -> Result<bool> {
+    let expected = validate_address(address, false)?;
+    let signature = parse_signature(signature)?;
+    let signer = signature
+        .recover_address_from_msg(message)
+        .map_err(|_| anyhow::anyhow!("Invalid signature: no key could have produced it."))?;
+    debug!(%signer, %expected, "recovered message signer");
+    Ok(signer == expected)
+}
+
+/// Parse a `0x`-prefixed 65-byte signature, with `v` as 27/28 or 0/1.
+pub(crate) fn parse_signature(signature: &str) -> Result<Signature> {
+    let trimmed = signature.trim();
+    let digits = trimmed.strip_prefix("0x").unwrap_or(trimmed);
+    let bytes =
+        hex::decode(digits).map_err(|_| anyhow::anyhow!("Invalid signature: not valid hex."))?;
+    if bytes.len() != 65 {
+        bail!(
+            "Invalid signature: must be 65 bytes (130 hex digits), got {} bytes.",
+            bytes.len()
+        );
+    }
+    Signature::from_raw(&bytes)
+        .map_err(|_| anyhow::anyhow!("Invalid signature: its last byte (v) must be 27 or 28."))
+}
+
 // ---------------------------------------------------------------------------
 // Profile helpers
 // ---------------------------------------------------------------------------
@@ -200,6 +389,83 @@ pub fn load_profile() -> Result<AgentProfile> {
     Ok(profile)
 }
 
+/// Split a comma-separated capability list, trimming entries and dropping
+/// empty ones.
+pub fn parse_capabilities(raw: &str) -> Vec<String> {
+    raw.split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// Fields `profile update` may change. `None` leaves a field as it is.
+#[derive(Clone, Debug, Default)]
+pub struct ProfileChanges {
+    pub description: Option<String>,
+    pub capabilities: Option<Vec<String>>,
+    pub pricing_usd: Option<f64>,
+}
+
+impl ProfileChanges {
+    /// Returns `true` if no field is set.
+    pub fn is_empty(&self) -> bool {
+        self.description.is_none() && self.capabilities.is_none() && self.pricing_usd.is_none()
+    }
+}
+
+/// One field that differs between two profiles, rendered for display.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub old: String,
+    pub new: String,
+}
+
+/// Copy of `profile` with `changes` applied and the schema version brought
+/// up to date.
+pub fn apply_profile_changes(profile: &AgentProfile, changes: &ProfileChanges) -> AgentProfile {
+    let mut updated = profile.clone();
+    if let Some(description) = &changes.description {
+        updated.description = description.clone();
+    }
+    if let Some(capabilities) = &changes.capabilities {
+        updated.capabilities = capabilities.clone();
+    }
+    if let Some(pricing_usd) = changes.pricing_usd {
+        updated.pricing_usd = pricing_usd;
+    }
+    updated.version = PROFILE_VERSION.to_string();
+    updated
+}
+
+/// The user-visible fields that differ between `old` and `new`.
+pub fn diff_profiles(old: &AgentProfile, new: &AgentProfile) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    let mut compare = |field, old: String, new: String| {
+        if old != new {
+            changes.push(FieldChange { field, old, new });
+        }
+    };
+
+    compare("name", old.name.clone(), new.name.clone());
+    compare(
+        "description",
+        old.description.clone(),
+        new.description.clone(),
+    );
+    compare(
+        "capabilities",
+        old.capabilities.join(", "),
+        new.capabilities.join(", "),
+    );
+    compare(
+        "price",
+        format!("${:.2}", old.pricing_usd),
+        format!("${:.2}", new.pricing_usd),
+    );
+    changes
+}
+
 // ---------------------------------------------------------------------------
 // Identity state
 // ---------------------------------------------------------------------------
@@ -306,6 +572,75 @@ mod tests {
         assert_ne!(addr_a, addr_b, "two random addresses should differ");
     }
 
+    #[test]
+    fn test_mnemonic_known_vectors() {
+        // Well-known test phrases and the first address every Ethereum
+        // wallet derives from them; a change here means derivation drifted.
+        let (_, _, address) = keypair_from_mnemonic(
+            "abandon abandon abandon abandon abandon abandon \
+             abandon abandon abandon abandon abandon about",
+        )
+        .unwrap();
+        assert_eq!(address, "0x9858EfFD232B4033E47d90003D41EC34EcaEda94");
+
+        let (key_bytes, public_key, address) = keypair_from_mnemonic(
+            "  Test test test test test test test test test test test JUNK\n",
+        )
+        .unwrap();
+        assert_eq!(address, "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        assert_eq!(
+            hex::encode(&key_bytes),
+            "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+        );
+        assert_eq!(address_from_key(&key_bytes).unwrap(), (public_key, address));
+
+        // Bad checksum word and unknown words are rejected.
+        assert!(keypair_from_mnemonic(&"abandon ".repeat(12)).is_err());
+        assert!(keypair_from_mnemonic("not a recovery phrase").is_err());
+    }
+
+    #[test]
+    fn test_address_from_public_key_matches_private_key() {
+        let (key_bytes, public_key, address) = generate_keypair().unwrap();
+        assert_eq!(address_from_public_key(&public_key).unwrap(), address);
+        assert_eq!(address_from_key(&key_bytes).unwrap().1, address);
+        assert!(address_from_public_key("02ab").is_err());
+        assert!(address_from_public_key("zz").is_err());
+    }
+
+    #[test]
+    fn test_generate_mnemonic_keypair_recovers() {
+        let (phrase, key_bytes, public_key, address) = generate_mnemonic_keypair().unwrap();
+        assert_eq!(phrase.split_whitespace().count(), MNEMONIC_WORDS);
+        assert_eq!(
+            keypair_from_mnemonic(&phrase).unwrap(),
+            (key_bytes, public_key, address)
+        );
+    }
+
+    #[test]
+    fn test_parse_private_key_hex() {
+        let (private_key, _, address) = generate_keypair().expect("keypair");
+        let encoded = hex::encode(&private_key);
+
+        for input in [encoded.clone(), format!("0x{encoded}\n")] {
+            let parsed = parse_private_key_hex(&input).expect("valid key should parse");
+            assert_eq!(*parsed, private_key);
+            assert_eq!(address_from_key(&parsed).unwrap().1, address);
+        }
+    }
+
+    #[test]
+    fn test_parse_private_key_hex_rejects_bad_input() {
+        let err = parse_private_key_hex(&"ab".repeat(31)).unwrap_err();
+        assert!(err.to_string().contains("got 31 bytes"), "{err}");
+        assert!(parse_private_key_hex(&"ab".repeat(33)).is_err());
+        assert!(parse_private_key_hex("0xnot-hex").is_err());
+        assert!(parse_private_key_hex("").is_err());
+        // Zero is not a valid secp256k1 scalar.
+        assert!(parse_private_key_hex(&"00".repeat(32)).is_err());
+    }
+
     // -- Identity state -------------------------------------------------------
 
     #[test]
@@ -320,6 +655,7 @@ mod tests {
                 ipfs_profile_cid: String::new(),
             },
             services: ServicesConfig::default(),
+            ..Default::default()
         };
         assert_eq!(get_identity_state(&config), IdentityState::Uninitialized);
 
@@ -404,4 +740,172 @@ mod tests {
         let p = create_profile("n", "d", vec![], 0.0, "pk", "addr");
         assert_eq!(p.version, "0.1.0");
     }
+
+    // -- Profile updates ------------------------------------------------------
+
+    #[test]
+    fn test_parse_capabilities() {
+        assert_eq!(
+            parse_capabilities(" rust, ,review ,"),
+            vec!["rust".to_string(), "review".to_string()]
+        );
+        assert!(parse_capabilities("").is_empty());
+    }
+
+    #[test]
+    fn test_apply_profile_changes_only_touches_given_fields() {
+        let mut old = create_profile("n", "d", vec!["a".into()], 5.0, "pk", "addr");
+        old.version = "0.0.1".to_string();
+
+        let unchanged = apply_profile_changes(&old, &ProfileChanges::default());
+        assert!(ProfileChanges::default().is_empty());
+        assert!(diff_profiles(&old, &unchanged).is_empty());
+        assert_eq!(unchanged.version, PROFILE_VERSION);
+
+        let changes = ProfileChanges {
+            description: Some("new".into()),
+            capabilities: None,
+            pricing_usd: Some(7.5),
+        };
+        let new = apply_profile_changes(&old, &changes);
+        assert_eq!(new.capabilities, old.capabilities);
+        assert_eq!(new.public_key, "pk");
+        assert_eq!(
+            diff_profiles(&old, &new),
+            vec![
+                FieldChange {
+                    field: "description",
+                    old: "d".into(),
+                    new: "new".into(),
+                },
+                FieldChange {
+                    field: "price",
+                    old: "$5.00".into(),
+                    new: "$7.50".into(),
+                },
+            ]
+        );
+    }
+
+    // -- Message signing ---------------------------------------------------
+
+    /// The first well-known development key (Anvil/Hardhat account 0).
+    const DEV_KEY: &str = "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+    const DEV_ADDRESS: &str = "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266";
+
+    /// `personal_sign` of "hello world" by [`DEV_KEY`], as produced by other
+    /// EIP-191 implementations. Pinned so the format cannot drift silently.
+    const HELLO_WORLD_SIG: &str = "0xa461f509887bd19e312c0c58467ce8ff8e300d3c1a90b608a760c5b80318eaf15fe57c96f9175d6cd4daad4663763baa7e78836e067d0163e9a2ccf2ff753f5b1b";
+
+    fn dev_key() -> Vec<u8> {
+        hex::decode(DEV_KEY).unwrap()
+    }
+
+    #[test]
+    fn test_sign_message_known_vector() {
+        let signature = sign_message(&dev_key(), b"hello world").unwrap();
+        assert_eq!(signature, HELLO_WORLD_SIG);
+        assert_eq!(signature.len(), 2 + 65 * 2);
+
+        // An empty message is signed too (its prefix ends in "\n0").
+        let empty = sign_message(&dev_key(), b"").unwrap();
+        assert!(verify_message(DEV_ADDRESS, b"", &empty).unwrap());
+    }
+
+    #[test]
+    fn test_verify_message_recovers_the_signer() {
+        assert!(verify_message(DEV_ADDRESS, b"hello world", HELLO_WORLD_SIG).unwrap());
+        // Lowercase addresses and v as 0/1 are accepted.
+        let lower = DEV_ADDRESS.to_lowercase();
+        assert!(verify_message(&lower, b"hello world", HELLO_WORLD_SIG).unwrap());
+        let zero_v = format!("{}00", &HELLO_WORLD_SIG[..HELLO_WORLD_SIG.len() - 2]);
+        assert!(verify_message(DEV_ADDRESS, b"hello world", &zero_v).unwrap());
+
+        // A tampered message or another signer does not verify.
+        assert!(!verify_message(DEV_ADDRESS, b"hello world!", HELLO_WORLD_SIG).unwrap());
+        let (_, other) = address_from_key(&[7u8; 32]).unwrap();
+        assert!(!verify_message(&other, b"hello world", HELLO_WORLD_SIG).unwrap());
+    }
+
+    #[test]
+    fn test_verify_message_rejects_malformed_signatures() {
+        let cases = [
+            ("0x1234", "65 bytes"),
+            ("0xzz", "not valid hex"),
+            (
+                &format!("{}05", &HELLO_WORLD_SIG[..HELLO_WORLD_SIG.len() - 2]),
+                "27 or 28",
+            ),
+        ];
+        for (signature, expected) in cases {
+            let err = verify_message(DEV_ADDRESS, b"hello world", signature)
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains(expected), "{signature}: {err}");
+        }
+        assert!(verify_message("0x1234", b"hello world", HELLO_WORLD_SIG).is_err());
+    }
+
+    // -- validate_address --------------------------------------------------
+
+    /// EIP-55 test vector.
+    const CHECKSUMMED: &str = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+
+    #[test]
+    fn test_validate_address_accepts_checksummed() {
+        let parsed = validate_address(CHECKSUMMED, true).unwrap();
+        assert_eq!(parsed.to_checksum(None), CHECKSUMMED);
+    }
+
+    #[test]
+    fn test_validate_address_rejects_bad_checksum() {
+        let typo = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD";
+        for require_checksum in [true, false] {
+            let err = validate_address(typo, require_checksum)
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains("fails its EIP-55 checksum"), "{err}");
+        }
+    }
+
+    #[test]
+    fn test_validate_address_single_case_needs_no_checksum_flag() {
+        let lower = CHECKSUMMED.to_lowercase();
+        let upper = format!("0x{}", CHECKSUMMED[2..].to_uppercase());
+        for address in [&lower, &upper] {
+            let err = validate_address(address, true).unwrap_err().to_string();
+            assert!(err.contains("--no-checksum"), "{err}");
+            assert_eq!(
+                validate_address(address, false).unwrap().to_checksum(None),
+                CHECKSUMMED
+            );
+        }
+        // Digits only: there is nothing to checksum.
+        let digits = "0x1234567890123456789012345678901234567890";
+        assert!(validate_address(digits, true).is_ok());
+    }
+
+    #[test]
+    fn test_validate_address_rejects_malformed() {
+        let cases = [
+            (
+                "5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+                "must start with \"0x\"",
+            ),
+            ("0x1234", "42 characters"),
+            (
+                "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed00",
+                "42 characters",
+            ),
+            (
+                "0xGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGG",
+                "non-hexadecimal",
+            ),
+            ("0x0000000000000000000000000000000000000000", "zero address"),
+        ];
+        for (address, expected) in cases {
+            let err = validate_address(address, false).unwrap_err().to_string();
+            assert!(err.contains(expected), "{address}: {err}");
+        }
+    }
 }
@@ -0,0 +1,269 @@
+//! Local store of received mailbox messages.
+//!
+//! Mailbox announcements are not kept by the network, so every message
+//! fetched is saved to `~/.agentmarket/messages/{cid}.json` along with
+//! whether it has been read. Messages that could not be fetched or
+//! decrypted are saved too, with the error, so they are reported once
+//! rather than silently dropped.
+
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::config::store::config_dir;
+use crate::ipfs::mailbox::MailboxMessage;
+
+/// Directory under the config directory holding received messages.
+const MESSAGES_DIR: &str = "messages";
+
+/// Characters of payload shown by [`StoredMessage::preview`].
+pub const PREVIEW_CHARS: usize = 40;
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+/// A received message and its local read state.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StoredMessage {
+    /// CID the message was announced under.
+    pub id: String,
+    /// Unix timestamp when this machine received it.
+    pub received_at: u64,
+    #[serde(default)]
+    pub read: bool,
+    /// The decrypted message, or `None` if it was unreadable.
+    pub message: Option<MailboxMessage>,
+    /// Why the message could not be read.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl StoredMessage {
+    /// A message that was fetched and decrypted.
+    pub fn opened(id: &str, received_at: u64, message: MailboxMessage) -> Self {
+        Self {
+            id: id.to_string(),
+            received_at,
+            read: false,
+            message: Some(message),
+            error: None,
+        }
+    }
+
+    /// A message that could not be fetched or decrypted.
+    pub fn unreadable(id: &str, received_at: u64, error: String) -> Self {
+        Self {
+            id: id.to_string(),
+            received_at,
+            read: false,
+            message: None,
+            error: Some(error),
+        }
+    }
+
+    /// When the sender created the message, falling back to when it was
+    /// received for unreadable messages.
+    pub fn sent_at(&self) -> u64 {
+        self.message
+            .as_ref()
+            .map_or(self.received_at, |m| m.timestamp)
+    }
+
+    /// The payload as text, or `None` if it is not UTF-8.
+    pub fn payload_text(&self) -> Option<&str> {
+        self.message
+            .as_ref()
+            .and_then(|m| std::str::from_utf8(&m.payload).ok())
+    }
+
+    /// One-line summary of the payload: the start of the text on a single
+    /// line, the size for binary payloads, or the error.
+    pub fn preview(&self) -> String {
+        let Some(message) = &self.message else {
+            return format!("unreadable: {}", self.error.as_deref().unwrap_or("unknown"));
+        };
+        let Some(text) = self.payload_text() else {
+            return format!("<{} bytes of binary data>", message.payload.len());
+        };
+
+        let line = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if line.chars().count() > PREVIEW_CHARS {
+            let cut: String = line.chars().take(PREVIEW_CHARS).collect();
+            format!("{cut}...")
+        } else {
+            line
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Persistence
+// ---------------------------------------------------------------------------
+
+/// Returns the path to the messages directory, creating it if needed.
+fn messages_dir() -> Result<PathBuf> {
+    let dir = config_dir()?.join(MESSAGES_DIR);
+
+    if !dir.exists() {
+        debug!(path = %dir.display(), "creating messages directory");
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create messages directory: {}", dir.display()))?;
+    }
+
+    Ok(dir)
+}
+
+fn message_path(id: &str) -> Result<PathBuf> {
+    // CIDs are alphanumeric; anything else would escape the directory.
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric()) {
+        bail!("Invalid message ID '{id}'.");
+    }
+    Ok(messages_dir()?.join(format!("{id}.json")))
+}
+
+/// Whether a message with this ID has already been received.
+pub fn contains(id: &str) -> Result<bool> {
+    Ok(message_path(id)?.exists())
+}
+
+/// Save `message`, replacing any earlier copy.
+pub fn save(message: &StoredMessage) -> Result<()> {
+    let path = message_path(&message.id)?;
+    debug!(path = %path.display(), "saving message");
+
+    let json = serde_json::to_string_pretty(message).context("failed to serialise message")?;
+    fs::write(&path, json)
+        .with_context(|| format!("failed to write message: {}", path.display()))?;
+    Ok(())
+}
+
+/// All received messages, oldest first. Files that fail to parse are
+/// skipped.
+pub fn load_all() -> Result<Vec<StoredMessage>> {
+    let dir = messages_dir()?;
+    let entries = fs::read_dir(&dir)
+        .with_context(|| format!("failed to read messages directory: {}", dir.display()))?;
+
+    let mut messages = Vec::new();
+    for entry in entries {
+        let path = entry.context("failed to read directory entry")?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let parsed = fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|json| Ok(serde_json::from_str::<StoredMessage>(&json)?));
+        match parsed {
+            Ok(message) => messages.push(message),
+            Err(err) => debug!(path = %path.display(), error = %err, "skipping message file"),
+        }
+    }
+
+    messages.sort_by(|a, b| a.sent_at().cmp(&b.sent_at()).then(a.id.cmp(&b.id)));
+    debug!(count = messages.len(), "messages loaded");
+    Ok(messages)
+}
+
+/// Find a message by its full ID or by the short ID shown in listings
+/// (the end of the full ID).
+pub fn find<'m>(messages: &'m [StoredMessage], id: &str) -> Result<&'m StoredMessage> {
+    if let Some(exact) = messages.iter().find(|m| m.id == id) {
+        return Ok(exact);
+    }
+    let matches: Vec<&StoredMessage> = messages.iter().filter(|m| m.id.ends_with(id)).collect();
+    match matches.as_slice() {
+        [only] => Ok(only),
+        [] => bail!("No message with ID {id}."),
+        _ => bail!("More than one message matches {id}; use the full ID."),
+    }
+}
+
+/// Mark the messages with these IDs as read.
+pub fn mark_read<'a>(ids: impl IntoIterator<Item = &'a str>) -> Result<()> {
+    for id in ids {
+        let path = message_path(id)?;
+        let json = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read message: {}", path.display()))?;
+        let mut message: StoredMessage = serde_json::from_str(&json)
+            .with_context(|| format!("failed to parse message: {}", path.display()))?;
+        if !message.read {
+            message.read = true;
+            save(&message)?;
+        }
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Serialises tests that point `AGENTMARKET_HOME` at a temp directory.
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    fn message(payload: &[u8], timestamp: u64) -> MailboxMessage {
+        MailboxMessage {
+            sender: "02abcdef".to_string(),
+            timestamp,
+            message_type: "note".to_string(),
+            payload: payload.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_preview() {
+        let short = StoredMessage::opened("bafy1", 0, message(b"can you do\n 10 pages?", 0));
+        assert_eq!(short.preview(), "can you do 10 pages?");
+
+        let long = StoredMessage::opened("bafy2", 0, message(&[b'a'; 50], 0));
+        assert_eq!(long.preview(), format!("{}...", "a".repeat(PREVIEW_CHARS)));
+
+        let binary = StoredMessage::opened("bafy3", 0, message(&[0xff, 0xfe], 0));
+        assert_eq!(binary.preview(), "<2 bytes of binary data>");
+
+        let bad = StoredMessage::unreadable("bafy4", 0, "decryption failed".to_string());
+        assert_eq!(bad.preview(), "unreadable: decryption failed");
+    }
+
+    #[test]
+    fn test_store_round_trip_and_read_state() {
+        let _guard = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let tmp = tempfile::tempdir().unwrap();
+        std::env::set_var("AGENTMARKET_HOME", tmp.path());
+
+        let later = StoredMessage::opened("bafylater", 50, message(b"second", 20));
+        let earlier = StoredMessage::unreadable("bafyearlier", 10, "bad".to_string());
+        save(&later).unwrap();
+        save(&earlier).unwrap();
+        assert!(contains("bafylater").unwrap());
+        assert!(!contains("bafyother").unwrap());
+        assert!(contains("../config").is_err());
+
+        let all = load_all().unwrap();
+        assert_eq!(
+            all.iter().map(|m| m.id.as_str()).collect::<Vec<_>>(),
+            ["bafyearlier", "bafylater"]
+        );
+        assert!(all.iter().all(|m| !m.read));
+
+        assert_eq!(find(&all, "later").unwrap().id, "bafylater");
+        assert!(find(&all, "bafy").is_err());
+        assert!(find(&all, "missing").is_err());
+
+        mark_read(["bafylater"]).unwrap();
+        let all = load_all().unwrap();
+        assert!(find(&all, "bafylater").unwrap().read);
+        assert!(!find(&all, "bafyearlier").unwrap().read);
+
+        std::env::remove_var("AGENTMARKET_HOME");
+    }
+}
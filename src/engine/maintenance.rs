@@ -0,0 +1,542 @@
+//! Housekeeping tasks and the scheduler that runs them.
+//!
+//! Each chore (expiring past-deadline requests, upgrading old cache files,
+//! clearing stale locks, ...) is a [`MaintenanceTask`]. `agentmarket
+//! maintenance run` runs any subset on demand; the daemon runs each task at
+//! its own cadence through a [`Scheduler`], configured in one
+//! `[maintenance]` table:
+//!
+//! ```toml
+//! [maintenance]
+//! sweep = 60       # seconds between runs
+//! locks = 0        # never run from the daemon
+//! ```
+//!
+//! Every task supports a dry run, and every run produces a
+//! [`MaintenanceReport`] listing what each task did (or would do). A task
+//! that fails is recorded in the report without stopping the others.
+//!
+//! New tasks implement [`MaintenanceTask`] and are added to [`all_tasks`].
+
+use std::collections::BTreeMap;
+
+use anyhow::{bail, Result};
+use serde::Serialize;
+use tracing::debug;
+
+use crate::config::lock::{self, LockState};
+use crate::config::store::Config;
+use crate::engine::requests::RequestCache;
+
+// ---------------------------------------------------------------------------
+// Task trait
+// ---------------------------------------------------------------------------
+
+/// What a task gets to work with.
+pub struct TaskContext<'a> {
+    pub cfg: &'a Config,
+    /// Current Unix timestamp.
+    pub now: u64,
+    /// Report what would be done without changing anything.
+    pub dry_run: bool,
+}
+
+/// One housekeeping chore.
+pub trait MaintenanceTask {
+    /// Short name used on the command line and in `[maintenance]`.
+    fn name(&self) -> &'static str;
+
+    /// One-line description for `maintenance list`.
+    fn description(&self) -> &'static str;
+
+    /// Seconds between daemon runs when `[maintenance]` does not say.
+    fn default_every_secs(&self) -> u64;
+
+    /// Do the work (or, in a dry run, work out what it would be) and return
+    /// one line per action taken.
+    fn run(&self, ctx: &TaskContext) -> Result<Vec<String>>;
+}
+
+/// Every built-in task, in the order `--all` runs them.
+pub fn all_tasks() -> Vec<Box<dyn MaintenanceTask>> {
+    vec![
+        Box::new(SweepTask),
+        Box::new(MigrateTask),
+        Box::new(LocksTask),
+    ]
+}
+
+/// Pick the tasks named in `names` from `tasks`, keeping `tasks`' order.
+/// With `all`, every task is selected and `names` must be empty.
+pub fn select<'t>(
+    tasks: &'t [Box<dyn MaintenanceTask>],
+    names: &[String],
+    all: bool,
+) -> Result<Vec<&'t dyn MaintenanceTask>> {
+    let known = || {
+        tasks
+            .iter()
+            .map(|t| t.name())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    if all {
+        if !names.is_empty() {
+            bail!("Use either --all or --tasks, not both.");
+        }
+        return Ok(tasks.iter().map(|t| t.as_ref()).collect());
+    }
+    if names.is_empty() {
+        bail!(
+            "Choose tasks with --tasks or run everything with --all. Tasks: {}.",
+            known()
+        );
+    }
+
+    for name in names {
+        if !tasks
+            .iter()
+            .any(|t| t.name().eq_ignore_ascii_case(name.trim()))
+        {
+            bail!("Unknown maintenance task \"{name}\". Tasks: {}.", known());
+        }
+    }
+    Ok(tasks
+        .iter()
+        .filter(|t| {
+            names
+                .iter()
+                .any(|n| t.name().eq_ignore_ascii_case(n.trim()))
+        })
+        .map(|t| t.as_ref())
+        .collect())
+}
+
+// ---------------------------------------------------------------------------
+// Reports
+// ---------------------------------------------------------------------------
+
+/// What one task did.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct TaskReport {
+    pub task: String,
+    pub actions: Vec<String>,
+    /// Set when the task failed; `actions` is then empty.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Consolidated result of running a set of tasks.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct MaintenanceReport {
+    pub dry_run: bool,
+    pub tasks: Vec<TaskReport>,
+}
+
+impl MaintenanceReport {
+    /// Actions across every task.
+    pub fn total_actions(&self) -> usize {
+        self.tasks.iter().map(|t| t.actions.len()).sum()
+    }
+
+    /// Tasks that failed.
+    pub fn failures(&self) -> impl Iterator<Item = &TaskReport> {
+        self.tasks.iter().filter(|t| t.error.is_some())
+    }
+}
+
+/// Run `tasks` in order, collecting a report. A failing task is recorded and
+/// the rest still run.
+pub fn run_tasks(tasks: &[&dyn MaintenanceTask], ctx: &TaskContext) -> MaintenanceReport {
+    let mut report = MaintenanceReport {
+        dry_run: ctx.dry_run,
+        tasks: Vec::with_capacity(tasks.len()),
+    };
+
+    for task in tasks {
+        debug!(
+            task = task.name(),
+            dry_run = ctx.dry_run,
+            "running maintenance task"
+        );
+        let (actions, error) = match task.run(ctx) {
+            Ok(actions) => (actions, None),
+            Err(err) => {
+                debug!(task = task.name(), error = %err, "maintenance task failed");
+                (Vec::new(), Some(format!("{err:#}")))
+            }
+        };
+        report.tasks.push(TaskReport {
+            task: task.name().to_string(),
+            actions,
+            error,
+        });
+    }
+    report
+}
+
+// ---------------------------------------------------------------------------
+// Scheduler
+// ---------------------------------------------------------------------------
+
+/// Tracks when each task last ran so the daemon can run each at its own
+/// cadence. Kept in memory: after a restart every enabled task is due.
+#[derive(Debug, Default)]
+pub struct Scheduler {
+    last_run: BTreeMap<String, u64>,
+}
+
+impl Scheduler {
+    /// Seconds between runs of `task` under `cfg`; `0` means never.
+    pub fn every_secs(task: &dyn MaintenanceTask, cfg: &Config) -> u64 {
+        cfg.maintenance
+            .every_secs
+            .get(task.name())
+            .copied()
+            .unwrap_or_else(|| task.default_every_secs())
+    }
+
+    /// Tasks that are enabled and have not run within their interval.
+    pub fn due<'t>(
+        &self,
+        tasks: &'t [Box<dyn MaintenanceTask>],
+        cfg: &Config,
+        now: u64,
+    ) -> Vec<&'t dyn MaintenanceTask> {
+        tasks
+            .iter()
+            .map(|t| t.as_ref())
+            .filter(|t| {
+                let every = Self::every_secs(*t, cfg);
+                every > 0
+                    && self
+                        .last_run
+                        .get(t.name())
+                        .map_or(true, |last| now.saturating_sub(*last) >= every)
+            })
+            .collect()
+    }
+
+    /// Run every due task and record that it ran, whether or not it
+    /// succeeded, so a persistently failing task is not retried every tick.
+    pub fn run_due(
+        &mut self,
+        tasks: &[Box<dyn MaintenanceTask>],
+        ctx: &TaskContext,
+    ) -> MaintenanceReport {
+        let due = self.due(tasks, ctx.cfg, ctx.now);
+        let report = run_tasks(&due, ctx);
+        for task in due {
+            self.last_run.insert(task.name().to_string(), ctx.now);
+        }
+        report
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Built-in tasks
+// ---------------------------------------------------------------------------
+
+/// Expire requests whose deadline (plus claim grace) has passed.
+pub struct SweepTask;
+
+impl MaintenanceTask for SweepTask {
+    fn name(&self) -> &'static str {
+        "sweep"
+    }
+
+    fn description(&self) -> &'static str {
+        "Expire requests whose deadline has passed"
+    }
+
+    fn default_every_secs(&self) -> u64 {
+        60
+    }
+
+    fn run(&self, ctx: &TaskContext) -> Result<Vec<String>> {
+        let grace = ctx.cfg.requests.claim_grace_secs;
+        let ids = if ctx.dry_run {
+            RequestCache::expired_candidates(ctx.now, grace)?
+        } else {
+            RequestCache::sweep_expired_with_grace(ctx.now, grace)?
+        };
+        Ok(ids
+            .into_iter()
+            .map(|id| format!("expire request {id} (deadline passed)"))
+            .collect())
+    }
+}
+
+/// Upgrade request files written by older versions to the current schema.
+pub struct MigrateTask;
+
+impl MaintenanceTask for MigrateTask {
+    fn name(&self) -> &'static str {
+        "migrate"
+    }
+
+    fn description(&self) -> &'static str {
+        "Upgrade request files written by older versions"
+    }
+
+    fn default_every_secs(&self) -> u64 {
+        24 * 60 * 60
+    }
+
+    fn run(&self, ctx: &TaskContext) -> Result<Vec<String>> {
+        let ids = RequestCache::pending_migrations()?;
+        if !ctx.dry_run && !ids.is_empty() {
+            RequestCache::migrate_all()?;
+        }
+        Ok(ids
+            .into_iter()
+            .map(|id| format!("upgrade request file {id}"))
+            .collect())
+    }
+}
+
+/// Remove locks whose holder has exited.
+pub struct LocksTask;
+
+impl MaintenanceTask for LocksTask {
+    fn name(&self) -> &'static str {
+        "locks"
+    }
+
+    fn description(&self) -> &'static str {
+        "Remove locks left behind by processes that have exited"
+    }
+
+    fn default_every_secs(&self) -> u64 {
+        60 * 60
+    }
+
+    fn run(&self, ctx: &TaskContext) -> Result<Vec<String>> {
+        let mut actions = Vec::new();
+        for (name, _, state) in lock::list()? {
+            let LockState::Stale(reason) = state else {
+                continue;
+            };
+            if !ctx.dry_run {
+                lock::break_lock(&name)?;
+            }
+            actions.push(format!("remove stale lock {name} ({reason})"));
+        }
+        Ok(actions)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::env;
+    use std::rc::Rc;
+    use std::sync::Mutex;
+
+    use crate::engine::requests::{LocalRequest, LocalRequestStatus};
+
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    fn with_temp_home<F: FnOnce()>(f: F) {
+        let _guard = ENV_LOCK.lock().expect("env lock poisoned");
+
+        let tmp = tempfile::tempdir().expect("failed to create temp dir");
+        let prev = env::var("AGENTMARKET_HOME").ok();
+
+        env::set_var("AGENTMARKET_HOME", tmp.path());
+        f();
+
+        match prev {
+            Some(v) => env::set_var("AGENTMARKET_HOME", v),
+            None => env::remove_var("AGENTMARKET_HOME"),
+        }
+    }
+
+    /// A task that records when it ran and returns canned actions.
+    struct FakeTask {
+        name: &'static str,
+        every: u64,
+        fail: bool,
+        runs: Rc<RefCell<Vec<(u64, bool)>>>,
+    }
+
+    impl MaintenanceTask for FakeTask {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn description(&self) -> &'static str {
+            "fake"
+        }
+
+        fn default_every_secs(&self) -> u64 {
+            self.every
+        }
+
+        fn run(&self, ctx: &TaskContext) -> Result<Vec<String>> {
+            self.runs.borrow_mut().push((ctx.now, ctx.dry_run));
+            if self.fail {
+                bail!("{} broke", self.name);
+            }
+            Ok(vec![
+                format!("{} did one thing", self.name),
+                "and another".into(),
+            ])
+        }
+    }
+
+    fn fake_tasks(runs: &Rc<RefCell<Vec<(u64, bool)>>>) -> Vec<Box<dyn MaintenanceTask>> {
+        [
+            ("alpha", 10, false),
+            ("beta", 30, true),
+            ("gamma", 0, false),
+        ]
+        .into_iter()
+        .map(|(name, every, fail)| {
+            Box::new(FakeTask {
+                name,
+                every,
+                fail,
+                runs: runs.clone(),
+            }) as Box<dyn MaintenanceTask>
+        })
+        .collect()
+    }
+
+    fn names(tasks: &[&dyn MaintenanceTask]) -> Vec<&'static str> {
+        tasks.iter().map(|t| t.name()).collect()
+    }
+
+    #[test]
+    fn test_select_by_name_all_and_unknown() {
+        let runs = Rc::default();
+        let tasks = fake_tasks(&runs);
+        let pick = |list: &[&str], all| {
+            let list: Vec<String> = list.iter().map(|s| s.to_string()).collect();
+            select(&tasks, &list, all)
+        };
+
+        assert_eq!(
+            names(&pick(&["gamma", "ALPHA"], false).unwrap()),
+            ["alpha", "gamma"]
+        );
+        assert_eq!(names(&pick(&[], true).unwrap()), ["alpha", "beta", "gamma"]);
+
+        let err = pick(&["alpha", "nope"], false).err().unwrap().to_string();
+        assert!(
+            err.contains("\"nope\"") && err.contains("alpha, beta, gamma"),
+            "{err}"
+        );
+        assert!(pick(&[], false).is_err());
+        assert!(pick(&["alpha"], true).is_err());
+    }
+
+    #[test]
+    fn test_scheduler_runs_each_task_at_its_own_cadence() {
+        let runs = Rc::new(RefCell::new(Vec::new()));
+        let tasks = fake_tasks(&runs);
+        let mut cfg = Config::default();
+        // Config overrides the built-in cadence for beta only.
+        cfg.maintenance.every_secs.insert("beta".to_string(), 25);
+
+        let mut scheduler = Scheduler::default();
+        let mut ran_at = |now| {
+            let ctx = TaskContext {
+                cfg: &cfg,
+                now,
+                dry_run: false,
+            };
+            let report = scheduler.run_due(&tasks, &ctx);
+            report.tasks.into_iter().map(|t| t.task).collect::<Vec<_>>()
+        };
+
+        // Fake clock: everything enabled is due at first; gamma (0) never.
+        assert_eq!(ran_at(1_000), ["alpha", "beta"]);
+        assert!(ran_at(1_005).is_empty());
+        assert_eq!(ran_at(1_010), ["alpha"]);
+        assert_eq!(ran_at(1_024), ["alpha"]);
+        // beta failed at 1_000 but is still not retried before its interval.
+        assert_eq!(ran_at(1_025), ["beta"]);
+        assert_eq!(ran_at(1_034), ["alpha"]);
+        assert_eq!(runs.borrow().len(), 6);
+    }
+
+    #[test]
+    fn test_report_aggregates_actions_and_failures() {
+        let runs = Rc::new(RefCell::new(Vec::new()));
+        let tasks = fake_tasks(&runs);
+        let cfg = Config::default();
+        let ctx = TaskContext {
+            cfg: &cfg,
+            now: 42,
+            dry_run: true,
+        };
+
+        let selected = select(&tasks, &[], true).unwrap();
+        let report = run_tasks(&selected, &ctx);
+
+        assert!(report.dry_run);
+        assert_eq!(report.tasks.len(), 3);
+        assert_eq!(report.total_actions(), 4);
+        let failed: Vec<_> = report.failures().map(|t| t.task.as_str()).collect();
+        assert_eq!(failed, ["beta"]);
+        assert_eq!(report.tasks[1].error.as_deref(), Some("beta broke"));
+        assert!(report.tasks[1].actions.is_empty());
+        // A failing task does not stop the ones after it; all saw dry_run.
+        assert_eq!(*runs.borrow(), vec![(42, true); 3]);
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["tasks"][0]["actions"][0], "alpha did one thing");
+        assert!(json["tasks"][0].get("error").is_none());
+        assert_eq!(json["tasks"][1]["error"], "beta broke");
+    }
+
+    #[test]
+    fn test_sweep_task_dry_run_changes_nothing() {
+        with_temp_home(|| {
+            let request: LocalRequest = serde_json::from_value(serde_json::json!({
+                "request_id": "m-1",
+                "role": "Buyer",
+                "status": "Open",
+                "request_cid": "",
+                "price_usdc": 1,
+                "deadline": 100,
+                "response_cid": null,
+                "secret": null,
+                "secret_hash": null,
+                "counterparty": null,
+                "created_at": 0,
+                "updated_at": 0
+            }))
+            .unwrap();
+            RequestCache::save(&request).unwrap();
+
+            let cfg = Config::default();
+            let mut ctx = TaskContext {
+                cfg: &cfg,
+                now: 1_000,
+                dry_run: true,
+            };
+            let expected = vec!["expire request m-1 (deadline passed)".to_string()];
+            assert_eq!(SweepTask.run(&ctx).unwrap(), expected);
+            assert_eq!(
+                RequestCache::load("m-1").unwrap().status,
+                LocalRequestStatus::Open
+            );
+
+            ctx.dry_run = false;
+            assert_eq!(SweepTask.run(&ctx).unwrap(), expected);
+            assert_eq!(
+                RequestCache::load("m-1").unwrap().status,
+                LocalRequestStatus::Expired
+            );
+            assert!(SweepTask.run(&ctx).unwrap().is_empty());
+        });
+    }
+}
@@ -5,17 +5,23 @@
 //! and deliverable content, then enters a pass/fail decision,
 //! quality score, and optional reason.
 //!
+//! With prompts turned off (`--non-interactive`) the handler refuses to
+//! start, pointing at an external handler instead.
+//!
 //! For automated testing, the [`run_manual_review_with_reader`]
 //! variant accepts any [`BufRead`] source instead of stdin.
 
-use std::io::{self, BufRead, Write};
+use std::io::{self, BufRead};
 
-use anyhow::{Context, Result};
+use anyhow::Result;
 use tracing::debug;
 
-use crate::engine::requests::format_price_usd;
 use crate::engine::validation::{HandlerInput, HandlerOutput};
-use crate::output::formatter;
+use crate::output::{formatter, prompt};
+
+/// How to validate without the manual handler's prompts.
+const NON_INTERACTIVE_HINT: &str =
+    "Pass --handler external --handler-path <script> to validate without prompts.";
 
 // ---------------------------------------------------------------------------
 // Public API
@@ -29,11 +35,17 @@ use crate::output::formatter;
 ///
 /// Returns a [`HandlerOutput`] with the operator's decision.
 pub fn run_manual_review(input: &HandlerInput) -> Result<HandlerOutput> {
+    ensure_available()?;
     let stdin = io::stdin();
     let mut reader = stdin.lock();
     run_manual_review_with_reader(input, &mut reader)
 }
 
+/// Fail if the operator cannot be asked because prompts are turned off.
+pub fn ensure_available() -> Result<()> {
+    prompt::ensure_interactive("Manual review", NON_INTERACTIVE_HINT)
+}
+
 /// Testable version that accepts a custom reader for input.
 ///
 /// Identical logic to [`run_manual_review`] but reads from the
@@ -43,43 +55,52 @@ pub fn run_manual_review_with_reader<R: BufRead>(
     input: &HandlerInput,
     reader: &mut R,
 ) -> Result<HandlerOutput> {
+    ensure_available()?;
+
     // Display request details.
-    println!();
-    println!("=== Validation Review ===");
-    println!();
+    formatter::print_info("");
+    formatter::print_info("=== Validation Review ===");
+    formatter::print_info("");
     formatter::print_info(&format!("Request ID: {}", input.request_id));
     formatter::print_info(&format!("Task: {}", input.task_description));
     formatter::print_info(&format!("Seller: {}", input.seller));
-    formatter::print_info(&format!("Price: {}", format_price_usd(input.price_usdc)));
-    println!();
+    formatter::print_info(&format!(
+        "Price: {}",
+        formatter::format_price(input.price_usdc)
+    ));
+    formatter::print_info("");
 
     // Display deliverable content.
-    println!("--- Deliverable ---");
+    formatter::print_info("--- Deliverable ---");
     match std::str::from_utf8(&input.deliverable) {
         Ok(text) => {
             // Truncate very long content to keep terminal output manageable.
             if text.len() > 5000 {
-                println!("{}", &text[..5000]);
-                println!("... (truncated, {} bytes total)", text.len());
+                formatter::print_info(&text[..5000]);
+                formatter::print_info(&format!("... (truncated, {} bytes total)", text.len()));
             } else {
-                println!("{}", text);
+                formatter::print_info(text);
             }
         }
         Err(_) => {
-            println!("[Binary content, {} bytes]", input.deliverable.len());
+            formatter::print_info(&format!(
+                "[Binary content, {} bytes]",
+                input.deliverable.len()
+            ));
         }
     }
-    println!("--- End Deliverable ---");
-    println!();
+    formatter::print_info("--- End Deliverable ---");
+    formatter::print_info("");
 
     // Prompt for pass/fail decision.
-    let decision = prompt_line(reader, "Approve? (y/n): ")?;
+    let decision = prompt::ask_line(reader, "Approve? (y/n): ", NON_INTERACTIVE_HINT)?;
     let passed = decision.trim().to_lowercase().starts_with('y');
 
     // Prompt for quality score.
-    let score_str = prompt_line(
+    let score_str = prompt::ask_line(
         reader,
         "Score (0-100, default 80 if approved, 20 if rejected): ",
+        NON_INTERACTIVE_HINT,
     )?;
     let score: u8 = if score_str.trim().is_empty() {
         if passed {
@@ -95,7 +116,7 @@ pub fn run_manual_review_with_reader<R: BufRead>(
     };
 
     // Prompt for reason.
-    let reason_str = prompt_line(reader, "Reason (optional): ")?;
+    let reason_str = prompt::ask_line(reader, "Reason (optional): ", NON_INTERACTIVE_HINT)?;
     let reason = if reason_str.trim().is_empty() {
         if passed {
             "Manually approved".to_string()
@@ -116,25 +137,6 @@ pub fn run_manual_review_with_reader<R: BufRead>(
     Ok(HandlerOutput { score, reason })
 }
 
-// ---------------------------------------------------------------------------
-// Helpers
-// ---------------------------------------------------------------------------
-
-/// Print a prompt to stderr and read a single line from the reader.
-///
-/// The prompt is written to stderr so it does not interfere with
-/// stdout-based output capture in tests or piped workflows.
-fn prompt_line<R: BufRead>(reader: &mut R, prompt: &str) -> Result<String> {
-    eprint!("{}", prompt);
-    io::stderr().flush().context("failed to flush stderr")?;
-
-    let mut line = String::new();
-    reader
-        .read_line(&mut line)
-        .context("failed to read input")?;
-    Ok(line.trim().to_string())
-}
-
 // ---------------------------------------------------------------------------
 // Tests
 // ---------------------------------------------------------------------------
@@ -261,11 +263,4 @@ mod tests {
         assert_eq!(output.score, 15);
         assert_eq!(output.reason, "not acceptable");
     }
-
-    #[test]
-    fn test_prompt_line_reads_trimmed() {
-        let mut reader = Cursor::new("  hello world  \n");
-        let result = prompt_line(&mut reader, "test: ").unwrap();
-        assert_eq!(result, "hello world");
-    }
 }
@@ -1,6 +1,25 @@
+pub mod archive;
+pub mod attachments;
+pub mod backoff;
+pub mod daemon_state;
+pub mod discovery;
+pub mod economics;
+pub mod event_sync;
 pub mod handlers;
+pub mod health;
 pub mod identity;
+pub mod inbox;
+pub mod maintenance;
 pub mod manual_handler;
+pub mod notifications;
+pub mod orders;
+pub mod payment;
+pub mod registration;
 pub mod reputation;
 pub mod requests;
+pub mod stats;
+pub mod status;
+pub mod systemd;
+pub mod template;
+pub mod trust;
 pub mod validation;
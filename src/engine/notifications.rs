@@ -0,0 +1,210 @@
+//! Webhook notifications for daemon lifecycle events.
+//!
+//! When `notifications.webhook_url` is set, the daemon POSTs a small JSON
+//! event to it whenever it validates, claims, fails to claim, or hits an
+//! error, so monitoring can follow along without scraping logs. Delivery is
+//! best-effort: each event is sent from a background task with a short
+//! timeout, and failures are only logged at debug level.
+
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use tracing::debug;
+
+use crate::clock;
+use crate::config::store::Config;
+
+/// How long one delivery may take before it is abandoned.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What happened.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    ValidationCompleted,
+    ClaimSucceeded,
+    ClaimFailed,
+    CycleError,
+    /// Sent by `daemon --test-webhook` to check the endpoint.
+    Ping,
+}
+
+/// The JSON body POSTed to the webhook.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct WebhookEvent {
+    pub event: EventKind,
+    pub request_id: Option<String>,
+    /// Amount involved, in USDC atomic units (6 decimals).
+    pub amount_usdc: Option<u64>,
+    /// Unix timestamp of the event.
+    pub timestamp: u64,
+    pub agent_address: String,
+    /// What went wrong, for failure events.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl WebhookEvent {
+    /// An event not tied to a request.
+    pub fn new(event: EventKind, agent_address: &str, timestamp: u64) -> Self {
+        Self {
+            event,
+            request_id: None,
+            amount_usdc: None,
+            timestamp,
+            agent_address: agent_address.to_string(),
+            error: None,
+        }
+    }
+
+    /// Attach the request and amount the event is about.
+    pub fn for_request(mut self, request_id: &str, amount_usdc: u64) -> Self {
+        self.request_id = Some(request_id.to_string());
+        self.amount_usdc = Some(amount_usdc);
+        self
+    }
+
+    /// Attach the error behind a failure event.
+    pub fn with_error(mut self, error: impl Into<String>) -> Self {
+        self.error = Some(error.into());
+        self
+    }
+}
+
+/// Sends [`WebhookEvent`]s to the configured URL, or drops them when none
+/// is configured.
+#[derive(Clone, Debug)]
+pub struct Notifier {
+    url: Option<String>,
+    agent_address: String,
+    http: reqwest::Client,
+}
+
+impl Notifier {
+    /// A notifier for `cfg`'s webhook URL, stamping events with
+    /// `agent_address`.
+    pub fn from_config(cfg: &Config, agent_address: &str) -> Self {
+        let url = cfg.notifications.webhook_url.trim();
+        Self {
+            url: (!url.is_empty()).then(|| url.to_string()),
+            agent_address: agent_address.to_string(),
+            http: reqwest::Client::builder()
+                .timeout(WEBHOOK_TIMEOUT)
+                .build()
+                .expect("failed to build HTTP client"),
+        }
+    }
+
+    /// Whether a webhook URL is configured.
+    pub fn is_enabled(&self) -> bool {
+        self.url.is_some()
+    }
+
+    /// A new event of `kind` from this agent, timestamped now.
+    pub fn event(&self, kind: EventKind) -> WebhookEvent {
+        let now = clock::unix_now();
+        WebhookEvent::new(kind, &self.agent_address, now)
+    }
+
+    /// Send `event` in the background. Never blocks the caller and never
+    /// fails; a failed delivery is logged at debug level.
+    pub fn notify(&self, event: WebhookEvent) {
+        if !self.is_enabled() {
+            return;
+        }
+        let notifier = self.clone();
+        tokio::spawn(async move {
+            if let Err(err) = notifier.deliver(&event).await {
+                debug!(event = ?event.event, error = %format!("{err:#}"), "webhook delivery failed");
+            }
+        });
+    }
+
+    /// Send `event` and wait for the endpoint to accept it.
+    pub async fn deliver(&self, event: &WebhookEvent) -> Result<()> {
+        let Some(url) = &self.url else {
+            bail!("No webhook configured. Set one with `agentmarket config set notifications.webhook_url URL`.");
+        };
+        let resp = self
+            .http
+            .post(url)
+            .json(event)
+            .send()
+            .await
+            .with_context(|| format!("could not reach webhook at {url}"))?;
+        if !resp.status().is_success() {
+            bail!("webhook at {url} answered {}", resp.status());
+        }
+        debug!(event = ?event.event, "webhook delivered");
+        Ok(())
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const ADDRESS: &str = "0x1111111111111111111111111111111111111111";
+
+    #[test]
+    fn test_claim_event_serialization() {
+        let event = WebhookEvent::new(EventKind::ClaimSucceeded, ADDRESS, 1_760_000_000)
+            .for_request("42", 5_000_000);
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            json!({
+                "event": "claim_succeeded",
+                "request_id": "42",
+                "amount_usdc": 5_000_000,
+                "timestamp": 1_760_000_000u64,
+                "agent_address": ADDRESS,
+            })
+        );
+    }
+
+    #[test]
+    fn test_failure_event_serialization() {
+        let event = WebhookEvent::new(EventKind::ClaimFailed, ADDRESS, 1_760_000_000)
+            .for_request("7", 250_000)
+            .with_error("secret does not match");
+        let value = serde_json::to_value(&event).unwrap();
+        assert_eq!(value["event"], "claim_failed");
+        assert_eq!(value["error"], "secret does not match");
+    }
+
+    #[test]
+    fn test_event_without_request_has_null_fields() {
+        let event = WebhookEvent::new(EventKind::CycleError, ADDRESS, 1);
+        let value = serde_json::to_value(&event).unwrap();
+        assert_eq!(value["event"], "cycle_error");
+        assert!(value["request_id"].is_null());
+        assert!(value["amount_usdc"].is_null());
+
+        for (kind, name) in [
+            (EventKind::ValidationCompleted, "validation_completed"),
+            (EventKind::Ping, "ping"),
+        ] {
+            assert_eq!(serde_json::to_value(kind).unwrap(), json!(name));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_notifier_without_url_is_disabled() {
+        let notifier = Notifier::from_config(&Config::default(), ADDRESS);
+        assert!(!notifier.is_enabled());
+        // Dropped silently, and an explicit delivery explains what to set.
+        notifier.notify(notifier.event(EventKind::Ping));
+        let err = notifier
+            .deliver(&notifier.event(EventKind::Ping))
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("notifications.webhook_url"), "{err}");
+    }
+}
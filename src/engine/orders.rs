@@ -0,0 +1,328 @@
+//! Signed off-chain quotes (EIP-712 typed data).
+//!
+//! A seller can offer a price for a request before anything touches the
+//! chain. The offer is a [`Quote`] signed as EIP-712 typed data under the
+//! AgentMarket domain, so the buyer (and later the Request Registry) can
+//! check exactly what was offered and by whom. The domain binds the
+//! signature to one chain and one Request Registry deployment, so a quote
+//! made on testnet cannot be replayed on mainnet.
+//!
+//! Quotes travel through the mailbox as a compact JSON envelope (see
+//! [`SignedQuote::to_json`]) with message type [`QUOTE_MESSAGE_TYPE`].
+
+use alloy::primitives::{keccak256, Address, B256, U256};
+use alloy::signers::local::PrivateKeySigner;
+use alloy::signers::SignerSync;
+use alloy::sol_types::{eip712_domain, Eip712Domain, SolStruct};
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::engine::identity;
+
+// ---------------------------------------------------------------------------
+// Constants
+// ---------------------------------------------------------------------------
+
+/// EIP-712 domain name shared by every AgentMarket typed signature.
+pub const DOMAIN_NAME: &str = "AgentMarket";
+
+/// Mailbox message type for a [`SignedQuote`] envelope.
+pub const QUOTE_MESSAGE_TYPE: &str = "quote";
+
+/// The Solidity shape of a quote, which fixes its EIP-712 type string:
+/// `Quote(bytes32 requestHash,uint256 priceUsdc,uint256 deadline,address seller)`.
+mod typed {
+    alloy::sol! {
+        struct Quote {
+            bytes32 requestHash;
+            uint256 priceUsdc;
+            uint256 deadline;
+            address seller;
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+/// A seller's offer to fulfil a request at a price until a deadline.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Quote {
+    /// Identifies the request being quoted (see [`request_hash`]).
+    pub request_hash: B256,
+    /// Offered price in USDC atomic units (6 decimals).
+    pub price_usdc: u64,
+    /// Unix timestamp after which the offer no longer stands.
+    pub deadline: u64,
+    /// Address of the seller making the offer; it must sign the quote.
+    pub seller: Address,
+}
+
+/// A [`Quote`] with the seller's EIP-712 signature, as sent between agents.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedQuote {
+    pub quote: Quote,
+    /// 65-byte `r || s || v` signature as `0x`-prefixed hex.
+    pub signature: String,
+}
+
+// ---------------------------------------------------------------------------
+// Domain and hashing
+// ---------------------------------------------------------------------------
+
+/// The hash a quote carries for `request_id`: `keccak256` of the ID as
+/// UTF-8, so both sides derive the same value without sharing anything
+/// else about the request.
+pub fn request_hash(request_id: &str) -> B256 {
+    keccak256(request_id.trim().as_bytes())
+}
+
+/// The AgentMarket EIP-712 domain on the chain `chain_id`, with the Request
+/// Registry as the verifying contract.
+pub fn domain(chain_id: u64, request_registry: Address) -> Eip712Domain {
+    eip712_domain! {
+        name: DOMAIN_NAME,
+        chain_id: chain_id,
+        verifying_contract: request_registry,
+    }
+}
+
+impl Quote {
+    /// Whether the offer has lapsed at `now` (Unix seconds).
+    pub fn is_expired(&self, now: u64) -> bool {
+        now > self.deadline
+    }
+
+    /// The EIP-712 digest that is signed:
+    /// `keccak256("\x19\x01" || domainSeparator || hashStruct(quote))`.
+    pub fn signing_hash(&self, domain: &Eip712Domain) -> B256 {
+        self.typed().eip712_signing_hash(domain)
+    }
+
+    fn typed(&self) -> typed::Quote {
+        typed::Quote {
+            requestHash: self.request_hash,
+            priceUsdc: U256::from(self.price_usdc),
+            deadline: U256::from(self.deadline),
+            seller: self.seller,
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Signing
+// ---------------------------------------------------------------------------
+
+/// Sign `quote` under `domain` with the seller's key.
+///
+/// Fails if the key does not belong to `quote.seller`, since nobody could
+/// verify such a quote.
+pub fn sign_quote(
+    private_key_bytes: &[u8],
+    quote: &Quote,
+    domain: &Eip712Domain,
+) -> Result<SignedQuote> {
+    let signer = PrivateKeySigner::from_slice(private_key_bytes)
+        .context("failed to construct signer from private key bytes")?;
+    if signer.address() != quote.seller {
+        bail!(
+            "This agent's key ({}) cannot sign a quote for seller {}.",
+            signer.address(),
+            quote.seller
+        );
+    }
+    let signature = signer
+        .sign_hash_sync(&quote.signing_hash(domain))
+        .context("failed to sign quote")?;
+    debug!(
+        seller = %quote.seller,
+        request_hash = %quote.request_hash,
+        price_usdc = quote.price_usdc,
+        "signed quote"
+    );
+    Ok(SignedQuote {
+        quote: quote.clone(),
+        signature: format!("0x{}", hex::encode(signature.as_bytes())),
+    })
+}
+
+/// Check that `signed` was signed under `domain` by its seller.
+///
+/// Returns `Ok(false)` for a well-formed signature by anyone else, which is
+/// also what a tampered quote or a quote from another network looks like.
+/// Whether the quote has expired is left to the caller
+/// ([`Quote::is_expired`]).
+pub fn verify_quote(signed: &SignedQuote, domain: &Eip712Domain) -> Result<bool> {
+    let signature = identity::parse_signature(&signed.signature)?;
+    let signer = signature
+        .recover_address_from_prehash(&signed.quote.signing_hash(domain))
+        .map_err(|_| anyhow::anyhow!("Invalid signature: no key could have produced it."))?;
+    debug!(%signer, seller = %signed.quote.seller, "recovered quote signer");
+    Ok(signer == signed.quote.seller)
+}
+
+// ---------------------------------------------------------------------------
+// Envelope
+// ---------------------------------------------------------------------------
+
+impl SignedQuote {
+    /// Compact JSON for a mailbox payload.
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string(self).context("failed to serialize quote")
+    }
+
+    /// Parse a mailbox payload produced by [`SignedQuote::to_json`].
+    pub fn from_json(payload: &[u8]) -> Result<Self> {
+        serde_json::from_slice(payload).context("message is not a readable quote")
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::address;
+    use alloy::sol_types::SolValue;
+
+    /// The first well-known development key (Anvil/Hardhat account 0).
+    const DEV_KEY: &str = "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+    const DEV_ADDRESS: Address = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+    const REGISTRY: Address = address!("1111111111111111111111111111111111111111");
+
+    fn dev_key() -> Vec<u8> {
+        hex::decode(DEV_KEY).unwrap()
+    }
+
+    fn quote() -> Quote {
+        Quote {
+            request_hash: request_hash("42"),
+            price_usdc: 5_000_000,
+            deadline: 1_800_000_000,
+            seller: DEV_ADDRESS,
+        }
+    }
+
+    #[test]
+    fn test_domain_separator_vector() {
+        let domain = domain(8453, REGISTRY);
+        let type_hash =
+            keccak256("EIP712Domain(string name,uint256 chainId,address verifyingContract)");
+        let expected = keccak256(
+            (
+                type_hash,
+                keccak256(DOMAIN_NAME),
+                U256::from(8453u64),
+                REGISTRY,
+            )
+                .abi_encode(),
+        );
+        assert_eq!(domain.separator(), expected);
+        assert_eq!(
+            domain.separator().to_string(),
+            "0x88856fe3d860e255d0dfed1a7de09632ed4cd1f4e6011d90a3d5d01e4f955d95"
+        );
+        assert_ne!(
+            domain.separator(),
+            self::domain(84532, REGISTRY).separator()
+        );
+    }
+
+    #[test]
+    fn test_quote_digest_vector() {
+        let domain = domain(8453, REGISTRY);
+        let quote = quote();
+        let type_hash = keccak256(
+            "Quote(bytes32 requestHash,uint256 priceUsdc,uint256 deadline,address seller)",
+        );
+        let struct_hash = keccak256(
+            (
+                type_hash,
+                quote.request_hash,
+                U256::from(quote.price_usdc),
+                U256::from(quote.deadline),
+                quote.seller,
+            )
+                .abi_encode(),
+        );
+        let mut preimage = vec![0x19, 0x01];
+        preimage.extend_from_slice(domain.separator().as_slice());
+        preimage.extend_from_slice(struct_hash.as_slice());
+
+        assert_eq!(quote.signing_hash(&domain), keccak256(&preimage));
+        assert_eq!(
+            quote.signing_hash(&domain).to_string(),
+            "0xcf733cd5c8d9a9c637e65976b4b1fa1f05bb603d1ae04edb5010f5e27bfe1d87"
+        );
+    }
+
+    #[test]
+    fn test_sign_quote_known_vector_and_verify() {
+        let domain = domain(8453, REGISTRY);
+        let signed = sign_quote(&dev_key(), &quote(), &domain).unwrap();
+        assert_eq!(
+            signed.signature,
+            "0xe3f99a90bb99867f6b971c99373adbd2e955ff3c6ce2ee3d5164034857c4dbc5\
+             2cc2ad2e4759c0a680635707ee5783bbe573a05ec0ecb986e5f80797eaeef0811c"
+        );
+        assert!(verify_quote(&signed, &domain).unwrap());
+
+        // Another network or registry deployment does not verify.
+        assert!(!verify_quote(&signed, &self::domain(84532, REGISTRY)).unwrap());
+        assert!(!verify_quote(&signed, &self::domain(8453, Address::ZERO)).unwrap());
+
+        // Neither does a tampered price or a quote claimed for another seller.
+        let mut tampered = signed.clone();
+        tampered.quote.price_usdc = 1;
+        assert!(!verify_quote(&tampered, &domain).unwrap());
+        let mut other_seller = signed;
+        other_seller.quote.seller = REGISTRY;
+        assert!(!verify_quote(&other_seller, &domain).unwrap());
+    }
+
+    #[test]
+    fn test_sign_quote_refuses_another_sellers_quote() {
+        let mut quote = quote();
+        quote.seller = REGISTRY;
+        let err = sign_quote(&dev_key(), &quote, &domain(8453, REGISTRY))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("cannot sign a quote for seller"), "{err}");
+    }
+
+    #[test]
+    fn test_envelope_round_trip() {
+        let domain = domain(8453, REGISTRY);
+        let signed = sign_quote(&dev_key(), &quote(), &domain).unwrap();
+        let json = signed.to_json().unwrap();
+        assert!(!json.contains('\n'));
+        assert!(
+            json.starts_with(r#"{"quote":{"request_hash":"0x"#),
+            "{json}"
+        );
+        assert!(
+            json.contains(r#""price_usdc":5000000,"deadline":1800000000"#),
+            "{json}"
+        );
+
+        let parsed = SignedQuote::from_json(json.as_bytes()).unwrap();
+        assert_eq!(parsed, signed);
+        assert!(verify_quote(&parsed, &domain).unwrap());
+        assert!(SignedQuote::from_json(b"hello").is_err());
+    }
+
+    #[test]
+    fn test_request_hash_and_expiry() {
+        assert_eq!(request_hash("42"), keccak256("42"));
+        assert_eq!(request_hash(" 42 "), request_hash("42"));
+        assert_ne!(request_hash("42"), request_hash("43"));
+
+        let quote = quote();
+        assert!(!quote.is_expired(quote.deadline));
+        assert!(quote.is_expired(quote.deadline + 1));
+    }
+}
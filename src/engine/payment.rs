@@ -0,0 +1,153 @@
+//! Checks that a buyer's payment is actually secured before a seller works.
+//!
+//! The Request Registry never holds funds: settlement pulls the price from
+//! the buyer with `transferFrom` when the seller claims. If the buyer never
+//! approved enough USDC toward the registry, or no longer holds it, the
+//! claim fails even though the work passed validation.
+//!
+//! The caller reads the buyer's balance and approval from the chain and
+//! passes them to [`assess`]. The result is stored on the request
+//! ([`LocalRequest::payment_check`]) so a later claim failure can point back
+//! to it.
+//!
+//! [`LocalRequest::payment_check`]: crate::engine::requests::LocalRequest::payment_check
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::output::formatter;
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+/// Whether the buyer's payment will be there at claim time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PaymentStatus {
+    /// The buyer's balance and approval both cover the price.
+    Covered,
+    /// The balance or approval falls short of the price.
+    Unsecured,
+}
+
+impl fmt::Display for PaymentStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PaymentStatus::Covered => "secured",
+            PaymentStatus::Unsecured => "payment not secured",
+        })
+    }
+}
+
+/// Outcome of [`assess`], recorded on the request. Amounts are in USDC
+/// atomic units.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PaymentCheck {
+    pub status: PaymentStatus,
+    /// Price the check was made against.
+    pub price_usdc: u64,
+    /// Buyer's USDC balance.
+    pub balance_usdc: u64,
+    /// Buyer's approval toward the registry.
+    pub allowance_usdc: u64,
+    /// Unix timestamp of the check.
+    pub checked_at: u64,
+}
+
+impl PaymentCheck {
+    /// Returns `true` unless the payment was found to be short.
+    pub fn is_secured(&self) -> bool {
+        self.status != PaymentStatus::Unsecured
+    }
+
+    /// One-line explanation, naming what falls short when unsecured.
+    pub fn describe(&self) -> String {
+        if self.is_secured() {
+            return format!("Payment {}.", self.status);
+        }
+
+        let price = self.price_usdc;
+        let mut gaps = Vec::new();
+        if self.balance_usdc < price {
+            gaps.push(format!(
+                "the buyer holds {} of {}",
+                formatter::format_price(self.balance_usdc),
+                formatter::format_price(price)
+            ));
+        }
+        if self.allowance_usdc < price {
+            gaps.push(format!(
+                "the buyer approved {} of {}",
+                formatter::format_price(self.allowance_usdc),
+                formatter::format_price(price)
+            ));
+        }
+        format!("Payment not secured: {}.", gaps.join("; "))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Check
+// ---------------------------------------------------------------------------
+
+/// Judge whether a buyer holding `balance_usdc`, with `allowance_usdc`
+/// approved toward the Request Registry, can pay `price_usdc` at claim
+/// time: both must cover the price.
+pub fn assess(price_usdc: u64, balance_usdc: u64, allowance_usdc: u64, now: u64) -> PaymentCheck {
+    let status = if balance_usdc >= price_usdc && allowance_usdc >= price_usdc {
+        PaymentStatus::Covered
+    } else {
+        PaymentStatus::Unsecured
+    };
+    PaymentCheck {
+        status,
+        price_usdc,
+        balance_usdc,
+        allowance_usdc,
+        checked_at: now,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRICE: u64 = 5_000_000;
+    const NOW: u64 = 1_700_000_000;
+
+    #[test]
+    fn test_balance_and_allowance_cover_price() {
+        let result = assess(PRICE, PRICE * 10, PRICE, NOW);
+        assert_eq!(result.status, PaymentStatus::Covered);
+        assert!(result.is_secured());
+        assert_eq!(result.balance_usdc, PRICE * 10);
+        assert_eq!(result.allowance_usdc, PRICE);
+        assert_eq!(result.checked_at, NOW);
+        assert_eq!(result.describe(), "Payment secured.");
+    }
+
+    #[test]
+    fn test_short_allowance_is_unsecured() {
+        let result = assess(PRICE, PRICE * 10, 1_000_000, NOW);
+        assert_eq!(result.status, PaymentStatus::Unsecured);
+        assert!(!result.is_secured());
+        let text = result.describe();
+        assert!(text.starts_with("Payment not secured"), "{text}");
+        assert!(text.contains("approved"), "{text}");
+        assert!(!text.contains("holds"), "{text}");
+    }
+
+    #[test]
+    fn test_short_balance_is_unsecured() {
+        let result = assess(PRICE, PRICE - 1, PRICE, NOW);
+        assert_eq!(result.status, PaymentStatus::Unsecured);
+        let text = result.describe();
+        assert!(text.contains("holds"), "{text}");
+        assert!(!text.contains("approved"), "{text}");
+    }
+}
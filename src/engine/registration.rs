@@ -0,0 +1,143 @@
+//! Checkpoint for resuming an interrupted `register`.
+//!
+//! Registration is a sequence of steps (upload the profile, submit the
+//! registration, record the agent ID), any of which can fail after the
+//! earlier ones succeeded. Progress is kept in
+//! `~/.agentmarket/registration.json` so a re-run skips finished steps
+//! instead of uploading again. The checkpoint only applies to the key and
+//! profile it was written for; if either changes it is discarded.
+
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::debug;
+
+use crate::config::store::config_dir;
+
+/// Name of the checkpoint file inside the config directory.
+pub const CHECKPOINT_FILE: &str = "registration.json";
+
+/// Progress of a registration.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Public key of the identity being registered.
+    pub public_key: String,
+    /// SHA-256 of the profile JSON, see [`profile_digest`].
+    pub profile_digest: String,
+    /// CID of the uploaded profile, once uploaded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile_cid: Option<String>,
+    /// Unix timestamp when the registration was submitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub submitted_at: Option<u64>,
+}
+
+impl Checkpoint {
+    /// Continue from `saved` if it was written for the same key and
+    /// profile, otherwise start over.
+    pub fn resume(saved: Option<Checkpoint>, public_key: &str, profile_digest: &str) -> Self {
+        match saved {
+            Some(saved)
+                if saved.public_key == public_key && saved.profile_digest == profile_digest =>
+            {
+                debug!(?saved, "resuming registration");
+                saved
+            }
+            stale => {
+                if stale.is_some() {
+                    debug!("registration checkpoint is for another key or profile; starting over");
+                }
+                Self {
+                    public_key: public_key.to_string(),
+                    profile_digest: profile_digest.to_string(),
+                    ..Default::default()
+                }
+            }
+        }
+    }
+}
+
+/// Hex SHA-256 of the profile JSON that would be uploaded.
+pub fn profile_digest(profile_json: &str) -> String {
+    hex::encode(Sha256::digest(profile_json.as_bytes()))
+}
+
+fn checkpoint_path() -> Result<PathBuf> {
+    Ok(config_dir()?.join(CHECKPOINT_FILE))
+}
+
+/// The saved checkpoint, or `None` if there is none. An unreadable
+/// checkpoint is treated as absent; the worst case is one extra upload.
+pub fn load() -> Result<Option<Checkpoint>> {
+    let path = checkpoint_path()?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    let checkpoint = fs::read_to_string(&path)
+        .map_err(anyhow::Error::from)
+        .and_then(|json| Ok(serde_json::from_str(&json)?));
+    match checkpoint {
+        Ok(checkpoint) => Ok(Some(checkpoint)),
+        Err(err) => {
+            debug!(path = %path.display(), error = %err, "ignoring unreadable checkpoint");
+            Ok(None)
+        }
+    }
+}
+
+/// Save `checkpoint`, replacing any earlier one.
+pub fn save(checkpoint: &Checkpoint) -> Result<()> {
+    let path = checkpoint_path()?;
+    let json =
+        serde_json::to_string_pretty(checkpoint).context("failed to serialise checkpoint")?;
+    fs::write(&path, json)
+        .with_context(|| format!("failed to write checkpoint: {}", path.display()))?;
+    debug!(path = %path.display(), "registration checkpoint saved");
+    Ok(())
+}
+
+/// Remove the checkpoint once registration is complete.
+pub fn clear() -> Result<()> {
+    let path = checkpoint_path()?;
+    if path.exists() {
+        fs::remove_file(&path)
+            .with_context(|| format!("failed to remove checkpoint: {}", path.display()))?;
+        debug!(path = %path.display(), "registration checkpoint removed");
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resume_discards_checkpoint_for_other_key_or_profile() {
+        let saved = Checkpoint {
+            public_key: "02aa".to_string(),
+            profile_digest: "d1".to_string(),
+            profile_cid: Some("bafyold".to_string()),
+            submitted_at: None,
+        };
+
+        let same = Checkpoint::resume(Some(saved.clone()), "02aa", "d1");
+        assert_eq!(same, saved);
+
+        let edited = Checkpoint::resume(Some(saved.clone()), "02aa", "d2");
+        assert_eq!(edited.profile_cid, None);
+        assert_eq!(edited.profile_digest, "d2");
+
+        let rekeyed = Checkpoint::resume(Some(saved), "02bb", "d1");
+        assert_eq!(rekeyed.profile_cid, None);
+
+        assert_eq!(Checkpoint::resume(None, "02aa", "d1").public_key, "02aa");
+        assert_ne!(profile_digest("{}"), profile_digest("{ }"));
+    }
+}
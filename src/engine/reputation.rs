@@ -1,5 +1,18 @@
+//! Reputation scores, computed from validation records.
+//!
+//! [`compute_reputation`] is the pure scoring formula. Looking another agent
+//! up on the network (for `agentmarket reputation` and `search
+//! --min-reputation`) happens in the `reputation` command, which scores the
+//! verdicts it reads here and reports them as a [`RemoteReputation`].
+
+use std::str::FromStr;
+
+use alloy::primitives::{Address, U256};
+use anyhow::{bail, Result};
 use serde::{Deserialize, Serialize};
 
+use crate::engine::identity::AgentProfile;
+
 /// A single validation record used for reputation computation.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ValidationRecord {
@@ -112,6 +125,55 @@ pub struct AgentSummary {
     pub pending_validations: u64,
 }
 
+// ---------------------------------------------------------------------------
+// Remote reputation
+// ---------------------------------------------------------------------------
+
+/// An agent to look up: its ID in the Agent Registry or its address.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AgentRef {
+    Id(U256),
+    Address(Address),
+}
+
+impl AgentRef {
+    /// Parse an agent ID such as `42` or a `0x` address.
+    pub fn parse(input: &str) -> Result<Self> {
+        let input = input.trim();
+        if input.starts_with("0x") || input.starts_with("0X") {
+            if let Ok(address) = Address::from_str(input) {
+                return Ok(AgentRef::Address(address));
+            }
+        } else if let Ok(id) = U256::from_str_radix(input, 10) {
+            if !id.is_zero() {
+                return Ok(AgentRef::Id(id));
+            }
+        }
+        bail!(
+            "'{input}' is not an agent. Pass an agent ID as shown by `agentmarket search`, \
+             or the agent's 0x address."
+        )
+    }
+}
+
+/// Another agent's published profile and the reputation earned by its
+/// responses, as shown by `agentmarket reputation`.
+#[derive(Clone, Debug, Serialize)]
+pub struct RemoteReputation {
+    pub agent_id: String,
+    pub address: String,
+    pub profile: AgentProfile,
+    pub reputation: ReputationScore,
+    pub tier: String,
+}
+
+impl RemoteReputation {
+    /// The score, or `None` if the agent is unrated.
+    pub fn rating(&self) -> Option<f64> {
+        (self.tier != "Unrated").then_some(self.reputation.score)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -248,6 +310,23 @@ mod tests {
         assert_eq!(reputation_tier(&score), "New");
     }
 
+    // -- remote reputation ----------------------------------------------------
+
+    #[test]
+    fn test_agent_ref_parse() {
+        assert_eq!(AgentRef::parse("42").unwrap(), AgentRef::Id(U256::from(42)));
+        assert_eq!(
+            AgentRef::parse(" 0x833589fcd6edb6e08f4c7c32d4f71b54bda02913 ").unwrap(),
+            AgentRef::Address(
+                Address::from_str("0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913").unwrap()
+            )
+        );
+        for input in ["0", "summarizer", "0x1234", "", "-3"] {
+            let err = AgentRef::parse(input).unwrap_err().to_string();
+            assert!(err.contains("is not an agent"), "{input:?}: {err}");
+        }
+    }
+
     #[test]
     fn test_reputation_tier_unrated() {
         let score = compute_reputation("agent1", &[], 0, 0);
@@ -11,23 +11,35 @@
 //! This module is pure business logic. It does not interact with the blockchain
 //! or IPFS directly — those operations are orchestrated by the command layer.
 
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::PathBuf;
+use std::sync::{mpsc, Mutex, MutexGuard};
+use std::time::Duration;
 
 use alloy::primitives::keccak256;
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use rand::Rng;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use tracing::debug;
+use zeroize::Zeroize;
 
+use crate::clock;
+use crate::clock::{format_duration, unix_now};
 use crate::config::store::config_dir;
+use crate::engine::payment::PaymentCheck;
+use crate::output::formatter;
 
 // ---------------------------------------------------------------------------
 // Request status (state machine)
 // ---------------------------------------------------------------------------
 
 /// Local representation of a request's lifecycle.
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+///
+/// Variants are ordered by lifecycle stage, which is the order used when
+/// statuses are listed or used as map keys.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum LocalRequestStatus {
     /// Request created, waiting for a response.
     Open,
@@ -44,6 +56,27 @@ pub enum LocalRequestStatus {
 }
 
 impl LocalRequestStatus {
+    /// Every status, in lifecycle order.
+    pub const ALL: [LocalRequestStatus; 6] = [
+        LocalRequestStatus::Open,
+        LocalRequestStatus::Responded,
+        LocalRequestStatus::Validated,
+        LocalRequestStatus::Claimed,
+        LocalRequestStatus::Cancelled,
+        LocalRequestStatus::Expired,
+    ];
+
+    /// `true` for `Claimed`, `Cancelled`, and `Expired`, which have no
+    /// outgoing transitions.
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            LocalRequestStatus::Claimed
+                | LocalRequestStatus::Cancelled
+                | LocalRequestStatus::Expired
+        )
+    }
+
     /// Returns `true` if transitioning from `self` to `next` is valid.
     ///
     /// Valid transitions:
@@ -70,13 +103,22 @@ impl LocalRequestStatus {
 // ---------------------------------------------------------------------------
 
 /// The role this agent plays in a given request.
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum RequestRole {
     Buyer,
     Seller,
     Validator,
 }
 
+impl RequestRole {
+    /// Every role.
+    pub const ALL: [RequestRole; 3] = [
+        RequestRole::Buyer,
+        RequestRole::Seller,
+        RequestRole::Validator,
+    ];
+}
+
 // ---------------------------------------------------------------------------
 // Local request
 // ---------------------------------------------------------------------------
@@ -86,6 +128,10 @@ pub enum RequestRole {
 /// Stored as `{request_id}.json` inside `~/.agentmarket/requests/`.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct LocalRequest {
+    /// On-disk schema version. Files written before versioning was introduced
+    /// have no such field and deserialise as version 0.
+    #[serde(default)]
+    pub schema_version: u32,
     /// On-chain request ID (stringified U256).
     pub request_id: String,
     /// Role of this agent in the request.
@@ -110,15 +156,357 @@ pub struct LocalRequest {
     pub created_at: u64,
     /// Last updated timestamp.
     pub updated_at: u64,
+    /// Status before the most recent transition, if any.
+    #[serde(default)]
+    pub previous_status: Option<LocalRequestStatus>,
+    /// User-defined labels for organising work (lowercase, deduplicated).
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Start of the task description, so the request can be recognised
+    /// without fetching and decrypting its payload. Never holds deliverable
+    /// content.
+    #[serde(default)]
+    pub task_summary: Option<String>,
+    /// Capability the buyer declared the task needs (e.g. `code-review`),
+    /// copied from the request payload. `None` if none was declared.
+    #[serde(default)]
+    pub capability: Option<String>,
+    /// Seller whose standing approval confirmed this request (see
+    /// [`crate::engine::trust`]). Set only for policy-driven requests.
+    #[serde(default)]
+    pub trust_policy: Option<String>,
+    /// Status transitions, oldest first, capped at [`MAX_HISTORY_ENTRIES`].
+    #[serde(default)]
+    pub history: Vec<TransitionEntry>,
+    /// Whether the buyer's payment was secured when we responded (see
+    /// [`crate::engine::payment`]). Set only for seller responses checked
+    /// against a deployed registry.
+    #[serde(default)]
+    pub payment_check: Option<PaymentCheck>,
+    /// A response uploaded by a `respond` run that did not finish, so a
+    /// re-run reuses the upload. Cleared once the response is recorded.
+    #[serde(default)]
+    pub response_attempt: Option<ResponseAttempt>,
+}
+
+/// One status change recorded in [`LocalRequest::history`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransitionEntry {
+    pub from: LocalRequestStatus,
+    pub to: LocalRequestStatus,
+    /// Unix timestamp of the transition.
+    pub timestamp: u64,
+    /// Why the transition happened, when it was not a plain user command
+    /// (e.g. `"auto-claimed"` or `"validation score 85"`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+/// Progress of an unfinished response, kept in
+/// [`LocalRequest::response_attempt`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResponseAttempt {
+    /// [`deliverable_digest`] of the uploaded deliverable.
+    pub digest: String,
+    /// Content ID of the encrypted deliverable.
+    pub cid: String,
+    /// When the response was submitted on-chain, if it was.
+    #[serde(default)]
+    pub submitted_at: Option<u64>,
+}
+
+/// Hex SHA-256 of a plaintext deliverable, to tell whether a re-run of
+/// `respond` is sending the same one.
+pub fn deliverable_digest(payload: &[u8]) -> String {
+    hex::encode(Sha256::digest(payload))
+}
+
+/// Maximum number of entries kept in [`LocalRequest::history`]; the oldest
+/// are dropped first.
+pub const MAX_HISTORY_ENTRIES: usize = 50;
+
+impl LocalRequest {
+    /// Move the request to `next`, enforcing the lifecycle state machine.
+    ///
+    /// On success the previous status is recorded, an entry is appended to
+    /// [`LocalRequest::history`], and `updated_at` is bumped to the current
+    /// time. On failure the request is left untouched.
+    pub fn transition(&mut self, next: LocalRequestStatus) -> Result<()> {
+        self.transition_with_note(next, None)
+    }
+
+    /// Like [`LocalRequest::transition`], recording `note` in the history
+    /// entry.
+    pub fn transition_with_note(
+        &mut self,
+        next: LocalRequestStatus,
+        note: Option<String>,
+    ) -> Result<()> {
+        if !self.status.can_transition_to(&next) {
+            bail!(
+                "invalid status transition for request {}: {:?} -> {:?}",
+                self.request_id,
+                self.status,
+                next
+            );
+        }
+
+        debug!(
+            request_id = %self.request_id,
+            from = ?self.status,
+            to = ?next,
+            "request status transition"
+        );
+        self.record_status(next, note);
+        Ok(())
+    }
+
+    /// Put the request back in `previous`, undoing a transition made from
+    /// chain data that a reorg has since removed. Unlike
+    /// [`LocalRequest::transition`] this may move backwards; the reversal is
+    /// recorded in the history with `note`.
+    pub fn revert_to(&mut self, previous: LocalRequestStatus, note: String) {
+        debug!(
+            request_id = %self.request_id,
+            from = ?self.status,
+            to = ?previous,
+            "request status reverted"
+        );
+        self.record_status(previous, Some(note));
+    }
+
+    fn record_status(&mut self, next: LocalRequestStatus, note: Option<String>) {
+        let now = unix_now();
+        let from = std::mem::replace(&mut self.status, next.clone());
+        self.history.push(TransitionEntry {
+            from: from.clone(),
+            to: next,
+            timestamp: now,
+            note,
+        });
+        if self.history.len() > MAX_HISTORY_ENTRIES {
+            let excess = self.history.len() - MAX_HISTORY_ENTRIES;
+            self.history.drain(..excess);
+        }
+        self.previous_status = Some(from);
+        self.updated_at = now;
+    }
+
+    /// `true` once `now` is strictly after the deadline. A deadline equal to
+    /// `now` has not yet passed.
+    pub fn is_past_deadline(&self, now: u64) -> bool {
+        self.deadline < now
+    }
+
+    /// `true` if the request is non-terminal and overdue: `Open` and
+    /// `Responded` requests at the deadline, `Validated` ones only once the
+    /// claim grace period has also passed, since the seller may still claim.
+    pub fn is_expirable(&self, now: u64, claim_grace_secs: u64) -> bool {
+        match self.status {
+            LocalRequestStatus::Open | LocalRequestStatus::Responded => self.is_past_deadline(now),
+            LocalRequestStatus::Validated => {
+                self.is_past_deadline(now.saturating_sub(claim_grace_secs))
+            }
+            _ => false,
+        }
+    }
+
+    /// Time left until the deadline, or `None` once it has passed.
+    pub fn time_remaining(&self, now: u64) -> Option<Duration> {
+        self.deadline.checked_sub(now).map(Duration::from_secs)
+    }
+
+    /// Copy of this request that is safe to print or export: the seller's
+    /// secret is removed. The secret hash stays, since it is public.
+    pub fn without_secret(&self) -> LocalRequest {
+        LocalRequest {
+            secret: None,
+            ..self.clone()
+        }
+    }
+
+    /// The deadline relative to `now`, e.g. `"in 3h 20m"`, `"due now"`, or
+    /// `"expired 2d ago"`.
+    pub fn format_deadline(&self, now: u64) -> String {
+        match self.time_remaining(now) {
+            Some(left) if left.is_zero() => "due now".to_string(),
+            Some(left) => format!("in {}", format_duration(left.as_secs())),
+            None => format!("expired {} ago", format_duration(now - self.deadline)),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Summary
+// ---------------------------------------------------------------------------
+
+/// Aggregates over the request cache, as returned by
+/// [`RequestCache::summary`]. Amounts are in USDC atomic units.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct RequestSummary {
+    /// Number of cached requests.
+    pub total: usize,
+    /// Requests per status; every status is present, possibly with 0.
+    pub by_status: BTreeMap<LocalRequestStatus, usize>,
+    /// Requests per role; every role is present, possibly with 0.
+    pub by_role: BTreeMap<RequestRole, usize>,
+    /// Price of our validated, not yet claimed work (seller side).
+    pub claimable_usdc: u64,
+    /// Price of our open requests (buyer side), set aside until they settle.
+    pub committed_usdc: u64,
+    /// Price of our claimed work (seller side).
+    pub earned_usdc: u64,
+}
+
+impl RequestSummary {
+    /// Summarise `requests`.
+    pub fn from_requests(requests: &[LocalRequest]) -> Self {
+        let mut summary = Self {
+            total: requests.len(),
+            by_status: LocalRequestStatus::ALL
+                .into_iter()
+                .map(|s| (s, 0))
+                .collect(),
+            by_role: RequestRole::ALL.into_iter().map(|r| (r, 0)).collect(),
+            ..Self::default()
+        };
+
+        for request in requests {
+            *summary.by_status.entry(request.status.clone()).or_default() += 1;
+            *summary.by_role.entry(request.role.clone()).or_default() += 1;
+            match (&request.status, &request.role) {
+                (LocalRequestStatus::Validated, RequestRole::Seller) => {
+                    summary.claimable_usdc += request.price_usdc;
+                }
+                (LocalRequestStatus::Open, RequestRole::Buyer) => {
+                    summary.committed_usdc += request.price_usdc;
+                }
+                (LocalRequestStatus::Claimed, RequestRole::Seller) => {
+                    summary.earned_usdc += request.price_usdc;
+                }
+                _ => {}
+            }
+        }
+        summary
+    }
+
+    /// Number of requests in `status`.
+    pub fn count(&self, status: &LocalRequestStatus) -> usize {
+        self.by_status.get(status).copied().unwrap_or(0)
+    }
+
+    /// Requests still in progress (`Open`, `Responded`, or `Validated`).
+    pub fn active(&self) -> usize {
+        self.count(&LocalRequestStatus::Open)
+            + self.count(&LocalRequestStatus::Responded)
+            + self.count(&LocalRequestStatus::Validated)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Schema migrations
+// ---------------------------------------------------------------------------
+
+/// Schema version written by this build of the CLI.
+pub const CURRENT_SCHEMA_VERSION: u32 = 1;
+
+/// A single migration step operating on the raw JSON of a request file.
+///
+/// The step registered at index `n` of [`MIGRATIONS`] upgrades a document
+/// from version `n` to version `n + 1`.
+type MigrationFn = fn(&mut serde_json::Value) -> Result<()>;
+
+/// Ordered migration steps. Append a new function here (and bump
+/// [`CURRENT_SCHEMA_VERSION`]) whenever the on-disk layout changes.
+const MIGRATIONS: &[MigrationFn] = &[migrate_v0_to_v1];
+
+/// v0 → v1: introduces the `schema_version` field. No other fields changed.
+fn migrate_v0_to_v1(_doc: &mut serde_json::Value) -> Result<()> {
+    Ok(())
+}
+
+/// Upgrade a raw request document to [`CURRENT_SCHEMA_VERSION`] in place.
+///
+/// Returns `true` if any migration step was applied. Fails if the document
+/// was written by a newer CLI than this one.
+fn migrate_document(doc: &mut serde_json::Value) -> Result<bool> {
+    let obj = doc
+        .as_object_mut()
+        .context("request file is not a JSON object")?;
+
+    let from = obj
+        .get("schema_version")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0) as u32;
+
+    if from > CURRENT_SCHEMA_VERSION {
+        bail!(
+            "request file has schema version {from}, but this CLI only understands up to \
+             version {CURRENT_SCHEMA_VERSION}; please upgrade agentmarket"
+        );
+    }
+
+    if from == CURRENT_SCHEMA_VERSION {
+        return Ok(false);
+    }
+
+    for version in from..CURRENT_SCHEMA_VERSION {
+        debug!(
+            from = version,
+            to = version + 1,
+            "migrating request document"
+        );
+        MIGRATIONS[version as usize](doc)?;
+        if let Some(obj) = doc.as_object_mut() {
+            obj.insert("schema_version".to_string(), (version + 1).into());
+        }
+    }
+
+    Ok(true)
+}
+
+/// Parse a request file's contents, applying any pending migrations.
+///
+/// Returns the request and whether it was migrated (and therefore needs to
+/// be written back to disk).
+fn parse_request(contents: &str) -> Result<(LocalRequest, bool)> {
+    let mut doc: serde_json::Value = serde_json::from_str(contents)?;
+    let migrated = migrate_document(&mut doc)?;
+    let request: LocalRequest = serde_json::from_value(doc)?;
+    Ok((request, migrated))
+}
+
+/// Returns `true` if `contents` is a request document from a newer schema
+/// than this CLI understands.
+fn written_by_newer_cli(contents: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(contents)
+        .ok()
+        .and_then(|doc| doc.get("schema_version")?.as_u64())
+        .is_some_and(|version| version > u64::from(CURRENT_SCHEMA_VERSION))
 }
 
 // ---------------------------------------------------------------------------
 // Request cache
 // ---------------------------------------------------------------------------
 
+/// Default number of seconds past the deadline that a `Validated` request
+/// remains claimable before the expiry sweep marks it `Expired`.
+pub const DEFAULT_CLAIM_GRACE_SECS: u64 = 3600;
+
 /// Name of the requests subdirectory inside the config directory.
 const REQUESTS_DIR: &str = "requests";
 
+/// Subdirectory of the requests directory holding corrupted files.
+pub const QUARANTINE_DIR: &str = "quarantine";
+
+/// Result of [`RequestCache::load_all_checked`].
+#[derive(Debug, Default)]
+pub struct LoadedRequests {
+    pub requests: Vec<LocalRequest>,
+    /// Where each corrupted file was moved during this load.
+    pub quarantined: Vec<PathBuf>,
+}
+
 /// Manages local request state in `~/.agentmarket/requests/`.
 ///
 /// Each request is stored as a JSON file: `{request_id}.json`.
@@ -142,20 +530,62 @@ impl RequestCache {
 
     /// Serialize `request` to JSON and write to
     /// `~/.agentmarket/requests/{request_id}.json`.
+    ///
+    /// The file is written to a temporary sibling first and then renamed into
+    /// place, so a crash mid-write never leaves a truncated request file.
     pub fn save(request: &LocalRequest) -> Result<()> {
         let path = Self::requests_dir()?.join(format!("{}.json", request.request_id));
         debug!(path = %path.display(), request_id = %request.request_id, "saving request");
 
-        let json =
-            serde_json::to_string_pretty(request).context("failed to serialise request to JSON")?;
+        let old_status = if has_subscribers() {
+            stored_status(&path)
+        } else {
+            None
+        };
+
+        let mut request = request.clone();
+        request.schema_version = CURRENT_SCHEMA_VERSION;
+        request.tags = normalize_tags(&request.tags);
 
-        fs::write(&path, json)
+        let json = serde_json::to_string_pretty(&request)
+            .context("failed to serialise request to JSON")?;
+
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, json)
+            .with_context(|| format!("failed to write request file: {}", tmp_path.display()))?;
+        fs::rename(&tmp_path, &path)
             .with_context(|| format!("failed to write request file: {}", path.display()))?;
 
         debug!(path = %path.display(), "request saved");
+        if old_status.as_ref() != Some(&request.status) {
+            notify(RequestEvent {
+                request_id: request.request_id.clone(),
+                old_status,
+                new_status: Some(request.status),
+            });
+        }
         Ok(())
     }
 
+    /// Receive a [`RequestEvent`] for every status change saved by this
+    /// process from now on, and for every deletion.
+    ///
+    /// Events are delivered in-process only; changes made by another process
+    /// (e.g. a separately started daemon) are not seen. Dropping the receiver
+    /// unsubscribes.
+    pub fn subscribe() -> mpsc::Receiver<RequestEvent> {
+        let (tx, rx) = mpsc::channel();
+        subscribers().push(tx);
+        rx
+    }
+
+    /// Returns `true` if `request_id` is tracked in the local cache.
+    pub fn contains(request_id: &str) -> Result<bool> {
+        Ok(Self::requests_dir()?
+            .join(format!("{}.json", request_id))
+            .exists())
+    }
+
     /// Read and deserialize a request from
     /// `~/.agentmarket/requests/{request_id}.json`.
     pub fn load(request_id: &str) -> Result<LocalRequest> {
@@ -165,19 +595,213 @@ impl RequestCache {
         let contents = fs::read_to_string(&path)
             .with_context(|| format!("failed to read request file: {}", path.display()))?;
 
-        let request: LocalRequest = serde_json::from_str(&contents)
+        let (request, migrated) = parse_request(&contents)
             .with_context(|| format!("failed to parse request file: {}", path.display()))?;
 
+        if migrated {
+            Self::save(&request)?;
+        }
+
         debug!(request_id = %request.request_id, "request loaded");
         Ok(request)
     }
 
     /// Read all request files in the requests directory.
+    ///
+    /// Files that cannot be read or parsed are moved aside (see
+    /// [`RequestCache::load_all_checked`]) rather than failing the load.
     pub fn load_all() -> Result<Vec<LocalRequest>> {
+        Ok(Self::load_all_checked()?.requests)
+    }
+
+    /// Read all request files, quarantining any that cannot be read or
+    /// parsed.
+    ///
+    /// A bad file is moved to `requests/quarantine/` with a timestamp suffix,
+    /// a warning is printed, and loading continues. Files written by a newer
+    /// CLI are not quarantined: they are valid, just not understood, so the
+    /// load fails and asks for an upgrade.
+    pub fn load_all_checked() -> Result<LoadedRequests> {
+        Self::scan(|_| true)
+    }
+
+    /// Read the requests matching every filter that is `Some`, in a single
+    /// pass over the requests directory. `counterparty` is compared
+    /// case-insensitively, since addresses may be stored checksummed.
+    /// Corrupted files are quarantined as in [`RequestCache::load_all`].
+    pub fn query(
+        status: Option<LocalRequestStatus>,
+        role: Option<RequestRole>,
+        counterparty: Option<&str>,
+    ) -> Result<Vec<LocalRequest>> {
+        let matched = Self::scan(|r| {
+            status.as_ref().map_or(true, |s| r.status == *s)
+                && role.as_ref().map_or(true, |ro| r.role == *ro)
+                && counterparty.map_or(true, |c| {
+                    r.counterparty
+                        .as_deref()
+                        .is_some_and(|rc| rc.eq_ignore_ascii_case(c))
+                })
+        })?
+        .requests;
+
+        debug!(
+            count = matched.len(),
+            ?status,
+            ?role,
+            ?counterparty,
+            "queried requests"
+        );
+        Ok(matched)
+    }
+
+    /// Read every request file, keeping those `keep` accepts. Shared by
+    /// [`RequestCache::load_all_checked`] and [`RequestCache::query`].
+    fn scan(keep: impl Fn(&LocalRequest) -> bool) -> Result<LoadedRequests> {
         let dir = Self::requests_dir()?;
         debug!(path = %dir.display(), "loading all requests");
 
-        let mut requests = Vec::new();
+        let mut loaded = LoadedRequests::default();
+
+        for entry in fs::read_dir(&dir)
+            .with_context(|| format!("failed to read requests directory: {}", dir.display()))?
+        {
+            let entry = entry.context("failed to read directory entry")?;
+            let path = entry.path();
+
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+
+            let parsed = match fs::read_to_string(&path) {
+                Err(err) => Err(anyhow::Error::new(err).context("unreadable")),
+                Ok(contents) => match parse_request(&contents) {
+                    Err(err) if written_by_newer_cli(&contents) => {
+                        return Err(err
+                            .context(format!("failed to parse request file: {}", path.display())));
+                    }
+                    other => other,
+                },
+            };
+
+            match parsed {
+                Ok((request, migrated)) => {
+                    if migrated {
+                        Self::save(&request)?;
+                    }
+                    if keep(&request) {
+                        loaded.requests.push(request);
+                    }
+                }
+                Err(err) => {
+                    let moved_to = Self::quarantine(&path)?;
+                    formatter::print_warning(&format!(
+                        "Request file {} is corrupted ({err}); moved it to {}.",
+                        path.display(),
+                        moved_to.display()
+                    ));
+                    loaded.quarantined.push(moved_to);
+                }
+            }
+        }
+
+        debug!(
+            count = loaded.requests.len(),
+            quarantined = loaded.quarantined.len(),
+            "loaded all requests"
+        );
+        Ok(loaded)
+    }
+
+    /// Move a bad request file into `requests/quarantine/`, appending the
+    /// current Unix time (and a counter if needed) so nothing is
+    /// overwritten. Returns the new path.
+    fn quarantine(path: &std::path::Path) -> Result<PathBuf> {
+        let dir = Self::requests_dir()?.join(QUARANTINE_DIR);
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create quarantine directory: {}", dir.display()))?;
+
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("request.json");
+        let now = clock::unix_now();
+        let mut target = dir.join(format!("{name}.{now}"));
+        let mut n = 1;
+        while target.exists() {
+            target = dir.join(format!("{name}.{now}.{n}"));
+            n += 1;
+        }
+
+        fs::rename(path, &target).with_context(|| {
+            format!(
+                "failed to quarantine request file {} to {}",
+                path.display(),
+                target.display()
+            )
+        })?;
+        debug!(from = %path.display(), to = %target.display(), "request file quarantined");
+        Ok(target)
+    }
+
+    /// Load a request, apply `f` to it, and persist the result.
+    ///
+    /// Nothing is written if `f` fails. If `f` changed the status (whether via
+    /// [`LocalRequest::transition`] or by assigning it directly), the change is
+    /// checked against the state machine before saving, so an invalid
+    /// transition can never reach disk. Returns the saved request.
+    pub fn update<F>(request_id: &str, f: F) -> Result<LocalRequest>
+    where
+        F: FnOnce(&mut LocalRequest) -> Result<()>,
+    {
+        debug!(request_id = %request_id, "updating request");
+
+        let original = Self::load(request_id)?;
+        let mut request = original.clone();
+        f(&mut request)?;
+
+        if request.request_id != original.request_id {
+            bail!(
+                "request ID cannot be changed during an update ({} -> {})",
+                original.request_id,
+                request.request_id
+            );
+        }
+
+        if request.status != original.status && !original.status.can_transition_to(&request.status)
+        {
+            bail!(
+                "invalid status transition for request {}: {:?} -> {:?}",
+                original.request_id,
+                original.status,
+                request.status
+            );
+        }
+
+        Self::save(&request)?;
+        Ok(request)
+    }
+
+    /// Upgrade every request file on disk to [`CURRENT_SCHEMA_VERSION`].
+    ///
+    /// Returns the number of files that were rewritten. Files that are
+    /// already current are left untouched.
+    pub fn migrate_all() -> Result<usize> {
+        Ok(Self::scan_migrations(true)?.len())
+    }
+
+    /// IDs of requests whose files are older than
+    /// [`CURRENT_SCHEMA_VERSION`] and would be rewritten by
+    /// [`RequestCache::migrate_all`]. Nothing is written.
+    pub fn pending_migrations() -> Result<Vec<String>> {
+        Self::scan_migrations(false)
+    }
+
+    fn scan_migrations(write: bool) -> Result<Vec<String>> {
+        let dir = Self::requests_dir()?;
+        debug!(path = %dir.display(), write, "scanning request cache for migrations");
+
+        let mut migrated = Vec::new();
 
         for entry in fs::read_dir(&dir)
             .with_context(|| format!("failed to read requests directory: {}", dir.display()))?
@@ -185,36 +809,115 @@ impl RequestCache {
             let entry = entry.context("failed to read directory entry")?;
             let path = entry.path();
 
-            if path.extension().and_then(|e| e.to_str()) == Some("json") {
-                let contents = fs::read_to_string(&path)
-                    .with_context(|| format!("failed to read request file: {}", path.display()))?;
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+
+            let contents = fs::read_to_string(&path)
+                .with_context(|| format!("failed to read request file: {}", path.display()))?;
+
+            let (request, needs_migration) = match parse_request(&contents) {
+                Ok(parsed) => parsed,
+                Err(err) if written_by_newer_cli(&contents) => {
+                    return Err(
+                        err.context(format!("failed to parse request file: {}", path.display()))
+                    );
+                }
+                Err(err) => {
+                    // Corrupted; the next full load quarantines it.
+                    debug!(path = %path.display(), error = %err, "skipping unparseable request file");
+                    continue;
+                }
+            };
+
+            if needs_migration {
+                if write {
+                    Self::save(&request)?;
+                }
+                migrated.push(request.request_id);
+            }
+        }
+
+        debug!(
+            count = migrated.len(),
+            write, "request cache migration scan complete"
+        );
+        Ok(migrated)
+    }
+
+    /// Expire every non-terminal request whose deadline has passed, using
+    /// the default claim grace period for `Validated` requests.
+    ///
+    /// See [`RequestCache::sweep_expired_with_grace`].
+    pub fn sweep_expired(now: u64) -> Result<Vec<String>> {
+        Self::sweep_expired_with_grace(now, DEFAULT_CLAIM_GRACE_SECS)
+    }
+
+    /// Expire every non-terminal request whose deadline has passed.
+    ///
+    /// `Open` and `Responded` requests expire as soon as `deadline < now`.
+    /// `Validated` requests are still claimable, so they only expire once
+    /// `deadline + claim_grace_secs < now`. Each request goes through
+    /// [`RequestCache::update`], so the state machine is enforced. Returns
+    /// the IDs of the requests that were expired.
+    pub fn sweep_expired_with_grace(now: u64, claim_grace_secs: u64) -> Result<Vec<String>> {
+        debug!(now, claim_grace_secs, "sweeping expired requests");
+
+        let expired = Self::expired_candidates(now, claim_grace_secs)?;
+        for request_id in &expired {
+            Self::update(request_id, |r| {
+                r.transition_with_note(
+                    LocalRequestStatus::Expired,
+                    Some("deadline passed".to_string()),
+                )
+            })?;
+        }
+
+        debug!(count = expired.len(), "expired requests swept");
+        Ok(expired)
+    }
 
-                let request: LocalRequest = serde_json::from_str(&contents)
-                    .with_context(|| format!("failed to parse request file: {}", path.display()))?;
+    /// IDs of the requests [`RequestCache::sweep_expired_with_grace`] would
+    /// expire. Nothing is written.
+    pub fn expired_candidates(now: u64, claim_grace_secs: u64) -> Result<Vec<String>> {
+        let mut candidates = Vec::new();
 
-                requests.push(request);
+        for request in Self::load_all()? {
+            if request.is_expirable(now, claim_grace_secs) {
+                candidates.push(request.request_id);
             }
         }
 
-        debug!(count = requests.len(), "loaded all requests");
-        Ok(requests)
+        Ok(candidates)
     }
 
     /// Read all requests and filter by status.
     pub fn load_by_status(status: LocalRequestStatus) -> Result<Vec<LocalRequest>> {
-        let all = Self::load_all()?;
-        let filtered: Vec<LocalRequest> = all.into_iter().filter(|r| r.status == status).collect();
-
-        debug!(count = filtered.len(), ?status, "loaded requests by status");
-        Ok(filtered)
+        Self::query(Some(status), None, None)
     }
 
     /// Read all requests and filter by role.
     pub fn load_by_role(role: RequestRole) -> Result<Vec<LocalRequest>> {
+        Self::query(None, Some(role), None)
+    }
+
+    /// Aggregate counts and amounts over every cached request.
+    pub fn summary() -> Result<RequestSummary> {
+        let summary = RequestSummary::from_requests(&Self::load_all()?);
+        debug!(total = summary.total, "request cache summarised");
+        Ok(summary)
+    }
+
+    /// Read all requests carrying `tag` (matched case-insensitively).
+    pub fn load_by_tag(tag: &str) -> Result<Vec<LocalRequest>> {
+        let tag = tag.trim().to_lowercase();
         let all = Self::load_all()?;
-        let filtered: Vec<LocalRequest> = all.into_iter().filter(|r| r.role == role).collect();
+        let filtered: Vec<LocalRequest> = all
+            .into_iter()
+            .filter(|r| normalize_tags(&r.tags).contains(&tag))
+            .collect();
 
-        debug!(count = filtered.len(), ?role, "loaded requests by role");
+        debug!(count = filtered.len(), tag = %tag, "loaded requests by tag");
         Ok(filtered)
     }
 
@@ -223,14 +926,146 @@ impl RequestCache {
         let path = Self::requests_dir()?.join(format!("{}.json", request_id));
         debug!(path = %path.display(), "deleting request");
 
+        let old_status = if has_subscribers() {
+            stored_status(&path)
+        } else {
+            None
+        };
+
         fs::remove_file(&path)
             .with_context(|| format!("failed to delete request file: {}", path.display()))?;
 
         debug!(path = %path.display(), "request deleted");
+        notify(RequestEvent {
+            request_id: request_id.to_string(),
+            old_status,
+            new_status: None,
+        });
         Ok(())
     }
 }
 
+// ---------------------------------------------------------------------------
+// Change notifications
+// ---------------------------------------------------------------------------
+
+/// A change to a cached request, delivered to receivers returned by
+/// [`RequestCache::subscribe`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RequestEvent {
+    pub request_id: String,
+    /// Status before the change; `None` if the request was not cached yet.
+    pub old_status: Option<LocalRequestStatus>,
+    /// Status after the change; `None` if the request was deleted.
+    pub new_status: Option<LocalRequestStatus>,
+}
+
+/// Live subscriptions. Senders whose receiver was dropped are pruned on the
+/// next notification.
+static SUBSCRIBERS: Mutex<Vec<mpsc::Sender<RequestEvent>>> = Mutex::new(Vec::new());
+
+fn subscribers() -> MutexGuard<'static, Vec<mpsc::Sender<RequestEvent>>> {
+    SUBSCRIBERS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn has_subscribers() -> bool {
+    !subscribers().is_empty()
+}
+
+fn notify(event: RequestEvent) {
+    let mut subscribers = subscribers();
+    if subscribers.is_empty() {
+        return;
+    }
+    debug!(request_id = %event.request_id, old = ?event.old_status, new = ?event.new_status, "request event");
+    subscribers.retain(|tx| tx.send(event.clone()).is_ok());
+}
+
+/// Status of the request currently stored at `path`, if it can be read.
+fn stored_status(path: &std::path::Path) -> Option<LocalRequestStatus> {
+    let contents = fs::read_to_string(path).ok()?;
+    parse_request(&contents).ok().map(|(r, _)| r.status)
+}
+
+// ---------------------------------------------------------------------------
+// Helpers: task summary
+// ---------------------------------------------------------------------------
+
+/// Maximum number of characters kept in [`LocalRequest::task_summary`].
+pub const TASK_SUMMARY_MAX_CHARS: usize = 200;
+
+/// Shorten a task description for [`LocalRequest::task_summary`]: whitespace
+/// is collapsed to single spaces and the text is cut at
+/// [`TASK_SUMMARY_MAX_CHARS`] characters, ending in `…` when truncated.
+pub fn summarize_task(task: &str) -> String {
+    let collapsed = task.split_whitespace().collect::<Vec<_>>().join(" ");
+    if collapsed.chars().count() <= TASK_SUMMARY_MAX_CHARS {
+        return collapsed;
+    }
+    let mut summary: String = collapsed.chars().take(TASK_SUMMARY_MAX_CHARS - 1).collect();
+    summary.push('…');
+    summary
+}
+
+// ---------------------------------------------------------------------------
+// Helpers: tags
+// ---------------------------------------------------------------------------
+
+/// Normalise user-supplied tags: trimmed, lowercased, empty entries dropped,
+/// and duplicates removed (first occurrence wins, order preserved).
+pub fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut out: Vec<String> = Vec::with_capacity(tags.len());
+    for tag in tags {
+        let tag = tag.trim().to_lowercase();
+        if !tag.is_empty() && !out.contains(&tag) {
+            out.push(tag);
+        }
+    }
+    out
+}
+
+// ---------------------------------------------------------------------------
+// Helpers: capability filter
+// ---------------------------------------------------------------------------
+
+/// Whether a request declaring `capability` passes the `--filter` pattern.
+///
+/// The pattern may use `*` to match any run of characters (`code-*`), and
+/// compares case-insensitively. Requests that declare no capability pass
+/// unless `strict` is set.
+pub fn matches_capability(pattern: &str, capability: Option<&str>, strict: bool) -> bool {
+    match capability {
+        Some(capability) => glob_match(
+            &pattern.trim().to_lowercase(),
+            &capability.trim().to_lowercase(),
+        ),
+        None => !strict,
+    }
+}
+
+/// Match `text` against `pattern`, where `*` matches any (possibly empty)
+/// run of characters and everything else matches literally.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    // `split` always yields at least one part.
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts: Vec<&str> = parts.collect();
+    let Some(last) = parts.pop() else {
+        // No `*` at all: the whole text must match.
+        return rest.is_empty();
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(idx) => rest = &rest[idx + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
 // ---------------------------------------------------------------------------
 // Helpers: secret generation
 // ---------------------------------------------------------------------------
@@ -253,11 +1088,83 @@ pub fn generate_secret() -> (String, String) {
     (secret_hex, hash_hex)
 }
 
+/// Check that `secret_hex` is the preimage of `expected_hash_hex`, so a
+/// claim is never sent with a secret the registry would reject.
+///
+/// Both values may carry a `0x` prefix and must decode to 32 bytes. An
+/// empty `secret_hex` means the secret is missing.
+pub fn verify_secret(secret_hex: &str, expected_hash_hex: &str) -> Result<()> {
+    if strip_hex_prefix(secret_hex.trim()).is_empty() {
+        bail!("The claim secret is missing.");
+    }
+    let secret = decode_word(secret_hex)
+        .map_err(|problem| anyhow::anyhow!("The claim secret {problem}."))?;
+    let expected = decode_word(expected_hash_hex)
+        .map_err(|problem| anyhow::anyhow!("The expected secret hash {problem}."))?;
+
+    if keccak256(secret).0 != expected {
+        bail!("The claim secret does not hash to {expected_hash_hex}.");
+    }
+    Ok(())
+}
+
+fn strip_hex_prefix(value: &str) -> &str {
+    value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+        .unwrap_or(value)
+}
+
+/// Decode a 32-byte hex value, with or without a `0x` prefix. The error
+/// describes the problem, e.g. `"is not valid hex"`.
+fn decode_word(value: &str) -> std::result::Result<[u8; 32], String> {
+    let bytes =
+        hex::decode(strip_hex_prefix(value.trim())).map_err(|_| "is not valid hex".to_string())?;
+    let len = bytes.len();
+    bytes
+        .try_into()
+        .map_err(|_| format!("is {len} bytes long instead of 32"))
+}
+
+/// Domain separator for [`derive_secret`], so the derived value cannot
+/// collide with any other keccak256 use of the private key.
+const SECRET_DERIVATION_DOMAIN: &[u8] = b"agentmarket/claim-secret/v1";
+
+/// Derive the hash-lock secret for `request_id` from the agent's private key.
+///
+/// The secret is `keccak256(domain || len(request_id) || request_id || key)`,
+/// so the seller can re-derive it from the keystore if the local cache is
+/// lost. keccak256 is not vulnerable to length extension, and the length
+/// prefix keeps `(id, key)` pairs from running together.
+///
+/// Returns `(secret_hex, hash_hex)` in the same format as
+/// [`generate_secret`].
+pub fn derive_secret(private_key: &[u8], request_id: &str) -> (String, String) {
+    let mut preimage = Vec::with_capacity(
+        SECRET_DERIVATION_DOMAIN.len() + 8 + request_id.len() + private_key.len(),
+    );
+    preimage.extend_from_slice(SECRET_DERIVATION_DOMAIN);
+    preimage.extend_from_slice(&(request_id.len() as u64).to_be_bytes());
+    preimage.extend_from_slice(request_id.as_bytes());
+    preimage.extend_from_slice(private_key);
+    let secret_bytes = keccak256(&preimage);
+    preimage.zeroize();
+
+    let secret_hex = hex::encode(secret_bytes);
+    let hash_hex = format!("0x{}", hex::encode(keccak256(secret_bytes)));
+
+    debug!(request_id, "derived secret and hash");
+    (secret_hex, hash_hex)
+}
+
 // ---------------------------------------------------------------------------
 // Helpers: price formatting
 // ---------------------------------------------------------------------------
 
-/// Convert a USDC amount (6 decimals) to a human-readable dollar string.
+/// Convert a USDC amount (6 decimals) to a plain dollar string.
+///
+/// This is the machine-friendly form used in `--json` output and logs; see
+/// [`format_price_usd_pretty`] for human output.
 ///
 /// # Examples
 ///
@@ -266,37 +1173,290 @@ pub fn generate_secret() -> (String, String) {
 /// assert_eq!(format_price_usd(5_000_000), "$5.00");
 /// assert_eq!(format_price_usd(0), "$0.00");
 /// assert_eq!(format_price_usd(1), "$0.000001");
+/// assert_eq!(format_price_usd(12_500_000_000), "$12500.00");
 /// ```
 pub fn format_price_usd(usdc_amount: u64) -> String {
-    let dollars = usdc_amount / 1_000_000;
-    let cents = usdc_amount % 1_000_000;
-
-    if cents == 0 {
-        format!("${dollars}.00")
-    } else {
-        // Determine how many trailing zeros to trim, but keep at least 2 decimal places.
-        let cents_str = format!("{:06}", cents);
-        let trimmed = cents_str.trim_end_matches('0');
-        let decimals = if trimmed.len() < 2 {
-            &cents_str[..2]
-        } else {
-            trimmed
-        };
-        format!("${dollars}.{decimals}")
-    }
+    format!(
+        "${}.{}",
+        usdc_amount / 1_000_000,
+        format_micros(usdc_amount % 1_000_000)
+    )
 }
 
-/// Convert a dollar amount to USDC atomic units (6 decimals).
+/// Like [`format_price_usd`], with thousands separators in the dollar part.
 ///
 /// # Examples
 ///
 /// ```
-/// # use agentmarket::engine::requests::dollars_to_usdc;
-/// assert_eq!(dollars_to_usdc(5.0), 5_000_000);
-/// assert_eq!(dollars_to_usdc(0.5), 500_000);
+/// # use agentmarket::engine::requests::format_price_usd_pretty;
+/// assert_eq!(format_price_usd_pretty(12_500_000_000), "$12,500.00");
+/// assert_eq!(format_price_usd_pretty(1), "$0.000001");
 /// ```
-pub fn dollars_to_usdc(dollars: f64) -> u64 {
-    (dollars * 1_000_000.0).round() as u64
+pub fn format_price_usd_pretty(usdc_amount: u64) -> String {
+    format!(
+        "${}.{}",
+        group_thousands(usdc_amount / 1_000_000),
+        format_micros(usdc_amount % 1_000_000)
+    )
+}
+
+/// Fractional part of a dollar amount given in micro-dollars: trailing zeros
+/// are trimmed, but at least 2 decimal places are kept.
+fn format_micros(micros: u64) -> String {
+    let digits = format!("{micros:06}");
+    let trimmed = digits.trim_end_matches('0');
+    if trimmed.len() < 2 {
+        digits[..2].to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+/// `12500` -> `"12,500"`.
+fn group_thousands(value: u64) -> String {
+    let digits = value.to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Convert a dollar amount to USDC atomic units (6 decimals).
+///
+/// Internal use only: floating-point input can be off by a micro-unit. User
+/// input must go through [`parse_usd_amount`]; this remains for values that
+/// are already `f64`, such as configured prices and costs.
+///
+/// # Examples
+///
+/// ```
+/// # use agentmarket::engine::requests::dollars_to_usdc;
+/// assert_eq!(dollars_to_usdc(5.0), 5_000_000);
+/// assert_eq!(dollars_to_usdc(0.5), 500_000);
+/// ```
+pub fn dollars_to_usdc(dollars: f64) -> u64 {
+    (dollars * 1_000_000.0).round() as u64
+}
+
+/// Number of fractional digits in a USDC amount.
+const USDC_DECIMALS: usize = 6;
+
+/// Parse a user-supplied dollar amount exactly into USDC atomic units.
+///
+/// Accepts plain decimals with at most six fractional digits and an optional
+/// leading `$`. Negative numbers, exponents, and anything else are rejected
+/// rather than rounded.
+///
+/// # Examples
+///
+/// ```
+/// # use agentmarket::engine::requests::parse_usd_amount;
+/// assert_eq!(parse_usd_amount("5").unwrap(), 5_000_000);
+/// assert_eq!(parse_usd_amount("$0.07").unwrap(), 70_000);
+/// assert!(parse_usd_amount("1e3").is_err());
+/// ```
+pub fn parse_usd_amount(s: &str) -> Result<u64> {
+    let trimmed = s.trim();
+    let digits = trimmed.strip_prefix('$').unwrap_or(trimmed);
+    let units = parse_fixed_point(trimmed, digits, USDC_DECIMALS)?;
+    u64::try_from(units).map_err(|_| anyhow::anyhow!("Invalid amount \"{trimmed}\": too large."))
+}
+
+/// Number of fractional digits in an ETH amount.
+const ETH_DECIMALS: usize = 18;
+
+/// Parse a user-supplied ETH amount exactly into wei.
+///
+/// Same rules as [`parse_usd_amount`], with up to 18 fractional digits and
+/// no `$` prefix.
+///
+/// # Examples
+///
+/// ```
+/// # use agentmarket::engine::requests::parse_eth_amount;
+/// assert_eq!(parse_eth_amount("0.01").unwrap(), 10_000_000_000_000_000);
+/// assert!(parse_eth_amount("$1").is_err());
+/// ```
+pub fn parse_eth_amount(s: &str) -> Result<u128> {
+    let trimmed = s.trim();
+    parse_fixed_point(trimmed, trimmed, ETH_DECIMALS)
+}
+
+/// Parse the plain decimal `digits` into units of `10^-decimals`. `input` is
+/// the text quoted in errors.
+fn parse_fixed_point(input: &str, digits: &str, decimals: usize) -> Result<u128> {
+    if digits.starts_with('-') {
+        bail!("Invalid amount \"{input}\": amounts cannot be negative.");
+    }
+
+    let (whole, frac) = digits.split_once('.').unwrap_or((digits, ""));
+    let is_digits = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+    if (whole.is_empty() && frac.is_empty()) || !is_digits(whole) || !is_digits(frac) {
+        bail!("Invalid amount \"{input}\": use a plain number such as 5 or 5.25.");
+    }
+    if frac.len() > decimals {
+        bail!("Invalid amount \"{input}\": at most {decimals} decimal places are supported.");
+    }
+
+    let too_large = || anyhow::anyhow!("Invalid amount \"{input}\": too large.");
+    let whole_units: u128 = if whole.is_empty() {
+        0
+    } else {
+        whole.parse().map_err(|_| too_large())?
+    };
+    let frac_units: u128 = format!("{frac:0<decimals$}")
+        .parse()
+        .expect("at most 18 ASCII digits always parse");
+
+    whole_units
+        .checked_mul(10u128.pow(decimals as u32))
+        .and_then(|units| units.checked_add(frac_units))
+        .ok_or_else(too_large)
+}
+
+// ---------------------------------------------------------------------------
+// Helpers: deadlines
+// ---------------------------------------------------------------------------
+
+/// Furthest ahead a request deadline may be set, in seconds.
+pub const MAX_DEADLINE_SECS: u64 = 365 * 86_400;
+
+/// Parse a `--deadline` value into a Unix timestamp.
+///
+/// Accepts a whole number of hours from `now` (`24`), a duration made of
+/// `d`, `h`, `m`, and `s` parts (`36h`, `2d12h`), or an RFC 3339 timestamp
+/// (`2026-10-16T17:00:00Z`). The deadline must be after `now` and no more
+/// than 365 days away.
+///
+/// # Examples
+///
+/// ```
+/// # use agentmarket::engine::requests::parse_deadline;
+/// assert_eq!(parse_deadline("24", 0).unwrap(), 86_400);
+/// assert_eq!(parse_deadline("2d12h", 0).unwrap(), 216_000);
+/// assert_eq!(parse_deadline("1970-01-02T00:00:00Z", 0).unwrap(), 86_400);
+/// ```
+pub fn parse_deadline(input: &str, now: u64) -> Result<u64> {
+    let trimmed = input.trim();
+    let invalid = || {
+        anyhow::anyhow!(
+            "Invalid deadline \"{trimmed}\": use hours (24), a duration (36h, 2d12h), \
+             or an RFC 3339 timestamp (2026-10-16T17:00:00Z)."
+        )
+    };
+
+    let deadline: i128 = if !trimmed.is_empty() && trimmed.chars().all(|c| c.is_ascii_digit()) {
+        let hours: u64 = trimmed.parse().unwrap_or(u64::MAX);
+        i128::from(now) + i128::from(hours) * 3600
+    } else if trimmed.starts_with(|c: char| c.is_ascii_digit()) && trimmed.contains('-') {
+        i128::from(parse_rfc3339(trimmed).ok_or_else(invalid)?)
+    } else {
+        i128::from(now) + i128::from(parse_duration_secs(trimmed).ok_or_else(invalid)?)
+    };
+
+    if deadline <= i128::from(now) {
+        bail!("Deadline \"{trimmed}\" is in the past; it must be later than now.");
+    }
+    if deadline - i128::from(now) > i128::from(MAX_DEADLINE_SECS) {
+        bail!(
+            "Deadline \"{trimmed}\" is more than {} days away.",
+            MAX_DEADLINE_SECS / 86_400
+        );
+    }
+    Ok(deadline as u64)
+}
+
+/// Seconds in a duration such as `36h`, `2d12h`, or `90m`. Every number
+/// needs a unit.
+fn parse_duration_secs(s: &str) -> Option<u64> {
+    let mut total: u64 = 0;
+    let mut number = String::new();
+    for c in s.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let unit = match c.to_ascii_lowercase() {
+            'd' => 86_400,
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return None,
+        };
+        let value: u64 = number.parse().ok()?;
+        total = total.checked_add(value.checked_mul(unit)?)?;
+        number.clear();
+    }
+    (number.is_empty() && !s.is_empty()).then_some(total)
+}
+
+/// Unix timestamp of an RFC 3339 date-time such as `2026-10-16T17:00:00Z`
+/// or `2026-10-16T19:00:00.5+02:00`. Fractional seconds are dropped.
+fn parse_rfc3339(s: &str) -> Option<i64> {
+    fn field(s: &str, range: std::ops::Range<usize>) -> Option<u32> {
+        let part = s.get(range)?;
+        part.chars()
+            .all(|c| c.is_ascii_digit())
+            .then(|| part.parse().ok())?
+    }
+
+    let bytes = s.as_bytes();
+    if s.len() < 20
+        || bytes[4] != b'-'
+        || bytes[7] != b'-'
+        || !matches!(bytes[10], b'T' | b't' | b' ')
+        || bytes[13] != b':'
+        || bytes[16] != b':'
+    {
+        return None;
+    }
+    let (year, month, day) = (field(s, 0..4)?, field(s, 5..7)?, field(s, 8..10)?);
+    let (hour, minute, second) = (field(s, 11..13)?, field(s, 14..16)?, field(s, 17..19)?);
+
+    let mut rest = &s[19..];
+    if let Some(frac) = rest.strip_prefix('.') {
+        let digits = frac.chars().take_while(char::is_ascii_digit).count();
+        if digits == 0 {
+            return None;
+        }
+        rest = &frac[digits..];
+    }
+    let offset_secs: i64 = match rest {
+        "Z" | "z" => 0,
+        _ if rest.len() == 6 && rest.as_bytes()[3] == b':' => {
+            let sign = match rest.as_bytes()[0] {
+                b'+' => 1,
+                b'-' => -1,
+                _ => return None,
+            };
+            let (hours, minutes) = (field(rest, 1..3)?, field(rest, 4..6)?);
+            if hours > 23 || minutes > 59 {
+                return None;
+            }
+            sign * i64::from(hours * 3600 + minutes * 60)
+        }
+        _ => return None,
+    };
+
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days_in_month = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return None,
+    };
+    if day == 0 || day > days_in_month || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    let days = crate::engine::template::days_from_civil(i64::from(year), month, day);
+    let secs_of_day = i64::from(hour * 3600 + minute * 60 + second.min(59));
+    Some(days * 86_400 + secs_of_day - offset_secs)
 }
 
 // ---------------------------------------------------------------------------
@@ -335,6 +1495,7 @@ mod tests {
     /// Build a sample `LocalRequest` for testing.
     fn sample_request(id: &str, status: LocalRequestStatus, role: RequestRole) -> LocalRequest {
         LocalRequest {
+            schema_version: CURRENT_SCHEMA_VERSION,
             request_id: id.to_string(),
             role,
             status,
@@ -347,6 +1508,14 @@ mod tests {
             counterparty: None,
             created_at: 1_699_000_000,
             updated_at: 1_699_000_000,
+            previous_status: None,
+            tags: Vec::new(),
+            task_summary: None,
+            capability: None,
+            trust_policy: None,
+            history: Vec::new(),
+            payment_check: None,
+            response_attempt: None,
         }
     }
 
@@ -442,6 +1611,87 @@ mod tests {
         assert_ne!(secret_a, secret_b, "two random secrets should differ");
     }
 
+    // -- derive_secret --------------------------------------------------------
+
+    #[test]
+    fn test_derive_secret_is_deterministic_and_hash_matches() {
+        let key = [7u8; 32];
+        let (secret_a, hash_a) = derive_secret(&key, "42");
+        let (secret_b, hash_b) = derive_secret(&key, "42");
+        assert_eq!(secret_a, secret_b);
+        assert_eq!(hash_a, hash_b);
+
+        assert_eq!(secret_a.len(), 64);
+        assert_eq!(hash_a.len(), 66);
+        let secret_bytes = hex::decode(&secret_a).unwrap();
+        assert_eq!(
+            hash_a,
+            format!("0x{}", hex::encode(keccak256(&secret_bytes)))
+        );
+    }
+
+    #[test]
+    fn test_verify_secret_matching() {
+        let (secret, hash) = generate_secret();
+        verify_secret(&secret, &hash).unwrap();
+        verify_secret(
+            &format!("0x{secret}"),
+            &hash.to_uppercase().replacen("0X", "0x", 1),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_verify_secret_mismatch() {
+        let (secret, _) = generate_secret();
+        let (_, other_hash) = generate_secret();
+        let err = verify_secret(&secret, &other_hash).unwrap_err().to_string();
+        assert_eq!(
+            err,
+            format!("The claim secret does not hash to {other_hash}.")
+        );
+    }
+
+    #[test]
+    fn test_verify_secret_malformed_hex() {
+        let (secret, hash) = generate_secret();
+        let err = verify_secret("zz", &hash).unwrap_err().to_string();
+        assert_eq!(err, "The claim secret is not valid hex.");
+        let err = verify_secret("abcd", &hash).unwrap_err().to_string();
+        assert_eq!(err, "The claim secret is 2 bytes long instead of 32.");
+        let err = verify_secret(&secret, "0xnothex").unwrap_err().to_string();
+        assert_eq!(err, "The expected secret hash is not valid hex.");
+    }
+
+    #[test]
+    fn test_verify_secret_missing() {
+        let (_, hash) = generate_secret();
+        for missing in ["", "  ", "0x"] {
+            let err = verify_secret(missing, &hash).unwrap_err().to_string();
+            assert_eq!(err, "The claim secret is missing.", "{missing:?}");
+        }
+    }
+
+    #[test]
+    fn test_derive_secret_differs_per_request_and_key() {
+        let key = [7u8; 32];
+        let secrets: std::collections::HashSet<String> = ["1", "2", "12", "42", "420"]
+            .iter()
+            .map(|id| derive_secret(&key, id).0)
+            .collect();
+        assert_eq!(secrets.len(), 5);
+
+        assert_ne!(
+            derive_secret(&key, "42").0,
+            derive_secret(&[8u8; 32], "42").0
+        );
+        // The length prefix keeps id and key from running together.
+        assert_ne!(
+            derive_secret(&[1, 2], "x").0,
+            derive_secret(&[2], "x\u{1}").0
+        );
+    }
+
     // -- format_price_usd -----------------------------------------------------
 
     #[test]
@@ -461,6 +1711,27 @@ mod tests {
         assert_eq!(format_price_usd(100_000), "$0.10");
         assert_eq!(format_price_usd(500_000), "$0.50");
         assert_eq!(format_price_usd(999_999), "$0.999999");
+        assert_eq!(format_price_usd(12_500_000_000), "$12500.00");
+    }
+
+    #[test]
+    fn test_format_price_usd_pretty() {
+        let cases = [
+            (0, "$0.00"),
+            (1, "$0.000001"),
+            (500_000, "$0.50"),
+            (999_999, "$0.999999"),
+            (1_000_000, "$1.00"),
+            (999_000_000, "$999.00"),
+            (1_000_000_000, "$1,000.00"),
+            (12_500_000_000, "$12,500.00"),
+            (1_234_567_890_001, "$1,234,567.890001"),
+            (1_000_000_000_000, "$1,000,000.00"),
+            (u64::MAX, "$18,446,744,073,709.551615"),
+        ];
+        for (amount, expected) in cases {
+            assert_eq!(format_price_usd_pretty(amount), expected, "{amount}");
+        }
     }
 
     // -- dollars_to_usdc ------------------------------------------------------
@@ -489,6 +1760,146 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_usd_amount_accepts_exact_decimals() {
+        assert_eq!(parse_usd_amount("5").unwrap(), 5_000_000);
+        assert_eq!(parse_usd_amount("5.5").unwrap(), 5_500_000);
+        assert_eq!(parse_usd_amount("0.000001").unwrap(), 1);
+        assert_eq!(parse_usd_amount("0.07").unwrap(), 70_000);
+        assert_eq!(parse_usd_amount("19.999999").unwrap(), 19_999_999);
+        assert_eq!(parse_usd_amount(" $12.50 ").unwrap(), 12_500_000);
+        assert_eq!(parse_usd_amount(".25").unwrap(), 250_000);
+        assert_eq!(parse_usd_amount("3.").unwrap(), 3_000_000);
+    }
+
+    #[test]
+    fn test_parse_usd_amount_rejects_non_plain_input() {
+        for input in [
+            "1e3", "-5", "-0.01", "", ".", "abc", "5.5.5", "+5", "1,000", "NaN",
+        ] {
+            assert!(
+                parse_usd_amount(input).is_err(),
+                "{input:?} should be rejected"
+            );
+        }
+
+        let err = parse_usd_amount("-5").unwrap_err().to_string();
+        assert!(err.contains("negative"), "{err}");
+    }
+
+    #[test]
+    fn test_parse_usd_amount_rejects_more_than_six_decimals() {
+        let err = parse_usd_amount("0.0000001").unwrap_err().to_string();
+        assert!(err.contains("at most 6 decimal places"), "{err}");
+    }
+
+    #[test]
+    fn test_parse_usd_amount_rejects_overflow() {
+        assert!(parse_usd_amount("18446744073709551615").is_err());
+        assert!(parse_usd_amount("99999999999999999999999").is_err());
+    }
+
+    #[test]
+    fn test_parse_eth_amount_accepts_exact_decimals() {
+        const ETH: u128 = 1_000_000_000_000_000_000;
+        assert_eq!(parse_eth_amount("1").unwrap(), ETH);
+        assert_eq!(parse_eth_amount("0.0001").unwrap(), ETH / 10_000);
+        assert_eq!(parse_eth_amount("0.000000000000000001").unwrap(), 1);
+        assert_eq!(parse_eth_amount(" 2.5 ").unwrap(), 5 * ETH / 2);
+
+        let err = parse_eth_amount("0.0000000000000000001")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("at most 18 decimal places"), "{err}");
+        for input in ["$1", "-1", "1e18", "", "abc"] {
+            assert!(
+                parse_eth_amount(input).is_err(),
+                "{input:?} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_deadline_accepts_each_syntax() {
+        let now = 1_760_000_000; // 2025-10-09T08:53:20Z
+
+        // Whole hours, the original syntax.
+        assert_eq!(parse_deadline("24", now).unwrap(), now + 86_400);
+        assert_eq!(parse_deadline(" 1 ", now).unwrap(), now + 3600);
+
+        // Durations.
+        assert_eq!(parse_deadline("36h", now).unwrap(), now + 36 * 3600);
+        assert_eq!(parse_deadline("2d12h", now).unwrap(), now + 60 * 3600);
+        assert_eq!(parse_deadline("90m", now).unwrap(), now + 5400);
+        assert_eq!(parse_deadline("1D30s", now).unwrap(), now + 86_430);
+
+        // RFC 3339 timestamps, with and without an offset.
+        assert_eq!(
+            parse_deadline("2025-10-10T17:00:00Z", now).unwrap(),
+            1_760_115_600
+        );
+        assert_eq!(
+            parse_deadline("2025-10-10T19:00:00.250+02:00", now).unwrap(),
+            1_760_115_600
+        );
+        assert_eq!(
+            parse_deadline("2025-10-10 12:00:00-05:00", now).unwrap(),
+            1_760_115_600
+        );
+        assert_eq!(
+            parse_deadline("2026-02-28T00:00:00z", now).unwrap(),
+            1_772_236_800
+        );
+    }
+
+    #[test]
+    fn test_parse_deadline_rejects_past_and_distant_deadlines() {
+        let now = 1_760_000_000;
+
+        for input in ["0", "0h", "2025-10-09T08:53:20Z", "2020-01-01T00:00:00Z"] {
+            let err = parse_deadline(input, now).unwrap_err().to_string();
+            assert!(err.contains("is in the past"), "{input}: {err}");
+        }
+
+        for input in [
+            "8761",
+            "366d",
+            "2027-01-01T00:00:00Z",
+            "18446744073709551615",
+        ] {
+            let err = parse_deadline(input, now).unwrap_err().to_string();
+            assert!(err.contains("more than 365 days away"), "{input}: {err}");
+        }
+        assert_eq!(
+            parse_deadline("365d", now).unwrap(),
+            now + MAX_DEADLINE_SECS
+        );
+    }
+
+    #[test]
+    fn test_parse_deadline_rejects_malformed_input() {
+        for input in [
+            "",
+            "h",
+            "-5",
+            "1.5",
+            "2d12",
+            "3w",
+            "tomorrow",
+            "2025-10-10",
+            "2025-10-10T17:00:00",
+            "2025-02-30T00:00:00Z",
+            "2025-10-10T24:00:00Z",
+            "2025-10-10T17:00:00+2:00",
+            "2025-10-10T17:00:00.Z",
+        ] {
+            let err = parse_deadline(input, 1_760_000_000)
+                .unwrap_err()
+                .to_string();
+            assert!(err.starts_with("Invalid deadline"), "{input:?}: {err}");
+        }
+    }
+
     // -- RequestCache save/load round-trip ------------------------------------
 
     #[test]
@@ -511,9 +1922,24 @@ mod tests {
             assert_eq!(loaded.counterparty, None);
             assert_eq!(loaded.created_at, 1_699_000_000);
             assert_eq!(loaded.updated_at, 1_699_000_000);
+
+            assert!(RequestCache::contains("42").unwrap());
+            assert!(!RequestCache::contains("43").unwrap());
         });
     }
 
+    #[test]
+    fn test_without_secret_keeps_public_fields() {
+        let mut request = sample_request("7", LocalRequestStatus::Responded, RequestRole::Seller);
+        request.secret = Some("ab".repeat(32));
+        request.secret_hash = Some("cd".repeat(32));
+
+        let public = request.without_secret();
+        assert_eq!(public.secret, None);
+        assert_eq!(public.secret_hash, request.secret_hash);
+        assert_eq!(public.request_id, "7");
+    }
+
     #[test]
     fn test_cache_save_load_with_optional_fields() {
         with_temp_home(|| {
@@ -601,6 +2027,118 @@ mod tests {
         });
     }
 
+    // -- RequestCache subscribe -----------------------------------------------
+
+    #[test]
+    fn test_subscribe_receives_status_changes_and_deletion() {
+        with_temp_home(|| {
+            let rx = RequestCache::subscribe();
+            // Other tests may save concurrently; only look at our request.
+            let next = || loop {
+                let event = rx
+                    .recv_timeout(Duration::from_secs(5))
+                    .expect("event should arrive");
+                if event.request_id == "sub-1" {
+                    return event;
+                }
+            };
+
+            let request = sample_request("sub-1", LocalRequestStatus::Open, RequestRole::Seller);
+            RequestCache::save(&request).unwrap();
+            assert_eq!(next().old_status, None);
+
+            // A save that does not change status emits nothing.
+            RequestCache::update("sub-1", |r| {
+                r.tags.push("quiet".to_string());
+                Ok(())
+            })
+            .unwrap();
+            RequestCache::update("sub-1", |r| r.transition(LocalRequestStatus::Responded)).unwrap();
+            assert_eq!(
+                next(),
+                RequestEvent {
+                    request_id: "sub-1".to_string(),
+                    old_status: Some(LocalRequestStatus::Open),
+                    new_status: Some(LocalRequestStatus::Responded),
+                }
+            );
+
+            RequestCache::delete("sub-1").unwrap();
+            let removed = next();
+            assert_eq!(removed.old_status, Some(LocalRequestStatus::Responded));
+            assert_eq!(removed.new_status, None);
+
+            drop(rx);
+            RequestCache::save(&request).unwrap();
+            // Dropping the receiver unsubscribes on the next notification.
+            assert!(!has_subscribers());
+        });
+    }
+
+    // -- RequestCache query ---------------------------------------------------
+
+    #[test]
+    fn test_cache_query_every_filter_combination() {
+        with_temp_home(|| {
+            let fixtures = [
+                (
+                    "q-1",
+                    LocalRequestStatus::Responded,
+                    RequestRole::Validator,
+                    Some("0xAbC"),
+                ),
+                (
+                    "q-2",
+                    LocalRequestStatus::Responded,
+                    RequestRole::Validator,
+                    Some("0xdef"),
+                ),
+                (
+                    "q-3",
+                    LocalRequestStatus::Responded,
+                    RequestRole::Seller,
+                    Some("0xabc"),
+                ),
+                (
+                    "q-4",
+                    LocalRequestStatus::Open,
+                    RequestRole::Validator,
+                    Some("0xabc"),
+                ),
+                ("q-5", LocalRequestStatus::Open, RequestRole::Buyer, None),
+            ];
+            for (id, status, role, counterparty) in fixtures {
+                let mut request = sample_request(id, status, role);
+                request.counterparty = counterparty.map(str::to_string);
+                RequestCache::save(&request).unwrap();
+            }
+
+            let ids = |status: Option<LocalRequestStatus>,
+                       role: Option<RequestRole>,
+                       counterparty: Option<&str>| {
+                let mut ids: Vec<String> = RequestCache::query(status, role, counterparty)
+                    .unwrap()
+                    .into_iter()
+                    .map(|r| r.request_id)
+                    .collect();
+                ids.sort();
+                ids
+            };
+            let responded = || Some(LocalRequestStatus::Responded);
+            let validator = || Some(RequestRole::Validator);
+
+            assert_eq!(ids(None, None, None), ["q-1", "q-2", "q-3", "q-4", "q-5"]);
+            assert_eq!(ids(responded(), None, None), ["q-1", "q-2", "q-3"]);
+            assert_eq!(ids(None, validator(), None), ["q-1", "q-2", "q-4"]);
+            assert_eq!(ids(None, None, Some("0xABC")), ["q-1", "q-3", "q-4"]);
+            assert_eq!(ids(responded(), validator(), None), ["q-1", "q-2"]);
+            assert_eq!(ids(responded(), None, Some("0xabc")), ["q-1", "q-3"]);
+            assert_eq!(ids(None, validator(), Some("0xabc")), ["q-1", "q-4"]);
+            assert_eq!(ids(responded(), validator(), Some("0xabc")), ["q-1"]);
+            assert!(ids(Some(LocalRequestStatus::Claimed), None, None).is_empty());
+        });
+    }
+
     // -- RequestCache load_by_role --------------------------------------------
 
     #[test]
@@ -633,6 +2171,312 @@ mod tests {
         });
     }
 
+    // -- Task summary ----------------------------------------------------------
+
+    #[test]
+    fn test_summarize_task_short_text_unchanged() {
+        assert_eq!(summarize_task("Review  my\nPR"), "Review my PR");
+    }
+
+    #[test]
+    fn test_summarize_task_truncates_on_char_boundary() {
+        let long = "é".repeat(500);
+        let summary = summarize_task(&long);
+        assert_eq!(summary.chars().count(), TASK_SUMMARY_MAX_CHARS);
+        assert!(summary.ends_with('…'));
+    }
+
+    #[test]
+    fn test_task_summary_roundtrip_and_v0_default() {
+        with_temp_home(|| {
+            let mut request = sample_request("ts-1", LocalRequestStatus::Open, RequestRole::Buyer);
+            request.task_summary = Some("Label 100 images".to_string());
+            RequestCache::save(&request).expect("save");
+            let loaded = RequestCache::load("ts-1").expect("load");
+            assert_eq!(loaded.task_summary.as_deref(), Some("Label 100 images"));
+
+            write_raw("legacy-3", V0_FIXTURE);
+            let legacy = RequestCache::load("legacy-3").expect("load v0 fixture");
+            assert_eq!(legacy.task_summary, None);
+        });
+    }
+
+    // -- Summary ---------------------------------------------------------------
+
+    #[test]
+    fn test_summary_over_mixed_cache() {
+        with_temp_home(|| {
+            let empty = RequestCache::summary().unwrap();
+            assert_eq!(empty.total, 0);
+            assert_eq!(empty.by_status.len(), LocalRequestStatus::ALL.len());
+            assert_eq!(empty.by_role.len(), RequestRole::ALL.len());
+
+            let fixtures = [
+                (
+                    "s-1",
+                    LocalRequestStatus::Open,
+                    RequestRole::Buyer,
+                    1_000_000,
+                ),
+                (
+                    "s-2",
+                    LocalRequestStatus::Open,
+                    RequestRole::Buyer,
+                    2_500_000,
+                ),
+                (
+                    "s-3",
+                    LocalRequestStatus::Open,
+                    RequestRole::Seller,
+                    9_000_000,
+                ),
+                (
+                    "s-4",
+                    LocalRequestStatus::Validated,
+                    RequestRole::Seller,
+                    4_000_000,
+                ),
+                (
+                    "s-5",
+                    LocalRequestStatus::Validated,
+                    RequestRole::Seller,
+                    6_000_000,
+                ),
+                (
+                    "s-6",
+                    LocalRequestStatus::Validated,
+                    RequestRole::Buyer,
+                    7_000_000,
+                ),
+                (
+                    "s-7",
+                    LocalRequestStatus::Responded,
+                    RequestRole::Validator,
+                    3_000_000,
+                ),
+                (
+                    "s-8",
+                    LocalRequestStatus::Claimed,
+                    RequestRole::Seller,
+                    5_000_000,
+                ),
+                (
+                    "s-9",
+                    LocalRequestStatus::Expired,
+                    RequestRole::Buyer,
+                    8_000_000,
+                ),
+            ];
+            for (id, status, role, price) in fixtures {
+                let mut request = sample_request(id, status, role);
+                request.price_usdc = price;
+                RequestCache::save(&request).unwrap();
+            }
+
+            let summary = RequestCache::summary().unwrap();
+            assert_eq!(summary.total, 9);
+            assert_eq!(summary.count(&LocalRequestStatus::Open), 3);
+            assert_eq!(summary.count(&LocalRequestStatus::Responded), 1);
+            assert_eq!(summary.count(&LocalRequestStatus::Validated), 3);
+            assert_eq!(summary.count(&LocalRequestStatus::Claimed), 1);
+            assert_eq!(summary.count(&LocalRequestStatus::Cancelled), 0);
+            assert_eq!(summary.count(&LocalRequestStatus::Expired), 1);
+            assert_eq!(summary.active(), 7);
+            assert_eq!(summary.by_role[&RequestRole::Buyer], 4);
+            assert_eq!(summary.by_role[&RequestRole::Seller], 4);
+            assert_eq!(summary.by_role[&RequestRole::Validator], 1);
+            assert_eq!(summary.claimable_usdc, 10_000_000);
+            assert_eq!(summary.committed_usdc, 3_500_000);
+            assert_eq!(summary.earned_usdc, 5_000_000);
+
+            let json = serde_json::to_value(&summary).unwrap();
+            assert_eq!(json["by_status"]["Open"], 3);
+            assert_eq!(json["by_status"]["Cancelled"], 0);
+            assert_eq!(json["by_role"]["Validator"], 1);
+            assert_eq!(json["claimable_usdc"], 10_000_000);
+        });
+    }
+
+    // -- Transition history ----------------------------------------------------
+
+    #[test]
+    fn test_transitions_append_history() {
+        with_temp_home(|| {
+            let request = sample_request("hist-1", LocalRequestStatus::Open, RequestRole::Seller);
+            RequestCache::save(&request).expect("save");
+
+            RequestCache::update("hist-1", |r| r.transition(LocalRequestStatus::Responded))
+                .unwrap();
+            RequestCache::update("hist-1", |r| {
+                r.transition_with_note(
+                    LocalRequestStatus::Validated,
+                    Some("validation score 85".to_string()),
+                )
+            })
+            .unwrap();
+            // A rejected transition leaves no trace.
+            assert!(
+                RequestCache::update("hist-1", |r| r.transition(LocalRequestStatus::Open)).is_err()
+            );
+
+            let loaded = RequestCache::load("hist-1").unwrap();
+            let steps: Vec<_> = loaded
+                .history
+                .iter()
+                .map(|e| (e.from.clone(), e.to.clone(), e.note.as_deref()))
+                .collect();
+            assert_eq!(
+                steps,
+                vec![
+                    (
+                        LocalRequestStatus::Open,
+                        LocalRequestStatus::Responded,
+                        None
+                    ),
+                    (
+                        LocalRequestStatus::Responded,
+                        LocalRequestStatus::Validated,
+                        Some("validation score 85")
+                    ),
+                ]
+            );
+            assert_eq!(loaded.history[1].timestamp, loaded.updated_at);
+        });
+    }
+
+    #[test]
+    fn test_history_is_capped() {
+        let mut request = sample_request("hist-2", LocalRequestStatus::Open, RequestRole::Buyer);
+        request.history = (0..MAX_HISTORY_ENTRIES as u64)
+            .map(|i| TransitionEntry {
+                from: LocalRequestStatus::Open,
+                to: LocalRequestStatus::Open,
+                timestamp: i,
+                note: None,
+            })
+            .collect();
+
+        request.transition(LocalRequestStatus::Cancelled).unwrap();
+
+        assert_eq!(request.history.len(), MAX_HISTORY_ENTRIES);
+        assert_eq!(request.history[0].timestamp, 1, "oldest entry dropped");
+        assert_eq!(
+            request.history.last().unwrap().to,
+            LocalRequestStatus::Cancelled
+        );
+    }
+
+    #[test]
+    fn test_history_defaults_for_legacy_files() {
+        with_temp_home(|| {
+            write_raw("legacy-4", V0_FIXTURE);
+            let legacy = RequestCache::load("legacy-4").expect("load v0 fixture");
+            assert!(legacy.history.is_empty());
+
+            // Entries without a note omit the field on disk.
+            let mut request =
+                sample_request("hist-3", LocalRequestStatus::Open, RequestRole::Buyer);
+            request.transition(LocalRequestStatus::Cancelled).unwrap();
+            let json = serde_json::to_string(&request.history[0]).unwrap();
+            assert!(!json.contains("note"), "{json}");
+        });
+    }
+
+    // -- Tags ------------------------------------------------------------------
+
+    #[test]
+    fn test_normalize_tags() {
+        let tags = vec![
+            " Code-Review ".to_string(),
+            "code-review".to_string(),
+            "".to_string(),
+            "DATA".to_string(),
+        ];
+        assert_eq!(normalize_tags(&tags), vec!["code-review", "data"]);
+    }
+
+    #[test]
+    fn test_save_normalizes_tags_and_load_by_tag_filters() {
+        with_temp_home(|| {
+            let mut r1 = sample_request("1", LocalRequestStatus::Open, RequestRole::Buyer);
+            r1.tags = vec!["Code-Review".to_string(), "code-review".to_string()];
+            let mut r2 = sample_request("2", LocalRequestStatus::Open, RequestRole::Seller);
+            r2.tags = vec!["labeling".to_string()];
+            let r3 = sample_request("3", LocalRequestStatus::Open, RequestRole::Buyer);
+
+            RequestCache::save(&r1).expect("save r1");
+            RequestCache::save(&r2).expect("save r2");
+            RequestCache::save(&r3).expect("save r3");
+
+            let loaded = RequestCache::load("1").expect("load r1");
+            assert_eq!(loaded.tags, vec!["code-review"]);
+
+            let tagged = RequestCache::load_by_tag("CODE-REVIEW").expect("load_by_tag");
+            assert_eq!(tagged.len(), 1);
+            assert_eq!(tagged[0].request_id, "1");
+
+            assert!(RequestCache::load_by_tag("missing").unwrap().is_empty());
+        });
+    }
+
+    #[test]
+    fn test_v0_fixture_has_no_tags() {
+        with_temp_home(|| {
+            write_raw("legacy-2", V0_FIXTURE);
+            let loaded = RequestCache::load("legacy-2").expect("load v0 fixture");
+            assert!(loaded.tags.is_empty());
+        });
+    }
+
+    // -- Capability filter -----------------------------------------------------
+
+    #[test]
+    fn test_matches_capability_exact() {
+        assert!(matches_capability(
+            "data-labeling",
+            Some("data-labeling"),
+            false
+        ));
+        assert!(matches_capability(
+            "Data-Labeling",
+            Some("data-labeling "),
+            false
+        ));
+        assert!(!matches_capability("data-labeling", Some("data"), false));
+        assert!(!matches_capability("data", Some("data-labeling"), false));
+    }
+
+    #[test]
+    fn test_matches_capability_glob() {
+        assert!(matches_capability("code-*", Some("code-review"), false));
+        assert!(matches_capability("code-*", Some("code-"), false));
+        assert!(matches_capability("*-review", Some("code-review"), false));
+        assert!(matches_capability("c*-r*w", Some("code-review"), false));
+        assert!(matches_capability("*", Some("anything"), true));
+        assert!(!matches_capability("code-*", Some("data-labeling"), false));
+        assert!(!matches_capability("*-review", Some("code-reviews"), false));
+    }
+
+    #[test]
+    fn test_matches_capability_missing_metadata() {
+        assert!(matches_capability("code-*", None, false));
+    }
+
+    #[test]
+    fn test_matches_capability_strict_excludes_missing_metadata() {
+        assert!(!matches_capability("code-*", None, true));
+        assert!(matches_capability("code-*", Some("code-review"), true));
+    }
+
+    #[test]
+    fn test_v0_fixture_has_no_capability() {
+        with_temp_home(|| {
+            write_raw("legacy-3", V0_FIXTURE);
+            let loaded = RequestCache::load("legacy-3").expect("load v0 fixture");
+            assert_eq!(loaded.capability, None);
+        });
+    }
+
     // -- RequestCache delete --------------------------------------------------
 
     #[test]
@@ -654,6 +2498,373 @@ mod tests {
         });
     }
 
+    // -- LocalRequest::transition / RequestCache::update ----------------------
+
+    #[test]
+    fn test_transition_records_previous_status() {
+        let mut request = sample_request("1", LocalRequestStatus::Open, RequestRole::Seller);
+        request
+            .transition(LocalRequestStatus::Responded)
+            .expect("valid transition");
+
+        assert_eq!(request.status, LocalRequestStatus::Responded);
+        assert_eq!(request.previous_status, Some(LocalRequestStatus::Open));
+        assert!(request.updated_at > 1_699_000_000);
+    }
+
+    #[test]
+    fn test_transition_rejects_invalid_and_leaves_request_untouched() {
+        let mut request = sample_request("1", LocalRequestStatus::Claimed, RequestRole::Seller);
+        let err = request
+            .transition(LocalRequestStatus::Open)
+            .expect_err("Claimed -> Open must fail");
+
+        assert!(err.to_string().contains("invalid status transition"));
+        assert_eq!(request.status, LocalRequestStatus::Claimed);
+        assert_eq!(request.previous_status, None);
+        assert_eq!(request.updated_at, 1_699_000_000);
+    }
+
+    #[test]
+    fn test_update_applies_and_persists() {
+        with_temp_home(|| {
+            let request = sample_request("7", LocalRequestStatus::Responded, RequestRole::Seller);
+            RequestCache::save(&request).expect("save");
+
+            let updated = RequestCache::update("7", |r| {
+                r.transition(LocalRequestStatus::Validated)?;
+                r.counterparty = Some("0xabc".to_string());
+                Ok(())
+            })
+            .expect("update");
+            assert_eq!(updated.status, LocalRequestStatus::Validated);
+
+            let loaded = RequestCache::load("7").expect("load");
+            assert_eq!(loaded.status, LocalRequestStatus::Validated);
+            assert_eq!(loaded.previous_status, Some(LocalRequestStatus::Responded));
+            assert_eq!(loaded.counterparty, Some("0xabc".to_string()));
+        });
+    }
+
+    #[test]
+    fn test_update_claimed_to_open_fails_and_file_unchanged() {
+        with_temp_home(|| {
+            let request = sample_request("9", LocalRequestStatus::Claimed, RequestRole::Seller);
+            RequestCache::save(&request).expect("save");
+
+            let path = RequestCache::requests_dir().expect("dir").join("9.json");
+            let before = fs::read(&path).expect("read before");
+
+            let result = RequestCache::update("9", |r| r.transition(LocalRequestStatus::Open));
+            assert!(result.is_err(), "Claimed -> Open via transition must fail");
+
+            // Bypassing transition() by assigning the status directly is
+            // caught as well.
+            let result = RequestCache::update("9", |r| {
+                r.status = LocalRequestStatus::Open;
+                Ok(())
+            });
+            assert!(result.is_err(), "Claimed -> Open via assignment must fail");
+
+            let after = fs::read(&path).expect("read after");
+            assert_eq!(
+                before, after,
+                "file must be unchanged after a rejected update"
+            );
+        });
+    }
+
+    #[test]
+    fn test_update_missing_request_fails() {
+        with_temp_home(|| {
+            assert!(RequestCache::update("missing", |_| Ok(())).is_err());
+        });
+    }
+
+    // -- Deadline helpers -----------------------------------------------------
+
+    #[test]
+    fn test_deadline_equal_to_now() {
+        let request = sample_request("dl-now", LocalRequestStatus::Open, RequestRole::Buyer);
+        let now = request.deadline;
+        assert!(!request.is_past_deadline(now));
+        assert_eq!(request.time_remaining(now), Some(Duration::ZERO));
+        assert_eq!(request.format_deadline(now), "due now");
+        assert!(request.is_past_deadline(now + 1));
+        assert_eq!(request.format_deadline(now + 1), "expired 1s ago");
+    }
+
+    #[test]
+    fn test_deadline_far_future() {
+        let request = sample_request("dl-future", LocalRequestStatus::Open, RequestRole::Buyer);
+        let now = request.deadline - (3 * 3600 + 20 * 60 + 15);
+        assert!(!request.is_past_deadline(now));
+        assert_eq!(
+            request.time_remaining(now),
+            Some(Duration::from_secs(3 * 3600 + 20 * 60 + 15))
+        );
+        assert_eq!(request.format_deadline(now), "in 3h 20m");
+
+        let long_before = request.deadline - 400 * 86_400;
+        assert_eq!(request.format_deadline(long_before), "in 400d");
+        assert_eq!(request.format_deadline(0), "in 19675d 22h");
+    }
+
+    #[test]
+    fn test_deadline_far_past() {
+        let request = sample_request("dl-past", LocalRequestStatus::Open, RequestRole::Buyer);
+        let now = request.deadline + 2 * 86_400 + 3600;
+        assert!(request.is_past_deadline(now));
+        assert_eq!(request.time_remaining(now), None);
+        assert_eq!(request.format_deadline(now), "expired 2d 1h ago");
+        assert_eq!(
+            request.format_deadline(u64::MAX),
+            "expired 213503982314925d 8h ago"
+        );
+        assert_eq!(
+            request.format_deadline(request.deadline + 59 * 60),
+            "expired 59m ago"
+        );
+    }
+
+    // -- RequestCache::sweep_expired -------------------------------------------
+
+    #[test]
+    fn test_sweep_expired_expires_past_deadline_only() {
+        with_temp_home(|| {
+            // sample_request deadline is 1_700_000_000.
+            let open = sample_request("open", LocalRequestStatus::Open, RequestRole::Buyer);
+            let responded = sample_request(
+                "responded",
+                LocalRequestStatus::Responded,
+                RequestRole::Seller,
+            );
+            let mut future = sample_request("future", LocalRequestStatus::Open, RequestRole::Buyer);
+            future.deadline = 1_800_000_000;
+            let claimed =
+                sample_request("claimed", LocalRequestStatus::Claimed, RequestRole::Seller);
+
+            for r in [&open, &responded, &future, &claimed] {
+                RequestCache::save(r).expect("save");
+            }
+
+            let mut expired = RequestCache::sweep_expired(1_700_000_001).expect("sweep");
+            expired.sort();
+            assert_eq!(expired, vec!["open".to_string(), "responded".to_string()]);
+
+            assert_eq!(
+                RequestCache::load("open").unwrap().status,
+                LocalRequestStatus::Expired
+            );
+            assert_eq!(
+                RequestCache::load("responded").unwrap().previous_status,
+                Some(LocalRequestStatus::Responded)
+            );
+            assert_eq!(
+                RequestCache::load("future").unwrap().status,
+                LocalRequestStatus::Open
+            );
+            assert_eq!(
+                RequestCache::load("claimed").unwrap().status,
+                LocalRequestStatus::Claimed
+            );
+
+            // Nothing left to sweep on a second pass.
+            assert!(RequestCache::sweep_expired(1_700_000_001)
+                .unwrap()
+                .is_empty());
+        });
+    }
+
+    #[test]
+    fn test_sweep_expired_honours_claim_grace_for_validated() {
+        with_temp_home(|| {
+            let validated = sample_request(
+                "validated",
+                LocalRequestStatus::Validated,
+                RequestRole::Seller,
+            );
+            RequestCache::save(&validated).expect("save");
+
+            // Inside the grace window: still claimable.
+            let expired = RequestCache::sweep_expired_with_grace(1_700_000_500, 1_000).unwrap();
+            assert!(expired.is_empty());
+            assert_eq!(
+                RequestCache::load("validated").unwrap().status,
+                LocalRequestStatus::Validated
+            );
+
+            // Past the grace window: expired.
+            let expired = RequestCache::sweep_expired_with_grace(1_700_001_001, 1_000).unwrap();
+            assert_eq!(expired, vec!["validated".to_string()]);
+        });
+    }
+
+    #[test]
+    fn test_is_expirable_by_status() {
+        let deadline = 1_700_000_000;
+        let at = |status| sample_request("1", status, RequestRole::Buyer);
+
+        assert!(!at(LocalRequestStatus::Open).is_expirable(deadline, 1_000));
+        assert!(at(LocalRequestStatus::Open).is_expirable(deadline + 1, 1_000));
+        assert!(at(LocalRequestStatus::Responded).is_expirable(deadline + 1, 1_000));
+        assert!(!at(LocalRequestStatus::Validated).is_expirable(deadline + 1_000, 1_000));
+        assert!(at(LocalRequestStatus::Validated).is_expirable(deadline + 1_001, 1_000));
+        for terminal in [
+            LocalRequestStatus::Claimed,
+            LocalRequestStatus::Cancelled,
+            LocalRequestStatus::Expired,
+        ] {
+            assert!(!at(terminal).is_expirable(u64::MAX, 0));
+        }
+    }
+
+    // -- Schema migrations ----------------------------------------------------
+
+    /// A request file as written before `schema_version` existed.
+    const V0_FIXTURE: &str = r#"{
+  "request_id": "legacy-1",
+  "role": "Seller",
+  "status": "Responded",
+  "request_cid": "QmLegacyCid",
+  "price_usdc": 2500000,
+  "deadline": 1700000000,
+  "response_cid": "QmLegacyResponse",
+  "secret": null,
+  "secret_hash": null,
+  "counterparty": null,
+  "created_at": 1699000000,
+  "updated_at": 1699000500
+}"#;
+
+    fn write_raw(id: &str, contents: &str) -> PathBuf {
+        let path = RequestCache::requests_dir()
+            .expect("requests dir")
+            .join(format!("{id}.json"));
+        fs::write(&path, contents).expect("write fixture");
+        path
+    }
+
+    fn on_disk_version(path: &PathBuf) -> Option<u64> {
+        let doc: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(path).expect("read")).expect("parse");
+        doc.get("schema_version").and_then(|v| v.as_u64())
+    }
+
+    #[test]
+    fn test_migrations_cover_every_version_step() {
+        assert_eq!(MIGRATIONS.len(), CURRENT_SCHEMA_VERSION as usize);
+    }
+
+    #[test]
+    fn test_load_v0_fixture_rewrites_as_current() {
+        with_temp_home(|| {
+            let path = write_raw("legacy-1", V0_FIXTURE);
+            assert_eq!(on_disk_version(&path), None);
+
+            let loaded = RequestCache::load("legacy-1").expect("load v0 fixture");
+            assert_eq!(loaded.schema_version, CURRENT_SCHEMA_VERSION);
+            assert_eq!(loaded.status, LocalRequestStatus::Responded);
+            assert_eq!(loaded.role, RequestRole::Seller);
+            assert_eq!(loaded.price_usdc, 2_500_000);
+            assert_eq!(loaded.response_cid, Some("QmLegacyResponse".to_string()));
+
+            assert_eq!(on_disk_version(&path), Some(CURRENT_SCHEMA_VERSION as u64));
+        });
+    }
+
+    #[test]
+    fn test_migrate_all_upgrades_only_stale_files() {
+        with_temp_home(|| {
+            let legacy = write_raw("legacy-1", V0_FIXTURE);
+            let current = sample_request("42", LocalRequestStatus::Open, RequestRole::Buyer);
+            RequestCache::save(&current).expect("save current");
+
+            let migrated = RequestCache::migrate_all().expect("migrate_all");
+            assert_eq!(migrated, 1);
+            assert_eq!(
+                on_disk_version(&legacy),
+                Some(CURRENT_SCHEMA_VERSION as u64)
+            );
+
+            // A second pass has nothing left to do.
+            assert_eq!(RequestCache::migrate_all().expect("migrate_all again"), 0);
+        });
+    }
+
+    #[test]
+    fn test_load_rejects_newer_schema_version() {
+        with_temp_home(|| {
+            let future = V0_FIXTURE.replacen(
+                "{",
+                &format!("{{\n  \"schema_version\": {},", CURRENT_SCHEMA_VERSION + 1),
+                1,
+            );
+            write_raw("legacy-1", &future);
+
+            let err = RequestCache::load("legacy-1").expect_err("newer schema must fail");
+            assert!(format!("{err:#}").contains("schema version"));
+        });
+    }
+
+    #[test]
+    fn test_load_all_quarantines_corrupted_files() {
+        with_temp_home(|| {
+            for id in ["good-1", "good-2"] {
+                RequestCache::save(&sample_request(
+                    id,
+                    LocalRequestStatus::Open,
+                    RequestRole::Buyer,
+                ))
+                .unwrap();
+            }
+            let dir = RequestCache::requests_dir().unwrap();
+            fs::write(dir.join("bad.json"), b"{ this is not json").unwrap();
+
+            let loaded = RequestCache::load_all_checked().unwrap();
+            let mut ids: Vec<_> = loaded
+                .requests
+                .iter()
+                .map(|r| r.request_id.as_str())
+                .collect();
+            ids.sort();
+            assert_eq!(ids, ["good-1", "good-2"]);
+            assert_eq!(loaded.quarantined.len(), 1);
+
+            let moved = &loaded.quarantined[0];
+            assert!(!dir.join("bad.json").exists());
+            assert_eq!(moved.parent().unwrap(), dir.join(QUARANTINE_DIR));
+            let name = moved.file_name().unwrap().to_str().unwrap();
+            assert!(name.starts_with("bad.json."), "{name}");
+            assert!(name["bad.json.".len()..].parse::<u64>().is_ok(), "{name}");
+            assert_eq!(fs::read(moved).unwrap(), b"{ this is not json");
+
+            // The bad file is gone, so later loads are clean.
+            let again = RequestCache::load_all_checked().unwrap();
+            assert_eq!(again.requests.len(), 2);
+            assert!(again.quarantined.is_empty());
+        });
+    }
+
+    #[test]
+    fn test_load_all_does_not_quarantine_newer_schema() {
+        with_temp_home(|| {
+            let dir = RequestCache::requests_dir().unwrap();
+            let mut doc = serde_json::to_value(sample_request(
+                "future-1",
+                LocalRequestStatus::Open,
+                RequestRole::Buyer,
+            ))
+            .unwrap();
+            doc["schema_version"] = (CURRENT_SCHEMA_VERSION + 1).into();
+            fs::write(dir.join("future-1.json"), doc.to_string()).unwrap();
+
+            let err = RequestCache::load_all().expect_err("newer schema must fail");
+            assert!(format!("{err:#}").contains("schema version"));
+            assert!(dir.join("future-1.json").exists());
+        });
+    }
+
     #[test]
     fn test_cache_delete_nonexistent() {
         with_temp_home(|| {
@@ -0,0 +1,230 @@
+//! Earnings statistics over the local request cache.
+//!
+//! Pure functions over [`LocalRequest`]s, so they can be tested with
+//! synthetic timestamps. Periods are calendar days, weeks starting Monday,
+//! or calendar months, all in UTC.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use anyhow::{bail, Result};
+use serde::Serialize;
+
+use crate::engine::requests::{LocalRequest, LocalRequestStatus, RequestRole};
+use crate::engine::template::format_date;
+
+// ---------------------------------------------------------------------------
+// Period
+// ---------------------------------------------------------------------------
+
+/// Length of the buckets earnings are grouped into.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Period {
+    Day,
+    Week,
+    Month,
+}
+
+impl Period {
+    /// Parse `day`, `week`, or `month` (case-insensitive).
+    pub fn parse(input: &str) -> Result<Self> {
+        match input.trim().to_lowercase().as_str() {
+            "day" => Ok(Period::Day),
+            "week" => Ok(Period::Week),
+            "month" => Ok(Period::Month),
+            _ => bail!("Unknown period '{input}'. Use one of: day, week, month."),
+        }
+    }
+
+    /// Label of the period containing `timestamp`: `2024-03-15` for a day,
+    /// the Monday it starts on for a week, `2024-03` for a month. Labels
+    /// sort chronologically.
+    pub fn label(&self, timestamp: u64) -> String {
+        let format = match self {
+            Period::Day | Period::Week => "%Y-%m-%d",
+            Period::Month => "%Y-%m",
+        };
+        let start = match self {
+            // 1970-01-01 was a Thursday, three days after a Monday.
+            Period::Week => {
+                let days = timestamp / 86_400;
+                (days - (days + 3) % 7) * 86_400
+            }
+            Period::Day | Period::Month => timestamp,
+        };
+        format_date(start, format).unwrap_or_default()
+    }
+}
+
+impl fmt::Display for Period {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Period::Day => "day",
+            Period::Week => "week",
+            Period::Month => "month",
+        })
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Earnings
+// ---------------------------------------------------------------------------
+
+/// Completed work and earnings within one period. Amounts are in USDC
+/// atomic units.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct EarningsBucket {
+    /// Period label, see [`Period::label`].
+    pub period: String,
+    /// Requests claimed in the period.
+    pub completed: usize,
+    /// Total price of those requests.
+    pub earnings_usdc: u64,
+}
+
+/// When `request` was claimed: the timestamp of its transition to
+/// `Claimed`, or its last update for requests without history.
+pub fn claimed_at(request: &LocalRequest) -> u64 {
+    request
+        .history
+        .iter()
+        .rev()
+        .find(|entry| entry.to == LocalRequestStatus::Claimed)
+        .map_or(request.updated_at, |entry| entry.timestamp)
+}
+
+/// Group the seller-side claimed requests in `requests` by `period`,
+/// oldest period first. Periods with no claims are omitted.
+pub fn earnings_by_period(requests: &[LocalRequest], period: Period) -> Vec<EarningsBucket> {
+    let mut buckets: BTreeMap<String, EarningsBucket> = BTreeMap::new();
+
+    for request in requests
+        .iter()
+        .filter(|r| r.role == RequestRole::Seller && r.status == LocalRequestStatus::Claimed)
+    {
+        let label = period.label(claimed_at(request));
+        let bucket = buckets
+            .entry(label.clone())
+            .or_insert_with(|| EarningsBucket {
+                period: label,
+                completed: 0,
+                earnings_usdc: 0,
+            });
+        bucket.completed += 1;
+        bucket.earnings_usdc = bucket.earnings_usdc.saturating_add(request.price_usdc);
+    }
+
+    buckets.into_values().collect()
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::requests::TransitionEntry;
+
+    /// 2024-03-13 (a Wednesday) 12:00:00 UTC.
+    const WED: u64 = 1_710_331_200;
+    const DAY: u64 = 86_400;
+
+    fn claimed(id: &str, role: RequestRole, at: u64, price_usdc: u64) -> LocalRequest {
+        LocalRequest {
+            schema_version: 0,
+            request_id: id.to_string(),
+            role,
+            status: LocalRequestStatus::Claimed,
+            request_cid: String::new(),
+            price_usdc,
+            deadline: at + DAY,
+            response_cid: None,
+            secret: None,
+            secret_hash: None,
+            counterparty: None,
+            created_at: at - DAY,
+            updated_at: at + 60,
+            previous_status: Some(LocalRequestStatus::Validated),
+            tags: Vec::new(),
+            task_summary: None,
+            capability: None,
+            trust_policy: None,
+            history: vec![TransitionEntry {
+                from: LocalRequestStatus::Validated,
+                to: LocalRequestStatus::Claimed,
+                timestamp: at,
+                note: None,
+            }],
+            payment_check: None,
+            response_attempt: None,
+        }
+    }
+
+    #[test]
+    fn test_period_parse_and_labels() {
+        assert_eq!(Period::parse(" Week ").unwrap(), Period::Week);
+        assert!(Period::parse("year").is_err());
+
+        assert_eq!(Period::Day.label(WED), "2024-03-13");
+        assert_eq!(Period::Week.label(WED), "2024-03-11");
+        assert_eq!(Period::Week.label(WED - 2 * DAY), "2024-03-11");
+        assert_eq!(Period::Week.label(WED - 3 * DAY), "2024-03-04");
+        assert_eq!(Period::Month.label(WED), "2024-03");
+    }
+
+    #[test]
+    fn test_claimed_at_prefers_history() {
+        let mut request = claimed("1", RequestRole::Seller, WED, 1);
+        assert_eq!(claimed_at(&request), WED);
+        request.history.clear();
+        assert_eq!(claimed_at(&request), WED + 60);
+    }
+
+    #[test]
+    fn test_earnings_grouped_by_period() {
+        let mut requests = vec![
+            claimed("1", RequestRole::Seller, WED, 5_000_000),
+            claimed("2", RequestRole::Seller, WED + 3_600, 2_500_000),
+            claimed("3", RequestRole::Seller, WED + 5 * DAY, 1_000_000),
+            claimed("4", RequestRole::Seller, WED - 20 * DAY, 7_000_000),
+            // Spending, not earnings.
+            claimed("5", RequestRole::Buyer, WED, 9_000_000),
+        ];
+        let mut open = claimed("6", RequestRole::Seller, WED, 9_000_000);
+        open.status = LocalRequestStatus::Validated;
+        requests.push(open);
+
+        let days = earnings_by_period(&requests, Period::Day);
+        assert_eq!(
+            days.iter().map(|b| b.period.as_str()).collect::<Vec<_>>(),
+            ["2024-02-22", "2024-03-13", "2024-03-18"]
+        );
+        assert_eq!(days[1].completed, 2);
+        assert_eq!(days[1].earnings_usdc, 7_500_000);
+
+        let weeks = earnings_by_period(&requests, Period::Week);
+        assert_eq!(weeks.len(), 3);
+        assert_eq!(weeks[1].period, "2024-03-11");
+
+        let months = earnings_by_period(&requests, Period::Month);
+        assert_eq!(
+            months,
+            vec![
+                EarningsBucket {
+                    period: "2024-02".to_string(),
+                    completed: 1,
+                    earnings_usdc: 7_000_000,
+                },
+                EarningsBucket {
+                    period: "2024-03".to_string(),
+                    completed: 3,
+                    earnings_usdc: 8_500_000,
+                },
+            ]
+        );
+
+        assert!(earnings_by_period(&[], Period::Day).is_empty());
+    }
+}
@@ -0,0 +1,382 @@
+//! The agent status report shown by `status`.
+//!
+//! [`StatusReport`] is the one source for both the human summary and the
+//! `--json` object. Its serialized field names are relied on by agents that
+//! drive the CLI, so each is spelled out with `#[serde(rename)]` and a Rust
+//! rename cannot change them; add fields freely, but do not rename or
+//! remove them.
+//!
+//! With `--verbose` the report also lists what needs attention, each with
+//! the command that deals with it (see [`pending_actions`]), and the most
+//! recently finished requests.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::chain::contracts::Network;
+use crate::engine::identity::IdentityState;
+use crate::engine::reputation::{self, ReputationScore};
+use crate::engine::requests::{LocalRequest, LocalRequestStatus, RequestRole, RequestSummary};
+
+/// How close to its deadline an open request of ours must be to need
+/// attention.
+pub const NEAR_DEADLINE_SECS: u64 = 24 * 60 * 60;
+
+/// Number of finished requests listed by `status --verbose`.
+pub const RECENT_SHOWN: usize = 5;
+
+/// Everything `status` reports about the agent.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct StatusReport {
+    /// Agent name from the config.
+    #[serde(rename = "name")]
+    pub name: String,
+    /// On-chain agent ID; `null` until registered.
+    #[serde(rename = "agent_id")]
+    pub agent_id: Option<String>,
+    /// Agent address; `null` before `init`.
+    #[serde(rename = "address")]
+    pub address: Option<String>,
+    /// `"uninitialized"`, `"local"`, or `"registered"`.
+    #[serde(rename = "identity_state")]
+    pub identity_state: &'static str,
+    /// Reputation score from 0.0 to 100.0.
+    #[serde(rename = "reputation_score")]
+    pub reputation_score: f64,
+    /// `"Unrated"`, `"New"`, `"Fair"`, `"Good"`, or `"Excellent"`.
+    #[serde(rename = "reputation_tier")]
+    pub reputation_tier: &'static str,
+    /// Total earnings in USDC atomic units (6 decimals).
+    #[serde(rename = "earnings_usdc")]
+    pub earnings_usdc: u64,
+    /// `earnings_usdc` in dollars, e.g. `"$42.50"`.
+    #[serde(rename = "earnings_formatted")]
+    pub earnings_formatted: String,
+    /// Requests per status (`"Open"`, `"Responded"`, ...); every status is
+    /// present, possibly with 0.
+    #[serde(rename = "requests_by_status")]
+    pub requests_by_status: BTreeMap<LocalRequestStatus, usize>,
+    /// Validated work not yet claimed, in USDC atomic units.
+    #[serde(rename = "claimable_usdc")]
+    pub claimable_usdc: u64,
+    /// The network the agent is on, e.g. `"base-mainnet"`.
+    #[serde(rename = "network")]
+    pub network: Network,
+    /// Whether that is a test network, whose funds have no real value.
+    #[serde(rename = "testnet")]
+    pub testnet: bool,
+    /// With `--verbose`: what needs attention, most urgent kind first.
+    #[serde(rename = "pending_actions", skip_serializing_if = "Option::is_none")]
+    pub pending_actions: Option<Vec<PendingAction>>,
+    /// With `--verbose`: the last [`RECENT_SHOWN`] finished requests, newest
+    /// first.
+    #[serde(rename = "recent_requests", skip_serializing_if = "Option::is_none")]
+    pub recent_requests: Option<Vec<RecentRequest>>,
+}
+
+/// What a [`PendingAction`] asks for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionKind {
+    /// Our validated work can be claimed.
+    Claim,
+    /// A response is waiting for us to validate it.
+    Validate,
+    /// Our open request has no response and its deadline is near.
+    NearDeadline,
+}
+
+/// A request that needs attention.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct PendingAction {
+    #[serde(rename = "kind")]
+    pub kind: ActionKind,
+    #[serde(rename = "request_id")]
+    pub request_id: String,
+    #[serde(rename = "price_usdc")]
+    pub price_usdc: u64,
+    #[serde(rename = "deadline")]
+    pub deadline: u64,
+    /// The command that deals with it, ready to copy.
+    #[serde(rename = "command")]
+    pub command: String,
+}
+
+/// A finished request, as listed by `status --verbose`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct RecentRequest {
+    #[serde(rename = "request_id")]
+    pub request_id: String,
+    #[serde(rename = "role")]
+    pub role: RequestRole,
+    #[serde(rename = "status")]
+    pub status: LocalRequestStatus,
+    #[serde(rename = "price_usdc")]
+    pub price_usdc: u64,
+    /// When it reached its final status.
+    #[serde(rename = "finished_at")]
+    pub finished_at: u64,
+}
+
+impl StatusReport {
+    /// Build the report for agent `name` in `state` on `network`.
+    pub fn new(
+        name: &str,
+        network: Network,
+        state: &IdentityState,
+        reputation: &ReputationScore,
+        summary: &RequestSummary,
+    ) -> Self {
+        let (identity_state, address, agent_id) = match state {
+            IdentityState::Uninitialized => ("uninitialized", None, None),
+            IdentityState::Local { address, .. } => ("local", Some(address.clone()), None),
+            IdentityState::Registered {
+                address, agent_id, ..
+            } => ("registered", Some(address.clone()), Some(agent_id.clone())),
+        };
+        Self {
+            name: name.to_string(),
+            agent_id,
+            address,
+            identity_state,
+            reputation_score: reputation.score,
+            reputation_tier: reputation::reputation_tier(reputation),
+            earnings_usdc: reputation.total_earnings_usdc,
+            earnings_formatted: reputation::format_earnings_usd(reputation.total_earnings_usdc),
+            requests_by_status: summary.by_status.clone(),
+            claimable_usdc: summary.claimable_usdc,
+            network,
+            testnet: network.is_testnet(),
+            pending_actions: None,
+            recent_requests: None,
+        }
+    }
+
+    /// Add the `--verbose` sections, computed from `requests` at `now`.
+    pub fn with_activity(mut self, requests: &[LocalRequest], now: u64) -> Self {
+        self.pending_actions = Some(pending_actions(requests, now));
+        self.recent_requests = Some(recent_requests(requests, RECENT_SHOWN));
+        self
+    }
+}
+
+/// Requests that need attention at `now`: validated work of ours to claim,
+/// responses for us to validate, and our open requests with no response
+/// due within [`NEAR_DEADLINE_SECS`]. Grouped in that order, soonest
+/// deadline first within each group.
+pub fn pending_actions(requests: &[LocalRequest], now: u64) -> Vec<PendingAction> {
+    let mut actions: Vec<PendingAction> = requests
+        .iter()
+        .filter_map(|r| {
+            let (kind, command) = match (&r.status, &r.role) {
+                (LocalRequestStatus::Validated, RequestRole::Seller) => (
+                    ActionKind::Claim,
+                    format!("agentmarket claim -i {}", r.request_id),
+                ),
+                (LocalRequestStatus::Responded, RequestRole::Validator) => {
+                    (ActionKind::Validate, "agentmarket validate".to_string())
+                }
+                (LocalRequestStatus::Open, RequestRole::Buyer)
+                    if !r.is_past_deadline(now)
+                        && r.deadline.saturating_sub(now) <= NEAR_DEADLINE_SECS =>
+                {
+                    (
+                        ActionKind::NearDeadline,
+                        format!("agentmarket cancel -i {}", r.request_id),
+                    )
+                }
+                _ => return None,
+            };
+            Some(PendingAction {
+                kind,
+                request_id: r.request_id.clone(),
+                price_usdc: r.price_usdc,
+                deadline: r.deadline,
+                command,
+            })
+        })
+        .collect();
+    actions.sort_by_key(|a| (a.kind, a.deadline));
+    actions
+}
+
+/// The `limit` most recently finished (claimed, cancelled, or expired)
+/// requests, newest first.
+pub fn recent_requests(requests: &[LocalRequest], limit: usize) -> Vec<RecentRequest> {
+    let mut finished: Vec<&LocalRequest> =
+        requests.iter().filter(|r| r.status.is_terminal()).collect();
+    finished.sort_by_key(|r| std::cmp::Reverse(r.updated_at));
+    finished
+        .into_iter()
+        .take(limit)
+        .map(|r| RecentRequest {
+            request_id: r.request_id.clone(),
+            role: r.role.clone(),
+            status: r.status.clone(),
+            price_usdc: r.price_usdc,
+            finished_at: r.updated_at,
+        })
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::requests::LocalRequest;
+
+    fn request(status: &str, role: &str, price_usdc: u64) -> LocalRequest {
+        serde_json::from_value(serde_json::json!({
+            "request_id": "1",
+            "role": role,
+            "status": status,
+            "request_cid": "QmTestCid123",
+            "price_usdc": price_usdc,
+            "deadline": 1_700_000_000,
+            "response_cid": null,
+            "secret": null,
+            "secret_hash": null,
+            "counterparty": null,
+            "created_at": 1_699_000_000,
+            "updated_at": 1_699_000_000,
+        }))
+        .unwrap()
+    }
+
+    /// Pins the JSON field names and layout that `status --json` consumers
+    /// rely on.
+    #[test]
+    fn test_json_snapshot() {
+        let summary = RequestSummary::from_requests(&[
+            request("Claimed", "Seller", 42_500_000),
+            request("Validated", "Seller", 5_000_000),
+            request("Open", "Buyer", 1_000_000),
+        ]);
+        let mut reputation = reputation::compute_reputation("42", &[], summary.earned_usdc, 0);
+        reputation.completed_requests = 1;
+        reputation.score = 100.0;
+        let state = IdentityState::Registered {
+            address: "0x1234567890abcdef1234567890abcdef12345678".to_string(),
+            public_key: "02ab".to_string(),
+            agent_id: "42".to_string(),
+        };
+
+        let report =
+            StatusReport::new("alice", Network::BaseMainnet, &state, &reputation, &summary);
+        assert_eq!(
+            serde_json::to_string_pretty(&report).unwrap(),
+            r#"{
+  "name": "alice",
+  "agent_id": "42",
+  "address": "0x1234567890abcdef1234567890abcdef12345678",
+  "identity_state": "registered",
+  "reputation_score": 100.0,
+  "reputation_tier": "Excellent",
+  "earnings_usdc": 42500000,
+  "earnings_formatted": "$42.50",
+  "requests_by_status": {
+    "Open": 1,
+    "Responded": 0,
+    "Validated": 1,
+    "Claimed": 1,
+    "Cancelled": 0,
+    "Expired": 0
+  },
+  "claimable_usdc": 5000000,
+  "network": "base-mainnet",
+  "testnet": false
+}"#
+        );
+    }
+
+    #[test]
+    fn test_local_identity_has_no_agent_id() {
+        let state = IdentityState::Local {
+            address: "0xabc".to_string(),
+            public_key: "02ab".to_string(),
+        };
+        let reputation = reputation::compute_reputation("", &[], 0, 0);
+        let summary = RequestSummary::from_requests(&[request("Open", "Seller", 1)]);
+        let report = StatusReport::new("bob", Network::BaseSepolia, &state, &reputation, &summary);
+
+        assert_eq!(report.identity_state, "local");
+        assert_eq!(report.address.as_deref(), Some("0xabc"));
+        assert_eq!(report.agent_id, None);
+        assert_eq!(report.reputation_tier, "Unrated");
+        assert_eq!(report.requests_by_status[&LocalRequestStatus::Open], 1);
+        assert!(report.testnet);
+    }
+
+    fn at(id: &str, status: &str, role: &str, deadline: u64, updated_at: u64) -> LocalRequest {
+        let mut r = request(status, role, 1_000_000);
+        r.request_id = id.to_string();
+        r.deadline = deadline;
+        r.updated_at = updated_at;
+        r
+    }
+
+    #[test]
+    fn test_pending_actions_selects_and_orders() {
+        let now = 1_700_000_000;
+        let hour = 3600;
+        let requests = vec![
+            at("open-far", "Open", "Buyer", now + 3 * NEAR_DEADLINE_SECS, 0),
+            at("open-soon", "Open", "Buyer", now + 2 * hour, 0),
+            at("open-past", "Open", "Buyer", now - hour, 0),
+            at("open-seller", "Open", "Seller", now + hour, 0),
+            at("review", "Responded", "Validator", now + 5 * hour, 0),
+            at("responded-seller", "Responded", "Seller", now + hour, 0),
+            at("claim-later", "Validated", "Seller", now + 9 * hour, 0),
+            at("claim-first", "Validated", "Seller", now + 4 * hour, 0),
+            at("validated-buyer", "Validated", "Buyer", now + hour, 0),
+        ];
+
+        let actions = pending_actions(&requests, now);
+        let listed: Vec<(ActionKind, &str, &str)> = actions
+            .iter()
+            .map(|a| (a.kind, a.request_id.as_str(), a.command.as_str()))
+            .collect();
+        assert_eq!(
+            listed,
+            [
+                (
+                    ActionKind::Claim,
+                    "claim-first",
+                    "agentmarket claim -i claim-first"
+                ),
+                (
+                    ActionKind::Claim,
+                    "claim-later",
+                    "agentmarket claim -i claim-later"
+                ),
+                (ActionKind::Validate, "review", "agentmarket validate"),
+                (
+                    ActionKind::NearDeadline,
+                    "open-soon",
+                    "agentmarket cancel -i open-soon"
+                ),
+            ]
+        );
+        assert_eq!(actions[0].price_usdc, 1_000_000);
+        assert!(pending_actions(&[], now).is_empty());
+    }
+
+    #[test]
+    fn test_recent_requests_newest_finished_first() {
+        let mut requests: Vec<LocalRequest> = (0..7)
+            .map(|i| at(&format!("done-{i}"), "Claimed", "Seller", 0, 100 + i))
+            .collect();
+        requests.push(at("cancelled", "Cancelled", "Buyer", 0, 150));
+        requests.push(at("active", "Open", "Buyer", 0, 999));
+
+        let recent = recent_requests(&requests, RECENT_SHOWN);
+        let ids: Vec<&str> = recent.iter().map(|r| r.request_id.as_str()).collect();
+        assert_eq!(ids, ["cancelled", "done-6", "done-5", "done-4", "done-3"]);
+        assert_eq!(recent[0].status, LocalRequestStatus::Cancelled);
+        assert_eq!(recent[0].finished_at, 150);
+    }
+}
@@ -0,0 +1,383 @@
+//! systemd integration for the daemon.
+//!
+//! `daemon install` writes a user-level unit (see [`render_unit`]) so an
+//! agent can run under `systemctl --user` without a hand-written unit file.
+//! The unit is `Type=notify`: the running daemon reports `READY=1` after its
+//! first successful cycle and, when the unit sets `WatchdogSec=`,
+//! `WATCHDOG=1` after every cycle (see [`SdNotify`]).
+//!
+//! Outside systemd there is no `NOTIFY_SOCKET` and notifications do
+//! nothing.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use tracing::debug;
+
+// ---------------------------------------------------------------------------
+// Unit file
+// ---------------------------------------------------------------------------
+
+/// What goes into a generated unit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Unit {
+    /// Agent name, for the description.
+    pub agent_name: String,
+    /// The `agentmarket` executable.
+    pub executable: PathBuf,
+    /// Arguments after the executable, e.g. `["daemon", "--interval", "60"]`.
+    pub args: Vec<String>,
+    /// Agent home, exported as `AGENTMARKET_HOME`.
+    pub home: PathBuf,
+}
+
+/// Unit name for the agent called `agent_name`, e.g.
+/// `agentmarket-summarizer.service`. Characters systemd would need escaped
+/// are replaced so each agent on a machine gets its own readable unit.
+pub fn unit_name(agent_name: &str) -> String {
+    let slug: String = agent_name
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    let slug = slug.trim_matches('-');
+    if slug.is_empty() {
+        "agentmarket.service".to_string()
+    } else {
+        format!("agentmarket-{slug}.service")
+    }
+}
+
+/// Directory user units are installed to:
+/// `$XDG_CONFIG_HOME/systemd/user`, or `~/.config/systemd/user`.
+pub fn user_unit_dir() -> Result<PathBuf> {
+    let config = dirs::config_dir().context("unable to determine the user config directory")?;
+    Ok(config.join("systemd").join("user"))
+}
+
+/// Text of the unit file for `unit`.
+pub fn render_unit(unit: &Unit) -> String {
+    let exec_start = std::iter::once(unit.executable.to_string_lossy().into_owned())
+        .chain(unit.args.iter().cloned())
+        .map(|arg| quote_arg(&arg))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let description = if unit.agent_name.is_empty() {
+        "AgentMarket daemon".to_string()
+    } else {
+        format!("AgentMarket daemon ({})", unit.agent_name)
+    };
+    format!(
+        "[Unit]\n\
+         Description={description}\n\
+         After=network-online.target\n\
+         Wants=network-online.target\n\
+         \n\
+         [Service]\n\
+         Type=notify\n\
+         NotifyAccess=main\n\
+         ExecStart={exec_start}\n\
+         Environment={env}\n\
+         Restart=on-failure\n\
+         RestartSec=30\n\
+         TimeoutStartSec=300\n\
+         \n\
+         [Install]\n\
+         WantedBy=default.target\n",
+        description = escape_specifiers(&description),
+        env = quote_arg(&format!("AGENTMARKET_HOME={}", unit.home.display())),
+    )
+}
+
+/// Quote `arg` for an `ExecStart=` or `Environment=` line: `%` specifiers
+/// and `$` expansion are escaped, and arguments with spaces, quotes, or
+/// backslashes are double-quoted.
+fn quote_arg(arg: &str) -> String {
+    let escaped = escape_specifiers(arg).replace('$', "$$");
+    let needs_quotes = escaped.is_empty()
+        || escaped
+            .chars()
+            .any(|c| c.is_whitespace() || matches!(c, '"' | '\'' | '\\' | ';'));
+    if !needs_quotes {
+        return escaped;
+    }
+    let mut quoted = String::with_capacity(escaped.len() + 2);
+    quoted.push('"');
+    for c in escaped.chars() {
+        if matches!(c, '"' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn escape_specifiers(text: &str) -> String {
+    text.replace('%', "%%")
+}
+
+// ---------------------------------------------------------------------------
+// sd_notify
+// ---------------------------------------------------------------------------
+
+/// A state reported to systemd.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NotifyState {
+    /// Startup finished.
+    Ready,
+    /// Still alive; resets the watchdog timer.
+    Watchdog,
+    /// Shutting down.
+    Stopping,
+    /// Free-form status shown by `systemctl status`.
+    Status(String),
+}
+
+impl NotifyState {
+    /// The `KEY=value` line sent for this state.
+    pub fn message(&self) -> String {
+        match self {
+            NotifyState::Ready => "READY=1".to_string(),
+            NotifyState::Watchdog => "WATCHDOG=1".to_string(),
+            NotifyState::Stopping => "STOPPING=1".to_string(),
+            NotifyState::Status(status) => format!("STATUS={}", status.replace('\n', " ")),
+        }
+    }
+}
+
+/// Sends notifications to systemd when started by it; does nothing
+/// otherwise.
+#[derive(Debug, Default)]
+pub struct SdNotify {
+    socket: Option<String>,
+    watchdog: bool,
+    ready: bool,
+}
+
+impl SdNotify {
+    /// Read `NOTIFY_SOCKET` and the watchdog settings from the environment.
+    /// The watchdog is on when `WATCHDOG_USEC` is set and `WATCHDOG_PID`, if
+    /// set, names this process.
+    pub fn from_env() -> Self {
+        let socket = std::env::var("NOTIFY_SOCKET")
+            .ok()
+            .filter(|s| !s.is_empty());
+        let watchdog_pid_matches = std::env::var("WATCHDOG_PID")
+            .ok()
+            .and_then(|pid| pid.parse::<u32>().ok())
+            .map_or(true, |pid| pid == std::process::id());
+        let watchdog = socket.is_some()
+            && watchdog_pid_matches
+            && std::env::var("WATCHDOG_USEC")
+                .ok()
+                .and_then(|usec| usec.parse::<u64>().ok())
+                .is_some_and(|usec| usec > 0);
+        Self {
+            socket,
+            watchdog,
+            ready: false,
+        }
+    }
+
+    /// Report a finished cycle: `READY=1` after the first successful one,
+    /// and `WATCHDOG=1` after every one when the watchdog is on.
+    pub fn cycle_finished(&mut self, succeeded: bool) {
+        if succeeded && !self.ready {
+            self.ready = true;
+            self.send(&[NotifyState::Ready]);
+        }
+        if self.watchdog {
+            self.send(&[NotifyState::Watchdog]);
+        }
+    }
+
+    /// Report that the daemon is shutting down.
+    pub fn stopping(&self) {
+        self.send(&[NotifyState::Stopping]);
+    }
+
+    fn send(&self, states: &[NotifyState]) {
+        let Some(socket) = &self.socket else {
+            return;
+        };
+        let message = states
+            .iter()
+            .map(NotifyState::message)
+            .collect::<Vec<_>>()
+            .join("\n");
+        if let Err(err) = send_to(socket, &message) {
+            debug!(socket = %socket, error = %err, "sd_notify failed");
+        }
+    }
+}
+
+/// Send `message` as one datagram to the notify socket at `socket`, a path
+/// or (on Linux) an abstract name starting with `@`.
+#[cfg(unix)]
+pub fn send_to(socket: &str, message: &str) -> std::io::Result<()> {
+    use std::os::unix::net::UnixDatagram;
+
+    let sender = UnixDatagram::unbound()?;
+    if let Some(name) = socket.strip_prefix('@') {
+        send_abstract(&sender, name, message)
+    } else {
+        sender.send_to(message.as_bytes(), Path::new(socket))?;
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn send_abstract(
+    sender: &std::os::unix::net::UnixDatagram,
+    name: &str,
+    message: &str,
+) -> std::io::Result<()> {
+    use std::os::linux::net::SocketAddrExt;
+    use std::os::unix::net::SocketAddr;
+
+    let addr = SocketAddr::from_abstract_name(name.as_bytes())?;
+    sender.send_to_addr(message.as_bytes(), &addr)?;
+    Ok(())
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn send_abstract(
+    _sender: &std::os::unix::net::UnixDatagram,
+    _name: &str,
+    _message: &str,
+) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "abstract notify sockets are Linux-only",
+    ))
+}
+
+#[cfg(not(unix))]
+pub fn send_to(_socket: &str, _message: &str) -> std::io::Result<()> {
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit() -> Unit {
+        Unit {
+            agent_name: "Summarizer".to_string(),
+            executable: PathBuf::from("/usr/local/bin/agentmarket"),
+            args: vec![
+                "daemon".to_string(),
+                "--interval".to_string(),
+                "60".to_string(),
+            ],
+            home: PathBuf::from("/home/luis/.agentmarket"),
+        }
+    }
+
+    #[test]
+    fn test_render_unit() {
+        assert_eq!(
+            render_unit(&unit()),
+            "[Unit]\n\
+             Description=AgentMarket daemon (Summarizer)\n\
+             After=network-online.target\n\
+             Wants=network-online.target\n\
+             \n\
+             [Service]\n\
+             Type=notify\n\
+             NotifyAccess=main\n\
+             ExecStart=/usr/local/bin/agentmarket daemon --interval 60\n\
+             Environment=AGENTMARKET_HOME=/home/luis/.agentmarket\n\
+             Restart=on-failure\n\
+             RestartSec=30\n\
+             TimeoutStartSec=300\n\
+             \n\
+             [Install]\n\
+             WantedBy=default.target\n"
+        );
+    }
+
+    #[test]
+    fn test_render_unit_quotes_awkward_arguments() {
+        let mut unit = unit();
+        unit.args.extend([
+            "--handler-path".to_string(),
+            "/opt/my handler/run \"v2\".sh".to_string(),
+        ]);
+        unit.home = PathBuf::from("/srv/agents/100% $HOME");
+        let text = render_unit(&unit);
+        assert!(
+            text.contains(r#"--handler-path "/opt/my handler/run \"v2\".sh""#),
+            "{text}"
+        );
+        assert!(
+            text.contains(r#"Environment="AGENTMARKET_HOME=/srv/agents/100%% $$HOME""#),
+            "{text}"
+        );
+    }
+
+    #[test]
+    fn test_unit_name() {
+        assert_eq!(unit_name("Summarizer"), "agentmarket-summarizer.service");
+        assert_eq!(
+            unit_name("Code Review Bot!"),
+            "agentmarket-code-review-bot.service"
+        );
+        assert_eq!(unit_name("  "), "agentmarket.service");
+    }
+
+    #[test]
+    fn test_notify_messages() {
+        assert_eq!(NotifyState::Ready.message(), "READY=1");
+        assert_eq!(NotifyState::Watchdog.message(), "WATCHDOG=1");
+        assert_eq!(NotifyState::Stopping.message(), "STOPPING=1");
+        assert_eq!(
+            NotifyState::Status("3 cycles\nrun".to_string()).message(),
+            "STATUS=3 cycles run"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_ready_sent_once_then_watchdog_each_cycle() {
+        use std::os::unix::net::UnixDatagram;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notify.sock");
+        let receiver = UnixDatagram::bind(&path).unwrap();
+        receiver.set_nonblocking(true).unwrap();
+
+        let mut notify = SdNotify {
+            socket: Some(path.to_string_lossy().into_owned()),
+            watchdog: true,
+            ready: false,
+        };
+        notify.cycle_finished(false);
+        notify.cycle_finished(true);
+        notify.cycle_finished(true);
+
+        let mut received = Vec::new();
+        let mut buf = [0u8; 256];
+        while let Ok(n) = receiver.recv(&mut buf) {
+            received.push(String::from_utf8_lossy(&buf[..n]).into_owned());
+        }
+        assert_eq!(
+            received,
+            ["WATCHDOG=1", "READY=1", "WATCHDOG=1", "WATCHDOG=1"]
+        );
+    }
+
+    #[test]
+    fn test_without_socket_nothing_is_sent() {
+        let mut notify = SdNotify::default();
+        notify.cycle_finished(true);
+        notify.stopping();
+        assert!(notify.ready);
+    }
+}
@@ -0,0 +1,323 @@
+//! Payload templating for AgentMarket CLI.
+//!
+//! Request text may contain variables that are resolved when the request is
+//! created, so batch manifests and schedules can reuse one payload:
+//!
+//! | Syntax               | Value                                           |
+//! |----------------------|-------------------------------------------------|
+//! | `{{date}}`           | Creation date (UTC), `%Y-%m-%d`                 |
+//! | `{{date:FORMAT}}`    | Creation time (UTC) in a strftime-style format  |
+//! | `{{seq}}`            | Sequence number supplied by the caller          |
+//! | `{{row.FIELD}}`      | Field of the current batch row                  |
+//!
+//! Rules:
+//!
+//! - Unknown variables and missing values are errors — nothing silently
+//!   renders as an empty string.
+//! - `\{`, `\}`, and `\\` produce a literal `{`, `}`, and `\`, so `\{{`
+//!   writes a literal `{{`. A lone `}}` outside a variable is literal text.
+//! - Resolution is a single pass: resolved values are copied into the output
+//!   verbatim and are never scanned for further variables.
+//!
+//! Every resolved variable is reported back in [`Rendered::values`] so the
+//! exact values sent can be recorded alongside the payload.
+
+use std::collections::BTreeMap;
+
+use crate::clock;
+use anyhow::{bail, Result};
+
+// ---------------------------------------------------------------------------
+// Constants
+// ---------------------------------------------------------------------------
+
+/// Format used by a bare `{{date}}`.
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+/// Values available to a template.
+#[derive(Clone, Debug, Default)]
+pub struct TemplateContext {
+    /// Unix timestamp used for `{{date}}`.
+    pub now: u64,
+    /// Sequence number for `{{seq}}`, if the caller provides one.
+    pub seq: Option<u64>,
+    /// Fields of the current batch row for `{{row.FIELD}}`.
+    pub row: BTreeMap<String, String>,
+}
+
+impl TemplateContext {
+    /// Context with the current time and no sequence number or row.
+    pub fn now() -> Self {
+        let now = clock::unix_now();
+        Self {
+            now,
+            ..Self::default()
+        }
+    }
+}
+
+/// Output of [`render`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rendered {
+    /// The text with every variable resolved.
+    pub text: String,
+    /// Each distinct variable expression (e.g. `row.url`) and its value.
+    pub values: BTreeMap<String, String>,
+}
+
+// ---------------------------------------------------------------------------
+// Rendering
+// ---------------------------------------------------------------------------
+
+/// Returns `true` if `text` contains a variable or a brace escape and should
+/// be passed through [`render`]. Text without either (e.g. a Windows path
+/// with backslashes) is sent as typed.
+pub fn has_template_syntax(text: &str) -> bool {
+    text.contains("{{") || text.contains("\\{") || text.contains("\\}")
+}
+
+/// Resolve every variable in `template` against `ctx`.
+pub fn render(template: &str, ctx: &TemplateContext) -> Result<Rendered> {
+    let mut text = String::with_capacity(template.len());
+    let mut values = BTreeMap::new();
+    let mut rest = template;
+
+    while let Some(pos) = rest.find(['\\', '{']) {
+        text.push_str(&rest[..pos]);
+        let tail = &rest[pos..];
+
+        if let Some(escaped) = tail.strip_prefix('\\') {
+            match escaped.chars().next() {
+                Some(c @ ('{' | '}' | '\\')) => {
+                    text.push(c);
+                    rest = &escaped[1..];
+                }
+                _ => {
+                    text.push('\\');
+                    rest = escaped;
+                }
+            }
+            continue;
+        }
+
+        let Some(body_and_rest) = tail.strip_prefix("{{") else {
+            text.push('{');
+            rest = &tail[1..];
+            continue;
+        };
+
+        let Some(end) = body_and_rest.find("}}") else {
+            bail!("Unclosed template variable: \"{}\"", preview(tail));
+        };
+        let expr = body_and_rest[..end].trim();
+        let value = resolve(expr, ctx)?;
+        text.push_str(&value);
+        values.insert(expr.to_string(), value);
+        rest = &body_and_rest[end + 2..];
+    }
+
+    text.push_str(rest);
+    Ok(Rendered { text, values })
+}
+
+/// Resolve a single variable expression.
+fn resolve(expr: &str, ctx: &TemplateContext) -> Result<String> {
+    if expr.is_empty() {
+        bail!("Empty template variable \"{{{{}}}}\".");
+    }
+    if expr.contains("{{") {
+        bail!("Template variables cannot be nested: \"{{{{{expr}}}}}\"");
+    }
+
+    if expr == "date" {
+        return format_date(ctx.now, DEFAULT_DATE_FORMAT);
+    }
+    if let Some(format) = expr.strip_prefix("date:") {
+        return format_date(ctx.now, format);
+    }
+    if expr == "seq" {
+        return ctx.seq.map(|n| n.to_string()).ok_or_else(|| {
+            anyhow::anyhow!("Template uses {{{{seq}}}} but no sequence number is available here.")
+        });
+    }
+    if let Some(field) = expr.strip_prefix("row.") {
+        return ctx.row.get(field).cloned().ok_or_else(|| {
+            anyhow::anyhow!(
+                "Template uses {{{{row.{field}}}}} but the row has no \"{field}\" column."
+            )
+        });
+    }
+
+    bail!(
+        "Unknown template variable {{{{{expr}}}}}. \
+         Supported: {{{{date}}}}, {{{{date:FORMAT}}}}, {{{{seq}}}}, {{{{row.FIELD}}}}. \
+         Write \\{{{{ for a literal {{{{."
+    )
+}
+
+fn preview(text: &str) -> String {
+    text.chars().take(24).collect()
+}
+
+// ---------------------------------------------------------------------------
+// Date formatting
+// ---------------------------------------------------------------------------
+
+/// Format a Unix timestamp (UTC) with a small strftime subset:
+/// `%Y %m %d %H %M %S %j %%`.
+pub fn format_date(timestamp: u64, format: &str) -> Result<String> {
+    let days = (timestamp / 86_400) as i64;
+    let secs_of_day = timestamp % 86_400;
+    let (year, month, day) = civil_from_days(days);
+    let day_of_year = days - days_from_civil(year, 1, 1) + 1;
+
+    let mut out = String::with_capacity(format.len() + 8);
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('Y') => out.push_str(&format!("{year:04}")),
+            Some('m') => out.push_str(&format!("{month:02}")),
+            Some('d') => out.push_str(&format!("{day:02}")),
+            Some('H') => out.push_str(&format!("{:02}", secs_of_day / 3600)),
+            Some('M') => out.push_str(&format!("{:02}", secs_of_day / 60 % 60)),
+            Some('S') => out.push_str(&format!("{:02}", secs_of_day % 60)),
+            Some('j') => out.push_str(&format!("{day_of_year:03}")),
+            Some('%') => out.push('%'),
+            Some(other) => bail!("Unsupported date format code %{other} in \"{format}\"."),
+            None => bail!("Date format \"{format}\" ends with a bare %."),
+        }
+    }
+    Ok(out)
+}
+
+/// Days since 1970-01-01 to (year, month, day), proleptic Gregorian.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// (year, month, day) to days since 1970-01-01, proleptic Gregorian.
+pub(crate) fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y.rem_euclid(400);
+    let m = i64::from(month);
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2024-03-05 14:07:09 UTC.
+    const NOW: u64 = 1_709_647_629;
+
+    fn ctx() -> TemplateContext {
+        TemplateContext {
+            now: NOW,
+            seq: Some(7),
+            row: BTreeMap::from([
+                ("url".to_string(), "https://example.com/doc.pdf".to_string()),
+                ("evil".to_string(), "{{seq}} \\{{date}}".to_string()),
+            ]),
+        }
+    }
+
+    #[test]
+    fn test_resolves_all_variable_kinds() {
+        let out = render(
+            "Summarise {{row.url}} (job {{ seq }}) by {{date}} at {{date:%H:%M:%S}}",
+            &ctx(),
+        )
+        .unwrap();
+        assert_eq!(
+            out.text,
+            "Summarise https://example.com/doc.pdf (job 7) by 2024-03-05 at 14:07:09"
+        );
+        assert_eq!(out.values["row.url"], "https://example.com/doc.pdf");
+        assert_eq!(out.values["seq"], "7");
+        assert_eq!(out.values["date"], "2024-03-05");
+        assert_eq!(out.values["date:%H:%M:%S"], "14:07:09");
+    }
+
+    #[test]
+    fn test_plain_text_passes_through() {
+        let input = r#"{"task": "no variables here", "nested": {"a": {"b": 1}}}"#;
+        let out = render(input, &ctx()).unwrap();
+        assert_eq!(out.text, input);
+        assert!(out.values.is_empty());
+        assert!(!has_template_syntax(input));
+        assert!(!has_template_syntax(r"C:\Users\agent"));
+    }
+
+    #[test]
+    fn test_escapes_produce_literal_braces() {
+        let out = render(r"literal \{{seq}} and \}} and \\ and \n", &ctx()).unwrap();
+        assert_eq!(out.text, r"literal {{seq}} and }} and \ and \n");
+        assert!(out.values.is_empty());
+    }
+
+    #[test]
+    fn test_unknown_and_missing_variables_fail() {
+        for template in [
+            "{{nope}}",
+            "{{row.missing}}",
+            "{{}}",
+            "{{date:%Q}}",
+            "{{seq",
+        ] {
+            assert!(
+                render(template, &ctx()).is_err(),
+                "{template:?} should fail"
+            );
+        }
+
+        let no_seq = TemplateContext { seq: None, ..ctx() };
+        let err = render("{{seq}}", &no_seq).unwrap_err().to_string();
+        assert!(err.contains("no sequence number"), "{err}");
+    }
+
+    #[test]
+    fn test_resolved_values_cannot_inject_template_syntax() {
+        let out = render("{{row.evil}}", &ctx()).unwrap();
+        assert_eq!(out.text, "{{seq}} \\{{date}}");
+        assert_eq!(out.values.len(), 1);
+    }
+
+    #[test]
+    fn test_format_date_known_values() {
+        assert_eq!(
+            format_date(0, "%Y-%m-%d %H:%M:%S").unwrap(),
+            "1970-01-01 00:00:00"
+        );
+        // Leap day and day-of-year.
+        assert_eq!(
+            format_date(1_709_164_800, "%Y-%m-%d %j").unwrap(),
+            "2024-02-29 060"
+        );
+        assert_eq!(format_date(NOW, "100%%").unwrap(), "100%");
+        assert!(format_date(NOW, "%").is_err());
+    }
+}
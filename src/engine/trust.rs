@@ -0,0 +1,546 @@
+//! Standing approvals ("trust policies") for repeat work with one seller.
+//!
+//! A buyer who works with the same seller regularly can pre-authorize them
+//! up to a per-request and a monthly cap. While a request is within both
+//! caps, the buyer's side runs without ceremony:
+//!
+//! 1. **Confirm** — creating a request targeted at the seller is approved by
+//!    the policy. Its price is counted against the monthly cap.
+//! 2. **Accept** — a response from that seller (and only that seller) is
+//!    reviewed under the policy. The validation handler still runs, and a
+//!    passing verdict lets the seller claim payment as usual.
+//!
+//! A policy may carry a short alias (`trust add 42 --alias weekly`), so
+//! later commands can name the seller as `@weekly`.
+//!
+//! Every automatic action is appended to a ledger so it can be audited and
+//! so monthly spending can be totalled. Anything a policy does not cover
+//! (no policy, a capability outside the policy, or a cap exceeded) falls
+//! back to the normal behaviour.
+//!
+//! Policies and the ledger live in `~/.agentmarket/trust.json`.
+
+use std::fs;
+
+use alloy::primitives::Address;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::clock::unix_now;
+use crate::config::store::config_dir;
+use crate::engine::requests::{format_price_usd, matches_capability};
+use crate::engine::template::format_date;
+
+// ---------------------------------------------------------------------------
+// Constants
+// ---------------------------------------------------------------------------
+
+/// Name of the trust file inside the config directory.
+const TRUST_FILE: &str = "trust.json";
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+/// A standing approval for one seller.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrustPolicy {
+    /// Seller the policy applies to, as returned by [`parse_seller`].
+    pub seller: String,
+    /// Short name for the seller, without the `@`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+    /// Largest request price covered, in USDC atomic units.
+    pub per_request_usdc: u64,
+    /// Total price of covered requests per calendar month (UTC).
+    pub monthly_usdc: u64,
+    /// Capabilities the policy covers, matched against a request's declared
+    /// capability (or its tags when it declares none). `*` matches any run
+    /// of characters. Empty means any request.
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+    /// When the policy was created (Unix timestamp).
+    pub created_at: u64,
+}
+
+/// An automatic action taken under a policy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyAction {
+    /// A request was confirmed without a prompt.
+    Confirm,
+    /// A response from the policy's seller was taken up for review.
+    Accept,
+}
+
+/// One ledger line: what was done automatically, and for how much.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub request_id: String,
+    pub seller: String,
+    pub action: PolicyAction,
+    pub amount_usdc: u64,
+    pub at: u64,
+}
+
+/// Contents of `trust.json`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct TrustStore {
+    #[serde(default)]
+    pub policies: Vec<TrustPolicy>,
+    #[serde(default)]
+    pub ledger: Vec<LedgerEntry>,
+}
+
+/// Whether a request is covered by a standing approval.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Coverage {
+    /// Covered: act automatically under this policy.
+    Approved(TrustPolicy),
+    /// No policy exists for the seller.
+    NoPolicy,
+    /// A policy exists but does not cover this request; the reason is shown
+    /// to the user before falling back to the normal flow.
+    Declined(String),
+}
+
+// ---------------------------------------------------------------------------
+// Seller references
+// ---------------------------------------------------------------------------
+
+/// Normalise a seller reference: an agent ID (`42` or `#42`) becomes `#42`
+/// and an address is lower-cased. Aliases are resolved by
+/// [`TrustStore::resolve_seller`].
+pub fn parse_seller(input: &str) -> Result<String> {
+    let input = input.trim();
+    let id = input.strip_prefix('#').unwrap_or(input);
+    if !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit()) {
+        let id: u64 = id
+            .parse()
+            .with_context(|| format!("Agent ID {input} is too large."))?;
+        if id == 0 {
+            bail!("Agent ID must be greater than 0.");
+        }
+        return Ok(format!("#{id}"));
+    }
+    if input.parse::<Address>().is_ok() {
+        return Ok(input.to_ascii_lowercase());
+    }
+    bail!("\"{input}\" is not an agent ID or address.")
+}
+
+/// Normalise an alias: the leading `@` is optional, and the rest must be
+/// letters, digits, `-` or `_`. Aliases compare case-insensitively.
+pub fn parse_alias(input: &str) -> Result<String> {
+    let alias = input.trim();
+    let alias = alias
+        .strip_prefix('@')
+        .unwrap_or(alias)
+        .to_ascii_lowercase();
+    if alias.is_empty()
+        || !alias
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+    {
+        bail!("\"{input}\" is not a valid alias. Use letters, digits, '-' or '_'.");
+    }
+    if alias.bytes().all(|b| b.is_ascii_digit()) {
+        bail!("Alias \"{input}\" looks like an agent ID. Include at least one letter.");
+    }
+    Ok(alias)
+}
+
+/// Seller reference for a request targeted at agent `target_agent_id`.
+pub fn agent_seller(target_agent_id: u64) -> String {
+    format!("#{target_agent_id}")
+}
+
+// ---------------------------------------------------------------------------
+// Coverage
+// ---------------------------------------------------------------------------
+
+impl TrustStore {
+    /// Load `trust.json`, or an empty store if it does not exist.
+    pub fn load() -> Result<Self> {
+        let path = config_dir()?.join(TRUST_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read trust policies: {}", path.display()))?;
+        serde_json::from_str(&contents)
+            .with_context(|| format!("failed to parse trust policies: {}", path.display()))
+    }
+
+    /// Write `trust.json`.
+    pub fn save(&self) -> Result<()> {
+        let path = config_dir()?.join(TRUST_FILE);
+        debug!(path = %path.display(), policies = self.policies.len(), "saving trust policies");
+        let json =
+            serde_json::to_string_pretty(self).context("failed to serialise trust policies")?;
+        fs::write(&path, json)
+            .with_context(|| format!("failed to write trust policies: {}", path.display()))
+    }
+
+    /// Resolve a seller reference as typed by the user: `@alias` names the
+    /// seller of the policy with that alias, and anything else goes through
+    /// [`parse_seller`].
+    pub fn resolve_seller(&self, input: &str) -> Result<String> {
+        let input = input.trim();
+        if !input.starts_with('@') {
+            return parse_seller(input);
+        }
+        let alias = parse_alias(input)?;
+        match self
+            .policies
+            .iter()
+            .find(|p| p.alias.as_ref() == Some(&alias))
+        {
+            Some(policy) => Ok(policy.seller.clone()),
+            None => bail!(
+                "No standing approval is called {input}. Name one with \
+                 `agentmarket trust add <agent ID> --alias {alias}`."
+            ),
+        }
+    }
+
+    /// The policy for `seller`, if any.
+    pub fn policy(&self, seller: &str) -> Option<&TrustPolicy> {
+        self.policies.iter().find(|p| p.seller == seller)
+    }
+
+    /// Add or replace the policy for `policy.seller`.
+    pub fn upsert(&mut self, policy: TrustPolicy) {
+        self.policies.retain(|p| p.seller != policy.seller);
+        self.policies.push(policy);
+    }
+
+    /// Remove the policy for `seller`. Returns `false` if there was none.
+    /// Ledger entries are kept.
+    pub fn remove(&mut self, seller: &str) -> bool {
+        let before = self.policies.len();
+        self.policies.retain(|p| p.seller != seller);
+        self.policies.len() != before
+    }
+
+    /// Amount confirmed under `seller`'s policy in the calendar month (UTC)
+    /// containing `now`.
+    pub fn spent_this_month(&self, seller: &str, now: u64) -> u64 {
+        let month = month_of(now);
+        self.ledger
+            .iter()
+            .filter(|e| {
+                e.seller == seller && e.action == PolicyAction::Confirm && month_of(e.at) == month
+            })
+            .map(|e| e.amount_usdc)
+            .sum()
+    }
+
+    /// Decide whether a new request to `seller` for `price_usdc` can be
+    /// confirmed automatically. A policy limited to some capabilities covers
+    /// the request if its declared `capability` matches one of them, the same
+    /// way `validate --filter` matches; a request that declares none is
+    /// matched on its `tags` instead.
+    pub fn check_request(
+        &self,
+        seller: &str,
+        price_usdc: u64,
+        capability: Option<&str>,
+        tags: &[String],
+        now: u64,
+    ) -> Coverage {
+        let Some(policy) = self.policy(seller) else {
+            return Coverage::NoPolicy;
+        };
+
+        if !policy.capabilities.is_empty() && !covers_capability(policy, capability, tags) {
+            return Coverage::Declined(format!(
+                "Your standing approval for {seller} only covers {}.",
+                policy.capabilities.join(", ")
+            ));
+        }
+        if price_usdc > policy.per_request_usdc {
+            return Coverage::Declined(format!(
+                "{} is above your standing approval for {seller} ({} per request).",
+                format_price_usd(price_usdc),
+                format_price_usd(policy.per_request_usdc)
+            ));
+        }
+        let spent = self.spent_this_month(seller, now);
+        if spent.saturating_add(price_usdc) > policy.monthly_usdc {
+            return Coverage::Declined(format!(
+                "This request would exceed your monthly standing approval for {seller} \
+                 ({} of {} used).",
+                format_price_usd(spent),
+                format_price_usd(policy.monthly_usdc)
+            ));
+        }
+        Coverage::Approved(policy.clone())
+    }
+
+    /// Decide whether a response to `request_id` can be accepted and paid
+    /// automatically. Only requests that were confirmed under the same
+    /// policy qualify, and only when `responder` (written the way
+    /// [`parse_seller`] writes it) is the policy's seller; their price was
+    /// already counted when confirmed.
+    pub fn check_response(
+        &self,
+        request_id: &str,
+        seller: &str,
+        responder: &str,
+        price_usdc: u64,
+    ) -> Coverage {
+        let Some(policy) = self.policy(seller) else {
+            return Coverage::NoPolicy;
+        };
+        let confirmed = self.ledger.iter().any(|e| {
+            e.request_id == request_id && e.seller == seller && e.action == PolicyAction::Confirm
+        });
+        if !confirmed {
+            return Coverage::Declined(format!(
+                "Request {request_id} was not confirmed under your standing approval for {seller}."
+            ));
+        }
+        if responder != policy.seller {
+            return Coverage::Declined(format!(
+                "Request {request_id} was answered by {responder}, not {seller}."
+            ));
+        }
+        if price_usdc > policy.per_request_usdc {
+            return Coverage::Declined(format!(
+                "{} is above your standing approval for {seller} ({} per request).",
+                format_price_usd(price_usdc),
+                format_price_usd(policy.per_request_usdc)
+            ));
+        }
+        Coverage::Approved(policy.clone())
+    }
+
+    /// Append an action to the ledger.
+    pub fn record(
+        &mut self,
+        request_id: &str,
+        seller: &str,
+        action: PolicyAction,
+        amount_usdc: u64,
+    ) {
+        self.ledger.push(LedgerEntry {
+            request_id: request_id.to_string(),
+            seller: seller.to_string(),
+            action,
+            amount_usdc,
+            at: unix_now(),
+        });
+    }
+}
+
+/// Load the store, record an action, and save it again.
+pub fn record_action(
+    request_id: &str,
+    seller: &str,
+    action: PolicyAction,
+    amount_usdc: u64,
+) -> Result<()> {
+    let mut store = TrustStore::load()?;
+    store.record(request_id, seller, action, amount_usdc);
+    store.save()
+}
+
+/// Whether `policy`'s capabilities cover a request declaring `capability`,
+/// falling back to its `tags` when it declares none.
+fn covers_capability(policy: &TrustPolicy, capability: Option<&str>, tags: &[String]) -> bool {
+    let covers = |value: &str| {
+        policy
+            .capabilities
+            .iter()
+            .any(|pattern| matches_capability(pattern, Some(value), true))
+    };
+    match capability {
+        Some(capability) => covers(capability),
+        None => tags.iter().any(|tag| covers(tag)),
+    }
+}
+
+fn month_of(timestamp: u64) -> String {
+    format_date(timestamp, "%Y-%m").unwrap_or_default()
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2024-03-05 14:07:09 UTC.
+    const MARCH: u64 = 1_709_647_629;
+    /// 2024-04-01 00:00:00 UTC.
+    const APRIL: u64 = 1_711_929_600;
+
+    fn store() -> TrustStore {
+        let mut store = TrustStore::default();
+        store.upsert(TrustPolicy {
+            seller: "#42".to_string(),
+            alias: Some("weekly".to_string()),
+            per_request_usdc: 10_000_000,
+            monthly_usdc: 25_000_000,
+            capabilities: Vec::new(),
+            created_at: MARCH,
+        });
+        store
+    }
+
+    fn confirm(store: &mut TrustStore, request_id: &str, amount: u64, at: u64) {
+        store.ledger.push(LedgerEntry {
+            request_id: request_id.to_string(),
+            seller: "#42".to_string(),
+            action: PolicyAction::Confirm,
+            amount_usdc: amount,
+            at,
+        });
+    }
+
+    #[test]
+    fn test_parse_seller() {
+        assert_eq!(parse_seller("42").unwrap(), "#42");
+        assert_eq!(parse_seller(" #42 ").unwrap(), "#42");
+        assert_eq!(
+            parse_seller("0xAbCdEf0123456789aBcDeF0123456789AbCdEf01").unwrap(),
+            "0xabcdef0123456789abcdef0123456789abcdef01"
+        );
+        assert!(parse_seller("@weekly").is_err());
+        assert!(parse_seller("0").is_err());
+        assert!(parse_seller("bob").is_err());
+    }
+
+    #[test]
+    fn test_within_caps_is_approved() {
+        let mut store = store();
+        confirm(&mut store, "1", 10_000_000, MARCH);
+        assert!(matches!(
+            store.check_request("#42", 10_000_000, None, &[], MARCH),
+            Coverage::Approved(_)
+        ));
+        assert_eq!(
+            store.check_request("#7", 1, None, &[], MARCH),
+            Coverage::NoPolicy
+        );
+    }
+
+    #[test]
+    fn test_caps_decline() {
+        let mut store = store();
+        assert!(matches!(
+            store.check_request("#42", 10_000_001, None, &[], MARCH),
+            Coverage::Declined(reason) if reason.contains("per request")
+        ));
+
+        confirm(&mut store, "1", 10_000_000, MARCH);
+        confirm(&mut store, "2", 10_000_000, MARCH);
+        assert!(matches!(
+            store.check_request("#42", 5_000_001, None, &[], MARCH),
+            Coverage::Declined(reason) if reason.contains("$20.00 of $25.00")
+        ));
+        assert!(matches!(
+            store.check_request("#42", 5_000_000, None, &[], MARCH),
+            Coverage::Approved(_)
+        ));
+
+        // A new month starts from zero.
+        assert_eq!(store.spent_this_month("#42", APRIL), 0);
+        assert!(matches!(
+            store.check_request("#42", 10_000_000, None, &[], APRIL),
+            Coverage::Approved(_)
+        ));
+    }
+
+    #[test]
+    fn test_aliases_resolve_to_their_seller() {
+        let store = store();
+        assert_eq!(store.resolve_seller("@weekly").unwrap(), "#42");
+        assert_eq!(store.resolve_seller(" @Weekly ").unwrap(), "#42");
+        assert_eq!(store.resolve_seller("42").unwrap(), "#42");
+        assert!(store
+            .resolve_seller("@daily")
+            .unwrap_err()
+            .to_string()
+            .contains("--alias daily"));
+
+        assert_eq!(parse_alias("@Weekly-2").unwrap(), "weekly-2");
+        assert!(parse_alias("@").is_err());
+        assert!(parse_alias("@42").is_err());
+        assert!(parse_alias("we ekly").is_err());
+    }
+
+    #[test]
+    fn test_capabilities_limit_coverage() {
+        let mut store = store();
+        store.policies[0].capabilities = vec!["translation".to_string(), "code-*".to_string()];
+        let tags = |t: &str| vec![t.to_string()];
+
+        // The declared capability decides, glob patterns included.
+        assert!(matches!(
+            store.check_request("#42", 1, Some("translation"), &[], MARCH),
+            Coverage::Approved(_)
+        ));
+        assert!(matches!(
+            store.check_request("#42", 1, Some("code-review"), &[], MARCH),
+            Coverage::Approved(_)
+        ));
+        assert!(matches!(
+            store.check_request("#42", 1, Some("legal"), &tags("translation"), MARCH),
+            Coverage::Declined(_)
+        ));
+
+        // Without one, the tags are matched instead.
+        assert!(matches!(
+            store.check_request("#42", 1, None, &tags("translation"), MARCH),
+            Coverage::Approved(_)
+        ));
+        assert!(matches!(
+            store.check_request("#42", 1, None, &tags("legal"), MARCH),
+            Coverage::Declined(_)
+        ));
+        assert!(matches!(
+            store.check_request("#42", 1, None, &[], MARCH),
+            Coverage::Declined(_)
+        ));
+    }
+
+    #[test]
+    fn test_response_requires_policy_confirmation() {
+        let mut store = store();
+        assert!(matches!(
+            store.check_response("1", "#42", "#42", 1_000_000),
+            Coverage::Declined(_)
+        ));
+        confirm(&mut store, "1", 1_000_000, MARCH);
+        assert!(matches!(
+            store.check_response("1", "#42", "#42", 1_000_000),
+            Coverage::Approved(_)
+        ));
+
+        assert!(store.remove("#42"));
+        assert!(!store.remove("#42"));
+        assert_eq!(
+            store.check_response("1", "#42", "#42", 1_000_000),
+            Coverage::NoPolicy
+        );
+        assert_eq!(store.ledger.len(), 1, "removing a policy keeps its ledger");
+    }
+
+    #[test]
+    fn test_response_from_another_seller_is_declined() {
+        let mut store = store();
+        confirm(&mut store, "1", 1_000_000, MARCH);
+        for responder in ["#7", "0x000000000000000000000000000000000000dead"] {
+            let Coverage::Declined(reason) = store.check_response("1", "#42", responder, 1_000_000)
+            else {
+                panic!("a response from {responder} must not be covered");
+            };
+            assert!(reason.contains(responder), "{reason}");
+        }
+    }
+}
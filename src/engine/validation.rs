@@ -12,7 +12,9 @@ use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use tracing::debug;
 
+use crate::clock;
 use crate::config::store::config_dir;
+use crate::engine::requests::LocalRequest;
 
 // ---------------------------------------------------------------------------
 // Types
@@ -72,6 +74,41 @@ pub struct HandlerOutput {
     pub reason: String,
 }
 
+/// A response waiting for this agent's review, as listed by
+/// `validate --list`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct PendingValidation {
+    pub request_id: String,
+    /// Start of the task description, if known.
+    pub task_summary: Option<String>,
+    /// Capability the buyer declared, if any.
+    pub capability: Option<String>,
+    /// Price in USDC (6 decimals).
+    pub price_usdc: u64,
+    /// The seller's address, if known.
+    pub seller: Option<String>,
+    /// Unix timestamp after which the request expires.
+    pub deadline: u64,
+    /// Whether the expected fee falls below the configured margin, so the
+    /// loop skips it unless `--accept-unprofitable` is set.
+    pub below_margin: bool,
+}
+
+impl PendingValidation {
+    /// The listing for `req`, flagged `below_margin` as given.
+    pub fn new(req: &LocalRequest, below_margin: bool) -> Self {
+        Self {
+            request_id: req.request_id.clone(),
+            task_summary: req.task_summary.clone(),
+            capability: req.capability.clone(),
+            price_usdc: req.price_usdc,
+            seller: req.counterparty.clone(),
+            deadline: req.deadline,
+            below_margin,
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Defaults
 // ---------------------------------------------------------------------------
@@ -112,10 +149,7 @@ pub fn is_passing(output: &HandlerOutput) -> bool {
 ///
 /// The timestamp is set to the current system time.
 pub fn create_result(request_id: &str, output: &HandlerOutput) -> ValidationResult {
-    let now = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs();
+    let now = clock::unix_now();
 
     debug!(
         request_id = %request_id,
@@ -166,7 +200,7 @@ pub fn parse_handler_output(json_str: &str) -> Result<HandlerOutput> {
 /// Returns the path to the validations directory.
 ///
 /// Creates the directory if it does not exist.
-fn validations_dir() -> Result<PathBuf> {
+pub fn validations_dir() -> Result<PathBuf> {
     let dir = config_dir()?.join(VALIDATIONS_DIR);
 
     if !dir.exists() {
@@ -194,6 +228,21 @@ pub fn save_result(result: &ValidationResult) -> Result<()> {
     Ok(())
 }
 
+/// Whether a validation result is saved for `request_id`. A missing
+/// validations directory means nothing has been validated yet.
+pub fn has_result(request_id: &str) -> Result<bool> {
+    let path = config_dir()?
+        .join(VALIDATIONS_DIR)
+        .join(format!("{}.json", request_id));
+    Ok(path.exists())
+}
+
+/// Whether `request_id` should go to the handler: it has no saved result,
+/// or `revalidate` asks for it to be reviewed again.
+pub fn needs_validation(request_id: &str, revalidate: bool) -> Result<bool> {
+    Ok(revalidate || !has_result(request_id)?)
+}
+
 /// Load a validation result for the given request ID.
 pub fn load_result(request_id: &str) -> Result<ValidationResult> {
     let path = validations_dir()?.join(format!("{}.json", request_id));
@@ -471,6 +520,53 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_has_result_without_validations_dir() {
+        with_temp_home(|| {
+            assert!(!has_result("req-none").unwrap());
+            assert!(needs_validation("req-none", false).unwrap());
+            // Checking does not create the directory.
+            let dir = config_dir().unwrap().join(VALIDATIONS_DIR);
+            assert!(!dir.exists());
+        });
+    }
+
+    #[test]
+    fn test_has_result_after_save() {
+        with_temp_home(|| {
+            let result = ValidationResult {
+                request_id: "req-seen".to_string(),
+                passed: false,
+                score: 30,
+                reason: "off topic".to_string(),
+                timestamp: 1_700_000_000,
+            };
+            save_result(&result).expect("save should succeed");
+
+            assert!(has_result("req-seen").unwrap());
+            assert!(!has_result("req-other").unwrap());
+            assert!(!needs_validation("req-seen", false).unwrap());
+            assert!(needs_validation("req-other", false).unwrap());
+        });
+    }
+
+    #[test]
+    fn test_needs_validation_revalidate_override() {
+        with_temp_home(|| {
+            let result = ValidationResult {
+                request_id: "req-again".to_string(),
+                passed: true,
+                score: 80,
+                reason: "fine".to_string(),
+                timestamp: 1_700_000_000,
+            };
+            save_result(&result).expect("save should succeed");
+
+            assert!(needs_validation("req-again", true).unwrap());
+            assert!(needs_validation("req-new", true).unwrap());
+        });
+    }
+
     // -- serialization --------------------------------------------------------
 
     #[test]
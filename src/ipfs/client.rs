@@ -7,9 +7,12 @@
 use std::time::Duration;
 
 use anyhow::{Context, Result};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
 use reqwest::multipart;
-use tracing::debug;
+use tracing::{debug, warn};
 
+use super::gateway::{self, FetchOutcome};
 use crate::config::store::Config;
 
 // ---------------------------------------------------------------------------
@@ -23,6 +26,13 @@ struct AddResponse {
     hash: String,
 }
 
+/// One line of the stream returned by `/api/v0/pubsub/sub`.
+#[derive(serde::Deserialize)]
+struct PubsubMessage {
+    /// Multibase-encoded message data.
+    data: String,
+}
+
 // ---------------------------------------------------------------------------
 // IpfsClient
 // ---------------------------------------------------------------------------
@@ -32,11 +42,15 @@ struct AddResponse {
 /// - `api_url` targets a local (or remote) Kubo-compatible IPFS HTTP API,
 ///   typically running on port 5001.
 /// - `gateway_url` targets a public or private IPFS gateway used for fast
-///   content retrieval. The gateway is tried first when fetching content; the
-///   API endpoint is used as a fallback.
+///   content retrieval. The gateway is tried first when fetching content,
+///   then `fallback_gateways`; the API endpoint is the last resort.
 pub struct IpfsClient {
     api_url: String,
     gateway_url: String,
+    fallback_gateways: Vec<String>,
+    /// Whether fetch outcomes are persisted for `doctor` in addition to the
+    /// in-memory session stats.
+    persist_health: bool,
     http: reqwest::Client,
 }
 
@@ -48,6 +62,8 @@ impl IpfsClient {
         Self {
             api_url: api_url.trim_end_matches('/').to_string(),
             gateway_url: gateway_url.trim_end_matches('/').to_string(),
+            fallback_gateways: Vec::new(),
+            persist_health: false,
             http: reqwest::Client::builder()
                 .timeout(Duration::from_secs(30))
                 .build()
@@ -57,9 +73,23 @@ impl IpfsClient {
 
     /// Creates a new `IpfsClient` from the application configuration.
     ///
-    /// Reads `config.network.ipfs_api` and `config.network.ipfs_gateway`.
+    /// Reads `config.network.ipfs_api`, `config.network.ipfs_gateway`, and
+    /// `config.network.ipfs_fallback_gateways`, and persists gateway health
+    /// under the config directory.
     pub fn from_config(config: &Config) -> Self {
-        Self::new(&config.network.ipfs_api, &config.network.ipfs_gateway)
+        let mut client = Self::new(&config.network.ipfs_api, &config.network.ipfs_gateway)
+            .with_fallback_gateways(&config.network.ipfs_fallback_gateways);
+        client.persist_health = true;
+        client
+    }
+
+    /// Sets the gateways tried after `gateway_url`, in order.
+    pub fn with_fallback_gateways(mut self, gateways: &[String]) -> Self {
+        self.fallback_gateways = gateways
+            .iter()
+            .map(|g| g.trim_end_matches('/').to_string())
+            .collect();
+        self
     }
 
     /// Uploads content to IPFS via the HTTP API (`/api/v0/add`).
@@ -101,62 +131,103 @@ impl IpfsClient {
 
     /// Retrieves content by CID.
     ///
-    /// The gateway URL is tried first (`{gateway_url}/ipfs/{cid}`). If that
-    /// fails, the method falls back to the IPFS API (`/api/v0/cat?arg={cid}`).
+    /// Gateways are tried first (`{gateway}/ipfs/{cid}`) -- the configured
+    /// gateway, then the fallback gateways, with any that have interfered or
+    /// mostly failed this session moved to the back. The IPFS API
+    /// (`/api/v0/cat?arg={cid}`) is the last resort.
+    ///
+    /// Each body is checked with [`gateway::check_response`]; a body that
+    /// does not match the CID or is an HTML page in place of the content
+    /// counts as a failure and the next endpoint is tried.
     pub async fn cat(&self, cid: &str) -> Result<Vec<u8>> {
-        // --- Attempt 1: gateway ---
-        let gateway_url = format!("{}/ipfs/{}", self.gateway_url, cid);
-        debug!(url = %gateway_url, "fetching content via gateway");
-
-        match self.http.get(&gateway_url).send().await {
-            Ok(resp) if resp.status().is_success() => {
-                let bytes = resp
-                    .bytes()
-                    .await
-                    .context("failed to read gateway response body")?;
-                debug!(cid = %cid, size = bytes.len(), "content retrieved via gateway");
-                return Ok(bytes.to_vec());
-            }
-            Ok(resp) => {
-                debug!(
-                    cid = %cid,
-                    status = %resp.status(),
-                    "gateway request returned non-success status, falling back to API"
-                );
-            }
-            Err(err) => {
-                debug!(
-                    cid = %cid,
-                    error = %err,
-                    "gateway request failed, falling back to API"
-                );
+        let mut last_error = None;
+
+        for gateway in gateway::rank(&self.gateways()) {
+            let url = format!("{gateway}/ipfs/{cid}");
+            debug!(url = %url, "fetching content via gateway");
+            match self.fetch(cid, &gateway, self.http.get(&url)).await {
+                Ok(bytes) => return Ok(bytes),
+                Err(err) => {
+                    debug!(cid = %cid, error = %err, "gateway fetch failed, trying next endpoint");
+                    last_error = Some(err);
+                }
             }
         }
 
-        // --- Attempt 2: API fallback ---
-        let api_url = format!("{}/api/v0/cat?arg={}", self.api_url, cid);
-        debug!(url = %api_url, "fetching content via API fallback");
-
-        let response = self
-            .http
-            .post(&api_url)
-            .send()
+        let url = format!("{}/api/v0/cat?arg={}", self.api_url, cid);
+        debug!(url = %url, "fetching content via API fallback");
+        self.fetch(cid, &self.api_url, self.http.post(&url))
             .await
-            .with_context(|| format!("failed to fetch CID {cid} from IPFS API"))?;
+            .map_err(|err| match last_error {
+                Some(gateway_err) => err.context(format!("gateways also failed: {gateway_err:#}")),
+                None => err,
+            })
+            .with_context(|| format!("failed to fetch CID {cid}"))
+    }
 
-        let status = response.status();
-        if !status.is_success() {
-            let body = response.text().await.unwrap_or_default();
-            anyhow::bail!("IPFS cat request failed with status {status}: {body}");
+    /// Configured gateways in preference order.
+    fn gateways(&self) -> Vec<String> {
+        let mut gateways = vec![self.gateway_url.clone()];
+        for fallback in &self.fallback_gateways {
+            if !gateways.contains(fallback) {
+                gateways.push(fallback.clone());
+            }
         }
+        gateways
+    }
 
-        let bytes = response
-            .bytes()
-            .await
-            .context("failed to read IPFS API cat response body")?;
+    /// Send one fetch request, check the body, and record the outcome
+    /// against `endpoint`.
+    async fn fetch(
+        &self,
+        cid: &str,
+        endpoint: &str,
+        request: reqwest::RequestBuilder,
+    ) -> Result<Vec<u8>> {
+        let health_file = if self.persist_health {
+            gateway::health_file().ok()
+        } else {
+            None
+        };
+        let health_file = health_file.as_deref();
+
+        let result = async {
+            let response = request.send().await.context("request failed")?;
+            let status = response.status();
+            if !status.is_success() {
+                let body = response.text().await.unwrap_or_default();
+                anyhow::bail!("status {status}: {body}");
+            }
+            let content_type = response
+                .headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string);
+            let bytes = response
+                .bytes()
+                .await
+                .context("failed to read response body")?;
+            Ok((content_type, bytes.to_vec()))
+        }
+        .await;
 
-        debug!(cid = %cid, size = bytes.len(), "content retrieved via API");
-        Ok(bytes.to_vec())
+        let (content_type, bytes) = match result {
+            Ok(ok) => ok,
+            Err(err) => {
+                gateway::record(endpoint, FetchOutcome::Failure, health_file);
+                return Err(err.context(format!("{endpoint} could not serve the content")));
+            }
+        };
+
+        if let Some(interference) = gateway::check_response(cid, content_type.as_deref(), &bytes) {
+            warn!(endpoint = %endpoint, cid = %cid, %interference, "gateway interference detected");
+            gateway::record(endpoint, FetchOutcome::Interference, health_file);
+            anyhow::bail!("{endpoint} {interference}");
+        }
+
+        gateway::record(endpoint, FetchOutcome::Success, health_file);
+        debug!(cid = %cid, endpoint = %endpoint, size = bytes.len(), "content retrieved");
+        Ok(bytes)
     }
 
     /// Pins an existing CID so the local IPFS node retains it.
@@ -204,6 +275,157 @@ impl IpfsClient {
             }
         }
     }
+
+    /// Returns `true` if the primary gateway answers HTTP requests.
+    ///
+    /// Any response other than a server error counts: a gateway's root page
+    /// is often a 404, which still shows it is up.
+    pub async fn is_gateway_reachable(&self) -> bool {
+        debug!(url = %self.gateway_url, "checking gateway connectivity");
+
+        match self.http.get(&self.gateway_url).send().await {
+            Ok(resp) => {
+                debug!(status = %resp.status(), "gateway responded");
+                !resp.status().is_server_error()
+            }
+            Err(err) => {
+                debug!(error = %err, "gateway is unreachable");
+                false
+            }
+        }
+    }
+
+    /// The primary gateway URL.
+    pub fn gateway_url(&self) -> &str {
+        &self.gateway_url
+    }
+
+    /// Publishes `data` on a pubsub topic (`/api/v0/pubsub/pub`).
+    ///
+    /// Requires the node to run with pubsub enabled.
+    pub async fn pubsub_publish(&self, topic: &str, data: &[u8]) -> Result<()> {
+        let url = format!(
+            "{}/api/v0/pubsub/pub?arg={}",
+            self.api_url,
+            multibase_encode(topic.as_bytes())
+        );
+        debug!(topic = %topic, size = data.len(), "publishing to IPFS pubsub");
+
+        let part = multipart::Part::bytes(data.to_vec())
+            .file_name("data")
+            .mime_str("application/octet-stream")
+            .context("failed to create multipart part")?;
+        let form = multipart::Form::new().part("file", part);
+
+        let response = self
+            .http
+            .post(&url)
+            .multipart(form)
+            .send()
+            .await
+            .with_context(|| format!("failed to POST to IPFS pubsub endpoint: {url}"))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("IPFS pubsub publish failed with status {status}: {body}");
+        }
+        Ok(())
+    }
+
+    /// Collects the messages published on `topic` during the next `wait`
+    /// (`/api/v0/pubsub/sub`). Lines that cannot be decoded are skipped.
+    pub async fn pubsub_collect(&self, topic: &str, wait: Duration) -> Result<Vec<Vec<u8>>> {
+        let url = format!(
+            "{}/api/v0/pubsub/sub?arg={}",
+            self.api_url,
+            multibase_encode(topic.as_bytes())
+        );
+        debug!(topic = %topic, ?wait, "collecting IPFS pubsub messages");
+
+        let mut response = self
+            .http
+            .post(&url)
+            .timeout(wait + Duration::from_secs(10))
+            .send()
+            .await
+            .with_context(|| format!("failed to POST to IPFS pubsub endpoint: {url}"))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("IPFS pubsub subscribe failed with status {status}: {body}");
+        }
+
+        // The node streams one JSON object per line for as long as the
+        // connection stays open.
+        let deadline = tokio::time::Instant::now() + wait;
+        let mut buffer = Vec::new();
+        while let Ok(chunk) = tokio::time::timeout_at(deadline, response.chunk()).await {
+            match chunk.context("failed to read IPFS pubsub stream")? {
+                Some(bytes) => buffer.extend_from_slice(&bytes),
+                None => break,
+            }
+        }
+
+        let messages: Vec<Vec<u8>> = buffer
+            .split(|b| *b == b'\n')
+            .filter(|line| !line.is_empty())
+            .filter_map(|line| {
+                let message: PubsubMessage = serde_json::from_slice(line)
+                    .map_err(|err| debug!(error = %err, "skipping malformed pubsub line"))
+                    .ok()?;
+                multibase_decode(&message.data)
+            })
+            .collect();
+        debug!(count = messages.len(), "pubsub messages collected");
+        Ok(messages)
+    }
+}
+
+/// Encode bytes as base64url multibase (`u` prefix), as the pubsub API
+/// expects for topics.
+fn multibase_encode(bytes: &[u8]) -> String {
+    format!("u{}", URL_SAFE_NO_PAD.encode(bytes))
+}
+
+/// Decode base64url multibase, the encoding the pubsub API uses for data.
+fn multibase_decode(encoded: &str) -> Option<Vec<u8>> {
+    URL_SAFE_NO_PAD.decode(encoded.strip_prefix('u')?).ok()
+}
+
+/// Serve every request on a local port with a fixed response, counting
+/// the requests received. Stands in for an IPFS gateway or API in tests.
+#[cfg(test)]
+pub(crate) async fn mock_gateway(
+    content_type: &'static str,
+    body: &'static [u8],
+) -> (String, std::sync::Arc<std::sync::atomic::AtomicUsize>) {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let hits = std::sync::Arc::new(AtomicUsize::new(0));
+    let counter = hits.clone();
+    tokio::spawn(async move {
+        loop {
+            let Ok((mut socket, _)) = listener.accept().await else {
+                return;
+            };
+            counter.fetch_add(1, Ordering::SeqCst);
+            let mut buf = [0u8; 4096];
+            let _ = socket.read(&mut buf).await;
+            let head = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: {content_type}\r\n\
+                 content-length: {}\r\nconnection: close\r\n\r\n",
+                body.len()
+            );
+            let _ = socket.write_all(head.as_bytes()).await;
+            let _ = socket.write_all(body).await;
+        }
+    });
+    (url, hits)
 }
 
 // ---------------------------------------------------------------------------
@@ -227,6 +449,7 @@ mod tests {
         let client = IpfsClient::from_config(&config);
         assert_eq!(client.api_url, "http://localhost:5001");
         assert_eq!(client.gateway_url, "https://gateway.pinata.cloud");
+        assert_eq!(client.fallback_gateways, vec!["https://ipfs.io"]);
     }
 
     #[tokio::test]
@@ -235,4 +458,75 @@ mod tests {
         let client = IpfsClient::new("http://127.0.0.1:19999", "http://127.0.0.1:19998");
         assert!(!client.is_connected().await);
     }
+
+    #[test]
+    fn multibase_round_trip() {
+        let encoded = multibase_encode(b"mailbox-topic");
+        assert_eq!(encoded, "ubWFpbGJveC10b3BpYw");
+        assert_eq!(multibase_decode(&encoded).unwrap(), b"mailbox-topic");
+        assert!(multibase_decode("fabcdef").is_none());
+    }
+
+    #[tokio::test]
+    async fn pubsub_collect_decodes_stream() {
+        const STREAM: &[u8] = b"{\"from\":\"12D3\",\"data\":\"uYmFmeWZpcnN0\",\"seqno\":\"u\"}\n\
+            not json\n\
+            {\"from\":\"12D3\",\"data\":\"uYmFmeXNlY29uZA\",\"seqno\":\"u\"}\n";
+        let (api, _) = mock_gateway("application/json", STREAM).await;
+        let client = IpfsClient::new(&api, "http://127.0.0.1:19998");
+
+        let messages = client
+            .pubsub_collect("topic", Duration::from_secs(2))
+            .await
+            .unwrap();
+        assert_eq!(
+            messages,
+            vec![b"bafyfirst".to_vec(), b"bafysecond".to_vec()]
+        );
+    }
+
+    const HELLO_CID: &str = "QmT78zSuBmuS4z925WZfrqQ1qHaJ56DQaTfyMUF7F8ff5o";
+    const BLOCKED_PAGE: &[u8] =
+        b"<!DOCTYPE html><html><body>This content is unavailable.</body></html>";
+
+    #[tokio::test]
+    async fn cat_falls_back_past_interfering_gateway() {
+        use std::sync::atomic::Ordering;
+
+        let (blocking, blocking_hits) = mock_gateway("text/html", BLOCKED_PAGE).await;
+        let (honest, _) = mock_gateway("application/octet-stream", b"hello world\n").await;
+        let client = IpfsClient::new("http://127.0.0.1:19999", &blocking)
+            .with_fallback_gateways(std::slice::from_ref(&honest));
+
+        assert_eq!(client.cat(HELLO_CID).await.unwrap(), b"hello world\n");
+        let bad = gateway::session_health(&blocking);
+        assert_eq!((bad.attempts, bad.successes, bad.interference), (1, 0, 1));
+        let good = gateway::session_health(&honest);
+        assert_eq!(
+            (good.attempts, good.successes, good.interference),
+            (1, 1, 0)
+        );
+
+        // The interfering gateway is now tried last, so it is skipped.
+        assert_eq!(client.cat(HELLO_CID).await.unwrap(), b"hello world\n");
+        assert_eq!(blocking_hits.load(Ordering::SeqCst), 1);
+        assert_eq!(gateway::session_health(&honest).successes, 2);
+    }
+
+    #[tokio::test]
+    async fn cat_rejects_content_not_matching_cid() {
+        let (wrong, _) = mock_gateway("application/octet-stream", b"tampered").await;
+        let client = IpfsClient::new("http://127.0.0.1:19999", &wrong);
+
+        let err = client.cat(HELLO_CID).await.unwrap_err();
+        assert!(
+            format!("{err:#}").contains("does not match the requested ID"),
+            "{err:#}"
+        );
+        assert_eq!(gateway::session_health(&wrong).interference, 1);
+        assert_eq!(
+            gateway::session_health("http://127.0.0.1:19999").successes,
+            0
+        );
+    }
 }
@@ -0,0 +1,467 @@
+//! Gateway response checks and per-endpoint health tracking.
+//!
+//! Some public gateways answer a blocked or rate-limited CID with an HTML
+//! error page and a `200` status. Passing that page on as the content leads
+//! to confusing decryption or parsing errors far from the real cause, so
+//! every fetched body is checked before it is returned:
+//!
+//! 1. If the CID's hash can be recomputed from the body (raw-leaf CIDs and
+//!    single-chunk UnixFS files, sha2-256 only), the body must match it.
+//! 2. Otherwise, an HTML document is treated as gateway interference.
+//!    AgentMarket only ever stores encrypted payloads and JSON documents on
+//!    IPFS, so an HTML body is never the content that was asked for.
+//!
+//! Outcomes are recorded per endpoint. Within a session, endpoints that have
+//! interfered or mostly fail are moved to the back of the fetch order;
+//! across sessions, totals are kept in `gateway_health.json` so `doctor` can
+//! report them.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::debug;
+
+use crate::config::store;
+
+// ---------------------------------------------------------------------------
+// Constants
+// ---------------------------------------------------------------------------
+
+/// Name of the health file inside the config directory.
+const HEALTH_FILE: &str = "gateway_health.json";
+
+/// Largest file Kubo stores as a single UnixFS chunk by default. Bigger
+/// files are split into a DAG whose root hash cannot be checked from the
+/// body alone.
+const DEFAULT_CHUNK_SIZE: usize = 256 * 1024;
+
+/// Attempts required before a low success rate deprioritizes an endpoint.
+const MIN_ATTEMPTS_FOR_RATE: u64 = 3;
+
+/// Multicodec codes used in CIDv1.
+const CODEC_RAW: u64 = 0x55;
+const CODEC_DAG_PB: u64 = 0x70;
+
+/// Multihash prefix for a sha2-256 digest (code 0x12, length 32).
+const SHA2_256_PREFIX: [u8; 2] = [0x12, 0x20];
+
+// ---------------------------------------------------------------------------
+// Response checks
+// ---------------------------------------------------------------------------
+
+/// Result of comparing a body against the CID it was fetched for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CidCheck {
+    /// The body hashes to the requested CID.
+    Verified,
+    /// The body hashes to something else.
+    Mismatch,
+    /// The CID's format, hash function, or chunking cannot be checked here.
+    Unverifiable,
+}
+
+/// Why a fetched body was rejected.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Interference {
+    /// The body does not hash to the requested CID.
+    CidMismatch,
+    /// The body is an HTML page where content was expected.
+    HtmlPage,
+}
+
+impl std::fmt::Display for Interference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Interference::CidMismatch => write!(f, "content does not match the requested ID"),
+            Interference::HtmlPage => write!(f, "returned a web page instead of the content"),
+        }
+    }
+}
+
+/// Check a fetched body, returning the reason it should be rejected, if any.
+pub fn check_response(cid: &str, content_type: Option<&str>, body: &[u8]) -> Option<Interference> {
+    match verify_cid(cid, body) {
+        CidCheck::Verified => None,
+        CidCheck::Mismatch => Some(Interference::CidMismatch),
+        CidCheck::Unverifiable if looks_like_html(content_type, body) => {
+            Some(Interference::HtmlPage)
+        }
+        CidCheck::Unverifiable => None,
+    }
+}
+
+/// Heuristic: `true` if the response is an HTML document.
+pub fn looks_like_html(content_type: Option<&str>, body: &[u8]) -> bool {
+    if content_type.is_some_and(|ct| ct.trim().to_ascii_lowercase().starts_with("text/html")) {
+        return true;
+    }
+    let start = body
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(body.len());
+    let head: Vec<u8> = body[start..]
+        .iter()
+        .take(16)
+        .map(u8::to_ascii_lowercase)
+        .collect();
+    head.starts_with(b"<!doctype html") || head.starts_with(b"<html")
+}
+
+/// Recompute the hash of `body` and compare it against `cid`.
+pub fn verify_cid(cid: &str, body: &[u8]) -> CidCheck {
+    let Some((codec, digest)) = decode_cid(cid) else {
+        return CidCheck::Unverifiable;
+    };
+    let computed = match codec {
+        CODEC_RAW => Sha256::digest(body),
+        CODEC_DAG_PB if body.len() <= DEFAULT_CHUNK_SIZE => Sha256::digest(unixfs_file_node(body)),
+        _ => return CidCheck::Unverifiable,
+    };
+    if computed[..] == digest[..] {
+        CidCheck::Verified
+    } else {
+        CidCheck::Mismatch
+    }
+}
+
+/// Decode a CID into its codec and sha2-256 digest. Returns `None` for
+/// anything this module cannot verify.
+fn decode_cid(cid: &str) -> Option<(u64, Vec<u8>)> {
+    let (codec, multihash) = if cid.starts_with("Qm") {
+        (CODEC_DAG_PB, base58_decode(cid)?)
+    } else {
+        let bytes = base32_decode(cid.strip_prefix('b')?)?;
+        let (version, rest) = read_varint(&bytes)?;
+        let (codec, rest) = read_varint(rest)?;
+        if version != 1 {
+            return None;
+        }
+        (codec, rest.to_vec())
+    };
+    let digest = multihash.strip_prefix(&SHA2_256_PREFIX[..])?;
+    (digest.len() == 32).then(|| (codec, digest.to_vec()))
+}
+
+/// The dag-pb node Kubo builds for a file that fits in one chunk:
+/// `PBNode { Data: UnixFS { Type: File, Data: body, filesize } }`.
+fn unixfs_file_node(body: &[u8]) -> Vec<u8> {
+    let mut unixfs = vec![0x08, 0x02];
+    if !body.is_empty() {
+        unixfs.push(0x12);
+        write_varint(&mut unixfs, body.len() as u64);
+        unixfs.extend_from_slice(body);
+    }
+    unixfs.push(0x18);
+    write_varint(&mut unixfs, body.len() as u64);
+
+    let mut node = vec![0x0a];
+    write_varint(&mut node, unixfs.len() as u64);
+    node.extend_from_slice(&unixfs);
+    node
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn read_varint(bytes: &[u8]) -> Option<(u64, &[u8])> {
+    let mut value = 0u64;
+    for (i, byte) in bytes.iter().enumerate().take(9) {
+        value |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some((value, &bytes[i + 1..]));
+        }
+    }
+    None
+}
+
+fn base58_decode(text: &str) -> Option<Vec<u8>> {
+    const ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+    let mut out: Vec<u8> = Vec::new();
+    for c in text.bytes() {
+        let mut carry = ALPHABET.iter().position(|&a| a == c)? as u32;
+        for byte in out.iter_mut().rev() {
+            carry += u32::from(*byte) * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            out.insert(0, carry as u8);
+            carry >>= 8;
+        }
+    }
+    let zeros = text.bytes().take_while(|&c| c == b'1').count();
+    let mut decoded = vec![0u8; zeros];
+    decoded.extend(out);
+    Some(decoded)
+}
+
+fn base32_decode(text: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(text.len() * 5 / 8);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for c in text.bytes() {
+        let value = match c {
+            b'a'..=b'z' => c - b'a',
+            b'2'..=b'7' => c - b'2' + 26,
+            _ => return None,
+        };
+        buffer = (buffer << 5) | u32::from(value);
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+    }
+    Some(out)
+}
+
+// ---------------------------------------------------------------------------
+// Health tracking
+// ---------------------------------------------------------------------------
+
+/// Fetch statistics for one endpoint.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GatewayHealth {
+    /// Fetches attempted.
+    pub attempts: u64,
+    /// Fetches that returned acceptable content.
+    pub successes: u64,
+    /// Responses rejected as gateway interference.
+    pub interference: u64,
+}
+
+impl GatewayHealth {
+    /// Share of attempts that succeeded, or `None` before the first attempt.
+    pub fn success_rate(&self) -> Option<f64> {
+        (self.attempts > 0).then(|| self.successes as f64 / self.attempts as f64)
+    }
+
+    /// `true` if this endpoint should be tried after the others.
+    pub fn is_degraded(&self) -> bool {
+        self.interference > 0
+            || (self.attempts >= MIN_ATTEMPTS_FOR_RATE && self.successes * 2 < self.attempts)
+    }
+}
+
+/// Outcome of a single fetch attempt.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FetchOutcome {
+    Success,
+    Failure,
+    Interference,
+}
+
+/// Health of every endpoint used in this process.
+static SESSION: Mutex<BTreeMap<String, GatewayHealth>> = Mutex::new(BTreeMap::new());
+
+/// Record the outcome of a fetch from `endpoint` for this session and, if
+/// `health_file` is given, in the persisted totals.
+pub fn record(endpoint: &str, outcome: FetchOutcome, health_file: Option<&Path>) {
+    let mut session = SESSION.lock().unwrap_or_else(|e| e.into_inner());
+    apply(session.entry(endpoint.to_string()).or_default(), outcome);
+    drop(session);
+
+    if let Some(path) = health_file {
+        let result = load_from(path).and_then(|mut totals| {
+            apply(totals.entry(endpoint.to_string()).or_default(), outcome);
+            save_to(path, &totals)
+        });
+        if let Err(err) = result {
+            debug!(error = %err, "failed to persist gateway health (non-fatal)");
+        }
+    }
+}
+
+fn apply(health: &mut GatewayHealth, outcome: FetchOutcome) {
+    health.attempts += 1;
+    match outcome {
+        FetchOutcome::Success => health.successes += 1,
+        FetchOutcome::Failure => {}
+        FetchOutcome::Interference => health.interference += 1,
+    }
+}
+
+/// Health of `endpoint` in this session.
+pub fn session_health(endpoint: &str) -> GatewayHealth {
+    SESSION
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(endpoint)
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Order endpoints for fetching: healthy ones first, degraded ones last,
+/// otherwise keeping the configured order.
+pub fn rank(endpoints: &[String]) -> Vec<String> {
+    let mut ranked = endpoints.to_vec();
+    ranked.sort_by_key(|endpoint| session_health(endpoint).is_degraded());
+    ranked
+}
+
+/// Path of the persisted health file.
+pub fn health_file() -> Result<PathBuf> {
+    Ok(store::config_dir()?.join(HEALTH_FILE))
+}
+
+/// Persisted health totals, keyed by endpoint. Empty if nothing has been
+/// recorded yet.
+pub fn load() -> Result<BTreeMap<String, GatewayHealth>> {
+    load_from(&health_file()?)
+}
+
+fn load_from(path: &Path) -> Result<BTreeMap<String, GatewayHealth>> {
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let data =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    serde_json::from_str(&data).with_context(|| format!("failed to parse {}", path.display()))
+}
+
+fn save_to(path: &Path, totals: &BTreeMap<String, GatewayHealth>) -> Result<()> {
+    let data =
+        serde_json::to_string_pretty(totals).context("failed to serialize gateway health")?;
+    fs::write(path, data).with_context(|| format!("failed to write {}", path.display()))
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_cid_known_vectors() {
+        // `echo "hello world" | ipfs add` (CIDv0, dag-pb).
+        assert_eq!(
+            verify_cid(
+                "QmT78zSuBmuS4z925WZfrqQ1qHaJ56DQaTfyMUF7F8ff5o",
+                b"hello world\n"
+            ),
+            CidCheck::Verified
+        );
+        // `ipfs add --cid-version 1 --raw-leaves` of "hello world" (raw).
+        assert_eq!(
+            verify_cid(
+                "bafkreifzjut3te2nhyekklss27nh3k72ysco7y32koao5eei66wof36n5e",
+                b"hello world"
+            ),
+            CidCheck::Verified
+        );
+        // The empty file.
+        assert_eq!(
+            verify_cid("QmbFMke1KXqnYyBBWxB74N4c5SBnJMVAiMNRcGu6x1AwQH", b""),
+            CidCheck::Verified
+        );
+    }
+
+    #[test]
+    fn verify_cid_rejects_other_content() {
+        assert_eq!(
+            verify_cid(
+                "QmT78zSuBmuS4z925WZfrqQ1qHaJ56DQaTfyMUF7F8ff5o",
+                b"<html>blocked</html>"
+            ),
+            CidCheck::Mismatch
+        );
+        assert_eq!(verify_cid("not-a-cid", b"x"), CidCheck::Unverifiable);
+        let large = vec![0u8; DEFAULT_CHUNK_SIZE + 1];
+        assert_eq!(
+            verify_cid("QmT78zSuBmuS4z925WZfrqQ1qHaJ56DQaTfyMUF7F8ff5o", &large),
+            CidCheck::Unverifiable
+        );
+    }
+
+    #[test]
+    fn html_heuristic() {
+        let cases: &[(Option<&str>, &[u8], bool)] = &[
+            (Some("text/html; charset=utf-8"), b"anything", true),
+            (None, b"  \n<!DOCTYPE html><title>Blocked</title>", true),
+            (Some("text/plain"), b"<HTML><body>451</body></HTML>", true),
+            (
+                Some("application/octet-stream"),
+                &[0x04, 0x9a, 0x3c, 0x68],
+                false,
+            ),
+            (Some("application/json"), br#"{"name": "<html>"}"#, false),
+            (None, b"", false),
+        ];
+        for (content_type, body, expected) in cases {
+            assert_eq!(
+                looks_like_html(*content_type, body),
+                *expected,
+                "{content_type:?} {:?}",
+                String::from_utf8_lossy(body)
+            );
+        }
+    }
+
+    #[test]
+    fn verified_html_is_accepted() {
+        // A CID that really is an HTML document is not interference.
+        let body = b"<html></html>";
+        let digest = Sha256::digest(body);
+        let mut bytes = vec![0x01, CODEC_RAW as u8, 0x12, 0x20];
+        bytes.extend_from_slice(&digest);
+        let cid = format!("b{}", base32_encode(&bytes));
+        assert_eq!(check_response(&cid, Some("text/html"), body), None);
+        assert_eq!(
+            check_response("unverifiable", Some("text/html"), body),
+            Some(Interference::HtmlPage)
+        );
+    }
+
+    #[test]
+    fn health_ranking_and_persistence() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(HEALTH_FILE);
+        let good = "http://health-test-good".to_string();
+        let bad = "http://health-test-bad".to_string();
+
+        record(&bad, FetchOutcome::Interference, Some(&path));
+        record(&good, FetchOutcome::Success, Some(&path));
+        record(&good, FetchOutcome::Success, Some(&path));
+
+        assert_eq!(
+            rank(&[bad.clone(), good.clone()]),
+            vec![good.clone(), bad.clone()]
+        );
+        assert!(session_health(&bad).is_degraded());
+        assert_eq!(session_health(&good).success_rate(), Some(1.0));
+
+        let totals = load_from(&path).unwrap();
+        assert_eq!(totals[&bad].interference, 1);
+        assert_eq!(totals[&good].successes, 2);
+    }
+
+    fn base32_encode(bytes: &[u8]) -> String {
+        const ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyz234567";
+        let mut out = String::new();
+        let (mut buffer, mut bits) = (0u32, 0);
+        for &byte in bytes {
+            buffer = (buffer << 8) | u32::from(byte);
+            bits += 8;
+            while bits >= 5 {
+                bits -= 5;
+                out.push(ALPHABET[((buffer >> bits) & 31) as usize] as char);
+            }
+        }
+        if bits > 0 {
+            out.push(ALPHABET[((buffer << (5 - bits)) & 31) as usize] as char);
+        }
+        out
+    }
+}
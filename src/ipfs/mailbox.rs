@@ -207,6 +207,64 @@ pub async fn retrieve_message(
     Ok(message)
 }
 
+// ---------------------------------------------------------------------------
+// Transport
+// ---------------------------------------------------------------------------
+
+/// Announce the message stored at `cid` on the recipient's mailbox topic.
+pub async fn announce(ipfs: &IpfsClient, recipient_public_key_hex: &str, cid: &str) -> Result<()> {
+    let mailbox = Mailbox::new(recipient_public_key_hex)?;
+    ipfs.pubsub_publish(mailbox.topic(), cid.as_bytes())
+        .await
+        .context("failed to announce message on mailbox topic")?;
+    debug!(topic = %mailbox.topic(), cid = %cid, "message announced");
+    Ok(())
+}
+
+/// Publish `message` for the recipient and announce it on their mailbox
+/// topic. Returns the message CID.
+pub async fn send_message(
+    ipfs: &IpfsClient,
+    recipient_public_key_hex: &str,
+    message: &MailboxMessage,
+) -> Result<String> {
+    let cid = publish_message(ipfs, recipient_public_key_hex, message).await?;
+    announce(ipfs, recipient_public_key_hex, &cid).await?;
+    Ok(cid)
+}
+
+/// Collect the message CIDs announced on `mailbox`'s pubsub topic during
+/// the next `wait`, without duplicates and in arrival order.
+///
+/// Senders publish a sealed message to IPFS and then announce its CID on
+/// the recipient's topic. Pubsub does not store messages, so only
+/// announcements made while listening are seen.
+pub async fn fetch_announced(
+    ipfs: &IpfsClient,
+    mailbox: &Mailbox,
+    wait: std::time::Duration,
+) -> Result<Vec<String>> {
+    let announcements = ipfs
+        .pubsub_collect(mailbox.topic(), wait)
+        .await
+        .context("failed to listen on mailbox topic")?;
+
+    let mut cids: Vec<String> = Vec::new();
+    for data in announcements {
+        match String::from_utf8(data) {
+            Ok(cid) if !cid.trim().is_empty() => {
+                let cid = cid.trim().to_string();
+                if !cids.contains(&cid) {
+                    cids.push(cid);
+                }
+            }
+            _ => debug!("ignoring malformed mailbox announcement"),
+        }
+    }
+    debug!(topic = %mailbox.topic(), count = cids.len(), "mailbox announcements collected");
+    Ok(cids)
+}
+
 // ---------------------------------------------------------------------------
 // Tests
 // ---------------------------------------------------------------------------
@@ -1,4 +1,5 @@
 pub mod client;
 pub mod encryption;
+pub mod gateway;
 pub mod mailbox;
 pub mod pin;
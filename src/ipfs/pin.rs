@@ -261,6 +261,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[allow(clippy::single_match)]
     async fn test_authentication_returns_false_for_invalid_key() {
         // Use a dummy key against the real Pinata endpoint. The API should
         // return a non-success status (401), which we interpret as `false`.
@@ -4,6 +4,7 @@
 //! can access the same code that `main.rs` uses.
 
 pub mod chain;
+pub mod clock;
 pub mod commands;
 pub mod config;
 pub mod engine;
@@ -1,8 +1,19 @@
+use agentmarket::chain::client;
+use agentmarket::chain::contracts::Network;
+use agentmarket::chain::fees;
 use agentmarket::commands;
-use agentmarket::output::formatter;
+use agentmarket::config::{keystore, store};
+use agentmarket::engine::requests::RequestCache;
+use agentmarket::output::{formatter, prompt};
 
 use clap::{Parser, Subcommand};
-use tracing_subscriber::{fmt, EnvFilter};
+use std::path::{Path, PathBuf};
+
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, EnvFilter, Layer};
 
 #[derive(Parser)]
 #[command(name = "agentmarket")]
@@ -13,6 +24,39 @@ struct Cli {
     #[arg(long, global = true)]
     json: bool,
 
+    /// Make this machine the owner of an agent home shared with another machine
+    #[arg(long, global = true)]
+    takeover: bool,
+
+    /// Read the keystore passphrase from this file instead of prompting.
+    /// AGENTMARKET_PASSPHRASE takes precedence over it
+    #[arg(long, global = true, value_name = "PATH")]
+    passphrase_file: Option<std::path::PathBuf>,
+
+    /// Use this agent home instead of AGENTMARKET_HOME or ~/.agentmarket
+    #[arg(long, global = true, value_name = "PATH")]
+    home: Option<std::path::PathBuf>,
+
+    /// Answer yes to confirmations, including before committing or moving
+    /// funds. Required when not running in a terminal
+    #[arg(short, long, global = true)]
+    yes: bool,
+
+    /// Fail instead of prompting when an answer was not given by flag or
+    /// environment variable. Also set by AGENTMARKET_NONINTERACTIVE=1
+    #[arg(long, global = true)]
+    non_interactive: bool,
+
+    /// Connect even if the network reports a chain ID other than
+    /// network.expected_chain_id (for development against a local node)
+    #[arg(long, global = true)]
+    allow_chain_mismatch: bool,
+
+    /// Abort instead of warning when a transaction's network fee cannot be
+    /// estimated before it is sent
+    #[arg(long, global = true)]
+    strict_fees: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -30,14 +74,52 @@ enum Commands {
         /// Capabilities, comma-separated (skip interactive prompt)
         #[arg(long)]
         capabilities: Option<String>,
-        /// Price per task in USD (skip interactive prompt)
+        /// Price per task in USD, e.g. 5.00 (skip interactive prompt)
         #[arg(long)]
-        price: Option<f64>,
+        price: Option<String>,
+        /// Use the hex private key in this file instead of generating one
+        #[arg(long, value_name = "PATH")]
+        import_key_file: Option<std::path::PathBuf>,
+        /// Derive the key from a new 24-word recovery phrase, shown once
+        #[arg(long, conflicts_with = "import_key_file")]
+        mnemonic: bool,
+        /// Replace an existing identity, backing it up first
+        #[arg(long)]
+        force: bool,
+        /// With --force, also replace a registered identity, abandoning its
+        /// registration
+        #[arg(long, requires = "force")]
+        abandon_registration: bool,
+        /// Also show the funding address as a QR code
+        #[arg(long)]
+        qr: bool,
+        /// Network to join: base-mainnet (default), base-sepolia to try
+        /// everything with test funds, or local
+        #[arg(long, value_name = "NAME", default_value = "base-mainnet")]
+        network: Network,
     },
     /// Check agent balance and add funds
-    Fund,
+    Fund {
+        /// Keep checking until the agent has enough funds to register
+        #[arg(long)]
+        watch: bool,
+        /// With --watch, seconds between checks
+        #[arg(long, default_value = "10", requires = "watch")]
+        interval: u64,
+        /// With --watch, give up after this many seconds
+        #[arg(long, requires = "watch")]
+        timeout: Option<u64>,
+        /// Also show the address as a QR code
+        #[arg(long)]
+        qr: bool,
+    },
     /// Register agent on the network
-    Register,
+    Register {
+        /// Show the profile, fee estimate, and balance check without
+        /// uploading or sending anything; fails if the balance is too low
+        #[arg(long)]
+        dry_run: bool,
+    },
     /// Discover agents and open requests
     Search {
         /// Filter by capability
@@ -46,24 +128,62 @@ enum Commands {
         /// Search for open requests instead of agents
         #[arg(short, long)]
         requests: bool,
+        /// Sort agents by price, reputation, or name
+        #[arg(long, default_value = "reputation", conflicts_with = "requests")]
+        sort: String,
+        /// Show at most this many results
+        #[arg(short = 'n', long)]
+        limit: Option<usize>,
+        /// Only agents with at least this reputation score (0-100)
+        #[arg(long, conflicts_with = "requests")]
+        min_reputation: Option<f64>,
+        /// Only agents charging at most this per task, in USD
+        #[arg(long, conflicts_with = "requests")]
+        max_price: Option<String>,
+        /// Only requests paying at least this, in USD
+        #[arg(long, requires = "requests")]
+        min_price: Option<String>,
+        /// Only requests due within this many hours
+        #[arg(long, value_name = "HOURS", requires = "requests")]
+        deadline_within: Option<u64>,
+        /// Only requests matching this agent's capabilities and price
+        #[arg(long, requires = "requests")]
+        mine: bool,
+    },
+    /// Show another agent's reputation, earnings, and profile before hiring it
+    Reputation {
+        /// Agent ID (as shown by `search`) or address
+        agent: String,
     },
     /// Create a service request for another agent
     Request {
-        /// Task description
+        /// Task description; may use {{date}}, {{date:%Y-%m-%d}} (escape braces as \{)
         #[arg(short, long)]
         task: String,
-        /// Price in USD (e.g., 5.00)
+        /// Price in USD (e.g., 5.00; at most 6 decimal places)
         #[arg(short, long)]
-        price: f64,
-        /// Deadline in hours from now
+        price: String,
+        /// Deadline: hours from now (24), a duration (36h, 2d12h), or an RFC 3339 timestamp
         #[arg(short, long, default_value = "24")]
-        deadline: u64,
+        deadline: String,
         /// Target agent ID (optional, 0 for open request)
         #[arg(long, default_value = "0")]
         to: u64,
-        /// Path to a file to attach (optional)
-        #[arg(short, long)]
-        file: Option<String>,
+        /// Path to a file to attach (repeatable)
+        #[arg(short, long = "file")]
+        files: Vec<String>,
+        /// Directory to attach as a .tar.gz (skips .git, target, node_modules)
+        #[arg(long)]
+        dir: Option<String>,
+        /// Label for organising your requests (repeatable or comma-separated)
+        #[arg(long = "tag", value_delimiter = ',')]
+        tags: Vec<String>,
+        /// Capability the task needs (e.g., code-review), for sellers and validators to filter on
+        #[arg(long)]
+        capability: Option<String>,
+        /// Build, encrypt, and price the request without publishing it
+        #[arg(long)]
+        dry_run: bool,
     },
     /// Submit a response to a request
     Respond {
@@ -73,9 +193,29 @@ enum Commands {
         /// Path to the deliverable file
         #[arg(short, long)]
         file: Option<String>,
+        /// Directory to deliver as a .tar.gz (skips .git, target, node_modules)
+        #[arg(long, conflicts_with = "file")]
+        dir: Option<String>,
+        /// Read the deliverable from standard input (set AGENTMARKET_PASSPHRASE,
+        /// since stdin cannot also answer the passphrase prompt)
+        #[arg(long, conflicts_with_all = ["file", "dir"])]
+        stdin: bool,
         /// Response message
         #[arg(short, long)]
         message: Option<String>,
+        /// Label for organising your requests (repeatable or comma-separated)
+        #[arg(long = "tag", value_delimiter = ',')]
+        tags: Vec<String>,
+        /// Respond even if the buyer's payment is not secured
+        #[arg(long)]
+        accept_unsecured: bool,
+        /// Use a random claim secret instead of one derived from your key
+        /// (it cannot be recovered if local data is lost)
+        #[arg(long)]
+        random_secret: bool,
+        /// Attach a corrected deliverable to a response not yet validated
+        #[arg(long)]
+        replace: bool,
     },
     /// Enter the validation loop to review and earn
     Validate {
@@ -88,61 +228,636 @@ enum Commands {
         /// Run continuously (poll for pending validations)
         #[arg(long)]
         auto: bool,
-        /// Filter by capability
+        /// Only validate requests whose capability matches (e.g., code-review or code-*)
         #[arg(long)]
         filter: Option<String>,
+        /// With --filter, also skip requests that declare no capability
+        #[arg(long, requires = "filter")]
+        strict_filter: bool,
+        /// Validate even when the expected fee is below the configured margin
+        #[arg(long)]
+        accept_unprofitable: bool,
+        /// Validate only this request and exit: 0 if it passes, 2 if it fails
+        #[arg(long, conflicts_with = "auto")]
+        request_id: Option<String>,
+        /// Review requests again even if they already have a saved result
+        #[arg(long)]
+        revalidate: bool,
+        /// List the responses waiting for review and exit without running a handler
+        #[arg(long, conflicts_with_all = ["auto", "request_id"])]
+        list: bool,
     },
     /// Claim payment for completed work
     Claim {
         /// Request ID to claim payment for
+        #[arg(
+            short = 'i',
+            long,
+            required_unless_present = "all",
+            conflicts_with = "all"
+        )]
+        request_id: Option<String>,
+        /// Claim every validated request you responded to, continuing past failures
+        #[arg(long)]
+        all: bool,
+    },
+    /// Cancel an open request you created
+    Cancel {
+        /// Request ID to cancel
         #[arg(short = 'i', long)]
         request_id: String,
     },
+    /// Close overdue requests so committed funds are released
+    Expire {
+        /// Request ID to expire
+        #[arg(
+            short = 'i',
+            long,
+            required_unless_present = "all",
+            conflicts_with = "all"
+        )]
+        request_id: Option<String>,
+        /// Expire every overdue request, continuing past failures
+        #[arg(long)]
+        all: bool,
+        /// Only update local state; do not submit anything to the network
+        #[arg(long)]
+        local_only: bool,
+    },
+    /// List locally tracked requests
+    List {
+        /// Only show requests in this status (e.g. open, validated)
+        #[arg(long)]
+        status: Option<String>,
+        /// Only show requests where this agent is buyer, seller, or validator
+        #[arg(long)]
+        role: Option<String>,
+        /// Only show requests with this tag
+        #[arg(long)]
+        tag: Option<String>,
+        /// Show at most this many requests
+        #[arg(short = 'n', long)]
+        limit: Option<usize>,
+        /// Sort order: created, updated, deadline, or price
+        #[arg(long, default_value = "created")]
+        sort: String,
+    },
+    /// Show everything known about one request
+    Show {
+        /// Request ID
+        request_id: String,
+        /// Include the seller's secret in the output
+        #[arg(long)]
+        reveal_secret: bool,
+    },
     /// View agent status, earnings, and reputation
-    Status,
+    Status {
+        /// Also list what needs attention and recently finished requests
+        #[arg(short, long)]
+        verbose: bool,
+    },
+    /// Print this agent's name, address, and registration state
+    Whoami {
+        /// Skip the passphrase prompt; the address is shown only if cached
+        #[arg(long)]
+        no_unlock: bool,
+    },
+    /// Sign a message with the agent's key (EIP-191 personal_sign)
+    Sign {
+        /// Text to sign
+        #[arg(short, long, required_unless_present = "file", conflicts_with = "file")]
+        message: Option<String>,
+        /// File whose bytes to sign
+        #[arg(short, long)]
+        file: Option<String>,
+    },
+    /// Check that a message was signed by an address
+    Verify {
+        /// Address that should have signed (0x-prefixed)
+        #[arg(short = 'a', long)]
+        address: String,
+        /// Signature printed by `sign` or a wallet's personal_sign
+        #[arg(short, long)]
+        signature: String,
+        /// Text that was signed
+        #[arg(short, long, required_unless_present = "file", conflicts_with = "file")]
+        message: Option<String>,
+        /// File whose bytes were signed
+        #[arg(short, long)]
+        file: Option<String>,
+    },
+    /// Show completed work and earnings over time
+    History {
+        /// Group by day, week, or month
+        #[arg(long, default_value = "week")]
+        period: String,
+    },
     /// Transfer earnings to another address
     Withdraw {
-        /// Destination address (0x-prefixed)
+        /// Destination address (0x-prefixed, EIP-55 checksummed)
         #[arg(short = 'a', long)]
         address: String,
-        /// Amount in USD to withdraw (withdraws all if not specified)
+        /// Amount to withdraw: dollars for usdc, ETH for eth (withdraws all if not specified)
         #[arg(long)]
-        amount: Option<f64>,
+        amount: Option<String>,
+        /// What to withdraw: usdc (earnings) or eth (the network fee balance)
+        #[arg(long, default_value = "usdc")]
+        asset: String,
+        /// Accept an all-lowercase or all-uppercase address without a checksum
+        #[arg(long)]
+        no_checksum: bool,
     },
     /// Run validate + auto-claim as a continuous loop
     Daemon {
+        #[command(subcommand)]
+        action: Option<DaemonAction>,
         /// Poll interval in seconds
-        #[arg(long, default_value = "60")]
+        #[arg(long, default_value = "60", global = true)]
         interval: u64,
         /// Handler type for validation
-        #[arg(long, default_value = "manual")]
+        #[arg(long, default_value = "manual", global = true)]
         handler: String,
         /// Path to external handler executable
-        #[arg(long)]
+        #[arg(long, global = true)]
         handler_path: Option<String>,
+        /// Report whether a daemon is running, and for how long
+        #[arg(long, conflicts_with = "stop")]
+        status: bool,
+        /// Ask the running daemon to finish its current request and stop
+        #[arg(long)]
+        stop: bool,
+        /// Send a ping event to notifications.webhook_url and report the result
+        #[arg(long, conflicts_with_all = ["status", "stop"])]
+        test_webhook: bool,
+        /// Serve /healthz and /status on this port (with --status, read the snapshot from it)
+        #[arg(long, global = true)]
+        health_port: Option<u16>,
+        /// Address the health listener binds to
+        #[arg(long, default_value = "127.0.0.1", global = true)]
+        health_host: String,
+        /// Most validation handlers to run at once (the manual handler runs one at a time)
+        #[arg(long, default_value_t = 2, global = true)]
+        max_concurrent: usize,
+        /// Also write logs to this file, rotated daily (overrides logging.file)
+        #[arg(long, value_name = "PATH", global = true)]
+        log_file: Option<PathBuf>,
+    },
+    /// List daemon and request locks, or break a stuck one
+    Locks {
+        /// Remove the named lock after confirmation (e.g. `daemon`, `request-42`)
+        #[arg(long = "break", value_name = "LOCK")]
+        break_lock: Option<String>,
+    },
+    /// Read messages other agents sent to this agent's mailbox
+    Messages {
+        /// Show one message in full (full ID or the short ID from the list)
+        #[arg(long)]
+        id: Option<String>,
+        /// List only messages not yet read
+        #[arg(long)]
+        unread: bool,
+        /// Seconds to listen for newly announced messages (0 skips the check)
+        #[arg(long, default_value_t = 3)]
+        wait: u64,
+    },
+    /// Send an encrypted message to another agent
+    Send {
+        /// Recipient agent ID or compressed public key
+        to: String,
+        /// Message text
+        #[arg(required_unless_present_any = ["file", "quote"], conflicts_with = "file")]
+        message: Option<String>,
+        /// Send the contents of a file instead (up to 64 KiB)
+        #[arg(long)]
+        file: Option<String>,
+        /// Message type, e.g. note or quote
+        #[arg(long = "type", default_value = "note")]
+        r#type: String,
+        /// Send a signed price quote for this request ID instead of a message
+        #[arg(
+            long,
+            value_name = "REQUEST_ID",
+            requires = "price",
+            conflicts_with_all = ["message", "file", "type"]
+        )]
+        quote: Option<String>,
+        /// Quoted price in USD (with --quote)
+        #[arg(long, requires = "quote")]
+        price: Option<String>,
+        /// How long the quote stands: hours (1), a duration (30m), or an RFC 3339 timestamp
+        #[arg(long, requires = "quote")]
+        deadline: Option<String>,
+    },
+    /// Check config, keystore, network, and storage for common problems
+    Doctor {
+        /// Also ask for the passphrase and check the keystore decrypts
+        #[arg(long)]
+        unlock: bool,
+    },
+    /// Collect a sanitized diagnostic archive for bug reports
+    Bugreport {
+        /// Include this request's cached state and validation result
+        #[arg(short = 'i', long)]
+        request_id: Option<String>,
+        /// Include every cached request and longer log excerpts
+        #[arg(long)]
+        verbose: bool,
+        /// Archive path (default: ./agentmarket-bugreport-<timestamp>.tar.gz)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Manage standing approvals for sellers you work with regularly
+    Trust {
+        #[command(subcommand)]
+        action: TrustAction,
+    },
+    /// Back up or restore the agent's private key
+    Key {
+        #[command(subcommand)]
+        action: KeyAction,
+    },
+    /// Change the agent's published profile
+    Profile {
+        #[command(subcommand)]
+        action: ProfileAction,
+    },
+    /// Read or change settings in config.toml
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Run housekeeping tasks (expiry sweep, cache upgrades, stale locks)
+    Maintenance {
+        #[command(subcommand)]
+        action: MaintenanceAction,
+    },
+    /// Show the transactions this agent has sent
+    Tx {
+        #[command(subcommand)]
+        action: TxAction,
     },
 }
 
-#[tokio::main]
-async fn main() {
-    let filter =
-        EnvFilter::try_from_env("AGENTMARKET_LOG_LEVEL").unwrap_or_else(|_| EnvFilter::new("warn"));
+#[derive(Subcommand)]
+enum KeyAction {
+    /// Write the private key to an encrypted backup file
+    Export {
+        /// Backup file to create
+        #[arg(short, long)]
+        output: String,
+    },
+    /// Replace the keystore with a key from a backup or a hex private key
+    Import {
+        /// Backup file written by `key export`
+        #[arg(required_unless_present = "hex", conflicts_with = "hex")]
+        file: Option<String>,
+        /// Read a raw 32-byte hex private key from stdin instead
+        #[arg(long)]
+        hex: bool,
+        /// Replace an existing keystore
+        #[arg(long)]
+        force: bool,
+    },
+    /// Rebuild the keystore from a BIP-39 recovery phrase read from stdin
+    Recover {
+        /// Replace an existing keystore
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ProfileAction {
+    /// Update description, capabilities, or price and republish the profile
+    Update {
+        /// New agent description
+        #[arg(long)]
+        description: Option<String>,
+        /// New capabilities, comma-separated (replaces the current list)
+        #[arg(long)]
+        capabilities: Option<String>,
+        /// New price per task in USD, e.g. 5.00
+        #[arg(long)]
+        price: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Print a setting, or every setting when no key is given
+    Get {
+        /// Dotted key, e.g. services.pricing_usd
+        key: Option<String>,
+    },
+    /// Change a setting
+    Set {
+        /// Dotted key, e.g. services.pricing_usd
+        key: String,
+        /// New value; lists are comma-separated
+        value: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum DaemonAction {
+    /// Write a systemd user unit that runs the daemon with the given flags
+    Install {
+        /// Remove the unit instead
+        #[arg(long)]
+        uninstall: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum MaintenanceAction {
+    /// Run some or all housekeeping tasks once
+    Run {
+        /// Tasks to run (comma-separated), e.g. sweep,locks
+        #[arg(long, value_delimiter = ',')]
+        tasks: Vec<String>,
+        /// Run every task
+        #[arg(long)]
+        all: bool,
+        /// Show what would be done without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// List tasks and how often the daemon runs them
+    List,
+}
+
+#[derive(Subcommand)]
+enum TxAction {
+    /// List sent transactions and how each ended, oldest first
+    List {
+        /// Only transactions for this request
+        #[arg(short = 'i', long)]
+        request_id: Option<String>,
+        /// Only transactions not yet confirmed or reverted
+        #[arg(long)]
+        pending: bool,
+    },
+    /// Replace a stuck transaction with one paying a higher fee
+    Bump {
+        /// Hash of the pending transaction
+        hash: String,
+        /// Send nothing to this agent instead, so the transaction never runs
+        #[arg(long)]
+        cancel: bool,
+        /// Raise the fee by this many percent (at least 10)
+        #[arg(long, value_name = "PERCENT", default_value_t = fees::MIN_FEE_BUMP_PERCENT)]
+        percent: u64,
+    },
+}
+
+#[derive(Subcommand)]
+enum TrustAction {
+    /// Pre-authorize a seller up to a per-request and monthly cap
+    Add {
+        /// Seller agent ID (e.g. 42), address, or @alias of an existing approval
+        seller: String,
+        /// Largest request price covered, in USD
+        #[arg(long)]
+        per_request: String,
+        /// Total covered per calendar month, in USD
+        #[arg(long)]
+        monthly: String,
+        /// Only cover requests for these capabilities (comma-separated;
+        /// requests without a capability are matched on their tags)
+        #[arg(long, value_delimiter = ',')]
+        capabilities: Vec<String>,
+        /// Short name to refer to the seller by, as @NAME
+        #[arg(long, value_name = "NAME")]
+        alias: Option<String>,
+    },
+    /// Show standing approvals and this month's use
+    List,
+    /// Remove a seller's standing approval
+    Remove {
+        /// Seller agent ID, address, or @alias
+        seller: String,
+    },
+}
 
-    fmt::Subscriber::builder()
-        .with_env_filter(filter)
+impl Commands {
+    /// Short command name, as typed on the command line.
+    fn name(&self) -> &'static str {
+        match self {
+            Commands::Init { .. } => "init",
+            Commands::Fund { .. } => "fund",
+            Commands::Register { .. } => "register",
+            Commands::Search { .. } => "search",
+            Commands::Reputation { .. } => "reputation",
+            Commands::Request { .. } => "request",
+            Commands::Respond { .. } => "respond",
+            Commands::Validate { .. } => "validate",
+            Commands::Claim { .. } => "claim",
+            Commands::Cancel { .. } => "cancel",
+            Commands::Expire { .. } => "expire",
+            Commands::List { .. } => "list",
+            Commands::Show { .. } => "show",
+            Commands::Status { .. } => "status",
+            Commands::Whoami { .. } => "whoami",
+            Commands::Sign { .. } => "sign",
+            Commands::Verify { .. } => "verify",
+            Commands::History { .. } => "history",
+            Commands::Withdraw { .. } => "withdraw",
+            Commands::Daemon { .. } => "daemon",
+            Commands::Locks { .. } => "locks",
+            Commands::Messages { .. } => "messages",
+            Commands::Send { .. } => "send",
+            Commands::Doctor { .. } => "doctor",
+            Commands::Bugreport { .. } => "bugreport",
+            Commands::Trust { .. } => "trust",
+            Commands::Key { .. } => "key",
+            Commands::Profile { .. } => "profile",
+            Commands::Config { .. } => "config",
+            Commands::Maintenance { .. } => "maintenance",
+            Commands::Tx { .. } => "tx",
+        }
+    }
+
+    /// Log file the command should write to besides the console: only a
+    /// running daemon does, from `--log-file` or `logging.file`. Returns the
+    /// path and how many rotated files to keep.
+    fn log_file(&self) -> Option<(PathBuf, usize)> {
+        let Commands::Daemon {
+            action: None,
+            log_file,
+            status: false,
+            stop: false,
+            test_webhook: false,
+            ..
+        } = self
+        else {
+            return None;
+        };
+        let logging = match store::exists() {
+            Ok(true) => store::load().map(|cfg| cfg.logging).unwrap_or_default(),
+            _ => Default::default(),
+        };
+        let path = log_file
+            .clone()
+            .or_else(|| (!logging.file.is_empty()).then(|| PathBuf::from(&logging.file)))?;
+        Some((path, logging.keep_files))
+    }
+
+    /// Whether the command writes to the agent home (config, keystore, or
+    /// request cache) and must therefore pass the shared-home guard.
+    fn is_mutating(&self) -> bool {
+        !matches!(
+            self,
+            Commands::Fund { .. }
+                | Commands::Search { .. }
+                | Commands::Reputation { .. }
+                | Commands::List { .. }
+                | Commands::Show { .. }
+                | Commands::Status { .. }
+                | Commands::Whoami { .. }
+                | Commands::Sign { .. }
+                | Commands::Verify { .. }
+                | Commands::History { .. }
+                | Commands::Register { dry_run: true }
+                | Commands::Request { dry_run: true, .. }
+                | Commands::Locks { break_lock: None }
+                | Commands::Daemon {
+                    action: Some(_),
+                    ..
+                }
+                | Commands::Daemon { status: true, .. }
+                | Commands::Daemon { stop: true, .. }
+                | Commands::Daemon {
+                    test_webhook: true,
+                    ..
+                }
+                | Commands::Send { .. }
+                | Commands::Doctor { .. }
+                | Commands::Bugreport { .. }
+                | Commands::Key {
+                    action: KeyAction::Export { .. }
+                }
+                | Commands::Trust {
+                    action: TrustAction::List
+                }
+                | Commands::Config {
+                    action: ConfigAction::Get { .. }
+                }
+                | Commands::Maintenance {
+                    action: MaintenanceAction::List | MaintenanceAction::Run { dry_run: true, .. }
+                }
+        )
+    }
+}
+
+/// Open `path` for logging, rotated daily to `<path>.YYYY-MM-DD` and
+/// keeping the newest `keep_files` files.
+fn file_appender(path: &Path, keep_files: usize) -> anyhow::Result<RollingFileAppender> {
+    let prefix = path
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("log file path has no file name: {}", path.display()))?;
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(prefix.to_string_lossy())
+        .max_log_files(keep_files.max(1))
+        .build(dir)
+        .map_err(|err| anyhow::anyhow!("cannot write logs to {}: {err}", path.display()))
+}
+
+fn log_filter(default: &str) -> EnvFilter {
+    EnvFilter::try_from_env("AGENTMARKET_LOG_LEVEL").unwrap_or_else(|_| EnvFilter::new(default))
+}
+
+/// Install the tracing subscriber: the console always, plus a rotating log
+/// file when `command` asks for one. The returned guard flushes the file
+/// on drop, so it must live until the process exits.
+fn init_tracing(command: &Commands) -> Option<WorkerGuard> {
+    let console = fmt::layer()
         .compact()
         .with_timer(fmt::time::SystemTime)
-        .init();
+        .with_filter(log_filter("warn"));
 
+    let (path, keep_files) = match command.log_file() {
+        Some(log_file) => log_file,
+        None => {
+            tracing_subscriber::registry().with(console).init();
+            return None;
+        }
+    };
+    match file_appender(&path, keep_files) {
+        Ok(appender) => {
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            let file = fmt::layer()
+                .with_ansi(false)
+                .with_timer(fmt::time::SystemTime)
+                .with_writer(writer)
+                .with_filter(log_filter("info"));
+            tracing_subscriber::registry()
+                .with(console)
+                .with(file)
+                .init();
+            Some(guard)
+        }
+        Err(err) => {
+            tracing_subscriber::registry().with(console).init();
+            formatter::print_warning(&format!("{err:#}. Logging to the console only."));
+            None
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() {
     let cli = Cli::parse();
+    // Before anything reads the agent home, including the log file setting.
+    store::set_home(cli.home.clone());
+    let _log_guard = init_tracing(&cli.command);
 
     formatter::set_json_mode(cli.json);
+    keystore::set_passphrase_file(cli.passphrase_file.clone());
+    prompt::set_assume_yes(cli.yes);
+    prompt::set_non_interactive(cli.non_interactive);
+    client::set_allow_chain_mismatch(cli.allow_chain_mismatch);
+    commands::set_strict_fees(cli.strict_fees);
+
+    // Upgrade request files written by older CLI versions before any command
+    // touches the cache. Skipped until `init` has created the home directory.
+    if matches!(store::exists(), Ok(true)) {
+        // Badge output on a test network, so test funds are never taken for
+        // real ones. A config that cannot be read is reported by the command.
+        if let Ok(network) = store::load().and_then(|cfg| Network::for_config(&cfg.network)) {
+            formatter::set_testnet(network.is_testnet().then(|| network.name()));
+        }
+
+        if let Err(err) = RequestCache::migrate_all() {
+            tracing::warn!(error = %format!("{err:#}"), "request cache migration failed");
+        }
+
+        // Detect an agent home that another machine is also driving (e.g.
+        // via a synced folder) before anything is written.
+        if cli.command.is_mutating() {
+            if let Err(err) = commands::guard_shared_home(cli.command.name(), cli.takeover) {
+                formatter::print_error(&err);
+                std::process::exit(formatter::error_exit_code(&err));
+            }
+        }
+    }
 
     tracing::debug!("command dispatched");
 
     if let Err(err) = run_command(cli.command).await {
+        // The verdict is already on screen; only the exit code is left.
+        if err
+            .downcast_ref::<commands::validate::ValidationFailed>()
+            .is_some()
+        {
+            std::process::exit(commands::validate::EXIT_VALIDATION_FAILED);
+        }
         formatter::print_error(&err);
-        std::process::exit(1);
+        std::process::exit(formatter::error_exit_code(&err));
     }
 }
 
@@ -153,38 +868,303 @@ async fn run_command(command: Commands) -> anyhow::Result<()> {
             description,
             capabilities,
             price,
-        } => commands::init::run(name, description, capabilities, price).await,
-        Commands::Fund => commands::fund::run().await,
-        Commands::Register => commands::register::run().await,
+            import_key_file,
+            mnemonic,
+            force,
+            abandon_registration,
+            qr,
+            network,
+        } => {
+            commands::init::run(
+                name,
+                description,
+                capabilities,
+                price,
+                import_key_file,
+                mnemonic,
+                force,
+                abandon_registration,
+                qr,
+                network,
+            )
+            .await
+        }
+        Commands::Fund {
+            watch,
+            interval,
+            timeout,
+            qr,
+        } => commands::fund::run(watch, interval, timeout, qr).await,
+        Commands::Register { dry_run } => commands::register::run(dry_run).await,
         Commands::Search {
             capability,
             requests,
-        } => commands::search::run(capability, requests).await,
+            sort,
+            limit,
+            min_reputation,
+            max_price,
+            min_price,
+            deadline_within,
+            mine,
+        } => {
+            commands::search::run(
+                capability,
+                requests,
+                sort,
+                limit,
+                min_reputation,
+                max_price,
+                min_price,
+                deadline_within,
+                mine,
+            )
+            .await
+        }
+        Commands::Reputation { agent } => commands::reputation::run(agent).await,
         Commands::Request {
             task,
             price,
             deadline,
             to,
-            file,
-        } => commands::request::run(task, price, deadline, to, file).await,
+            files,
+            dir,
+            tags,
+            capability,
+            dry_run,
+        } => {
+            commands::request::run(
+                task, price, deadline, to, files, dir, tags, capability, dry_run,
+            )
+            .await
+        }
         Commands::Respond {
             request_id,
             file,
+            dir,
+            stdin,
             message,
-        } => commands::respond::run(request_id, file, message).await,
+            tags,
+            accept_unsecured,
+            random_secret,
+            replace,
+        } => {
+            commands::respond::run(
+                request_id,
+                file,
+                dir,
+                stdin,
+                message,
+                tags,
+                accept_unsecured,
+                random_secret,
+                replace,
+            )
+            .await
+        }
         Commands::Validate {
             handler,
             handler_path,
             auto,
             filter,
-        } => commands::validate::run(handler, handler_path, auto, filter).await,
-        Commands::Claim { request_id } => commands::claim::run(request_id).await,
-        Commands::Status => commands::status::run().await,
-        Commands::Withdraw { address, amount } => commands::withdraw::run(address, amount).await,
+            strict_filter,
+            accept_unprofitable,
+            request_id,
+            revalidate,
+            list,
+        } => {
+            commands::validate::run(
+                handler,
+                handler_path,
+                auto,
+                filter,
+                strict_filter,
+                accept_unprofitable,
+                request_id,
+                revalidate,
+                list,
+            )
+            .await
+        }
+        Commands::Claim { request_id, all } => commands::claim::run(request_id, all).await,
+        Commands::Cancel { request_id } => commands::cancel::run(request_id).await,
+        Commands::Expire {
+            request_id,
+            all,
+            local_only,
+        } => commands::expire::run(request_id, all, local_only).await,
+        Commands::List {
+            status,
+            role,
+            tag,
+            limit,
+            sort,
+        } => commands::list::run(status, role, tag, limit, sort).await,
+        Commands::Show {
+            request_id,
+            reveal_secret,
+        } => commands::show::run(request_id, reveal_secret).await,
+        Commands::Status { verbose } => commands::status::run(verbose).await,
+        Commands::Whoami { no_unlock } => commands::whoami::run(no_unlock).await,
+        Commands::Sign { message, file } => commands::sign::sign(message, file).await,
+        Commands::Verify {
+            address,
+            signature,
+            message,
+            file,
+        } => commands::sign::verify(address, signature, message, file).await,
+        Commands::History { period } => commands::history::run(period).await,
+        Commands::Withdraw {
+            address,
+            amount,
+            asset,
+            no_checksum,
+        } => commands::withdraw::run(address, amount, asset, no_checksum).await,
         Commands::Daemon {
+            action: Some(DaemonAction::Install { uninstall }),
             interval,
             handler,
             handler_path,
-        } => commands::daemon::run(interval, handler, handler_path).await,
+            health_port,
+            health_host,
+            max_concurrent,
+            log_file,
+            ..
+        } => commands::daemon::install(
+            commands::daemon::ServiceFlags {
+                interval,
+                handler,
+                handler_path,
+                health_port,
+                health_host,
+                max_concurrent,
+                log_file,
+            },
+            uninstall,
+        ),
+        Commands::Daemon {
+            action: None,
+            interval,
+            handler,
+            handler_path,
+            status,
+            stop,
+            test_webhook,
+            health_port,
+            health_host,
+            max_concurrent,
+            log_file: _,
+        } => {
+            commands::daemon::run(
+                interval,
+                handler,
+                handler_path,
+                status,
+                stop,
+                test_webhook,
+                health_port,
+                health_host,
+                max_concurrent,
+            )
+            .await
+        }
+        Commands::Locks { break_lock } => commands::locks::run(break_lock).await,
+        Commands::Messages { id, unread, wait } => commands::messages::run(id, unread, wait).await,
+        Commands::Send {
+            to,
+            message,
+            file,
+            r#type,
+            quote,
+            price,
+            deadline,
+        } => commands::send::run(to, message, file, r#type, quote, price, deadline).await,
+        Commands::Doctor { unlock } => commands::doctor::run(unlock).await,
+        Commands::Bugreport {
+            request_id,
+            verbose,
+            output,
+        } => commands::bugreport::run(request_id, verbose, output).await,
+        Commands::Trust { action } => match action {
+            TrustAction::Add {
+                seller,
+                per_request,
+                monthly,
+                capabilities,
+                alias,
+            } => commands::trust::add(seller, per_request, monthly, capabilities, alias).await,
+            TrustAction::List => commands::trust::list().await,
+            TrustAction::Remove { seller } => commands::trust::remove(seller).await,
+        },
+        Commands::Key { action } => match action {
+            KeyAction::Export { output } => commands::key::export(output).await,
+            KeyAction::Import { file, hex, force } => commands::key::import(file, hex, force).await,
+            KeyAction::Recover { force } => commands::key::recover(force).await,
+        },
+        Commands::Config { action } => match action {
+            ConfigAction::Get { key } => commands::config_cmd::get(key).await,
+            ConfigAction::Set { key, value } => commands::config_cmd::set(key, value).await,
+        },
+        Commands::Profile { action } => match action {
+            ProfileAction::Update {
+                description,
+                capabilities,
+                price,
+            } => commands::profile::update(description, capabilities, price).await,
+        },
+        Commands::Maintenance { action } => match action {
+            MaintenanceAction::Run {
+                tasks,
+                all,
+                dry_run,
+            } => commands::maintenance::run(tasks, all, dry_run).await,
+            MaintenanceAction::List => commands::maintenance::list().await,
+        },
+        Commands::Tx { action } => match action {
+            TxAction::List {
+                request_id,
+                pending,
+            } => commands::tx::list(request_id, pending).await,
+            TxAction::Bump {
+                hash,
+                cancel,
+                percent,
+            } => commands::tx::bump(hash, cancel, percent).await,
+        },
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_file_appender_writes_to_dated_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("daemon.log");
+
+        let mut appender = file_appender(&path, 3).unwrap();
+        appender.write_all(b"cycle complete\n").unwrap();
+        appender.flush().unwrap();
+
+        let written: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        assert_eq!(written.len(), 1, "{written:?}");
+        let name = written[0]
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .into_owned();
+        assert!(name.starts_with("daemon.log."), "{name}");
+        assert_eq!(
+            std::fs::read_to_string(&written[0]).unwrap(),
+            "cycle complete\n"
+        );
     }
 }
@@ -7,10 +7,21 @@
 //! The only exception is [`print_wallet_address`] and [`print_funding_instructions`],
 //! which are used exclusively by `init` and `fund` commands where the raw
 //! address must be shown so the user can send funds.
+//!
+//! On a test network (see [`set_testnet`]), status, address, and funding
+//! output is badged TESTNET so play money is never mistaken for earnings.
+//!
+//! Nothing here writes to stdout/stderr directly: lines go through
+//! [`super::sink`], so output can be captured or redirected. In JSON mode,
+//! human-readable lines are sent to stderr and stdout is reserved for JSON.
 
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use anyhow::{Context, Error};
+use serde::Serialize;
 
-use anyhow::Error;
+use super::sink;
 
 // ---------------------------------------------------------------------------
 // JSON mode
@@ -31,80 +42,222 @@ pub fn is_json_mode() -> bool {
     JSON_MODE.load(Ordering::Relaxed)
 }
 
+// ---------------------------------------------------------------------------
+// Test network badge
+// ---------------------------------------------------------------------------
+
+/// Name of the test network the agent is on; `None` on mainnet.
+static TESTNET: Mutex<Option<&'static str>> = Mutex::new(None);
+
+/// Badge balances, earnings, and addresses as being on test network
+/// `network`, or stop badging them with `None`.
+pub fn set_testnet(network: Option<&'static str>) {
+    *TESTNET.lock().unwrap_or_else(|e| e.into_inner()) = network;
+}
+
+/// The test network output is badged for, if any.
+pub fn testnet() -> Option<&'static str> {
+    *TESTNET.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// ` [TESTNET]` on a test network, otherwise nothing.
+fn badge() -> &'static str {
+    if testnet().is_some() {
+        " [TESTNET]"
+    } else {
+        ""
+    }
+}
+
+/// On a test network, print a line saying so ahead of balances or
+/// earnings. Prints nothing on mainnet.
+pub fn print_testnet_banner() {
+    if let Some(network) = testnet() {
+        out(&format!(
+            "TESTNET ({network}): balances and earnings here are test funds with no real value."
+        ));
+    }
+}
+
+/// Format a USDC amount (6 decimals) for the current output mode:
+/// `$12,500.00` for people, `$12500.00` in JSON mode.
+pub fn format_price(usdc_amount: u64) -> String {
+    if is_json_mode() {
+        crate::engine::requests::format_price_usd(usdc_amount)
+    } else {
+        crate::engine::requests::format_price_usd_pretty(usdc_amount)
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Success / info / warning primitives
 // ---------------------------------------------------------------------------
 
+/// Write a line of human-readable output. In JSON mode it goes to stderr
+/// so that stdout carries nothing but JSON documents.
+fn out(line: &str) {
+    if is_json_mode() {
+        sink::write_err_line(line);
+    } else {
+        sink::emit_line(line);
+    }
+}
+
+/// Print `value` as a JSON document on one line of stdout. This is the only
+/// formatter output that stays on stdout in JSON mode, and the one way
+/// commands emit their results there.
+pub fn print_json<T: Serialize + ?Sized>(value: &T) -> anyhow::Result<()> {
+    let json = serde_json::to_string(value).context("failed to serialise JSON output")?;
+    sink::write_line(&json);
+    Ok(())
+}
+
 /// Print a success message to stdout: "✓ {msg}"
 pub fn print_success(msg: &str) {
-    println!("\u{2713} {msg}");
+    out(&format!("\u{2713} {msg}"));
 }
 
 /// Print an informational message to stdout.
 pub fn print_info(msg: &str) {
-    println!("{msg}");
+    out(msg);
 }
 
 /// Print a warning to stderr: "⚠ {msg}"
 pub fn print_warning(msg: &str) {
-    eprintln!("\u{26A0} {msg}");
+    sink::write_err_line(&format!("\u{26A0} {msg}"));
 }
 
 // ---------------------------------------------------------------------------
 // Error formatting
 // ---------------------------------------------------------------------------
 
-/// Translate an internal error into a human-readable message that contains
-/// no blockchain or IPFS jargon.
+/// Exit code for a failure that fits no other class.
+pub const EXIT_FAILURE: i32 = 1;
+/// Exit code when the agent is not initialized or not registered yet.
+pub const EXIT_NOT_SET_UP: i32 = 2;
+/// Exit code when there is not enough money to pay for the operation.
+pub const EXIT_INSUFFICIENT_FUNDS: i32 = 3;
+/// Exit code when a request, agent, or file does not exist.
+pub const EXIT_NOT_FOUND: i32 = 4;
+/// Exit code when a deliverable fails validation.
+pub const EXIT_VALIDATION_FAILED: i32 = 5;
+/// Exit code when the network or content network cannot be reached.
+pub const EXIT_NETWORK: i32 = 6;
+/// Exit code for bad arguments, input, or passphrase.
+pub const EXIT_INVALID_INPUT: i32 = 7;
+
+/// Friendly message (`None` keeps the original) and exit code for an error
+/// message, already lowercased.
 ///
 /// Pattern-matching is intentionally ordered so that the most specific
 /// patterns are checked first.
-pub fn format_error(err: &Error) -> String {
-    let msg = err.to_string();
-    let lower = msg.to_lowercase();
-
-    if lower.contains("insufficient funds") {
-        "Insufficient funds. Run `agentmarket fund` to check your balance.".to_string()
+fn classify(lower: &str) -> (Option<&'static str>, i32) {
+    if lower.contains("agent not initialized") || lower.contains("agent not registered") {
+        (None, EXIT_NOT_SET_UP)
+    } else if lower.contains("no agent") {
+        (None, EXIT_NOT_FOUND)
+    } else if lower.contains("insufficient funds") {
+        (
+            Some("Insufficient funds. Run `agentmarket fund` to check your balance."),
+            EXIT_INSUFFICIENT_FUNDS,
+        )
     } else if lower.contains("already registered") {
-        "Agent is already registered on the network.".to_string()
+        (
+            Some("Agent is already registered on the network."),
+            EXIT_FAILURE,
+        )
+    } else if lower.contains("already responded") || lower.contains("not open") {
+        (
+            Some("Request already has a response and can no longer be changed."),
+            EXIT_FAILURE,
+        )
     } else if lower.contains("nonce") {
-        "Transaction conflict. Please try again.".to_string()
+        (
+            Some("Transaction conflict. Please try again."),
+            EXIT_FAILURE,
+        )
     } else if lower.contains("timeout") || lower.contains("connection") {
-        "Network unreachable. Check your internet connection.".to_string()
+        (
+            Some("Network unreachable. Check your internet connection."),
+            EXIT_NETWORK,
+        )
     } else if lower.contains("ipfs") {
-        "Content network unavailable. Please try again later.".to_string()
+        (
+            Some("Content network unavailable. Please try again later."),
+            EXIT_NETWORK,
+        )
     } else if lower.contains("keystore") || lower.contains("decrypt") {
-        "Invalid passphrase. Please try again.".to_string()
+        (
+            Some("Invalid passphrase. Please try again."),
+            EXIT_INVALID_INPUT,
+        )
     } else if lower.contains("not found") {
-        "Request not found. Check the ID and try again.".to_string()
+        (
+            Some("Request not found. Check the ID and try again."),
+            EXIT_NOT_FOUND,
+        )
     } else if lower.contains("expired") {
-        "Request has expired and can no longer be processed.".to_string()
+        (
+            Some("Request has expired and can no longer be processed."),
+            EXIT_FAILURE,
+        )
     } else if lower.contains("secret") {
-        "Secret key missing. Your local data may be corrupted.".to_string()
+        (
+            Some("Secret key missing. Your local data may be corrupted."),
+            EXIT_FAILURE,
+        )
     } else if lower.contains("cancelled") {
-        "Request was cancelled.".to_string()
+        (Some("Request was cancelled."), EXIT_FAILURE)
     } else if lower.contains("validation") {
-        "Validation failed. Check the handler output.".to_string()
+        (
+            Some("Validation failed. Check the handler output."),
+            EXIT_VALIDATION_FAILED,
+        )
     } else if lower.contains("permission") || lower.contains("unauthorized") {
-        "Permission denied. Check your identity and try again.".to_string()
+        (
+            Some("Permission denied. Check your identity and try again."),
+            EXIT_FAILURE,
+        )
     } else if lower.contains("parse") {
-        "Invalid input format. Please check your command arguments.".to_string()
+        (
+            Some("Invalid input format. Please check your command arguments."),
+            EXIT_INVALID_INPUT,
+        )
     } else {
-        format!("Operation failed: {msg}")
+        (None, EXIT_FAILURE)
     }
 }
 
+/// Translate an internal error into a human-readable message that contains
+/// no blockchain or IPFS jargon.
+pub fn format_error(err: &Error) -> String {
+    let msg = err.to_string();
+    match classify(&msg.to_lowercase()) {
+        (Some(friendly), _) => friendly.to_string(),
+        (None, _) => format!("Operation failed: {msg}"),
+    }
+}
+
+/// Process exit code for `err`, from the same patterns as [`format_error`]:
+/// one of the `EXIT_*` constants. The codes are documented and stable, so
+/// scripts can tell a failure worth retrying from one that is not.
+pub fn error_exit_code(err: &Error) -> i32 {
+    classify(&err.to_string().to_lowercase()).1
+}
+
 /// Format and print an error to stderr.
 ///
-/// In JSON mode, emits `{"error": "..."}` instead of plain text.
+/// In JSON mode, emits `{"error": "...", "code": N}` instead of plain text,
+/// where `code` is the [`error_exit_code`].
 pub fn print_error(err: &Error) {
     if is_json_mode() {
-        let message = format_error(err);
-        // Escape any double-quotes or backslashes in the message for valid JSON.
-        let escaped = message.replace('\\', "\\\\").replace('"', "\\\"");
-        eprintln!("{{\"error\": \"{escaped}\"}}");
+        let json = serde_json::json!({
+            "error": format_error(err),
+            "code": error_exit_code(err),
+        });
+        sink::write_err_line(&json.to_string());
     } else {
-        eprintln!("{}", format_error(err));
+        sink::write_err_line(&format_error(err));
     }
 }
 
@@ -117,7 +270,7 @@ pub fn print_error(err: &Error) {
 /// Example output: `$1,234.56` (no thousands separator — keeps parsing simple
 /// for agent consumers; just `$1234.56`).
 pub fn print_earnings(amount_usd: f64) {
-    println!("${:.2}", amount_usd);
+    out(&format!("${:.2}", amount_usd));
 }
 
 /// Shorten an agent ID for display purposes.
@@ -132,79 +285,200 @@ fn short_id(id: &str) -> String {
     }
 }
 
-/// Print a table of agents (name, description).
-pub fn print_agent_list(agents: &[(String, String)]) {
+/// Print a table of agents: name, price per task, reputation, and
+/// description.
+pub fn print_agent_list(agents: &[crate::engine::discovery::AgentListing]) {
     if agents.is_empty() {
-        println!("No agents found.");
+        out("No agents found.");
+        return;
+    }
+
+    let rows: Vec<[String; 4]> = agents
+        .iter()
+        .map(|a| {
+            [
+                a.name.clone(),
+                format!("${:.2}", a.pricing_usd),
+                a.reputation
+                    .map_or_else(|| "N/A".to_string(), |r| format!("{r:.1}")),
+                a.description.clone(),
+            ]
+        })
+        .collect();
+    print_table(["Name", "Price", "Reputation", "Description"], &rows);
+}
+
+/// Print a table of open requests: ID, description, price, and deadline
+/// relative to `now`.
+pub fn print_request_list(requests: &[crate::engine::discovery::RequestListing], now: u64) {
+    if requests.is_empty() {
+        out("No requests found.");
         return;
     }
 
-    // Determine column width for the name column.
-    let name_width = agents
+    let rows: Vec<[String; 4]> = requests
         .iter()
-        .map(|(name, _)| name.len())
-        .max()
-        .unwrap_or(4)
-        .max(4); // minimum width = "Name"
+        .map(|r| {
+            [
+                short_id(&r.request_id),
+                r.task_summary.clone(),
+                format_price(r.price_usdc),
+                r.format_deadline(now),
+            ]
+        })
+        .collect();
+    print_table(["ID", "Description", "Price", "Deadline"], &rows);
+}
 
-    println!("{:<width$}  Description", "Name", width = name_width);
-    println!("{:<width$}  -----------", "----", width = name_width);
+/// Print `rows` under `headers`, each column padded to its widest cell.
+fn print_table<const N: usize>(headers: [&str; N], rows: &[[String; N]]) {
+    let mut widths = headers.map(str::len);
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
 
-    for (name, description) in agents {
-        println!("{:<width$}  {description}", name, width = name_width);
+    let line = |cells: &[String]| {
+        let padded: Vec<String> = cells
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect();
+        out(padded.join("  ").trim_end());
+    };
+    line(&headers.map(String::from));
+    line(&headers.map(|h| "-".repeat(h.len())));
+    for row in rows {
+        line(row);
     }
 }
 
-/// Print a table of requests (id, description, price in USD).
-pub fn print_request_list(requests: &[(String, String, f64)]) {
+/// Print a table of locally cached requests: ID, role, status, price,
+/// deadline relative to `now`, and counterparty.
+pub fn print_local_request_list(requests: &[crate::engine::requests::LocalRequest], now: u64) {
     if requests.is_empty() {
-        println!("No requests found.");
+        out("No requests found.");
         return;
     }
 
-    // Compute column widths.
-    let id_width = requests
+    let rows: Vec<[String; 6]> = requests
         .iter()
-        .map(|(id, _, _)| short_id(id).len())
-        .max()
-        .unwrap_or(2)
-        .max(2); // minimum width = "ID"
+        .map(|r| {
+            [
+                short_id(&r.request_id),
+                format!("{:?}", r.role),
+                format!("{:?}", r.status),
+                format_price(r.price_usdc),
+                r.format_deadline(now),
+                r.counterparty
+                    .as_deref()
+                    .map(short_id)
+                    .unwrap_or_else(|| "-".to_string()),
+            ]
+        })
+        .collect();
 
-    let desc_width = requests
-        .iter()
-        .map(|(_, desc, _)| desc.len())
-        .max()
-        .unwrap_or(11)
-        .max(11); // minimum width = "Description"
-
-    println!(
-        "{:<id_w$}  {:<desc_w$}  Price",
-        "ID",
-        "Description",
-        id_w = id_width,
-        desc_w = desc_width,
-    );
-    println!(
-        "{:<id_w$}  {:<desc_w$}  -----",
-        "--",
-        "-----------",
-        id_w = id_width,
-        desc_w = desc_width,
+    print_table(
+        ["ID", "Role", "Status", "Price", "Deadline", "Counterparty"],
+        &rows,
     );
+}
 
-    for (id, description, price_usd) in requests {
-        println!(
-            "{:<id_w$}  {:<desc_w$}  ${:.2}",
-            short_id(id),
-            description,
-            price_usd,
-            id_w = id_width,
-            desc_w = desc_width,
-        );
+/// Print the responses waiting for review: ID, task, price, seller, and
+/// deadline relative to `now`.
+pub fn print_pending_validations(
+    pending: &[crate::engine::validation::PendingValidation],
+    now: u64,
+) {
+    if pending.is_empty() {
+        out("No pending validations found.");
+        return;
     }
+
+    let rows: Vec<[String; 5]> = pending
+        .iter()
+        .map(|p| {
+            [
+                p.request_id.clone(),
+                p.task_summary.clone().unwrap_or_else(|| "-".to_string()),
+                format_price(p.price_usdc),
+                p.seller
+                    .as_deref()
+                    .map(short_id)
+                    .unwrap_or_else(|| "-".to_string()),
+                format_relative(p.deadline, now),
+            ]
+        })
+        .collect();
+    print_table(["ID", "Task", "Price", "Seller", "Deadline"], &rows);
+}
+
+/// Print the requests that need attention (from
+/// [`crate::engine::status::pending_actions`]) with the command for each.
+pub fn print_pending_actions(actions: &[crate::engine::status::PendingAction], now: u64) {
+    use crate::engine::status::ActionKind;
+
+    if actions.is_empty() {
+        out("Nothing needs your attention.");
+        return;
+    }
+    out("Needs your attention:");
+    let rows: Vec<[String; 5]> = actions
+        .iter()
+        .map(|a| {
+            [
+                match a.kind {
+                    ActionKind::Claim => "Claim",
+                    ActionKind::Validate => "Validate",
+                    ActionKind::NearDeadline => "No response yet",
+                }
+                .to_string(),
+                a.request_id.clone(),
+                format_price(a.price_usdc),
+                format_relative(a.deadline, now),
+                a.command.clone(),
+            ]
+        })
+        .collect();
+    print_table(["Action", "ID", "Price", "Deadline", "Command"], &rows);
 }
 
-/// Print a formatted agent status summary.
+/// Print the most recently finished requests (from
+/// [`crate::engine::status::recent_requests`]).
+pub fn print_recent_requests(requests: &[crate::engine::status::RecentRequest], now: u64) {
+    if requests.is_empty() {
+        out("No finished requests yet.");
+        return;
+    }
+    out("Recently finished:");
+    let rows: Vec<[String; 5]> = requests
+        .iter()
+        .map(|r| {
+            [
+                r.request_id.clone(),
+                format!("{:?}", r.role),
+                format!("{:?}", r.status),
+                format_price(r.price_usdc),
+                format_relative(r.finished_at, now),
+            ]
+        })
+        .collect();
+    print_table(["ID", "Role", "Status", "Price", "When"], &rows);
+}
+
+/// `"in 3h"` for a future `timestamp`, `"2d ago"` for a past one.
+fn format_relative(timestamp: u64, now: u64) -> String {
+    use crate::clock::format_duration;
+    if timestamp > now {
+        format!("in {}", format_duration(timestamp - now))
+    } else {
+        format!("{} ago", format_duration(now - timestamp))
+    }
+}
+
+/// Print a formatted agent status summary, or the whole report as one JSON
+/// object in JSON mode.
 ///
 /// Example output:
 /// ```text
@@ -213,11 +487,24 @@ pub fn print_request_list(requests: &[(String, String, f64)]) {
 /// Earnings:   $42.50
 /// Reputation: 97.3
 /// ```
-pub fn print_status(name: &str, agent_id: &str, earnings: f64, reputation: f64) {
-    println!("Agent:      {name}");
-    println!("ID:         {}", short_id(agent_id));
-    println!("Earnings:   ${:.2}", earnings);
-    println!("Reputation: {:.1}", reputation);
+pub fn print_status(report: &crate::engine::status::StatusReport) -> anyhow::Result<()> {
+    if is_json_mode() {
+        return print_json(report);
+    }
+    out(&format!("Agent:      {}", report.name));
+    if let Some(agent_id) = &report.agent_id {
+        out(&format!("ID:         {}", short_id(agent_id)));
+    }
+    if report.testnet {
+        out(&format!("Network:    {} [TESTNET]", report.network));
+    }
+    out(&format!(
+        "Earnings:   {}{}",
+        report.earnings_formatted,
+        if report.testnet { " [TESTNET]" } else { "" }
+    ));
+    out(&format!("Reputation: {:.1}", report.reputation_score));
+    Ok(())
 }
 
 /// Print a raw wallet address.
@@ -225,7 +512,85 @@ pub fn print_status(name: &str, agent_id: &str, earnings: f64, reputation: f64)
 /// **This is the one place where a crypto-specific detail is allowed in
 /// user-facing output**, used only by the `init` and `fund` commands.
 pub fn print_wallet_address(address: &str) {
-    println!("Address: {address}");
+    out(&format!("Address: {address}{}", badge()));
+}
+
+/// Modules of light margin a scanner needs around a QR code.
+const QR_QUIET_ZONE: usize = 4;
+
+/// Terminal width assumed when `COLUMNS` is not set.
+const DEFAULT_COLUMNS: usize = 80;
+
+/// Encode `data` as a QR code (error correction level M, smallest version
+/// that fits). Returns the modules row by row, `true` for dark.
+pub fn qr_matrix(data: &str) -> anyhow::Result<Vec<Vec<bool>>> {
+    let code = qrcode::QrCode::new(data.as_bytes())
+        .map_err(|err| anyhow::anyhow!("failed to encode QR code: {err}"))?;
+    let width = code.width();
+    Ok(code
+        .into_colors()
+        .chunks(width)
+        .map(|row| row.iter().map(|&c| c == qrcode::Color::Dark).collect())
+        .collect())
+}
+
+/// Render a QR `matrix` with unicode half blocks, two module rows per line,
+/// inside a quiet zone. Light modules are drawn and dark ones left blank,
+/// so the code reads correctly on the usual dark terminal background.
+fn render_qr(matrix: &[Vec<bool>]) -> Vec<String> {
+    let size = matrix.len() + 2 * QR_QUIET_ZONE;
+    let light = |row: usize, col: usize| {
+        let (Some(row), Some(col)) = (
+            row.checked_sub(QR_QUIET_ZONE),
+            col.checked_sub(QR_QUIET_ZONE),
+        ) else {
+            return true;
+        };
+        !matrix
+            .get(row)
+            .and_then(|cells| cells.get(col))
+            .copied()
+            .unwrap_or(false)
+    };
+
+    (0..size)
+        .step_by(2)
+        .map(|row| {
+            (0..size)
+                .map(
+                    |col| match (light(row, col), row + 1 < size && light(row + 1, col)) {
+                        (true, true) => '\u{2588}',
+                        (true, false) => '\u{2580}',
+                        (false, true) => '\u{2584}',
+                        (false, false) => ' ',
+                    },
+                )
+                .collect()
+        })
+        .collect()
+}
+
+/// Print `address` as a terminal QR code for scanning with a phone wallet,
+/// followed by the address as text for copy-paste.
+///
+/// Falls back to the text alone in JSON mode or when the terminal (per
+/// `COLUMNS`) is too narrow. Like [`print_wallet_address`], used only by
+/// `init` and `fund`.
+pub fn print_address_qr(address: &str) {
+    let columns = std::env::var("COLUMNS")
+        .ok()
+        .and_then(|c| c.trim().parse().ok())
+        .unwrap_or(DEFAULT_COLUMNS);
+    match qr_matrix(address) {
+        Ok(matrix) if !is_json_mode() && matrix.len() + 2 * QR_QUIET_ZONE <= columns => {
+            for line in render_qr(&matrix) {
+                out(&line);
+            }
+        }
+        Ok(_) => {}
+        Err(err) => tracing::debug!(error = %err, "QR code not rendered"),
+    }
+    print_wallet_address(address);
 }
 
 /// Print funding instructions including the wallet address and the amount
@@ -234,11 +599,17 @@ pub fn print_wallet_address(address: &str) {
 /// Like [`print_wallet_address`], this is one of the few places where raw
 /// crypto details are intentionally exposed to the user.
 pub fn print_funding_instructions(address: &str, needed: &str) {
-    println!("Your agent needs funding to continue.");
-    println!("Address: {address}");
-    println!("Amount needed: {needed}");
-    println!();
-    println!("Send the required amount to the address above, then retry your command.");
+    out("Your agent needs funding to continue.");
+    out(&format!("Address: {address}{}", badge()));
+    out(&format!("Amount needed: {needed}"));
+    out("");
+    match testnet() {
+        Some(network) => out(&format!(
+            "Send test funds on {network} (free from a faucet) to the address above, \
+             then retry your command."
+        )),
+        None => out("Send the required amount to the address above, then retry your command."),
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -261,6 +632,15 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_format_error_already_responded() {
+        let expected = "Request already has a response and can no longer be changed.";
+        let err = anyhow!("execution reverted: request already responded");
+        assert_eq!(format_error(&err), expected);
+        let err = anyhow!("execution reverted: RequestRegistry: not open");
+        assert_eq!(format_error(&err), expected);
+    }
+
     #[test]
     fn test_format_error_nonce() {
         let err = anyhow!("nonce too low");
@@ -402,6 +782,62 @@ mod tests {
         assert_eq!(format_error(&err), "Operation failed: something unexpected");
     }
 
+    #[test]
+    fn test_format_error_not_set_up_keeps_message() {
+        let err = anyhow!("Agent not initialized. Run `agentmarket init` first.");
+        assert_eq!(
+            format_error(&err),
+            "Operation failed: Agent not initialized. Run `agentmarket init` first."
+        );
+    }
+
+    // -- error_exit_code ------------------------------------------------------
+
+    #[test]
+    fn test_error_exit_code_per_class() {
+        let cases = [
+            ("Agent not initialized. Run `agentmarket init` first.", 2),
+            ("Agent not registered. Run `agentmarket register` first.", 2),
+            ("No agent with ID 42 is registered.", 4),
+            ("insufficient funds for gas * price + value", 3),
+            ("AlreadyRegistered: already registered", 1),
+            ("request already responded", 1),
+            ("request is not open", 1),
+            ("nonce too low", 1),
+            ("request timeout after 30s", 6),
+            ("connection refused", 6),
+            ("IPFS daemon not running", 6),
+            ("keystore file corrupt", 7),
+            ("failed to decrypt", 7),
+            ("request not found", 4),
+            ("deadline expired", 1),
+            ("secret missing", 1),
+            ("request cancelled", 1),
+            ("Request 42 did not pass validation.", 5),
+            ("permission denied", 1),
+            ("unauthorized caller", 1),
+            ("failed to parse amount", 7),
+            ("something unexpected", 1),
+        ];
+        for (message, code) in cases {
+            assert_eq!(error_exit_code(&anyhow!(message)), code, "{message}");
+        }
+    }
+
+    #[test]
+    fn test_exit_codes_are_stable() {
+        let codes = [
+            EXIT_FAILURE,
+            EXIT_NOT_SET_UP,
+            EXIT_INSUFFICIENT_FUNDS,
+            EXIT_NOT_FOUND,
+            EXIT_VALIDATION_FAILED,
+            EXIT_NETWORK,
+            EXIT_INVALID_INPUT,
+        ];
+        assert_eq!(codes, [1, 2, 3, 4, 5, 6, 7]);
+    }
+
     // -- short_id -------------------------------------------------------------
 
     #[test]
@@ -426,8 +862,16 @@ mod tests {
 
     // -- JSON mode ------------------------------------------------------------
 
+    /// Serialises tests that toggle the global JSON mode.
+    static JSON_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+    fn json_lock() -> std::sync::MutexGuard<'static, ()> {
+        JSON_LOCK.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
     #[test]
     fn test_json_mode_default_off() {
+        let _lock = json_lock();
         // Reset to known state.
         set_json_mode(false);
         assert!(!is_json_mode());
@@ -435,9 +879,355 @@ mod tests {
 
     #[test]
     fn test_json_mode_toggle() {
+        let _lock = json_lock();
         set_json_mode(true);
         assert!(is_json_mode());
         set_json_mode(false);
         assert!(!is_json_mode());
     }
+
+    #[test]
+    fn test_format_price_follows_json_mode() {
+        let _lock = json_lock();
+        assert_eq!(format_price(12_500_000_000), "$12,500.00");
+        assert_eq!(format_price(1), "$0.000001");
+        set_json_mode(true);
+        assert_eq!(format_price(12_500_000_000), "$12500.00");
+        assert_eq!(format_price(1_000_000_000_000), "$1000000.00");
+        set_json_mode(false);
+    }
+
+    #[test]
+    fn test_print_error_json_includes_code() {
+        let _lock = json_lock();
+        set_json_mode(true);
+        let ((), captured) = sink::capture(|| print_error(&anyhow!("request not found")));
+        set_json_mode(false);
+
+        let json: serde_json::Value = serde_json::from_str(&captured.err()[0]).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "error": "Request not found. Check the ID and try again.",
+                "code": EXIT_NOT_FOUND,
+            })
+        );
+    }
+
+    // -- QR codes -------------------------------------------------------------
+
+    #[test]
+    fn test_qr_matrix_matches_annex_i() {
+        // ISO/IEC 18004 Annex I: "01234567" as a version 1-M symbol.
+        let expected = [
+            "#######..#.##.#######",
+            "#.....#..####.#.....#",
+            "#.###.#.#.....#.###.#",
+            "#.###.#.##....#.###.#",
+            "#.###.#.#.###.#.###.#",
+            "#.....#.#...#.#.....#",
+            "#######.#.#.#.#######",
+            "........#..##........",
+            "#.#####..#..#.#####..",
+            "...#.#.##.#.#..#.##..",
+            "..#...##.#.#.#..#####",
+            "....#....#.....####..",
+            "...######..#.#..#....",
+            "........#.#####..##..",
+            "#######..##.#.##.....",
+            "#.....#.#.#####...#.#",
+            "#.###.#.#...#..#.##..",
+            "#.###.#.##..#..#.....",
+            "#.###.#.#.##.#..#.#..",
+            "#.....#........##.##.",
+            "#######.####.#..#.#..",
+        ];
+        let rows: Vec<String> = qr_matrix("01234567")
+            .unwrap()
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|&dark| if dark { '#' } else { '.' })
+                    .collect()
+            })
+            .collect();
+        assert_eq!(rows, expected);
+    }
+
+    #[test]
+    fn test_render_qr_pairs_rows_inside_quiet_zone() {
+        let address = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+        let matrix = qr_matrix(address).unwrap();
+        let size = matrix.len() + 2 * QR_QUIET_ZONE;
+        let lines = render_qr(&matrix);
+
+        assert_eq!(lines.len(), size.div_ceil(2));
+        assert!(lines.iter().all(|line| line.chars().count() == size));
+        // The quiet zone is light: full blocks across the top.
+        assert!(lines[0].chars().all(|c| c == '\u{2588}'));
+        // Row pair 4-5 starts the dark top-left finder pattern.
+        assert_eq!(lines[2].chars().nth(QR_QUIET_ZONE), Some(' '));
+    }
+
+    #[test]
+    fn test_print_address_qr_falls_back_to_text_in_json_mode() {
+        let _lock = json_lock();
+        set_json_mode(true);
+        let ((), captured) = sink::capture(|| print_address_qr("0xabc"));
+        set_json_mode(false);
+
+        assert!(captured.out().is_empty());
+        assert_eq!(captured.err(), vec!["Address: 0xabc".to_string()]);
+    }
+
+    // -- Sink routing ---------------------------------------------------------
+
+    #[test]
+    fn test_print_helpers_route_through_sink() {
+        let _lock = json_lock();
+        let ((), captured) = sink::capture(|| {
+            print_success("done");
+            print_info("detail");
+            print_warning("careful");
+            print_error(&anyhow!("nonce too low"));
+            print_request_list(&[], 0);
+        });
+        assert_eq!(
+            captured.out(),
+            vec!["\u{2713} done", "detail", "No requests found."]
+        );
+        assert_eq!(
+            captured.err(),
+            vec![
+                "\u{26A0} careful",
+                "Transaction conflict. Please try again."
+            ]
+        );
+    }
+
+    #[test]
+    fn test_print_agent_list_columns() {
+        use crate::engine::discovery::AgentListing;
+
+        let _lock = json_lock();
+        let agents = [
+            AgentListing {
+                agent_id: "1".to_string(),
+                name: "summarizer".to_string(),
+                description: "Summarizes documents".to_string(),
+                capabilities: Vec::new(),
+                pricing_usd: 5.0,
+                reputation: Some(97.3),
+            },
+            AgentListing {
+                agent_id: "2".to_string(),
+                name: "new".to_string(),
+                description: String::new(),
+                capabilities: Vec::new(),
+                pricing_usd: 0.5,
+                reputation: None,
+            },
+        ];
+        let ((), captured) = sink::capture(|| print_agent_list(&agents));
+        assert_eq!(
+            captured.out(),
+            vec![
+                "Name        Price  Reputation  Description",
+                "----        -----  ----------  -----------",
+                "summarizer  $5.00  97.3        Summarizes documents",
+                "new         $0.50  N/A",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_print_pending_actions_and_recent() {
+        use crate::engine::requests::{LocalRequestStatus, RequestRole};
+        use crate::engine::status::{ActionKind, PendingAction, RecentRequest};
+
+        let now = 1_700_000_000;
+        let actions = [PendingAction {
+            kind: ActionKind::Claim,
+            request_id: "123".to_string(),
+            price_usdc: 5_000_000,
+            deadline: now + 3 * 3600,
+            command: "agentmarket claim -i 123".to_string(),
+        }];
+        let recent = [RecentRequest {
+            request_id: "99".to_string(),
+            role: RequestRole::Buyer,
+            status: LocalRequestStatus::Cancelled,
+            price_usdc: 1_000_000,
+            finished_at: now - 2 * 86_400,
+        }];
+
+        let ((), captured) = sink::capture(|| {
+            print_pending_actions(&actions, now);
+            print_recent_requests(&recent, now);
+            print_pending_actions(&[], now);
+            print_recent_requests(&[], now);
+        });
+        let lines = captured.out();
+        assert_eq!(lines[0], "Needs your attention:");
+        assert_eq!(lines[1], "Action  ID   Price  Deadline  Command");
+        assert_eq!(
+            lines[3],
+            "Claim   123  $5.00  in 3h     agentmarket claim -i 123"
+        );
+        assert_eq!(lines[4], "Recently finished:");
+        assert_eq!(lines[7], "99  Buyer  Cancelled  $1.00  2d ago");
+        assert_eq!(lines[8], "Nothing needs your attention.");
+        assert_eq!(lines[9], "No finished requests yet.");
+    }
+
+    #[test]
+    fn test_print_local_request_list() {
+        use crate::engine::requests::{LocalRequest, LocalRequestStatus, RequestRole};
+
+        let _lock = json_lock();
+        let request = LocalRequest {
+            schema_version: 0,
+            request_id: "12345678901234567890".to_string(),
+            role: RequestRole::Buyer,
+            status: LocalRequestStatus::Open,
+            request_cid: String::new(),
+            price_usdc: 5_000_000,
+            deadline: 1_000 + 7_200,
+            response_cid: None,
+            secret: None,
+            secret_hash: None,
+            counterparty: Some("0x1234567890abcdef1234567890abcdef12345678".to_string()),
+            created_at: 1_000,
+            updated_at: 1_000,
+            previous_status: None,
+            tags: Vec::new(),
+            task_summary: None,
+            capability: None,
+            trust_policy: None,
+            history: Vec::new(),
+            payment_check: None,
+            response_attempt: None,
+        };
+
+        let ((), captured) = sink::capture(|| {
+            print_local_request_list(&[], 1_000);
+            print_local_request_list(&[request], 1_000);
+        });
+        let lines = captured.out();
+        assert_eq!(lines[0], "No requests found.");
+        assert!(
+            lines[1].starts_with("ID           Role   Status  Price"),
+            "{}",
+            lines[1]
+        );
+        assert_eq!(
+            lines[3],
+            "12345678...  Buyer  Open    $5.00  in 2h     0x123456..."
+        );
+    }
+
+    #[test]
+    fn test_print_pending_validations() {
+        use crate::engine::validation::PendingValidation;
+
+        let pending = [
+            PendingValidation {
+                request_id: "7".to_string(),
+                task_summary: Some("Review my PR".to_string()),
+                capability: Some("code-review".to_string()),
+                price_usdc: 5_000_000,
+                seller: Some("0x1234567890abcdef1234567890abcdef12345678".to_string()),
+                deadline: 1_000 + 7_200,
+                below_margin: false,
+            },
+            PendingValidation {
+                request_id: "8".to_string(),
+                task_summary: None,
+                capability: None,
+                price_usdc: 250_000,
+                seller: None,
+                deadline: 1_000 + 90_000,
+                below_margin: true,
+            },
+        ];
+
+        let ((), captured) = sink::capture(|| {
+            print_pending_validations(&[], 1_000);
+            print_pending_validations(&pending, 1_000);
+        });
+        assert_eq!(
+            captured.out(),
+            [
+                "No pending validations found.",
+                "ID  Task          Price  Seller       Deadline",
+                "--  ----          -----  ------       --------",
+                "7   Review my PR  $5.00  0x123456...  in 2h",
+                "8   -             $0.25  -            in 1d 1h",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_testnet_output_is_badged() {
+        use crate::chain::contracts::Network;
+        use crate::engine::identity::IdentityState;
+        use crate::engine::reputation;
+        use crate::engine::requests::RequestSummary;
+        use crate::engine::status::StatusReport;
+
+        let _lock = json_lock();
+        let state = IdentityState::Registered {
+            address: "0xabc".to_string(),
+            public_key: "02ab".to_string(),
+            agent_id: "42".to_string(),
+        };
+        let rep = reputation::compute_reputation("42", &[], 1_500_000, 0);
+        let summary = RequestSummary::from_requests(&[]);
+        let report = |network| StatusReport::new("alice", network, &state, &rep, &summary);
+
+        set_testnet(Some("base-sepolia"));
+        let ((), captured) = sink::capture(|| {
+            print_testnet_banner();
+            print_status(&report(Network::BaseSepolia)).unwrap();
+            print_wallet_address("0xabc");
+            print_funding_instructions("0xabc", "0.0001 ETH");
+        });
+        set_testnet(None);
+        let out = captured.out();
+        assert!(out[0].starts_with("TESTNET (base-sepolia):"), "{out:?}");
+        assert!(out.contains(&"Network:    base-sepolia [TESTNET]".to_string()));
+        assert!(out.contains(&"Earnings:   $1.50 [TESTNET]".to_string()));
+        assert!(out.contains(&"Address: 0xabc [TESTNET]".to_string()));
+        assert!(out.last().unwrap().contains("faucet"), "{out:?}");
+
+        let ((), captured) = sink::capture(|| {
+            print_testnet_banner();
+            print_status(&report(Network::BaseMainnet)).unwrap();
+            print_wallet_address("0xabc");
+        });
+        assert!(
+            captured.out().iter().all(|line| !line.contains("TESTNET")),
+            "{:?}",
+            captured.out()
+        );
+    }
+
+    #[test]
+    fn test_json_mode_keeps_stdout_json_only() {
+        let _lock = json_lock();
+        set_json_mode(true);
+        let ((), captured) = sink::capture(|| {
+            print_info("human text");
+            print_funding_instructions("0xabc", "0.0001 ETH");
+            print_json(&serde_json::json!({"ok": true})).unwrap();
+            print_error(&anyhow!("boom"));
+        });
+        set_json_mode(false);
+
+        assert_eq!(captured.out(), vec![r#"{"ok":true}"#]);
+        assert!(captured.err().contains(&"human text".to_string()));
+        let error: serde_json::Value =
+            serde_json::from_str(captured.err().last().unwrap()).unwrap();
+        assert_eq!(error["error"], "Operation failed: boom");
+    }
 }
@@ -1 +1,4 @@
 pub mod formatter;
+pub mod prompt;
+pub mod redact;
+pub mod sink;
@@ -0,0 +1,237 @@
+//! Prompts, and confirmation before committing or moving funds.
+//!
+//! `request`, `claim`, `withdraw`, and `cancel` describe what they are about
+//! to do and ask before doing it. The global `--yes` flag answers for the
+//! user. Without it, a command that is not attached to a terminal refuses
+//! rather than spending money unattended.
+//!
+//! Every other prompt goes through [`confirm`], [`ask_line`] or
+//! [`ask_secret`] (or [`ensure_interactive`] first), so `--non-interactive` or
+//! `AGENTMARKET_NONINTERACTIVE=1` turns each one into an immediate error
+//! saying which flag or variable supplies the answer, instead of a prompt
+//! that would hang a pipeline.
+//!
+//! For automated testing, [`confirm`] and [`confirm_funds_with_reader`]
+//! accept any [`BufRead`] source instead of stdin.
+
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::{bail, Context, Result};
+use tracing::debug;
+
+/// Environment variable that turns prompts off, like `--non-interactive`.
+pub const NON_INTERACTIVE_ENV: &str = "AGENTMARKET_NONINTERACTIVE";
+
+/// Whether `--yes` was passed.
+static ASSUME_YES: AtomicBool = AtomicBool::new(false);
+
+/// Whether `--non-interactive` was passed.
+static NON_INTERACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Answer every confirmation with yes (the global `--yes` flag).
+pub fn set_assume_yes(enabled: bool) {
+    ASSUME_YES.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether confirmations are answered automatically.
+pub fn assume_yes() -> bool {
+    ASSUME_YES.load(Ordering::Relaxed)
+}
+
+/// Refuse every prompt (the global `--non-interactive` flag).
+pub fn set_non_interactive(enabled: bool) {
+    NON_INTERACTIVE.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether prompts are off, through `--non-interactive` or
+/// `AGENTMARKET_NONINTERACTIVE` set to `1`, `true`, or `yes`.
+pub fn non_interactive() -> bool {
+    NON_INTERACTIVE.load(Ordering::Relaxed)
+        || std::env::var(NON_INTERACTIVE_ENV).is_ok_and(|val| {
+            matches!(
+                val.trim().to_ascii_lowercase().as_str(),
+                "1" | "true" | "yes"
+            )
+        })
+}
+
+/// Fail if prompts are off. `prompt` is what would have been asked and
+/// `supply` how to give the answer up front, e.g. "Pass --name."
+pub fn ensure_interactive(prompt: &str, supply: &str) -> Result<()> {
+    if non_interactive() {
+        bail!(refusal(prompt, supply));
+    }
+    Ok(())
+}
+
+fn refusal(prompt: &str, supply: &str) -> String {
+    format!(
+        "Cannot ask \"{}\" with prompts turned off (--non-interactive). {supply}",
+        prompt.trim()
+    )
+}
+
+/// Print `prompt` to stderr (so it appears even when stdout is redirected)
+/// and read a single trimmed line from `reader`. Fails with `supply` as the
+/// remedy when prompts are off.
+pub fn ask_line<R: BufRead>(reader: &mut R, prompt: &str, supply: &str) -> Result<String> {
+    ensure_interactive(prompt, supply)?;
+    eprint!("{prompt}");
+    io::stderr().flush().context("failed to flush stderr")?;
+
+    let mut line = String::new();
+    reader
+        .read_line(&mut line)
+        .context("failed to read input")?;
+    Ok(line.trim().to_string())
+}
+
+/// Read a secret from the terminal without echoing it. Fails with `supply`
+/// as the remedy when prompts are off.
+pub fn ask_secret(prompt: &str, supply: &str) -> Result<String> {
+    ensure_interactive(prompt, supply)?;
+    rpassword::prompt_password_stdout(prompt).context("failed to read input")
+}
+
+/// Ask a yes/no question on stderr and read the answer from `reader`.
+/// Anything other than `y`/`yes` — including end of input — declines.
+/// `--yes` answers without asking; with prompts turned off and no `--yes`
+/// this fails.
+pub fn confirm<R: BufRead>(question: &str, reader: &mut R) -> Result<bool> {
+    if assume_yes() {
+        return Ok(true);
+    }
+    ensure_interactive(question, "Pass --yes to answer yes.")?;
+    Ok(ask_yes_no(question, reader)?.unwrap_or(false))
+}
+
+/// Ask "`{action}`, proceed?" on the terminal. Returns `Ok(())` to go ahead
+/// and an error if the user declines, gives no answer, or cannot be asked.
+pub fn confirm_funds(action: &str) -> Result<()> {
+    let interactive = !non_interactive() && io::stdin().is_terminal() && io::stdout().is_terminal();
+    confirm_funds_with_reader(action, assume_yes(), interactive, &mut io::stdin().lock())
+}
+
+/// Testable version of [`confirm_funds`]: `assume_yes` and `interactive`
+/// are passed in and the answer is read from `reader`.
+pub fn confirm_funds_with_reader<R: BufRead>(
+    action: &str,
+    assume_yes: bool,
+    interactive: bool,
+    reader: &mut R,
+) -> Result<()> {
+    if assume_yes {
+        debug!(action, "confirmation skipped (--yes)");
+        return Ok(());
+    }
+    if !interactive {
+        bail!(
+            "{action}. Not proceeding without confirmation: pass --yes to run non-interactively."
+        );
+    }
+
+    match ask_yes_no(&format!("{action}, proceed?"), reader)? {
+        Some(true) => Ok(()),
+        Some(false) => bail!("Stopped at your request. Nothing was done."),
+        None => bail!("No answer given. Nothing was done."),
+    }
+}
+
+/// Print `question` with a `[y/N]` hint and read the answer: `Some(true)`
+/// for `y`/`yes`, `Some(false)` for anything else, and `None` at end of
+/// input.
+fn ask_yes_no<R: BufRead>(question: &str, reader: &mut R) -> Result<Option<bool>> {
+    eprint!("{question} [y/N] ");
+    io::stderr().flush().ok();
+
+    let mut answer = String::new();
+    let read = reader
+        .read_line(&mut answer)
+        .context("failed to read confirmation")?;
+    if read == 0 {
+        eprintln!();
+        return Ok(None);
+    }
+    Ok(Some(matches!(
+        answer.trim().to_ascii_lowercase().as_str(),
+        "y" | "yes"
+    )))
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ACTION: &str = "About to commit $5.00 to agent #42";
+
+    #[test]
+    fn test_yes_proceeds() {
+        for answer in ["y\n", "YES\n", " yes "] {
+            assert!(
+                confirm_funds_with_reader(ACTION, false, true, &mut answer.as_bytes()).is_ok(),
+                "{answer:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_no_or_anything_else_declines() {
+        for answer in ["n\n", "\n", "sure\n"] {
+            let err = confirm_funds_with_reader(ACTION, false, true, &mut answer.as_bytes())
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains("Stopped at your request"), "{answer:?}: {err}");
+        }
+    }
+
+    #[test]
+    fn test_eof_declines() {
+        let err = confirm_funds_with_reader(ACTION, false, true, &mut "".as_bytes())
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("No answer given"), "{err}");
+    }
+
+    #[test]
+    fn test_confirm_requires_explicit_yes() {
+        assert!(confirm("Go?", &mut "y\n".as_bytes()).unwrap());
+        assert!(confirm("Go?", &mut "YES\n".as_bytes()).unwrap());
+        assert!(!confirm("Go?", &mut "n\n".as_bytes()).unwrap());
+        assert!(!confirm("Go?", &mut "\n".as_bytes()).unwrap());
+        assert!(
+            !confirm("Go?", &mut "".as_bytes()).unwrap(),
+            "EOF must decline"
+        );
+    }
+
+    #[test]
+    fn test_ask_line_reads_trimmed() {
+        let result = ask_line(&mut "  hello world  \n".as_bytes(), "test: ", "").unwrap();
+        assert_eq!(result, "hello world");
+    }
+
+    #[test]
+    fn test_refusal_names_the_prompt_and_remedy() {
+        assert_eq!(
+            refusal("Agent name: ", "Pass --name."),
+            "Cannot ask \"Agent name:\" with prompts turned off (--non-interactive). Pass --name."
+        );
+    }
+
+    #[test]
+    fn test_assume_yes_and_non_interactive() {
+        // --yes never reads the answer.
+        assert!(confirm_funds_with_reader(ACTION, true, false, &mut "n\n".as_bytes()).is_ok());
+
+        let err = confirm_funds_with_reader(ACTION, false, false, &mut "y\n".as_bytes())
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with(ACTION), "{err}");
+        assert!(err.contains("pass --yes"), "{err}");
+    }
+}
@@ -0,0 +1,514 @@
+//! Redaction of sensitive values from diagnostic output.
+//!
+//! Used by `agentmarket bugreport` to scrub config, request state, and logs
+//! before they leave the machine. Redaction is driven by the [`RULES`] table
+//! so new kinds of sensitive data are a one-line addition:
+//!
+//! - **Fields** — JSON keys whose values are always replaced (`secret`,
+//!   `passphrase`, ...). The same names are also redacted when they appear
+//!   as `name=value` / `name: value` pairs in free text.
+//! - **Hex runs** — 40-digit hex (addresses) and 64+ digit hex (private
+//!   keys, claim secrets, public keys) anywhere in text.
+//! - **URL credentials** — user info, paths, and queries are dropped from
+//!   URLs, since RPC endpoints often embed API keys.
+//!
+//! On top of the table, a [`Redactor`] can be seeded with known sensitive
+//! values (the keystore passphrase, cached claim secrets) which are replaced
+//! wherever they occur, and [`Redactor::find_leaks`] re-checks finished
+//! output so a leak is an error rather than a silent failure.
+
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+// ---------------------------------------------------------------------------
+// Rule table
+// ---------------------------------------------------------------------------
+
+/// What a redaction rule matches.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RuleKind {
+    /// A field name: JSON values under this key, and `name=value` /
+    /// `name: value` pairs in text.
+    Field(&'static str),
+    /// A run of hex digits (optionally `0x`-prefixed) whose length falls in
+    /// `min..=max`.
+    HexRun { min: usize, max: usize },
+    /// Credentials, paths, and queries in `http(s)://` and `ws(s)://` URLs.
+    UrlCredentials,
+}
+
+/// A single named redaction rule.
+#[derive(Clone, Copy, Debug)]
+pub struct Rule {
+    /// Human-readable name shown in the review step.
+    pub name: &'static str,
+    /// What the rule matches.
+    pub kind: RuleKind,
+    /// Replacement text.
+    pub replacement: &'static str,
+}
+
+/// Redaction rules applied to every bug report artifact, in order.
+pub const RULES: &[Rule] = &[
+    Rule {
+        name: "claim secret",
+        kind: RuleKind::Field("secret"),
+        replacement: "[REDACTED:secret]",
+    },
+    Rule {
+        name: "passphrase",
+        kind: RuleKind::Field("passphrase"),
+        replacement: "[REDACTED:passphrase]",
+    },
+    Rule {
+        name: "private key",
+        kind: RuleKind::Field("private_key"),
+        replacement: "[REDACTED:private-key]",
+    },
+    Rule {
+        name: "keystore ciphertext",
+        kind: RuleKind::Field("ciphertext"),
+        replacement: "[REDACTED:ciphertext]",
+    },
+    Rule {
+        name: "keystore salt",
+        kind: RuleKind::Field("salt"),
+        replacement: "[REDACTED:salt]",
+    },
+    Rule {
+        name: "key material (64+ hex digits)",
+        kind: RuleKind::HexRun {
+            min: 64,
+            max: usize::MAX,
+        },
+        replacement: "[REDACTED:hex]",
+    },
+    Rule {
+        name: "address",
+        kind: RuleKind::HexRun { min: 40, max: 40 },
+        replacement: "[REDACTED:address]",
+    },
+    Rule {
+        name: "URL credentials",
+        kind: RuleKind::UrlCredentials,
+        replacement: "[REDACTED]",
+    },
+];
+
+/// Name used in reports for replacements of seeded known values.
+pub const KNOWN_VALUE_RULE: &str = "known sensitive value";
+
+/// Replacement text for seeded known values.
+const KNOWN_VALUE_REPLACEMENT: &str = "[REDACTED:known]";
+
+/// Known values shorter than this are not seeded; replacing them would
+/// mangle unrelated text.
+const MIN_KNOWN_VALUE_LEN: usize = 6;
+
+// ---------------------------------------------------------------------------
+// Redactor
+// ---------------------------------------------------------------------------
+
+/// Count of replacements made per rule name.
+pub type RedactionCounts = BTreeMap<&'static str, usize>;
+
+/// Applies [`RULES`] plus a set of seeded known-sensitive values.
+#[derive(Clone, Debug, Default)]
+pub struct Redactor {
+    known: Vec<String>,
+}
+
+impl Redactor {
+    /// A redactor with only the static rule table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Seed a value that must never appear in output (e.g. the keystore
+    /// passphrase). Empty and very short values are ignored.
+    pub fn add_known(&mut self, value: &str) {
+        let value = value.trim();
+        if value.len() >= MIN_KNOWN_VALUE_LEN && !self.known.iter().any(|k| k == value) {
+            self.known.push(value.to_string());
+        }
+    }
+
+    /// Redact free text, returning the scrubbed text and per-rule counts.
+    pub fn redact_text(&self, text: &str) -> (String, RedactionCounts) {
+        let mut counts = RedactionCounts::new();
+        let mut out = self.replace_known(text, &mut counts);
+
+        for rule in RULES {
+            out = match rule.kind {
+                RuleKind::Field(name) => redact_assignments(&out, name, rule, &mut counts),
+                RuleKind::HexRun { min, max } => redact_hex_runs(&out, min, max, rule, &mut counts),
+                RuleKind::UrlCredentials => redact_urls(&out, rule, &mut counts),
+            };
+        }
+
+        (out, counts)
+    }
+
+    /// Redact a JSON document in place, returning per-rule counts.
+    ///
+    /// Values under sensitive field names are replaced wholesale (whatever
+    /// their type); every other string is passed through [`redact_text`].
+    ///
+    /// [`redact_text`]: Redactor::redact_text
+    pub fn redact_json(&self, value: &mut Value) -> RedactionCounts {
+        let mut counts = RedactionCounts::new();
+        self.redact_json_inner(value, &mut counts);
+        counts
+    }
+
+    /// Return every seeded known value that still occurs in `output`.
+    ///
+    /// Used as a final check on finished artifacts: a non-empty result means
+    /// redaction failed and the output must not be written.
+    pub fn find_leaks<'a>(&'a self, output: &str) -> Vec<&'a str> {
+        self.known
+            .iter()
+            .filter(|k| output.contains(k.as_str()))
+            .map(String::as_str)
+            .collect()
+    }
+
+    fn redact_json_inner(&self, value: &mut Value, counts: &mut RedactionCounts) {
+        match value {
+            Value::Object(map) => {
+                for (key, child) in map.iter_mut() {
+                    let field_rule = RULES
+                        .iter()
+                        .find(|r| r.kind == RuleKind::Field(field_name(key)));
+
+                    match field_rule {
+                        Some(rule) if !child.is_null() => {
+                            *child = Value::String(rule.replacement.to_string());
+                            *counts.entry(rule.name).or_default() += 1;
+                        }
+                        _ => self.redact_json_inner(child, counts),
+                    }
+                }
+            }
+            Value::Array(items) => {
+                for item in items {
+                    self.redact_json_inner(item, counts);
+                }
+            }
+            Value::String(s) => {
+                let (redacted, c) = self.redact_text(s);
+                *s = redacted;
+                merge_counts(counts, c);
+            }
+            _ => {}
+        }
+    }
+
+    fn replace_known(&self, text: &str, counts: &mut RedactionCounts) -> String {
+        let mut out = text.to_string();
+        for known in &self.known {
+            let n = out.matches(known.as_str()).count();
+            if n > 0 {
+                out = out.replace(known.as_str(), KNOWN_VALUE_REPLACEMENT);
+                *counts.entry(KNOWN_VALUE_RULE).or_default() += n;
+            }
+        }
+        out
+    }
+}
+
+/// Add `from` into `into`.
+pub fn merge_counts(into: &mut RedactionCounts, from: RedactionCounts) {
+    for (rule, n) in from {
+        *into.entry(rule).or_default() += n;
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Rule implementations
+// ---------------------------------------------------------------------------
+
+/// Normalise a JSON key for field matching (`Secret`, `claim_secret`, and
+/// `AGENTMARKET_KEYSTORE_PASSPHRASE` all match their base names).
+fn field_name(key: &str) -> &'static str {
+    let lower = key.to_ascii_lowercase();
+    RULES
+        .iter()
+        .filter_map(|r| match r.kind {
+            RuleKind::Field(name) => Some(name),
+            _ => None,
+        })
+        .find(|name| lower == *name || lower.ends_with(&format!("_{name}")))
+        .unwrap_or("")
+}
+
+/// Redact `name=value` and `name: value` pairs (case-insensitive, including
+/// prefixed names such as `KEYSTORE_PASSPHRASE=`). The value runs to the
+/// next whitespace, comma, or closing quote/brace.
+fn redact_assignments(text: &str, name: &str, rule: &Rule, counts: &mut RedactionCounts) -> String {
+    let lower = text.to_ascii_lowercase();
+    let mut out = String::with_capacity(text.len());
+    let mut cursor = 0;
+    let mut search_from = 0;
+
+    while let Some(found) = lower[search_from..].find(name) {
+        let name_start = search_from + found;
+        let name_end = name_start + name.len();
+        search_from = name_end;
+
+        // Must not be the tail of a longer word (e.g. `secret_hash`).
+        let after = &text[name_end..];
+        let mut value_start = name_end;
+        let mut rest = after;
+        if let Some(stripped) = rest.strip_prefix('"') {
+            rest = stripped;
+            value_start += 1;
+        }
+        let sep_len = if rest.starts_with('=') || rest.starts_with(':') {
+            1
+        } else {
+            continue;
+        };
+        value_start += sep_len;
+        rest = &rest[sep_len..];
+
+        let trimmed = rest.trim_start_matches([' ', '"', '\'']);
+        value_start += rest.len() - trimmed.len();
+
+        let value_len = trimmed
+            .find(|c: char| c.is_whitespace() || matches!(c, ',' | '"' | '\'' | '}' | ']'))
+            .unwrap_or(trimmed.len());
+
+        if value_len == 0 || trimmed[..value_len].starts_with("[REDACTED") {
+            continue;
+        }
+
+        out.push_str(&text[cursor..value_start]);
+        out.push_str(rule.replacement);
+        cursor = value_start + value_len;
+        search_from = cursor;
+        *counts.entry(rule.name).or_default() += 1;
+    }
+
+    out.push_str(&text[cursor..]);
+    out
+}
+
+/// Redact hex runs whose digit count (excluding any `0x`) is in `min..=max`.
+fn redact_hex_runs(
+    text: &str,
+    min: usize,
+    max: usize,
+    rule: &Rule,
+    counts: &mut RedactionCounts,
+) -> String {
+    let bytes = text.as_bytes();
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+    let mut cursor = 0;
+
+    while i < bytes.len() {
+        if !bytes[i].is_ascii_hexdigit() || (i > 0 && bytes[i - 1].is_ascii_alphanumeric()) {
+            i += 1;
+            continue;
+        }
+
+        // Treat a leading "0x" as part of the run but not of its length.
+        let (run_start, digits_start) =
+            if bytes[i] == b'0' && bytes.get(i + 1).is_some_and(|b| *b == b'x' || *b == b'X') {
+                (i, i + 2)
+            } else {
+                (i, i)
+            };
+
+        let mut end = digits_start;
+        while end < bytes.len() && bytes[end].is_ascii_hexdigit() {
+            end += 1;
+        }
+        let len = end - digits_start;
+        let bounded = end == bytes.len() || !bytes[end].is_ascii_alphanumeric();
+
+        if bounded && len >= min && len <= max {
+            out.push_str(&text[cursor..run_start]);
+            out.push_str(rule.replacement);
+            cursor = end;
+            *counts.entry(rule.name).or_default() += 1;
+        }
+
+        i = end.max(i + 1);
+    }
+
+    out.push_str(&text[cursor..]);
+    out
+}
+
+/// Reduce URLs to `scheme://host[:port]/[REDACTED]` when they carry user
+/// info, a path, or a query (RPC providers put API keys in all three).
+fn redact_urls(text: &str, rule: &Rule, counts: &mut RedactionCounts) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(pos) = find_url_start(rest) {
+        out.push_str(&rest[..pos]);
+        let url_text = &rest[pos..];
+        let url_len = url_text
+            .find(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '<' | '>' | ')'))
+            .unwrap_or(url_text.len());
+        let url = &url_text[..url_len];
+
+        let scheme_end = url.find("://").map(|i| i + 3).unwrap_or(0);
+        let after_scheme = &url[scheme_end..];
+        let authority_end = after_scheme
+            .find(['/', '?', '#'])
+            .unwrap_or(after_scheme.len());
+        let authority = &after_scheme[..authority_end];
+        let host = authority.rsplit('@').next().unwrap_or(authority);
+        let tail = &after_scheme[authority_end..];
+
+        let has_secrets = authority.contains('@') || !tail.trim_matches('/').is_empty();
+        if has_secrets {
+            out.push_str(&url[..scheme_end]);
+            out.push_str(host);
+            out.push('/');
+            out.push_str(rule.replacement);
+            *counts.entry(rule.name).or_default() += 1;
+        } else {
+            out.push_str(url);
+        }
+
+        rest = &url_text[url_len..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+fn find_url_start(text: &str) -> Option<usize> {
+    ["https://", "http://", "wss://", "ws://"]
+        .iter()
+        .filter_map(|scheme| text.find(scheme))
+        .min()
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const KEY_HEX: &str = "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+    const ADDRESS: &str = "0x71C7656EC7ab88b098defB751B7401B5f6d8976F";
+
+    /// One case per rule in the table: input text and a fragment that must
+    /// not survive redaction.
+    const TEXT_CASES: &[(&str, &str, &str)] = &[
+        ("claim secret", "secret=abcdef123456 done", "abcdef123456"),
+        (
+            "passphrase",
+            "KEYSTORE_PASSPHRASE: hunter2hunter2",
+            "hunter2hunter2",
+        ),
+        (
+            "private key",
+            "private_key=\"deadbeefcafe\"",
+            "deadbeefcafe",
+        ),
+        (
+            "key material (64+ hex digits)",
+            "loaded key 4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318 ok",
+            KEY_HEX,
+        ),
+        (
+            "address",
+            "seller 0x71C7656EC7ab88b098defB751B7401B5f6d8976F paid",
+            ADDRESS,
+        ),
+        (
+            "URL credentials",
+            "rpc=https://base-mainnet.g.alchemy.com/v2/SuperSecretApiKey123",
+            "SuperSecretApiKey123",
+        ),
+    ];
+
+    #[test]
+    fn test_every_rule_case_redacts() {
+        let redactor = Redactor::new();
+        for (rule, input, forbidden) in TEXT_CASES {
+            let (out, counts) = redactor.redact_text(input);
+            assert!(
+                !out.contains(forbidden),
+                "rule {rule:?} left {forbidden:?} in {out:?}"
+            );
+            assert!(
+                counts.get(rule).copied().unwrap_or(0) >= 1,
+                "rule {rule:?} not counted for {input:?}: {counts:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_every_rule_has_a_case_or_is_a_field() {
+        for rule in RULES {
+            let covered = TEXT_CASES.iter().any(|(name, _, _)| *name == rule.name)
+                || matches!(rule.kind, RuleKind::Field(_));
+            assert!(covered, "rule {:?} has no test case", rule.name);
+        }
+    }
+
+    #[test]
+    fn test_harmless_text_unchanged() {
+        let redactor = Redactor::new();
+        let input = "request local-1700000000 moved Open -> Responded (secret_hash set)";
+        let (out, counts) = redactor.redact_text(input);
+        assert_eq!(out, input);
+        assert!(counts.is_empty());
+
+        let (out, _) = redactor.redact_text("endpoint https://mainnet.base.org");
+        assert_eq!(out, "endpoint https://mainnet.base.org");
+    }
+
+    #[test]
+    fn test_redact_json_fields_and_nested_strings() {
+        let redactor = Redactor::new();
+        let mut doc = json!({
+            "request_id": "42",
+            "secret": KEY_HEX,
+            "secret_hash": format!("0x{KEY_HEX}"),
+            "counterparty": ADDRESS,
+            "nested": { "claim_secret": "abc", "notes": ["see https://user:pw@host.io/x"] },
+            "response_cid": null,
+        });
+
+        let counts = redactor.redact_json(&mut doc);
+        let out = doc.to_string();
+
+        assert_eq!(doc["request_id"], "42");
+        assert_eq!(doc["secret"], "[REDACTED:secret]");
+        assert_eq!(doc["nested"]["claim_secret"], "[REDACTED:secret]");
+        assert!(!out.contains(KEY_HEX));
+        assert!(!out.contains(ADDRESS));
+        assert!(!out.contains("user:pw"));
+        assert_eq!(counts.get("claim secret"), Some(&2));
+    }
+
+    #[test]
+    fn test_known_values_are_replaced_and_leaks_detected() {
+        let mut redactor = Redactor::new();
+        redactor.add_known("correct horse battery staple");
+        redactor.add_known("abc"); // too short, ignored
+
+        let (out, counts) = redactor.redact_text("typed correct horse battery staple by mistake");
+        assert!(!out.contains("correct horse"));
+        assert_eq!(counts.get(KNOWN_VALUE_RULE), Some(&1));
+        assert!(redactor.find_leaks(&out).is_empty());
+
+        assert_eq!(
+            redactor.find_leaks("oops correct horse battery staple"),
+            vec!["correct horse battery staple"]
+        );
+    }
+}
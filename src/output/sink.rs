@@ -0,0 +1,193 @@
+//! Destination for everything the formatter prints.
+//!
+//! Formatter functions never call `println!` directly; they hand finished
+//! lines to a [`Sink`]. By default that is the process's stdout/stderr
+//! ([`StdSink`]). Code that needs the output instead — tests asserting on
+//! human output, or frontends that render it themselves — installs a sink
+//! for the current thread with [`with_sink`], or collects it with
+//! [`capture`].
+//!
+//! The sink layer also guards the JSON-mode contract: while JSON mode is on,
+//! every stdout line must be a JSON document. Debug builds assert this so a
+//! stray human-readable line is caught in tests rather than by a consumer.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use super::formatter::is_json_mode;
+
+// ---------------------------------------------------------------------------
+// Sink trait and implementations
+// ---------------------------------------------------------------------------
+
+/// Receives complete output lines (without trailing newlines).
+pub trait Sink {
+    /// A line of primary output (stdout).
+    fn write_line(&self, line: &str);
+    /// A line of diagnostic output: warnings, errors, progress (stderr).
+    fn write_err_line(&self, line: &str);
+}
+
+/// The default sink: the process's stdout and stderr.
+pub struct StdSink;
+
+impl Sink for StdSink {
+    fn write_line(&self, line: &str) {
+        println!("{line}");
+    }
+
+    fn write_err_line(&self, line: &str) {
+        eprintln!("{line}");
+    }
+}
+
+/// Which stream a captured line was written to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stream {
+    Out,
+    Err,
+}
+
+/// A sink that records lines in memory.
+#[derive(Debug, Default)]
+pub struct CaptureSink {
+    lines: RefCell<Vec<(Stream, String)>>,
+}
+
+impl CaptureSink {
+    /// Every captured line in the order written.
+    pub fn lines(&self) -> Vec<(Stream, String)> {
+        self.lines.borrow().clone()
+    }
+
+    /// Lines written to stdout.
+    pub fn out(&self) -> Vec<String> {
+        self.stream(Stream::Out)
+    }
+
+    /// Lines written to stderr.
+    pub fn err(&self) -> Vec<String> {
+        self.stream(Stream::Err)
+    }
+
+    fn stream(&self, stream: Stream) -> Vec<String> {
+        self.lines
+            .borrow()
+            .iter()
+            .filter(|(s, _)| *s == stream)
+            .map(|(_, line)| line.clone())
+            .collect()
+    }
+}
+
+impl Sink for CaptureSink {
+    fn write_line(&self, line: &str) {
+        self.lines
+            .borrow_mut()
+            .push((Stream::Out, line.to_string()));
+    }
+
+    fn write_err_line(&self, line: &str) {
+        self.lines
+            .borrow_mut()
+            .push((Stream::Err, line.to_string()));
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Routing
+// ---------------------------------------------------------------------------
+
+thread_local! {
+    /// Sink installed for this thread by [`with_sink`], if any.
+    static OVERRIDE: RefCell<Option<Rc<dyn Sink>>> = const { RefCell::new(None) };
+}
+
+/// Run `f` with `sink` receiving all formatter output on this thread. The
+/// previous sink is restored afterwards, even if `f` panics. Calls nest.
+pub fn with_sink<R>(sink: Rc<dyn Sink>, f: impl FnOnce() -> R) -> R {
+    struct Restore(Option<Rc<dyn Sink>>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            OVERRIDE.with(|slot| *slot.borrow_mut() = previous);
+        }
+    }
+
+    let previous = OVERRIDE.with(|slot| slot.borrow_mut().replace(sink));
+    let _restore = Restore(previous);
+    f()
+}
+
+/// Run `f` and return its result together with everything it printed.
+pub fn capture<R>(f: impl FnOnce() -> R) -> (R, Rc<CaptureSink>) {
+    let sink = Rc::new(CaptureSink::default());
+    let result = with_sink(sink.clone(), f);
+    (result, sink)
+}
+
+/// Write a line to the current sink's stdout.
+///
+/// In JSON mode the line must be a JSON document; debug builds assert it.
+pub fn write_line(line: &str) {
+    debug_assert!(
+        !is_json_mode() || serde_json::from_str::<serde_json::Value>(line).is_ok(),
+        "non-JSON line written to stdout in JSON mode: {line:?}"
+    );
+    emit_line(line);
+}
+
+/// Write a line to the current sink's stdout without the JSON-mode check,
+/// for callers that have already decided the line belongs on stdout.
+pub(super) fn emit_line(line: &str) {
+    dispatch(|sink| sink.write_line(line));
+}
+
+/// Write a line to the current sink's stderr.
+pub fn write_err_line(line: &str) {
+    dispatch(|sink| sink.write_err_line(line));
+}
+
+fn dispatch(write: impl FnOnce(&dyn Sink)) {
+    let sink = OVERRIDE.with(|slot| slot.borrow().clone());
+    match sink {
+        Some(sink) => write(sink.as_ref()),
+        None => write(&StdSink),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capture_separates_streams_and_restores() {
+        let ((), outer) = capture(|| {
+            write_line("one");
+            let ((), inner) = capture(|| write_err_line("nested"));
+            assert_eq!(inner.err(), vec!["nested"]);
+            write_err_line("two");
+        });
+        assert_eq!(
+            outer.lines(),
+            vec![
+                (Stream::Out, "one".to_string()),
+                (Stream::Err, "two".to_string())
+            ]
+        );
+        OVERRIDE.with(|slot| assert!(slot.borrow().is_none()));
+    }
+
+    #[test]
+    fn override_is_restored_after_panic() {
+        let result = std::panic::catch_unwind(|| {
+            capture(|| panic!("boom"));
+        });
+        assert!(result.is_err());
+        OVERRIDE.with(|slot| assert!(slot.borrow().is_none()));
+    }
+}
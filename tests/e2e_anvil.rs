@@ -27,7 +27,7 @@ use agentmarket::engine::reputation::{
 };
 use agentmarket::engine::requests::{
     dollars_to_usdc, format_price_usd, generate_secret, LocalRequest, LocalRequestStatus,
-    RequestCache, RequestRole,
+    RequestCache, RequestRole, CURRENT_SCHEMA_VERSION,
 };
 use agentmarket::engine::validation::{self, HandlerOutput};
 use agentmarket::ipfs::encryption;
@@ -74,6 +74,7 @@ fn make_request(
 
     LocalRequest {
         request_id: id.to_string(),
+        schema_version: CURRENT_SCHEMA_VERSION,
         role,
         status,
         request_cid: format!("QmRequest{}", id),
@@ -85,6 +86,14 @@ fn make_request(
         counterparty: Some(counterparty.to_string()),
         created_at: now,
         updated_at: now,
+        previous_status: None,
+        tags: Vec::new(),
+        task_summary: None,
+        capability: None,
+        trust_policy: None,
+        history: Vec::new(),
+        payment_check: None,
+        response_attempt: None,
     }
 }
 
@@ -736,3 +745,136 @@ async fn e2e_multi_request_reputation() {
         }
     });
 }
+
+// ===========================================================================
+// Test 5: Replacing a stuck transaction against Anvil
+// ===========================================================================
+
+/// Anvil default account #1, so the balance checks above are unaffected.
+const ANVIL_KEY_1: &str = "59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d";
+
+/// Leave a transaction pending on Anvil (with automining off), cancel it
+/// with `ChainClient::replace_transaction`, mine a block, and check that
+/// the replacement landed with the stuck transaction's nonce and cannot
+/// itself be bumped.
+///
+/// Skips gracefully if Anvil is not running.
+#[tokio::test]
+#[ignore]
+async fn e2e_replace_stuck_transaction() {
+    use std::time::Duration;
+
+    use agentmarket::chain::receipt::TxOutcome;
+    use agentmarket::chain::signer::TransactionSigner;
+    use alloy::consensus::Transaction as _;
+    use alloy::network::EthereumWallet;
+    use alloy::primitives::U256;
+    use alloy::providers::{Provider, ProviderBuilder};
+    use alloy::rpc::types::TransactionRequest;
+
+    let anvil_url = "http://127.0.0.1:8545";
+    let client = match ChainClient::new(anvil_url).await {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!(
+                "SKIP: could not create chain client (Anvil may not be running): {}",
+                e
+            );
+            return;
+        }
+    };
+    if !client.is_connected().await {
+        eprintln!("SKIP: Anvil is not reachable at {}", anvil_url);
+        return;
+    }
+
+    let mut key = hex::decode(ANVIL_KEY_1).expect("valid key hex");
+    let signer = TransactionSigner::from_bytes(&mut key).expect("valid key");
+    let provider = ProviderBuilder::new()
+        .wallet(EthereumWallet::from(signer.inner().clone()))
+        .connect_http(anvil_url.parse().expect("valid URL"));
+
+    // Step 1: With automining off, a transaction stays pending.
+    provider
+        .raw_request::<_, serde_json::Value>("evm_setAutomine".into(), (false,))
+        .await
+        .expect("disable automining");
+    let stuck = provider
+        .send_transaction(
+            TransactionRequest::default()
+                .to(signer.address())
+                .value(U256::from(1)),
+        )
+        .await
+        .expect("send the transaction to replace");
+    let stuck_hash = *stuck.tx_hash();
+    let stuck_nonce = provider
+        .get_transaction_by_hash(stuck_hash)
+        .await
+        .expect("look up the stuck transaction")
+        .expect("the stuck transaction is pending")
+        .nonce();
+
+    // Step 2: Cancel it; a block is mined while the client waits.
+    let miner = {
+        let provider = provider.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            provider
+                .raw_request::<_, serde_json::Value>("evm_mine".into(), Vec::<u64>::new())
+                .await
+        })
+    };
+    let replacement = client
+        .replace_transaction(
+            &signer,
+            stuck_hash,
+            10,
+            true,
+            1,
+            Duration::from_secs(30),
+            |_| Ok(()),
+        )
+        .await;
+    miner.await.expect("miner task").expect("mine a block");
+    provider
+        .raw_request::<_, serde_json::Value>("evm_setAutomine".into(), (true,))
+        .await
+        .expect("re-enable automining");
+
+    // Step 3: The replacement took the nonce; the original never ran.
+    let replacement = replacement.expect("replace the stuck transaction");
+    assert_eq!(replacement.nonce, stuck_nonce);
+    assert_eq!(replacement.landed, Some(replacement.replacement));
+    assert!(
+        matches!(replacement.outcome, TxOutcome::Success { .. }),
+        "replacement should succeed, got {:?}",
+        replacement.outcome
+    );
+    let mined = provider
+        .get_transaction_by_hash(replacement.replacement)
+        .await
+        .expect("look up the replacement")
+        .expect("the replacement is known");
+    assert_eq!(mined.nonce(), stuck_nonce);
+    assert!(provider
+        .get_transaction_receipt(stuck_hash)
+        .await
+        .expect("look up the original's receipt")
+        .is_none());
+
+    // Step 4: A mined transaction cannot be bumped.
+    let err = client
+        .replace_transaction(
+            &signer,
+            replacement.replacement,
+            10,
+            false,
+            1,
+            Duration::ZERO,
+            |_| Ok(()),
+        )
+        .await
+        .expect_err("a mined transaction is refused");
+    assert!(err.to_string().contains("already mined"), "{err}");
+}
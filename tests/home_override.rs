@@ -0,0 +1,131 @@
+//! Integration tests for the global `--home` flag.
+//!
+//! `store::set_home` is process-wide, so these tests run two agents in
+//! different homes within one process, switching between them, and check
+//! that config, keys, requests, and validation results never cross over.
+//! They live in their own test binary so the override cannot leak into the
+//! library's unit tests.
+
+use std::env;
+use std::path::Path;
+use std::sync::Mutex;
+
+use agentmarket::config::{keystore, store};
+use agentmarket::engine::requests::{
+    LocalRequest, LocalRequestStatus, RequestCache, RequestRole, CURRENT_SCHEMA_VERSION,
+};
+use agentmarket::engine::validation::{self, ValidationResult};
+
+/// Mutex to serialise tests that change the home override or environment.
+static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+const PASSPHRASE: &str = "correct horse battery staple";
+
+/// Build a sample `LocalRequest` for testing.
+fn sample_request(id: &str) -> LocalRequest {
+    LocalRequest {
+        request_id: id.to_string(),
+        schema_version: CURRENT_SCHEMA_VERSION,
+        role: RequestRole::Buyer,
+        status: LocalRequestStatus::Open,
+        request_cid: "QmTestCid123".to_string(),
+        price_usdc: 5_000_000,
+        deadline: 1_700_000_000,
+        response_cid: None,
+        secret: None,
+        secret_hash: None,
+        counterparty: None,
+        created_at: 1_699_000_000,
+        updated_at: 1_699_000_000,
+        previous_status: None,
+        tags: Vec::new(),
+        task_summary: None,
+        capability: None,
+        trust_policy: None,
+        history: Vec::new(),
+        payment_check: None,
+        response_attempt: None,
+    }
+}
+
+/// Set up an agent called `name` in `home`: config, key, one request, and
+/// one validation result.
+fn set_up_agent(home: &Path, name: &str, request_id: &str, key: &[u8]) {
+    store::set_home(Some(home.to_path_buf()));
+
+    let mut config = store::Config::default();
+    config.agent.name = name.to_string();
+    store::save(&config).unwrap();
+    keystore::save_key(key, PASSPHRASE).unwrap();
+    RequestCache::save(&sample_request(request_id)).unwrap();
+    validation::save_result(&ValidationResult {
+        request_id: request_id.to_string(),
+        passed: true,
+        score: 90,
+        reason: format!("checked by {name}"),
+        timestamp: 1_699_000_000,
+    })
+    .unwrap();
+}
+
+#[test]
+fn test_two_agents_in_one_process_stay_separate() {
+    let _guard = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let alpha_home = tempfile::tempdir().unwrap();
+    let beta_home = tempfile::tempdir().unwrap();
+
+    set_up_agent(alpha_home.path(), "alpha", "1", &[0xaa; 32]);
+    set_up_agent(beta_home.path(), "beta", "2", &[0xbb; 32]);
+
+    // Switch back and forth: each home only ever sees its own agent.
+    for _ in 0..2 {
+        for (home, name, own, other, key) in [
+            (alpha_home.path(), "alpha", "1", "2", [0xaa; 32]),
+            (beta_home.path(), "beta", "2", "1", [0xbb; 32]),
+        ] {
+            store::set_home(Some(home.to_path_buf()));
+            assert_eq!(store::config_dir().unwrap(), home);
+            assert_eq!(store::load().unwrap().agent.name, name);
+            assert_eq!(keystore::load_key(PASSPHRASE).unwrap(), key);
+
+            let ids: Vec<String> = RequestCache::load_all()
+                .unwrap()
+                .into_iter()
+                .map(|r| r.request_id)
+                .collect();
+            assert_eq!(ids, vec![own.to_string()], "requests seen by {name}");
+            assert!(!RequestCache::contains(other).unwrap());
+
+            assert_eq!(
+                validation::load_result(own).unwrap().reason,
+                format!("checked by {name}")
+            );
+            assert!(validation::load_result(other).is_err());
+        }
+    }
+
+    store::set_home(None);
+}
+
+#[test]
+fn test_home_override_beats_env_var() {
+    let _guard = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let env_home = tempfile::tempdir().unwrap();
+    let flag_home = tempfile::tempdir().unwrap();
+    let prev = env::var("AGENTMARKET_HOME").ok();
+    env::set_var("AGENTMARKET_HOME", env_home.path());
+
+    store::set_home(Some(flag_home.path().to_path_buf()));
+    assert_eq!(store::config_dir().unwrap(), flag_home.path());
+    store::save(&store::Config::default()).unwrap();
+    assert!(flag_home.path().join(store::CONFIG_FILE).exists());
+    assert!(!env_home.path().join(store::CONFIG_FILE).exists());
+
+    store::set_home(None);
+    assert_eq!(store::config_dir().unwrap(), env_home.path());
+
+    match prev {
+        Some(v) => env::set_var("AGENTMARKET_HOME", v),
+        None => env::remove_var("AGENTMARKET_HOME"),
+    }
+}
@@ -0,0 +1,170 @@
+//! Integration tests for `--non-interactive`.
+//!
+//! With prompts turned off, every place that would ask for input must fail
+//! straight away with a message naming the flag or variable that supplies
+//! the answer. The setting is process-wide, so these tests live in their
+//! own test binary and serialise on a lock.
+
+use std::env;
+use std::sync::Mutex;
+
+use agentmarket::chain::contracts::Network;
+use agentmarket::commands;
+use agentmarket::config::{keystore, store};
+use agentmarket::engine::manual_handler;
+use agentmarket::engine::validation::HandlerInput;
+use agentmarket::output::prompt;
+
+/// Mutex to serialise tests that change the prompt setting or environment.
+static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+/// Variables that would otherwise answer the prompts under test.
+const ANSWER_VARS: [&str; 4] = [
+    "AGENTMARKET_HOME",
+    keystore::PASSPHRASE_ENV,
+    keystore::LEGACY_PASSPHRASE_ENV,
+    "AGENTMARKET_BACKUP_PASSPHRASE",
+];
+
+/// Run `f` with prompts turned off, a fresh agent home, and none of the
+/// answering variables set.
+fn non_interactive<F: FnOnce()>(f: F) {
+    let _guard = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let tmp = tempfile::tempdir().expect("failed to create temp dir");
+    let prev: Vec<_> = ANSWER_VARS.iter().map(|var| env::var(var).ok()).collect();
+    for var in ANSWER_VARS {
+        env::remove_var(var);
+    }
+    env::set_var("AGENTMARKET_HOME", tmp.path());
+    prompt::set_non_interactive(true);
+
+    f();
+
+    prompt::set_non_interactive(false);
+    for (var, value) in ANSWER_VARS.iter().zip(prev) {
+        match value {
+            Some(v) => env::set_var(var, v),
+            None => env::remove_var(var),
+        }
+    }
+}
+
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    tokio::runtime::Runtime::new().unwrap().block_on(future)
+}
+
+/// Run `init` with the given flags and return its error message.
+fn init_error(name: Option<&str>, price: Option<&str>, force: bool) -> String {
+    block_on(commands::init::run(
+        name.map(str::to_string),
+        Some("Summarises documents".to_string()),
+        Some("summarize".to_string()),
+        price.map(str::to_string),
+        None,
+        false,
+        force,
+        false,
+        false,
+        Network::BaseMainnet,
+    ))
+    .expect_err("init should refuse to prompt")
+    .to_string()
+}
+
+fn assert_refused(err: &str, remedy: &str) {
+    assert!(err.contains("--non-interactive"), "{err}");
+    assert!(err.contains(remedy), "expected {remedy:?} in: {err}");
+}
+
+#[test]
+fn test_init_names_the_missing_flag() {
+    non_interactive(|| {
+        assert_refused(&init_error(None, Some("1"), false), "Pass --name.");
+        assert_refused(&init_error(Some("alpha"), None, false), "Pass --price.");
+        assert!(
+            !store::exists().unwrap(),
+            "nothing is written before the refusal"
+        );
+    });
+}
+
+#[test]
+fn test_init_force_needs_yes() {
+    non_interactive(|| {
+        store::save(&store::Config::default()).unwrap();
+        assert_refused(&init_error(Some("alpha"), Some("1"), true), "Pass --yes");
+    });
+}
+
+#[test]
+fn test_keystore_passphrase_names_env_var_and_flag() {
+    non_interactive(|| {
+        let err = keystore::get_passphrase().unwrap_err().to_string();
+        assert_refused(&err, "AGENTMARKET_PASSPHRASE");
+        assert_refused(&err, "--passphrase-file");
+
+        let err = keystore::get_backup_passphrase(false)
+            .unwrap_err()
+            .to_string();
+        assert_refused(&err, "AGENTMARKET_BACKUP_PASSPHRASE");
+    });
+}
+
+#[test]
+fn test_manual_handler_points_at_external_handler() {
+    non_interactive(|| {
+        let input = HandlerInput {
+            request_id: "42".to_string(),
+            task_description: "Summarise".to_string(),
+            deliverable: b"summary".to_vec(),
+            seller: "0xSeller".to_string(),
+            price_usdc: 5_000_000,
+            deadline: 1_700_000_000,
+        };
+        let err = manual_handler::run_manual_review_with_reader(&input, &mut "y\n".as_bytes())
+            .unwrap_err()
+            .to_string();
+        assert_refused(&err, "--handler external --handler-path");
+    });
+}
+
+#[test]
+fn test_confirmations_need_yes() {
+    non_interactive(|| {
+        let err = prompt::confirm("Break the daemon lock?", &mut "y\n".as_bytes())
+            .unwrap_err()
+            .to_string();
+        assert_refused(&err, "Pass --yes");
+
+        let err = prompt::confirm_funds("About to commit $5.00 to agent #42")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("pass --yes"), "{err}");
+
+        prompt::set_assume_yes(true);
+        assert!(prompt::confirm("Break the daemon lock?", &mut "".as_bytes()).unwrap());
+        assert!(prompt::confirm_funds("About to commit $5.00 to agent #42").is_ok());
+        prompt::set_assume_yes(false);
+    });
+}
+
+#[test]
+fn test_env_var_turns_prompts_off() {
+    let _guard = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let prev = env::var(prompt::NON_INTERACTIVE_ENV).ok();
+
+    for (value, expected) in [("1", true), ("true", true), ("0", false), ("", false)] {
+        env::set_var(prompt::NON_INTERACTIVE_ENV, value);
+        assert_eq!(prompt::non_interactive(), expected, "{value:?}");
+    }
+    env::set_var(prompt::NON_INTERACTIVE_ENV, "1");
+    let err = prompt::ask_line(&mut "alpha\n".as_bytes(), "Agent name: ", "Pass --name.")
+        .unwrap_err()
+        .to_string();
+    assert_refused(&err, "Pass --name.");
+
+    match prev {
+        Some(v) => env::set_var(prompt::NON_INTERACTIVE_ENV, v),
+        None => env::remove_var(prompt::NON_INTERACTIVE_ENV),
+    }
+}
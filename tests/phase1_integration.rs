@@ -148,7 +148,8 @@ fn config_env_override_rpc_url_takes_precedence() {
 
         let loaded = store::load().expect("load failed");
         assert_eq!(
-            loaded.network.chain_rpc, "https://overridden-rpc.example.com",
+            loaded.network.chain_rpc.primary(),
+            "https://overridden-rpc.example.com",
             "env var should override file value"
         );
 
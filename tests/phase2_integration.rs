@@ -435,6 +435,7 @@ fn config_empty_identity_is_uninitialized() {
             ipfs_profile_cid: String::new(),
         },
         services: ServicesConfig::default(),
+        ..Default::default()
     };
 
     let state = identity::get_identity_state(&config);
@@ -454,6 +455,7 @@ fn config_with_pubkey_only_is_local() {
             ipfs_profile_cid: String::new(),
         },
         services: ServicesConfig::default(),
+        ..Default::default()
     };
 
     let state = identity::get_identity_state(&config);
@@ -479,6 +481,7 @@ fn config_with_pubkey_and_agent_id_is_registered() {
             ipfs_profile_cid: "QmTestCid".to_string(),
         },
         services: ServicesConfig::default(),
+        ..Default::default()
     };
 
     let state = identity::get_identity_state(&config);
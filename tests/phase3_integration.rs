@@ -18,7 +18,7 @@ use agentmarket::engine::reputation::{
 };
 use agentmarket::engine::requests::{
     dollars_to_usdc, format_price_usd, generate_secret, LocalRequest, LocalRequestStatus,
-    RequestCache, RequestRole,
+    RequestCache, RequestRole, CURRENT_SCHEMA_VERSION,
 };
 use alloy::primitives::keccak256;
 
@@ -52,6 +52,7 @@ fn make_request(
 ) -> LocalRequest {
     LocalRequest {
         request_id: id.to_string(),
+        schema_version: CURRENT_SCHEMA_VERSION,
         role,
         status,
         request_cid: format!("QmRequest{}", id),
@@ -63,6 +64,14 @@ fn make_request(
         counterparty: Some(address.to_string()),
         created_at: 1_699_000_000,
         updated_at: 1_699_000_000,
+        previous_status: None,
+        tags: Vec::new(),
+        task_summary: None,
+        capability: None,
+        trust_policy: None,
+        history: Vec::new(),
+        payment_check: None,
+        response_attempt: None,
     }
 }
 
@@ -106,6 +115,7 @@ fn request_lifecycle_with_identity_full_path() {
                 capabilities: vec!["code-review".to_string()],
                 pricing_usd: 5.0,
             },
+            ..Default::default()
         };
 
         // Verify identity state is Local (not yet registered).
@@ -699,6 +709,7 @@ fn cache_persistence_all_fields_preserved() {
 
         let request = LocalRequest {
             request_id: "persist-1".to_string(),
+            schema_version: CURRENT_SCHEMA_VERSION,
             role: RequestRole::Seller,
             status: LocalRequestStatus::Validated,
             request_cid: "QmRequestCidPersist".to_string(),
@@ -710,6 +721,14 @@ fn cache_persistence_all_fields_preserved() {
             counterparty: Some(address.clone()),
             created_at: 1_699_000_000,
             updated_at: 1_699_050_000,
+            previous_status: None,
+            tags: Vec::new(),
+            task_summary: None,
+            capability: None,
+            trust_policy: None,
+            history: Vec::new(),
+            payment_check: None,
+            response_attempt: None,
         };
 
         RequestCache::save(&request).expect("save failed");
@@ -890,6 +909,7 @@ fn full_request_lifecycle_to_reputation() {
                 capabilities: vec!["testing".to_string()],
                 pricing_usd: 10.0,
             },
+            ..Default::default()
         };
 
         assert_eq!(
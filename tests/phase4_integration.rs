@@ -14,8 +14,12 @@ use std::sync::Mutex;
 
 use agentmarket::engine::handlers::{self, HandlerType};
 use agentmarket::engine::manual_handler;
-use agentmarket::engine::requests::{LocalRequest, LocalRequestStatus, RequestCache, RequestRole};
+use agentmarket::engine::requests::{
+    LocalRequest, LocalRequestStatus, RequestCache, RequestRole, CURRENT_SCHEMA_VERSION,
+};
+use agentmarket::engine::trust::{self, Coverage, PolicyAction, TrustPolicy, TrustStore};
 use agentmarket::engine::validation::{self, HandlerConfig, HandlerInput, HandlerOutput};
+use agentmarket::output::sink;
 
 /// Mutex to serialise tests that mutate environment variables.
 static ENV_LOCK: Mutex<()> = Mutex::new(());
@@ -41,6 +45,7 @@ fn with_temp_home<F: FnOnce()>(f: F) {
 fn sample_request(id: &str, status: LocalRequestStatus, role: RequestRole) -> LocalRequest {
     LocalRequest {
         request_id: id.to_string(),
+        schema_version: CURRENT_SCHEMA_VERSION,
         role,
         status,
         request_cid: "QmTestCid123".to_string(),
@@ -52,6 +57,14 @@ fn sample_request(id: &str, status: LocalRequestStatus, role: RequestRole) -> Lo
         counterparty: None,
         created_at: 1_699_000_000,
         updated_at: 1_699_000_000,
+        previous_status: None,
+        tags: Vec::new(),
+        task_summary: None,
+        capability: None,
+        trust_policy: None,
+        history: Vec::new(),
+        payment_check: None,
+        response_attempt: None,
     }
 }
 
@@ -654,8 +667,17 @@ fn manual_handler_to_validation_result() {
 
     // Simulate user approving with score 75 and a reason.
     let mut reader = Cursor::new("y\n75\nlooks acceptable\n");
-    let handler_output =
-        manual_handler::run_manual_review_with_reader(&input, &mut reader).unwrap();
+    let (handler_output, captured) =
+        sink::capture(|| manual_handler::run_manual_review_with_reader(&input, &mut reader));
+    let handler_output = handler_output.unwrap();
+
+    // The reviewer sees the request details and the deliverable.
+    let shown = captured.out();
+    assert!(shown.contains(&"=== Validation Review ===".to_string()));
+    assert!(shown.contains(&"Request ID: req-manual-1".to_string()));
+    assert!(shown.contains(&"Price: $5.00".to_string()));
+    assert!(shown.contains(&"Here is the deliverable content".to_string()));
+    assert!(captured.err().is_empty());
 
     assert_eq!(handler_output.score, 75);
     assert_eq!(handler_output.reason, "looks acceptable");
@@ -674,10 +696,19 @@ fn manual_handler_to_validation_result() {
 fn manual_handler_rejection_to_validation_result() {
     let input = sample_handler_input("req-manual-2");
 
-    // Simulate user rejecting with score 30.
+    // Simulate user rejecting with score 30. Binary deliverables are
+    // summarised rather than printed.
+    let input = HandlerInput {
+        deliverable: vec![0xff, 0xfe, 0x00],
+        ..input
+    };
     let mut reader = Cursor::new("n\n30\nincomplete deliverable\n");
-    let handler_output =
-        manual_handler::run_manual_review_with_reader(&input, &mut reader).unwrap();
+    let (handler_output, captured) =
+        sink::capture(|| manual_handler::run_manual_review_with_reader(&input, &mut reader));
+    let handler_output = handler_output.unwrap();
+    assert!(captured
+        .out()
+        .contains(&"[Binary content, 3 bytes]".to_string()));
 
     let result = validation::create_result("req-manual-2", &handler_output);
 
@@ -801,3 +832,79 @@ fn handler_input_serialization_through_handler() {
     assert_eq!(handler_output.reason, "parsed request_id from stdin");
     assert!(validation::is_passing(&handler_output));
 }
+
+// ===========================================================================
+// Standing approvals (trust policies)
+// ===========================================================================
+
+/// Walk a buyer and a seller through the trusted flow: a policy-confirmed
+/// request is answered and auto-accepted, every step lands in the ledger,
+/// and the next request over the monthly cap falls back to the normal flow.
+#[test]
+fn trusted_seller_flow_end_to_end() {
+    with_temp_home(|| {
+        let seller = trust::parse_seller("42").unwrap();
+        let mut store = TrustStore::default();
+        store.upsert(TrustPolicy {
+            seller: seller.clone(),
+            alias: Some("weekly".to_string()),
+            per_request_usdc: 5_000_000,
+            monthly_usdc: 8_000_000,
+            capabilities: Vec::new(),
+            created_at: 1_699_000_000,
+        });
+        store.save().unwrap();
+
+        // Buyer: the request is within both caps and confirmed by the policy.
+        let now = 1_699_000_000;
+        let store = TrustStore::load().unwrap();
+        assert_eq!(store.resolve_seller("@weekly").unwrap(), seller);
+        assert!(matches!(
+            store.check_request(&seller, 5_000_000, None, &[], now),
+            Coverage::Approved(_)
+        ));
+        let mut request = sample_request("trusted-1", LocalRequestStatus::Open, RequestRole::Buyer);
+        request.trust_policy = Some(seller.clone());
+        RequestCache::save(&request).unwrap();
+        trust::record_action("trusted-1", &seller, PolicyAction::Confirm, 5_000_000).unwrap();
+
+        // Seller responds.
+        RequestCache::update("trusted-1", |r| r.transition(LocalRequestStatus::Responded)).unwrap();
+
+        // Buyer: the response is accepted under the policy.
+        let store = TrustStore::load().unwrap();
+        assert!(matches!(
+            store.check_response("trusted-1", &seller, &seller, 5_000_000),
+            Coverage::Approved(_)
+        ));
+        trust::record_action("trusted-1", &seller, PolicyAction::Accept, 5_000_000).unwrap();
+        RequestCache::update("trusted-1", |r| r.transition(LocalRequestStatus::Validated)).unwrap();
+
+        let store = TrustStore::load().unwrap();
+        let actions: Vec<PolicyAction> = store.ledger.iter().map(|e| e.action).collect();
+        assert_eq!(actions, vec![PolicyAction::Confirm, PolicyAction::Accept]);
+        let reloaded = RequestCache::load("trusted-1").unwrap();
+        assert_eq!(reloaded.status, LocalRequestStatus::Validated);
+        assert_eq!(reloaded.trust_policy.as_deref(), Some("#42"));
+
+        // Only the confirmation counts against the monthly cap, so the next
+        // $5 request would exceed $8 and falls back to the normal flow.
+        let at = store.ledger[0].at;
+        assert_eq!(store.spent_this_month(&seller, at), 5_000_000);
+        assert!(matches!(
+            store.check_request(&seller, 5_000_000, None, &[], at),
+            Coverage::Declined(_)
+        ));
+        assert!(matches!(
+            store.check_request(&seller, 3_000_000, None, &[], at),
+            Coverage::Approved(_)
+        ));
+
+        // A request that was never confirmed under the policy is reviewed
+        // normally even if it is for the same seller.
+        assert!(matches!(
+            store.check_response("untrusted", &seller, &seller, 1_000_000),
+            Coverage::Declined(_)
+        ));
+    });
+}